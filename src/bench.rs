@@ -0,0 +1,229 @@
+//! Built-in benchmark (`ns bench`).
+//!
+//! Times what users wait for on their own repo: a full index, an
+//! incremental index with nothing to do (the cost of every git hook and
+//! `ns watch` tick), and a set of searches. The full index rebuilds `.ns/`
+//! in place with the settings the existing index was built with, so the
+//! index afterwards is the one `ns index` would leave.
+//!
+//! Queries come from `--query` when given, else from `.ns/golden.toml`,
+//! else from the index: symbol names spread evenly over the sorted names
+//! (as `ns tune --calibrate` picks them) and two-word queries pairing them,
+//! so two runs over the same index time the same queries. Each query runs
+//! `runs` times; the first run starts with an empty context cache.
+
+use std::path::Path;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::config::load_config;
+use crate::error::NsError;
+use crate::indexer::verify::evenly_spaced;
+use crate::indexer::writer::{read_meta, IndexMeta};
+use crate::indexer::{run_full_index, run_incremental_index, IndexOptions, StoredCompression};
+use crate::searcher::cache::clear_context_cache;
+use crate::searcher::calibrate::{defining_files, is_plain_symbol, MAX_DEFINING_FILES};
+use crate::searcher::golden::{golden_path, load_golden};
+use crate::searcher::query::{execute_search, SearchOptions};
+
+/// Options for `run_bench`.
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// Time searches only, against the existing index.
+    pub skip_index: bool,
+    /// Times each query is run.
+    pub runs: usize,
+    /// Symbol names sampled from the index when there are no `queries`
+    /// and no golden queries; half as many two-word queries are added.
+    pub sample: usize,
+    /// Queries to time instead of golden or sampled ones.
+    pub queries: Vec<String>,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            skip_index: false,
+            runs: 5,
+            sample: 10,
+            queries: Vec::new(),
+        }
+    }
+}
+
+/// Timings of one query.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryTiming {
+    pub query: String,
+    /// Where the query came from: `custom`, `golden`, `symbol` or `words`.
+    pub kind: &'static str,
+    pub results: usize,
+    /// First run, with an empty context cache.
+    pub cold_ms: f64,
+    /// Median over all runs.
+    pub median_ms: f64,
+}
+
+/// Outcome of `run_bench`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    /// Version of ns that ran the benchmark.
+    pub version: &'static str,
+    /// Files in the index.
+    pub files: usize,
+    /// Wall time of the full index; `None` with `skip_index`.
+    pub full_index_ms: Option<u64>,
+    /// Wall time of an incremental index with no changes; `None` with
+    /// `skip_index`.
+    pub incremental_index_ms: Option<u64>,
+    /// Runs per query.
+    pub runs: usize,
+    /// Median and 95th percentile of the queries' `median_ms`.
+    pub query_p50_ms: f64,
+    pub query_p95_ms: f64,
+    pub queries: Vec<QueryTiming>,
+}
+
+/// Benchmarks the repo at `root`. Fails with `NsError::Io` `NotFound` when
+/// there is nothing to index, or no index with `skip_index`.
+pub fn run_bench(root: &Path, opts: &BenchOptions) -> Result<BenchReport, NsError> {
+    let mut full_index_ms = None;
+    let mut incremental_index_ms = None;
+    if !opts.skip_index {
+        let index_opts = rebuild_options(read_meta(root).ok().as_ref());
+        let start = Instant::now();
+        if run_full_index(root, &index_opts)?.is_none() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "no indexable files found",
+            )
+            .into());
+        }
+        full_index_ms = Some(start.elapsed().as_millis() as u64);
+
+        let start = Instant::now();
+        run_incremental_index(root, &index_opts)?;
+        incremental_index_ms = Some(start.elapsed().as_millis() as u64);
+    }
+    let meta = read_meta(root)?;
+
+    let mut search_opts = SearchOptions {
+        force: true,
+        ..SearchOptions::default()
+    };
+    load_config(root)?.apply(&mut search_opts);
+    let runs = opts.runs.max(1);
+    let mut queries = Vec::new();
+    for (query, kind) in bench_queries(root, opts)? {
+        let mut times = Vec::with_capacity(runs);
+        let mut results = 0;
+        clear_context_cache();
+        for _ in 0..runs {
+            let start = Instant::now();
+            let (found, _) = execute_search(root, &query, &search_opts)?;
+            times.push(start.elapsed().as_secs_f64() * 1000.0);
+            results = found.len();
+        }
+        let cold_ms = times[0];
+        queries.push(QueryTiming {
+            query,
+            kind,
+            results,
+            cold_ms: round_ms(cold_ms),
+            median_ms: round_ms(percentile(&mut times, 50)),
+        });
+    }
+    let mut medians: Vec<f64> = queries.iter().map(|q| q.median_ms).collect();
+    Ok(BenchReport {
+        version: env!("CARGO_PKG_VERSION"),
+        files: meta.file_count,
+        full_index_ms,
+        incremental_index_ms,
+        runs,
+        query_p50_ms: round_ms(percentile(&mut medians, 50)),
+        query_p95_ms: round_ms(percentile(&mut medians, 95)),
+        queries,
+    })
+}
+
+/// Options for rebuilding an index like `previous`: the fields a full
+/// rebuild doesn't take from `meta.json` by itself.
+fn rebuild_options(previous: Option<&IndexMeta>) -> IndexOptions {
+    let Some(meta) = previous else {
+        return IndexOptions::default();
+    };
+    IndexOptions {
+        max_index_size: meta.max_index_size,
+        compression: if meta.max_index_size.is_some() {
+            StoredCompression::Zstd
+        } else {
+            StoredCompression::default()
+        },
+        ngram: meta.ngram,
+        stem: meta.stem,
+        cjk: meta.cjk,
+        no_symbols: meta.no_symbols,
+        follow_symlinks: meta.follow_symlinks,
+        ..IndexOptions::default()
+    }
+}
+
+/// The queries to time, with their `QueryTiming::kind`.
+fn bench_queries(root: &Path, opts: &BenchOptions) -> Result<Vec<(String, &'static str)>, NsError> {
+    if !opts.queries.is_empty() {
+        return Ok(opts.queries.iter().map(|q| (q.clone(), "custom")).collect());
+    }
+    let golden = golden_path(root);
+    if golden.exists() {
+        let queries = load_golden(&golden)?;
+        if !queries.is_empty() {
+            return Ok(queries.into_iter().map(|g| (g.query, "golden")).collect());
+        }
+    }
+
+    let definitions = defining_files(root)?;
+    let eligible: Vec<&String> = definitions
+        .iter()
+        .filter(|(name, files)| is_plain_symbol(name) && files.len() <= MAX_DEFINING_FILES)
+        .map(|(name, _)| name)
+        .collect();
+    let names = evenly_spaced(&eligible, Some(opts.sample));
+    let mut queries: Vec<(String, &'static str)> =
+        names.iter().map(|name| (name.to_string(), "symbol")).collect();
+    queries.extend(
+        names
+            .chunks_exact(2)
+            .map(|pair| (format!("{} {}", pair[0], pair[1]), "words")),
+    );
+    Ok(queries)
+}
+
+/// The `p`th percentile of `values` (nearest rank), 0 when empty. Sorts
+/// `values`.
+fn percentile(values: &mut [f64], p: usize) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let rank = (p * values.len()).div_ceil(100).max(1);
+    values[rank - 1]
+}
+
+fn round_ms(ms: f64) -> f64 {
+    (ms * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let mut values = vec![5.0, 1.0, 4.0, 2.0, 3.0];
+        assert_eq!(percentile(&mut values, 50), 3.0);
+        assert_eq!(percentile(&mut values, 95), 5.0);
+        assert_eq!(percentile(&mut values, 0), 1.0);
+        assert_eq!(percentile(&mut [], 50), 0.0);
+    }
+}
@@ -0,0 +1,74 @@
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::cmd::{AuditArgs, Cli};
+use crate::error::NsError;
+use crate::searcher::audit::{audit, AuditMatch, AuditOptions};
+
+pub fn run(args: &AuditArgs, cli: &Cli) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let opts = AuditOptions {
+        regex: args.regex,
+        lines: args.lines,
+        file_type: cli.file_type.clone(),
+        file_glob: cli.file_glob.clone(),
+    };
+
+    // Matches are written as they are found; stdout is flushed per file so a
+    // long sweep shows progress when piped.
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut emit = |found: &AuditMatch| -> std::io::Result<()> {
+        if args.json {
+            let line = serde_json::to_string(found).map_err(std::io::Error::other)?;
+            writeln!(out, "{}", line)?;
+        } else if args.lines {
+            for l in &found.lines {
+                writeln!(out, "{}:{}:{}", found.path, l.line, l.text)?;
+            }
+        } else {
+            writeln!(out, "{}", found.path)?;
+        }
+        out.flush()
+    };
+
+    match audit(&root, &args.query, &opts, &mut emit) {
+        Ok(stats) => {
+            if args.lines {
+                eprintln!("{} files, {} lines", stats.files, stats.lines);
+            } else {
+                eprintln!("{} files", stats.files);
+            }
+            if stats.files == 0 {
+                std::process::exit(1);
+            }
+        }
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(NsError::QueryParse(e)) => {
+            eprintln!("error: invalid query: {}", e);
+            std::process::exit(1);
+        }
+        Err(NsError::Regex(e)) => {
+            eprintln!("error: invalid regex: {}", e);
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: audit failed: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use crate::bench::{run_bench, BenchOptions, BenchReport};
+use crate::cmd::BenchArgs;
+use crate::error::NsError;
+
+pub fn run(args: &BenchArgs) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let opts = BenchOptions {
+        skip_index: args.skip_index,
+        runs: args.runs,
+        sample: args.sample,
+        queries: args.queries.clone(),
+    };
+    if !args.skip_index {
+        eprintln!("Rebuilding the index...");
+    }
+    let report = match run_bench(&root, &opts) {
+        Ok(r) => r,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            if args.skip_index {
+                eprintln!("error: no index found. Run 'ns index' to create one.");
+            } else {
+                eprintln!("error: {}", e);
+            }
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(err) if err.is_lock_error() => {
+            eprintln!("error: index is locked by another process.");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: benchmark failed: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    if args.json {
+        match serde_json::to_string(&report) {
+            Ok(json) => println!("{}", json),
+            Err(err) => {
+                eprintln!("error: cannot serialize report: {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    print_report(&report);
+}
+
+fn print_report(report: &BenchReport) {
+    println!("ns bench (ns {}, {} files)", report.version, report.files);
+    if let Some(ms) = report.full_index_ms {
+        println!("  full index        : {} ms", ms);
+    }
+    if let Some(ms) = report.incremental_index_ms {
+        println!("  incremental index : {} ms (no changes)", ms);
+    }
+    println!(
+        "  queries           : {} x {} runs, p50 {:.2} ms, p95 {:.2} ms",
+        report.queries.len(),
+        report.runs,
+        report.query_p50_ms,
+        report.query_p95_ms
+    );
+    let width = report.queries.iter().map(|q| q.query.len()).max().unwrap_or(0);
+    for q in &report.queries {
+        println!(
+            "    {:<6} {:<width$}  {:>8.2} ms  cold {:>8.2} ms  results {}",
+            q.kind,
+            q.query,
+            q.median_ms,
+            q.cold_ms,
+            q.results,
+            width = width
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+use crate::cmd::CheckArgs;
+use crate::error::NsError;
+use crate::searcher::golden::{golden_path, load_golden, run_golden};
+
+pub fn run(args: &CheckArgs) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let path = args.file.clone().unwrap_or_else(|| golden_path(&root));
+    let queries = match load_golden(&path) {
+        Ok(q) => q,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!(
+                "error: {} not found. Add [[query]] entries with `query` and `expect` to it.",
+                path.display()
+            );
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: cannot read {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    };
+
+    let outcomes = match run_golden(&root, &queries) {
+        Ok(o) => o,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: golden query failed: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let failed = outcomes.iter().filter(|o| !o.passed()).count();
+
+    if args.json {
+        let json = serde_json::json!({
+            "queries": outcomes.len(),
+            "failed": failed,
+            "results": outcomes
+                .iter()
+                .map(|o| {
+                    let mut v = serde_json::to_value(o).unwrap_or_default();
+                    v["passed"] = o.passed().into();
+                    v
+                })
+                .collect::<Vec<_>>(),
+        });
+        println!("{}", json);
+    } else {
+        for o in &outcomes {
+            let status = if o.passed() { "ok  " } else { "FAIL" };
+            let rank = o
+                .rank
+                .map_or_else(|| "not found".to_string(), |r| format!("rank {}", r));
+            print!("{} {:<32} {} ({}", status, o.golden.query, o.golden.expect, rank);
+            if !o.passed() {
+                if let Some(ref got) = o.got {
+                    print!(", got {}", got);
+                }
+            }
+            println!(")");
+        }
+        println!("{} passed, {} failed", outcomes.len() - failed, failed);
+    }
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
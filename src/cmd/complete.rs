@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+
+use crate::cmd::CompleteArgs;
+use crate::error::NsError;
+use crate::searcher::complete::complete;
+
+pub fn run(args: &CompleteArgs) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let completions = match complete(&root, &args.prefix, args.limit) {
+        Ok(c) => c,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: failed to read index: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    if args.json {
+        println!("{}", serde_json::json!(completions));
+    } else {
+        // One bare suggestion per line, for shell completion scripts.
+        for c in &completions {
+            println!("{}", c.text);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::cmd::{Cli, DefArgs};
+use crate::error::NsError;
+use crate::searcher::symbol_search::{find_definitions, preferred, FoundSymbol, SymbolSearchOptions};
+
+pub fn run(args: &DefArgs, cli: &Cli) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let opts = SymbolSearchOptions {
+        file_type: cli.file_type.clone(),
+        file_glob: cli.file_glob.clone(),
+        kinds: Vec::new(),
+    };
+    let found = match find_definitions(&root, &args.symbol, &opts) {
+        Ok(f) => f,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(NsError::Glob(e)) => {
+            eprintln!("error: invalid glob: {}", e);
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: definition lookup failed: {}", err);
+            std::process::exit(1);
+        }
+    };
+    if found.is_empty() {
+        eprintln!(
+            "No definition of '{}'. Try 'ns symbols {}' for similar names, or 'ns {}' to search.",
+            args.symbol, args.symbol, args.symbol
+        );
+        std::process::exit(1);
+    }
+
+    let shown = if args.all {
+        &found[..]
+    } else {
+        preferred(&found, &args.symbol)
+    };
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let _ = print_definitions(&mut out, shown, args.json).and_then(|_| out.flush());
+    if shown.len() < found.len() {
+        eprintln!("{} more with --all", found.len() - shown.len());
+    }
+}
+
+fn print_definitions(
+    out: &mut impl Write,
+    definitions: &[FoundSymbol],
+    json: bool,
+) -> std::io::Result<()> {
+    for def in definitions {
+        if json {
+            let line = serde_json::to_string(def).map_err(std::io::Error::other)?;
+            writeln!(out, "{}", line)?;
+            continue;
+        }
+        let location = match (def.line, def.end_line) {
+            (Some(line), Some(end)) if end > line => format!("{}:{}-{}", def.path, line, end),
+            (Some(line), _) => format!("{}:{}", def.path, line),
+            _ => def.path.clone(),
+        };
+        writeln!(out, "{}  {} {}", location, def.kind.as_str(), def.name)?;
+        if let Some(text) = def.definition.as_deref().filter(|t| !t.is_empty()) {
+            writeln!(out, "    {}", text)?;
+        }
+    }
+    Ok(())
+}
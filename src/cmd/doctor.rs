@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+
+use crate::cmd::DoctorArgs;
+use crate::error::NsError;
+use crate::indexer::verify::verify_content;
+
+/// Maximum number of stale paths listed individually in text output.
+const STALE_REPORT_LIMIT: usize = 20;
+
+pub fn run(args: &DoctorArgs) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let sample = if args.sample == 0 { None } else { Some(args.sample) };
+    let check = match verify_content(&root, sample) {
+        Ok(c) => c,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: content check failed: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let stale_percent = check.stale_percent();
+
+    if args.json {
+        let json = serde_json::json!({
+            "indexed": check.indexed,
+            "checked": check.checked,
+            "missing": check.missing,
+            "changed": check.changed,
+            "stale_percent": (stale_percent * 10.0).round() / 10.0,
+        });
+        println!("{}", json);
+    } else {
+        println!("ns doctor --verify-content");
+        println!("  files indexed : {}", check.indexed);
+        println!("  files checked : {}", check.checked);
+        println!("  missing       : {}", check.missing.len());
+        println!("  changed       : {}", check.changed.len());
+        println!("  stale         : {:.1}%", stale_percent);
+
+        let stale: Vec<(&str, &String)> = check
+            .missing
+            .iter()
+            .map(|p| ("missing", p))
+            .chain(check.changed.iter().map(|p| ("changed", p)))
+            .collect();
+        for (kind, path) in stale.iter().take(STALE_REPORT_LIMIT) {
+            println!("    {:<8} {}", kind, path);
+        }
+        if stale.len() > STALE_REPORT_LIMIT {
+            println!("    ... and {} more", stale.len() - STALE_REPORT_LIMIT);
+        }
+        if !stale.is_empty() {
+            eprintln!("Run 'ns index --incremental' to bring the index up to date.");
+        }
+    }
+
+    if stale_percent > args.max_stale {
+        std::process::exit(1);
+    }
+}
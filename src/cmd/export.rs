@@ -0,0 +1,96 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::cmd::ExportArgs;
+use crate::error::NsError;
+use crate::export::ctags::export_ctags;
+use crate::export::scip::export_scip;
+use crate::indexer::bundle::write_bundle;
+
+pub fn run(args: &ExportArgs) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let default_output = if args.bundle {
+        "index.nsb"
+    } else if args.ctags {
+        "tags"
+    } else {
+        "index.scip"
+    };
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(default_output));
+
+    let file = match File::create(&output) {
+        Ok(f) => f,
+        Err(err) => {
+            eprintln!(
+                "error: cannot create '{}': {}",
+                output.display(),
+                err
+            );
+            std::process::exit(1);
+        }
+    };
+    let mut out = BufWriter::new(file);
+
+    let result = if args.bundle {
+        write_bundle(&root, &mut out).map(|stats| {
+            format!(
+                "Exported index bundle ({} files) to {} ({} bytes)",
+                stats.files,
+                output.display(),
+                stats.bytes_written
+            )
+        })
+    } else if args.ctags {
+        export_ctags(&root, &mut out).map(|stats| {
+            format!(
+                "Exported {} tags from {} files to {} ({} bytes)",
+                stats.tags,
+                stats.files,
+                output.display(),
+                stats.bytes_written
+            )
+        })
+    } else {
+        export_scip(&root, &mut out).map(|stats| {
+            format!(
+                "Exported {} documents, {} symbols ({} located) to {} ({} bytes)",
+                stats.documents,
+                stats.symbols,
+                stats.occurrences,
+                output.display(),
+                stats.bytes_written
+            )
+        })
+    };
+    let result = result.and_then(|summary| {
+        out.flush()?;
+        Ok(summary)
+    });
+
+    match result {
+        Ok(summary) => eprintln!("{}", summary),
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: export failed: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use crate::cmd::FreqArgs;
+use crate::error::NsError;
+use crate::searcher::calibrate::percent;
+use crate::searcher::freq::{term_frequencies, TermFreq};
+
+pub fn run(args: &FreqArgs) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let (report, total_docs) = match term_frequencies(&root, &args.terms, args.sym, args.by) {
+        Ok(r) => r,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: failed to read index: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    if args.json {
+        let json = serde_json::json!({
+            "field": if args.sym { "symbols" } else { "content" },
+            "total_docs": total_docs,
+            "terms": report,
+        });
+        println!("{}", json);
+    } else {
+        print_table(&report, total_docs);
+    }
+}
+
+fn print_table(report: &[TermFreq], total_docs: u64) {
+    let width = report
+        .iter()
+        .flat_map(|t| {
+            std::iter::once(t.term.len()).chain(t.groups.iter().map(|g| g.key.len() + 2))
+        })
+        .max()
+        .unwrap_or(4)
+        .max(4);
+
+    println!("{:<width$}  {:>6}  {:>6}  {:>11}", "term", "docs", "% docs", "occurrences");
+    for t in report {
+        println!(
+            "{:<width$}  {:>6}  {:>5.1}%  {:>11}",
+            t.term,
+            t.doc_freq,
+            percent(t.doc_freq, total_docs),
+            t.occurrences
+        );
+        for g in &t.groups {
+            println!(
+                "  {:<w$}  {:>6}  {:>5.1}%  {:>11}",
+                g.key,
+                g.doc_freq,
+                percent(g.doc_freq, total_docs),
+                g.occurrences,
+                w = width - 2
+            );
+        }
+    }
+    println!("({} files indexed)", total_docs);
+}
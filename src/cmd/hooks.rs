@@ -9,6 +9,7 @@ use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 
 use crate::cmd::HooksAction;
+use crate::config::{load_config, HOOK_EVENTS};
 
 /// Marker comment used to identify ns-managed hook lines.
 const NS_MARKER: &str = "# ns: auto-generated";
@@ -16,8 +17,8 @@ const NS_MARKER: &str = "# ns: auto-generated";
 /// The hook payload: run incremental indexing in the background.
 const NS_HOOK_LINE: &str = "ns index --incremental &";
 
-/// Hook names that ns installs.
-const HOOK_NAMES: &[&str] = &["post-commit", "post-merge", "post-checkout"];
+/// The hook payload with `[hooks] background = false`: git waits for it.
+const NS_HOOK_LINE_FOREGROUND: &str = "ns index --incremental";
 
 pub fn run(action: &HooksAction) {
     match action {
@@ -47,6 +48,18 @@ fn install() {
             std::process::exit(1);
         }
     };
+    let config = match load_config(Path::new(".")) {
+        Ok(c) => c.hooks,
+        Err(err) => {
+            eprintln!("error: cannot load .ns/config.toml: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let line = if config.background {
+        NS_HOOK_LINE
+    } else {
+        NS_HOOK_LINE_FOREGROUND
+    };
 
     if let Err(e) = fs::create_dir_all(&hooks_dir) {
         eprintln!("error: cannot create hooks directory: {}", e);
@@ -56,13 +69,17 @@ fn install() {
     let mut installed = 0;
     let mut skipped = 0;
 
-    for &hook_name in HOOK_NAMES {
+    for hook_name in &config.events {
         let hook_path = hooks_dir.join(hook_name);
-        match install_hook(&hook_path, hook_name) {
+        match install_hook(&hook_path, line) {
             HookResult::Created => {
                 eprintln!("installed {}", hook_name);
                 installed += 1;
             }
+            HookResult::Updated => {
+                eprintln!("updated ns hook in {}", hook_name);
+                installed += 1;
+            }
             HookResult::Appended => {
                 eprintln!("appended to existing {}", hook_name);
                 installed += 1;
@@ -99,13 +116,21 @@ fn install() {
 
 enum HookResult {
     Created,
+    /// The ns line was there, in its other form (see `HooksConfig::background`).
+    Updated,
     Appended,
     AlreadyPresent,
     NotShellScript,
     Error(String),
 }
 
-fn install_hook(hook_path: &Path, _hook_name: &str) -> HookResult {
+/// Whether `line` is an ns hook payload, in either form.
+fn is_ns_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed == NS_HOOK_LINE || trimmed == NS_HOOK_LINE_FOREGROUND
+}
+
+fn install_hook(hook_path: &Path, line: &str) -> HookResult {
     if hook_path.exists() {
         // Read existing content
         let content = match fs::read_to_string(hook_path) {
@@ -114,17 +139,29 @@ fn install_hook(hook_path: &Path, _hook_name: &str) -> HookResult {
         };
 
         // Already has our hook line?
-        if content.contains(NS_HOOK_LINE) {
+        if content.lines().any(|l| l.trim() == line) {
             return HookResult::AlreadyPresent;
         }
 
+        // Has it in the other form: swap it in place
+        if content.lines().any(is_ns_line) {
+            let swapped: Vec<&str> = content
+                .lines()
+                .map(|l| if is_ns_line(l) { line } else { l })
+                .collect();
+            if let Err(e) = fs::write(hook_path, format!("{}\n", swapped.join("\n"))) {
+                return HookResult::Error(format!("cannot write: {}", e));
+            }
+            return HookResult::Updated;
+        }
+
         // Check it's a shell script — must have a shell shebang
         if !is_shell_script(&content) {
             return HookResult::NotShellScript;
         }
 
         // Append our lines
-        let appendix = format!("\n{}\n{}\n", NS_MARKER, NS_HOOK_LINE);
+        let appendix = format!("\n{}\n{}\n", NS_MARKER, line);
         if let Err(e) = fs::write(hook_path, format!("{}{}", content, appendix)) {
             return HookResult::Error(format!("cannot write: {}", e));
         }
@@ -138,7 +175,7 @@ fn install_hook(hook_path: &Path, _hook_name: &str) -> HookResult {
         HookResult::Appended
     } else {
         // Create new hook
-        let content = format!("#!/bin/sh\n{}\n{}\n", NS_MARKER, NS_HOOK_LINE);
+        let content = format!("#!/bin/sh\n{}\n{}\n", NS_MARKER, line);
         if let Err(e) = fs::write(hook_path, content) {
             return HookResult::Error(format!("cannot write: {}", e));
         }
@@ -212,7 +249,9 @@ fn remove() {
 
     let mut removed = 0;
 
-    for &hook_name in HOOK_NAMES {
+    // Every hook ns knows, not just the configured ones, so narrowing
+    // `[hooks] events` doesn't strand old hooks.
+    for &hook_name in HOOK_EVENTS {
         let hook_path = hooks_dir.join(hook_name);
         match remove_hook(&hook_path, hook_name) {
             RemoveResult::Deleted => {
@@ -259,7 +298,7 @@ fn remove_hook(hook_path: &Path, _hook_name: &str) -> RemoveResult {
     };
 
     // Check for either marker or hook line (handles orphaned markers too)
-    if !content.contains(NS_HOOK_LINE) && !content.contains(NS_MARKER) {
+    if !content.lines().any(is_ns_line) && !content.contains(NS_MARKER) {
         return RemoveResult::NotPresent;
     }
 
@@ -267,8 +306,7 @@ fn remove_hook(hook_path: &Path, _hook_name: &str) -> RemoveResult {
     let cleaned: Vec<&str> = content
         .lines()
         .filter(|line| {
-            let trimmed = line.trim();
-            trimmed != NS_MARKER && trimmed != NS_HOOK_LINE
+            line.trim() != NS_MARKER && !is_ns_line(line)
         })
         .collect();
 
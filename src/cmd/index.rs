@@ -1,39 +1,557 @@
-use std::path::PathBuf;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
 
+use crate::cmd::status::format_bytes;
 use crate::cmd::IndexArgs;
+use crate::config::load_config;
 use crate::error::NsError;
 use crate::indexer;
-use crate::indexer::writer::check_gitignore_warning;
+use crate::indexer::embed::Embedder;
+use crate::indexer::listing::list_docs as read_doc_listing;
+use crate::indexer::notify;
+use crate::indexer::optimize::optimize_index;
+use crate::indexer::prune::{PrunedFile, StoredTrim};
+use crate::indexer::walker::{SkipKind, SkippedFile};
+use crate::indexer::writer::{
+    check_gitignore_warning, open_index, read_meta, utc_timestamp_iso8601, SCHEMA_VERSION,
+};
+use crate::indexer::incremental::IncrementalStats;
+use crate::indexer::integrity::check_index;
+use crate::indexer::{
+    IndexOptions, StoredCompression, MAX_MEMORY_MB, MAX_MEMORY_MB_PER_THREAD, MIN_MEMORY_MB_PER_THREAD,
+};
+use crate::registry;
+
+/// Maximum number of pruned paths listed individually on stderr.
+const PRUNED_REPORT_LIMIT: usize = 20;
+
+/// Maximum number of problems `--check` lists individually.
+const CHECK_REPORT_LIMIT: usize = 20;
+
+/// Maximum number of skipped paths listed individually per reason.
+const SKIPPED_REPORT_LIMIT: usize = 10;
 
 pub fn run(args: &IndexArgs) {
-    let root = args
-        .root
-        .clone()
-        .unwrap_or_else(|| PathBuf::from("."));
+    let given = if args.root.is_empty() {
+        vec![PathBuf::from(".")]
+    } else {
+        args.root.clone()
+    };
+    let dirs: Vec<PathBuf> = given
+        .iter()
+        .map(|dir| match dir.canonicalize() {
+            Ok(p) => p,
+            Err(err) => {
+                eprintln!("error: cannot resolve root path '{}': {}", dir.display(), err);
+                std::process::exit(1);
+            }
+        })
+        .collect();
+    let (root, mut roots) = match index_home(&dirs) {
+        Ok(home) => home,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+    };
+    // Without `--root` the index keeps the directories it remembers; an
+    // explicit `--root .` indexes the whole tree again.
+    if args.root.is_empty() {
+        roots.clear();
+    }
+    let config = match load_config(&root) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("error: cannot load .ns/config.toml: {}", err);
+            std::process::exit(1);
+        }
+    };
+    // The command line wins over the config file.
+    if roots.is_empty() {
+        roots = config.index.roots;
+    }
+    let exclude = if args.exclude.is_empty() {
+        config.index.exclude
+    } else {
+        args.exclude.clone()
+    };
+
+    let opts = IndexOptions {
+        max_file_size: args.max_file_size,
+        truncate_large_kb: args.truncate_large,
+        max_index_size: index_budget(args),
+        compression: compression(args),
+        ngram: args.ngram,
+        stem: args.stem,
+        cjk: args.cjk,
+        on_update: args.on_update.clone(),
+        churn_days: args.churn_days,
+        embed_model: args.embed_model.as_deref().map(resolve_model_dir),
+        exclude,
+        max_depth: args.max_depth,
+        no_symbols: args.no_symbols,
+        follow_symlinks: args.follow_symlinks,
+        chunk_lines: args.chunk_lines,
+        verbose: args.verbose,
+        roots,
+        memory_mb: args.memory_mb,
+        threads: args.threads,
+    };
+    if let Some(threads) = args.threads {
+        let needed = (threads as u64).saturating_mul(MIN_MEMORY_MB_PER_THREAD);
+        if args.memory_mb < needed {
+            eprintln!(
+                "error: --threads {} needs --memory-mb {} or more ({} MB per thread)",
+                threads, needed, MIN_MEMORY_MB_PER_THREAD
+            );
+            std::process::exit(1);
+        }
+        let most = (threads as u64).saturating_mul(MAX_MEMORY_MB_PER_THREAD);
+        if args.memory_mb > most {
+            eprintln!(
+                "error: --memory-mb {} needs more than --threads {} ({} MB per thread at most)",
+                args.memory_mb, threads, MAX_MEMORY_MB_PER_THREAD
+            );
+            std::process::exit(1);
+        }
+    }
 
-    let root = match root.canonicalize() {
+    if args.list_docs {
+        list_docs(&root, args.path_prefix.as_deref());
+        return;
+    }
+
+    if args.check {
+        if !run_check(&root) {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if args.optimize {
+        if !run_optimize(&root, &opts) {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(every) = args.every {
+        if args.daemonize {
+            let dirs = if args.root.is_empty() { &[][..] } else { &dirs[..] };
+            spawn_daemon(&root, dirs, args, every);
+        } else {
+            run_scheduled(&root, &opts, every);
+        }
+        return;
+    }
+
+    let ok = if args.incremental {
+        run_incremental(&root, &opts, args.json)
+    } else {
+        run_full(&root, &opts)
+    };
+    if !ok {
+        std::process::exit(1);
+    }
+}
+
+/// Re-indexes every `every`, forever: a full build when there is no usable
+/// index yet, incremental updates after that. Errors are reported and the
+/// loop keeps going, so a transient lock or I/O failure doesn't stop it.
+fn run_scheduled(root: &Path, opts: &IndexOptions, every: Duration) {
+    eprintln!(
+        "Re-indexing {} every {}s (Ctrl-C to stop)",
+        root.display(),
+        every.as_secs()
+    );
+    loop {
+        eprintln!("[{}]", utc_timestamp_iso8601());
+        let has_index = matches!(read_meta(root), Ok(meta) if meta.schema_version == SCHEMA_VERSION);
+        if has_index {
+            run_incremental(root, opts, false);
+        } else {
+            run_full(root, opts);
+        }
+        std::thread::sleep(every);
+    }
+}
+
+/// The directory an index over `dirs` lives in, and `dirs` relative to it
+/// (`IndexOptions::roots`). A single directory is its own home, indexed
+/// whole (the root `""`); several are indexed into their nearest common
+/// ancestor, leaving out any inside another. Unrelated directories, whose
+/// only common ancestor is `/`, are an error.
+fn index_home(dirs: &[PathBuf]) -> Result<(PathBuf, Vec<String>), String> {
+    let mut home = dirs[0].clone();
+    while !dirs.iter().all(|dir| dir.starts_with(&home)) {
+        if !home.pop() {
+            break;
+        }
+    }
+    let outermost: Vec<&PathBuf> = dirs
+        .iter()
+        .filter(|dir| !dirs.iter().any(|other| other != *dir && dir.starts_with(other)))
+        .collect();
+    let mut roots: Vec<String> = outermost
+        .iter()
+        .filter_map(|dir| dir.strip_prefix(&home).ok())
+        .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+        .collect();
+    roots.sort();
+    roots.dedup();
+    if roots.iter().any(|r| r.is_empty()) {
+        roots = vec![String::new()];
+    } else if home.parent().is_none() {
+        return Err(format!(
+            "{} share no directory below {}; index them separately",
+            outermost.iter().map(|dir| dir.display().to_string()).collect::<Vec<_>>().join(" and "),
+            home.display()
+        ));
+    }
+    Ok((home, roots))
+}
+
+/// Starts `ns index --every` as a detached background process logging to
+/// `.ns/index-daemon.log` under `root`, then returns. `dirs` are the
+/// `--root` directories given, passed on as they were.
+fn spawn_daemon(root: &Path, dirs: &[PathBuf], args: &IndexArgs, every: Duration) {
+    let exe = match std::env::current_exe() {
         Ok(p) => p,
         Err(err) => {
-            eprintln!("error: cannot resolve root path '{}': {}", root.display(), err);
+            eprintln!("error: cannot locate ns executable: {}", err);
             std::process::exit(1);
         }
     };
 
-    if args.incremental {
-        run_incremental(&root, args.max_file_size);
-    } else {
-        run_full(&root, args.max_file_size);
+    let ns_dir = root.join(".ns");
+    let log_path = ns_dir.join("index-daemon.log");
+    let log = std::fs::create_dir_all(&ns_dir).and_then(|_| {
+        OpenOptions::new().create(true).append(true).open(&log_path)
+    });
+    let log = match log {
+        Ok(f) => f,
+        Err(err) => {
+            eprintln!("error: cannot open {}: {}", log_path.display(), err);
+            std::process::exit(1);
+        }
+    };
+    let log_err = match log.try_clone() {
+        Ok(f) => f,
+        Err(err) => {
+            eprintln!("error: cannot open {}: {}", log_path.display(), err);
+            std::process::exit(1);
+        }
+    };
+
+    let mut cmd = Command::new(exe);
+    cmd.arg("index");
+    for dir in dirs {
+        cmd.arg("--root").arg(dir);
+    }
+    cmd.arg("--every")
+        .arg(format!("{}s", every.as_secs()))
+        .arg("--max-file-size")
+        .arg(args.max_file_size.to_string())
+        .arg("--compression")
+        .arg(compression(args).as_str());
+    if let Some(kb) = args.truncate_large {
+        cmd.arg("--truncate-large").arg(kb.to_string());
+    }
+    if let Some(budget) = index_budget(args) {
+        cmd.arg("--max-index-size").arg(budget.to_string());
+    }
+    if args.ngram {
+        cmd.arg("--ngram");
+    }
+    if args.stem {
+        cmd.arg("--stem");
+    }
+    if args.cjk {
+        cmd.arg("--cjk");
+    }
+    if let Some(days) = args.churn_days {
+        cmd.arg("--churn-days").arg(days.to_string());
+    }
+    if let Some(ref command) = args.on_update {
+        cmd.arg("--on-update").arg(command);
+    }
+    if let Some(ref model) = args.embed_model {
+        cmd.arg("--embed-model").arg(resolve_model_dir(model));
+    }
+    for glob in &args.exclude {
+        cmd.arg("--exclude").arg(glob);
+    }
+    if let Some(depth) = args.max_depth {
+        cmd.arg("--max-depth").arg(depth.to_string());
+    }
+    if args.no_symbols {
+        cmd.arg("--no-symbols");
+    }
+    if args.follow_symlinks {
+        cmd.arg("--follow-symlinks");
+    }
+    if let Some(lines) = args.chunk_lines {
+        cmd.arg("--chunk-lines").arg(lines.to_string());
+    }
+    cmd.arg("--memory-mb").arg(args.memory_mb.to_string());
+    if let Some(threads) = args.threads {
+        cmd.arg("--threads").arg(threads.to_string());
+    }
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::from(log))
+        .stderr(Stdio::from(log_err));
+
+    // Start a new session so closing the terminal doesn't SIGHUP the indexer.
+    #[cfg(unix)]
+    unsafe {
+        use std::os::unix::process::CommandExt;
+        cmd.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+
+    match cmd.spawn() {
+        Ok(child) => {
+            eprintln!(
+                "Started background indexer (pid {}), logging to {}",
+                child.id(),
+                log_path.display()
+            );
+        }
+        Err(err) => {
+            eprintln!("error: failed to start background indexer: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Prints every document in the index, marking tombstones (deleted documents
+/// not yet merged away), followed by per-segment counts.
+fn list_docs(root: &Path, path_prefix: Option<&str>) {
+    let listing = match open_index(root).and_then(|(index, _)| read_doc_listing(&index, path_prefix)) {
+        Ok(l) => l,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: failed to read index: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let path_width = listing.docs.iter().map(|d| d.path.len()).max().unwrap_or(4).max(4);
+    println!("{:<path_width$}  {:<12}  {:<8}  state", "path", "lang", "segment");
+    for doc in &listing.docs {
+        println!(
+            "{:<path_width$}  {:<12}  {:<8}  {}",
+            doc.path,
+            doc.lang.as_deref().unwrap_or("-"),
+            doc.segment,
+            if doc.deleted { "deleted" } else { "live" }
+        );
+    }
+
+    let live: u32 = listing.segments.iter().map(|s| s.live).sum();
+    let deleted: u32 = listing.segments.iter().map(|s| s.deleted).sum();
+    eprintln!(
+        "{} live, {} deleted (unmerged) documents in {} segment(s)",
+        live,
+        deleted,
+        listing.segments.len()
+    );
+    for segment in listing.segments.iter().filter(|s| s.deleted > 0) {
+        eprintln!(
+            "  segment {}: {} live, {} deleted",
+            segment.segment, segment.live, segment.deleted
+        );
     }
 }
 
-fn run_full(root: &std::path::Path, max_file_size: u64) {
-    match indexer::run_full_index(root, max_file_size) {
+/// Reports what `ns index --check` found; false when anything is wrong.
+fn run_check(root: &Path) -> bool {
+    let report = match check_index(root) {
+        Ok(r) => r,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            return false;
+        }
+        Err(err) => {
+            eprintln!("error: index check failed: {}", err);
+            return false;
+        }
+    };
+
+    println!("ns index --check");
+    match (&report.meta_error, report.meta_file_count) {
+        (Some(err), _) => println!("  meta.json      : {}", err),
+        (None, Some(count)) => println!("  meta.json      : ok, {} files", count),
+        (None, None) => println!("  meta.json      : ok"),
+    }
+    println!(
+        "  segments       : {} ({} missing, {} corrupted files)",
+        report.segments,
+        report.missing_files.len(),
+        report.corrupted_files.len()
+    );
+    if let Some(err) = &report.read_error {
+        println!("  documents      : {}", err);
+    } else if let Some(files) = report.indexed_files {
+        let note = if report.count_mismatch() { " (meta.json disagrees)" } else { "" };
+        println!("  documents      : {} files{}", files, note);
+        println!("  orphaned       : {}", report.orphaned.len());
+    }
+
+    let problems = report
+        .missing_files
+        .iter()
+        .map(|f| ("missing", f))
+        .chain(report.corrupted_files.iter().map(|f| ("corrupted", f)))
+        .chain(report.orphaned.iter().map(|p| ("orphaned", p)));
+    for (kind, path) in problems.take(CHECK_REPORT_LIMIT) {
+        println!("    {:<9} {}", kind, path);
+    }
+    let listed = report.missing_files.len() + report.corrupted_files.len() + report.orphaned.len();
+    if listed > CHECK_REPORT_LIMIT {
+        println!("    ... and {} more", listed - CHECK_REPORT_LIMIT);
+    }
+
+    if report.needs_rebuild() {
+        eprintln!("The index is damaged. Run 'ns index' to rebuild it.");
+    } else if !report.orphaned.is_empty() {
+        eprintln!("Run 'ns index --incremental' to drop documents of deleted files.");
+    }
+    report.is_ok()
+}
+
+/// Merges the index into one segment and reports what that saved.
+fn run_optimize(root: &Path, opts: &IndexOptions) -> bool {
+    match optimize_index(root, opts) {
+        Ok(stats) => {
+            eprintln!(
+                "Optimized index: {} segment(s) -> {}, {} deleted document(s) removed, {} -> {} in {}ms",
+                stats.segments_before,
+                stats.segments_after,
+                stats.deleted_docs,
+                format_bytes(stats.bytes_before),
+                format_bytes(stats.bytes_after),
+                stats.elapsed_ms
+            );
+            true
+        }
+        Err(err) => {
+            match &err {
+                NsError::Io(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    eprintln!("error: no index found. Run 'ns index' to create one.");
+                }
+                NsError::SchemaVersionMismatch { .. } => {
+                    eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+                }
+                _ if err.is_lock_error() => {
+                    eprintln!("error: index is locked by another process.");
+                }
+                _ => {
+                    eprintln!("error: optimizing the index failed: {}", err);
+                }
+            }
+            false
+        }
+    }
+}
+
+/// Parses `--memory-mb`: at least enough heap for one indexing thread.
+pub(crate) fn parse_memory_mb(s: &str) -> Result<u64, String> {
+    let mb: u64 = s
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid memory size '{}': expected megabytes, e.g. 200", s))?;
+    if mb < MIN_MEMORY_MB_PER_THREAD {
+        return Err(format!("the index writer needs at least {} MB", MIN_MEMORY_MB_PER_THREAD));
+    }
+    if mb > MAX_MEMORY_MB {
+        return Err(format!(
+            "the index writer takes at most {} MB ({} MB per thread)",
+            MAX_MEMORY_MB, MAX_MEMORY_MB_PER_THREAD
+        ));
+    }
+    Ok(mb)
+}
+
+/// Parses `--threads`: a positive thread count.
+pub(crate) fn parse_threads(s: &str) -> Result<usize, String> {
+    match s.trim().parse::<usize>() {
+        Ok(0) | Err(_) => Err(format!("invalid thread count '{}': expected a positive number", s)),
+        Ok(n) => Ok(n),
+    }
+}
+
+/// Parses an interval such as `15m`, `30s`, `2h` or `90` (seconds).
+pub(crate) fn parse_interval(s: &str) -> Result<Duration, String> {
+    let trimmed = s.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid interval '{}': expected e.g. 15m", s))?;
+    let secs = match unit {
+        "" | "s" => value,
+        "m" => value * 60,
+        "h" => value * 3600,
+        other => return Err(format!("invalid interval unit '{}': use s, m or h", other)),
+    };
+    if secs == 0 {
+        return Err("interval must be greater than zero".to_string());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// Absolute path of an `--embed-model` directory, so searches and the
+/// background indexer find it from any working directory. Exits if the
+/// model can't be loaded, before any indexing work. `''` passes through.
+fn resolve_model_dir(dir: &str) -> String {
+    if dir.is_empty() {
+        return String::new();
+    }
+    let path = match Path::new(dir).canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve embedding model '{}': {}", dir, err);
+            std::process::exit(1);
+        }
+    };
+    if let Err(err) = Embedder::load(&path) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+    path.to_string_lossy().into_owned()
+}
+
+pub(crate) fn run_full(root: &Path, opts: &IndexOptions) -> bool {
+    match indexer::run_full_index(root, opts) {
         Ok(None) => {
             eprintln!("No indexable files found.");
+            true
         }
         Ok(Some(stats)) => {
             eprintln!("Indexed {} files in {}ms", stats.file_count, stats.elapsed_ms);
+            report_budget(root, stats.trimmed, &stats.pruned);
+            if opts.verbose {
+                report_skipped(&stats.skipped);
+            }
+            register_repo(root);
+            notify_update(root);
             check_gitignore_warning(root);
+            true
         }
         Err(err) => {
             match &err {
@@ -53,14 +571,17 @@ fn run_full(root: &std::path::Path, max_file_size: u64) {
                     eprintln!("error: indexing failed: {}", err);
                 }
             }
-            std::process::exit(1);
+            false
         }
     }
 }
 
-fn run_incremental(root: &std::path::Path, max_file_size: u64) {
-    match indexer::run_incremental_index(root, max_file_size) {
+pub(crate) fn run_incremental(root: &Path, opts: &IndexOptions, json: bool) -> bool {
+    match indexer::run_incremental_index(root, opts) {
         Ok(stats) => {
+            if json {
+                println!("{}", incremental_json(&stats));
+            }
             if stats.added == 0 && stats.modified == 0 && stats.deleted == 0 {
                 eprintln!("Index is up to date.");
             } else {
@@ -69,7 +590,16 @@ fn run_incremental(root: &std::path::Path, max_file_size: u64) {
                     stats.added, stats.modified, stats.deleted, stats.elapsed_ms
                 );
             }
+            report_budget(root, stats.trimmed, &stats.pruned);
+            if opts.verbose {
+                report_skipped(&stats.skipped);
+            }
+            register_repo(root);
+            if stats.added + stats.modified + stats.deleted > 0 {
+                notify_update(root);
+            }
             check_gitignore_warning(root);
+            true
         }
         Err(err) => {
             match &err {
@@ -97,7 +627,223 @@ fn run_incremental(root: &std::path::Path, max_file_size: u64) {
                     eprintln!("error: incremental indexing failed: {}", err);
                 }
             }
-            std::process::exit(1);
+            false
         }
     }
 }
+
+/// `ns index --incremental --json` output: what changed, and how long each
+/// re-indexed file took.
+fn incremental_json(stats: &IncrementalStats) -> serde_json::Value {
+    let files: Vec<serde_json::Value> = stats
+        .file_timings
+        .iter()
+        .map(|t| {
+            serde_json::json!({
+                "path": t.path,
+                "elapsed_us": t.elapsed_us,
+                "indexed": t.indexed,
+            })
+        })
+        .collect();
+    let pruned: Vec<serde_json::Value> = stats
+        .pruned
+        .iter()
+        .map(|p| {
+            serde_json::json!({
+                "path": p.path,
+                "bytes": p.bytes,
+                "reason": p.reason.as_str(),
+            })
+        })
+        .collect();
+    let skipped: Vec<serde_json::Value> = stats
+        .skipped
+        .iter()
+        .map(|s| serde_json::json!({ "path": s.rel_path, "reason": s.kind.as_str() }))
+        .collect();
+    serde_json::json!({
+        "added": stats.added_paths,
+        "modified": stats.modified_paths,
+        "deleted": stats.deleted_paths,
+        "elapsed_ms": stats.elapsed_ms,
+        "files": files,
+        "pruned": pruned,
+        "skipped": skipped,
+    })
+}
+
+/// Records this repo in the global `ns repos` registry (best-effort).
+fn register_repo(root: &Path) {
+    if let Ok(meta) = read_meta(root) {
+        registry::record_index(root, &meta);
+    }
+}
+
+/// Runs the `--on-update` command recorded in `meta.json`, if any. Failures
+/// are reported but don't fail the index run: the index itself is written.
+fn notify_update(root: &Path) {
+    let Some(command) = read_meta(root).ok().and_then(|m| m.on_update) else {
+        return;
+    };
+    match notify::run_on_update(root, &command) {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("warning: on-update command exited with {}", status),
+        Err(err) => eprintln!("warning: cannot run on-update command: {}", err),
+    }
+}
+
+/// The index size budget in bytes: `--max-index-size`, or `--index-budget`
+/// in megabytes.
+fn index_budget(args: &IndexArgs) -> Option<u64> {
+    args.max_index_size
+        .or(args.index_budget.map(|mb| mb * 1024 * 1024))
+}
+
+/// Stored field compression: `--compression`, else zstd when the index has
+/// a size budget to fit, else the default lz4.
+fn compression(args: &IndexArgs) -> StoredCompression {
+    match args.compression {
+        Some(compression) => compression,
+        None if index_budget(args).is_some() => StoredCompression::Zstd,
+        None => StoredCompression::default(),
+    }
+}
+
+/// Reports stored fields left out and documents dropped to honour the
+/// index size budget.
+fn report_budget(root: &Path, trimmed: Option<StoredTrim>, pruned: &[PrunedFile]) {
+    let budget = read_meta(root)
+        .ok()
+        .and_then(|m| m.max_index_size.map(|b| (b, m.index_size_bytes)));
+    if let Some(trim) = trimmed {
+        match budget {
+            Some((budget, size)) => eprintln!(
+                "Left out {} to fit index budget of {} (index size: {})",
+                trim.as_str(),
+                format_bytes(budget),
+                format_bytes(size)
+            ),
+            None => eprintln!("Left out {} to fit index budget", trim.as_str()),
+        }
+    }
+    if pruned.is_empty() {
+        return;
+    }
+    match budget {
+        Some((budget, size)) => eprintln!(
+            "Pruned {} file{} to fit index budget of {} (index size: {}):",
+            pruned.len(),
+            if pruned.len() == 1 { "" } else { "s" },
+            format_bytes(budget),
+            format_bytes(size)
+        ),
+        None => eprintln!("Pruned {} files to fit index budget:", pruned.len()),
+    }
+    for file in pruned.iter().take(PRUNED_REPORT_LIMIT) {
+        eprintln!(
+            "  {} ({}, {})",
+            file.path,
+            file.reason.as_str(),
+            format_bytes(file.bytes)
+        );
+    }
+    if pruned.len() > PRUNED_REPORT_LIMIT {
+        eprintln!("  ... and {} more", pruned.len() - PRUNED_REPORT_LIMIT);
+    }
+}
+
+/// Reports what the walk left out (`--verbose`), grouped by reason.
+fn report_skipped(skipped: &[SkippedFile]) {
+    eprint!("{}", skipped_report(skipped));
+}
+
+fn skipped_report(skipped: &[SkippedFile]) -> String {
+    if skipped.is_empty() {
+        return "Skipped nothing.\n".to_string();
+    }
+    let mut kinds: Vec<SkipKind> = skipped.iter().map(|s| s.kind).collect();
+    kinds.sort();
+    kinds.dedup();
+    let mut out = format!(
+        "Skipped {} path{}:\n",
+        skipped.len(),
+        if skipped.len() == 1 { "" } else { "s" }
+    );
+    for kind in kinds {
+        let paths: Vec<&str> = skipped
+            .iter()
+            .filter(|s| s.kind == kind)
+            .map(|s| s.rel_path.as_str())
+            .collect();
+        out.push_str(&format!("  {} ({}):\n", kind.as_str(), paths.len()));
+        for path in paths.iter().take(SKIPPED_REPORT_LIMIT) {
+            out.push_str(&format!("    {}\n", path));
+        }
+        if paths.len() > SKIPPED_REPORT_LIMIT {
+            out.push_str(&format!("    ... and {} more\n", paths.len() - SKIPPED_REPORT_LIMIT));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_intervals() {
+        assert_eq!(parse_interval("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_interval("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_interval("15m"), Ok(Duration::from_secs(900)));
+        assert_eq!(parse_interval("2h"), Ok(Duration::from_secs(7200)));
+        assert!(parse_interval("0m").is_err());
+        assert!(parse_interval("5d").is_err());
+        assert!(parse_interval("soon").is_err());
+    }
+
+    #[test]
+    fn parses_memory_and_threads() {
+        assert_eq!(parse_memory_mb("200"), Ok(200));
+        assert!(parse_memory_mb("10").is_err());
+        assert!(parse_memory_mb("1GB").is_err());
+        assert_eq!(parse_memory_mb("32760"), Ok(32760));
+        assert!(parse_memory_mb("32761").is_err());
+        assert!(parse_memory_mb("18446744073709551615").is_err());
+        assert_eq!(parse_threads("4"), Ok(4));
+        assert!(parse_threads("0").is_err());
+    }
+
+    #[test]
+    fn several_roots_are_indexed_into_their_common_parent() {
+        let dirs = |paths: &[&str]| -> Vec<PathBuf> { paths.iter().map(PathBuf::from).collect() };
+        let whole = vec![String::new()];
+        assert_eq!(index_home(&dirs(&["/w/api"])), Ok((PathBuf::from("/w/api"), whole.clone())));
+        assert_eq!(
+            index_home(&dirs(&["/w/web", "/w/api", "/w/api/internal"])),
+            Ok((PathBuf::from("/w"), vec!["api".to_string(), "web".to_string()]))
+        );
+        assert_eq!(
+            index_home(&dirs(&["/w/lib/core", "/w/web"])),
+            Ok((PathBuf::from("/w"), vec!["lib/core".to_string(), "web".to_string()]))
+        );
+        assert_eq!(index_home(&dirs(&["/w", "/w/api"])), Ok((PathBuf::from("/w"), whole.clone())));
+        assert_eq!(index_home(&dirs(&["/"])), Ok((PathBuf::from("/"), whole)));
+        let err = index_home(&dirs(&["/srv/api", "/home/me/web"])).unwrap_err();
+        assert!(err.contains("/srv/api and /home/me/web share no directory below /"), "{err}");
+    }
+
+    #[test]
+    fn skipped_report_groups_by_reason() {
+        let skip = |path: &str, kind| SkippedFile {
+            rel_path: path.to_string(),
+            kind,
+        };
+        let mut skipped = vec![skip("target/", SkipKind::Ignored), skip("logo.png", SkipKind::Binary)];
+        skipped.extend((0..12).map(|i| skip(&format!("dump{}.sql", i), SkipKind::TooLarge)));
+        let report = skipped_report(&skipped);
+        assert!(report.starts_with("Skipped 14 paths:\n  too large (12):\n    dump0.sql\n"), "{report}");
+        assert!(report.contains("    ... and 2 more\n  binary (1):\n    logo.png\n  ignored (1):\n    target/\n"), "{report}");
+        assert_eq!(skipped_report(&[]), "Skipped nothing.\n");
+    }
+}
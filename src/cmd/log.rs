@@ -0,0 +1,102 @@
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::cmd::LogArgs;
+use crate::indexer::writer::iso8601_at;
+use crate::stats::{read_search_log, LoggedSearch, SearchLogFilter, SearchOutcome};
+
+pub fn run(args: &LogArgs) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let filter = SearchLogFilter {
+        since: args.since.map(iso8601_at),
+        outcome: args.outcome,
+        query_contains: args.query_contains.clone(),
+    };
+    let Some(searches) = read_search_log(&root, &filter) else {
+        eprintln!("No searches logged yet (.ns/search_log.jsonl is missing).");
+        std::process::exit(1);
+    };
+    if searches.is_empty() {
+        eprintln!("No logged searches match.");
+        std::process::exit(1);
+    }
+    let shown = match args.limit {
+        0 => &searches[..],
+        n => &searches[searches.len().saturating_sub(n)..],
+    };
+
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let _ = print_searches(&mut out, shown, args.json).and_then(|_| out.flush());
+    if shown.len() < searches.len() {
+        eprintln!(
+            "{} of {} matching searches shown (-n 0 for all)",
+            shown.len(),
+            searches.len()
+        );
+    }
+}
+
+fn print_searches(out: &mut impl Write, searches: &[LoggedSearch], json: bool) -> std::io::Result<()> {
+    for search in searches {
+        if json {
+            writeln!(out, "{}", search.line)?;
+            continue;
+        }
+        let outcome = search.outcome();
+        writeln!(
+            out,
+            "{:<20}  {:<10}  {:>6} tok  {:>3} files  {}",
+            search.ts,
+            outcome.as_str(),
+            search.tokens,
+            search.files,
+            search.query
+        )?;
+        // The command line, when it held more than the query.
+        if search.argv.iter().any(|arg| *arg != search.query && arg != "--") {
+            writeln!(out, "    ns {}", shell_words(&search.argv))?;
+        }
+        if outcome == SearchOutcome::Error {
+            if let Some(error) = &search.error {
+                writeln!(out, "    {}: {}", error.code, error.message)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `argv` joined with spaces, quoting the arguments that need it.
+fn shell_words(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            if !arg.is_empty() && arg.chars().all(|c| c.is_alphanumeric() || "-_./=:,".contains(c)) {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_words_quotes_what_needs_it() {
+        let argv: Vec<String> = ["-t", "rust", "retry backoff", "it's", ""]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(shell_words(&argv), r"-t rust 'retry backoff' 'it'\''s' ''");
+    }
+}
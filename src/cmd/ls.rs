@@ -0,0 +1,85 @@
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::cmd::{Cli, LsArgs};
+use crate::error::NsError;
+use crate::indexer::listing::{list_files, ListOptions, ListedFile};
+
+pub fn run(args: &LsArgs, cli: &Cli) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let opts = ListOptions {
+        file_type: cli.file_type.clone(),
+        file_glob: cli.file_glob.clone(),
+    };
+    let files = match list_files(&root, &opts) {
+        Ok(f) => f,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(NsError::Glob(e)) => {
+            eprintln!("error: invalid glob: {}", e);
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: failed to read index: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let _ = print_files(&mut out, &files, args).and_then(|_| out.flush());
+
+    let symbols: Option<usize> = files.iter().map(|f| f.symbols).sum();
+    match symbols {
+        Some(symbols) => eprintln!("{} files, {} symbols", files.len(), symbols),
+        None => eprintln!("{} files (symbol counts not in the index)", files.len()),
+    }
+    if files.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+fn print_files(out: &mut impl Write, files: &[ListedFile], args: &LsArgs) -> std::io::Result<()> {
+    if args.json {
+        for file in files {
+            let line = serde_json::to_string(file).map_err(std::io::Error::other)?;
+            writeln!(out, "{}", line)?;
+        }
+        return Ok(());
+    }
+    if args.paths_only {
+        for file in files {
+            writeln!(out, "{}", file.path)?;
+        }
+        return Ok(());
+    }
+    if files.is_empty() {
+        return Ok(());
+    }
+    let path_width = files.iter().map(|f| f.path.len()).max().unwrap_or(4).max(4);
+    writeln!(out, "{:<path_width$}  {:<12}  {:>7}", "path", "lang", "symbols")?;
+    for file in files {
+        let symbols = file.symbols.map_or("-".to_string(), |n| n.to_string());
+        writeln!(
+            out,
+            "{:<path_width$}  {:<12}  {:>7}",
+            file.path,
+            file.lang.as_deref().unwrap_or("-"),
+            symbols
+        )?;
+    }
+    Ok(())
+}
@@ -1,11 +1,44 @@
+pub mod audit;
+pub mod bench;
+pub mod check;
+pub mod complete;
+pub mod def;
+pub mod doctor;
+pub mod export;
+pub mod freq;
 pub mod hooks;
 pub mod index;
+pub mod log;
+pub mod ls;
+pub mod outline;
+pub mod refs;
+pub mod repl;
+pub mod repos;
 pub mod search;
+pub mod stats;
 pub mod status;
+pub mod symbols;
+pub mod tui;
+pub mod tune;
+pub mod watch;
+pub mod why;
 
 use std::path::PathBuf;
+use std::time::Duration;
 
-use crate::stats::SearchLogFlags;
+use crate::config::SearchConfig;
+use crate::cmd::index::{parse_interval, parse_memory_mb, parse_threads};
+use crate::cmd::search::{parse_separator, parse_since};
+use crate::indexer::prune::parse_byte_size;
+use crate::indexer::symbols::SymbolKind;
+use crate::indexer::walker::parse_exclude;
+use crate::indexer::{StoredCompression, DEFAULT_WRITER_MEMORY_MB};
+use crate::searcher::budget::BudgetModel;
+use crate::searcher::context::ContextScope;
+use crate::searcher::format::ColorChoice;
+use crate::searcher::granularity::Granularity;
+use crate::searcher::freq::FreqBreakdown;
+use crate::stats::{LogPeriod, SearchLogFlags, SearchOutcome};
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
@@ -21,6 +54,10 @@ pub struct Cli {
     /// Search query (default when no subcommand is given)
     pub query: Option<String>,
 
+    /// Only search these files or directories (relative to the repo root)
+    #[arg(value_name = "PATH")]
+    pub paths: Vec<String>,
+
     /// Language filter (e.g. rust, python, go)
     #[arg(short = 't', long = "type", global = true)]
     pub file_type: Option<String>,
@@ -37,13 +74,13 @@ pub struct Cli {
     #[arg(short = 'i', long = "ignore-case")]
     pub ignore_case: bool,
 
-    /// Maximum number of results
-    #[arg(short = 'm', long = "max-count", default_value_t = 10)]
-    pub max_count: usize,
+    /// Maximum number of results (default: 10, or [search] max_count in .ns/config.toml)
+    #[arg(short = 'm', long = "max-count")]
+    pub max_count: Option<usize>,
 
-    /// Context lines around matches
-    #[arg(short = 'C', long = "context", default_value_t = 1)]
-    pub context: usize,
+    /// Context lines around matches (default: 1, or [search] context in .ns/config.toml)
+    #[arg(short = 'C', long = "context")]
+    pub context: Option<usize>,
 
     /// Output results as JSON
     #[arg(long = "json")]
@@ -68,6 +105,142 @@ pub struct Cli {
     /// Use AST-guided span extraction (replaces grep-and-expand context)
     #[arg(long = "spans")]
     pub spans: bool,
+
+    /// Keep files with identical content as separate results
+    #[arg(long = "no-dedupe")]
+    pub no_dedupe: bool,
+
+    /// Terminate --files paths with NUL instead of newline (for xargs -0); end each text result with NUL
+    #[arg(short = '0', long = "null")]
+    pub null: bool,
+
+    /// JSON output without ranking diagnostics or empty fields (implies --json)
+    #[arg(long = "json-compact")]
+    pub json_compact: bool,
+
+    /// Treat the query as a regular expression and match it line by line
+    #[arg(long, conflicts_with_all = ["sym", "fuzzy"])]
+    pub regex: bool,
+
+    /// Skip the existence check that drops results for files deleted since the last index
+    #[arg(long = "no-verify")]
+    pub no_verify: bool,
+
+    /// When results point at deleted files, start `ns index --incremental` in the background
+    #[arg(long = "refresh-stale", conflicts_with = "no_verify")]
+    pub refresh_stale: bool,
+
+    /// Search a read-only index bundle (from `ns export --bundle`) instead of .ns/
+    #[arg(long = "index-file", value_name = "BUNDLE", conflicts_with_all = ["regex", "substring"])]
+    pub index_file: Option<PathBuf>,
+
+    /// For files changed since the last index, show context from the indexed git commit
+    #[arg(long = "context-from-git")]
+    pub context_from_git: bool,
+
+    /// Low-latency preset: at most 5 results, signature lines only, ~300-token budget, compact JSON
+    #[arg(long = "quick")]
+    pub quick: bool,
+
+    /// Maximal-context preset: 30 results, whole enclosing definitions, budget spread breadth-first
+    #[arg(long = "deep", conflicts_with = "quick")]
+    pub deep: bool,
+
+    /// Raise (or, with 0, lift) the 100-result hard cap on -m. Default: $NS_MAX_COUNT_HARD, no cap with -l
+    #[arg(long = "max-count-hard", value_name = "N")]
+    pub max_count_hard: Option<usize>,
+
+    /// Match QUERY as a literal, case-insensitive substring of lines (fast with `ns index --ngram`)
+    #[arg(long, conflicts_with_all = ["regex", "sym", "fuzzy"])]
+    pub substring: bool,
+
+    /// Also match inflected forms (validating ~ validate); needs `ns index --stem`
+    #[arg(long, conflicts_with_all = ["sym", "fuzzy", "regex", "substring"])]
+    pub stem: bool,
+
+    /// Return N matches spread across directories and languages instead of the top N
+    #[arg(long, value_name = "N")]
+    pub sample: Option<usize>,
+
+    /// Run queries that would match nearly every indexed file
+    #[arg(long)]
+    pub force: bool,
+
+    /// Require every query word to appear in a file (AND) instead of any (OR)
+    #[arg(long = "all-terms")]
+    pub all_terms: bool,
+
+    /// Rank test files like other files instead of downweighting them
+    #[arg(long)]
+    pub tests: bool,
+
+    /// Do not search symbols first for CamelCase/snake_case queries
+    #[arg(long = "no-route", conflicts_with = "sym")]
+    pub no_route: bool,
+
+    /// Add per-term, per-field score contributions to ranking_factors (implies --json)
+    #[arg(long, conflicts_with_all = ["json_compact", "regex", "substring"])]
+    pub explain: bool,
+
+    /// Scan files on disk instead of reading the index (works without .ns/; no ranking signals)
+    #[arg(long = "no-index", conflicts_with_all = ["index_file", "sym", "fuzzy", "stem", "explain"])]
+    pub no_index: bool,
+
+    /// Line printed between non-contiguous context groups instead of "..." (escapes: \0 \t \n \\ \xHH)
+    #[arg(long = "group-separator", value_name = "SEP", value_parser = parse_separator)]
+    pub group_separator: Option<String>,
+
+    /// Append SEP after each text result, e.g. \x1e, so tools can split results reliably
+    #[arg(long = "record-separator", value_name = "SEP", value_parser = parse_separator, conflicts_with = "null")]
+    pub record_separator: Option<String>,
+
+    /// Re-rank results by embedding similarity to the query (index built with --embed-model)
+    #[arg(long = "semantic", conflicts_with_all = ["no_index", "sample"])]
+    pub semantic: bool,
+
+    /// Show at most N results from any one directory, filling the page from other directories
+    #[arg(long = "max-per-dir", value_name = "N")]
+    pub max_per_dir: Option<usize>,
+
+    /// Context around each match: lines (±N window, the default) or function (the whole enclosing function or method, capped by --max-context-lines)
+    #[arg(long = "context-scope", value_name = "SCOPE", conflicts_with_all = ["spans", "regex"])]
+    pub context_scope: Option<ContextScope>,
+
+    /// Color text output: auto (when stdout is a terminal and NO_COLOR is unset), always, never
+    #[arg(long = "color", value_name = "WHEN")]
+    pub color: Option<ColorChoice>,
+
+    /// Tokenizer --budget counts in: cl100k (default), o200k, a model name such as gpt-4o, or chars (4 characters per token)
+    #[arg(long = "budget-model", value_name = "MODEL")]
+    pub budget_model: Option<BudgetModel>,
+
+    /// Show one line per result: path, score and matched symbols, without context
+    #[arg(long, conflicts_with_all = ["files_only", "json", "json_compact", "explain"])]
+    pub summary: bool,
+
+    /// What each result is: file (default) or symbol (a definition with its line range, ranked by matches in its name and body)
+    #[arg(long = "granularity", value_name = "UNIT", conflicts_with_all = ["files_only", "summary", "regex", "substring", "no_index"])]
+    pub granularity: Option<Granularity>,
+
+    /// Only files defining a symbol of this kind whose name contains a query word (type, function, module, constant, impl; comma-separated or repeated)
+    #[arg(long = "kind", value_name = "KIND", value_delimiter = ',', conflicts_with_all = ["regex", "substring", "no_index"])]
+    pub kind: Vec<SymbolKind>,
+
+    /// Only files larger than SIZE when indexed (e.g. 10KB, 1.5MB)
+    #[arg(long = "larger-than", value_name = "SIZE", value_parser = parse_byte_size, conflicts_with_all = ["regex", "substring", "no_index"])]
+    pub larger_than: Option<u64>,
+
+    /// Only files smaller than SIZE when indexed
+    #[arg(long = "smaller-than", value_name = "SIZE", value_parser = parse_byte_size, conflicts_with_all = ["regex", "substring", "no_index"])]
+    pub smaller_than: Option<u64>,
+
+    /// Only files modified within WHEN (e.g. 2h, 7d, 2w) or since a date (YYYY-MM-DD), as of the last index
+    #[arg(long = "modified-since", value_name = "WHEN", value_parser = parse_since, conflicts_with_all = ["regex", "substring", "no_index"])]
+    pub modified_since: Option<u64>,
+
+    /// Search the registered repo NAME (see ns repos list) instead of the current directory
+    #[arg(long, value_name = "NAME")]
+    pub repo: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -76,13 +249,56 @@ pub enum Command {
     Search(SearchSubArgs),
     /// Build or update the search index
     Index(IndexArgs),
+    /// Keep the index up to date by watching the repo for file changes
+    Watch(WatchArgs),
     /// Show index status
-    Status,
+    Status(StatusArgs),
+    /// Summarize the search log: searches over time, top and zero-result queries, tokens emitted
+    Stats(StatsArgs),
+    /// List logged searches, filtered by time, outcome or query text
+    Log(LogArgs),
+    /// List indexed files with their language and symbol count (filter with -t/-g)
+    Ls(LsArgs),
+    /// List symbol definitions whose name matches a pattern (e.g. 'Event*'), with kind, file and line
+    Symbols(SymbolsArgs),
+    /// Show where a symbol is defined: file, line range and definition line
+    Def(DefArgs),
+    /// List the lines referencing a symbol, marking files that import or define it
+    Refs(RefsArgs),
+    /// Print the definitions in a file (kind, name, line), nested, without needing an index
+    Outline(OutlineArgs),
     /// Manage git hooks
     Hooks {
         #[command(subcommand)]
         action: HooksAction,
     },
+    /// Export the symbol index for other tools
+    Export(ExportArgs),
+    /// Show how many indexed files contain each term
+    Freq(FreqArgs),
+    /// Suggest indexed symbols and path segments starting with a prefix
+    Complete(CompleteArgs),
+    /// Check the index against the working tree
+    Doctor(DoctorArgs),
+    /// List every file matching a query, unranked (for migrations and sweeps)
+    Audit(AuditArgs),
+    /// Measure how well ranking finds symbol definitions in this repo
+    Tune(TuneArgs),
+    /// Time a full index, an incremental index and a set of queries on this repo
+    Bench(BenchArgs),
+    /// Search interactively, keeping the index open between queries (:help for commands)
+    Repl,
+    /// Browse search results in a terminal UI: query box, result list and file preview
+    Tui(TuiArgs),
+    /// Run the golden queries in .ns/golden.toml and fail if any regressed
+    Check(CheckArgs),
+    /// Explain why a file does or doesn't rank for a query
+    Why(WhyArgs),
+    /// Manage the global registry of indexed repos
+    Repos {
+        #[command(subcommand)]
+        action: ReposAction,
+    },
 }
 
 #[derive(Parser)]
@@ -90,6 +306,10 @@ pub struct SearchSubArgs {
     /// Search query
     pub query: String,
 
+    /// Only search these files or directories (relative to the repo root)
+    #[arg(value_name = "PATH")]
+    pub paths: Vec<String>,
+
     /// Show matching file paths only
     #[arg(short = 'l', long = "files")]
     pub files_only: bool,
@@ -98,13 +318,13 @@ pub struct SearchSubArgs {
     #[arg(short = 'i', long = "ignore-case")]
     pub ignore_case: bool,
 
-    /// Maximum number of results
-    #[arg(short = 'm', long = "max-count", default_value_t = 10)]
-    pub max_count: usize,
+    /// Maximum number of results (default: 10, or [search] max_count in .ns/config.toml)
+    #[arg(short = 'm', long = "max-count")]
+    pub max_count: Option<usize>,
 
-    /// Context lines around matches
-    #[arg(short = 'C', long = "context", default_value_t = 1)]
-    pub context: usize,
+    /// Context lines around matches (default: 1, or [search] context in .ns/config.toml)
+    #[arg(short = 'C', long = "context")]
+    pub context: Option<usize>,
 
     /// Output results as JSON
     #[arg(long = "json")]
@@ -129,6 +349,142 @@ pub struct SearchSubArgs {
     /// Use AST-guided span extraction (replaces grep-and-expand context)
     #[arg(long = "spans")]
     pub spans: bool,
+
+    /// Keep files with identical content as separate results
+    #[arg(long = "no-dedupe")]
+    pub no_dedupe: bool,
+
+    /// Terminate --files paths with NUL instead of newline (for xargs -0); end each text result with NUL
+    #[arg(short = '0', long = "null")]
+    pub null: bool,
+
+    /// JSON output without ranking diagnostics or empty fields (implies --json)
+    #[arg(long = "json-compact")]
+    pub json_compact: bool,
+
+    /// Treat the query as a regular expression and match it line by line
+    #[arg(long, conflicts_with_all = ["sym", "fuzzy"])]
+    pub regex: bool,
+
+    /// Skip the existence check that drops results for files deleted since the last index
+    #[arg(long = "no-verify")]
+    pub no_verify: bool,
+
+    /// When results point at deleted files, start `ns index --incremental` in the background
+    #[arg(long = "refresh-stale", conflicts_with = "no_verify")]
+    pub refresh_stale: bool,
+
+    /// Search a read-only index bundle (from `ns export --bundle`) instead of .ns/
+    #[arg(long = "index-file", value_name = "BUNDLE", conflicts_with_all = ["regex", "substring"])]
+    pub index_file: Option<PathBuf>,
+
+    /// For files changed since the last index, show context from the indexed git commit
+    #[arg(long = "context-from-git")]
+    pub context_from_git: bool,
+
+    /// Low-latency preset: at most 5 results, signature lines only, ~300-token budget, compact JSON
+    #[arg(long = "quick")]
+    pub quick: bool,
+
+    /// Maximal-context preset: 30 results, whole enclosing definitions, budget spread breadth-first
+    #[arg(long = "deep", conflicts_with = "quick")]
+    pub deep: bool,
+
+    /// Raise (or, with 0, lift) the 100-result hard cap on -m. Default: $NS_MAX_COUNT_HARD, no cap with -l
+    #[arg(long = "max-count-hard", value_name = "N")]
+    pub max_count_hard: Option<usize>,
+
+    /// Match QUERY as a literal, case-insensitive substring of lines (fast with `ns index --ngram`)
+    #[arg(long, conflicts_with_all = ["regex", "sym", "fuzzy"])]
+    pub substring: bool,
+
+    /// Also match inflected forms (validating ~ validate); needs `ns index --stem`
+    #[arg(long, conflicts_with_all = ["sym", "fuzzy", "regex", "substring"])]
+    pub stem: bool,
+
+    /// Return N matches spread across directories and languages instead of the top N
+    #[arg(long, value_name = "N")]
+    pub sample: Option<usize>,
+
+    /// Run queries that would match nearly every indexed file
+    #[arg(long)]
+    pub force: bool,
+
+    /// Require every query word to appear in a file (AND) instead of any (OR)
+    #[arg(long = "all-terms")]
+    pub all_terms: bool,
+
+    /// Rank test files like other files instead of downweighting them
+    #[arg(long)]
+    pub tests: bool,
+
+    /// Do not search symbols first for CamelCase/snake_case queries
+    #[arg(long = "no-route", conflicts_with = "sym")]
+    pub no_route: bool,
+
+    /// Add per-term, per-field score contributions to ranking_factors (implies --json)
+    #[arg(long, conflicts_with_all = ["json_compact", "regex", "substring"])]
+    pub explain: bool,
+
+    /// Scan files on disk instead of reading the index (works without .ns/; no ranking signals)
+    #[arg(long = "no-index", conflicts_with_all = ["index_file", "sym", "fuzzy", "stem", "explain"])]
+    pub no_index: bool,
+
+    /// Line printed between non-contiguous context groups instead of "..." (escapes: \0 \t \n \\ \xHH)
+    #[arg(long = "group-separator", value_name = "SEP", value_parser = parse_separator)]
+    pub group_separator: Option<String>,
+
+    /// Append SEP after each text result, e.g. \x1e, so tools can split results reliably
+    #[arg(long = "record-separator", value_name = "SEP", value_parser = parse_separator, conflicts_with = "null")]
+    pub record_separator: Option<String>,
+
+    /// Re-rank results by embedding similarity to the query (index built with --embed-model)
+    #[arg(long = "semantic", conflicts_with_all = ["no_index", "sample"])]
+    pub semantic: bool,
+
+    /// Show at most N results from any one directory, filling the page from other directories
+    #[arg(long = "max-per-dir", value_name = "N")]
+    pub max_per_dir: Option<usize>,
+
+    /// Context around each match: lines (±N window, the default) or function (the whole enclosing function or method, capped by --max-context-lines)
+    #[arg(long = "context-scope", value_name = "SCOPE", conflicts_with_all = ["spans", "regex"])]
+    pub context_scope: Option<ContextScope>,
+
+    /// Color text output: auto (when stdout is a terminal and NO_COLOR is unset), always, never
+    #[arg(long = "color", value_name = "WHEN")]
+    pub color: Option<ColorChoice>,
+
+    /// Tokenizer --budget counts in: cl100k (default), o200k, a model name such as gpt-4o, or chars (4 characters per token)
+    #[arg(long = "budget-model", value_name = "MODEL")]
+    pub budget_model: Option<BudgetModel>,
+
+    /// Show one line per result: path, score and matched symbols, without context
+    #[arg(long, conflicts_with_all = ["files_only", "json", "json_compact", "explain"])]
+    pub summary: bool,
+
+    /// What each result is: file (default) or symbol (a definition with its line range, ranked by matches in its name and body)
+    #[arg(long = "granularity", value_name = "UNIT", conflicts_with_all = ["files_only", "summary", "regex", "substring", "no_index"])]
+    pub granularity: Option<Granularity>,
+
+    /// Only files defining a symbol of this kind whose name contains a query word (type, function, module, constant, impl; comma-separated or repeated)
+    #[arg(long = "kind", value_name = "KIND", value_delimiter = ',', conflicts_with_all = ["regex", "substring", "no_index"])]
+    pub kind: Vec<SymbolKind>,
+
+    /// Only files larger than SIZE when indexed (e.g. 10KB, 1.5MB)
+    #[arg(long = "larger-than", value_name = "SIZE", value_parser = parse_byte_size, conflicts_with_all = ["regex", "substring", "no_index"])]
+    pub larger_than: Option<u64>,
+
+    /// Only files smaller than SIZE when indexed
+    #[arg(long = "smaller-than", value_name = "SIZE", value_parser = parse_byte_size, conflicts_with_all = ["regex", "substring", "no_index"])]
+    pub smaller_than: Option<u64>,
+
+    /// Only files modified within WHEN (e.g. 2h, 7d, 2w) or since a date (YYYY-MM-DD), as of the last index
+    #[arg(long = "modified-since", value_name = "WHEN", value_parser = parse_since, conflicts_with_all = ["regex", "substring", "no_index"])]
+    pub modified_since: Option<u64>,
+
+    /// Search the registered repo NAME (see ns repos list) instead of the current directory
+    #[arg(long, value_name = "NAME")]
+    pub repo: Option<String>,
 }
 
 #[derive(Parser)]
@@ -137,15 +493,413 @@ pub struct IndexArgs {
     #[arg(long)]
     pub incremental: bool,
 
+    /// With --incremental, print the changed paths and per-file timings as JSON
+    #[arg(long, requires = "incremental", conflicts_with = "every")]
+    pub json: bool,
+
+    /// Show a progress bar, then list skipped files by reason (too large, binary, non-UTF-8, ignored, excluded)
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+
+    /// Repository root directory; repeat to index several directories into one index in their common parent
+    #[arg(long = "root")]
+    pub root: Vec<PathBuf>,
+
+    /// Maximum file size in bytes (default: 1 MB)
+    #[arg(long = "max-file-size", default_value_t = 1_048_576)]
+    pub max_file_size: u64,
+
+    /// Index the first KB kilobytes of files over --max-file-size instead of skipping them, remembered in meta.json (0 skips them again)
+    #[arg(long = "truncate-large", value_name = "KB")]
+    pub truncate_large: Option<u64>,
+
+    /// Index size budget (e.g. 200MB); when exceeded, leaves out stored content, then raw symbols, then prunes vendored and large files
+    #[arg(long = "max-index-size", value_parser = parse_byte_size)]
+    pub max_index_size: Option<u64>,
+
+    /// Index size budget in megabytes, like --max-index-size
+    #[arg(long = "index-budget", value_name = "MB", conflicts_with = "max_index_size")]
+    pub index_budget: Option<u64>,
+
+    /// Stored field compression: none, lz4 or zstd (applies to full rebuilds; default: zstd with an index budget, else lz4)
+    #[arg(long)]
+    pub compression: Option<StoredCompression>,
+
+    /// Also index character trigrams for fast --substring searches (larger index; full rebuilds)
+    #[arg(long)]
+    pub ngram: bool,
+
+    /// Also index stemmed content so --stem matches inflected words (full rebuilds)
+    #[arg(long)]
+    pub stem: bool,
+
+    /// Split Chinese/Japanese/Korean text into bigrams so CJK queries match (full rebuilds)
+    #[arg(long)]
+    pub cjk: bool,
+
+    /// Shell command to run after every index write, remembered in meta.json ('' clears it)
+    #[arg(long = "on-update", value_name = "CMD")]
+    pub on_update: Option<String>,
+
+    /// Boost files by their commit count over this many days, remembered in meta.json (0 turns it off)
+    #[arg(long = "churn-days", value_name = "DAYS")]
+    pub churn_days: Option<u32>,
+
+    /// Embed files with the ONNX model in DIR for --semantic, remembered in meta.json ('' turns it off)
+    #[arg(long = "embed-model", value_name = "DIR")]
+    pub embed_model: Option<String>,
+
+    /// Leave out paths matching this glob (e.g. '*.min.js', 'vendor/'); repeatable, remembered in meta.json ('' clears them)
+    #[arg(long = "exclude", value_name = "GLOB", value_parser = parse_exclude)]
+    pub exclude: Vec<String>,
+
+    /// Only walk this many directory levels below the root (1 = root files only), remembered in meta.json (0 removes the limit)
+    #[arg(long = "max-depth", value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Skip symbol extraction for a quick content-only index; search then has no symbol boost (full rebuilds; incremental runs keep the mode)
+    #[arg(long = "no-symbols")]
+    pub no_symbols: bool,
+
+    /// Follow symlinked files and directories, skipping loops (full rebuilds; incremental runs keep the setting)
+    #[arg(long = "follow-symlinks")]
+    pub follow_symlinks: bool,
+
+    /// Index files longer than N lines as overlapping N-line chunks, so matches deep in huge files rank (full rebuilds; remembered, 0 turns it off)
+    #[arg(long = "chunk-lines", value_name = "N")]
+    pub chunk_lines: Option<usize>,
+
+    /// Index writer heap in MB, shared by its threads (at least 15 per thread)
+    #[arg(long = "memory-mb", value_name = "MB", default_value_t = DEFAULT_WRITER_MEMORY_MB, value_parser = parse_memory_mb)]
+    pub memory_mb: u64,
+
+    /// Indexing threads (default: one per core, up to 8, as memory allows)
+    #[arg(long, value_name = "N", value_parser = parse_threads)]
+    pub threads: Option<usize>,
+
+    /// Keep running, re-indexing on this interval (e.g. 15m, 30s, 1h)
+    #[arg(long, value_parser = parse_interval)]
+    pub every: Option<Duration>,
+
+    /// With --every, run in the background and log to .ns/index-daemon.log
+    #[arg(long, requires = "every")]
+    pub daemonize: bool,
+
+    /// List the paths currently in the index (and deleted-but-unmerged documents) instead of indexing
+    #[arg(long = "list-docs", conflicts_with_all = ["incremental", "every"])]
+    pub list_docs: bool,
+
+    /// Merge all index segments into one and drop deleted documents, instead of indexing
+    #[arg(long, conflicts_with_all = ["incremental", "every", "list_docs"])]
+    pub optimize: bool,
+
+    /// Check the index for missing or corrupted segment files, a file count that disagrees with meta.json and documents of deleted files, instead of indexing
+    #[arg(long, conflicts_with_all = ["incremental", "every", "list_docs", "optimize"])]
+    pub check: bool,
+
+    /// With --list-docs, only list paths starting with this prefix (e.g. src/)
+    #[arg(long = "path-prefix", requires = "list_docs")]
+    pub path_prefix: Option<String>,
+}
+
+#[derive(Parser)]
+pub struct WatchArgs {
     /// Repository root directory
     #[arg(long = "root")]
     pub root: Option<PathBuf>,
 
+    /// Wait until files have been quiet this many milliseconds before updating
+    #[arg(long, value_name = "MS", default_value_t = 500)]
+    pub debounce: u64,
+
     /// Maximum file size in bytes (default: 1 MB)
     #[arg(long = "max-file-size", default_value_t = 1_048_576)]
     pub max_file_size: u64,
 }
 
+#[derive(Parser)]
+pub struct StatusArgs {
+    /// Show per-field and per-component on-disk sizes
+    #[arg(long)]
+    pub detail: bool,
+}
+
+#[derive(Parser)]
+pub struct StatsArgs {
+    /// Only searches since an age (7d, 12h, 2w) or a UTC date (2024-05-01)
+    #[arg(long, value_parser = parse_since)]
+    pub since: Option<u64>,
+
+    /// Group searches over time by day or month
+    #[arg(long, default_value = "day")]
+    pub by: LogPeriod,
+
+    /// Number of top and zero-result queries to list
+    #[arg(long, default_value_t = 10)]
+    pub top: usize,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct LogArgs {
+    /// Only searches since an age (7d, 12h, 2w) or a UTC date (2024-05-01)
+    #[arg(long, value_parser = parse_since)]
+    pub since: Option<u64>,
+
+    /// Only searches with this outcome: success, no_results, error
+    #[arg(long)]
+    pub outcome: Option<SearchOutcome>,
+
+    /// Only searches whose query contains this text (ignoring case)
+    #[arg(long, value_name = "TEXT")]
+    pub query_contains: Option<String>,
+
+    /// Show the most recent N matching searches (0 = all)
+    #[arg(short = 'n', long, default_value_t = 50)]
+    pub limit: usize,
+
+    /// Print the matching log lines as logged (JSON Lines)
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct LsArgs {
+    /// Print paths only, one per line
+    #[arg(long = "paths-only", conflicts_with = "json")]
+    pub paths_only: bool,
+
+    /// Output one JSON object per file (JSON Lines)
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct SymbolsArgs {
+    /// Name pattern, case-insensitive: * and ? are wildcards; a plain word matches anywhere in the name
+    pub pattern: String,
+
+    /// Only symbols of these kinds (type, function, module, constant, impl, reexport, other)
+    #[arg(long = "kind", value_name = "KIND", value_delimiter = ',')]
+    pub kind: Vec<SymbolKind>,
+
+    /// Maximum number of symbols to list (0 = all)
+    #[arg(short = 'n', long = "limit", default_value_t = 100)]
+    pub limit: usize,
+
+    /// Output one JSON object per symbol (JSON Lines)
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct DefArgs {
+    /// Symbol name, matched exactly but ignoring case; qualify with Type::name or Type.name
+    pub symbol: String,
+
+    /// Also show other spellings and impls, constants and re-exports when declarations exist
+    #[arg(long)]
+    pub all: bool,
+
+    /// Output one JSON object per definition (JSON Lines)
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct TuiArgs {
+    /// Query to start with
+    pub query: Option<String>,
+}
+
+#[derive(Parser)]
+pub struct OutlineArgs {
+    /// File to outline
+    pub file: PathBuf,
+
+    /// Output one JSON object per definition (JSON Lines)
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct RefsArgs {
+    /// Symbol name, matched as a whole word; Type::name looks for name
+    pub symbol: String,
+
+    /// Leave out the files that define the symbol
+    #[arg(long = "exclude-def")]
+    pub exclude_def: bool,
+
+    /// Print matching files only
+    #[arg(short = 'l', long = "files")]
+    pub files_only: bool,
+
+    /// Output one JSON object per file (JSON Lines)
+    #[arg(long, conflicts_with = "files_only")]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+#[command(group(clap::ArgGroup::new("format").required(true).args(["scip", "ctags", "bundle"])))]
+pub struct ExportArgs {
+    /// Export as a SCIP index (protobuf)
+    #[arg(long)]
+    pub scip: bool,
+
+    /// Export as a ctags file for editor jump-to-definition
+    #[arg(long)]
+    pub ctags: bool,
+
+    /// Export the search index as a single-file read-only bundle (for --index-file)
+    #[arg(long)]
+    pub bundle: bool,
+
+    /// Output file (default: index.scip, tags or index.nsb)
+    #[arg(short = 'o', long = "output")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct FreqArgs {
+    /// Terms to look up
+    #[arg(required = true)]
+    pub terms: Vec<String>,
+
+    /// Count symbol-name terms instead of content terms
+    #[arg(long)]
+    pub sym: bool,
+
+    /// Break counts down by language or top-level directory: lang, dir
+    #[arg(long)]
+    pub by: Option<FreqBreakdown>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct WhyArgs {
+    /// File to explain, relative to the repo root
+    pub file: String,
+
+    /// Search query, as passed to `ns`
+    #[arg(allow_hyphen_values = true)]
+    pub query: String,
+
+    /// Also match Porter-stemmed content, as `ns --stem` does
+    #[arg(long)]
+    pub stem: bool,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct CompleteArgs {
+    /// Prefix to complete (case-insensitive)
+    pub prefix: String,
+
+    /// Maximum number of suggestions
+    #[arg(short = 'n', long = "limit", default_value_t = 20)]
+    pub limit: usize,
+
+    /// Output as JSON, with kind and file count per suggestion
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct AuditArgs {
+    /// Query; every term must match (or a regex with --regex)
+    pub query: String,
+
+    /// Treat the query as a regular expression
+    #[arg(long)]
+    pub regex: bool,
+
+    /// Also list matching lines as path:line:text
+    #[arg(long)]
+    pub lines: bool,
+
+    /// Output one JSON object per file (JSON Lines)
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct DoctorArgs {
+    /// Re-read a sample of indexed files and compare them to the index
+    #[arg(long = "verify-content", required = true)]
+    pub verify_content: bool,
+
+    /// Number of files to check (0 = all)
+    #[arg(long, default_value_t = 200)]
+    pub sample: usize,
+
+    /// Exit with status 1 when more than this percentage of checked files is stale
+    #[arg(long = "max-stale", default_value_t = 0.0)]
+    pub max_stale: f64,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct TuneArgs {
+    /// Search for a sample of indexed symbols and report how often a defining file ranks first
+    #[arg(long, required = true)]
+    pub calibrate: bool,
+
+    /// Number of symbols to query (0 = all)
+    #[arg(long, default_value_t = 200)]
+    pub sample: usize,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct BenchArgs {
+    /// Only time queries, against the existing index (no rebuild)
+    #[arg(long)]
+    pub skip_index: bool,
+
+    /// Runs per query
+    #[arg(long, default_value_t = 5)]
+    pub runs: usize,
+
+    /// Indexed symbols to query when no --query or .ns/golden.toml is given
+    #[arg(long, default_value_t = 10)]
+    pub sample: usize,
+
+    /// Query to time instead of golden or sampled ones (repeatable)
+    #[arg(short = 'q', long = "query", value_name = "QUERY")]
+    pub queries: Vec<String>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct CheckArgs {
+    /// Golden-query file (default: .ns/golden.toml)
+    #[arg(long)]
+    pub file: Option<PathBuf>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
 #[derive(Subcommand)]
 pub enum HooksAction {
     /// Install git hooks for automatic re-indexing
@@ -154,21 +908,73 @@ pub enum HooksAction {
     Remove,
 }
 
+#[derive(Subcommand)]
+pub enum ReposAction {
+    /// List registered repos with their last index time
+    List,
+    /// Incrementally re-index every registered repo
+    IndexAll,
+    /// Remove repos whose directory or index no longer exists
+    Gc,
+}
+
+/// `--max-count` when neither the command line nor `.ns/config.toml` sets it.
+pub const DEFAULT_MAX_COUNT: usize = 10;
+
+/// `--context` when neither the command line nor `.ns/config.toml` sets it.
+pub const DEFAULT_CONTEXT: usize = 1;
+
 /// Extracts search args from the top-level Cli struct.
+#[derive(Clone)]
 pub struct SearchArgs {
     pub query: String,
     pub file_type: Option<String>,
     pub file_glob: Option<String>,
     pub files_only: bool,
     pub ignore_case: bool,
-    pub max_count: usize,
-    pub context: usize,
+    pub max_count: Option<usize>,
+    pub context: Option<usize>,
     pub json: bool,
     pub sym: bool,
     pub fuzzy: bool,
     pub max_context_lines: usize,
     pub budget: Option<usize>,
     pub spans: bool,
+    pub no_dedupe: bool,
+    pub null: bool,
+    pub json_compact: bool,
+    pub regex: bool,
+    pub no_verify: bool,
+    pub refresh_stale: bool,
+    pub index_file: Option<PathBuf>,
+    pub context_from_git: bool,
+    pub quick: bool,
+    pub deep: bool,
+    pub max_count_hard: Option<usize>,
+    pub substring: bool,
+    pub stem: bool,
+    pub sample: Option<usize>,
+    pub force: bool,
+    pub all_terms: bool,
+    pub paths: Vec<String>,
+    pub tests: bool,
+    pub no_route: bool,
+    pub explain: bool,
+    pub no_index: bool,
+    pub group_separator: Option<String>,
+    pub record_separator: Option<String>,
+    pub semantic: bool,
+    pub max_per_dir: Option<usize>,
+    pub context_scope: Option<ContextScope>,
+    pub color: Option<ColorChoice>,
+    pub budget_model: Option<BudgetModel>,
+    pub summary: bool,
+    pub granularity: Option<Granularity>,
+    pub kind: Vec<SymbolKind>,
+    pub larger_than: Option<u64>,
+    pub smaller_than: Option<u64>,
+    pub modified_since: Option<u64>,
+    pub repo: Option<String>,
 }
 
 impl SearchArgs {
@@ -187,6 +993,41 @@ impl SearchArgs {
             max_context_lines: cli.max_context_lines,
             budget: cli.budget,
             spans: cli.spans,
+            no_dedupe: cli.no_dedupe,
+            null: cli.null,
+            json_compact: cli.json_compact,
+            regex: cli.regex,
+            no_verify: cli.no_verify,
+            refresh_stale: cli.refresh_stale,
+            index_file: cli.index_file.clone(),
+            context_from_git: cli.context_from_git,
+            quick: cli.quick,
+            deep: cli.deep,
+            max_count_hard: cli.max_count_hard,
+            substring: cli.substring,
+            stem: cli.stem,
+            sample: cli.sample,
+            force: cli.force,
+            all_terms: cli.all_terms,
+            paths: cli.paths.clone(),
+            tests: cli.tests,
+            no_route: cli.no_route,
+            explain: cli.explain,
+            no_index: cli.no_index,
+            group_separator: cli.group_separator.clone(),
+            record_separator: cli.record_separator.clone(),
+            semantic: cli.semantic,
+            max_per_dir: cli.max_per_dir,
+            context_scope: cli.context_scope,
+            color: cli.color,
+            budget_model: cli.budget_model,
+            summary: cli.summary,
+            granularity: cli.granularity,
+            kind: cli.kind.clone(),
+            larger_than: cli.larger_than,
+            smaller_than: cli.smaller_than,
+            modified_since: cli.modified_since,
+            repo: cli.repo.clone(),
         }
     }
 
@@ -205,9 +1046,54 @@ impl SearchArgs {
             max_context_lines: sub.max_context_lines,
             budget: sub.budget,
             spans: sub.spans,
+            no_dedupe: sub.no_dedupe,
+            null: sub.null,
+            json_compact: sub.json_compact,
+            regex: sub.regex,
+            no_verify: sub.no_verify,
+            refresh_stale: sub.refresh_stale,
+            index_file: sub.index_file.clone(),
+            context_from_git: sub.context_from_git,
+            quick: sub.quick,
+            deep: sub.deep,
+            max_count_hard: sub.max_count_hard,
+            substring: sub.substring,
+            stem: sub.stem,
+            sample: sub.sample,
+            force: sub.force,
+            all_terms: sub.all_terms,
+            paths: sub.paths.clone(),
+            tests: sub.tests,
+            no_route: sub.no_route,
+            explain: sub.explain,
+            no_index: sub.no_index,
+            group_separator: sub.group_separator.clone(),
+            record_separator: sub.record_separator.clone(),
+            semantic: sub.semantic,
+            max_per_dir: sub.max_per_dir,
+            context_scope: sub.context_scope,
+            color: sub.color,
+            budget_model: sub.budget_model,
+            summary: sub.summary,
+            granularity: sub.granularity,
+            kind: sub.kind.clone(),
+            larger_than: sub.larger_than,
+            smaller_than: sub.smaller_than,
+            modified_since: sub.modified_since,
+            repo: sub.repo.clone(),
         }
     }
 
+    /// These args with the `[search]` defaults of `.ns/config.toml` filled
+    /// in where no flag was given.
+    pub fn with_defaults(mut self, defaults: &SearchConfig) -> Self {
+        self.max_count = self.max_count.or(defaults.max_count);
+        self.context = self.context.or(defaults.context);
+        self.budget = self.budget.or(defaults.budget);
+        self.file_type = self.file_type.or_else(|| defaults.file_type.clone());
+        self
+    }
+
     pub fn to_log_flags(&self) -> SearchLogFlags {
         SearchLogFlags {
             file_type: self.file_type.clone(),
@@ -217,11 +1103,39 @@ impl SearchArgs {
             json: self.json,
             sym: self.sym,
             fuzzy: self.fuzzy,
-            max_count: self.max_count,
-            context: self.context,
+            max_count: self.max_count.unwrap_or(DEFAULT_MAX_COUNT),
+            context: self.context.unwrap_or(DEFAULT_CONTEXT),
             max_context_lines: self.max_context_lines,
             budget: self.budget,
             spans: self.spans,
+            no_dedupe: self.no_dedupe,
+            json_compact: self.json_compact,
+            regex: self.regex,
+            no_verify: self.no_verify,
+            context_from_git: self.context_from_git,
+            quick: self.quick,
+            deep: self.deep,
+            max_count_hard: self.max_count_hard,
+            substring: self.substring,
+            stem: self.stem,
+            sample: self.sample,
+            force: self.force,
+            all_terms: self.all_terms,
+            paths: self.paths.clone(),
+            tests: self.tests,
+            no_route: self.no_route,
+            explain: self.explain,
+            no_index: self.no_index,
+            semantic: self.semantic,
+            max_per_dir: self.max_per_dir,
+            context_scope: self.context_scope.map(|s| s.to_string()),
+            budget_model: self.budget_model.map(|m| m.to_string()),
+            summary: self.summary,
+            granularity: self.granularity.map(|g| g.to_string()),
+            kind: self.kind.iter().map(|k| k.as_str().to_string()).collect(),
+            larger_than: self.larger_than,
+            smaller_than: self.smaller_than,
+            modified_since: self.modified_since,
         }
     }
 }
@@ -0,0 +1,56 @@
+use std::io::{BufWriter, Write};
+
+use crate::cmd::{Cli, OutlineArgs};
+use crate::indexer::language::{detect_language, LANGUAGES};
+use crate::indexer::outline::{outline, OutlineEntry};
+
+pub fn run(args: &OutlineArgs, cli: &Cli) {
+    // `-t` names the language of a file whose extension doesn't.
+    let lang = match cli.file_type.as_deref() {
+        Some(lang) => LANGUAGES.iter().copied().find(|l| *l == lang),
+        None => detect_language(&args.file),
+    };
+    let Some(lang) = lang else {
+        eprintln!(
+            "error: no symbol extractor for {}. Supported languages: {}; pass one with -t.",
+            args.file.display(),
+            LANGUAGES.join(", ")
+        );
+        std::process::exit(1);
+    };
+    let source = match std::fs::read(&args.file) {
+        Ok(s) => s,
+        Err(err) => {
+            eprintln!("error: cannot read {}: {}", args.file.display(), err);
+            std::process::exit(1);
+        }
+    };
+    let entries = outline(lang, &source).unwrap_or_default();
+    if entries.is_empty() {
+        eprintln!("No definitions in {}.", args.file.display());
+        std::process::exit(1);
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let _ = print_outline(&mut out, &entries, args.json).and_then(|_| out.flush());
+}
+
+fn print_outline(out: &mut impl Write, entries: &[OutlineEntry], json: bool) -> std::io::Result<()> {
+    for entry in entries {
+        if json {
+            let line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+            writeln!(out, "{}", line)?;
+            continue;
+        }
+        writeln!(
+            out,
+            "{:>6}  {}{} {}",
+            entry.line,
+            "  ".repeat(entry.depth),
+            entry.kind.as_str(),
+            entry.name
+        )?;
+    }
+    Ok(())
+}
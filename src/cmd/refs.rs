@@ -0,0 +1,82 @@
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::cmd::{Cli, RefsArgs};
+use crate::error::NsError;
+use crate::searcher::refs::{find_refs, FileRefs, RefsOptions};
+
+pub fn run(args: &RefsArgs, cli: &Cli) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let opts = RefsOptions {
+        file_type: cli.file_type.clone(),
+        file_glob: cli.file_glob.clone(),
+        exclude_definitions: args.exclude_def,
+    };
+    let (definitions, files) = match find_refs(&root, &args.symbol, &opts) {
+        Ok(r) => r,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(NsError::Glob(e)) => {
+            eprintln!("error: invalid glob: {}", e);
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: reference lookup failed: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let _ = print_refs(&mut out, &files, args).and_then(|_| out.flush());
+
+    let lines: usize = files.iter().map(|f| f.lines.len()).sum();
+    eprintln!("{} files, {} lines", files.len(), lines);
+    if definitions.is_empty() {
+        eprintln!("note: '{}' has no indexed definition; these are plain word matches.", args.symbol);
+    }
+    if files.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+fn print_refs(out: &mut impl Write, files: &[FileRefs], args: &RefsArgs) -> std::io::Result<()> {
+    for file in files {
+        if args.json {
+            let line = serde_json::to_string(file).map_err(std::io::Error::other)?;
+            writeln!(out, "{}", line)?;
+            continue;
+        }
+        if args.files_only {
+            writeln!(out, "{}", file.path)?;
+            continue;
+        }
+        let labels: Vec<&str> = [(file.imports, "imports"), (file.defines, "defines")]
+            .iter()
+            .filter(|(on, _)| *on)
+            .map(|(_, label)| *label)
+            .collect();
+        if labels.is_empty() {
+            writeln!(out, "{}", file.path)?;
+        } else {
+            writeln!(out, "{}  ({})", file.path, labels.join(", "))?;
+        }
+        for line in &file.lines {
+            writeln!(out, "{:>6}: {}", line.line, line.text.trim_end())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::cmd::search::search_in;
+use crate::cmd::{Cli, SearchArgs};
+use crate::searcher::session::keep_index_open;
+
+const HELP: &str = "\
+Type a query to search. Commands:
+  :json  :l  :sym  :spans     toggle --json, -l, --sym, --spans
+  :t [LANG]  :g [GLOB]        set or clear the language or path filter
+  :m [N]  :C [N]              set or clear the result count or context lines
+  :set FLAGS...               add any search flags (e.g. :set --budget 500)
+  :reset                      clear all flags
+  :flags                      show the flags in use
+  :q                          quit (or Ctrl-D)";
+
+/// Search flags passed with every query, as command-line arguments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Flags(Vec<String>);
+
+impl Flags {
+    /// Adds `flag` if absent, else removes it.
+    fn toggle(&mut self, flag: &str) {
+        match self.0.iter().position(|f| f == flag) {
+            Some(at) => {
+                self.0.remove(at);
+            }
+            None => self.0.push(flag.to_string()),
+        }
+    }
+
+    /// Replaces the value of `flag`, or removes it when `value` is `None`.
+    fn set(&mut self, flag: &str, value: Option<&str>) {
+        if let Some(at) = self.0.iter().position(|f| f == flag) {
+            self.0.drain(at..(at + 2).min(self.0.len()));
+        }
+        if let Some(value) = value {
+            self.0.extend([flag.to_string(), value.to_string()]);
+        }
+    }
+
+    /// Arguments for searching `query`, as `ns` would be run.
+    fn argv(&self, query: &str) -> Vec<String> {
+        let mut argv = self.0.clone();
+        argv.extend(["--".to_string(), query.to_string()]);
+        argv
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Reply {
+    Message(String),
+    Quit,
+}
+
+pub fn run(cli: &Cli) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let _kept = keep_index_open();
+    let mut flags = Flags::default();
+    flags.set("-t", cli.file_type.as_deref());
+    flags.set("-g", cli.file_glob.as_deref());
+    let interactive = std::io::stdin().is_terminal();
+    if interactive {
+        eprintln!("ns repl -- type a query, :help for commands, :q to quit");
+    }
+
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        if interactive {
+            eprint!("ns> ");
+            let _ = std::io::stderr().flush();
+        }
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(command) = line.strip_prefix(':') {
+            match apply_command(&mut flags, command) {
+                Ok(Reply::Message(message)) => eprintln!("{}", message),
+                Ok(Reply::Quit) => break,
+                Err(err) => eprintln!("error: {}", err),
+            }
+            continue;
+        }
+        let argv = flags.argv(line);
+        match search_args(&argv) {
+            Ok(args) => {
+                search_in(&root, &args, &argv);
+                if args.json || args.json_compact || args.explain {
+                    // JSON is printed without a trailing newline; keep one
+                    // document per line.
+                    println!();
+                }
+                let _ = std::io::stdout().flush();
+            }
+            Err(err) => eprintln!("error: {}", err),
+        }
+    }
+}
+
+/// Runs the `:` command `command` against `flags`. A change that leaves
+/// flags `ns` would reject is undone and reported as an error.
+fn apply_command(flags: &mut Flags, command: &str) -> Result<Reply, String> {
+    let mut words = command.split_whitespace();
+    let name = words.next().unwrap_or("");
+    let value = words.next();
+    let before = flags.clone();
+    match name {
+        "q" | "quit" | "exit" => return Ok(Reply::Quit),
+        "help" | "h" | "?" => return Ok(Reply::Message(HELP.to_string())),
+        "flags" => {}
+        "json" => flags.toggle("--json"),
+        "l" | "files" => flags.toggle("-l"),
+        "sym" => flags.toggle("--sym"),
+        "spans" => flags.toggle("--spans"),
+        "t" | "type" => flags.set("-t", value),
+        "g" | "glob" => flags.set("-g", value),
+        "m" | "max-count" => flags.set("-m", value),
+        "C" | "context" => flags.set("-C", value),
+        "set" => flags.0.extend(value.into_iter().chain(words).map(str::to_string)),
+        "reset" => flags.0.clear(),
+        other => return Err(format!("unknown command ':{}' (:help lists them)", other)),
+    }
+    if let Err(err) = search_args(&flags.argv("query")) {
+        *flags = before;
+        return Err(err);
+    }
+    Ok(Reply::Message(format!("flags: {}", flags.0.join(" "))))
+}
+
+/// Parses `argv` as `ns` would a search.
+fn search_args(argv: &[String]) -> Result<SearchArgs, String> {
+    let cli = Cli::try_parse_from(std::iter::once("ns").chain(argv.iter().map(String::as_str)))
+        .map_err(|err| {
+            // clap's message, without its "error: " prefix and usage hints.
+            let message = err.to_string();
+            let first = message.lines().next().unwrap_or("");
+            first.strip_prefix("error: ").unwrap_or(first).to_string()
+        })?;
+    if cli.repo.is_some() {
+        return Err("--repo is not supported in ns repl; start it in the repo instead".to_string());
+    }
+    match (&cli.command, &cli.query) {
+        (None, Some(query)) => Ok(SearchArgs::from_cli(&cli, query.clone())),
+        _ => Err("flags must come before the query".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_toggle_and_set_flags() {
+        let mut flags = Flags::default();
+        apply_command(&mut flags, "t rust").unwrap();
+        apply_command(&mut flags, "json").unwrap();
+        apply_command(&mut flags, "t go").unwrap();
+        assert_eq!(flags.0, vec!["--json", "-t", "go"]);
+        apply_command(&mut flags, "json").unwrap();
+        apply_command(&mut flags, "t").unwrap();
+        assert!(flags.0.is_empty());
+
+        apply_command(&mut flags, "set --budget 500 --spans").unwrap();
+        let args = search_args(&flags.argv("retry backoff")).unwrap();
+        assert_eq!(args.query, "retry backoff");
+        assert_eq!(args.budget, Some(500));
+        assert!(args.spans);
+
+        assert!(apply_command(&mut flags, "m lots").is_err());
+        assert!(apply_command(&mut flags, "set --no-such-flag").is_err());
+        assert_eq!(flags.0, vec!["--budget", "500", "--spans"], "bad changes are undone");
+        assert!(apply_command(&mut flags, "bogus").is_err());
+        assert_eq!(apply_command(&mut flags, "q"), Ok(Reply::Quit));
+    }
+}
@@ -0,0 +1,143 @@
+use std::path::Path;
+
+use crate::cmd::ReposAction;
+use crate::error::NsError;
+use crate::indexer;
+use crate::indexer::writer::{read_meta, SCHEMA_VERSION};
+use crate::indexer::IndexOptions;
+use crate::registry::{self, default_registry_path, update_registry, Registry};
+
+pub fn run(action: &ReposAction) {
+    let path = match default_registry_path() {
+        Some(p) => p,
+        None => {
+            eprintln!("error: cannot locate config directory (set HOME or XDG_CONFIG_HOME).");
+            std::process::exit(1);
+        }
+    };
+
+    match action {
+        ReposAction::List => list(&path),
+        ReposAction::IndexAll => index_all(&path),
+        ReposAction::Gc => gc(&path),
+    }
+}
+
+fn load_or_exit(path: &Path) -> Registry {
+    match Registry::load(path) {
+        Ok(r) => r,
+        Err(err) => {
+            eprintln!("error: cannot read {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn list(path: &Path) {
+    let registry = load_or_exit(path);
+    if registry.repos.is_empty() {
+        eprintln!("No repos registered. Run 'ns index' in a repo to add it.");
+        return;
+    }
+
+    let name_width = registry.repos.iter().map(|r| r.name.len()).max().unwrap_or(4).max(4);
+    println!(
+        "{:<name_width$}  {:<20}  {:>7}  path",
+        "name", "last indexed", "files"
+    );
+    for repo in &registry.repos {
+        let missing = if repo.path.join(".ns").join("meta.json").is_file() {
+            ""
+        } else {
+            "  (missing)"
+        };
+        println!(
+            "{:<name_width$}  {:<20}  {:>7}  {}{}",
+            repo.name,
+            repo.last_indexed.as_deref().unwrap_or("-"),
+            repo.file_count,
+            repo.path.display(),
+            missing
+        );
+    }
+}
+
+fn index_all(path: &Path) {
+    let registry = load_or_exit(path);
+    if registry.repos.is_empty() {
+        eprintln!("No repos registered. Run 'ns index' in a repo to add it.");
+        return;
+    }
+
+    let opts = IndexOptions::default();
+    let mut failures = 0;
+    for repo in &registry.repos {
+        if !repo.path.is_dir() {
+            eprintln!("{}: skipped, directory not found", repo.name);
+            failures += 1;
+            continue;
+        }
+        match index_repo(&repo.path, &opts) {
+            Ok(summary) => {
+                eprintln!("{}: {}", repo.name, summary);
+                if let Ok(meta) = read_meta(&repo.path) {
+                    registry::record_index(&repo.path, &meta);
+                }
+            }
+            Err(err) => {
+                eprintln!("{}: error: {}", repo.name, err);
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        eprintln!("{} of {} repos failed", failures, registry.repos.len());
+        std::process::exit(1);
+    }
+}
+
+/// Updates one repo's index: incremental when a current index exists, full otherwise.
+fn index_repo(root: &Path, opts: &IndexOptions) -> Result<String, NsError> {
+    let needs_full = match read_meta(root) {
+        Ok(meta) => meta.schema_version != SCHEMA_VERSION,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => true,
+        Err(err) => return Err(err),
+    };
+
+    if needs_full {
+        return Ok(match indexer::run_full_index(root, opts)? {
+            Some(stats) => format!(
+                "rebuilt, {} files in {}ms",
+                stats.file_count, stats.elapsed_ms
+            ),
+            None => "no indexable files".to_string(),
+        });
+    }
+
+    let stats = indexer::run_incremental_index(root, opts)?;
+    Ok(if stats.added == 0 && stats.modified == 0 && stats.deleted == 0 {
+        "up to date".to_string()
+    } else {
+        format!(
+            "{} added, {} modified, {} deleted in {}ms",
+            stats.added, stats.modified, stats.deleted, stats.elapsed_ms
+        )
+    })
+}
+
+fn gc(path: &Path) {
+    match update_registry(path, |registry| registry.gc()) {
+        Ok(removed) if removed.is_empty() => eprintln!("Registry is clean."),
+        Ok(removed) => {
+            for repo in &removed {
+                eprintln!("removed {} ({})", repo.name, repo.path.display());
+            }
+            eprintln!("Removed {} stale repo(s).", removed.len());
+        }
+        Err(err) => {
+            eprintln!("error: cannot update {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,27 +1,61 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
-use crate::cmd::SearchArgs;
+use crate::cmd::{SearchArgs, DEFAULT_CONTEXT, DEFAULT_MAX_COUNT};
+use crate::config::{load_config, SearchConfig};
 use crate::error::NsError;
 use crate::indexer::writer::utc_timestamp_iso8601;
+use crate::registry::{default_registry_path, Registry};
 use crate::searcher;
-use crate::searcher::format::format_summary;
-use crate::searcher::query::SearchOptions;
+use crate::searcher::format::{format_summary, DEFAULT_GROUP_SEPARATOR};
+use crate::searcher::query::{
+    default_vendor_dirs, SearchOptions, DEFAULT_GENERATED_WEIGHT, DEFAULT_TEST_WEIGHT,
+    DEFAULT_VENDOR_WEIGHT,
+};
+use crate::searcher::rerank::RERANK_POOL;
 use crate::searcher::OutputMode;
 use crate::stats;
 
 pub fn run(args: &SearchArgs, argv: &[String]) {
-    let root = match PathBuf::from(".").canonicalize() {
-        Ok(p) => p,
+    let root = match &args.repo {
+        Some(name) => registered_root(name),
+        None => match PathBuf::from(".").canonicalize() {
+            Ok(p) => p,
+            Err(err) => {
+                eprintln!("error: cannot resolve current directory: {}", err);
+                std::process::exit(1);
+            }
+        },
+    };
+    if !search_in(&root, args, argv) {
+        std::process::exit(1);
+    }
+}
+
+/// Runs one search of the repo at `root` and prints it, as `ns <query>`
+/// does, logging it under `argv`. False when nothing was found or the
+/// search failed (exit status 1).
+pub(crate) fn search_in(root: &Path, args: &SearchArgs, argv: &[String]) -> bool {
+    let config = match load_config(root) {
+        Ok(c) => c,
+        Err(err @ NsError::InvalidConfig(_)) => {
+            eprintln!("error: {}", err);
+            return false;
+        }
         Err(err) => {
-            eprintln!("error: cannot resolve current directory: {}", err);
-            std::process::exit(1);
+            eprintln!("error: cannot load .ns/config.toml: {}", err);
+            return false;
         }
     };
 
-    let is_json = args.json;
+    let args = &args.clone().with_defaults(&config.search);
+
+    let is_json = args.json || args.json_compact || args.explain;
     let (output_mode, mode_str) = if args.files_only {
         (OutputMode::FilesOnly, "files")
-    } else if args.json {
+    } else if args.summary {
+        (OutputMode::Summary, "summary")
+    } else if is_json {
         (OutputMode::Json, "json")
     } else {
         (OutputMode::Text, "text")
@@ -39,22 +73,105 @@ pub fn run(args: &SearchArgs, argv: &[String]) {
         other => other,
     };
 
-    let opts = SearchOptions {
-        max_results: args.max_count,
-        context_window: args.context,
+    let max_results_ceiling = match result_ceiling(args, &config.search) {
+        Ok(ceiling) => ceiling,
+        Err(msg) => {
+            eprintln!("error: {}", msg);
+            return false;
+        }
+    };
+
+    let mut opts = SearchOptions {
+        max_results: args.max_count.unwrap_or(DEFAULT_MAX_COUNT),
+        context_window: args.context.unwrap_or(DEFAULT_CONTEXT),
         file_type: args.file_type.clone(),
         file_glob: args.file_glob.clone(),
         sym_only: args.sym,
         fuzzy: args.fuzzy,
         max_context_lines,
         budget,
+        budget_model: args.budget_model.unwrap_or_default(),
         spans: args.spans,
+        context_scope: args.context_scope.unwrap_or_default(),
+        granularity: args.granularity.unwrap_or_default(),
+        kinds: args.kind.clone(),
+        larger_than: args.larger_than,
+        smaller_than: args.smaller_than,
+        modified_since: args.modified_since,
+        dedupe: !args.no_dedupe,
+        null_separated: args.null,
+        semantic: args.semantic,
+        group_separator: args
+            .group_separator
+            .clone()
+            .unwrap_or_else(|| DEFAULT_GROUP_SEPARATOR.to_string()),
+        record_separator: if args.null {
+            Some("\0".to_string())
+        } else {
+            args.record_separator.clone()
+        },
+        color: args.color.unwrap_or_default().enabled(),
+        json_compact: args.json_compact,
+        regex: args.regex,
+        substring: args.substring,
+        stem: args.stem,
+        // A bundle is usually searched away from its source tree, where
+        // every indexed file would look deleted.
+        verify: !args.no_verify && args.index_file.is_none(),
+        index_file: args.index_file.clone(),
+        context_from_git: args.context_from_git,
+        signatures_only: false,
+        breadth_first: false,
+        rerank_pool: RERANK_POOL,
+        max_results_ceiling,
+        sample: args.sample,
+        force: args.force,
+        all_terms: args.all_terms,
+        paths: args.paths.clone(),
+        lang_boost: Vec::new(),
+        test_weight: DEFAULT_TEST_WEIGHT,
+        generated_weight: DEFAULT_GENERATED_WEIGHT,
+        vendor_weight: DEFAULT_VENDOR_WEIGHT,
+        vendor_dirs: default_vendor_dirs(),
+        auto_route: !args.no_route,
+        explain: args.explain,
+        no_index: args.no_index,
+        // 0 means no cap, like --budget 0
+        max_per_dir: args.max_per_dir.filter(|&n| n > 0),
+    };
+    config.apply(&mut opts);
+    if args.tests {
+        opts.test_weight = 1.0;
+    }
+    let opts = if args.quick {
+        opts.quick()
+    } else if args.deep {
+        opts.deep()
+    } else {
+        opts
+    };
+
+    // A scan of a repo without an index leaves no `.ns/` behind.
+    let log_searches = !args.no_index || root.join(".ns").is_dir();
+    let record_log = |entry: stats::SearchLogEntry| {
+        if log_searches {
+            stats::record_search_log(root, entry);
+        }
     };
 
-    match searcher::search(&root, &args.query, output_mode, &opts) {
+    match searcher::search(root, &args.query, output_mode, &opts) {
         Ok(search_output) => {
             let output = &search_output.formatted;
             let stats = &search_output.stats;
+            // Text output already ends with the warnings.
+            if output_mode != OutputMode::Text {
+                for warning in &search_output.warnings {
+                    eprintln!("warning: {}", warning);
+                }
+            }
+            if stats.stale_results > 0 && args.refresh_stale {
+                refresh_stale(root);
+            }
             if stats.total_results == 0 {
                 // JSON mode: print the body to stdout (structured data for consumers)
                 if is_json {
@@ -62,53 +179,44 @@ pub fn run(args: &SearchArgs, argv: &[String]) {
                 }
                 // Summary to stderr — consistent with exit 1 (rg convention)
                 eprintln!("{}", format_summary(stats));
-                stats::record_search_log(
-                    &root,
-                    stats::SearchLogEntry {
-                        ts: utc_timestamp_iso8601(),
-                        v: env!("CARGO_PKG_VERSION"),
-                        query: args.query.clone(),
-                        tokens: output.len() / 4,
-                        lines: output.lines().count(),
-                        files: stats.total_results,
-                        mode: mode_str.to_string(),
-                        budget,
-                        outcome: stats::SearchOutcome::NoResults,
-                        zero_results: true,
-                        flags: args.to_log_flags(),
-                        argv: argv.to_vec(),
-                        error: None,
-                    },
-                );
-                std::process::exit(1);
+                record_log(stats::SearchLogEntry {
+                    ts: utc_timestamp_iso8601(),
+                    v: env!("CARGO_PKG_VERSION"),
+                    query: args.query.clone(),
+                    tokens: output.len() / 4,
+                    lines: output.lines().count(),
+                    files: stats.total_results,
+                    mode: mode_str.to_string(),
+                    budget,
+                    outcome: stats::SearchOutcome::NoResults,
+                    zero_results: true,
+                    flags: args.to_log_flags(),
+                    argv: argv.to_vec(),
+                    error: None,
+                });
+                false
             } else {
                 print!("{}", output);
-                if search_output.budget_exhausted {
-                    eprintln!(
-                        "warning: token budget exceeded, {} results omitted",
-                        search_output.results_omitted
-                    );
-                }
                 eprintln!("{}", format_summary(stats));
-                stats::record_search(&root, output.len());
-                stats::record_search_log(
-                    &root,
-                    stats::SearchLogEntry {
-                        ts: utc_timestamp_iso8601(),
-                        v: env!("CARGO_PKG_VERSION"),
-                        query: args.query.clone(),
-                        tokens: output.len() / 4,
-                        lines: output.lines().count(),
-                        files: stats.total_results,
-                        mode: mode_str.to_string(),
-                        budget,
-                        outcome: stats::SearchOutcome::Success,
-                        zero_results: false,
-                        flags: args.to_log_flags(),
-                        argv: argv.to_vec(),
-                        error: None,
-                    },
-                );
+                if log_searches {
+                    stats::record_search(root, output.len());
+                }
+                record_log(stats::SearchLogEntry {
+                    ts: utc_timestamp_iso8601(),
+                    v: env!("CARGO_PKG_VERSION"),
+                    query: args.query.clone(),
+                    tokens: output.len() / 4,
+                    lines: output.lines().count(),
+                    files: stats.total_results,
+                    mode: mode_str.to_string(),
+                    budget,
+                    outcome: stats::SearchOutcome::Success,
+                    zero_results: false,
+                    flags: args.to_log_flags(),
+                    argv: argv.to_vec(),
+                    error: None,
+                });
+                true
             }
         }
         Err(err) => {
@@ -129,9 +237,27 @@ pub fn run(args: &SearchArgs, argv: &[String]) {
                 NsError::QueryParse(e) => {
                     ("invalid_query", format!("error: invalid query: {}", e))
                 }
+                NsError::Regex(e) => {
+                    ("invalid_regex", format!("error: invalid regex: {}", e))
+                }
+                NsError::MissingIndexOption(flag) => {
+                    (
+                        "missing_index_option",
+                        format!("error: {} needs an index built with 'ns index {}'.", flag, flag),
+                    )
+                }
                 NsError::Glob(e) => {
                     ("invalid_glob", format!("error: invalid glob pattern: {}", e))
                 }
+                NsError::QueryTooBroad { term, matches, total } => {
+                    (
+                        "query_too_broad",
+                        format!(
+                            "error: '{}' matches {} of {} indexed files. Refine the query (add terms, -t, -g, quotes) or pass --force.",
+                            term, matches, total
+                        ),
+                    )
+                }
                 NsError::Json(_) => {
                     (
                         "corrupt_meta",
@@ -149,28 +275,242 @@ pub fn run(args: &SearchArgs, argv: &[String]) {
                 }
             };
             eprintln!("{}", stderr_message);
-            stats::record_search_log(
-                &root,
-                stats::SearchLogEntry {
-                    ts: utc_timestamp_iso8601(),
-                    v: env!("CARGO_PKG_VERSION"),
-                    query: args.query.clone(),
-                    tokens: 0,
-                    lines: 0,
-                    files: 0,
-                    mode: mode_str.to_string(),
-                    budget,
-                    outcome: stats::SearchOutcome::Error,
-                    zero_results: false,
-                    flags: args.to_log_flags(),
-                    argv: argv.to_vec(),
-                    error: Some(stats::SearchLogError {
-                        code: error_code,
-                        message: stderr_message,
-                    }),
-                },
+            if error_code == "no_index" {
+                eprintln!("hint: pass --no-index to scan files without an index.");
+            }
+            record_log(stats::SearchLogEntry {
+                ts: utc_timestamp_iso8601(),
+                v: env!("CARGO_PKG_VERSION"),
+                query: args.query.clone(),
+                tokens: 0,
+                lines: 0,
+                files: 0,
+                mode: mode_str.to_string(),
+                budget,
+                outcome: stats::SearchOutcome::Error,
+                zero_results: false,
+                flags: args.to_log_flags(),
+                argv: argv.to_vec(),
+                error: Some(stats::SearchLogError {
+                    code: error_code,
+                    message: stderr_message,
+                }),
+            });
+            false
+        }
+    }
+}
+
+/// Root of the repo registered as `name` (`--repo`); exits when there is
+/// none.
+fn registered_root(name: &str) -> PathBuf {
+    let registry = match default_registry_path().map(|path| Registry::load(&path)) {
+        Some(Ok(registry)) => registry,
+        Some(Err(err)) => {
+            eprintln!("error: cannot read the repo registry: {}", err);
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("error: cannot locate config directory (set HOME or XDG_CONFIG_HOME).");
+            std::process::exit(1);
+        }
+    };
+    match registry.find(name) {
+        Some(repo) => repo.path.clone(),
+        None => {
+            eprintln!(
+                "error: no registered repo named '{}'. Run 'ns repos list' to see them.",
+                name
             );
             std::process::exit(1);
         }
     }
 }
+
+/// Starts a detached incremental index after results were dropped for
+/// deleted files (`--refresh-stale`), so the next search is accurate.
+fn refresh_stale(root: &Path) {
+    let spawned = std::env::current_exe().and_then(|exe| {
+        Command::new(exe)
+            .arg("index")
+            .arg("--incremental")
+            // From the root rather than with `--root`, which would replace
+            // the directories the index remembers.
+            .current_dir(root)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+    });
+    match spawned {
+        Ok(_) => eprintln!("updating index in the background"),
+        Err(err) => eprintln!("warning: could not start background index: {}", err),
+    }
+}
+
+/// `--max-count-hard`, else `$NS_MAX_COUNT_HARD`, else `max_count_hard` in
+/// `[search]`. Files-only and summary output read no files for context, so
+/// `-l` and `--summary` lift the cap unless one is set explicitly. A
+/// `$NS_MAX_COUNT_HARD` that isn't a number is an error.
+fn result_ceiling(args: &SearchArgs, defaults: &SearchConfig) -> Result<Option<usize>, String> {
+    if args.max_count_hard.is_some() {
+        return Ok(args.max_count_hard);
+    }
+    let from_env = match std::env::var("NS_MAX_COUNT_HARD") {
+        Ok(v) if !v.trim().is_empty() => Some(v.trim().parse::<usize>().map_err(|_| {
+            format!("invalid NS_MAX_COUNT_HARD '{}': expected a number (0 lifts the cap)", v)
+        })?),
+        _ => None,
+    };
+    Ok(from_env.or(defaults.max_count_hard).or(if args.files_only || args.summary {
+        Some(0)
+    } else {
+        None
+    }))
+}
+
+/// Parses a `--modified-since` value into a Unix timestamp: an age such as
+/// `90m`, `12h`, `7d` or `2w` back from now, or a UTC date `YYYY-MM-DD`.
+pub(crate) fn parse_since(s: &str) -> Result<u64, String> {
+    let trimmed = s.trim();
+    if let Some(secs) = parse_date(trimmed) {
+        return Ok(secs);
+    }
+    if trimmed.len() == 10 && trimmed.as_bytes()[4] == b'-' && trimmed.as_bytes()[7] == b'-' {
+        return Err(format!("invalid date '{}': expected a real day, YYYY-MM-DD, from 1970 on", s));
+    }
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid time '{}': expected e.g. 7d or 2024-05-01", s))?;
+    let unit_secs: u64 = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 7 * 86400,
+        other => return Err(format!("invalid time unit '{}': use s, m, h, d or w", other)),
+    };
+    let secs = value
+        .checked_mul(unit_secs)
+        .ok_or_else(|| format!("invalid time '{}': time too large", s))?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Ok(now.saturating_sub(secs))
+}
+
+/// Seconds since the epoch at the start of the UTC day `YYYY-MM-DD`.
+fn parse_date(s: &str) -> Option<u64> {
+    let mut parts = s.splitn(3, '-');
+    let (y, m, d) = (parts.next()?, parts.next()?, parts.next()?);
+    if y.len() != 4 || m.len() != 2 || d.len() != 2 {
+        return None;
+    }
+    let (y, m, d): (i64, i64, i64) = (y.parse().ok()?, m.parse().ok()?, d.parse().ok()?);
+    let leap = y % 4 == 0 && (y % 100 != 0 || y % 400 == 0);
+    let month_days = match m {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if !(1..=12).contains(&m) || !(1..=month_days).contains(&d) || y < 1970 {
+        return None;
+    }
+    // Days from civil, the inverse of `utc_timestamp_iso8601`'s breakdown.
+    // Ref: http://howardhinnant.github.io/date_algorithms.html
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let mp = if m > 2 { m - 3 } else { m + 9 };
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    Some(days as u64 * 86400)
+}
+
+/// Parses a `--group-separator`/`--record-separator` value, expanding the
+/// escapes `\0`, `\t`, `\n`, `\\` and `\xHH` so control characters can
+/// be passed without shell quoting tricks.
+pub(crate) fn parse_separator(s: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('0') => out.push('\0'),
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('\\') => out.push('\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&hex, 16).ok().filter(|b| {
+                    b.is_ascii() && hex.len() == 2 && hex.chars().all(|c| c.is_ascii_hexdigit())
+                });
+                match byte {
+                    Some(b) => out.push(b as char),
+                    None => {
+                        return Err(format!(
+                            "invalid escape '\\x{}': expected \\x00 to \\x7f",
+                            hex
+                        ))
+                    }
+                }
+            }
+            Some(other) => {
+                return Err(format!(
+                    "unknown escape '\\{}': use \\0, \\t, \\n, \\\\ or \\xHH",
+                    other
+                ))
+            }
+            None => return Err("separator ends with a lone '\\'".to_string()),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_separator_expands_escapes() {
+        assert_eq!(parse_separator("--"), Ok("--".to_string()));
+        assert_eq!(parse_separator("\\x1e"), Ok("\x1e".to_string()));
+        assert_eq!(parse_separator("\\0"), Ok("\0".to_string()));
+        assert_eq!(parse_separator("a\\tb\\n\\\\"), Ok("a\tb\n\\".to_string()));
+        assert!(parse_separator("\\xZZ").is_err());
+        assert!(parse_separator("\\x1").is_err());
+        assert!(parse_separator("\\x+1").is_err());
+        assert!(parse_separator("\\q").is_err());
+        assert!(parse_separator("end\\").is_err());
+    }
+
+    #[test]
+    fn parse_since_takes_ages_and_dates() {
+        assert_eq!(parse_since("1970-01-02"), Ok(86400));
+        assert_eq!(parse_since("2024-03-01"), Ok(1_709_251_200));
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let week = parse_since("1w").unwrap();
+        assert!(now - week >= 7 * 86400 && now - week < 7 * 86400 + 5);
+        assert!(parse_since("7y").is_err());
+        assert!(parse_since("2024-13-01").is_err());
+        assert!(parse_since("2024-02-31").is_err());
+        assert!(parse_since("2023-02-29").is_err());
+        assert!(parse_since("2024-02-29").is_ok());
+        assert!(parse_since("99999999999999999w").unwrap_err().contains("time too large"));
+        assert!(parse_since("99999999999999999999s").is_err());
+        assert!(parse_since("soon").is_err());
+    }
+}
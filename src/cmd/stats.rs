@@ -0,0 +1,96 @@
+use std::path::PathBuf;
+
+use crate::cmd::StatsArgs;
+use crate::indexer::writer::iso8601_at;
+use crate::stats::{format_token_count, summarize_search_log, QueryCount, SearchLogSummary};
+
+/// Width of the longest bar in the searches-over-time chart.
+const BAR_WIDTH: u64 = 40;
+
+pub fn run(args: &StatsArgs) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let since = args.since.map(iso8601_at);
+    let Some(summary) = summarize_search_log(&root, since.as_deref(), args.by, args.top) else {
+        eprintln!("No searches logged yet (.ns/search_log.jsonl is missing).");
+        std::process::exit(1);
+    };
+
+    if args.json {
+        match serde_json::to_string(&summary) {
+            Ok(json) => println!("{}", json),
+            Err(err) => {
+                eprintln!("error: cannot serialize stats: {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if summary.searches == 0 {
+        eprintln!("No searches logged in this period.");
+        std::process::exit(1);
+    }
+    print_summary(&summary);
+}
+
+fn print_summary(summary: &SearchLogSummary) {
+    let day = |ts: &Option<String>| ts.as_deref().and_then(|t| t.get(..10)).unwrap_or("?").to_string();
+    println!(
+        "{} searches from {} to {}",
+        summary.searches,
+        day(&summary.first_at),
+        day(&summary.last_at)
+    );
+    println!("  successful     : {}", summary.successes);
+    println!(
+        "  no results     : {} ({:.1}%)",
+        summary.no_results,
+        summary.no_results as f64 * 100.0 / summary.searches as f64
+    );
+    println!("  errors         : {}", summary.errors);
+    println!(
+        "  est. tokens out: {} ({} per search)",
+        format_token_count(summary.tokens),
+        format_token_count(summary.tokens / summary.searches)
+    );
+
+    println!();
+    println!("searches over time");
+    let most = summary.over_time.iter().map(|p| p.searches).max().unwrap_or(1);
+    for period in &summary.over_time {
+        let bar = (period.searches * BAR_WIDTH).div_ceil(most) as usize;
+        println!(
+            "  {:<10} {:>6}  {}",
+            period.period,
+            period.searches,
+            "#".repeat(bar)
+        );
+    }
+
+    print_queries("top queries", &summary.top_queries);
+    print_queries("zero-result queries", &summary.zero_result_queries);
+    if !summary.errors_by_code.is_empty() {
+        println!();
+        println!("errors");
+        for (code, count) in &summary.errors_by_code {
+            println!("  {:>6}  {}", count, code);
+        }
+    }
+}
+
+fn print_queries(title: &str, queries: &[QueryCount]) {
+    if queries.is_empty() {
+        return;
+    }
+    println!();
+    println!("{}", title);
+    for q in queries {
+        println!("  {:>6}  {}", q.count, q.query);
+    }
+}
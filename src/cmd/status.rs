@@ -1,10 +1,15 @@
 use std::path::PathBuf;
 
+use crate::cmd::StatusArgs;
 use crate::error::NsError;
-use crate::indexer::writer::{read_meta, SCHEMA_VERSION};
+use crate::indexer::footprint::{index_footprint, FieldFootprint, IndexFootprint};
+use crate::indexer::health::{read_health, HealthState, IndexingHealth};
+use crate::indexer::prune::StoredTrim;
+use crate::indexer::symbol_stats::{LanguageCounts, SymbolStats};
+use crate::indexer::writer::{open_index, read_meta, SCHEMA_VERSION};
 use crate::stats;
 
-pub fn run() {
+pub fn run(args: &StatusArgs) {
     let root = match PathBuf::from(".").canonicalize() {
         Ok(p) => p,
         Err(err) => {
@@ -16,6 +21,12 @@ pub fn run() {
     let meta = match read_meta(&root) {
         Ok(m) => m,
         Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            // The first full build writes meta.json only when it finishes.
+            if let Some(HealthState::InProgress(health)) = read_health(&root) {
+                println!("ns index status");
+                println!("  indexing       : {}", in_progress(&health));
+                return;
+            }
             eprintln!("error: no index found. Run 'ns index' to create one.");
             std::process::exit(1);
         }
@@ -44,6 +55,53 @@ pub fn run() {
     if let Some(commit) = &meta.git_commit {
         println!("  git commit     : {}", &commit[..commit.len().min(12)]);
     }
+    if meta.no_symbols {
+        println!("  symbols        : off (built with --no-symbols)");
+    }
+    if let Some(lines) = meta.chunk_lines {
+        println!("  chunking       : files over {} lines", lines);
+    }
+    if let Some(budget) = meta.max_index_size {
+        match meta.stored_trim {
+            StoredTrim::None => println!("  index budget   : {}", format_bytes(budget)),
+            trim => println!(
+                "  index budget   : {} ({} left out)",
+                format_bytes(budget),
+                trim.as_str()
+            ),
+        }
+    }
+    match read_health(&root) {
+        Some(HealthState::InProgress(health)) => {
+            println!("  indexing       : {}", in_progress(&health));
+        }
+        Some(HealthState::Crashed(health)) => {
+            println!(
+                "  indexing       : last background index crashed (PID {}, {} run started {}, {})",
+                health.pid,
+                health.mode,
+                health.started_at,
+                progress(&health)
+            );
+            eprintln!("warning: the index may be incomplete. Run 'ns index' to rebuild.");
+        }
+        None => {}
+    }
+    if let Some(stats) = meta.symbol_stats.as_ref().filter(|s| !s.by_language.is_empty()) {
+        print_languages(stats);
+    }
+
+    if args.detail {
+        let footprint = open_index(&root).and_then(|(index, _)| index_footprint(&index));
+        match footprint {
+            Ok(fp) => print_footprint(&fp),
+            Err(err) => {
+                eprintln!("error: failed to measure index footprint: {}", err);
+                std::process::exit(1);
+            }
+        }
+        print_symbol_stats(meta.symbol_stats.as_ref());
+    }
 
     let st = stats::read_stats(&root);
     if st.total_searches > 0 {
@@ -57,10 +115,144 @@ pub fn run() {
             "  est. tokens out: {}",
             stats::format_token_count(st.total_estimated_tokens)
         );
+        println!("  (see 'ns stats' for queries and searches over time)");
+    }
+}
+
+/// "indexing in progress (PID 1234, 40%)".
+fn in_progress(health: &IndexingHealth) -> String {
+    match health.percent() {
+        Some(pct) => format!("{} indexing in progress (PID {}, {}%)", health.mode, health.pid, pct),
+        None => format!("{} indexing in progress (PID {})", health.mode, health.pid),
     }
 }
 
-fn format_bytes(bytes: u64) -> String {
+fn progress(health: &IndexingHealth) -> String {
+    format!(
+        "{}/{} files, last heartbeat {}",
+        health.done, health.total, health.heartbeat_at
+    )
+}
+
+fn print_footprint(fp: &IndexFootprint) {
+    println!();
+    println!("on-disk footprint");
+    println!("  compression    : {}", fp.compression.as_str());
+    println!("  segments       : {}", fp.segments);
+    println!(
+        "  documents      : {} live, {} deleted",
+        fp.live_docs, fp.deleted_docs
+    );
+
+    println!();
+    println!(
+        "  {:<13}{:>11}{:>11}{:>11}{:>11}{:>14}",
+        "field", "termdict", "postings", "positions", "indexed", "stored (raw)"
+    );
+    for field in &fp.fields {
+        let stored = if field.stored {
+            format_bytes(field.stored_raw_bytes)
+        } else {
+            "-".to_string()
+        };
+        println!(
+            "  {:<13}{:>11}{:>11}{:>11}{:>11}{:>14}",
+            field.name,
+            format_bytes(field.termdict_bytes),
+            format_bytes(field.postings_bytes),
+            format_bytes(field.positions_bytes),
+            format_bytes(field.indexed_bytes()),
+            stored
+        );
+    }
+
+    let sum = |get: fn(&FieldFootprint) -> u64| -> u64 {
+        fp.fields.iter().map(get).sum()
+    };
+    println!();
+    println!("  term dicts     : {}", format_bytes(sum(|f| f.termdict_bytes)));
+    println!("  postings       : {}", format_bytes(sum(|f| f.postings_bytes)));
+    println!("  positions      : {}", format_bytes(sum(|f| f.positions_bytes)));
+    println!("  fieldnorms     : {}", format_bytes(sum(|f| f.fieldnorms_bytes)));
+    println!("  fast fields    : {}", format_bytes(sum(|f| f.fast_fields_bytes)));
+    match fp.compression_ratio() {
+        Some(ratio) => println!(
+            "  doc store      : {} ({} raw, {:.1}x)",
+            format_bytes(fp.store_bytes),
+            format_bytes(fp.store_raw_bytes),
+            ratio
+        ),
+        None => println!("  doc store      : {}", format_bytes(fp.store_bytes)),
+    }
+    if fp.deletes_bytes > 0 {
+        println!("  deletes        : {}", format_bytes(fp.deletes_bytes));
+    }
+    println!("  total          : {}", format_bytes(fp.total_bytes));
+}
+
+/// Files and symbols per language, most files first.
+fn print_languages(stats: &SymbolStats) {
+    println!();
+    println!("languages");
+    for line in language_lines(stats) {
+        println!("  {}", line);
+    }
+}
+
+fn language_lines(stats: &SymbolStats) -> Vec<String> {
+    let mut languages: Vec<(&String, &LanguageCounts)> = stats.by_language.iter().collect();
+    languages.sort_by(|a, b| b.1.files.cmp(&a.1.files).then_with(|| a.0.cmp(b.0)));
+    languages
+        .into_iter()
+        .map(|(lang, counts)| {
+            format!(
+                "{:<15}: {} file{}, {} symbol{}",
+                lang,
+                group_digits(counts.files),
+                if counts.files == 1 { "" } else { "s" },
+                group_digits(counts.symbols),
+                if counts.symbols == 1 { "" } else { "s" }
+            )
+        })
+        .collect()
+}
+
+/// `n` with commas between groups of three digits: `9,301`.
+fn group_digits(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+fn print_symbol_stats(stats: Option<&SymbolStats>) {
+    println!();
+    println!("symbols");
+    let Some(stats) = stats else {
+        println!("  not collected for this index. Run 'ns index' to rebuild.");
+        return;
+    };
+    println!("  definitions    : {}", stats.total);
+    println!("  distinct names : {}", stats.distinct);
+    println!("  files with any : {}", stats.files_with_symbols);
+    for (kind, count) in &stats.by_kind {
+        println!("  {:<15}: {}", kind, count);
+    }
+    if !stats.duplicates.is_empty() {
+        println!();
+        println!("  most duplicated definitions");
+        for dup in &stats.duplicates {
+            println!("  {:>6} files  {}", dup.files, dup.name);
+        }
+    }
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String {
     if bytes < 1024 {
         format!("{} B", bytes)
     } else if bytes < 1024 * 1024 {
@@ -69,3 +261,27 @@ fn format_bytes(bytes: u64) -> String {
         format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_list_most_files_first_with_grouped_counts() {
+        let mut stats = SymbolStats::default();
+        let counts = |files, symbols| LanguageCounts { files, symbols };
+        stats.by_language.insert("go".to_string(), counts(3, 1));
+        stats.by_language.insert("rust".to_string(), counts(412, 9301));
+        stats.by_language.insert("elixir".to_string(), counts(1, 0));
+        assert_eq!(
+            language_lines(&stats),
+            vec![
+                "rust           : 412 files, 9,301 symbols",
+                "go             : 3 files, 1 symbol",
+                "elixir         : 1 file, 0 symbols",
+            ]
+        );
+        assert_eq!(group_digits(1_234_567), "1,234,567");
+        assert_eq!(group_digits(999), "999");
+    }
+}
@@ -0,0 +1,94 @@
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::cmd::{Cli, SymbolsArgs};
+use crate::error::NsError;
+use crate::indexer::writer::read_meta;
+use crate::searcher::symbol_search::{find_symbols, FoundSymbol, SymbolSearchOptions};
+
+pub fn run(args: &SymbolsArgs, cli: &Cli) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let opts = SymbolSearchOptions {
+        file_type: cli.file_type.clone(),
+        file_glob: cli.file_glob.clone(),
+        kinds: args.kind.clone(),
+    };
+    let found = match find_symbols(&root, &args.pattern, &opts) {
+        Ok(f) => f,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(NsError::Glob(e)) => {
+            eprintln!("error: invalid glob: {}", e);
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: symbol search failed: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let shown = if args.limit == 0 {
+        &found[..]
+    } else {
+        &found[..found.len().min(args.limit)]
+    };
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let _ = print_symbols(&mut out, shown, args.json).and_then(|_| out.flush());
+
+    if shown.len() < found.len() {
+        eprintln!(
+            "{} symbols, {} shown (raise --limit to see more)",
+            found.len(),
+            shown.len()
+        );
+    } else {
+        eprintln!("{} symbols", found.len());
+    }
+    if let Ok(meta) = read_meta(&root) {
+        if meta.no_symbols || !meta.stored_trim.keeps_symbols() {
+            eprintln!("warning: the index has no stored symbols. Run 'ns index' to rebuild with them.");
+        }
+    }
+    if found.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+fn print_symbols(out: &mut impl Write, symbols: &[FoundSymbol], json: bool) -> std::io::Result<()> {
+    if json {
+        for symbol in symbols {
+            let line = serde_json::to_string(symbol).map_err(std::io::Error::other)?;
+            writeln!(out, "{}", line)?;
+        }
+        return Ok(());
+    }
+    let name_width = symbols.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    for symbol in symbols {
+        let location = match symbol.line {
+            Some(line) => format!("{}:{}", symbol.path, line),
+            None => symbol.path.clone(),
+        };
+        writeln!(
+            out,
+            "{:<8}  {:<name_width$}  {}",
+            symbol.kind.as_str(),
+            symbol.name,
+            location
+        )?;
+    }
+    Ok(())
+}
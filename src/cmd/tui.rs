@@ -0,0 +1,492 @@
+use std::io::IsTerminal;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::{execute, terminal};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::cmd::{Cli, TuiArgs};
+use crate::config::load_config;
+use crate::error::NsError;
+use crate::indexer::highlight::{highlight, Highlight};
+use crate::indexer::writer::open_index;
+use crate::searcher::query::{execute_search, SearchOptions, SearchResult};
+use crate::searcher::session::keep_index_open;
+
+/// Results listed per query.
+const MAX_RESULTS: usize = 200;
+/// Larger files are previewed without highlighting.
+const MAX_HIGHLIGHT_BYTES: usize = 512 * 1024;
+/// Lines shown above the first match when a preview opens.
+const PREVIEW_LEAD: usize = 3;
+
+const KEYS: &str =
+    " ↑/↓ select  PgUp/PgDn scroll  Enter open in $EDITOR  Ctrl-F fuzzy on/off  Ctrl-U clear  Esc quit";
+
+pub fn run(args: &TuiArgs, cli: &Cli) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    match open_index(&root) {
+        Ok(_) => {}
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: cannot open index: {}", err);
+            std::process::exit(1);
+        }
+    }
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        eprintln!("error: ns tui needs a terminal (use 'ns repl' to pipe queries in).");
+        std::process::exit(1);
+    }
+
+    let mut opts = SearchOptions {
+        max_results: MAX_RESULTS,
+        file_type: cli.file_type.clone(),
+        file_glob: cli.file_glob.clone(),
+        fuzzy: true,
+        ..Default::default()
+    };
+    match load_config(&root) {
+        Ok(config) => config.apply(&mut opts),
+        Err(err) => {
+            eprintln!("error: cannot load .ns/config.toml: {}", err);
+            std::process::exit(1);
+        }
+    }
+
+    let _kept = keep_index_open();
+    let mut app = App::new(root, opts, args.query.clone().unwrap_or_default());
+    let mut terminal = ratatui::init();
+    let outcome = app.run(&mut terminal);
+    ratatui::restore();
+    if let Err(err) = outcome {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+/// What a key asks the event loop to do.
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    None,
+    Open,
+    Quit,
+}
+
+/// The selected result's file, ready to draw.
+struct Preview {
+    path: String,
+    lines: Vec<Line<'static>>,
+    /// 1-based line the editor opens at.
+    first_match: usize,
+    /// Index of the top line shown.
+    scroll: usize,
+}
+
+struct App {
+    root: PathBuf,
+    opts: SearchOptions,
+    query: String,
+    /// The query changed since the last search.
+    stale: bool,
+    results: Vec<SearchResult>,
+    list: ListState,
+    preview: Option<Preview>,
+    /// Lines the preview pane showed last frame (its page size).
+    preview_height: usize,
+    error: Option<String>,
+}
+
+impl App {
+    fn new(root: PathBuf, opts: SearchOptions, query: String) -> App {
+        App {
+            root,
+            opts,
+            stale: !query.is_empty(),
+            query,
+            results: Vec::new(),
+            list: ListState::default(),
+            preview: None,
+            preview_height: 0,
+            error: None,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            if self.stale {
+                self.search();
+            }
+            terminal.draw(|frame| self.draw(frame))?;
+            // Take every key already typed before searching again.
+            let mut wait = true;
+            while wait || event::poll(Duration::ZERO)? {
+                wait = false;
+                let Event::Key(key) = event::read()? else {
+                    continue;
+                };
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match self.handle_key(key) {
+                    Action::None => {}
+                    Action::Open => {
+                        self.open_selected(terminal)?;
+                        break;
+                    }
+                    Action::Quit => return Ok(()),
+                }
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return Action::Quit,
+            KeyCode::Char('c') if ctrl => return Action::Quit,
+            KeyCode::Enter => return Action::Open,
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::Char('p') if ctrl => self.move_selection(-1),
+            KeyCode::Char('n') if ctrl => self.move_selection(1),
+            KeyCode::PageUp => self.scroll_preview(-(self.preview_height.max(1) as isize)),
+            KeyCode::PageDown => self.scroll_preview(self.preview_height.max(1) as isize),
+            KeyCode::Char('f') if ctrl => {
+                self.opts.fuzzy = !self.opts.fuzzy;
+                self.stale = true;
+            }
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.stale = true;
+            }
+            KeyCode::Char('w') if ctrl => {
+                let kept = self.query.trim_end().rfind(' ').map_or(0, |at| at + 1);
+                self.query.truncate(kept);
+                self.stale = true;
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.stale = true;
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.stale = true;
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn search(&mut self) {
+        self.stale = false;
+        self.error = None;
+        if self.query.trim().is_empty() {
+            self.results.clear();
+            self.select(None);
+            return;
+        }
+        match execute_search(&self.root, &self.query, &self.opts) {
+            Ok((results, _)) => {
+                self.results = results;
+                self.select((!self.results.is_empty()).then_some(0));
+            }
+            // Keep the last results while a query is being typed.
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    fn move_selection(&mut self, by: isize) {
+        if self.results.is_empty() {
+            return;
+        }
+        let last = self.results.len() - 1;
+        let at = self.list.selected().unwrap_or(0).saturating_add_signed(by).min(last);
+        self.select(Some(at));
+    }
+
+    fn scroll_preview(&mut self, by: isize) {
+        if let Some(preview) = &mut self.preview {
+            let last = preview.lines.len().saturating_sub(1);
+            preview.scroll = preview.scroll.saturating_add_signed(by).min(last);
+        }
+    }
+
+    /// Selects result `index` and loads its preview, unless it shows that
+    /// file already.
+    fn select(&mut self, index: Option<usize>) {
+        self.list.select(index);
+        let Some(result) = index.and_then(|i| self.results.get(i)) else {
+            self.preview = None;
+            return;
+        };
+        if self.preview.as_ref().is_some_and(|p| p.path == result.path) {
+            return;
+        }
+        self.preview = Some(load_preview(&self.root, result, &self.query));
+    }
+
+    /// Opens the selected file in the editor at its first match, with the
+    /// terminal handed over until the editor exits.
+    fn open_selected(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        let Some(preview) = &self.preview else {
+            return Ok(());
+        };
+        let editor = std::env::var("VISUAL")
+            .ok()
+            .filter(|e| !e.trim().is_empty())
+            .or_else(|| std::env::var("EDITOR").ok().filter(|e| !e.trim().is_empty()))
+            .unwrap_or_else(|| "vi".to_string());
+        let Some(mut command) = editor_command(&editor, &self.root.join(&preview.path), preview.first_match)
+        else {
+            return Ok(());
+        };
+
+        terminal::disable_raw_mode()?;
+        execute!(std::io::stdout(), terminal::LeaveAlternateScreen)?;
+        let status = command.status();
+        terminal::enable_raw_mode()?;
+        execute!(std::io::stdout(), terminal::EnterAlternateScreen)?;
+        terminal.clear()?;
+
+        match status {
+            Ok(status) if !status.success() => self.error = Some(format!("{} exited with {}", editor, status)),
+            Ok(_) => {}
+            Err(err) => self.error = Some(format!("cannot run {}: {}", editor, err)),
+        }
+        // The file may have been edited.
+        self.preview = None;
+        self.select(self.list.selected());
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [query_area, body, keys_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(3), Constraint::Length(1)])
+                .areas(frame.area());
+        let [list_area, preview_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(body);
+
+        let title = if self.opts.fuzzy { " ns (fuzzy) " } else { " ns " };
+        frame.render_widget(
+            Paragraph::new(self.query.as_str()).block(Block::bordered().title(title)),
+            query_area,
+        );
+        let typed = self.query.chars().count() as u16;
+        frame.set_cursor_position((
+            (query_area.x + 1 + typed).min(query_area.right().saturating_sub(2)),
+            query_area.y + 1,
+        ));
+
+        let title = match &self.error {
+            Some(err) => Line::from(format!(" {} ", err)).red(),
+            None => Line::from(format!(" {} results ", self.results.len())),
+        };
+        let items: Vec<ListItem> = self
+            .results
+            .iter()
+            .map(|r| {
+                ListItem::new(Line::from(vec![
+                    Span::raw(r.path.clone()),
+                    Span::styled(format!("  {:.1}", r.score), Style::new().dark_gray()),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().reversed())
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+
+        self.preview_height = preview_area.height.saturating_sub(2) as usize;
+        let preview = match &self.preview {
+            Some(preview) => {
+                let end = (preview.scroll + self.preview_height).min(preview.lines.len());
+                let shown = preview.lines[preview.scroll.min(end)..end].to_vec();
+                Paragraph::new(shown).block(Block::bordered().title(format!(" {} ", preview.path)))
+            }
+            None => Paragraph::new("").block(Block::bordered()),
+        };
+        frame.render_widget(preview, preview_area);
+        frame.render_widget(Paragraph::new(KEYS).dark_gray(), keys_area);
+    }
+}
+
+/// Reads `result`'s file for the preview, scrolled to just above its first
+/// matching line.
+fn load_preview(root: &Path, result: &SearchResult, query: &str) -> Preview {
+    let source = match std::fs::read(root.join(&result.path)) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(err) => format!("cannot read {}: {}", result.path, err),
+    };
+    let spans = result
+        .lang
+        .as_deref()
+        .filter(|_| source.len() <= MAX_HIGHLIGHT_BYTES)
+        .and_then(|lang| highlight(lang, source.as_bytes()))
+        .unwrap_or_default();
+    let mut matches = result.match_lines.clone();
+    if matches.is_empty() {
+        // Not read from the postings (fuzzy terms, older indexes): lines
+        // holding a query word.
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        matches = source
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| {
+                let line = line.to_lowercase();
+                words.iter().any(|w| line.contains(w.as_str()))
+            })
+            .map(|(i, _)| i + 1)
+            .collect();
+    }
+    let first_match = matches.iter().copied().min().unwrap_or(1);
+    Preview {
+        path: result.path.clone(),
+        lines: preview_lines(&source, &spans, &matches),
+        first_match,
+        scroll: first_match.saturating_sub(1 + PREVIEW_LEAD),
+    }
+}
+
+/// `source` as numbered, highlighted lines; the numbers of `matches`
+/// (1-based) stand out.
+fn preview_lines(source: &str, spans: &[(Range<usize>, Highlight)], matches: &[usize]) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut next_span = 0;
+    let mut start = 0;
+    for (i, text) in source.split_inclusive('\n').enumerate() {
+        let line_start = start;
+        start += text.len();
+        let text = text.trim_end_matches(['\n', '\r']);
+        let end = line_start + text.len();
+
+        let number = i + 1;
+        let gutter = if matches.contains(&number) {
+            Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::new().dark_gray()
+        };
+        let mut parts = vec![Span::styled(format!("{:>5} ", number), gutter)];
+        while next_span < spans.len() && spans[next_span].0.end <= line_start {
+            next_span += 1;
+        }
+        let mut at = line_start;
+        for (range, class) in spans[next_span..].iter().take_while(|(r, _)| r.start < end) {
+            let (from, to) = (range.start.max(line_start), range.end.min(end));
+            if from > at {
+                parts.push(Span::raw(expand_tabs(&source[at..from])));
+            }
+            parts.push(Span::styled(expand_tabs(&source[from..to]), style_of(*class)));
+            at = to;
+        }
+        if at < end {
+            parts.push(Span::raw(expand_tabs(&source[at..end])));
+        }
+        lines.push(Line::from(parts));
+    }
+    lines
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', "    ")
+}
+
+fn style_of(class: Highlight) -> Style {
+    match class {
+        Highlight::Comment => Style::new().dark_gray().italic(),
+        Highlight::String => Style::new().green(),
+        Highlight::Number => Style::new().magenta(),
+        Highlight::Keyword => Style::new().blue().bold(),
+        Highlight::Type => Style::new().cyan(),
+        Highlight::Function => Style::new().yellow(),
+    }
+}
+
+/// `editor` (a command line such as `code -w`) opening `path` at `line`;
+/// `None` for a blank editor.
+fn editor_command(editor: &str, path: &Path, line: usize) -> Option<Command> {
+    let mut words = editor.split_whitespace();
+    let mut command = Command::new(words.next()?);
+    command.args(words).arg(format!("+{}", line)).arg(path);
+    Some(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    #[test]
+    fn keys_edit_the_query_and_toggle_fuzzy() {
+        let mut app = App::new(PathBuf::from("."), SearchOptions::default(), String::new());
+        for c in "retry backoff".chars() {
+            app.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(app.query, "retry backoff");
+        assert!(app.stale);
+        app.handle_key(ctrl('w'));
+        assert_eq!(app.query, "retry ");
+        app.handle_key(key(KeyCode::Backspace));
+        assert_eq!(app.query, "retry");
+        app.handle_key(ctrl('f'));
+        assert!(app.opts.fuzzy);
+        app.handle_key(ctrl('u'));
+        assert_eq!(app.query, "");
+
+        app.handle_key(key(KeyCode::Down));
+        assert_eq!(app.list.selected(), None, "nothing to select");
+        assert_eq!(app.handle_key(key(KeyCode::Enter)), Action::Open);
+        assert_eq!(app.handle_key(ctrl('c')), Action::Quit);
+        assert_eq!(app.handle_key(key(KeyCode::Esc)), Action::Quit);
+    }
+
+    #[test]
+    fn preview_lines_split_highlights_across_lines() {
+        let source = "/* one\ntwo */ fn\tgo() {}\n";
+        let spans = vec![(0..13, Highlight::Comment), (14..16, Highlight::Keyword)];
+        let lines = preview_lines(source, &spans, &[2]);
+        assert_eq!(lines.len(), 2);
+        let text = |line: &Line| line.spans.iter().map(|s| s.content.to_string()).collect::<Vec<_>>();
+        assert_eq!(text(&lines[0]), vec!["    1 ", "/* one"]);
+        assert_eq!(text(&lines[1]), vec!["    2 ", "two */", " ", "fn", "    go() {}"]);
+        assert_eq!(lines[1].spans[0].style.fg, Some(Color::Yellow), "matching line");
+        assert_eq!(lines[1].spans[1].style, style_of(Highlight::Comment));
+    }
+
+    #[test]
+    fn editor_command_passes_flags_then_line_and_path() {
+        let command = editor_command("code -w", Path::new("src/lib.rs"), 12).unwrap();
+        assert_eq!(command.get_program(), "code");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args, vec!["-w", "+12", "src/lib.rs"]);
+        assert!(editor_command("  ", Path::new("a"), 1).is_none());
+    }
+}
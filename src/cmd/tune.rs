@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+
+use crate::cmd::TuneArgs;
+use crate::error::NsError;
+use crate::searcher::calibrate::{calibrate, CALIBRATION_DEPTH};
+
+/// Maximum number of missed queries listed individually in text output.
+const MISS_REPORT_LIMIT: usize = 20;
+
+pub fn run(args: &TuneArgs) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let sample = if args.sample == 0 { None } else { Some(args.sample) };
+    let report = match calibrate(&root, sample) {
+        Ok(r) => r,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: calibration failed: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    if args.json {
+        let json = serde_json::json!({
+            "candidates": report.candidates,
+            "queries": report.queries,
+            "top1": report.top1,
+            "top1_percent": (report.top1_percent() * 10.0).round() / 10.0,
+            "top5": report.top5,
+            "top5_percent": (report.top5_percent() * 10.0).round() / 10.0,
+            "mrr": (report.mrr * 1000.0).round() / 1000.0,
+            "misses": report.misses,
+        });
+        println!("{}", json);
+        return;
+    }
+
+    println!("ns tune --calibrate");
+    println!("  symbols eligible : {}", report.candidates);
+    println!("  queries run      : {}", report.queries);
+    println!(
+        "  rank-1 accuracy  : {:.1}% ({}/{})",
+        report.top1_percent(),
+        report.top1,
+        report.queries
+    );
+    println!(
+        "  top-5 accuracy   : {:.1}% ({}/{})",
+        report.top5_percent(),
+        report.top5,
+        report.queries
+    );
+    println!("  MRR@{:<12} : {:.3}", CALIBRATION_DEPTH, report.mrr);
+    if !report.misses.is_empty() {
+        println!("  missed queries:");
+    }
+    for miss in report.misses.iter().take(MISS_REPORT_LIMIT) {
+        let rank = miss
+            .rank
+            .map_or_else(|| format!(">{}", CALIBRATION_DEPTH), |r| r.to_string());
+        println!(
+            "    {:<24} rank {:<4} expected {}, got {}",
+            miss.query,
+            rank,
+            miss.expected.join(", "),
+            miss.got.as_deref().unwrap_or("(no results)")
+        );
+    }
+    if report.misses.len() > MISS_REPORT_LIMIT {
+        println!("    ... and {} more", report.misses.len() - MISS_REPORT_LIMIT);
+    }
+}
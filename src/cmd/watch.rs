@@ -0,0 +1,114 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::Duration;
+
+use ::notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::cmd::index::{run_full, run_incremental};
+use crate::cmd::WatchArgs;
+use crate::indexer::walker::in_skipped_dir;
+use crate::indexer::writer::{read_meta, utc_timestamp_iso8601, SCHEMA_VERSION};
+use crate::indexer::IndexOptions;
+
+pub fn run(args: &WatchArgs) {
+    let root = args
+        .root
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let root = match root.canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve root path '{}': {}", root.display(), err);
+            std::process::exit(1);
+        }
+    };
+
+    let opts = IndexOptions {
+        max_file_size: args.max_file_size,
+        ..Default::default()
+    };
+
+    let has_index = matches!(read_meta(&root), Ok(meta) if meta.schema_version == SCHEMA_VERSION);
+    let ok = if has_index {
+        run_incremental(&root, &opts, false)
+    } else {
+        run_full(&root, &opts)
+    };
+    if !ok {
+        std::process::exit(1);
+    }
+
+    let (tx, rx) = channel();
+    let mut watcher: RecommendedWatcher = match ::notify::recommended_watcher(tx) {
+        Ok(w) => w,
+        Err(err) => {
+            eprintln!("error: cannot start file watcher: {}", err);
+            std::process::exit(1);
+        }
+    };
+    if let Err(err) = watcher.watch(&root, RecursiveMode::Recursive) {
+        eprintln!("error: cannot watch '{}': {}", root.display(), err);
+        std::process::exit(1);
+    }
+
+    let debounce = Duration::from_millis(args.debounce);
+    eprintln!("Watching {} for changes (Ctrl-C to stop)", root.display());
+    loop {
+        // Block until something outside .ns/ and .git/ changes. The index
+        // writes of the previous update land here too and are dropped.
+        match rx.recv() {
+            Ok(Ok(event)) if is_relevant(&root, &event) => {}
+            Ok(Ok(_)) => continue,
+            Ok(Err(err)) => {
+                eprintln!("warning: file watcher error: {}", err);
+                continue;
+            }
+            Err(_) => return,
+        }
+        // Wait for the burst (a save, a checkout, a build) to go quiet.
+        loop {
+            match rx.recv_timeout(debounce) {
+                Ok(_) => continue,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        eprintln!("[{}]", utc_timestamp_iso8601());
+        // Errors are reported and watching goes on, as with `ns index --every`.
+        run_incremental(&root, &opts, false);
+    }
+}
+
+/// Whether `event` may change what is indexed: anything but a read, on a
+/// path outside `.ns/` and `.git/`. A rescan request always counts.
+fn is_relevant(root: &Path, event: &Event) -> bool {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return false;
+    }
+    if event.need_rescan() {
+        return true;
+    }
+    event.paths.iter().any(|path| {
+        let rel = path.strip_prefix(root).unwrap_or(path);
+        !in_skipped_dir(&rel.to_string_lossy())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::notify::event::{AccessKind, CreateKind, ModifyKind};
+
+    #[test]
+    fn index_and_git_writes_are_not_relevant() {
+        let root = Path::new("/repo");
+        let event = |kind, path: &str| Event::new(kind).add_path(root.join(path));
+
+        assert!(is_relevant(root, &event(EventKind::Modify(ModifyKind::Any), "src/main.rs")));
+        assert!(is_relevant(root, &event(EventKind::Create(CreateKind::File), "README.md")));
+        assert!(!is_relevant(root, &event(EventKind::Access(AccessKind::Any), "src/main.rs")));
+        assert!(!is_relevant(root, &event(EventKind::Modify(ModifyKind::Any), ".ns/index/meta.json")));
+        assert!(!is_relevant(root, &event(EventKind::Create(CreateKind::File), ".git/index.lock")));
+    }
+}
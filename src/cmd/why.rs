@@ -0,0 +1,156 @@
+use std::path::PathBuf;
+
+use crate::cmd::WhyArgs;
+use crate::config::load_config;
+use crate::error::NsError;
+use crate::searcher::format::format_single_json_value;
+use crate::searcher::query::SearchOptions;
+use crate::searcher::why::{explain_file, Verdict, WhyReport};
+use crate::searcher::DisplayResult;
+
+pub fn run(args: &WhyArgs) {
+    let root = match PathBuf::from(".").canonicalize() {
+        Ok(p) => p,
+        Err(err) => {
+            eprintln!("error: cannot resolve current directory: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    // Rank the way a plain `ns <query>` would, including .ns/config.toml.
+    let mut opts = SearchOptions {
+        stem: args.stem,
+        ..Default::default()
+    };
+    match load_config(&root) {
+        Ok(config) => config.apply(&mut opts),
+        Err(err) => {
+            eprintln!("error: cannot load .ns/config.toml: {}", err);
+            std::process::exit(1);
+        }
+    }
+
+    let report = match explain_file(&root, &args.file, &args.query, &opts) {
+        Ok(r) => r,
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("error: no index found. Run 'ns index' to create one.");
+            std::process::exit(1);
+        }
+        Err(NsError::SchemaVersionMismatch { .. }) => {
+            eprintln!("error: index schema is outdated. Run 'ns index' to rebuild.");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    if args.json {
+        let mut json = serde_json::to_value(&report).unwrap_or_default();
+        if let Some(factors) = ranking_factors(report) {
+            json["ranking_factors"] = factors;
+        }
+        println!("{}", json);
+    } else {
+        print_report(&report, &args.query);
+    }
+}
+
+/// `ranking_factors` of the file's result, as `ns --json` reports them.
+fn ranking_factors(report: WhyReport) -> Option<serde_json::Value> {
+    let Verdict::Ranked { rank, .. } = report.verdict else {
+        return None;
+    };
+    let result = report.result?;
+    let score = result.score;
+    let display = DisplayResult {
+        rank,
+        result,
+        context_lines: Vec::new(),
+        truncated_count: 0,
+        matches: Vec::new(),
+        merged: Vec::new(),
+    };
+    let mut value = format_single_json_value(&display, "", None);
+    value["ranking_factors"]["score"] = serde_json::json!(score);
+    Some(value["ranking_factors"].take())
+}
+
+fn print_report(report: &WhyReport, query: &str) {
+    let summary = match report.verdict {
+        Verdict::NotIndexed { ref reason } => format!("not in the index: {}", reason),
+        Verdict::Excluded => format!("excluded by -{}", report.excluded_by.join(", -")),
+        Verdict::NoMatch => "none of the query's terms occur in it".to_string(),
+        Verdict::Partial => "matches some terms, but not a quoted phrase of the query".to_string(),
+        Verdict::Collapsed {
+            ref into,
+            identical,
+        } => format!(
+            "folded into {} as {} copy (--no-dedupe lists it)",
+            into,
+            if identical {
+                "an identical"
+            } else {
+                "a near-identical"
+            }
+        ),
+        Verdict::Ranked { rank, total } if rank <= report.page_size => {
+            format!("rank {} of {}", rank, total)
+        }
+        Verdict::Ranked { rank, total } => format!(
+            "rank {} of {}, below the first {} results (-m {} shows it)",
+            rank, total, report.page_size, rank
+        ),
+    };
+    println!("{}: {}", report.path, summary);
+    println!("query: {}", query);
+
+    if !report.terms.is_empty() {
+        let width = report
+            .terms
+            .iter()
+            .map(|t| t.term.len())
+            .max()
+            .unwrap_or(4)
+            .max(4);
+        println!();
+        println!("{:<12}  {:<width$}  {:>7}", "field", "term", "score");
+        for t in &report.terms {
+            let score = t.score.map_or("-".to_string(), |s| format!("{:.2}", s));
+            println!("{:<12}  {:<width$}  {:>7}", t.field, t.term, score);
+        }
+    }
+
+    if let Some(ref result) = report.result {
+        let s = &result.rerank;
+        println!();
+        println!(
+            "score {:.2} (bm25 content {:.1}, symbols {:.1})",
+            result.score, result.score_content, result.score_symbols
+        );
+        println!(
+            "signals: exact_symbol {}, coverage {}, proximity {}, path_match {:.2}, recency {:.2}, churn {:.2}, semantic {:.2}",
+            s.exact_symbol,
+            s.coverage.map_or("-".to_string(), |c| format!("{:.2}", c)),
+            s.proximity,
+            s.path_match,
+            s.recency,
+            s.churn,
+            s.semantic
+        );
+        let mut reasons = Vec::new();
+        if result.is_test {
+            reasons.push("a test file");
+        }
+        if result.generated {
+            reasons.push("a generated file");
+        }
+        if result.vendored {
+            reasons.push("vendored code");
+        }
+        if !reasons.is_empty() {
+            println!("down-weighted as {}", reasons.join(" and "));
+        }
+    }
+}
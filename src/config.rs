@@ -0,0 +1,292 @@
+//! Per-repo configuration (`.ns/config.toml`).
+//!
+//! Settings that belong to a repo rather than to one invocation. A missing
+//! file is the default configuration; an invalid one is an error, so a typo
+//! doesn't silently change ranking.
+//!
+//! Flags given on the command line override what is set here.
+//!
+//! ```toml
+//! [search]
+//! max_count = 20
+//! context = 3
+//! budget = 4000
+//! type = "rust"
+//! max_count_hard = 500
+//!
+//! [lang_boost]
+//! rust = 1.2
+//! json = 0.5
+//!
+//! [ranking]
+//! test_weight = 0.3
+//! generated_weight = 0.05
+//! vendor_weight = 0.1
+//! vendor_dirs = ["vendor", "node_modules", "generated"]
+//!
+//! [index]
+//! roots = ["api", "web"]
+//! exclude = ["*.min.js", "fixtures/"]
+//!
+//! [hooks]
+//! events = ["post-commit", "post-merge"]
+//! background = false
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::NsError;
+use crate::indexer::walker::parse_exclude;
+use crate::searcher::query::SearchOptions;
+
+/// Name of the config file inside `.ns/`.
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Git hooks `ns hooks install` knows how to install.
+pub const HOOK_EVENTS: &[&str] = &["post-commit", "post-merge", "post-checkout"];
+
+/// Contents of `.ns/config.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub search: SearchConfig,
+    /// Score multipliers by language name, or by file extension for files
+    /// without a detected language (see `SearchOptions::lang_boost`).
+    #[serde(default)]
+    pub lang_boost: BTreeMap<String, f32>,
+    #[serde(default)]
+    pub ranking: RankingConfig,
+    #[serde(default)]
+    pub index: IndexConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+}
+
+/// The `[search]` table: defaults for search flags that are not given.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct SearchConfig {
+    /// `--max-count`.
+    pub max_count: Option<usize>,
+    /// `--context`.
+    pub context: Option<usize>,
+    /// `--budget`; 0 is unlimited.
+    pub budget: Option<usize>,
+    /// `--type`.
+    #[serde(rename = "type")]
+    pub file_type: Option<String>,
+    /// `--max-count-hard`, below `$NS_MAX_COUNT_HARD`; 0 lifts the cap.
+    pub max_count_hard: Option<usize>,
+}
+
+/// The `[index]` table.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct IndexConfig {
+    /// Directories below the repo to index instead of all of it, like
+    /// `ns index --root` given once per directory.
+    #[serde(default)]
+    pub roots: Vec<String>,
+    /// Globs of paths to leave out, like `ns index --exclude` given once
+    /// per glob.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// The `[hooks]` table, read by `ns hooks install`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct HooksConfig {
+    /// Git hooks to install, from `HOOK_EVENTS`; all of them by default.
+    #[serde(default = "default_hook_events")]
+    pub events: Vec<String>,
+    /// Let the hook re-index in the background, so git returns at once.
+    /// Off, the commit waits until the index is up to date.
+    #[serde(default = "default_true")]
+    pub background: bool,
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            events: default_hook_events(),
+            background: true,
+        }
+    }
+}
+
+fn default_hook_events() -> Vec<String> {
+    HOOK_EVENTS.iter().map(|e| e.to_string()).collect()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// The `[ranking]` table.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RankingConfig {
+    /// Score multiplier for test files; `DEFAULT_TEST_WEIGHT` when unset.
+    pub test_weight: Option<f32>,
+    /// Score multiplier for generated files; `DEFAULT_GENERATED_WEIGHT`
+    /// when unset.
+    pub generated_weight: Option<f32>,
+    /// Score multiplier for vendored files; `DEFAULT_VENDOR_WEIGHT` when unset.
+    pub vendor_weight: Option<f32>,
+    /// Directory names counted as vendored, replacing the default list.
+    pub vendor_dirs: Option<Vec<String>>,
+}
+
+/// Path of the config file for the repo at `root`.
+pub fn config_path(root: &Path) -> PathBuf {
+    root.join(".ns").join(CONFIG_FILE)
+}
+
+/// Reads `.ns/config.toml` under `root`. A missing file is the default config.
+pub fn load_config(root: &Path) -> Result<Config, NsError> {
+    let config: Config = match std::fs::read_to_string(config_path(root)) {
+        Ok(content) => toml::from_str(&content)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(e.into()),
+    };
+    for (key, factor) in &config.lang_boost {
+        check_factor(&format!("lang_boost.{}", key), *factor)?;
+    }
+    if let Some(weight) = config.ranking.test_weight {
+        check_factor("ranking.test_weight", weight)?;
+    }
+    if let Some(weight) = config.ranking.generated_weight {
+        check_factor("ranking.generated_weight", weight)?;
+    }
+    if let Some(weight) = config.ranking.vendor_weight {
+        check_factor("ranking.vendor_weight", weight)?;
+    }
+    for dir in &config.index.roots {
+        let path = Path::new(dir);
+        if dir.is_empty() || path.is_absolute() || path.components().any(|c| c.as_os_str() == "..") {
+            return Err(NsError::InvalidConfig(format!(
+                "index.roots must be directories inside the repo, got '{}'",
+                dir
+            )));
+        }
+    }
+    for glob in &config.index.exclude {
+        parse_exclude(glob).map_err(|e| NsError::InvalidConfig(format!("index.exclude: {}", e)))?;
+    }
+    for event in &config.hooks.events {
+        if !HOOK_EVENTS.contains(&event.as_str()) {
+            return Err(NsError::InvalidConfig(format!(
+                "hooks.events must be among {}, got '{}'",
+                HOOK_EVENTS.join(", "),
+                event
+            )));
+        }
+    }
+    Ok(config)
+}
+
+fn check_factor(key: &str, factor: f32) -> Result<(), NsError> {
+    if factor.is_finite() && factor > 0.0 {
+        Ok(())
+    } else {
+        Err(NsError::InvalidConfig(format!(
+            "{} must be a positive number, got {}",
+            key, factor
+        )))
+    }
+}
+
+impl Config {
+    /// Applies the ranking settings to `opts`.
+    pub fn apply(&self, opts: &mut SearchOptions) {
+        opts.lang_boost = self
+            .lang_boost
+            .iter()
+            .map(|(key, factor)| (key.clone(), *factor))
+            .collect();
+        if let Some(weight) = self.ranking.test_weight {
+            opts.test_weight = weight;
+        }
+        if let Some(weight) = self.ranking.generated_weight {
+            opts.generated_weight = weight;
+        }
+        if let Some(weight) = self.ranking.vendor_weight {
+            opts.vendor_weight = weight;
+        }
+        if let Some(ref dirs) = self.ranking.vendor_dirs {
+            opts.vendor_dirs = dirs.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_config_is_default() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(load_config(dir.path()).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn lang_boost_must_be_positive() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".ns")).unwrap();
+        let path = config_path(dir.path());
+
+        std::fs::write(&path, "[lang_boost]\nrust = 1.5\njson = 0.5\n").unwrap();
+        let config = load_config(dir.path()).unwrap();
+        assert_eq!(config.lang_boost.get("json"), Some(&0.5));
+
+        std::fs::write(&path, "[lang_boost]\nrust = 0\n").unwrap();
+        assert!(matches!(load_config(dir.path()), Err(NsError::InvalidConfig(_))));
+
+        std::fs::write(&path, "[ranking]\ntest_weight = -1\n").unwrap();
+        assert!(matches!(load_config(dir.path()), Err(NsError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn index_roots_stay_inside_the_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".ns")).unwrap();
+        let path = config_path(dir.path());
+
+        std::fs::write(&path, "[index]\nroots = [\"api\", \"web/src\"]\n").unwrap();
+        assert_eq!(load_config(dir.path()).unwrap().index.roots, vec!["api", "web/src"]);
+
+        std::fs::write(&path, "[index]\nroots = [\"../elsewhere\"]\n").unwrap();
+        assert!(matches!(load_config(dir.path()), Err(NsError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn search_index_and_hooks_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".ns")).unwrap();
+        let path = config_path(dir.path());
+
+        let config = load_config(dir.path()).unwrap();
+        assert_eq!(config.hooks.events.len(), HOOK_EVENTS.len());
+        assert!(config.hooks.background);
+
+        std::fs::write(
+            &path,
+            "[search]\nmax_count = 3\ntype = \"rust\"\nmax_count_hard = 500\n\n[index]\nexclude = [\"*.min.js\"]\n\n[hooks]\nevents = [\"post-merge\"]\nbackground = false\n",
+        )
+        .unwrap();
+        let config = load_config(dir.path()).unwrap();
+        assert_eq!(config.search.max_count, Some(3));
+        assert_eq!(config.search.context, None);
+        assert_eq!(config.search.file_type.as_deref(), Some("rust"));
+        assert_eq!(config.search.max_count_hard, Some(500));
+        assert_eq!(config.index.exclude, vec!["*.min.js"]);
+        assert_eq!(config.hooks.events, vec!["post-merge"]);
+        assert!(!config.hooks.background);
+
+        std::fs::write(&path, "[hooks]\nevents = [\"pre-push\"]\n").unwrap();
+        assert!(matches!(load_config(dir.path()), Err(NsError::InvalidConfig(_))));
+
+        std::fs::write(&path, "[index]\nexclude = [\"[oops\"]\n").unwrap();
+        assert!(matches!(load_config(dir.path()), Err(NsError::InvalidConfig(_))));
+    }
+}
@@ -19,6 +19,18 @@ pub enum NsError {
     SchemaVersionMismatch { found: u32, expected: u32 },
     /// Invalid glob pattern passed via `-g`/`--glob`.
     Glob(glob::PatternError),
+    /// TOML parse failure (repo registry, golden queries, config).
+    Toml(toml::de::Error),
+    /// Invalid regular expression passed via `--regex`.
+    Regex(regex::Error),
+    /// A search option needs an index built with the matching `ns index` flag.
+    MissingIndexOption(&'static str),
+    /// A query term matches nearly every indexed file; `--force` runs it anyway.
+    QueryTooBroad { term: String, matches: u64, total: u64 },
+    /// `.ns/config.toml` parsed but holds an invalid value.
+    InvalidConfig(String),
+    /// The embedding model could not be loaded or run (`--embed-model`, `--semantic`).
+    Embedding(String),
 }
 
 impl fmt::Display for NsError {
@@ -34,6 +46,20 @@ impl fmt::Display for NsError {
                 found, expected
             ),
             NsError::Glob(e) => write!(f, "invalid glob pattern: {}", e),
+            NsError::Toml(e) => write!(f, "toml error: {}", e),
+            NsError::Regex(e) => write!(f, "invalid regex: {}", e),
+            NsError::MissingIndexOption(flag) => write!(
+                f,
+                "index was built without {} — run `ns index {}` to rebuild",
+                flag, flag
+            ),
+            NsError::QueryTooBroad { term, matches, total } => write!(
+                f,
+                "'{}' matches {} of {} indexed files — refine the query or pass --force",
+                term, matches, total
+            ),
+            NsError::InvalidConfig(msg) => write!(f, "invalid .ns/config.toml: {}", msg),
+            NsError::Embedding(msg) => write!(f, "embedding model error: {}", msg),
         }
     }
 }
@@ -47,6 +73,12 @@ impl std::error::Error for NsError {
             NsError::Json(e) => Some(e),
             NsError::SchemaVersionMismatch { .. } => None,
             NsError::Glob(e) => Some(e),
+            NsError::Toml(e) => Some(e),
+            NsError::Regex(e) => Some(e),
+            NsError::MissingIndexOption(_) => None,
+            NsError::QueryTooBroad { .. } => None,
+            NsError::InvalidConfig(_) => None,
+            NsError::Embedding(_) => None,
         }
     }
 }
@@ -81,6 +113,18 @@ impl From<glob::PatternError> for NsError {
     }
 }
 
+impl From<toml::de::Error> for NsError {
+    fn from(e: toml::de::Error) -> Self {
+        NsError::Toml(e)
+    }
+}
+
+impl From<regex::Error> for NsError {
+    fn from(e: regex::Error) -> Self {
+        NsError::Regex(e)
+    }
+}
+
 impl NsError {
     /// Returns `true` if this error is a tantivy lock-acquisition failure.
     ///
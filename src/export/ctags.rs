@@ -0,0 +1,106 @@
+//! ctags export of the symbol index (`ns export --ctags`).
+//!
+//! Writes a `tags` file in the extended ctags format that Vim, Emacs and
+//! most editors read for jump-to-definition: one line per symbol with its
+//! file, the line it is defined on as the address, and its kind and last
+//! line as extension fields. Everything comes from stored fields, so no
+//! source file is read or parsed.
+
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::NsError;
+use crate::indexer::symbols::SymbolKind;
+
+use super::read_indexed_files;
+
+/// Counts reported after an export.
+#[derive(Debug, Default)]
+pub struct CtagsExportStats {
+    /// Files with at least one tag.
+    pub files: usize,
+    pub tags: usize,
+    pub bytes_written: usize,
+}
+
+/// One line of the tags file.
+#[derive(Debug)]
+struct Tag<'a> {
+    name: &'a str,
+    path: &'a str,
+    line: usize,
+    end_line: usize,
+    kind: SymbolKind,
+}
+
+/// Writes a tags file for the repository at `root` to `out`, sorted by name
+/// so editors can binary-search it. Paths are relative to `root`.
+pub fn export_ctags(root: &Path, out: &mut impl Write) -> Result<CtagsExportStats, NsError> {
+    let files = read_indexed_files(root)?;
+    let mut stats = CtagsExportStats::default();
+
+    let mut tags = Vec::new();
+    for file in &files {
+        if file.spans.len() != file.symbols.len() {
+            continue;
+        }
+        for (i, name) in file.symbols.iter().enumerate() {
+            let (line, end_line) = file.spans[i];
+            tags.push(Tag {
+                name,
+                path: &file.path,
+                line,
+                end_line,
+                kind: file.kinds.get(i).copied().unwrap_or(SymbolKind::Other),
+            });
+        }
+        stats.files += usize::from(!file.symbols.is_empty());
+    }
+    tags.sort_by(|a, b| (a.name, a.path, a.line).cmp(&(b.name, b.path, b.line)));
+
+    let mut text = String::new();
+    text.push_str("!_TAG_FILE_FORMAT\t2\t/extended format; --format=1 will not append ;\" to lines/\n");
+    text.push_str("!_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted, 2=foldcase/\n");
+    text.push_str("!_TAG_PROGRAM_NAME\tns\t//\n");
+    text.push_str(&format!("!_TAG_PROGRAM_VERSION\t{}\t//\n", env!("CARGO_PKG_VERSION")));
+    for tag in &tags {
+        text.push_str(&tag_line(tag));
+    }
+
+    out.write_all(text.as_bytes())?;
+    stats.tags = tags.len();
+    stats.bytes_written = text.len();
+    Ok(stats)
+}
+
+/// `name<TAB>path<TAB>line;"<TAB>kind:K<TAB>end:N`, newline-terminated.
+fn tag_line(tag: &Tag) -> String {
+    format!(
+        "{}\t{}\t{};\"\tkind:{}\tend:{}\n",
+        tag.name,
+        tag.path,
+        tag.line,
+        tag.kind.as_str(),
+        tag.end_line
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_line_addresses_the_definition_line() {
+        let tag = Tag {
+            name: "EventStore",
+            path: "src/event_store.rs",
+            line: 12,
+            end_line: 40,
+            kind: SymbolKind::Type,
+        };
+        assert_eq!(
+            tag_line(&tag),
+            "EventStore\tsrc/event_store.rs\t12;\"\tkind:type\tend:40\n"
+        );
+    }
+}
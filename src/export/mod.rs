@@ -0,0 +1,107 @@
+//! Converters from the ns index to formats consumed by other tools.
+
+pub mod ctags;
+pub mod scip;
+
+use std::path::Path;
+
+use tantivy::schema::Value;
+use tantivy::{ReloadPolicy, TantivyDocument};
+
+use crate::error::NsError;
+use crate::indexer::symbols::SymbolKind;
+use crate::indexer::writer::open_index;
+use crate::schema::{
+    lang_field, path_field, symbol_kinds_field, symbol_spans_field, symbols_raw_field,
+};
+use crate::searcher::query::{stored_kinds, stored_spans};
+
+/// One indexed file as read back from the index.
+pub(crate) struct IndexedFile {
+    pub path: String,
+    pub lang: Option<String>,
+    pub symbols: Vec<String>,
+    /// First and last line of each of `symbols`, same order.
+    pub spans: Vec<(usize, usize)>,
+    /// Kind of each of `symbols`, same order.
+    pub kinds: Vec<SymbolKind>,
+}
+
+impl IndexedFile {
+    /// Orders the symbols by their first line. Symbols without a span (an
+    /// index trimmed to its budget) keep their order.
+    fn sort_by_line(&mut self) {
+        if self.spans.len() != self.symbols.len() || self.kinds.len() != self.symbols.len() {
+            return;
+        }
+        let mut order: Vec<usize> = (0..self.symbols.len()).collect();
+        order.sort_by_key(|&i| self.spans[i].0);
+        self.symbols = order.iter().map(|&i| self.symbols[i].clone()).collect();
+        self.spans = order.iter().map(|&i| self.spans[i]).collect();
+        self.kinds = order.iter().map(|&i| self.kinds[i]).collect();
+    }
+}
+
+/// Reads path, language and symbols for every live file, sorted by path.
+pub(crate) fn read_indexed_files(root: &Path) -> Result<Vec<IndexedFile>, NsError> {
+    let (index, _meta) = open_index(root)?;
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let schema = index.schema();
+    let path_f = path_field(&schema);
+    let lang_f = lang_field(&schema);
+    let symbols_raw_f = symbols_raw_field(&schema);
+    let symbol_spans_f = symbol_spans_field(&schema);
+    let symbol_kinds_f = symbol_kinds_field(&schema);
+
+    let mut files = Vec::new();
+    for segment_reader in searcher.segment_readers() {
+        let store_reader = segment_reader.get_store_reader(1)?;
+        for doc_id in segment_reader.doc_ids_alive() {
+            let doc = store_reader.get::<TantivyDocument>(doc_id)?;
+            let Some(path) = doc.get_first(path_f).and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let lang = doc
+                .get_first(lang_f)
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+            let symbols = doc
+                .get_first(symbols_raw_f)
+                .and_then(|v| v.as_str())
+                .map(|s| {
+                    s.split('|')
+                        .filter(|s| !s.is_empty())
+                        .map(|s| s.to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+            files.push(IndexedFile {
+                path: path.to_string(),
+                lang,
+                symbols,
+                spans: stored_spans(&doc, symbol_spans_f),
+                kinds: stored_kinds(&doc, symbol_kinds_f),
+            });
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    // Chunks of a file (`ns index --chunk-lines`) come back as one file
+    // with all their symbols, in line order.
+    files.dedup_by(|chunk, file| {
+        if chunk.path != file.path {
+            return false;
+        }
+        file.symbols.append(&mut chunk.symbols);
+        file.spans.append(&mut chunk.spans);
+        file.kinds.append(&mut chunk.kinds);
+        true
+    });
+    for file in &mut files {
+        file.sort_by_line();
+    }
+    Ok(files)
+}
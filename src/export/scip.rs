@@ -0,0 +1,267 @@
+//! SCIP (SCIP Code Intelligence Protocol) export of the symbol index.
+//!
+//! Emits a protobuf-encoded `scip.Index` with one `Document` per indexed file
+//! and one definition `Occurrence` + `SymbolInformation` per extracted symbol.
+//! The encoder is hand-rolled: SCIP only needs varints and length-delimited
+//! fields, which is not worth a protobuf dependency.
+//!
+//! Each definition is located at the name on the line stored for the symbol
+//! at index time, or failing that (the file changed since) at the first
+//! whole-word occurrence of the name in the file. Symbols that cannot be
+//! located are still listed in `Document.symbols`.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::NsError;
+
+use super::{read_indexed_files, IndexedFile};
+
+/// SCIP symbol scheme used for all exported symbols.
+const SCHEME: &str = "ns";
+
+/// `SymbolRole.Definition`.
+const ROLE_DEFINITION: u64 = 1;
+/// `TextEncoding.UTF8`.
+const TEXT_ENCODING_UTF8: u64 = 1;
+/// `PositionEncoding.UTF8CodeUnitOffsetFromLineStart`.
+const POSITION_ENCODING_UTF8: u64 = 1;
+
+/// Counts reported after an export.
+#[derive(Debug, Default)]
+pub struct ScipExportStats {
+    pub documents: usize,
+    pub symbols: usize,
+    /// Symbols for which a definition occurrence was emitted.
+    pub occurrences: usize,
+    pub bytes_written: usize,
+}
+
+/// Writes a SCIP index for the repository at `root` to `out`.
+pub fn export_scip(root: &Path, out: &mut impl Write) -> Result<ScipExportStats, NsError> {
+    let files = read_indexed_files(root)?;
+    let mut stats = ScipExportStats::default();
+
+    let mut index = ProtoBuf::new();
+    index.message(1, &encode_metadata(root));
+
+    for file in &files {
+        let source = fs::read_to_string(root.join(&file.path)).unwrap_or_default();
+        let (document, located) = encode_document(file, &source);
+        index.message(2, &document);
+
+        stats.documents += 1;
+        stats.symbols += file.symbols.len();
+        stats.occurrences += located;
+    }
+
+    out.write_all(&index.bytes)?;
+    stats.bytes_written = index.bytes.len();
+    Ok(stats)
+}
+
+fn encode_metadata(root: &Path) -> Vec<u8> {
+    let mut tool_info = ProtoBuf::new();
+    tool_info.string(1, "ns");
+    tool_info.string(2, env!("CARGO_PKG_VERSION"));
+
+    let mut metadata = ProtoBuf::new();
+    // field 1 (ProtocolVersion) is 0 = UnspecifiedProtocolVersion, the default.
+    metadata.message(2, &tool_info.bytes);
+    metadata.string(3, &format!("file://{}", root.display()));
+    metadata.varint(4, TEXT_ENCODING_UTF8);
+    metadata.bytes
+}
+
+/// Encodes a `Document`. Returns the bytes and the number of located definitions.
+fn encode_document(file: &IndexedFile, source: &str) -> (Vec<u8>, usize) {
+    let mut document = ProtoBuf::new();
+    document.string(1, &file.path);
+
+    let lines: Vec<&str> = source.lines().collect();
+    let mut located = 0;
+    for (i, name) in file.symbols.iter().enumerate() {
+        let symbol = symbol_string(&file.path, name);
+        let at_stored_line = file.spans.get(i).and_then(|&(line, _)| {
+            let index = line.checked_sub(1)?;
+            let (_, col) = find_definition(lines.get(index)?, name)?;
+            Some((index, col))
+        });
+        if let Some((line, col)) = at_stored_line.or_else(|| find_definition(source, name)) {
+            let mut occurrence = ProtoBuf::new();
+            occurrence.packed_int32(1, &[line as i32, col as i32, (col + name.len()) as i32]);
+            occurrence.string(2, &symbol);
+            occurrence.varint(3, ROLE_DEFINITION);
+            document.message(2, &occurrence.bytes);
+            located += 1;
+        }
+    }
+
+    for name in &file.symbols {
+        let mut info = ProtoBuf::new();
+        info.string(1, &symbol_string(&file.path, name));
+        info.string(6, name);
+        document.message(3, &info.bytes);
+    }
+
+    if let Some(lang) = &file.lang {
+        document.string(4, scip_language(lang));
+    }
+    document.varint(6, POSITION_ENCODING_UTF8);
+
+    (document.bytes, located)
+}
+
+/// Builds a global SCIP symbol: `ns . . . <path namespaces>/<name>.`
+///
+/// Package manager, name and version are left empty (`.`) — ns has no notion
+/// of packages, so the file path serves as the namespace.
+pub fn symbol_string(rel_path: &str, name: &str) -> String {
+    let mut symbol = format!("{} . . . ", SCHEME);
+    for segment in rel_path.split('/').filter(|s| !s.is_empty()) {
+        symbol.push_str(&escape_identifier(segment));
+        symbol.push('/');
+    }
+    symbol.push_str(&escape_identifier(name));
+    symbol.push('.');
+    symbol
+}
+
+/// Escapes a descriptor name per the SCIP grammar: simple identifiers pass
+/// through, anything else is wrapped in backticks (with backticks doubled).
+pub fn escape_identifier(name: &str) -> String {
+    let simple = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-' | '$'));
+    if simple {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('`', "``"))
+    }
+}
+
+/// Finds the 0-based (line, byte column) of the first whole-word occurrence of `name`.
+pub fn find_definition(source: &str, name: &str) -> Option<(usize, usize)> {
+    if name.is_empty() {
+        return None;
+    }
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    for (line_no, line) in source.lines().enumerate() {
+        let mut from = 0;
+        while let Some(pos) = line[from..].find(name) {
+            let start = from + pos;
+            let end = start + name.len();
+            let before_ok = !line[..start].chars().next_back().is_some_and(is_ident);
+            let after_ok = !line[end..].chars().next().is_some_and(is_ident);
+            if before_ok && after_ok {
+                return Some((line_no, start));
+            }
+            from = start + line[start..].chars().next().map_or(1, |c| c.len_utf8());
+        }
+    }
+    None
+}
+
+/// Maps ns language names to the names of SCIP's `Language` enum.
+fn scip_language(lang: &str) -> &str {
+    match lang {
+        "rust" => "Rust",
+        "python" => "Python",
+        "go" => "Go",
+        "javascript" => "JavaScript",
+        "typescript" => "TypeScript",
+        "elixir" => "Elixir",
+        other => other,
+    }
+}
+
+/// Minimal protobuf wire-format writer (varint and length-delimited fields only).
+struct ProtoBuf {
+    bytes: Vec<u8>,
+}
+
+impl ProtoBuf {
+    fn new() -> Self {
+        Self { bytes: Vec::new() }
+    }
+
+    fn raw_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+
+    fn tag(&mut self, field: u32, wire_type: u8) {
+        self.raw_varint((u64::from(field) << 3) | u64::from(wire_type));
+    }
+
+    fn varint(&mut self, field: u32, value: u64) {
+        self.tag(field, 0);
+        self.raw_varint(value);
+    }
+
+    fn message(&mut self, field: u32, payload: &[u8]) {
+        self.tag(field, 2);
+        self.raw_varint(payload.len() as u64);
+        self.bytes.extend_from_slice(payload);
+    }
+
+    fn string(&mut self, field: u32, value: &str) {
+        self.message(field, value.as_bytes());
+    }
+
+    fn packed_int32(&mut self, field: u32, values: &[i32]) {
+        let mut packed = ProtoBuf::new();
+        for &v in values {
+            // int32 is encoded as a sign-extended 64-bit varint.
+            packed.raw_varint(i64::from(v) as u64);
+        }
+        self.message(field, &packed.bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_varints() {
+        let mut buf = ProtoBuf::new();
+        buf.raw_varint(1);
+        buf.raw_varint(300);
+        assert_eq!(buf.bytes, vec![0x01, 0xac, 0x02]);
+    }
+
+    #[test]
+    fn encodes_string_field() {
+        let mut buf = ProtoBuf::new();
+        buf.string(1, "ns");
+        assert_eq!(buf.bytes, vec![0x0a, 0x02, b'n', b's']);
+    }
+
+    #[test]
+    fn escapes_non_simple_identifiers() {
+        assert_eq!(escape_identifier("EventStore"), "EventStore");
+        assert_eq!(escape_identifier("lib.rs"), "`lib.rs`");
+        assert_eq!(escape_identifier("a`b"), "`a``b`");
+    }
+
+    #[test]
+    fn builds_symbol_from_path_and_name() {
+        assert_eq!(
+            symbol_string("src/event_store.rs", "EventStore"),
+            "ns . . . src/`event_store.rs`/EventStore."
+        );
+    }
+
+    #[test]
+    fn finds_whole_word_definitions() {
+        let source = "use store;\nstruct EventStoreX;\nstruct EventStore {}\n";
+        assert_eq!(find_definition(source, "EventStore"), Some((2, 7)));
+        assert_eq!(find_definition(source, "missing"), None);
+    }
+}
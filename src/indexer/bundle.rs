@@ -0,0 +1,221 @@
+//! Single-file, read-only index bundles (`ns export --bundle`, `--index-file`).
+//!
+//! A bundle packs `meta.json` and every file of `.ns/index/` into one file
+//! that can be shipped inside a container or test harness and searched
+//! without unpacking. Layout (integers little-endian):
+//!
+//! ```text
+//! "NSBUNDLE" u32 version
+//! u64 meta.json length, meta.json bytes
+//! u32 file count, then per file: u32 name length, name, u64 length, bytes
+//! ```
+//!
+//! Bundles are loaded into memory; they only hold the index, not the source
+//! files, so context lines are shown only where the sources are present.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use tantivy::directory::{Directory, RamDirectory};
+use tantivy::Index;
+
+use crate::error::NsError;
+
+use super::storage::{open_index_in, IndexStorage};
+use super::writer::{read_meta, IndexMeta, SCHEMA_VERSION};
+
+const MAGIC: &[u8; 8] = b"NSBUNDLE";
+const BUNDLE_VERSION: u32 = 1;
+
+/// Summary of a written bundle.
+#[derive(Debug)]
+pub struct BundleStats {
+    pub files: usize,
+    pub bytes_written: u64,
+}
+
+/// Writes the index of the repo at `root` as a bundle to `out`.
+///
+/// Refuses indexes from another schema version, since the bundle would be
+/// unreadable by this binary. Lock files are skipped.
+pub fn write_bundle(root: &Path, out: &mut impl Write) -> Result<BundleStats, NsError> {
+    let meta = read_meta(root)?;
+    if meta.schema_version != SCHEMA_VERSION {
+        return Err(NsError::SchemaVersionMismatch {
+            found: meta.schema_version,
+            expected: SCHEMA_VERSION,
+        });
+    }
+    let meta_json = serde_json::to_vec(&meta)?;
+
+    let index_dir = root.join(".ns").join("index");
+    let mut names: Vec<String> = Vec::new();
+    for entry in fs::read_dir(&index_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_file() && !name.ends_with(".lock") {
+            names.push(name);
+        }
+    }
+    names.sort();
+
+    let mut written = 0u64;
+    let mut put = |out: &mut dyn Write, bytes: &[u8]| -> io::Result<()> {
+        written += bytes.len() as u64;
+        out.write_all(bytes)
+    };
+
+    put(out, MAGIC)?;
+    put(out, &BUNDLE_VERSION.to_le_bytes())?;
+    put(out, &(meta_json.len() as u64).to_le_bytes())?;
+    put(out, &meta_json)?;
+    put(out, &(names.len() as u32).to_le_bytes())?;
+    for name in &names {
+        let data = fs::read(index_dir.join(name))?;
+        put(out, &(name.len() as u32).to_le_bytes())?;
+        put(out, name.as_bytes())?;
+        put(out, &(data.len() as u64).to_le_bytes())?;
+        put(out, &data)?;
+    }
+
+    Ok(BundleStats {
+        files: names.len(),
+        bytes_written: written,
+    })
+}
+
+/// A bundle loaded into memory. Read-only: `create_directory` fails.
+#[derive(Debug, Clone)]
+pub struct BundleStorage {
+    dir: RamDirectory,
+}
+
+impl BundleStorage {
+    /// Reads the bundle at `path`, returning its storage and the `meta.json`
+    /// it was exported with.
+    pub fn load(path: &Path) -> Result<(BundleStorage, IndexMeta), NsError> {
+        let mut reader = io::BufReader::new(fs::File::open(path)?);
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic).map_err(|_| invalid(path))?;
+        if &magic != MAGIC {
+            return Err(invalid(path));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != BUNDLE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported bundle version {} in {}", version, path.display()),
+            )
+            .into());
+        }
+
+        let meta_len = read_u64(&mut reader)?;
+        let meta: IndexMeta = serde_json::from_slice(&read_bytes(&mut reader, meta_len)?)?;
+
+        let dir = RamDirectory::create();
+        let count = read_u32(&mut reader)?;
+        for _ in 0..count {
+            let name_len = read_u32(&mut reader)?;
+            let name = String::from_utf8(read_bytes(&mut reader, name_len as u64)?)
+                .map_err(|_| invalid(path))?;
+            if name.contains('/') || name.contains('\\') || name == ".." {
+                return Err(invalid(path));
+            }
+            let data_len = read_u64(&mut reader)?;
+            let data = read_bytes(&mut reader, data_len)?;
+            dir.atomic_write(Path::new(&name), &data)?;
+        }
+
+        Ok((BundleStorage { dir }, meta))
+    }
+}
+
+impl IndexStorage for BundleStorage {
+    fn open_directory(&self) -> Result<Box<dyn Directory>, NsError> {
+        Ok(Box::new(self.dir.clone()))
+    }
+
+    fn create_directory(&self) -> Result<Box<dyn Directory>, NsError> {
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, "index bundles are read-only").into())
+    }
+
+    fn size_bytes(&self) -> u64 {
+        self.dir.total_mem_usage() as u64
+    }
+}
+
+/// Opens the index stored in the bundle at `path`, checking its schema
+/// version like `open_index` does for `.ns/`.
+pub fn open_bundle(path: &Path) -> Result<(Index, IndexMeta), NsError> {
+    let (storage, meta) = BundleStorage::load(path)?;
+    if meta.schema_version != SCHEMA_VERSION {
+        return Err(NsError::SchemaVersionMismatch {
+            found: meta.schema_version,
+            expected: SCHEMA_VERSION,
+        });
+    }
+    let index = open_index_in(&storage)?;
+    Ok((index, meta))
+}
+
+fn invalid(path: &Path) -> NsError {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} is not an ns index bundle", path.display()),
+    )
+    .into()
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_bytes(reader: &mut impl Read, len: u64) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(len).read_to_end(&mut data)?;
+    if data.len() as u64 != len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "index bundle is truncated",
+        ));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_files_without_magic() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("not-a-bundle");
+        fs::write(&path, b"hello world, definitely not a bundle").unwrap();
+
+        let err = BundleStorage::load(&path).unwrap_err();
+        assert!(matches!(err, NsError::Io(ref e) if e.kind() == io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn rejects_truncated_bundles() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("short.nsb");
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&BUNDLE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&100u64.to_le_bytes());
+        bytes.extend_from_slice(b"{}");
+        fs::write(&path, bytes).unwrap();
+
+        assert!(BundleStorage::load(&path).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+//! Splitting large files into overlapping chunks (`ns index --chunk-lines`).
+//!
+//! BM25 normalizes a term's score by document length, so a match deep in a
+//! 5,000-line file scores like a passing mention and the file sinks below
+//! short files that say the word twice. Files over `chunk_lines` lines are
+//! indexed as several documents instead, each `chunk_lines` long and
+//! overlapping the previous one by a tenth of that, so code near a boundary
+//! is whole in at least one chunk. The chunks share the file's path; the
+//! searcher keeps the best-scoring one per file, and its `chunk_line`
+//! points match lines back into the file.
+
+/// A chunk overlaps the previous one by `chunk_lines / OVERLAP_DIVISOR`
+/// lines.
+const OVERLAP_DIVISOR: usize = 10;
+
+/// One chunk of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunk<'a> {
+    /// 0-based line of the file the chunk starts at.
+    pub first_line: usize,
+    /// 0-based line the next chunk starts at, or the file's line count for
+    /// the last one. Lines from `first_line` up to here are the chunk's own:
+    /// symbols defined on them belong to it, not to the overlapping next
+    /// chunk.
+    pub own_until: usize,
+    /// The chunk's lines, newlines included.
+    pub text: &'a str,
+}
+
+/// `content` split into chunks of `chunk_lines` lines, or a single chunk
+/// holding all of it when it is not longer than that.
+pub fn split_lines(content: &str, chunk_lines: usize) -> Vec<Chunk<'_>> {
+    let starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .filter(|&offset| offset < content.len())
+        .collect();
+    let line_count = starts.len();
+    if chunk_lines == 0 || line_count <= chunk_lines {
+        return vec![Chunk {
+            first_line: 0,
+            own_until: line_count,
+            text: content,
+        }];
+    }
+
+    let step = chunk_lines - chunk_lines / OVERLAP_DIVISOR;
+    let mut chunks = Vec::new();
+    let mut first = 0;
+    loop {
+        let last = (first + chunk_lines).min(line_count);
+        let end = starts.get(last).copied().unwrap_or(content.len());
+        let done = last == line_count;
+        chunks.push(Chunk {
+            first_line: first,
+            own_until: if done { line_count } else { first + step },
+            text: &content[starts[first]..end],
+        });
+        if done {
+            return chunks;
+        }
+        first += step;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(lines: usize) -> String {
+        (0..lines).map(|i| format!("line {}\n", i)).collect()
+    }
+
+    #[test]
+    fn short_files_are_one_chunk() {
+        let content = numbered(100);
+        let chunks = split_lines(&content, 100);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].first_line, 0);
+        assert_eq!(chunks[0].own_until, 100);
+        assert_eq!(chunks[0].text, content);
+        assert_eq!(split_lines("", 10).len(), 1);
+    }
+
+    #[test]
+    fn long_files_split_into_overlapping_chunks() {
+        let content = numbered(250);
+        let chunks = split_lines(&content, 100);
+        let firsts: Vec<usize> = chunks.iter().map(|c| c.first_line).collect();
+        assert_eq!(firsts, vec![0, 90, 180]);
+        let owns: Vec<usize> = chunks.iter().map(|c| c.own_until).collect();
+        assert_eq!(owns, vec![90, 180, 250]);
+        assert!(chunks[1].text.starts_with("line 90\n"));
+        assert!(chunks[1].text.ends_with("line 189\n"));
+        assert_eq!(chunks[1].text.lines().count(), 100);
+        assert!(chunks[2].text.ends_with("line 249\n"), "the last chunk runs to the end");
+    }
+
+    #[test]
+    fn last_line_without_newline_is_kept() {
+        let content = "a\nb\nc\nd\ne";
+        let chunks = split_lines(content, 2);
+        assert_eq!(chunks.last().unwrap().text, "e");
+        let joined: String = chunks.iter().map(|c| c.text).collect();
+        assert_eq!(joined, content, "without overlap, chunks tile the file");
+    }
+}
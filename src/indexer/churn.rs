@@ -0,0 +1,135 @@
+//! Commit frequency per file (`ns index --churn-days`).
+//!
+//! Files that change often are more likely to matter to whoever is working
+//! in the repo now. With churn enabled, every index write counts the commits
+//! touching each file over the last `days` days with one `git log`, and
+//! saves the counts to `.ns/churn.json`. The reranker reads them as a
+//! ranking signal (see `searcher::rerank`), so search never runs git.
+//!
+//! The counts describe history, not content, so they are refreshed by every
+//! full and incremental run, including incremental runs that find no
+//! changed files: a new commit moves the window either way.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::NsError;
+
+/// Name of the churn file inside `.ns/`.
+pub const CHURN_FILE: &str = "churn.json";
+
+/// Contents of `.ns/churn.json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Churn {
+    /// Length of the window counted, in days.
+    pub days: u32,
+    /// Commits touching each path (relative to the root) within the window.
+    /// Paths without commits are absent.
+    pub commits: HashMap<String, u32>,
+}
+
+impl Churn {
+    /// Churn of `path` relative to the most committed file: 0 for a file
+    /// untouched in the window, 1 for the most committed one. Log-scaled, so
+    /// one file with hundreds of commits doesn't flatten every other file
+    /// to nothing.
+    pub fn score(&self, path: &str) -> f32 {
+        let Some(&count) = self.commits.get(path) else {
+            return 0.0;
+        };
+        let max = self.commits.values().copied().max().unwrap_or(0);
+        if max == 0 {
+            return 0.0;
+        }
+        (count as f32).ln_1p() / (max as f32).ln_1p()
+    }
+}
+
+/// Path of the churn file for the repo at `root`.
+pub fn churn_path(root: &Path) -> PathBuf {
+    root.join(".ns").join(CHURN_FILE)
+}
+
+/// Reads `.ns/churn.json`; `None` when churn is off or the file is unreadable.
+pub fn read_churn(root: &Path) -> Option<Churn> {
+    let text = std::fs::read_to_string(churn_path(root)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// Counts commits per file over the last `days` days, or `None` when `root`
+/// is not in a git repository (or git is unavailable).
+pub fn compute_churn(root: &Path, days: u32) -> Option<Churn> {
+    // `--relative` limits the log to `root` and prints paths relative to it,
+    // like the indexed paths, when the index covers a subdirectory.
+    let output = Command::new("git")
+        .args([
+            "log",
+            "--relative",
+            "--no-renames",
+            "--format=",
+            "--name-only",
+        ])
+        .arg(format!("--since={} days ago", days))
+        .current_dir(root)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(Churn {
+        days,
+        commits: parse_name_only(&String::from_utf8_lossy(&output.stdout)),
+    })
+}
+
+/// Counts paths in `git log --format= --name-only` output: each commit lists
+/// every path it touched once.
+fn parse_name_only(output: &str) -> HashMap<String, u32> {
+    let mut commits: HashMap<String, u32> = HashMap::new();
+    for line in output.lines() {
+        let path = line.trim();
+        if !path.is_empty() {
+            *commits.entry(path.to_string()).or_default() += 1;
+        }
+    }
+    commits
+}
+
+/// Recomputes `.ns/churn.json` for a `days` window, or removes it when
+/// `days` is `None` or `root` is not a git repository.
+pub(crate) fn update_churn(root: &Path, days: Option<u32>) -> Result<(), NsError> {
+    let path = churn_path(root);
+    match days.and_then(|d| compute_churn(root, d)) {
+        Some(churn) => std::fs::write(&path, serde_json::to_string(&churn)?)?,
+        None => match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        },
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_commits_and_scales_against_the_busiest_file() {
+        let output = "src/a.rs\nsrc/b.rs\n\nsrc/a.rs\n\nsrc/a.rs\nREADME.md\n";
+        let churn = Churn {
+            days: 30,
+            commits: parse_name_only(output),
+        };
+        assert_eq!(churn.commits.get("src/a.rs"), Some(&3));
+        assert_eq!(churn.commits.get("src/b.rs"), Some(&1));
+        assert_eq!(churn.commits.len(), 3);
+
+        assert_eq!(churn.score("src/a.rs"), 1.0);
+        assert_eq!(churn.score("src/b.rs"), 0.5);
+        assert_eq!(churn.score("src/untouched.rs"), 0.0);
+        assert_eq!(Churn::default().score("src/a.rs"), 0.0);
+    }
+}
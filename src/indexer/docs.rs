@@ -0,0 +1,170 @@
+//! Doc comment extraction for the `docs` field.
+//!
+//! Identifiers and prose share `content`, so a query like "retry failed
+//! uploads" scores a file that says so in its documentation no higher than
+//! one whose code happens to use those words. The doc comments of each file
+//! are also indexed into `docs`, which the searcher queries with its own
+//! boost (`DOCS_BOOST`). Only documentation counts, not every comment:
+//!
+//! - Rust: `///`, `//!`, `/** */` and `/*! */`
+//! - JavaScript and TypeScript: `/** */` (JSDoc/TSDoc)
+//! - Python: docstrings, the string literal opening a module, class or
+//!   function body
+//! - Go: comments directly above a declaration or the package clause
+//! - Elixir: `@moduledoc`, `@doc` and `@typedoc` strings
+//!
+//! Comment markers are left in: the tokenizer drops them anyway.
+
+use tree_sitter::{Node, Tree};
+
+/// Go nodes a comment directly above documents.
+const GO_DECLARATIONS: &[&str] = &[
+    "package_clause",
+    "function_declaration",
+    "method_declaration",
+    "type_declaration",
+    "const_declaration",
+    "var_declaration",
+];
+
+/// The doc comments in `tree`, the parse of `source` as `lang`, in source
+/// order and newline-separated. Empty for languages without doc comments.
+pub fn doc_text(lang: &str, tree: &Tree, source: &[u8]) -> String {
+    let mut docs = Vec::new();
+    walk(lang, tree.root_node(), source, &mut docs);
+    docs.join("\n")
+}
+
+fn walk<'a>(lang: &str, node: Node, source: &'a [u8], docs: &mut Vec<&'a str>) {
+    if is_doc(lang, &node, source) {
+        if let Ok(text) = node.utf8_text(source) {
+            docs.push(text);
+        }
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        walk(lang, child, source, docs);
+    }
+}
+
+/// Whether `node` is documentation for `lang`.
+fn is_doc(lang: &str, node: &Node, source: &[u8]) -> bool {
+    let text = || node.utf8_text(source).unwrap_or("");
+    match (lang, node.kind()) {
+        ("rust", "line_comment") => {
+            let text = text();
+            (text.starts_with("///") && !text.starts_with("////")) || text.starts_with("//!")
+        }
+        ("rust", "block_comment") => {
+            let text = text();
+            (text.starts_with("/**") && !text.starts_with("/**/") && !text.starts_with("/***"))
+                || text.starts_with("/*!")
+        }
+        ("typescript" | "javascript", "comment") => {
+            let text = text();
+            text.starts_with("/**") && !text.starts_with("/**/")
+        }
+        ("python", "expression_statement") => is_docstring(node),
+        ("go", "comment") => documents_declaration(node),
+        ("elixir", "unary_operator") => is_elixir_doc(node, source),
+        _ => false,
+    }
+}
+
+/// A string statement opening a module, class or function body.
+fn is_docstring(node: &Node) -> bool {
+    let is_string = node
+        .named_child(0)
+        .is_some_and(|c| c.kind() == "string" || c.kind() == "concatenated_string");
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    let first = parent.named_child(0).is_some_and(|c| c.id() == node.id());
+    is_string && first && matches!(parent.kind(), "module" | "block")
+}
+
+/// A Go comment followed, line after line, by more comments and then a
+/// declaration.
+fn documents_declaration(node: &Node) -> bool {
+    let Some(next) = node.next_sibling() else {
+        return false;
+    };
+    if next.start_position().row != node.end_position().row + 1 {
+        return false;
+    }
+    if next.kind() == "comment" {
+        documents_declaration(&next)
+    } else {
+        GO_DECLARATIONS.contains(&next.kind())
+    }
+}
+
+/// `@moduledoc`, `@doc` or `@typedoc` with a string or sigil argument, not
+/// `@doc false`.
+fn is_elixir_doc(node: &Node, source: &[u8]) -> bool {
+    let Some(call) = node.child_by_field_name("operand") else {
+        return false;
+    };
+    if call.kind() != "call" {
+        return false;
+    }
+    let attribute = call
+        .child_by_field_name("target")
+        .and_then(|t| t.utf8_text(source).ok());
+    let documents = matches!(attribute, Some("moduledoc" | "doc" | "typedoc"));
+    let has_text = call
+        .named_children(&mut call.walk())
+        .find(|c| c.kind() == "arguments")
+        .and_then(|args| args.named_child(0))
+        .is_some_and(|arg| matches!(arg.kind(), "string" | "sigil"));
+    documents && has_text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::symbols::parse;
+
+    fn docs(lang: &str, source: &str) -> String {
+        let tree = parse(lang, source.as_bytes()).unwrap();
+        doc_text(lang, &tree, source.as_bytes())
+    }
+
+    #[test]
+    fn rust_doc_comments_only() {
+        let source = "//! Crate docs.\n\n// plain comment\n/// Appends an event.\nfn append() {}\n/** Block doc. */\nstruct S;\n/* plain block */\n";
+        assert_eq!(docs("rust", source), "//! Crate docs.\n\n/// Appends an event.\n\n/** Block doc. */");
+    }
+
+    #[test]
+    fn jsdoc_blocks() {
+        let source = "// note\n/** Fetches the user. */\nfunction getUser() {}\n/* plain */\n";
+        assert_eq!(docs("javascript", source), "/** Fetches the user. */");
+        assert_eq!(docs("typescript", source), "/** Fetches the user. */");
+    }
+
+    #[test]
+    fn python_docstrings() {
+        let source = "\"\"\"Module docs.\"\"\"\nimport os\n\ndef f():\n    \"\"\"Function docs.\"\"\"\n    x = \"not a docstring\"\n    return x\n";
+        assert_eq!(docs("python", source), "\"\"\"Module docs.\"\"\"\n\"\"\"Function docs.\"\"\"");
+    }
+
+    #[test]
+    fn go_comments_above_declarations() {
+        let source = "// Package store keeps events.\npackage store\n\n// Append adds an event\n// to the log.\nfunc Append() {\n\t// inside\n}\n\n// stray\n\nvar x = 1\n";
+        assert_eq!(
+            docs("go", source),
+            "// Package store keeps events.\n// Append adds an event\n// to the log."
+        );
+    }
+
+    #[test]
+    fn elixir_doc_attributes() {
+        let source = "defmodule Store do\n  @moduledoc \"Keeps events.\"\n  @doc \"\"\"\n  Appends one.\n  \"\"\"\n  def append(e), do: e\n  @doc false\n  def hidden, do: nil\n  @timeout 5\nend\n";
+        let text = docs("elixir", source);
+        assert!(text.contains("Keeps events."), "{text}");
+        assert!(text.contains("Appends one."), "{text}");
+        assert!(!text.contains("false") && !text.contains("timeout"), "{text}");
+    }
+}
@@ -0,0 +1,133 @@
+//! Tantivy documents for an indexed file, shared by full and incremental
+//! indexing.
+
+use tantivy::schema::Schema;
+use tantivy::TantivyDocument;
+
+use crate::schema::{
+    chunk_line_field, content_field, content_hash_field, content_ngram_field, content_stem_field,
+    docs_field, filename_field, generated_field, imports_field, is_test_field, lang_field,
+    line_starts_field, minhash_field, mtime_field, path_field, size_field, symbol_kinds_field,
+    symbol_spans_field, symbols_def_field, symbols_field, symbols_raw_field, truncated_field,
+};
+
+use super::chunks::split_lines;
+use super::docs::doc_text;
+use super::generated::is_generated;
+use super::imports::import_paths;
+use super::language::{filename_stem, is_test_path};
+use super::lines::{self, line_starts};
+use super::minhash::{self, minhash};
+use super::prune::StoredTrim;
+use super::symbols::{definition_lines, kinds_to_bytes, parse, spans_to_bytes, symbols_in, Symbol};
+use super::walker::WalkedFile;
+use super::writer::content_hash;
+
+/// Optional fields, tokenization and layout the index is built with (see
+/// `IndexMeta`).
+#[derive(Clone, Copy)]
+pub(crate) struct OptionalFields {
+    pub ngram: bool,
+    pub stem: bool,
+    pub cjk: bool,
+    /// Parse the file for symbols, doc comments and imports.
+    pub symbols: bool,
+    /// Stored fields to leave out.
+    pub trim: StoredTrim,
+    /// Split files longer than this many lines into chunks (see `chunks`).
+    pub chunk_lines: Option<usize>,
+}
+
+/// The documents for `file`: one, or one per chunk when it is longer than
+/// `fill.chunk_lines`.
+///
+/// The file is parsed once. Each chunk gets the symbols defined on its own
+/// lines; doc comments, imports and the MinHash signature describe the
+/// whole file and go with the first chunk. Every chunk carries the whole
+/// file's path, hash and metadata, so deleting the path's term removes them
+/// all and change detection sees one file.
+pub(crate) fn file_documents(
+    schema: &Schema,
+    file: &WalkedFile,
+    fill: OptionalFields,
+) -> Vec<TantivyDocument> {
+    let content = file.content.as_str();
+    let bytes = content.as_bytes();
+    let tree = file
+        .lang
+        .as_deref()
+        .filter(|_| fill.symbols)
+        .and_then(|l| Some((l, parse(l, bytes)?)));
+    let found = tree
+        .as_ref()
+        .map(|(l, tree)| symbols_in(l, tree, bytes))
+        .unwrap_or_default();
+    let hash = content_hash(bytes);
+    let generated = is_generated(&file.rel_path, content);
+
+    let chunks = split_lines(content, fill.chunk_lines.unwrap_or(0));
+    let mut documents = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        let first = chunk.first_line == 0;
+        let own: Vec<Symbol> = found
+            .iter()
+            .filter(|s| (chunk.first_line..chunk.own_until).contains(&(s.line - 1)))
+            .cloned()
+            .collect();
+        let symbol_names: Vec<&str> = own.iter().map(|s| s.name.as_str()).collect();
+
+        let mut doc = TantivyDocument::new();
+        doc.add_text(content_field(schema), chunk.text);
+        // symbols: space-separated for tokenized search
+        doc.add_text(symbols_field(schema), symbol_names.join(" "));
+        if fill.trim.keeps_symbols() {
+            // symbols_raw: pipe-separated, original casing, for display;
+            // spans and kinds in the same order
+            doc.add_text(symbols_raw_field(schema), symbol_names.join("|"));
+            doc.add_bytes(symbol_spans_field(schema), &spans_to_bytes(&own));
+            doc.add_bytes(symbol_kinds_field(schema), &kinds_to_bytes(&own));
+        }
+        if fill.trim.keeps_content() {
+            // symbols_def: definition lines, newline-separated, same order
+            doc.add_text(symbols_def_field(schema), definition_lines(&own, content));
+        }
+        // docs: doc comments, newline-separated; imports: imported
+        // modules, one per line
+        if let Some((l, tree)) = tree.as_ref().filter(|_| first) {
+            doc.add_text(docs_field(schema), doc_text(l, tree, bytes));
+            doc.add_text(imports_field(schema), import_paths(l, tree, bytes).join("\n"));
+        }
+
+        doc.add_text(path_field(schema), &file.rel_path);
+        doc.add_text(filename_field(schema), filename_stem(&file.rel_path));
+        doc.add_u64(size_field(schema), file.size);
+        doc.add_u64(mtime_field(schema), file.mtime);
+        if let Some(ref lang) = file.lang {
+            doc.add_text(lang_field(schema), lang);
+        }
+        doc.add_u64(content_hash_field(schema), hash);
+        doc.add_u64(is_test_field(schema), is_test_path(&file.rel_path) as u64);
+        doc.add_u64(truncated_field(schema), file.truncated as u64);
+        doc.add_u64(generated_field(schema), generated as u64);
+        doc.add_u64(chunk_line_field(schema), chunk.first_line as u64);
+        if fill.trim.keeps_content() {
+            if first {
+                if let Some(signature) = minhash(content) {
+                    doc.add_bytes(minhash_field(schema), &minhash::to_bytes(&signature));
+                }
+            }
+            doc.add_bytes(
+                line_starts_field(schema),
+                &lines::to_bytes(&line_starts(chunk.text, fill.cjk)),
+            );
+        }
+        if fill.ngram {
+            doc.add_text(content_ngram_field(schema), chunk.text);
+        }
+        if fill.stem {
+            doc.add_text(content_stem_field(schema), chunk.text);
+        }
+        documents.push(doc);
+    }
+    documents
+}
@@ -0,0 +1,185 @@
+//! Sentence embeddings from a local ONNX model (`ns index --embed-model`).
+//!
+//! A model directory holds `model.onnx`, a BERT-style sentence encoder such
+//! as all-MiniLM-L6-v2 exported to ONNX, and the `tokenizer.json` it was
+//! trained with. A text's embedding is the attention-weighted mean of the
+//! encoder's last hidden state, L2-normalized, so the cosine similarity of
+//! two embeddings is their dot product.
+//!
+//! Running a model needs ns built with the `semantic` cargo feature, which
+//! adds the ONNX Runtime bindings; the runtime library itself is loaded
+//! when the first model is, from `ORT_DYLIB_PATH`. Without the feature,
+//! `Embedder::load` fails with `NsError::Embedding`.
+
+use std::path::Path;
+
+use crate::error::NsError;
+
+/// ONNX encoder inside a model directory.
+#[cfg_attr(not(feature = "semantic"), allow(dead_code))]
+pub const MODEL_FILE: &str = "model.onnx";
+
+/// Hugging Face tokenizer definition inside a model directory.
+#[cfg_attr(not(feature = "semantic"), allow(dead_code))]
+pub const TOKENIZER_FILE: &str = "tokenizer.json";
+
+/// Tokens embedded per text; the rest of a longer text is ignored.
+#[cfg_attr(not(feature = "semantic"), allow(dead_code))]
+pub const MAX_TOKENS: usize = 256;
+
+/// A loaded embedding model.
+pub struct Embedder {
+    #[cfg(feature = "semantic")]
+    session: ort::session::Session,
+    #[cfg(feature = "semantic")]
+    tokenizer: tokenizers::Tokenizer,
+}
+
+#[cfg(not(feature = "semantic"))]
+impl Embedder {
+    /// Always fails: this build has no ONNX Runtime bindings.
+    pub fn load(_model_dir: &Path) -> Result<Self, NsError> {
+        Err(NsError::Embedding(
+            "ns was built without the 'semantic' feature — rebuild with `cargo install --features semantic`".to_string(),
+        ))
+    }
+
+    /// Unreachable without the feature, since `load` never succeeds.
+    pub fn embed(&mut self, _texts: &[&str]) -> Result<Vec<Vec<f32>>, NsError> {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(feature = "semantic")]
+impl Embedder {
+    /// Loads `model.onnx` and `tokenizer.json` from `model_dir`.
+    pub fn load(model_dir: &Path) -> Result<Self, NsError> {
+        use tokenizers::{PaddingParams, TruncationParams};
+
+        let tokenizer_path = model_dir.join(TOKENIZER_FILE);
+        let mut tokenizer = tokenizers::Tokenizer::from_file(&tokenizer_path)
+            .map_err(|e| failed(&tokenizer_path, e))?;
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: MAX_TOKENS,
+                ..Default::default()
+            }))
+            .map_err(|e| failed(&tokenizer_path, e))?;
+        // Pads each batch to its longest text.
+        tokenizer.with_padding(Some(PaddingParams::default()));
+
+        let model_path = model_dir.join(MODEL_FILE);
+        let session = ort::session::Session::builder()
+            .and_then(|b| b.commit_from_file(&model_path))
+            .map_err(|e| failed(&model_path, e))?;
+        Ok(Embedder { session, tokenizer })
+    }
+
+    /// Embeds `texts`, one normalized vector per text.
+    pub fn embed(&mut self, texts: &[&str]) -> Result<Vec<Vec<f32>>, NsError> {
+        use ort::value::Tensor;
+
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(|e| NsError::Embedding(e.to_string()))?;
+        let rows = encodings.len();
+        let cols = encodings[0].get_ids().len();
+        let mut ids = Vec::with_capacity(rows * cols);
+        let mut mask = Vec::with_capacity(rows * cols);
+        let mut type_ids = Vec::with_capacity(rows * cols);
+        for encoding in &encodings {
+            ids.extend(encoding.get_ids().iter().map(|&t| t as i64));
+            mask.extend(encoding.get_attention_mask().iter().map(|&m| m as i64));
+            type_ids.extend(encoding.get_type_ids().iter().map(|&t| t as i64));
+        }
+
+        let run_error = |e: ort::Error| NsError::Embedding(e.to_string());
+        let mut inputs = vec![
+            (
+                "input_ids",
+                Tensor::from_array(([rows, cols], ids)).map_err(run_error)?,
+            ),
+            (
+                "attention_mask",
+                Tensor::from_array(([rows, cols], mask.clone())).map_err(run_error)?,
+            ),
+        ];
+        // Not every exported encoder takes segment ids.
+        if self
+            .session
+            .inputs
+            .iter()
+            .any(|i| i.name == "token_type_ids")
+        {
+            inputs.push((
+                "token_type_ids",
+                Tensor::from_array(([rows, cols], type_ids)).map_err(run_error)?,
+            ));
+        }
+        let outputs = self.session.run(inputs).map_err(run_error)?;
+        let (shape, hidden) = outputs[0].try_extract_tensor::<f32>().map_err(run_error)?;
+        if shape.len() != 3 {
+            return Err(NsError::Embedding(format!(
+                "expected a [batch, tokens, dims] output, got {:?}",
+                &shape[..]
+            )));
+        }
+        let dims = shape[2] as usize;
+        Ok((0..rows)
+            .map(|row| {
+                mean_pool(
+                    &hidden[row * cols * dims..(row + 1) * cols * dims],
+                    &mask[row * cols..(row + 1) * cols],
+                    dims,
+                )
+            })
+            .collect())
+    }
+}
+
+#[cfg(feature = "semantic")]
+fn failed(path: &Path, e: impl std::fmt::Display) -> NsError {
+    NsError::Embedding(format!("{}: {}", path.display(), e))
+}
+
+/// Mean of the `dims`-wide token vectors in `hidden` whose `mask` entry is
+/// set, scaled to unit length.
+#[cfg_attr(not(feature = "semantic"), allow(dead_code))]
+fn mean_pool(hidden: &[f32], mask: &[i64], dims: usize) -> Vec<f32> {
+    let mut sum = vec![0.0f32; dims];
+    let mut tokens = 0;
+    for (token, &m) in hidden.chunks_exact(dims).zip(mask) {
+        if m == 0 {
+            continue;
+        }
+        tokens += 1;
+        for (s, v) in sum.iter_mut().zip(token) {
+            *s += v;
+        }
+    }
+    let norm = sum.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if tokens > 0 && norm > 0.0 {
+        for s in &mut sum {
+            *s /= norm;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_pool_skips_padding_and_normalizes() {
+        // Two tokens and one padding token, three dims each.
+        let hidden = [3.0, 0.0, 0.0, 1.0, 4.0, 0.0, 9.0, 9.0, 9.0];
+        let pooled = mean_pool(&hidden, &[1, 1, 0], 3);
+        assert_eq!(pooled, vec![0.70710677, 0.70710677, 0.0]);
+        assert_eq!(mean_pool(&hidden, &[0, 0, 0], 3), vec![0.0, 0.0, 0.0]);
+    }
+}
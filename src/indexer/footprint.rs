@@ -0,0 +1,170 @@
+use tantivy::schema::{Field, OwnedValue};
+use tantivy::space_usage::PerFieldSpaceUsage;
+use tantivy::store::Compressor;
+use tantivy::{Index, ReloadPolicy, TantivyDocument};
+
+use crate::error::NsError;
+
+use super::StoredCompression;
+
+/// On-disk cost of one schema field, summed over all segments.
+#[derive(Debug, Clone)]
+pub struct FieldFootprint {
+    pub name: String,
+    /// Whether the field is kept in the doc store.
+    pub stored: bool,
+    pub termdict_bytes: u64,
+    pub postings_bytes: u64,
+    pub positions_bytes: u64,
+    pub fast_fields_bytes: u64,
+    pub fieldnorms_bytes: u64,
+    /// Uncompressed size of this field's stored values. The doc store is
+    /// compressed per block, not per field, so only the raw size is attributable.
+    pub stored_raw_bytes: u64,
+}
+
+impl FieldFootprint {
+    /// Bytes used by this field's inverted index and columnar structures.
+    pub fn indexed_bytes(&self) -> u64 {
+        self.termdict_bytes
+            + self.postings_bytes
+            + self.positions_bytes
+            + self.fast_fields_bytes
+            + self.fieldnorms_bytes
+    }
+}
+
+/// Breakdown of the index's on-disk size, as reported by `ns status --detail`.
+#[derive(Debug, Clone)]
+pub struct IndexFootprint {
+    pub compression: StoredCompression,
+    pub segments: usize,
+    pub live_docs: u64,
+    pub deleted_docs: u64,
+    pub fields: Vec<FieldFootprint>,
+    /// Compressed doc store size (data blocks + offsets index).
+    pub store_bytes: u64,
+    /// Uncompressed size of all stored values.
+    pub store_raw_bytes: u64,
+    pub deletes_bytes: u64,
+    pub total_bytes: u64,
+}
+
+impl IndexFootprint {
+    /// Raw-to-compressed ratio of the doc store, or `None` when it is empty.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        if self.store_bytes == 0 {
+            None
+        } else {
+            Some(self.store_raw_bytes as f64 / self.store_bytes as f64)
+        }
+    }
+}
+
+impl StoredCompression {
+    fn from_compressor(compressor: &Compressor) -> Self {
+        match compressor {
+            Compressor::None => StoredCompression::None,
+            Compressor::Lz4 => StoredCompression::Lz4,
+            Compressor::Zstd(_) => StoredCompression::Zstd,
+        }
+    }
+}
+
+/// Measures per-field and per-component disk usage of `index`.
+///
+/// Reads every live stored document to attribute raw stored bytes to fields,
+/// so cost is proportional to index size — intended for diagnostics only.
+pub fn index_footprint(index: &Index) -> Result<IndexFootprint, NsError> {
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let schema = index.schema();
+    let usage = searcher.space_usage()?;
+
+    let mut fields: Vec<FieldFootprint> = schema
+        .fields()
+        .map(|(_, entry)| FieldFootprint {
+            name: entry.name().to_string(),
+            stored: entry.is_stored(),
+            termdict_bytes: 0,
+            postings_bytes: 0,
+            positions_bytes: 0,
+            fast_fields_bytes: 0,
+            fieldnorms_bytes: 0,
+            stored_raw_bytes: 0,
+        })
+        .collect();
+
+    let mut store_bytes = 0;
+    let mut deletes_bytes = 0;
+    for segment in usage.segments() {
+        add_usage(&mut fields, segment.termdict(), |f| &mut f.termdict_bytes);
+        add_usage(&mut fields, segment.postings(), |f| &mut f.postings_bytes);
+        add_usage(&mut fields, segment.positions(), |f| &mut f.positions_bytes);
+        add_usage(&mut fields, segment.fast_fields(), |f| &mut f.fast_fields_bytes);
+        add_usage(&mut fields, segment.fieldnorms(), |f| &mut f.fieldnorms_bytes);
+        store_bytes += segment.store().total().get_bytes();
+        deletes_bytes += segment.deletes().get_bytes();
+    }
+
+    let mut live_docs = 0;
+    let mut deleted_docs = 0;
+    for segment_reader in searcher.segment_readers() {
+        live_docs += u64::from(segment_reader.num_docs());
+        deleted_docs += u64::from(segment_reader.num_deleted_docs());
+
+        let store_reader = segment_reader.get_store_reader(1)?;
+        for doc_id in segment_reader.doc_ids_alive() {
+            let doc = store_reader.get::<TantivyDocument>(doc_id)?;
+            for (field, value) in doc.field_values() {
+                if let Some(footprint) = fields.get_mut(field_index(field)) {
+                    footprint.stored_raw_bytes += stored_value_len(&OwnedValue::from(value));
+                }
+            }
+        }
+    }
+
+    let store_raw_bytes = fields.iter().map(|f| f.stored_raw_bytes).sum();
+
+    Ok(IndexFootprint {
+        compression: StoredCompression::from_compressor(&index.settings().docstore_compression),
+        segments: usage.segments().len(),
+        live_docs,
+        deleted_docs,
+        fields,
+        store_bytes,
+        store_raw_bytes,
+        deletes_bytes,
+        total_bytes: usage.total().get_bytes(),
+    })
+}
+
+/// Adds one component's per-field usage into the matching `FieldFootprint`s.
+fn add_usage(
+    fields: &mut [FieldFootprint],
+    usage: &PerFieldSpaceUsage,
+    slot: impl Fn(&mut FieldFootprint) -> &mut u64,
+) {
+    for (field, field_usage) in usage.fields() {
+        if let Some(footprint) = fields.get_mut(field_index(*field)) {
+            *slot(footprint) += field_usage.total().get_bytes();
+        }
+    }
+}
+
+fn field_index(field: Field) -> usize {
+    field.field_id() as usize
+}
+
+/// Approximate serialized length of a stored value.
+fn stored_value_len(value: &OwnedValue) -> u64 {
+    match value {
+        OwnedValue::Str(s) => s.len() as u64,
+        OwnedValue::Bytes(b) => b.len() as u64,
+        OwnedValue::Null => 0,
+        _ => 8,
+    }
+}
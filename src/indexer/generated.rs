@@ -0,0 +1,108 @@
+//! Generated file detection for the `generated` field.
+//!
+//! A minified bundle repeats every identifier of the code it was built from,
+//! and a lockfile names every dependency, so on frontend repos they outscore
+//! the source for most queries. Files that look machine-written are flagged
+//! at index time and ranked down by `generated_weight`:
+//!
+//! - lockfiles (`package-lock.json`, `yarn.lock`, `Cargo.lock`, ...)
+//! - source maps and `.min.js` / `.min.css` bundles
+//! - files marked `@generated`, or with Go's `Code generated ... DO NOT
+//!   EDIT.` header, near the top
+//! - minified code: lines averaging over `MINIFIED_LINE_LENGTH` characters
+
+/// File names of lockfiles written by package managers.
+const LOCKFILES: &[&str] = &[
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lock",
+    "Cargo.lock",
+    "Gemfile.lock",
+    "poetry.lock",
+    "Pipfile.lock",
+    "uv.lock",
+    "composer.lock",
+    "mix.lock",
+    "go.sum",
+];
+
+/// Suffixes of bundler and minifier output.
+const GENERATED_SUFFIXES: &[&str] = &[".map", ".min.js", ".min.css", ".min.mjs"];
+
+/// How far into the file a generated-code marker counts, in bytes.
+const MARKER_WINDOW: usize = 1024;
+
+/// Average line length, in characters, above which a file counts as
+/// minified. Hand-written code stays far below it, even with long lines
+/// here and there.
+const MINIFIED_LINE_LENGTH: usize = 300;
+
+/// Files shorter than this are never judged minified: a one-line config is
+/// not a bundle.
+const MINIFIED_MIN_SIZE: usize = 2048;
+
+/// Whether the file at `path` (relative to the repo root) with `content`
+/// looks generated.
+pub fn is_generated(path: &str, content: &str) -> bool {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    if LOCKFILES.contains(&name) || GENERATED_SUFFIXES.iter().any(|s| name.ends_with(s)) {
+        return true;
+    }
+    has_marker(content) || looks_minified(content)
+}
+
+/// `@generated` or Go's `Code generated ... DO NOT EDIT.` in the first
+/// `MARKER_WINDOW` bytes.
+fn has_marker(content: &str) -> bool {
+    let mut end = content.len().min(MARKER_WINDOW);
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    let head = &content[..end];
+    head.contains("@generated")
+        || head
+            .lines()
+            .any(|line| line.contains("Code generated") && line.contains("DO NOT EDIT"))
+}
+
+fn looks_minified(content: &str) -> bool {
+    if content.len() < MINIFIED_MIN_SIZE {
+        return false;
+    }
+    let lines = content.lines().count().max(1);
+    content.len() / lines > MINIFIED_LINE_LENGTH
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockfiles_bundles_and_source_maps_by_name() {
+        assert!(is_generated("web/package-lock.json", "{}"));
+        assert!(is_generated("Cargo.lock", ""));
+        assert!(is_generated("dist/app.js.map", "{}"));
+        assert!(is_generated("static/vendor.min.js", "x"));
+        assert!(!is_generated("src/lock.rs", "fn lock() {}"));
+        assert!(!is_generated("src/map.ts", "export const m = new Map();"));
+    }
+
+    #[test]
+    fn markers_near_the_top() {
+        assert!(is_generated("api.pb.go", "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage api\n"));
+        assert!(is_generated("schema.ts", "/* @generated */\nexport type A = 1;\n"));
+        let late = format!("{}// @generated\n", "let x = 1;\n".repeat(200));
+        assert!(!is_generated("late.js", &late), "a marker deep in the file is prose");
+    }
+
+    #[test]
+    fn minified_code_by_line_length() {
+        let bundle = "var a=function(){return 1};".repeat(200);
+        assert!(is_generated("app.js", &bundle));
+        let source = "function add(a, b) {\n  return a + b;\n}\n".repeat(100);
+        assert!(!is_generated("app.js", &source));
+        assert!(!is_generated("config.json", &"x".repeat(500)), "too small to judge");
+    }
+}
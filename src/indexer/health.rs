@@ -0,0 +1,215 @@
+//! Health file for indexing runs.
+//!
+//! Git hooks start `ns index --incremental &` and the daemon re-indexes on a
+//! timer, so indexing usually happens where nobody sees its output. While a
+//! run is in progress it keeps `.ns/indexing.json` up to date with its PID and
+//! progress, and removes the file when it finishes, successfully or not. A
+//! file left behind whose process is gone means the run was killed or
+//! panicked; `ns status` reports either case.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::progress::ProgressBar;
+use super::writer::utc_timestamp_iso8601;
+
+/// Name of the health file inside `.ns/`.
+pub const HEALTH_FILE: &str = "indexing.json";
+
+/// Minimum time between two rewrites of the health file.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Contents of `.ns/indexing.json`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IndexingHealth {
+    pub pid: u32,
+    /// `full` or `incremental`.
+    pub mode: String,
+    pub started_at: String,
+    /// Last time the run reported progress.
+    pub heartbeat_at: String,
+    /// Files processed so far.
+    pub done: usize,
+    /// Files to process in this run.
+    pub total: usize,
+}
+
+impl IndexingHealth {
+    /// Progress as a whole percentage, or `None` before the file count is known.
+    pub fn percent(&self) -> Option<usize> {
+        (self.total > 0).then(|| self.done.min(self.total) * 100 / self.total)
+    }
+}
+
+/// What a health file says about the last indexing run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthState {
+    /// The process that wrote the file is still running.
+    InProgress(IndexingHealth),
+    /// The process is gone without removing the file.
+    Crashed(IndexingHealth),
+}
+
+/// Path of the health file for the repo at `root`.
+pub fn health_path(root: &Path) -> PathBuf {
+    root.join(".ns").join(HEALTH_FILE)
+}
+
+/// Reads the health file. `None` when no run is in progress and the last one
+/// exited cleanly (or the file is unreadable).
+pub fn read_health(root: &Path) -> Option<HealthState> {
+    let text = std::fs::read_to_string(health_path(root)).ok()?;
+    let health: IndexingHealth = serde_json::from_str(&text).ok()?;
+    Some(if process_alive(health.pid) {
+        HealthState::InProgress(health)
+    } else {
+        HealthState::Crashed(health)
+    })
+}
+
+/// Removes a health file left by a crashed run, once a later run has
+/// completed without needing to write anything.
+pub(crate) fn clear_crashed(root: &Path) {
+    if let Some(HealthState::Crashed(_)) = read_health(root) {
+        let _ = std::fs::remove_file(health_path(root));
+    }
+}
+
+/// Keeps the health file current for one indexing run.
+///
+/// Writes are best-effort: failing to record health never fails indexing.
+/// Dropping the guard removes the file, except while panicking, so a panic
+/// is reported as a crash like a kill is.
+pub(crate) struct HealthGuard {
+    path: PathBuf,
+    health: IndexingHealth,
+    last_write: Instant,
+    bar: Option<ProgressBar>,
+}
+
+impl HealthGuard {
+    /// Records the start of a `mode` run over `total` files.
+    pub(crate) fn start(root: &Path, mode: &str, total: usize) -> Self {
+        let now = utc_timestamp_iso8601();
+        let guard = HealthGuard {
+            path: health_path(root),
+            health: IndexingHealth {
+                pid: std::process::id(),
+                mode: mode.to_string(),
+                started_at: now.clone(),
+                heartbeat_at: now,
+                done: 0,
+                total,
+            },
+            last_write: Instant::now(),
+            bar: None,
+        };
+        guard.write();
+        guard
+    }
+
+    /// Also shows progress on a bar on stderr (`ns index --verbose`) when
+    /// `show` is set.
+    pub(crate) fn with_progress_bar(mut self, show: bool) -> Self {
+        if show {
+            self.bar = ProgressBar::for_stderr(self.health.total);
+        }
+        self
+    }
+
+    /// Records that `done` files have been processed. Rewrites the file at
+    /// most once per `HEARTBEAT_INTERVAL`, and always for the last file.
+    pub(crate) fn progress(&mut self, done: usize) {
+        self.health.done = done;
+        if let Some(bar) = &mut self.bar {
+            bar.update(done);
+        }
+        if done < self.health.total && self.last_write.elapsed() < HEARTBEAT_INTERVAL {
+            return;
+        }
+        self.health.heartbeat_at = utc_timestamp_iso8601();
+        self.last_write = Instant::now();
+        self.write();
+    }
+
+    fn write(&self) {
+        if let Ok(json) = serde_json::to_string(&self.health) {
+            let _ = std::fs::write(&self.path, json);
+        }
+    }
+}
+
+impl Drop for HealthGuard {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Whether a process with `pid` exists. PIDs can be reused, so a crashed run
+/// may rarely look alive until the next run replaces the file.
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+    // Signal 0 only checks that the process exists; EPERM means it exists
+    // but belongs to another user.
+    let alive = unsafe { libc::kill(pid, 0) } == 0;
+    alive || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guard_records_progress_and_cleans_up() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".ns")).unwrap();
+
+        let mut guard = HealthGuard::start(dir.path(), "full", 5);
+        guard.progress(5);
+        match read_health(dir.path()) {
+            Some(HealthState::InProgress(h)) => {
+                assert_eq!(h.pid, std::process::id());
+                assert_eq!(h.percent(), Some(100));
+            }
+            other => panic!("expected a run in progress, got {:?}", other),
+        }
+        drop(guard);
+        assert_eq!(read_health(dir.path()), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_left_by_exited_process_is_a_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".ns")).unwrap();
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+
+        let health = IndexingHealth {
+            pid,
+            mode: "incremental".to_string(),
+            started_at: "2026-01-01T00:00:00Z".to_string(),
+            heartbeat_at: "2026-01-01T00:00:01Z".to_string(),
+            done: 2,
+            total: 5,
+        };
+        std::fs::write(health_path(dir.path()), serde_json::to_string(&health).unwrap()).unwrap();
+        assert_eq!(read_health(dir.path()), Some(HealthState::Crashed(health)));
+    }
+}
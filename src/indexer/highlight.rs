@@ -0,0 +1,112 @@
+//! Syntax highlighting for the `ns tui` preview.
+//!
+//! Classifies the leaves of the tree-sitter parse the indexer already uses
+//! for symbols, so it covers the same languages and needs no highlight
+//! queries. Coarse on purpose: comments, strings, numbers, keywords, type
+//! names and the names of function definitions.
+
+use std::ops::Range;
+
+use tree_sitter::Node;
+
+use super::symbols::parse;
+
+/// What a highlighted byte range of source is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    Comment,
+    String,
+    Number,
+    Keyword,
+    Type,
+    Function,
+}
+
+/// Highlighted byte ranges of `source`, parsed as `lang`, in order and not
+/// overlapping; `None` when `lang` has no grammar.
+pub fn highlight(lang: &str, source: &[u8]) -> Option<Vec<(Range<usize>, Highlight)>> {
+    let tree = parse(lang, source)?;
+    let mut spans = Vec::new();
+    let mut cursor = tree.walk();
+    'walk: loop {
+        let node = cursor.node();
+        let class = classify(&node);
+        if let Some(class) = class {
+            if !node.byte_range().is_empty() {
+                spans.push((node.byte_range(), class));
+            }
+        }
+        // Highlighted nodes are taken whole.
+        if class.is_none() && cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+    Some(spans)
+}
+
+fn classify(node: &Node) -> Option<Highlight> {
+    let kind = node.kind();
+    if kind.contains("comment") {
+        return Some(Highlight::Comment);
+    }
+    if !node.is_named() {
+        // Anonymous word tokens are the grammar's keywords (`fn`, `def`, `if`).
+        let keyword = kind.len() > 1 && kind.chars().all(|c| c.is_ascii_lowercase() || c == '_');
+        return keyword.then_some(Highlight::Keyword);
+    }
+    if kind.contains("string") || kind == "char_literal" || kind == "charlist" || kind == "sigil" {
+        return Some(Highlight::String);
+    }
+    if kind.contains("integer") || kind.contains("float") || kind == "number" {
+        return Some(Highlight::Number);
+    }
+    if kind == "type_identifier" || kind == "primitive_type" {
+        return Some(Highlight::Type);
+    }
+    if kind.ends_with("identifier") {
+        let defines = node.parent().is_some_and(|parent| {
+            let parent_kind = parent.kind();
+            (parent_kind.contains("function") || parent_kind.contains("method"))
+                && parent.child_by_field_name("name") == Some(*node)
+        });
+        if defines {
+            return Some(Highlight::Function);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlights_rust_tokens() {
+        let source = "// Adds one.\npub fn bump(n: u32) -> u32 {\n    let s = \"x\";\n    n + 1\n}\n";
+        let spans = highlight("rust", source.as_bytes()).unwrap();
+        let classes: Vec<(&str, Highlight)> = spans
+            .iter()
+            .map(|(range, class)| (&source[range.clone()], *class))
+            .collect();
+        assert_eq!(
+            classes,
+            vec![
+                ("// Adds one.", Highlight::Comment),
+                ("pub", Highlight::Keyword),
+                ("fn", Highlight::Keyword),
+                ("bump", Highlight::Function),
+                ("u32", Highlight::Type),
+                ("u32", Highlight::Type),
+                ("let", Highlight::Keyword),
+                ("\"x\"", Highlight::String),
+                ("1", Highlight::Number),
+            ]
+        );
+        assert!(highlight("cobol", b"MOVE A TO B.").is_none());
+    }
+}
@@ -0,0 +1,164 @@
+//! Import extraction for the `imports` field.
+//!
+//! A file depending on `tokio` mentions it in its `use` lines, but so does
+//! every file with a comment about it. The modules a file imports are indexed
+//! into `imports`, one path per line, so `imports:tokio` finds exactly the
+//! files that depend on it. The paths are stored too, for listing what a
+//! file imports without reading it. Extracted from the same parse as the
+//! symbols:
+//!
+//! - Rust: `use` declarations and `extern crate`
+//! - JavaScript and TypeScript: `import ... from`, `export ... from`,
+//!   `require(...)` and dynamic `import(...)`
+//! - Python: `import` and `from ... import`
+//! - Go: import specs
+//! - Elixir: `alias`, `import`, `require` and `use`
+//!
+//! Paths are kept as written (`std::io::{self, Write}`, `./store`,
+//! `github.com/pkg/errors`); the `code` tokenizer splits them into words.
+
+use tree_sitter::{Node, Tree};
+
+/// The modules imported in `tree`, the parse of `source` as `lang`, in
+/// source order and deduplicated.
+pub fn import_paths(lang: &str, tree: &Tree, source: &[u8]) -> Vec<String> {
+    let mut paths = Vec::new();
+    walk(lang, tree.root_node(), source, &mut paths);
+    let mut seen = std::collections::HashSet::new();
+    paths.retain(|p| !p.is_empty() && seen.insert(p.clone()));
+    paths
+}
+
+fn walk(lang: &str, node: Node, source: &[u8], paths: &mut Vec<String>) {
+    let text = |n: Node| n.utf8_text(source).unwrap_or("").to_string();
+    match (lang, node.kind()) {
+        ("rust", "use_declaration") => {
+            if let Some(arg) = node.child_by_field_name("argument") {
+                paths.push(squeeze(&text(arg)));
+            }
+            return;
+        }
+        ("rust", "extern_crate_declaration") => {
+            if let Some(name) = node.child_by_field_name("name") {
+                paths.push(text(name));
+            }
+            return;
+        }
+        ("typescript" | "javascript", "import_statement" | "export_statement") => {
+            if let Some(from) = node.child_by_field_name("source") {
+                paths.push(unquote(&text(from)));
+            }
+        }
+        ("typescript" | "javascript", "call_expression") => {
+            let callee = node.child_by_field_name("function").map(text);
+            if matches!(callee.as_deref(), Some("require" | "import")) {
+                if let Some(arg) = first_argument(&node).filter(|a| a.kind() == "string") {
+                    paths.push(unquote(&text(arg)));
+                }
+            }
+        }
+        ("python", "import_statement") => {
+            let mut cursor = node.walk();
+            for name in node.children_by_field_name("name", &mut cursor) {
+                let name = if name.kind() == "aliased_import" {
+                    name.child_by_field_name("name").unwrap_or(name)
+                } else {
+                    name
+                };
+                paths.push(text(name));
+            }
+            return;
+        }
+        ("python", "import_from_statement") => {
+            if let Some(module) = node.child_by_field_name("module_name") {
+                paths.push(text(module));
+            }
+            return;
+        }
+        ("go", "import_spec") => {
+            if let Some(path) = node.child_by_field_name("path") {
+                paths.push(unquote(&text(path)));
+            }
+            return;
+        }
+        ("elixir", "call") => {
+            let target = node.child_by_field_name("target").map(text);
+            if matches!(target.as_deref(), Some("alias" | "import" | "require" | "use")) {
+                if let Some(arg) = first_argument(&node) {
+                    paths.push(squeeze(&text(arg)));
+                }
+                return;
+            }
+        }
+        _ => {}
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        walk(lang, child, source, paths);
+    }
+}
+
+/// First named child of a call's `arguments` node.
+fn first_argument<'t>(call: &Node<'t>) -> Option<Node<'t>> {
+    let args = call
+        .child_by_field_name("arguments")
+        .or_else(|| call.named_children(&mut call.walk()).find(|c| c.kind() == "arguments"))?;
+    args.named_child(0)
+}
+
+/// A string literal's text without its quotes.
+fn unquote(literal: &str) -> String {
+    literal.trim_matches(|c| c == '"' || c == '\'' || c == '`').to_string()
+}
+
+/// `text` with whitespace runs (a `use` list over several lines) collapsed
+/// to single spaces.
+fn squeeze(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::symbols::parse;
+
+    fn imports(lang: &str, source: &str) -> Vec<String> {
+        let tree = parse(lang, source.as_bytes()).unwrap();
+        import_paths(lang, &tree, source.as_bytes())
+    }
+
+    #[test]
+    fn rust_use_and_extern_crate() {
+        let source = "extern crate serde;\nuse std::io::{\n    self,\n    Write,\n};\nuse tokio::sync::mpsc;\nfn f() { use crate::store::EventStore; }\n";
+        assert_eq!(
+            imports("rust", source),
+            vec!["serde", "std::io::{ self, Write, }", "tokio::sync::mpsc", "crate::store::EventStore"]
+        );
+    }
+
+    #[test]
+    fn js_ts_imports_exports_and_requires() {
+        let source = "import React from 'react';\nimport { z } from \"zod\";\nexport { a } from './a';\nconst fs = require('fs');\nconst lazy = () => import('./lazy');\nfoo('not-an-import');\n";
+        let expected = vec!["react", "zod", "./a", "fs", "./lazy"];
+        assert_eq!(imports("javascript", source), expected);
+        assert_eq!(imports("typescript", source), expected);
+    }
+
+    #[test]
+    fn python_imports() {
+        let source = "import os, sys as system\nimport os.path\nfrom collections.abc import Mapping\nfrom . import sibling\n";
+        assert_eq!(imports("python", source), vec!["os", "sys", "os.path", "collections.abc", "."]);
+    }
+
+    #[test]
+    fn go_import_specs() {
+        let source = "package main\n\nimport \"fmt\"\nimport (\n\terrs \"github.com/pkg/errors\"\n\t\"net/http\"\n)\n";
+        assert_eq!(imports("go", source), vec!["fmt", "github.com/pkg/errors", "net/http"]);
+    }
+
+    #[test]
+    fn elixir_alias_import_require_use() {
+        let source = "defmodule Store do\n  use GenServer\n  alias MyApp.Repo\n  import Ecto.Query, only: [from: 2]\n  require Logger\n  def f, do: Logger.info(\"x\")\nend\n";
+        assert_eq!(imports("elixir", source), vec!["GenServer", "MyApp.Repo", "Ecto.Query", "Logger"]);
+    }
+}
@@ -1,23 +1,35 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 use std::time::Instant;
 
-use tantivy::schema::Value;
+use tantivy::collector::Count;
+use tantivy::query::TermQuery;
+use tantivy::schema::{IndexRecordOption, Value};
 use tantivy::{IndexWriter, ReloadPolicy, TantivyDocument, Term};
 
 use crate::error::NsError;
-use crate::schema::{
-    content_field, lang_field, path_field, symbols_field, symbols_raw_field,
-};
+use crate::schema::{chunk_line_field, content_hash_field, path_field};
 
+use super::churn::update_churn;
+use super::document::{file_documents, OptionalFields};
+use super::health::{clear_crashed, HealthGuard};
 use super::language::detect_language;
-use super::symbols::extract_symbols;
-use super::walker::walk_repo;
+use super::prune::{fit_index_budget, PrunedFile, StoredTrim};
+use super::storage::{FsStorage, IndexStorage};
+use super::symbol_stats::symbol_stats;
+use super::vectors::update_vectors;
+use super::walker::{
+    mtime_secs, read_text, walk_repo_reporting, walk_repo_with, FileText, SkippedFile, WalkOptions,
+    WalkedFile,
+};
 use super::writer::{
-    dir_size, get_git_commit, open_index, utc_timestamp_iso8601, IndexMeta,
-    SCHEMA_VERSION,
+    content_hash, get_git_commit, open_index, resolve_churn_days, resolve_embed_model,
+    resolve_exclude, resolve_max_depth, resolve_on_update, resolve_roots, resolve_truncate_large_kb,
+    utc_timestamp_iso8601, IndexMeta, SCHEMA_VERSION,
 };
+use super::notify::touch_updated;
+use super::IndexOptions;
 
 /// Summary of an incremental index operation.
 pub struct IncrementalStats {
@@ -25,48 +37,108 @@ pub struct IncrementalStats {
     pub modified: usize,
     pub deleted: usize,
     pub elapsed_ms: u64,
+    /// Stored fields this run had to leave out, beyond those already left
+    /// out, to fit the index size budget.
+    pub trimmed: Option<StoredTrim>,
+    /// Documents dropped to fit the index size budget.
+    pub pruned: Vec<PrunedFile>,
+    /// Relative paths of added, modified and deleted files, sorted.
+    pub added_paths: Vec<String>,
+    pub modified_paths: Vec<String>,
+    pub deleted_paths: Vec<String>,
+    /// Time spent reading, parsing and adding each re-indexed file, in
+    /// indexing order. Deletions are not timed (they are a single term delete).
+    pub file_timings: Vec<FileTiming>,
+    /// What the walk left out, with `IndexOptions::verbose`.
+    pub skipped: Vec<SkippedFile>,
 }
 
-/// Three lists of relative paths describing what changed since the last index.
+/// How long one file took to re-index.
+#[derive(Debug, Clone)]
+pub struct FileTiming {
+    pub path: String,
+    pub elapsed_us: u64,
+    /// `false` if the file was skipped (unreadable or no longer indexable).
+    pub indexed: bool,
+}
+
+/// Three lists of relative paths describing what changed since the last
+/// index, and what the walk left out when asked for.
 struct ChangeSet {
     added: Vec<String>,
     modified: Vec<String>,
     deleted: Vec<String>,
+    skipped: Vec<SkippedFile>,
 }
 
 /// Runs an incremental index update on the repository at `root`.
 ///
 /// 1. Opens the existing index and reads meta.json
-/// 2. Detects changes (git-based or mtime-based fallback)
+/// 2. Detects changes by comparing content hashes with the stored ones
 /// 3. Deletes documents for deleted/modified files
 /// 4. Re-indexes modified and added files
-/// 5. Commits and updates meta.json
+/// 5. Commits, re-applies the index size budget, and updates meta.json
 pub fn run_incremental(
     root: &Path,
-    max_file_size: u64,
+    opts: &IndexOptions,
 ) -> Result<IncrementalStats, NsError> {
+    let max_file_size = opts.max_file_size;
     let (index, meta) = open_index(root)?;
 
-    let changes = detect_changes(root, &meta, &index, max_file_size)?;
+    let truncate_large_kb = resolve_truncate_large_kb(opts, meta.truncate_large_kb);
+    let walk = WalkOptions {
+        max_file_size,
+        truncate_to: truncate_large_kb.map(|kb| kb * 1024),
+        exclude: resolve_exclude(opts, meta.exclude.clone()),
+        max_depth: resolve_max_depth(opts, meta.max_depth),
+        follow_symlinks: meta.follow_symlinks,
+        roots: resolve_roots(opts, meta.roots.clone()),
+    };
+    let mut changes = detect_changes(root, &index, &walk, opts.verbose)?;
+    // Files pruned for the size budget are not re-added as new files.
+    changes.added.retain(|p| !meta.pruned_paths.contains(p));
+    changes.added.sort();
+    changes.modified.sort();
+    changes.deleted.sort();
 
     let total_changes = changes.added.len() + changes.modified.len() + changes.deleted.len();
-    if total_changes == 0 {
+    let churn_days = resolve_churn_days(opts, meta.churn_days);
+    update_churn(root, churn_days)?;
+    let embed_model = resolve_embed_model(opts, meta.embed_model.clone());
+    // A new model has to embed every file even when none changed.
+    if total_changes == 0 && embed_model == meta.embed_model {
+        clear_crashed(root);
         return Ok(IncrementalStats {
             added: 0,
             modified: 0,
             deleted: 0,
             elapsed_ms: 0,
+            trimmed: None,
+            pruned: Vec::new(),
+            added_paths: Vec::new(),
+            modified_paths: Vec::new(),
+            deleted_paths: Vec::new(),
+            file_timings: Vec::new(),
+            skipped: changes.skipped,
         });
     }
 
     let schema = index.schema();
-    let content_f = content_field(&schema);
-    let symbols_f = symbols_field(&schema);
-    let symbols_raw_f = symbols_raw_field(&schema);
     let path_f = path_field(&schema);
-    let lang_f = lang_field(&schema);
+    // n-grams and stems are all or nothing: a partially filled field would
+    // make `--substring` / `--stem` silently miss files.
+    let fill = OptionalFields {
+        ngram: meta.ngram,
+        stem: meta.stem,
+        cjk: meta.cjk,
+        symbols: !meta.no_symbols,
+        trim: meta.stored_trim,
+        chunk_lines: meta.chunk_lines,
+    };
 
-    let mut writer: IndexWriter = index.writer(50_000_000)?;
+    let mut writer: IndexWriter = opts.writer(&index)?;
+    let mut health =
+        HealthGuard::start(root, "incremental", total_changes).with_progress_bar(opts.verbose);
 
     let start = Instant::now();
 
@@ -75,19 +147,32 @@ pub fn run_incremental(
         writer.delete_term(Term::from_field_text(path_f, rel_path));
     }
 
+    let mut file_timings = Vec::with_capacity(changes.modified.len() + changes.added.len());
+    let mut reindex = |rel_path: &String| -> Result<(), NsError> {
+        let file_start = Instant::now();
+        let file = read_file(root, rel_path, &walk);
+        let indexed = file.is_some();
+        for doc in file.iter().flat_map(|file| file_documents(&schema, file, fill)) {
+            writer.add_document(doc)?;
+        }
+        file_timings.push(FileTiming {
+            path: rel_path.clone(),
+            elapsed_us: file_start.elapsed().as_micros() as u64,
+            indexed,
+        });
+        health.progress(changes.deleted.len() + file_timings.len());
+        Ok(())
+    };
+
     // Delete then re-index modified files
     for rel_path in &changes.modified {
         writer.delete_term(Term::from_field_text(path_f, rel_path));
-        if let Some(doc) = build_document(root, rel_path, content_f, symbols_f, symbols_raw_f, path_f, lang_f) {
-            writer.add_document(doc)?;
-        }
+        reindex(rel_path)?;
     }
 
     // Index added files
     for rel_path in &changes.added {
-        if let Some(doc) = build_document(root, rel_path, content_f, symbols_f, symbols_raw_f, path_f, lang_f) {
-            writer.add_document(doc)?;
-        }
+        reindex(rel_path)?;
     }
 
     writer.commit()?;
@@ -98,19 +183,54 @@ pub fn run_incremental(
         .wait_merging_threads()
         .map_err(|e| crate::error::NsError::Tantivy(e))?;
 
+    let max_index_size = opts.max_index_size.or(meta.max_index_size);
+    let (stored_trim, pruned) = match max_index_size {
+        Some(budget) => fit_index_budget(root, &index, opts, budget, meta.stored_trim, |writer, trim| {
+            let fill = OptionalFields { trim, ..fill };
+            for rel_path in get_indexed_paths(&index)? {
+                writer.delete_term(Term::from_field_text(path_f, &rel_path));
+                let file = read_file(root, &rel_path, &walk);
+                for doc in file.iter().flat_map(|file| file_documents(&schema, file, fill)) {
+                    writer.add_document(doc)?;
+                }
+            }
+            Ok(())
+        })?,
+        None => (meta.stored_trim, Vec::new()),
+    };
+
+    update_vectors(root, &index, embed_model.as_deref())?;
+
     let elapsed_ms = start.elapsed().as_millis() as u64;
 
-    // Count total documents in the index after commit
+    // Count files in the index after commit: chunked files have several
+    // documents, but only one starting at line 0.
     let reader = index
         .reader_builder()
         .reload_policy(ReloadPolicy::Manual)
         .try_into()?;
     let searcher = reader.searcher();
-    let file_count = searcher.num_docs() as usize;
+    let first_chunks = TermQuery::new(
+        Term::from_field_u64(chunk_line_field(&schema), 0),
+        IndexRecordOption::Basic,
+    );
+    let file_count = searcher.search(&first_chunks, &Count)?;
 
     // Calculate index size
-    let index_dir = root.join(".ns").join("index");
-    let index_size = dir_size(&index_dir);
+    let index_size = FsStorage::for_root(root).size_bytes();
+
+    // Previously pruned paths stay pruned unless they were re-indexed or deleted.
+    let mut pruned_paths: Vec<String> = meta
+        .pruned_paths
+        .iter()
+        .filter(|p| !changes.modified.contains(p) && !changes.deleted.contains(p))
+        .cloned()
+        .collect();
+    for file in &pruned {
+        if !pruned_paths.contains(&file.path) {
+            pruned_paths.push(file.path.clone());
+        }
+    }
 
     // Update meta.json
     let git_commit = get_git_commit(root);
@@ -120,24 +240,55 @@ pub fn run_incremental(
         git_commit,
         file_count,
         index_size_bytes: index_size,
+        max_index_size,
+        pruned_paths,
+        ngram: meta.ngram,
+        stem: meta.stem,
+        cjk: meta.cjk,
+        on_update: resolve_on_update(opts, meta.on_update.clone()),
+        symbol_stats: Some(symbol_stats(&index)?),
+        churn_days,
+        embed_model,
+        exclude: walk.exclude,
+        max_depth: walk.max_depth,
+        follow_symlinks: walk.follow_symlinks,
+        roots: walk.roots,
+        truncate_large_kb,
+        no_symbols: meta.no_symbols,
+        stored_trim,
+        chunk_lines: meta.chunk_lines,
     };
 
     let meta_path = root.join(".ns").join("meta.json");
     let meta_json = serde_json::to_string(&new_meta)?;
     fs::write(&meta_path, &meta_json)?;
+    touch_updated(root, &new_meta.indexed_at)?;
 
     let stats = IncrementalStats {
         added: changes.added.len(),
         modified: changes.modified.len(),
         deleted: changes.deleted.len(),
         elapsed_ms,
+        trimmed: (stored_trim != meta.stored_trim).then_some(stored_trim),
+        pruned,
+        added_paths: changes.added,
+        modified_paths: changes.modified,
+        deleted_paths: changes.deleted,
+        file_timings,
+        skipped: changes.skipped,
     };
 
     Ok(stats)
 }
 
 /// Reads the set of all file paths currently in the tantivy index.
-fn get_indexed_paths(index: &tantivy::Index) -> Result<HashSet<String>, NsError> {
+pub(crate) fn get_indexed_paths(index: &tantivy::Index) -> Result<HashSet<String>, NsError> {
+    Ok(get_indexed_hashes(index)?.into_keys().collect())
+}
+
+/// Reads the path and stored `content_hash` of every live document in the
+/// tantivy index.
+fn get_indexed_hashes(index: &tantivy::Index) -> Result<HashMap<String, Option<u64>>, NsError> {
     let reader = index
         .reader_builder()
         .reload_policy(ReloadPolicy::Manual)
@@ -145,464 +296,88 @@ fn get_indexed_paths(index: &tantivy::Index) -> Result<HashSet<String>, NsError>
     let searcher = reader.searcher();
     let schema = index.schema();
     let path_f = path_field(&schema);
+    let hash_f = content_hash_field(&schema);
 
-    let mut paths = HashSet::new();
+    let mut hashes = HashMap::new();
     for segment_reader in searcher.segment_readers() {
         let store_reader = segment_reader.get_store_reader(1)?;
-        for doc_id in 0..segment_reader.num_docs() {
+        // doc_ids_alive() skips deleted-but-unmerged documents, whose ids
+        // would otherwise shadow live ones in the 0..num_docs range.
+        for doc_id in segment_reader.doc_ids_alive() {
             if let Ok(doc) = store_reader.get::<TantivyDocument>(doc_id) {
-                if let Some(val) = doc.get_first(path_f) {
-                    if let Some(path_str) = val.as_str() {
-                        paths.insert(path_str.to_string());
-                    }
+                if let Some(path_str) = doc.get_first(path_f).and_then(|v| v.as_str()) {
+                    let hash = doc.get_first(hash_f).and_then(|v| v.as_u64());
+                    hashes.insert(path_str.to_string(), hash);
                 }
             }
         }
     }
-    Ok(paths)
+    Ok(hashes)
 }
 
-/// Detects changes since the last index using git diff (preferred) or mtime fallback.
+/// Detects changes since the last index by content: every indexable file
+/// is hashed (`writer::content_hash`, xxh3) and compared with the hash
+/// stored in its document. Unlike mtimes, hashes catch edits made within
+/// the same second as the last index and ignore touches that change
+/// nothing; unlike `git diff`, they work outside git. Files `walk` now
+/// excludes count as deleted. With `report`, skipped files are listed too.
 fn detect_changes(
     root: &Path,
-    meta: &IndexMeta,
     index: &tantivy::Index,
-    max_file_size: u64,
-) -> Result<ChangeSet, NsError> {
-    // Try git-based detection first
-    if let Some(ref old_commit) = meta.git_commit {
-        if let Some(current_commit) = get_git_commit(root) {
-            let indexed_paths = get_indexed_paths(index)?;
-            if *old_commit == current_commit {
-                // Same commit — check for uncommitted changes via working tree diff
-                return detect_changes_git_uncommitted(
-                    root, max_file_size, &indexed_paths, &meta.indexed_at,
-                );
-            }
-            return detect_changes_git(
-                root, old_commit, &current_commit, max_file_size, &indexed_paths, &meta.indexed_at,
-            );
-        }
-    }
-
-    // Fallback: mtime-based detection
-    detect_changes_mtime(root, meta, index, max_file_size)
-}
-
-/// Detects changes using `git diff --name-status` between two commits,
-/// plus any uncommitted working tree changes.
-fn detect_changes_git(
-    root: &Path,
-    old_commit: &str,
-    current_commit: &str,
-    max_file_size: u64,
-    indexed_paths: &HashSet<String>,
-    indexed_at: &str,
+    walk: &WalkOptions,
+    report: bool,
 ) -> Result<ChangeSet, NsError> {
-    // Get committed changes between old and current commit
-    let mut changes = parse_git_diff(root, old_commit, current_commit)?;
-
-    // Also check for uncommitted working tree changes (staged + unstaged)
-    let working_changes =
-        detect_changes_git_uncommitted(root, max_file_size, indexed_paths, indexed_at)?;
-
-    // Merge working tree changes into committed changes
-    merge_changesets(&mut changes, working_changes);
-
-    // Filter: only include files that would actually be walked (exist, not binary, etc.)
-    filter_changeset(root, &mut changes, max_file_size);
-
-    Ok(changes)
-}
-
-/// Detects uncommitted changes (both staged and unstaged) against HEAD.
-///
-/// `indexed_paths` is the set of file paths already in the tantivy index.
-/// Untracked files already in the index are skipped (or classified as modified
-/// if their mtime is newer than `indexed_at`), preventing duplicate document
-/// insertion on repeated incremental runs.
-fn detect_changes_git_uncommitted(
-    root: &Path,
-    max_file_size: u64,
-    indexed_paths: &HashSet<String>,
-    indexed_at: &str,
-) -> Result<ChangeSet, NsError> {
-    // git diff --name-status HEAD (working tree vs HEAD, includes staged)
-    let output = std::process::Command::new("git")
-        .args(["diff", "--name-status", "HEAD"])
-        .current_dir(root)
-        .output()
-        .map_err(|e| NsError::Io(e))?;
-
-    if !output.status.success() {
-        // If git diff fails (e.g., initial commit with no HEAD), return empty
-        return Ok(ChangeSet {
-            added: Vec::new(),
-            modified: Vec::new(),
-            deleted: Vec::new(),
-        });
-    }
-
-    let mut changes = parse_name_status_output(&String::from_utf8_lossy(&output.stdout));
-
-    // Also check for untracked files
-    let untracked_output = std::process::Command::new("git")
-        .args(["ls-files", "--others", "--exclude-standard"])
-        .current_dir(root)
-        .output()
-        .map_err(|e| NsError::Io(e))?;
-
-    if untracked_output.status.success() {
-        let indexed_time = parse_iso8601_to_system_time(indexed_at);
-        let untracked = String::from_utf8_lossy(&untracked_output.stdout);
-        for line in untracked.lines() {
-            let path = line.trim();
-            if path.is_empty() || changes.added.contains(&path.to_string()) {
-                continue;
-            }
-            if indexed_paths.contains(path) {
-                // Already in the index — check if it was modified since last index
-                if let Some(ref idx_time) = indexed_time {
-                    let abs_path = root.join(path);
-                    if let Ok(file_meta) = abs_path.metadata() {
-                        if let Ok(mtime) = file_meta.modified() {
-                            if mtime > *idx_time {
-                                changes.modified.push(path.to_string());
-                            }
-                        }
-                    }
-                }
-                // If mtime is not newer, skip — already indexed and up to date
-            } else {
-                // Not in index — genuinely new file
-                changes.added.push(path.to_string());
-            }
-        }
-    }
-
-    filter_changeset(root, &mut changes, max_file_size);
-
-    Ok(changes)
-}
-
-/// Parses `git diff --name-status` output between two refs.
-fn parse_git_diff(
-    root: &Path,
-    old_ref: &str,
-    new_ref: &str,
-) -> Result<ChangeSet, NsError> {
-    let output = std::process::Command::new("git")
-        .args(["diff", "--name-status", old_ref, new_ref])
-        .current_dir(root)
-        .output()
-        .map_err(|e| NsError::Io(e))?;
-
-    if !output.status.success() {
-        return Ok(ChangeSet {
-            added: Vec::new(),
-            modified: Vec::new(),
-            deleted: Vec::new(),
-        });
-    }
-
-    Ok(parse_name_status_output(&String::from_utf8_lossy(&output.stdout)))
-}
-
-/// Parses the output of `git diff --name-status` into a ChangeSet.
-///
-/// Format: `<status>\t<path>` per line
-/// Status codes: A = added, M = modified, D = deleted, R = renamed (old\tnew)
-fn parse_name_status_output(output: &str) -> ChangeSet {
-    let mut added = Vec::new();
-    let mut modified = Vec::new();
-    let mut deleted = Vec::new();
-
-    for line in output.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-
-        let parts: Vec<&str> = line.splitn(3, '\t').collect();
-        if parts.len() < 2 {
-            continue;
-        }
-
-        let status = parts[0];
-        let path = parts[1].to_string();
-
-        match status.chars().next() {
-            Some('A') => added.push(path),
-            Some('M') => modified.push(path),
-            Some('D') => deleted.push(path),
-            Some('R') => {
-                // Renamed: old path is deleted, new path is added
-                deleted.push(path);
-                if parts.len() >= 3 {
-                    added.push(parts[2].to_string());
-                }
-            }
-            _ => {
-                // Other statuses (C = copied, T = type change) — treat as modified
-                modified.push(path);
-            }
-        }
-    }
-
-    ChangeSet { added, modified, deleted }
-}
-
-/// Merges `other` into `base`, deduplicating paths.
-fn merge_changesets(base: &mut ChangeSet, other: ChangeSet) {
-    let existing: HashSet<String> = base
-        .added
-        .iter()
-        .chain(base.modified.iter())
-        .chain(base.deleted.iter())
-        .cloned()
-        .collect();
-
-    for path in other.added {
-        if !existing.contains(&path) {
-            base.added.push(path);
-        }
-    }
-    for path in other.modified {
-        if !existing.contains(&path) {
-            base.modified.push(path);
-        }
-    }
-    for path in other.deleted {
-        if !existing.contains(&path) {
-            base.deleted.push(path);
-        }
-    }
-}
-
-/// Filters a changeset to remove paths that shouldn't be indexed
-/// (e.g., .ns/ directory, .git/, files that no longer exist for added/modified).
-fn filter_changeset(root: &Path, changes: &mut ChangeSet, max_file_size: u64) {
-    let should_skip = |path: &str| -> bool {
-        path.starts_with(".ns/")
-            || path.starts_with(".ns\\")
-            || path.starts_with(".git/")
-            || path.starts_with(".git\\")
-            || path == ".ns"
-            || path == ".git"
-    };
-
-    // For added/modified files: must exist and be indexable
-    let is_indexable = |rel_path: &str| -> bool {
-        if should_skip(rel_path) {
-            return false;
-        }
-        let abs_path = root.join(rel_path);
-        if !abs_path.is_file() {
-            return false;
-        }
-        // Check file size
-        if let Ok(meta) = abs_path.metadata() {
-            if meta.len() > max_file_size {
-                return false;
-            }
-        }
-        // Check for binary content (null bytes in first 512 bytes)
-        if let Ok(raw) = fs::read(&abs_path) {
-            let check_len = raw.len().min(512);
-            if raw[..check_len].contains(&0) {
-                return false;
-            }
-            // UTF-8 check
-            if String::from_utf8(raw).is_err() {
-                return false;
-            }
-        } else {
-            return false;
-        }
-        true
+    let indexed = get_indexed_hashes(index)?;
+    let (files, skipped) = if report {
+        walk_repo_reporting(root, walk)
+    } else {
+        (walk_repo_with(root, walk), Vec::new())
     };
 
-    changes.added.retain(|p| is_indexable(p));
-    changes.modified.retain(|p| is_indexable(p));
-    changes.deleted.retain(|p| !should_skip(p));
-}
-
-/// Detects changes using file mtime comparison against `meta.indexed_at`.
-///
-/// Used when git is not available or git_commit is not set.
-fn detect_changes_mtime(
-    root: &Path,
-    meta: &IndexMeta,
-    index: &tantivy::Index,
-    max_file_size: u64,
-) -> Result<ChangeSet, NsError> {
-    let indexed_at = parse_iso8601_to_system_time(&meta.indexed_at);
-
-    // Walk all current files
-    let current_files = walk_repo(root, max_file_size);
-    let current_paths: HashSet<String> = current_files
-        .iter()
-        .map(|f| f.rel_path.clone())
-        .collect();
-
-    let indexed_paths = get_indexed_paths(index)?;
-
     let mut added = Vec::new();
     let mut modified = Vec::new();
-    let mut deleted = Vec::new();
-
-    // Files in current walk but not in index → added
-    // Files in both → check mtime for modified
-    for file in &current_files {
-        if !indexed_paths.contains(&file.rel_path) {
-            added.push(file.rel_path.clone());
-        } else if let Some(ref indexed_time) = indexed_at {
-            let abs_path = root.join(&file.rel_path);
-            if let Ok(file_meta) = abs_path.metadata() {
-                if let Ok(mtime) = file_meta.modified() {
-                    if mtime > *indexed_time {
-                        modified.push(file.rel_path.clone());
-                    }
-                }
+    let mut current_paths = HashSet::new();
+    for file in files {
+        match indexed.get(&file.rel_path) {
+            None => added.push(file.rel_path.clone()),
+            Some(&stored) if stored != Some(content_hash(file.content.as_bytes())) => {
+                modified.push(file.rel_path.clone())
             }
+            Some(_) => {}
         }
+        current_paths.insert(file.rel_path);
     }
 
     // Files in index but not in current walk → deleted
-    for path in &indexed_paths {
-        if !current_paths.contains(path) {
-            deleted.push(path.clone());
-        }
-    }
+    let deleted = indexed
+        .into_keys()
+        .filter(|path| !current_paths.contains(path))
+        .collect();
 
-    Ok(ChangeSet { added, modified, deleted })
+    Ok(ChangeSet {
+        added,
+        modified,
+        deleted,
+        skipped,
+    })
 }
 
-/// Builds a tantivy document for a single file.
-///
-/// Returns `None` if the file cannot be read or is not indexable.
-fn build_document(
-    root: &Path,
-    rel_path: &str,
-    content_f: tantivy::schema::Field,
-    symbols_f: tantivy::schema::Field,
-    symbols_raw_f: tantivy::schema::Field,
-    path_f: tantivy::schema::Field,
-    lang_f: tantivy::schema::Field,
-) -> Option<TantivyDocument> {
+/// Reads the file at `rel_path` as `walk` reads it, so an oversized file
+/// gets the same head the walk hashed. `None` if it cannot be read or is
+/// not indexable.
+fn read_file(root: &Path, rel_path: &str, walk: &WalkOptions) -> Option<WalkedFile> {
     let abs_path = root.join(rel_path);
-    let content = fs::read_to_string(&abs_path).ok()?;
-    let lang = detect_language(&abs_path).map(|s| s.to_string());
-
-    let symbol_names = lang
-        .as_deref()
-        .map(|l| extract_symbols(l, content.as_bytes()))
-        .unwrap_or_default();
-
-    let mut doc = TantivyDocument::new();
-    doc.add_text(content_f, &content);
-    doc.add_text(symbols_f, &symbol_names.join(" "));
-    doc.add_text(symbols_raw_f, &symbol_names.join("|"));
-    doc.add_text(path_f, rel_path);
-    if let Some(ref lang_str) = lang {
-        doc.add_text(lang_f, lang_str);
-    }
-
-    Some(doc)
-}
-
-/// Parses an ISO 8601 timestamp string to SystemTime.
-fn parse_iso8601_to_system_time(s: &str) -> Option<std::time::SystemTime> {
-    // Parse format: "2025-02-11T14:30:00Z"
-    let parts: Vec<&str> = s.split('T').collect();
-    if parts.len() != 2 {
+    let metadata = fs::metadata(&abs_path).ok()?;
+    let FileText::Text { content, truncated } = read_text(&abs_path, metadata.len(), walk).ok()?
+    else {
         return None;
-    }
-    let date_parts: Vec<u64> = parts[0].split('-').filter_map(|p| p.parse().ok()).collect();
-    let time_str = parts[1].trim_end_matches('Z');
-    let time_parts: Vec<u64> = time_str.split(':').filter_map(|p| p.parse().ok()).collect();
-
-    if date_parts.len() != 3 || time_parts.len() != 3 {
-        return None;
-    }
-
-    let (year, month, day) = (date_parts[0], date_parts[1], date_parts[2]);
-    let (hour, min, sec) = (time_parts[0], time_parts[1], time_parts[2]);
-
-    // Convert to days since epoch using civil calendar algorithm
-    // Ref: http://howardhinnant.github.io/date_algorithms.html
-    let y = if month <= 2 { year - 1 } else { year };
-    let era = y / 400;
-    let yoe = y - era * 400;
-    let m = if month > 2 { month - 3 } else { month + 9 };
-    let doy = (153 * m + 2) / 5 + day - 1;
-    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
-    let days = era * 146097 + doe - 719468;
-
-    let total_secs = days * 86400 + hour * 3600 + min * 60 + sec;
-    let duration = std::time::Duration::from_secs(total_secs);
-    Some(std::time::UNIX_EPOCH + duration)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn parse_name_status_added() {
-        let output = "A\tsrc/new_file.rs\n";
-        let changes = parse_name_status_output(output);
-        assert_eq!(changes.added, vec!["src/new_file.rs"]);
-        assert!(changes.modified.is_empty());
-        assert!(changes.deleted.is_empty());
-    }
-
-    #[test]
-    fn parse_name_status_modified() {
-        let output = "M\tsrc/existing.rs\n";
-        let changes = parse_name_status_output(output);
-        assert!(changes.added.is_empty());
-        assert_eq!(changes.modified, vec!["src/existing.rs"]);
-        assert!(changes.deleted.is_empty());
-    }
-
-    #[test]
-    fn parse_name_status_deleted() {
-        let output = "D\tsrc/old_file.rs\n";
-        let changes = parse_name_status_output(output);
-        assert!(changes.added.is_empty());
-        assert!(changes.modified.is_empty());
-        assert_eq!(changes.deleted, vec!["src/old_file.rs"]);
-    }
-
-    #[test]
-    fn parse_name_status_renamed() {
-        let output = "R100\tsrc/old.rs\tsrc/new.rs\n";
-        let changes = parse_name_status_output(output);
-        assert_eq!(changes.added, vec!["src/new.rs"]);
-        assert!(changes.modified.is_empty());
-        assert_eq!(changes.deleted, vec!["src/old.rs"]);
-    }
-
-    #[test]
-    fn parse_name_status_mixed() {
-        let output = "A\tsrc/added.rs\nM\tsrc/modified.rs\nD\tsrc/deleted.rs\n";
-        let changes = parse_name_status_output(output);
-        assert_eq!(changes.added, vec!["src/added.rs"]);
-        assert_eq!(changes.modified, vec!["src/modified.rs"]);
-        assert_eq!(changes.deleted, vec!["src/deleted.rs"]);
-    }
-
-    #[test]
-    fn parse_iso8601_roundtrip() {
-        let ts = "2025-02-11T14:30:00Z";
-        let sys_time = parse_iso8601_to_system_time(ts);
-        assert!(sys_time.is_some(), "should parse valid ISO 8601 timestamp");
-    }
-
-    #[test]
-    fn parse_iso8601_invalid() {
-        assert!(parse_iso8601_to_system_time("not-a-date").is_none());
-        assert!(parse_iso8601_to_system_time("").is_none());
-    }
+    };
+    Some(WalkedFile {
+        rel_path: rel_path.to_string(),
+        lang: detect_language(&abs_path).map(|s| s.to_string()),
+        content,
+        size: metadata.len(),
+        truncated,
+        mtime: mtime_secs(&metadata),
+    })
 }
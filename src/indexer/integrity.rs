@@ -0,0 +1,205 @@
+//! Index integrity check for `ns index --check`.
+//!
+//! `ns doctor --verify-content` asks whether the index still matches the
+//! working tree; this asks whether the index is sound at all. A disk that
+//! filled up mid-commit, a sync tool that skipped a file or a copy of
+//! `.ns/` taken while a hook was writing can leave segments that tantivy
+//! lists but cannot read, and a search then fails with an error that
+//! doesn't say what to do. The check reads `meta.json`, makes sure every
+//! file of every listed segment exists and passes its checksum, compares
+//! the documents with `meta.json`'s file count, and lists documents whose
+//! file has been deleted since the last index.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use tantivy::directory::Directory;
+use tantivy::schema::Value;
+use tantivy::{Index, ReloadPolicy, TantivyDocument};
+
+use crate::error::NsError;
+use crate::schema::{chunk_line_field, path_field};
+
+use super::storage::{open_index_in, FsStorage};
+use super::writer::{read_meta, IndexMeta, SCHEMA_VERSION};
+
+/// Result of `check_index`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// Why `meta.json` can't be used, when it can't.
+    pub meta_error: Option<String>,
+    /// `meta.json`'s file count.
+    pub meta_file_count: Option<usize>,
+    /// Why the index or its documents couldn't be read, when they couldn't.
+    pub read_error: Option<String>,
+    /// Segments listed by the index.
+    pub segments: usize,
+    /// Files of listed segments that don't exist.
+    pub missing_files: Vec<String>,
+    /// Files of listed segments whose checksum doesn't match.
+    pub corrupted_files: Vec<String>,
+    /// Files with a live document, when the documents could be read.
+    pub indexed_files: Option<usize>,
+    /// Indexed paths whose file no longer exists, sorted.
+    pub orphaned: Vec<String>,
+}
+
+impl IntegrityReport {
+    /// Whether the index is damaged or out of step with `meta.json`, so
+    /// only a full rebuild fixes it.
+    pub fn needs_rebuild(&self) -> bool {
+        self.meta_error.is_some()
+            || self.read_error.is_some()
+            || !self.missing_files.is_empty()
+            || !self.corrupted_files.is_empty()
+            || self.count_mismatch()
+    }
+
+    /// Whether the live documents disagree with `meta.json`'s file count.
+    pub fn count_mismatch(&self) -> bool {
+        matches!(
+            (self.meta_file_count, self.indexed_files),
+            (Some(meta), Some(indexed)) if meta != indexed
+        )
+    }
+
+    /// Whether nothing is wrong.
+    pub fn is_ok(&self) -> bool {
+        !self.needs_rebuild() && self.orphaned.is_empty()
+    }
+}
+
+/// Checks the index at `root`. Problems go into the report; the error case
+/// is only a missing index (`NsError::Io` with `NotFound`).
+pub fn check_index(root: &Path) -> Result<IntegrityReport, NsError> {
+    let mut report = IntegrityReport::default();
+    match read_meta(root) {
+        Ok(meta) => check_meta(&meta, &mut report),
+        Err(NsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(NsError::Io(e))
+        }
+        Err(err) => report.meta_error = Some(format!("cannot read meta.json: {}", err)),
+    }
+
+    let index = match open_index_in(&FsStorage::for_root(root)) {
+        Ok(index) => index,
+        Err(err) => {
+            report.read_error = Some(format!("cannot open index: {}", err));
+            return Ok(report);
+        }
+    };
+    if let Err(err) = check_segments(&index, &mut report) {
+        report.read_error = Some(format!("cannot list segments: {}", err));
+        return Ok(report);
+    }
+    if !report.missing_files.is_empty() || !report.corrupted_files.is_empty() {
+        return Ok(report);
+    }
+    match live_paths(&index) {
+        Ok((paths, files)) => {
+            report.indexed_files = Some(files);
+            report.orphaned = paths
+                .into_iter()
+                .filter(|path| !root.join(path).exists())
+                .collect();
+        }
+        Err(err) => report.read_error = Some(format!("cannot read documents: {}", err)),
+    }
+    Ok(report)
+}
+
+fn check_meta(meta: &IndexMeta, report: &mut IntegrityReport) {
+    if meta.schema_version != SCHEMA_VERSION {
+        report.meta_error = Some(format!(
+            "schema version {} (this ns uses {})",
+            meta.schema_version, SCHEMA_VERSION
+        ));
+    } else {
+        report.meta_file_count = Some(meta.file_count);
+    }
+}
+
+/// Fills in the segment count and the missing and corrupted segment files.
+fn check_segments(index: &Index, report: &mut IntegrityReport) -> Result<(), NsError> {
+    let segments = index.searchable_segment_metas()?;
+    report.segments = segments.len();
+    for segment in &segments {
+        let mut files: Vec<_> = segment.list_files().into_iter().collect();
+        // Every segment lists a delete file; only segments with deletes have one.
+        if !segment.has_deletes() {
+            files.retain(|f| f.extension().is_none_or(|ext| ext != "del"));
+        }
+        for file in files {
+            if !index.directory().exists(&file).unwrap_or(false) {
+                report.missing_files.push(file.display().to_string());
+            }
+        }
+    }
+    report.missing_files.sort();
+    if report.missing_files.is_empty() {
+        let mut corrupted: Vec<String> = index
+            .validate_checksum()?
+            .into_iter()
+            .map(|f| f.display().to_string())
+            .collect();
+        corrupted.sort();
+        report.corrupted_files = corrupted;
+    }
+    Ok(())
+}
+
+/// Paths with a live document, and the number of files they make up.
+/// Reading every stored document also catches a store that is damaged
+/// past what its checksum covers.
+fn live_paths(index: &Index) -> Result<(BTreeSet<String>, usize), NsError> {
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let schema = index.schema();
+    let path_f = path_field(&schema);
+    let chunk_f = chunk_line_field(&schema);
+
+    let mut paths = BTreeSet::new();
+    let mut files = 0;
+    for segment_reader in searcher.segment_readers() {
+        let store_reader = segment_reader.get_store_reader(1)?;
+        for doc_id in segment_reader.doc_ids_alive() {
+            let doc = store_reader.get::<TantivyDocument>(doc_id)?;
+            // Chunks of one file share its path; the first starts at line 0.
+            if doc.get_first(chunk_f).and_then(|v| v.as_u64()).unwrap_or(0) == 0 {
+                files += 1;
+            }
+            if let Some(path) = doc.get_first(path_f).and_then(|v| v.as_str()) {
+                paths.insert(path.to_string());
+            }
+        }
+    }
+    Ok((paths, files))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orphans_alone_do_not_need_a_rebuild() {
+        let report = IntegrityReport {
+            meta_file_count: Some(3),
+            indexed_files: Some(3),
+            orphaned: vec!["src/gone.rs".to_string()],
+            ..IntegrityReport::default()
+        };
+        assert!(!report.needs_rebuild());
+        assert!(!report.is_ok());
+        let mismatch = IntegrityReport {
+            meta_file_count: Some(3),
+            indexed_files: Some(2),
+            ..IntegrityReport::default()
+        };
+        assert!(mismatch.count_mismatch());
+        assert!(mismatch.needs_rebuild());
+        assert!(IntegrityReport::default().is_ok());
+    }
+}
@@ -1,5 +1,8 @@
 use std::path::Path;
 
+/// Every language identifier `detect_language` can return.
+pub const LANGUAGES: &[&str] = &["rust", "python", "go", "javascript", "typescript", "elixir"];
+
 /// Maps a file extension to a language identifier.
 /// Returns `None` for unsupported languages (content-only indexing, no symbols).
 pub fn detect_language(path: &Path) -> Option<&'static str> {
@@ -14,6 +17,37 @@ pub fn detect_language(path: &Path) -> Option<&'static str> {
     }
 }
 
+/// Directory names whose contents are tests.
+const TEST_DIRS: &[&str] = &["test", "tests", "__tests__", "spec", "specs", "testdata"];
+
+/// Whether `path` (relative to the repo root) looks like a test file: under a
+/// test directory, or named like `server_test.go`, `test_api.py`,
+/// `button.test.tsx` or `user.spec.js`.
+pub fn is_test_path(path: &str) -> bool {
+    let mut parts: Vec<&str> = path.split(['/', '\\']).collect();
+    let name = parts.pop().unwrap_or("");
+    if parts.iter().any(|dir| TEST_DIRS.contains(dir)) {
+        return true;
+    }
+    let stem = name.split('.').next().unwrap_or(name);
+    stem.ends_with("_test")
+        || stem.ends_with("_spec")
+        || stem.starts_with("test_")
+        || name.contains(".test.")
+        || name.contains(".spec.")
+}
+
+/// The file name of `path` without its last extension, as indexed in the
+/// `filename` field: `rule_validator` for `src/rule_validator.py`. Dotfiles
+/// and names without an extension are kept whole.
+pub fn filename_stem(path: &str) -> &str {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    match name.rfind('.') {
+        Some(dot) if dot > 0 => &name[..dot],
+        _ => name,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -40,6 +74,27 @@ mod tests {
         assert_eq!(detect_language(Path::new(".gitignore")), None);
     }
 
+    #[test]
+    fn test_paths() {
+        assert!(is_test_path("tests/integration_search.rs"));
+        assert!(is_test_path("src/__tests__/button.tsx"));
+        assert!(is_test_path("pkg/server/server_test.go"));
+        assert!(is_test_path("app/test_api.py"));
+        assert!(is_test_path("web/button.test.tsx"));
+        assert!(is_test_path("lib/user.spec.js"));
+        assert!(!is_test_path("src/testing.rs"));
+        assert!(!is_test_path("src/latest.rs"));
+        assert!(!is_test_path("src/contest/main.go"));
+    }
+
+    #[test]
+    fn filename_stems() {
+        assert_eq!(filename_stem("src/rule_validator.py"), "rule_validator");
+        assert_eq!(filename_stem("web/button.test.tsx"), "button.test");
+        assert_eq!(filename_stem("Makefile"), "Makefile");
+        assert_eq!(filename_stem("config/.eslintrc"), ".eslintrc");
+    }
+
     #[test]
     fn no_extension() {
         assert_eq!(detect_language(&PathBuf::from("Makefile")), None);
@@ -0,0 +1,133 @@
+//! Token positions of line starts, for mapping term matches to lines.
+//!
+//! `content` is indexed with positions, so the postings already say where in
+//! a file each term occurs, but as token positions, not lines. Each document
+//! stores the position its every line starts at in the `line_starts` field,
+//! and the searcher turns a term's positions into line numbers by binary
+//! search (`line_of`). The matching lines then agree with the tokenizer —
+//! `store` matches `EventStore` but not `restore` — instead of with a
+//! substring scan of the file.
+//!
+//! The field holds one unsigned LEB128 varint per line: the number of token
+//! positions the previous line took up, so most lines cost one byte.
+
+use tantivy::tokenizer::{TokenStream, Tokenizer};
+
+use super::tokenizer::CodeTokenizer;
+
+/// Token position at which each line of `content` starts, as the `content`
+/// field's tokenizer (`code`, or `code_cjk` with `cjk`) numbers them. A line
+/// without tokens starts where the next one does.
+pub fn line_starts(content: &str, cjk: bool) -> Vec<u32> {
+    let mut tokenizer = if cjk {
+        CodeTokenizer::code_cjk()
+    } else {
+        CodeTokenizer::code()
+    };
+    let line_offsets: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .filter(|&offset| offset < content.len())
+        .collect();
+
+    let mut starts = Vec::with_capacity(line_offsets.len());
+    let mut stream = tokenizer.token_stream(content);
+    let mut end = 0;
+    while stream.advance() {
+        let token = stream.token();
+        while starts.len() < line_offsets.len() && line_offsets[starts.len()] <= token.offset_from
+        {
+            starts.push(token.position as u32);
+        }
+        end = end.max(token.position + token.position_length);
+    }
+    starts.resize(line_offsets.len(), end as u32);
+    starts
+}
+
+/// 0-based line of the token at `position`, given the file's `line_starts`.
+pub fn line_of(starts: &[u32], position: u32) -> usize {
+    starts.partition_point(|&s| s <= position).saturating_sub(1)
+}
+
+/// Encodes `starts` for the `line_starts` field.
+pub fn to_bytes(starts: &[u32]) -> Vec<u8> {
+    let mut previous = 0;
+    to_varints(starts.iter().map(|&start| {
+        let delta = start - previous;
+        previous = start;
+        delta
+    }))
+}
+
+/// Decodes a `line_starts` field; `None` if it is truncated.
+pub fn from_bytes(bytes: &[u8]) -> Option<Vec<u32>> {
+    let mut current = 0u32;
+    from_varints(bytes)?
+        .into_iter()
+        .map(|delta| {
+            current = current.checked_add(delta)?;
+            Some(current)
+        })
+        .collect()
+}
+
+/// `values` as unsigned LEB128 varints.
+pub(crate) fn to_varints(values: impl IntoIterator<Item = u32>) -> Vec<u8> {
+    let mut out = Vec::new();
+    for mut value in values {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte);
+                break;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+    out
+}
+
+/// Decodes unsigned LEB128 varints; `None` if the last one is truncated or
+/// one overflows.
+pub(crate) fn from_varints(bytes: &[u8]) -> Option<Vec<u32>> {
+    let mut values = Vec::with_capacity(bytes.len());
+    let mut value = 0u32;
+    let mut shift = 0;
+    for &byte in bytes {
+        value |= ((byte & 0x7f) as u32).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            values.push(value);
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 7;
+        }
+    }
+    (shift == 0).then_some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_starts_follow_token_positions() {
+        // EventStore takes two positions (event, store); the blank line
+        // starts where the next line does.
+        let content = "let EventStore = 1;\n\nfn restore() {}\n";
+        let starts = line_starts(content, false);
+        assert_eq!(starts, vec![0, 4, 4]);
+        assert_eq!(line_of(&starts, 0), 0);
+        assert_eq!(line_of(&starts, 2), 0);
+        assert_eq!(line_of(&starts, 4), 2);
+        assert_eq!(line_starts("", false), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn line_starts_round_trip() {
+        let starts = vec![0, 3, 3, 200, 70_000];
+        assert_eq!(from_bytes(&to_bytes(&starts)), Some(starts));
+        assert_eq!(from_bytes(&[0x80]), None, "truncated varint");
+    }
+}
@@ -0,0 +1,173 @@
+use std::path::Path;
+
+use serde::Serialize;
+use tantivy::schema::Value;
+use tantivy::{Index, ReloadPolicy, TantivyDocument};
+
+use crate::error::NsError;
+use crate::schema::{lang_field, path_field, size_field, symbols_raw_field};
+
+use super::writer::open_index;
+
+/// One document in the index, live or tombstoned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedDoc {
+    pub path: String,
+    pub lang: Option<String>,
+    /// Short id of the segment holding the document.
+    pub segment: String,
+    /// Deleted but not yet merged away: still on disk, never returned by search.
+    pub deleted: bool,
+}
+
+/// Document counts for one segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentDocs {
+    pub segment: String,
+    pub live: u32,
+    pub deleted: u32,
+}
+
+/// Contents of the index, as reported by `ns index --list-docs`.
+#[derive(Debug, Clone)]
+pub struct DocListing {
+    /// Documents whose path starts with the requested prefix, sorted by path
+    /// with live documents before tombstones of the same path.
+    pub docs: Vec<IndexedDoc>,
+    /// Per-segment counts over the whole index, regardless of prefix.
+    pub segments: Vec<SegmentDocs>,
+}
+
+/// Lists every stored document in `index`, including deleted documents that
+/// are still waiting for a merge. Useful to debug incremental updates — e.g.
+/// a deleted file that still shows up in search means its path is live here.
+pub fn list_docs(index: &Index, path_prefix: Option<&str>) -> Result<DocListing, NsError> {
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let schema = index.schema();
+    let path_f = path_field(&schema);
+    let lang_f = lang_field(&schema);
+
+    let mut docs = Vec::new();
+    let mut segments = Vec::new();
+    for segment_reader in searcher.segment_readers() {
+        let segment = segment_reader.segment_id().short_uuid_string();
+        segments.push(SegmentDocs {
+            segment: segment.clone(),
+            live: segment_reader.num_docs(),
+            deleted: segment_reader.num_deleted_docs(),
+        });
+
+        let alive = segment_reader.alive_bitset();
+        let store_reader = segment_reader.get_store_reader(1)?;
+        for doc_id in 0..segment_reader.max_doc() {
+            let doc = store_reader.get::<TantivyDocument>(doc_id)?;
+            let path = doc
+                .get_first(path_f)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+            if path_prefix.is_some_and(|prefix| !path.starts_with(prefix)) {
+                continue;
+            }
+            let lang = doc
+                .get_first(lang_f)
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string());
+            docs.push(IndexedDoc {
+                path,
+                lang,
+                segment: segment.clone(),
+                deleted: alive.is_some_and(|bits| bits.is_deleted(doc_id)),
+            });
+        }
+    }
+
+    docs.sort_by(|a, b| a.path.cmp(&b.path).then_with(|| a.deleted.cmp(&b.deleted)));
+    Ok(DocListing { docs, segments })
+}
+
+/// Filters for `list_files`.
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    /// Language filter (`-t`).
+    pub file_type: Option<String>,
+    /// Path glob filter (`-g`).
+    pub file_glob: Option<String>,
+}
+
+/// One indexed file, as listed by `ns ls`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ListedFile {
+    pub path: String,
+    pub lang: Option<String>,
+    /// File size in bytes when indexed.
+    pub size: u64,
+    /// Symbols defined in the file; `None` when the index doesn't know
+    /// (`--no-symbols`, or raw symbols trimmed to fit the index budget).
+    pub symbols: Option<usize>,
+}
+
+/// Lists the live files in the index at `root` that pass `opts`, sorted by
+/// path. Chunks of a file (`ns index --chunk-lines`) are listed once, with
+/// their symbols added up.
+pub fn list_files(root: &Path, opts: &ListOptions) -> Result<Vec<ListedFile>, NsError> {
+    let (index, meta) = open_index(root)?;
+    let counts_symbols = !meta.no_symbols && meta.stored_trim.keeps_symbols();
+    let glob = match opts.file_glob {
+        Some(ref g) => Some(glob::Pattern::new(g)?),
+        None => None,
+    };
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let schema = index.schema();
+    let path_f = path_field(&schema);
+    let lang_f = lang_field(&schema);
+    let size_f = size_field(&schema);
+    let symbols_raw_f = symbols_raw_field(&schema);
+
+    let mut files = Vec::new();
+    for segment_reader in searcher.segment_readers() {
+        let store_reader = segment_reader.get_store_reader(1)?;
+        for doc_id in segment_reader.doc_ids_alive() {
+            let doc = store_reader.get::<TantivyDocument>(doc_id)?;
+            let Some(path) = doc.get_first(path_f).and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let lang = doc.get_first(lang_f).and_then(|v| v.as_str());
+            if opts.file_type.as_deref().is_some_and(|t| lang != Some(t))
+                || glob.as_ref().is_some_and(|g| !g.matches(path))
+            {
+                continue;
+            }
+            let symbols = doc
+                .get_first(symbols_raw_f)
+                .and_then(|v| v.as_str())
+                .map_or(0, |s| s.split('|').filter(|s| !s.is_empty()).count());
+            files.push(ListedFile {
+                path: path.to_string(),
+                lang: lang.map(|s| s.to_string()),
+                size: doc.get_first(size_f).and_then(|v| v.as_u64()).unwrap_or(0),
+                symbols: counts_symbols.then_some(symbols),
+            });
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files.dedup_by(|chunk, file| {
+        if chunk.path != file.path {
+            return false;
+        }
+        if let (Some(total), Some(more)) = (file.symbols.as_mut(), chunk.symbols) {
+            *total += more;
+        }
+        true
+    });
+    Ok(files)
+}
@@ -0,0 +1,114 @@
+//! MinHash signatures for near-duplicate detection.
+//!
+//! Exact copies are caught at search time by hashing file bytes
+//! (`searcher::dedupe`), but vendored libraries drift by a version comment
+//! and generated files by a timestamp, and those copies still crowd a page
+//! of results. Each document stores a MinHash signature of its word
+//! shingles in the `minhash` field; the share of equal slots in two
+//! signatures estimates the Jaccard similarity of the files' shingle sets.
+//!
+//! Shingles are runs of `SHINGLE_WORDS` consecutive words (letters, digits
+//! and `_`), so whitespace, punctuation and formatting don't count.
+
+use xxhash_rust::xxh3::xxh3_64;
+
+/// Slots per signature. The similarity estimate's standard error is about
+/// `1 / sqrt(MINHASH_SLOTS)`; 64 slots cost 256 stored bytes per file.
+pub const MINHASH_SLOTS: usize = 64;
+
+/// Words per shingle.
+const SHINGLE_WORDS: usize = 4;
+
+/// MinHash signature of `content`, or `None` when it has no words.
+pub fn minhash(content: &str) -> Option<Vec<u32>> {
+    let words: Vec<&str> = content
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    let mut signature = vec![u32::MAX; MINHASH_SLOTS];
+    for shingle in words.windows(SHINGLE_WORDS.min(words.len())) {
+        let base = xxh3_64(shingle.join(" ").as_bytes());
+        for (slot, min) in signature.iter_mut().enumerate() {
+            let h = slot_hash(base, slot as u64);
+            if h < *min {
+                *min = h;
+            }
+        }
+    }
+    Some(signature)
+}
+
+/// The `slot`-th hash of a shingle hashed to `base` (splitmix64 finalizer
+/// over a per-slot offset), so one xxh3 per shingle feeds every slot.
+fn slot_hash(base: u64, slot: u64) -> u32 {
+    let mut z = base.wrapping_add(slot.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (z ^ (z >> 31)) as u32
+}
+
+/// Estimated Jaccard similarity (0..=1) of the files behind two signatures.
+pub fn similarity(a: &[u32], b: &[u32]) -> f32 {
+    if a.is_empty() || a.len() != b.len() {
+        return 0.0;
+    }
+    let equal = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    equal as f32 / a.len() as f32
+}
+
+/// Encodes a signature for the `minhash` bytes field.
+pub fn to_bytes(signature: &[u32]) -> Vec<u8> {
+    signature.iter().flat_map(|h| h.to_le_bytes()).collect()
+}
+
+/// Decodes a stored signature; `None` if it is not whole slots.
+pub fn from_bytes(bytes: &[u8]) -> Option<Vec<u32>> {
+    if bytes.is_empty() || !bytes.len().is_multiple_of(4) {
+        return None;
+    }
+    Some(
+        bytes
+            .chunks_exact(4)
+            .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(n: usize, variant: &str) -> String {
+        (0..n)
+            .map(|i| format!("let value_{} = compute({}, \"{}\");\n", i, i * 7, variant))
+            .collect()
+    }
+
+    #[test]
+    fn similar_files_have_similar_signatures() {
+        let original = lines(200, "a");
+        let mut edited = original.clone();
+        edited.push_str("// vendored copy, v2.1\n");
+        let reformatted = original.replace(" = ", "=").replace(", ", ",");
+        let other: String = (0..200)
+            .map(|i| format!("fn handler_{}() -> Result<()> {{ Ok(()) }}\n", i))
+            .collect();
+
+        let sig = minhash(&original).unwrap();
+        assert_eq!(similarity(&sig, &minhash(&reformatted).unwrap()), 1.0);
+        assert!(similarity(&sig, &minhash(&edited).unwrap()) > 0.9);
+        assert!(similarity(&sig, &minhash(&other).unwrap()) < 0.5);
+        assert_eq!(minhash(" \n{}\n"), None);
+    }
+
+    #[test]
+    fn signatures_round_trip_through_bytes() {
+        let sig = minhash("fn main() { println!(\"hi\"); }").unwrap();
+        assert_eq!(sig.len(), MINHASH_SLOTS);
+        assert_eq!(from_bytes(&to_bytes(&sig)), Some(sig));
+        assert_eq!(from_bytes(&[1, 2, 3]), None);
+    }
+}
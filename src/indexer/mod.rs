@@ -1,36 +1,270 @@
+pub mod bundle;
+pub mod chunks;
+pub mod churn;
+pub mod docs;
+pub mod document;
+pub mod embed;
+pub mod footprint;
+pub mod generated;
+pub mod health;
+pub mod highlight;
+pub mod imports;
 pub mod incremental;
+pub mod integrity;
 pub mod language;
+pub mod listing;
+pub mod lines;
+pub mod minhash;
+pub mod notify;
+pub mod optimize;
+pub mod outline;
+pub mod progress;
+pub mod prune;
+pub mod storage;
+pub mod symbol_stats;
 pub mod symbols;
+pub mod tokenizer;
+pub mod vectors;
+pub mod verify;
 pub mod walker;
 pub mod writer;
 
 use std::path::Path;
+use std::str::FromStr;
+
+use tantivy::store::{Compressor, ZstdCompressor};
+use tantivy::{Index, IndexWriter};
 
 use crate::error::NsError;
 use incremental::{run_incremental, IncrementalStats};
-use walker::walk_repo;
-use writer::{build_index, FullIndexStats};
+use walker::{walk_repo_reporting, walk_repo_with, WalkOptions};
+use writer::{
+    build_index, resolve_exclude, resolve_max_depth, resolve_roots, resolve_truncate_large_kb,
+    FullIndexStats,
+};
+
+/// Default `IndexOptions::memory_mb`.
+pub const DEFAULT_WRITER_MEMORY_MB: u64 = 50;
+
+/// Smallest writer heap tantivy accepts per indexing thread, in MB.
+pub const MIN_MEMORY_MB_PER_THREAD: u64 = 15;
+
+/// Largest writer heap tantivy accepts per indexing thread, in MB (its
+/// arenas are addressed with 32 bits).
+pub const MAX_MEMORY_MB_PER_THREAD: u64 = 4095;
+
+/// Largest `IndexOptions::memory_mb` `ns index` accepts: the per-thread
+/// limit on as many threads as tantivy picks at most.
+pub const MAX_MEMORY_MB: u64 = MAX_MEMORY_MB_PER_THREAD * 8;
+
+/// Options that control indexing behaviour — maps 1:1 to `ns index` flags.
+#[derive(Debug, Clone)]
+pub struct IndexOptions {
+    /// Files larger than this many bytes are skipped by the walker, or
+    /// truncated with `truncate_large_kb`.
+    pub max_file_size: u64,
+    /// Index the first this many KB of files over `max_file_size` instead
+    /// of skipping them (see `walker::WalkOptions`). `None` keeps the size
+    /// remembered in `meta.json`; `Some(0)` skips them again.
+    pub truncate_large_kb: Option<u64>,
+    /// On-disk budget for `.ns/index/` in bytes. When exceeded, the lowest-value
+    /// documents are pruned (see `prune::plan_pruning`). None means unlimited.
+    pub max_index_size: Option<u64>,
+    /// Compression applied to stored fields (`path`, `lang`, `symbols_raw`).
+    pub compression: StoredCompression,
+    /// Fill the `content_ngram` trigram field so `--substring` can pre-filter
+    /// by index. Roughly triples index size; kept by incremental runs.
+    pub ngram: bool,
+    /// Fill the Porter-stemmed `content_stem` field used by `--stem`.
+    /// Kept by incremental runs.
+    pub stem: bool,
+    /// Tokenize `content` with `code_cjk`, splitting Chinese/Japanese/Korean
+    /// text into bigrams. Full rebuilds only; incremental runs keep the
+    /// tokenizer the index was built with.
+    pub cjk: bool,
+    /// Shell command the CLI runs after every index write (see `notify`).
+    /// `None` keeps the one remembered in `meta.json`; an empty string clears it.
+    pub on_update: Option<String>,
+    /// Count commits per file over this many days for the churn ranking
+    /// signal (see `churn`). `None` keeps the window remembered in
+    /// `meta.json`; `Some(0)` turns churn off.
+    pub churn_days: Option<u32>,
+    /// Directory of the embedding model for `--semantic` (see `vectors`).
+    /// `None` keeps the model remembered in `meta.json`; an empty string
+    /// turns embeddings off.
+    pub embed_model: Option<String>,
+    /// Globs of root-relative paths to leave out (see `walker::WalkOptions`).
+    /// Empty keeps the globs remembered in `meta.json`; `[""]` clears them.
+    pub exclude: Vec<String>,
+    /// Directory levels to walk below the root. `None` keeps the depth
+    /// remembered in `meta.json`; `Some(0)` removes the limit.
+    pub max_depth: Option<usize>,
+    /// Skip tree-sitter: no symbols, doc comments or imports, for a quick
+    /// content-only first pass over a huge repo. Full rebuilds only;
+    /// incremental runs keep the mode the index was built with.
+    pub no_symbols: bool,
+    /// Follow symlinks while walking (see `walker::WalkOptions`). Full
+    /// rebuilds only; incremental runs keep the setting the index was built
+    /// with.
+    pub follow_symlinks: bool,
+    /// Index files longer than this many lines as overlapping chunks (see
+    /// `chunks`). `None` keeps the length remembered in `meta.json`;
+    /// `Some(0)` indexes every file whole again. Full rebuilds only;
+    /// incremental runs keep the length the index was built with.
+    pub chunk_lines: Option<usize>,
+    /// Show a progress bar on stderr and list skipped files in the stats
+    /// (`FullIndexStats::skipped`, `IncrementalStats::skipped`).
+    pub verbose: bool,
+    /// Directories below the root to index instead of all of it (see
+    /// `walker::WalkOptions`). Empty keeps the ones remembered in `meta.json`;
+    /// an empty string among them indexes the whole tree again.
+    pub roots: Vec<String>,
+    /// Heap shared by the index writer's threads, in MB. More memory means
+    /// fewer, larger segments and less merging on big repos.
+    pub memory_mb: u64,
+    /// Indexing threads; `None` lets tantivy pick from the core count and
+    /// `memory_mb` (at least `MIN_MEMORY_MB_PER_THREAD` each), with enough
+    /// threads to keep each under `MAX_MEMORY_MB_PER_THREAD`.
+    pub threads: Option<usize>,
+}
+
+impl Default for IndexOptions {
+    fn default() -> Self {
+        Self {
+            max_file_size: 1_048_576,
+            truncate_large_kb: None,
+            max_index_size: None,
+            compression: StoredCompression::default(),
+            ngram: false,
+            stem: false,
+            cjk: false,
+            on_update: None,
+            churn_days: None,
+            embed_model: None,
+            exclude: Vec::new(),
+            max_depth: None,
+            no_symbols: false,
+            follow_symlinks: false,
+            chunk_lines: None,
+            verbose: false,
+            roots: Vec::new(),
+            memory_mb: DEFAULT_WRITER_MEMORY_MB,
+            threads: None,
+        }
+    }
+}
+
+impl IndexOptions {
+    /// Opens a writer on `index` with the configured heap and threads.
+    pub(crate) fn writer(&self, index: &Index) -> Result<IndexWriter, NsError> {
+        let budget = self
+            .memory_mb
+            .checked_mul(1_000_000)
+            .and_then(|bytes| usize::try_from(bytes).ok())
+            .ok_or_else(|| {
+                tantivy::TantivyError::InvalidArgument(format!("writer heap of {} MB is too large", self.memory_mb))
+            })?;
+        Ok(match self.threads {
+            Some(threads) => index.writer_with_num_threads(threads, budget)?,
+            None if self.memory_mb > MAX_MEMORY_MB_PER_THREAD => {
+                let threads = self.memory_mb.div_ceil(MAX_MEMORY_MB_PER_THREAD) as usize;
+                index.writer_with_num_threads(threads, budget)?
+            }
+            None => index.writer(budget)?,
+        })
+    }
+}
+
+/// Doc store compression for stored fields, selected with `ns index --compression`.
+///
+/// `Lz4` is tantivy's default and the fastest to decompress at search time.
+/// `Zstd` trades some decompression speed for a smaller doc store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StoredCompression {
+    None,
+    #[default]
+    Lz4,
+    Zstd,
+}
+
+impl StoredCompression {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StoredCompression::None => "none",
+            StoredCompression::Lz4 => "lz4",
+            StoredCompression::Zstd => "zstd",
+        }
+    }
+
+    /// Returns the tantivy doc store compressor for this setting.
+    pub fn compressor(&self) -> Compressor {
+        match self {
+            StoredCompression::None => Compressor::None,
+            StoredCompression::Lz4 => Compressor::Lz4,
+            StoredCompression::Zstd => Compressor::Zstd(ZstdCompressor::default()),
+        }
+    }
+}
+
+impl FromStr for StoredCompression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(StoredCompression::None),
+            "lz4" => Ok(StoredCompression::Lz4),
+            "zstd" => Ok(StoredCompression::Zstd),
+            other => Err(format!(
+                "unknown compression '{}': use none, lz4 or zstd",
+                other
+            )),
+        }
+    }
+}
 
 /// Runs a full (non-incremental) index of the repository at `root`.
 ///
 /// Returns `None` if no indexable files were found, or `Some(stats)` on success.
 /// Does not print to stderr — the CLI layer handles all output.
-pub fn run_full_index(root: &Path, max_file_size: u64) -> Result<Option<FullIndexStats>, NsError> {
-    let files = walk_repo(root, max_file_size);
+pub fn run_full_index(root: &Path, opts: &IndexOptions) -> Result<Option<FullIndexStats>, NsError> {
+    let previous = writer::read_meta(root).ok();
+    let previous_roots = previous.as_ref().map(|m| m.roots.clone()).unwrap_or_default();
+    let truncate_large_kb =
+        resolve_truncate_large_kb(opts, previous.as_ref().and_then(|m| m.truncate_large_kb));
+    let walk = WalkOptions {
+        max_file_size: opts.max_file_size,
+        truncate_to: truncate_large_kb.map(|kb| kb * 1024),
+        exclude: resolve_exclude(
+            opts,
+            previous.as_ref().map(|m| m.exclude.clone()).unwrap_or_default(),
+        ),
+        max_depth: resolve_max_depth(opts, previous.and_then(|m| m.max_depth)),
+        follow_symlinks: opts.follow_symlinks,
+        roots: resolve_roots(opts, previous_roots),
+    };
+    let (files, skipped) = if opts.verbose {
+        walk_repo_reporting(root, &walk)
+    } else {
+        (walk_repo_with(root, &walk), Vec::new())
+    };
     if files.is_empty() {
         return Ok(None);
     }
-    build_index(root, &files).map(Some)
+    let mut stats = build_index(root, &files, opts)?;
+    stats.skipped = skipped;
+    Ok(Some(stats))
 }
 
 /// Runs an incremental index update on the repository at `root`.
 ///
 /// Requires an existing index (created by `run_full_index`).
-/// Detects changes via git diff (preferred) or mtime fallback,
-/// then applies adds/modifies/deletes to the existing index.
+/// Detects changes by hashing every indexable file and comparing it with
+/// the stored `content_hash`, then applies adds/modifies/deletes to the existing index.
+/// Options not given explicitly (e.g. `max_index_size`) fall back to the
+/// values persisted in `meta.json` by the last full index.
 pub fn run_incremental_index(
     root: &Path,
-    max_file_size: u64,
+    opts: &IndexOptions,
 ) -> Result<IncrementalStats, NsError> {
-    run_incremental(root, max_file_size)
+    run_incremental(root, opts)
 }
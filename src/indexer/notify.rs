@@ -0,0 +1,64 @@
+//! Notifications that the index changed.
+//!
+//! Every index write (a full build, or an incremental run that found changes)
+//! ends by rewriting `.ns/updated` with the new `indexed_at` timestamp, so
+//! editors, daemons and long-running searchers can watch one small file to
+//! invalidate caches instead of polling `meta.json`. Optionally, the CLI also
+//! runs the `--on-update` command remembered in `meta.json`.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+/// Name of the notification file inside `.ns/`.
+pub const UPDATED_FILE: &str = "updated";
+
+/// Path of the notification file for the repo at `root`.
+pub fn updated_path(root: &Path) -> PathBuf {
+    root.join(".ns").join(UPDATED_FILE)
+}
+
+/// Rewrites `.ns/updated` with `indexed_at`, bumping its mtime.
+pub fn touch_updated(root: &Path, indexed_at: &str) -> io::Result<()> {
+    std::fs::write(updated_path(root), format!("{}\n", indexed_at))
+}
+
+/// Runs `command` through the shell in `root`, waiting for it to finish.
+///
+/// The command sees `NS_ROOT` (the repo root) and `NS_UPDATED` (the
+/// notification file). Use `&` in the command to run it in the background.
+pub fn run_on_update(root: &Path, command: &str) -> io::Result<ExitStatus> {
+    #[cfg(unix)]
+    let mut cmd = {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+    #[cfg(not(unix))]
+    let mut cmd = {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    };
+    cmd.current_dir(root)
+        .env("NS_ROOT", root)
+        .env("NS_UPDATED", updated_path(root))
+        .status()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn on_update_command_sees_repo_env() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".ns")).unwrap();
+        touch_updated(dir.path(), "2026-01-01T00:00:00Z").unwrap();
+
+        let status = run_on_update(dir.path(), "cat \"$NS_UPDATED\" > seen.txt").unwrap();
+        assert!(status.success());
+        let seen = std::fs::read_to_string(dir.path().join("seen.txt")).unwrap();
+        assert_eq!(seen, "2026-01-01T00:00:00Z\n");
+    }
+}
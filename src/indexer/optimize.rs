@@ -0,0 +1,85 @@
+//! Segment compaction for `ns index --optimize`.
+//!
+//! Every incremental run commits a new segment, and a modified or deleted
+//! file leaves its old document behind as a tombstone until the segment
+//! holding it is merged. Tantivy's merge policy only merges segments of
+//! similar size, so a long-lived index kept fresh by hooks or `ns watch`
+//! piles up small segments and dead documents, and every search pays for
+//! each segment it opens. Optimizing merges all segments into one, which
+//! drops the tombstones, then deletes the files nothing refers to anymore.
+
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use tantivy::{Index, IndexWriter, ReloadPolicy};
+
+use crate::error::NsError;
+
+use super::storage::{FsStorage, IndexStorage};
+use super::writer::{open_index, IndexMeta};
+use super::IndexOptions;
+
+/// Result of an optimize run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptimizeStats {
+    pub segments_before: usize,
+    pub segments_after: usize,
+    /// Deleted documents merged away.
+    pub deleted_docs: u64,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    pub elapsed_ms: u64,
+}
+
+/// Merges every segment of the index at `root` into one and
+/// garbage-collects unused files, then records the new size in `meta.json`.
+/// Searches and later incremental runs see the same documents as before.
+/// The merge runs with the writer heap and threads of `opts`.
+pub fn optimize_index(root: &Path, opts: &IndexOptions) -> Result<OptimizeStats, NsError> {
+    let start = Instant::now();
+    let (index, meta) = open_index(root)?;
+    let storage = FsStorage::for_root(root);
+    let bytes_before = storage.size_bytes();
+    let (segments_before, deleted_docs) = segment_counts(&index)?;
+
+    let mut writer: IndexWriter = opts.writer(&index)?;
+    let segment_ids = index.searchable_segment_ids()?;
+    if segment_ids.len() > 1 || deleted_docs > 0 {
+        writer.merge(&segment_ids).wait()?;
+    }
+    writer.garbage_collect_files().wait()?;
+    writer.wait_merging_threads()?;
+
+    let (segments_after, _) = segment_counts(&index)?;
+    let bytes_after = storage.size_bytes();
+    let new_meta = IndexMeta {
+        index_size_bytes: bytes_after,
+        ..meta
+    };
+    fs::write(root.join(".ns").join("meta.json"), serde_json::to_string(&new_meta)?)?;
+
+    Ok(OptimizeStats {
+        segments_before,
+        segments_after,
+        deleted_docs,
+        bytes_before,
+        bytes_after,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// Number of searchable segments in `index`, and deleted documents in them.
+fn segment_counts(index: &Index) -> Result<(usize, u64), NsError> {
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let deleted = searcher
+        .segment_readers()
+        .iter()
+        .map(|s| u64::from(s.num_deleted_docs()))
+        .sum();
+    Ok((searcher.segment_readers().len(), deleted))
+}
@@ -0,0 +1,76 @@
+//! Symbol outline of a single file (`ns outline`).
+//!
+//! Runs the indexer's extractors on one file, so it needs no index and
+//! sees the file as it is on disk. Unlike the indexed symbols, repeated
+//! names are kept: an outline lists a type and each of its `impl` blocks.
+
+use serde::Serialize;
+
+use super::symbols::{all_symbols_in, parse, SymbolKind};
+
+/// One definition in an outline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OutlineEntry {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// 1-based line of the name and last line of the definition.
+    pub line: usize,
+    pub end_line: usize,
+    /// Number of enclosing definitions (a method in an `impl` block is 1).
+    pub depth: usize,
+}
+
+/// The definitions in `source`, parsed as `lang`, in source order; `None`
+/// when `lang` has no extractor.
+pub fn outline(lang: &str, source: &[u8]) -> Option<Vec<OutlineEntry>> {
+    let tree = parse(lang, source)?;
+    let mut symbols = all_symbols_in(lang, &tree, source);
+    symbols.sort_by_key(|s| (s.line, std::cmp::Reverse(s.end_line)));
+
+    // Last lines of the definitions enclosing the current one.
+    let mut open: Vec<usize> = Vec::new();
+    let entries = symbols
+        .into_iter()
+        .map(|s| {
+            while open.last().is_some_and(|&end| end < s.line) {
+                open.pop();
+            }
+            let depth = open.len();
+            open.push(s.end_line);
+            OutlineEntry {
+                name: s.name,
+                kind: s.kind,
+                line: s.line,
+                end_line: s.end_line,
+                depth,
+            }
+        })
+        .collect();
+    Some(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outline_nests_methods_and_keeps_repeated_names() {
+        let source = b"pub struct Store {\n    items: Vec<u8>,\n}\n\nimpl Store {\n    pub fn new() -> Self {\n        Store { items: Vec::new() }\n    }\n\n    pub fn len(&self) -> usize {\n        self.items.len()\n    }\n}\n\nfn main() {}\n";
+        let entries = outline("rust", source).unwrap();
+        let summary: Vec<(&str, SymbolKind, usize, usize)> = entries
+            .iter()
+            .map(|e| (e.name.as_str(), e.kind, e.line, e.depth))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Store", SymbolKind::Type, 1, 0),
+                ("Store", SymbolKind::Impl, 5, 0),
+                ("new", SymbolKind::Function, 6, 1),
+                ("len", SymbolKind::Function, 10, 1),
+                ("main", SymbolKind::Function, 15, 0),
+            ]
+        );
+        assert!(outline("markdown", b"# Title\n").is_none());
+    }
+}
@@ -0,0 +1,82 @@
+//! Progress bar for `ns index --verbose`.
+//!
+//! Drawn on stderr, redrawn in place with `\r` as files are indexed, and
+//! only when stderr is a terminal: hooks and the daemon log to files, where
+//! a hundred partial bars would be noise.
+
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// Minimum time between two redraws.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Width of the bar between the brackets, in characters.
+const BAR_WIDTH: usize = 30;
+
+/// A progress bar over `total` files.
+pub(crate) struct ProgressBar {
+    total: usize,
+    started: Instant,
+    last_draw: Option<Instant>,
+}
+
+impl ProgressBar {
+    /// A bar over `total` files, or `None` when stderr is not a terminal.
+    pub(crate) fn for_stderr(total: usize) -> Option<Self> {
+        std::io::stderr().is_terminal().then(|| ProgressBar {
+            total,
+            started: Instant::now(),
+            last_draw: None,
+        })
+    }
+
+    /// Redraws the bar for `done` files, at most once per `REDRAW_INTERVAL`,
+    /// and always for the last file, after which the line is ended.
+    pub(crate) fn update(&mut self, done: usize) {
+        let finished = done >= self.total;
+        if !finished && self.last_draw.is_some_and(|t| t.elapsed() < REDRAW_INTERVAL) {
+            return;
+        }
+        self.last_draw = Some(Instant::now());
+        let line = render(done, self.total, self.started.elapsed());
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r{}", line);
+        if finished {
+            let _ = writeln!(stderr);
+        }
+        let _ = stderr.flush();
+    }
+}
+
+/// `[=========>          ]  412/1000 files  183 files/s`
+fn render(done: usize, total: usize, elapsed: Duration) -> String {
+    let filled = (done.min(total) * BAR_WIDTH).checked_div(total).unwrap_or(BAR_WIDTH);
+    let mut bar = "=".repeat(filled);
+    if filled < BAR_WIDTH {
+        bar.push('>');
+    }
+    let secs = elapsed.as_secs_f64();
+    let rate = if secs > 0.0 { done as f64 / secs } else { 0.0 };
+    let width = total.to_string().len();
+    format!(
+        "[{:<BAR_WIDTH$}] {:>width$}/{} files  {:.0} files/s",
+        bar, done, total, rate
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_fill_counts_and_rate() {
+        assert_eq!(
+            render(50, 200, Duration::from_secs(2)),
+            format!("[{:<30}]  50/200 files  25 files/s", "=======>")
+        );
+        assert_eq!(
+            render(200, 200, Duration::from_secs(4)),
+            format!("[{}] 200/200 files  50 files/s", "=".repeat(30))
+        );
+    }
+}
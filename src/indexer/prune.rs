@@ -0,0 +1,350 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tantivy::{Index, IndexWriter, Term};
+
+use crate::error::NsError;
+use crate::schema::path_field;
+
+use super::incremental::get_indexed_paths;
+use super::storage::{FsStorage, IndexStorage};
+use super::IndexOptions;
+
+/// Path components that mark vendored / third-party code. Files under these
+/// directories are the first to go when the index exceeds its size budget.
+pub const VENDOR_DIRS: &[&str] = &[
+    "vendor",
+    "vendors",
+    "node_modules",
+    "third_party",
+    "third-party",
+    "bower_components",
+];
+
+/// Maximum number of prune passes. Each pass estimates per-document cost from
+/// the current index size, so a second pass corrects for estimation error.
+const MAX_PRUNE_PASSES: usize = 3;
+
+/// Stored fields left out of every document to fit the index size budget.
+/// Tried in order before any file is dropped: the file is still found by
+/// everything it contains, only what results show about it shrinks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoredTrim {
+    /// Everything is stored.
+    #[default]
+    None,
+    /// Copies of file content are dropped: definition lines
+    /// (`symbols_def`), line starts and MinHash signatures. Results lose
+    /// their definition previews, context falls back to scanning the file
+    /// and near-duplicates are no longer grouped.
+    Content,
+    /// Also the raw symbol list with its spans and kinds. Symbols are still
+    /// searched and boosted, but results no longer list them.
+    Symbols,
+}
+
+impl StoredTrim {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StoredTrim::None => "none",
+            StoredTrim::Content => "stored content",
+            StoredTrim::Symbols => "stored content and raw symbols",
+        }
+    }
+
+    /// The next, more aggressive trim, if any.
+    fn next(self) -> Option<StoredTrim> {
+        match self {
+            StoredTrim::None => Some(StoredTrim::Content),
+            StoredTrim::Content => Some(StoredTrim::Symbols),
+            StoredTrim::Symbols => None,
+        }
+    }
+
+    /// Whether `symbols_def`, `line_starts` and `minhash` are stored.
+    pub fn keeps_content(&self) -> bool {
+        *self < StoredTrim::Content
+    }
+
+    /// Whether `symbols_raw`, `symbol_spans` and `symbol_kinds` are stored.
+    pub fn keeps_symbols(&self) -> bool {
+        *self < StoredTrim::Symbols
+    }
+}
+
+/// Why a document was dropped from the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneReason {
+    /// Path is under a vendored/third-party directory.
+    Vendored,
+    /// Largest remaining file.
+    Large,
+}
+
+impl PruneReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PruneReason::Vendored => "vendored",
+            PruneReason::Large => "large file",
+        }
+    }
+}
+
+/// A document removed from the index to stay within the size budget.
+#[derive(Debug, Clone)]
+pub struct PrunedFile {
+    /// Path relative to the repo root.
+    pub path: String,
+    /// Source file size in bytes.
+    pub bytes: u64,
+    pub reason: PruneReason,
+}
+
+/// Returns `true` if any component of `rel_path` is a vendored directory.
+pub fn is_vendored_path(rel_path: &str) -> bool {
+    rel_path
+        .split(['/', '\\'])
+        .any(|component| VENDOR_DIRS.contains(&component))
+}
+
+/// Parses a human-readable byte size such as `200MB`, `1.5G`, `512k` or `4096`.
+///
+/// Units are binary (1 KB = 1024 bytes), matching `ns status` output.
+/// Used as a clap `value_parser`, hence the `String` error.
+pub fn parse_byte_size(s: &str) -> Result<u64, String> {
+    let trimmed = s.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{}': expected e.g. 200MB", s))?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => 1024 * 1024,
+        "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+        other => return Err(format!("invalid size unit '{}': use B, KB, MB or GB", other)),
+    };
+
+    Ok((value * multiplier as f64) as u64)
+}
+
+/// Chooses which documents to drop so the index fits within `budget` bytes.
+///
+/// `docs` holds `(rel_path, source_bytes)` for every indexed document.
+/// Each document's share of the index is estimated as proportional to its
+/// source size. Policy: vendored paths go first (largest first), then the
+/// largest remaining files, until the estimated index size fits the budget.
+pub fn plan_pruning(docs: &[(String, u64)], index_size: u64, budget: u64) -> Vec<PrunedFile> {
+    if index_size <= budget {
+        return Vec::new();
+    }
+    let total_bytes: u64 = docs.iter().map(|(_, b)| *b).sum();
+    if total_bytes == 0 {
+        return Vec::new();
+    }
+
+    let mut ranked: Vec<&(String, u64)> = docs.iter().collect();
+    ranked.sort_by(|a, b| {
+        is_vendored_path(&b.0)
+            .cmp(&is_vendored_path(&a.0))
+            .then(b.1.cmp(&a.1))
+            .then(a.0.cmp(&b.0))
+    });
+
+    let bytes_per_index_byte = total_bytes as f64 / index_size as f64;
+    let mut excess = (index_size - budget) as f64 * bytes_per_index_byte;
+    let mut pruned = Vec::new();
+
+    for (path, bytes) in ranked {
+        if excess <= 0.0 {
+            break;
+        }
+        if *bytes == 0 {
+            continue;
+        }
+        let reason = if is_vendored_path(path) {
+            PruneReason::Vendored
+        } else {
+            PruneReason::Large
+        };
+        pruned.push(PrunedFile {
+            path: path.clone(),
+            bytes: *bytes,
+            reason,
+        });
+        excess -= *bytes as f64;
+    }
+
+    pruned
+}
+
+/// Fits `.ns/index/` within `budget` bytes, trimming stored fields before
+/// dropping files.
+///
+/// Starting from `trim`, the index the documents were written with, each
+/// more aggressive `StoredTrim` is tried in turn while the index is over
+/// budget: `rewrite` re-adds every document with that trim, and the index
+/// is compacted and re-measured. Files are pruned (`enforce_index_budget`)
+/// only if the most aggressive trim still doesn't fit. Returns the trim the
+/// index ends up with and the pruned files. Writers get the heap and
+/// threads of `opts`.
+pub(crate) fn fit_index_budget(
+    root: &Path,
+    index: &Index,
+    opts: &IndexOptions,
+    budget: u64,
+    mut trim: StoredTrim,
+    mut rewrite: impl FnMut(&mut IndexWriter, StoredTrim) -> Result<(), NsError>,
+) -> Result<(StoredTrim, Vec<PrunedFile>), NsError> {
+    let storage = FsStorage::for_root(root);
+    while storage.size_bytes() > budget {
+        let Some(next) = trim.next() else {
+            break;
+        };
+        trim = next;
+        let mut writer: IndexWriter = opts.writer(index)?;
+        rewrite(&mut writer, trim)?;
+        writer.commit()?;
+        compact(index, writer)?;
+    }
+    let pruned = enforce_index_budget(root, index, opts, budget)?;
+    Ok((trim, pruned))
+}
+
+/// Deletes documents from the index until `.ns/index/` fits within `budget` bytes.
+///
+/// Deleted documents only free disk space once their segments are merged, so
+/// each pass merges all segments and garbage-collects before re-measuring.
+/// Returns the documents that were pruned, in pruning order.
+pub(crate) fn enforce_index_budget(
+    root: &Path,
+    index: &Index,
+    opts: &IndexOptions,
+    budget: u64,
+) -> Result<Vec<PrunedFile>, NsError> {
+    let storage = FsStorage::for_root(root);
+    let path_f = path_field(&index.schema());
+    let mut all_pruned = Vec::new();
+
+    for _ in 0..MAX_PRUNE_PASSES {
+        let index_size = storage.size_bytes();
+        if index_size <= budget {
+            break;
+        }
+
+        let docs: Vec<(String, u64)> = get_indexed_paths(index)?
+            .into_iter()
+            .map(|p| {
+                let bytes = root.join(&p).metadata().map(|m| m.len()).unwrap_or(0);
+                (p, bytes)
+            })
+            .collect();
+
+        let plan = plan_pruning(&docs, index_size, budget);
+        if plan.is_empty() {
+            break;
+        }
+
+        let mut writer: IndexWriter = opts.writer(index)?;
+        for file in &plan {
+            writer.delete_term(Term::from_field_text(path_f, &file.path));
+        }
+        writer.commit()?;
+        compact(index, writer)?;
+
+        all_pruned.extend(plan);
+    }
+
+    Ok(all_pruned)
+}
+
+/// Merges all segments and garbage-collects, so deleted documents free
+/// their disk space before the index is re-measured.
+fn compact(index: &Index, mut writer: IndexWriter) -> Result<(), NsError> {
+    let segment_ids = index.searchable_segment_ids()?;
+    if !segment_ids.is_empty() {
+        writer.merge(&segment_ids).wait()?;
+    }
+    writer.garbage_collect_files().wait()?;
+    writer.wait_merging_threads()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_byte_sizes() {
+        assert_eq!(parse_byte_size("4096"), Ok(4096));
+        assert_eq!(parse_byte_size("512k"), Ok(512 * 1024));
+        assert_eq!(parse_byte_size("200MB"), Ok(200 * 1024 * 1024));
+        assert_eq!(parse_byte_size("1.5G"), Ok(1536 * 1024 * 1024));
+        assert_eq!(parse_byte_size(" 2 mb "), Ok(2 * 1024 * 1024));
+        assert!(parse_byte_size("lots").is_err());
+        assert!(parse_byte_size("10TB").is_err());
+    }
+
+    #[test]
+    fn detects_vendored_paths() {
+        assert!(is_vendored_path("vendor/lib.go"));
+        assert!(is_vendored_path("web/node_modules/react/index.js"));
+        assert!(is_vendored_path("third_party/zlib/zlib.h"));
+        assert!(!is_vendored_path("src/vendors_api.rs"));
+        assert!(!is_vendored_path("src/main.rs"));
+    }
+
+    #[test]
+    fn trims_content_before_symbols() {
+        assert_eq!(StoredTrim::None.next(), Some(StoredTrim::Content));
+        assert_eq!(StoredTrim::Content.next(), Some(StoredTrim::Symbols));
+        assert_eq!(StoredTrim::Symbols.next(), None);
+        assert!(StoredTrim::None.keeps_content() && StoredTrim::None.keeps_symbols());
+        assert!(!StoredTrim::Content.keeps_content() && StoredTrim::Content.keeps_symbols());
+        assert!(!StoredTrim::Symbols.keeps_content() && !StoredTrim::Symbols.keeps_symbols());
+    }
+
+    #[test]
+    fn no_pruning_under_budget() {
+        let docs = vec![("src/a.rs".to_string(), 100)];
+        assert!(plan_pruning(&docs, 1000, 2000).is_empty());
+    }
+
+    #[test]
+    fn prunes_vendored_before_large_files() {
+        let docs = vec![
+            ("src/huge.rs".to_string(), 5000),
+            ("vendor/small.go".to_string(), 100),
+            ("src/small.rs".to_string(), 100),
+        ];
+        // Index is 2x the source size; budget requires shedding ~1% of it.
+        let plan = plan_pruning(&docs, 10_400, 10_300);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].path, "vendor/small.go");
+        assert_eq!(plan[0].reason, PruneReason::Vendored);
+    }
+
+    #[test]
+    fn prunes_largest_files_until_budget_fits() {
+        let docs = vec![
+            ("src/a.rs".to_string(), 100),
+            ("src/b.rs".to_string(), 3000),
+            ("src/c.rs".to_string(), 2000),
+            ("src/d.rs".to_string(), 900),
+        ];
+        // Shedding half the index takes the largest file; two thirds takes two.
+        let plan = plan_pruning(&docs, 6000, 3000);
+        let paths: Vec<&str> = plan.iter().map(|p| p.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/b.rs"]);
+
+        let plan = plan_pruning(&docs, 6000, 2000);
+        let paths: Vec<&str> = plan.iter().map(|p| p.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/b.rs", "src/c.rs"]);
+        assert!(plan.iter().all(|p| p.reason == PruneReason::Large));
+    }
+}
@@ -0,0 +1,165 @@
+//! Where the tantivy index lives.
+//!
+//! The indexer and searcher only see a tantivy `Directory`; `IndexStorage`
+//! decides what backs it. `FsStorage` is the normal `.ns/index/` directory;
+//! `RamStorage` keeps everything in memory, for tests and throwaway indexes.
+//! New backends (read-only bundles, object stores) implement the same trait.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tantivy::directory::{Directory, MmapDirectory, RamDirectory};
+use tantivy::schema::Schema;
+use tantivy::{Index, IndexSettings};
+
+use crate::error::NsError;
+
+use super::tokenizer::register_tokenizers;
+use super::writer::dir_size;
+
+/// A place a tantivy index can be created in and opened from.
+pub trait IndexStorage {
+    /// Opens the directory holding an existing index.
+    fn open_directory(&self) -> Result<Box<dyn Directory>, NsError>;
+
+    /// Returns an empty directory for a full rebuild, discarding any
+    /// existing index.
+    fn create_directory(&self) -> Result<Box<dyn Directory>, NsError>;
+
+    /// Bytes currently used by the index.
+    fn size_bytes(&self) -> u64;
+}
+
+/// On-disk storage at `<root>/.ns/index/` (memory-mapped).
+#[derive(Debug, Clone)]
+pub struct FsStorage {
+    dir: PathBuf,
+}
+
+impl FsStorage {
+    /// Storage for the repo rooted at `root`.
+    pub fn for_root(root: &Path) -> Self {
+        FsStorage {
+            dir: root.join(".ns").join("index"),
+        }
+    }
+}
+
+impl IndexStorage for FsStorage {
+    fn open_directory(&self) -> Result<Box<dyn Directory>, NsError> {
+        // MmapDirectory::open reports a missing directory as its own error
+        // type; surface it as NotFound so callers print "no index found".
+        if !self.dir.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{} does not exist", self.dir.display()),
+            )
+            .into());
+        }
+        let dir = MmapDirectory::open(&self.dir)
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        Ok(Box::new(dir))
+    }
+
+    fn create_directory(&self) -> Result<Box<dyn Directory>, NsError> {
+        // tantivy refuses to create an index over existing files.
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+        fs::create_dir_all(&self.dir)?;
+        self.open_directory()
+    }
+
+    fn size_bytes(&self) -> u64 {
+        dir_size(&self.dir)
+    }
+}
+
+/// In-memory storage. Clones share the same underlying files, so an index
+/// created through one clone can be reopened through another.
+#[derive(Debug, Clone, Default)]
+pub struct RamStorage {
+    dir: RamDirectory,
+}
+
+#[allow(dead_code)] // constructed by library users and tests, not by the CLI
+impl RamStorage {
+    pub fn new() -> Self {
+        RamStorage::default()
+    }
+}
+
+impl IndexStorage for RamStorage {
+    fn open_directory(&self) -> Result<Box<dyn Directory>, NsError> {
+        Ok(Box::new(self.dir.clone()))
+    }
+
+    fn create_directory(&self) -> Result<Box<dyn Directory>, NsError> {
+        // RamDirectory has no bulk delete; start over with a fresh one only
+        // when this storage is still empty, so clones stay in sync.
+        if self.dir.total_mem_usage() > 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "in-memory index already exists; use a new RamStorage",
+            )
+            .into());
+        }
+        Ok(Box::new(self.dir.clone()))
+    }
+
+    fn size_bytes(&self) -> u64 {
+        self.dir.total_mem_usage() as u64
+    }
+}
+
+/// Creates an empty index with `schema` in `storage`.
+pub fn create_index_in(
+    storage: &dyn IndexStorage,
+    schema: Schema,
+    settings: IndexSettings,
+) -> Result<Index, NsError> {
+    let index = Index::create(storage.create_directory()?, schema, settings)?;
+    register_tokenizers(&index);
+    Ok(index)
+}
+
+/// Opens the index in `storage`. Does not check `meta.json`; see `open_index`.
+pub fn open_index_in(storage: &dyn IndexStorage) -> Result<Index, NsError> {
+    let index = Index::open(storage.open_directory()?)?;
+    register_tokenizers(&index);
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{build_schema, path_field};
+    use tantivy::{IndexWriter, TantivyDocument};
+
+    #[test]
+    fn ram_storage_round_trips_an_index() {
+        let storage = RamStorage::new();
+        let schema = build_schema(false);
+        let index = create_index_in(&storage, schema.clone(), IndexSettings::default()).unwrap();
+
+        let mut writer: IndexWriter = index.writer(15_000_000).unwrap();
+        let mut doc = TantivyDocument::new();
+        doc.add_text(path_field(&schema), "src/lib.rs");
+        writer.add_document(doc).unwrap();
+        writer.commit().unwrap();
+        writer.wait_merging_threads().unwrap();
+
+        let reopened = open_index_in(&storage.clone()).unwrap();
+        let reader = reopened.reader().unwrap();
+        assert_eq!(reader.searcher().num_docs(), 1);
+        assert!(storage.size_bytes() > 0);
+        assert!(storage.create_directory().is_err());
+    }
+
+    #[test]
+    fn fs_storage_missing_index_is_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = open_index_in(&FsStorage::for_root(dir.path())).unwrap_err();
+        assert!(matches!(err, NsError::Io(ref e) if e.kind() == std::io::ErrorKind::NotFound));
+    }
+}
@@ -0,0 +1,190 @@
+//! Index-wide symbol statistics, kept in `meta.json` for `ns status`: file
+//! and symbol counts per language, and with `--detail`, the symbol totals.
+//!
+//! Full and incremental runs recompute them from the stored `symbols_raw`
+//! and `symbol_kinds` fields after committing. Those are small stored fields
+//! (file content is not stored), so the scan costs about as much as the
+//! stored-path scan incremental change detection already does.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use tantivy::schema::Value;
+use tantivy::{Index, ReloadPolicy, TantivyDocument};
+
+use crate::error::NsError;
+use crate::schema::{chunk_line_field, lang_field, path_field, symbol_kinds_field, symbols_raw_field};
+
+use super::symbols::{kinds_from_bytes, SymbolKind};
+
+/// Most duplicated names kept in `SymbolStats::duplicates`.
+pub const TOP_DUPLICATES: usize = 10;
+
+/// Aggregate counts over every symbol in the index.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SymbolStats {
+    /// Symbol definitions; a name defined in two files counts twice.
+    pub total: usize,
+    /// Distinct symbol names.
+    pub distinct: usize,
+    /// Files defining at least one symbol.
+    pub files_with_symbols: usize,
+    /// Definitions per kind (`SymbolKind::as_str`).
+    pub by_kind: BTreeMap<String, usize>,
+    /// Names defined in the most files, most first (ties by name), at most
+    /// `TOP_DUPLICATES`. Only names defined in two or more files.
+    pub duplicates: Vec<DuplicateSymbol>,
+    /// Files and symbol definitions per detected language. A language with
+    /// files but no symbols points at an extraction problem. Empty for
+    /// indexes written before they were collected.
+    #[serde(default)]
+    pub by_language: BTreeMap<String, LanguageCounts>,
+}
+
+/// Files of one language and the symbols defined in them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageCounts {
+    pub files: usize,
+    pub symbols: usize,
+}
+
+/// A symbol name defined in several files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicateSymbol {
+    pub name: String,
+    pub files: usize,
+}
+
+/// Counts the symbols stored in `index`.
+pub fn symbol_stats(index: &Index) -> Result<SymbolStats, NsError> {
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let schema = index.schema();
+    let symbols_raw_f = symbols_raw_field(&schema);
+    let symbol_kinds_f = symbol_kinds_field(&schema);
+    let lang_f = lang_field(&schema);
+    let path_f = path_field(&schema);
+    let chunk_line_f = chunk_line_field(&schema);
+
+    let mut stats = SymbolStats::default();
+    let mut files_per_name: HashMap<String, usize> = HashMap::new();
+    // A chunked file's symbols are spread over its chunks.
+    let mut files_with_symbols: HashSet<String> = HashSet::new();
+    for segment_reader in searcher.segment_readers() {
+        let store_reader = segment_reader.get_store_reader(1)?;
+        for doc_id in segment_reader.doc_ids_alive() {
+            let doc = store_reader.get::<TantivyDocument>(doc_id)?;
+            let symbols = doc
+                .get_first(symbols_raw_f)
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let first_chunk = doc.get_first(chunk_line_f).and_then(|v| v.as_u64()).unwrap_or(0) == 0;
+            let language = doc.get_first(lang_f).and_then(|v| v.as_str()).map(|lang| {
+                let counts = stats.by_language.entry(lang.to_string()).or_default();
+                counts.files += first_chunk as usize;
+                counts
+            });
+            if symbols.is_empty() {
+                continue;
+            }
+            if let Some(counts) = language {
+                counts.symbols += symbols.split('|').count();
+            }
+            let kinds = kinds_from_bytes(
+                doc.get_first(symbol_kinds_f)
+                    .and_then(|v| v.as_bytes())
+                    .unwrap_or_default(),
+            );
+            if let Some(path) = doc.get_first(path_f).and_then(|v| v.as_str()) {
+                files_with_symbols.insert(path.to_string());
+            }
+            for (i, name) in symbols.split('|').enumerate() {
+                stats.total += 1;
+                let kind = kinds.get(i).copied().unwrap_or(SymbolKind::Other);
+                *stats.by_kind.entry(kind.as_str().to_string()).or_default() += 1;
+                *files_per_name.entry(name.to_string()).or_default() += 1;
+            }
+        }
+    }
+    stats.files_with_symbols = files_with_symbols.len();
+    stats.distinct = files_per_name.len();
+
+    let mut duplicates: Vec<DuplicateSymbol> = files_per_name
+        .into_iter()
+        .filter(|(_, files)| *files > 1)
+        .map(|(name, files)| DuplicateSymbol { name, files })
+        .collect();
+    duplicates.sort_by(|a, b| b.files.cmp(&a.files).then_with(|| a.name.cmp(&b.name)));
+    duplicates.truncate(TOP_DUPLICATES);
+    stats.duplicates = duplicates;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::storage::{create_index_in, RamStorage};
+    use crate::schema::build_schema;
+    use tantivy::{IndexSettings, IndexWriter};
+
+    #[test]
+    fn counts_kinds_and_duplicate_names() {
+        let schema = build_schema(false);
+        let index =
+            create_index_in(&RamStorage::new(), schema.clone(), IndexSettings::default()).unwrap();
+        let mut writer: IndexWriter = index.writer(15_000_000).unwrap();
+        let (ty, func, constant) = (SymbolKind::Type, SymbolKind::Function, SymbolKind::Constant);
+        let files = [
+            ("Config|load", vec![ty, func], Some("rust")),
+            ("Config|DEFAULT", vec![ty, constant], Some("go")),
+            ("load", vec![func], Some("rust")),
+            ("", vec![], Some("elixir")),
+            ("", vec![], None),
+        ];
+        for (i, (symbols, kinds, lang)) in files.into_iter().enumerate() {
+            let mut doc = TantivyDocument::new();
+            doc.add_text(path_field(&schema), format!("src/file{}", i));
+            doc.add_text(symbols_raw_field(&schema), symbols);
+            if let Some(lang) = lang {
+                doc.add_text(lang_field(&schema), lang);
+            }
+            let kinds: Vec<u8> = kinds.iter().map(|k| k.code()).collect();
+            doc.add_bytes(symbol_kinds_field(&schema), &kinds);
+            writer.add_document(doc).unwrap();
+        }
+        writer.commit().unwrap();
+
+        let stats = symbol_stats(&index).unwrap();
+        assert_eq!(stats.total, 5);
+        assert_eq!(stats.distinct, 3);
+        assert_eq!(stats.files_with_symbols, 3);
+        assert_eq!(stats.by_kind.get("type"), Some(&2));
+        assert_eq!(stats.by_kind.get("function"), Some(&2));
+        assert_eq!(stats.by_kind.get("constant"), Some(&1));
+        assert_eq!(
+            stats.duplicates,
+            vec![
+                DuplicateSymbol {
+                    name: "Config".to_string(),
+                    files: 2
+                },
+                DuplicateSymbol {
+                    name: "load".to_string(),
+                    files: 2
+                },
+            ]
+        );
+        let counts = |files, symbols| LanguageCounts { files, symbols };
+        assert_eq!(
+            stats.by_language.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("elixir".to_string(), counts(1, 0)),
+                ("go".to_string(), counts(1, 2)),
+                ("rust".to_string(), counts(2, 3)),
+            ]
+        );
+    }
+}
@@ -1,58 +1,364 @@
-use tree_sitter::{Node, Parser};
+use std::str::FromStr;
+
+use tree_sitter::{Node, Parser, Tree};
+
+use super::lines::{from_varints, to_varints};
+
+/// Maximum length in chars of a stored definition line.
+const MAX_DEFINITION_LEN: usize = 200;
+
+/// A symbol name, what its definition declares, the 1-based line its name
+/// appears on, and the last line of its definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub line: usize,
+    pub end_line: usize,
+}
+
+impl Symbol {
+    /// `name`, found at `node` in the definition `definition`. The kind
+    /// comes from the definition's syntax node, or for nodes that don't
+    /// tell (Elixir's `def` calls) from the source line the name is on.
+    fn at(node: &Node, definition: &Node, name: String, source: &[u8]) -> Self {
+        let line = node.start_position().row + 1;
+        let kind = syntax_kind(definition).unwrap_or_else(|| {
+            let start = node.start_byte();
+            let from = source[..start]
+                .iter()
+                .rposition(|&b| b == b'\n')
+                .map_or(0, |i| i + 1);
+            let to = source[start..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(source.len(), |i| start + i);
+            definition_kind(&String::from_utf8_lossy(&source[from..to]))
+        });
+        Symbol {
+            name,
+            kind,
+            line,
+            end_line: (definition.end_position().row + 1).max(line),
+        }
+    }
+}
+
+/// Kind of a definition from its tree-sitter node type, for the node types
+/// the extractors stop at; `None` for the others.
+fn syntax_kind(definition: &Node) -> Option<SymbolKind> {
+    Some(match definition.kind() {
+        "function_item" | "function_signature_item" | "function_declaration"
+        | "method_definition" | "method_declaration" | "function_definition" => {
+            SymbolKind::Function
+        }
+        "struct_item" | "enum_item" | "trait_item" | "type_item" | "class_declaration"
+        | "interface_declaration" | "type_alias_declaration" | "enum_declaration"
+        | "class_definition" | "type_spec" => SymbolKind::Type,
+        "const_item" | "const_spec" => SymbolKind::Constant,
+        "impl_item" => SymbolKind::Impl,
+        "variable_declarator" => match definition.child_by_field_name("value").map(|v| v.kind()) {
+            Some("arrow_function" | "function_expression" | "function" | "generator_function") => {
+                SymbolKind::Function
+            }
+            _ => SymbolKind::Constant,
+        },
+        _ => return None,
+    })
+}
 
 /// Extracts symbol names (functions, structs, classes, etc.) from source code.
 ///
 /// Returns an empty vec for unsupported languages or parse failures.
 /// Symbols are returned in source order, deduplicated by name.
+#[allow(dead_code)] // used by library users and tests; the indexer needs lines too
 pub fn extract_symbols(lang: &str, source: &[u8]) -> Vec<String> {
-    let symbols = match lang {
-        "rust" => extract_rust(source),
-        "typescript" => extract_typescript(source),
-        "javascript" => extract_javascript(source),
-        "python" => extract_python(source),
-        "go" => extract_go(source),
-        "elixir" => extract_elixir(source),
-        _ => return Vec::new(),
-    };
-
-    // Deduplicate while preserving order (first occurrence wins).
-    let mut seen = std::collections::HashSet::new();
-    symbols
+    extract_symbol_lines(lang, source)
         .into_iter()
-        .filter(|s| seen.insert(s.clone()))
+        .map(|s| s.name)
         .collect()
 }
 
-// ── Rust ──────────────────────────────────────────────────────────────────────
+/// Like `extract_symbols`, with the line each symbol is defined on.
+pub fn extract_symbol_lines(lang: &str, source: &[u8]) -> Vec<Symbol> {
+    parse(lang, source)
+        .map(|tree| symbols_in(lang, &tree, source))
+        .unwrap_or_default()
+}
 
-fn extract_rust(source: &[u8]) -> Vec<String> {
+/// Parses `source` with the grammar for `lang`; `None` for unsupported
+/// languages or parse failures. The indexer parses each file once and
+/// reads both symbols (`symbols_in`) and doc comments (`docs::doc_text`)
+/// from the tree.
+pub fn parse(lang: &str, source: &[u8]) -> Option<Tree> {
+    let language: tree_sitter::Language = match lang {
+        "rust" => tree_sitter_rust::LANGUAGE.into(),
+        // TSX is a superset of TypeScript, so it handles both .ts and .tsx.
+        "typescript" => tree_sitter_typescript::LANGUAGE_TSX.into(),
+        "javascript" => tree_sitter_javascript::LANGUAGE.into(),
+        "python" => tree_sitter_python::LANGUAGE.into(),
+        "go" => tree_sitter_go::LANGUAGE.into(),
+        "elixir" => tree_sitter_elixir::LANGUAGE.into(),
+        _ => return None,
+    };
     let mut parser = Parser::new();
     parser
-        .set_language(&tree_sitter_rust::LANGUAGE.into())
-        .expect("failed to load Rust grammar");
-    let tree = match parser.parse(source, None) {
-        Some(t) => t,
-        None => return Vec::new(),
-    };
+        .set_language(&language)
+        .unwrap_or_else(|_| panic!("failed to load {} grammar", lang));
+    parser.parse(source, None)
+}
 
+/// Symbols defined in `tree`, the parse of `source` as `lang`, in source
+/// order and deduplicated by name (first occurrence wins).
+pub fn symbols_in(lang: &str, tree: &Tree, source: &[u8]) -> Vec<Symbol> {
+    let mut seen = std::collections::HashSet::new();
+    all_symbols_in(lang, tree, source)
+        .into_iter()
+        .filter(|s| seen.insert(s.name.clone()))
+        .collect()
+}
+
+/// Like `symbols_in`, keeping repeated names (a type and its `impl`
+/// blocks, same-named methods of different classes).
+pub fn all_symbols_in(lang: &str, tree: &Tree, source: &[u8]) -> Vec<Symbol> {
     let mut symbols = Vec::new();
-    walk_rust(tree.root_node(), source, &mut symbols);
+    let root = tree.root_node();
+    match lang {
+        "rust" => walk_rust(root, source, &mut symbols),
+        "typescript" => walk_js_ts(root, source, &mut symbols, true),
+        "javascript" => walk_js_ts(root, source, &mut symbols, false),
+        "python" => walk_python(root, source, &mut symbols),
+        "go" => walk_go(root, source, &mut symbols),
+        "elixir" => walk_elixir(root, source, &mut symbols),
+        _ => {}
+    }
+    symbols
+}
+
+/// Definition text stored in `symbols_def`: the trimmed source line of each
+/// symbol (`pub struct EventStore {`), newline-separated, in the order of
+/// `symbols`. Long lines are cut to `MAX_DEFINITION_LEN` chars.
+pub fn definition_lines(symbols: &[Symbol], source: &str) -> String {
+    let lines: Vec<&str> = source.lines().collect();
     symbols
+        .iter()
+        .map(|s| {
+            let line = lines.get(s.line - 1).map_or("", |l| l.trim());
+            match line.char_indices().nth(MAX_DEFINITION_LEN) {
+                Some((cut, _)) => &line[..cut],
+                None => line,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// First and last line of each of `symbols`, in order, for the
+/// `symbol_spans` field: varints (see `lines::to_varints`) of each symbol's
+/// line and the number of lines after it.
+pub fn spans_to_bytes(symbols: &[Symbol]) -> Vec<u8> {
+    to_varints(
+        symbols
+            .iter()
+            .flat_map(|s| [s.line as u32, (s.end_line - s.line) as u32]),
+    )
+}
+
+/// Decodes a `symbol_spans` field into 1-based `(first, last)` lines;
+/// `None` if it is truncated.
+pub fn spans_from_bytes(bytes: &[u8]) -> Option<Vec<(usize, usize)>> {
+    let values = from_varints(bytes)?;
+    if values.len() % 2 != 0 {
+        return None;
+    }
+    Some(
+        values
+            .chunks_exact(2)
+            .map(|pair| (pair[0] as usize, (pair[0] + pair[1]) as usize))
+            .collect(),
+    )
+}
+
+/// Kind of each of `symbols`, in order, for the `symbol_kinds` field: one
+/// byte per symbol (see `SymbolKind::code`).
+pub fn kinds_to_bytes(symbols: &[Symbol]) -> Vec<u8> {
+    symbols.iter().map(|s| s.kind.code()).collect()
+}
+
+/// Decodes a `symbol_kinds` field.
+pub fn kinds_from_bytes(bytes: &[u8]) -> Vec<SymbolKind> {
+    bytes.iter().map(|&b| SymbolKind::from_code(b)).collect()
+}
+
+/// What a symbol's definition declares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// struct, enum, class, trait, interface, protocol, type alias
+    Type,
+    /// function, method, macro, guard
+    Function,
+    /// module, namespace
+    Module,
+    /// const, static, or a module-level assignment
+    Constant,
+    /// impl block, `defimpl`
+    Impl,
+    /// `defdelegate`, `use`, `export { ... }`
+    Reexport,
+    /// A definition line nothing above matches.
+    Other,
+}
+
+/// Every kind, in `code` order.
+const SYMBOL_KINDS: [SymbolKind; 7] = [
+    SymbolKind::Type,
+    SymbolKind::Function,
+    SymbolKind::Module,
+    SymbolKind::Constant,
+    SymbolKind::Impl,
+    SymbolKind::Reexport,
+    SymbolKind::Other,
+];
+
+impl SymbolKind {
+    /// Byte stored for this kind in `symbol_kinds`.
+    pub fn code(self) -> u8 {
+        SYMBOL_KINDS.iter().position(|&k| k == self).unwrap_or(6) as u8
+    }
+
+    /// The kind stored as `code`; `Other` for codes this version doesn't know.
+    pub fn from_code(code: u8) -> Self {
+        SYMBOL_KINDS.get(code as usize).copied().unwrap_or(SymbolKind::Other)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SymbolKind::Type => "type",
+            SymbolKind::Function => "function",
+            SymbolKind::Module => "module",
+            SymbolKind::Constant => "constant",
+            SymbolKind::Impl => "impl",
+            SymbolKind::Reexport => "reexport",
+            SymbolKind::Other => "other",
+        }
+    }
+
+    /// Whether this kind declares the thing its name stands for, as opposed
+    /// to naming a value, extending a type or forwarding a definition made
+    /// elsewhere. Unclassified lines count as declarations.
+    pub fn is_declaration(self) -> bool {
+        !matches!(
+            self,
+            SymbolKind::Constant | SymbolKind::Impl | SymbolKind::Reexport
+        )
+    }
+}
+
+impl serde::Serialize for SymbolKind {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl FromStr for SymbolKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "type" | "struct" | "enum" | "class" | "trait" | "interface" => Ok(SymbolKind::Type),
+            "function" | "fn" | "func" | "method" => Ok(SymbolKind::Function),
+            "module" | "mod" | "namespace" => Ok(SymbolKind::Module),
+            "constant" | "const" => Ok(SymbolKind::Constant),
+            "impl" => Ok(SymbolKind::Impl),
+            "reexport" => Ok(SymbolKind::Reexport),
+            "other" => Ok(SymbolKind::Other),
+            other => Err(format!(
+                "unknown symbol kind '{}': use type, function, module, constant, impl, reexport or other",
+                other
+            )),
+        }
+    }
+}
+
+/// Words before the keyword of a definition line.
+const DEFINITION_MODIFIERS: &[&str] = &[
+    "pub", "export", "default", "async", "unsafe", "extern", "abstract", "declare", "private",
+    "protected", "public", "readonly", "override",
+];
+
+/// Kind of a symbol from its stored definition line (`symbols_def`), by
+/// the first keyword after any modifiers: `pub struct EventStore {` is a
+/// type, `const ROUTER = ...` a constant, `const handler = () => ...` a
+/// function. Lines without a keyword are functions when the name is
+/// directly followed by `(` (methods) and constants when followed by `=`.
+pub fn definition_kind(line: &str) -> SymbolKind {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let mut i = 0;
+    while let Some(word) = words.get(i) {
+        let head = word.split(['(', '<', ':', '{']).next().unwrap_or("");
+        match head {
+            _ if DEFINITION_MODIFIERS.contains(&head) => {}
+            "" if word.starts_with('{') => return SymbolKind::Reexport,
+            "struct" | "enum" | "union" | "trait" | "class" | "interface" | "type"
+            | "defprotocol" => return SymbolKind::Type,
+            "fn" | "function" | "function*" | "def" | "defp" | "func" | "defmacro"
+            | "defmacrop" | "defguard" | "defguardp" => return SymbolKind::Function,
+            "mod" | "module" | "namespace" | "defmodule" | "package" => {
+                return SymbolKind::Module
+            }
+            "impl" | "defimpl" => return SymbolKind::Impl,
+            "use" | "import" | "from" | "defdelegate" => return SymbolKind::Reexport,
+            "const" | "let" | "var" => {
+                return if line.contains("=>") || line.contains("function") {
+                    SymbolKind::Function
+                } else {
+                    SymbolKind::Constant
+                };
+            }
+            // Rust `static FOO: T = ...`, or a static method in a class body.
+            "static" => {
+                let call = line.find('(');
+                return match (call, line.find('=')) {
+                    (Some(c), Some(e)) if c < e => SymbolKind::Function,
+                    (Some(_), None) => SymbolKind::Function,
+                    _ => SymbolKind::Constant,
+                };
+            }
+            _ if head.len() < word.len() && word[head.len()..].starts_with('(') => {
+                return SymbolKind::Function
+            }
+            _ => {
+                return match words.get(i + 1) {
+                    Some(next) if next.starts_with('(') => SymbolKind::Function,
+                    Some(next) if next.starts_with('=') || word.ends_with(':') => {
+                        SymbolKind::Constant
+                    }
+                    // Go `type (` blocks: `Store struct {`.
+                    Some(&"struct") | Some(&"interface") => SymbolKind::Type,
+                    _ => SymbolKind::Other,
+                };
+            }
+        }
+        i += 1;
+    }
+    SymbolKind::Other
 }
 
-fn walk_rust(node: Node, source: &[u8], symbols: &mut Vec<String>) {
+// ── Rust ──────────────────────────────────────────────────────────────────────
+
+fn walk_rust(node: Node, source: &[u8], symbols: &mut Vec<Symbol>) {
     match node.kind() {
         "function_item" | "function_signature_item" | "struct_item" | "enum_item"
         | "trait_item" | "const_item" | "type_item" => {
-            if let Some(name) = field_name_text(&node, "name", source) {
-                symbols.push(name);
+            if let Some(symbol) = field_symbol(&node, "name", source) {
+                symbols.push(symbol);
             }
         }
         "impl_item" => {
             // Extract the implemented type name (e.g., "EventStore" from `impl EventStore`)
             if let Some(type_node) = node.child_by_field_name("type") {
                 if let Some(name) = identifier_from_type(type_node, source) {
-                    symbols.push(name);
+                    symbols.push(Symbol::at(&type_node, &node, name, source));
                 }
             }
         }
@@ -68,44 +374,28 @@ fn walk_rust(node: Node, source: &[u8], symbols: &mut Vec<String>) {
 
 // ── TypeScript ────────────────────────────────────────────────────────────────
 
-fn extract_typescript(source: &[u8]) -> Vec<String> {
-    let mut parser = Parser::new();
-    // Use TSX parser — superset of TypeScript, handles both .ts and .tsx
-    parser
-        .set_language(&tree_sitter_typescript::LANGUAGE_TSX.into())
-        .expect("failed to load TypeScript grammar");
-    let tree = match parser.parse(source, None) {
-        Some(t) => t,
-        None => return Vec::new(),
-    };
-
-    let mut symbols = Vec::new();
-    walk_js_ts(tree.root_node(), source, &mut symbols, true);
-    symbols
-}
-
 /// Shared walker for JavaScript and TypeScript ASTs.
 ///
 /// When `ts_extras` is true, additionally extracts from TypeScript-specific nodes:
 /// `interface_declaration`, `type_alias_declaration`, `enum_declaration`.
-fn walk_js_ts(node: Node, source: &[u8], symbols: &mut Vec<String>, ts_extras: bool) {
+fn walk_js_ts(node: Node, source: &[u8], symbols: &mut Vec<Symbol>, ts_extras: bool) {
     match node.kind() {
         "function_declaration" | "class_declaration" | "method_definition" => {
-            if let Some(name) = field_name_text(&node, "name", source) {
-                symbols.push(name);
+            if let Some(symbol) = field_symbol(&node, "name", source) {
+                symbols.push(symbol);
             }
         }
         "interface_declaration" | "type_alias_declaration" | "enum_declaration"
             if ts_extras =>
         {
-            if let Some(name) = field_name_text(&node, "name", source) {
-                symbols.push(name);
+            if let Some(symbol) = field_symbol(&node, "name", source) {
+                symbols.push(symbol);
             }
         }
         "variable_declarator" => {
             if is_top_level_variable(&node) {
-                if let Some(name) = field_name_text(&node, "name", source) {
-                    symbols.push(name);
+                if let Some(symbol) = field_symbol(&node, "name", source) {
+                    symbols.push(symbol);
                 }
             }
         }
@@ -119,45 +409,13 @@ fn walk_js_ts(node: Node, source: &[u8], symbols: &mut Vec<String>, ts_extras: b
     }
 }
 
-// ── JavaScript ────────────────────────────────────────────────────────────────
-
-fn extract_javascript(source: &[u8]) -> Vec<String> {
-    let mut parser = Parser::new();
-    parser
-        .set_language(&tree_sitter_javascript::LANGUAGE.into())
-        .expect("failed to load JavaScript grammar");
-    let tree = match parser.parse(source, None) {
-        Some(t) => t,
-        None => return Vec::new(),
-    };
-
-    let mut symbols = Vec::new();
-    walk_js_ts(tree.root_node(), source, &mut symbols, false);
-    symbols
-}
-
 // ── Python ────────────────────────────────────────────────────────────────────
 
-fn extract_python(source: &[u8]) -> Vec<String> {
-    let mut parser = Parser::new();
-    parser
-        .set_language(&tree_sitter_python::LANGUAGE.into())
-        .expect("failed to load Python grammar");
-    let tree = match parser.parse(source, None) {
-        Some(t) => t,
-        None => return Vec::new(),
-    };
-
-    let mut symbols = Vec::new();
-    walk_python(tree.root_node(), source, &mut symbols);
-    symbols
-}
-
-fn walk_python(node: Node, source: &[u8], symbols: &mut Vec<String>) {
+fn walk_python(node: Node, source: &[u8], symbols: &mut Vec<Symbol>) {
     match node.kind() {
         "function_definition" | "class_definition" => {
-            if let Some(name) = field_name_text(&node, "name", source) {
-                symbols.push(name);
+            if let Some(symbol) = field_symbol(&node, "name", source) {
+                symbols.push(symbol);
             }
         }
         _ => {}
@@ -172,26 +430,11 @@ fn walk_python(node: Node, source: &[u8], symbols: &mut Vec<String>) {
 
 // ── Go ────────────────────────────────────────────────────────────────────────
 
-fn extract_go(source: &[u8]) -> Vec<String> {
-    let mut parser = Parser::new();
-    parser
-        .set_language(&tree_sitter_go::LANGUAGE.into())
-        .expect("failed to load Go grammar");
-    let tree = match parser.parse(source, None) {
-        Some(t) => t,
-        None => return Vec::new(),
-    };
-
-    let mut symbols = Vec::new();
-    walk_go(tree.root_node(), source, &mut symbols);
-    symbols
-}
-
-fn walk_go(node: Node, source: &[u8], symbols: &mut Vec<String>) {
+fn walk_go(node: Node, source: &[u8], symbols: &mut Vec<Symbol>) {
     match node.kind() {
         "function_declaration" | "method_declaration" | "type_spec" | "const_spec" => {
-            if let Some(name) = field_name_text(&node, "name", source) {
-                symbols.push(name);
+            if let Some(symbol) = field_symbol(&node, "name", source) {
+                symbols.push(symbol);
             }
         }
         _ => {}
@@ -206,22 +449,7 @@ fn walk_go(node: Node, source: &[u8], symbols: &mut Vec<String>) {
 
 // ── Elixir ────────────────────────────────────────────────────────────────────
 
-fn extract_elixir(source: &[u8]) -> Vec<String> {
-    let mut parser = Parser::new();
-    parser
-        .set_language(&tree_sitter_elixir::LANGUAGE.into())
-        .expect("failed to load Elixir grammar");
-    let tree = match parser.parse(source, None) {
-        Some(t) => t,
-        None => return Vec::new(),
-    };
-
-    let mut symbols = Vec::new();
-    walk_elixir(tree.root_node(), source, &mut symbols);
-    symbols
-}
-
-fn walk_elixir(node: Node, source: &[u8], symbols: &mut Vec<String>) {
+fn walk_elixir(node: Node, source: &[u8], symbols: &mut Vec<Symbol>) {
     if node.kind() == "call" {
         if let Some(id_node) = node.child_by_field_name("target") {
             if id_node.kind() == "identifier" {
@@ -255,7 +483,7 @@ fn walk_elixir(node: Node, source: &[u8], symbols: &mut Vec<String>) {
 ///
 /// Used by `defmodule`, `defprotocol`, and `defimpl` — all follow the same
 /// AST pattern: the first alias child of the `arguments` node is the name.
-fn elixir_extract_first_alias(call_node: &Node, source: &[u8], symbols: &mut Vec<String>) {
+fn elixir_extract_first_alias(call_node: &Node, source: &[u8], symbols: &mut Vec<Symbol>) {
     for i in 0..call_node.child_count() {
         if let Some(child) = call_node.child(i) {
             if child.kind() == "arguments" {
@@ -263,7 +491,7 @@ fn elixir_extract_first_alias(call_node: &Node, source: &[u8], symbols: &mut Vec
                     if let Some(arg) = child.named_child(j) {
                         if arg.kind() == "alias" {
                             if let Ok(name) = arg.utf8_text(source) {
-                                symbols.push(name.to_string());
+                                symbols.push(Symbol::at(&arg, call_node, name.to_string(), source));
                             }
                             return;
                         }
@@ -277,13 +505,13 @@ fn elixir_extract_first_alias(call_node: &Node, source: &[u8], symbols: &mut Vec
 
 /// Extracts the module/protocol name from `defmodule MyApp.Accounts do ... end`.
 /// The first argument is an `alias` node containing the full module name.
-fn elixir_extract_module_name(call_node: &Node, source: &[u8], symbols: &mut Vec<String>) {
+fn elixir_extract_module_name(call_node: &Node, source: &[u8], symbols: &mut Vec<Symbol>) {
     elixir_extract_first_alias(call_node, source, symbols);
 }
 
 /// Extracts the protocol name from `defimpl Printable, for: Atom do ... end`.
 /// The first argument is an `alias` node (the protocol being implemented).
-fn elixir_extract_impl_name(call_node: &Node, source: &[u8], symbols: &mut Vec<String>) {
+fn elixir_extract_impl_name(call_node: &Node, source: &[u8], symbols: &mut Vec<Symbol>) {
     elixir_extract_first_alias(call_node, source, symbols);
 }
 
@@ -294,7 +522,7 @@ fn elixir_extract_impl_name(call_node: &Node, source: &[u8], symbols: &mut Vec<S
 /// - `def run` (no args) → arguments > identifier
 /// - `def foo(x) when is_integer(x)` → arguments > binary_operator > left(call) > target(identifier)
 ///   (applies to any def/defp/defmacro/defguard with a `when` guard clause)
-fn elixir_extract_fn_name(call_node: &Node, source: &[u8], symbols: &mut Vec<String>) {
+fn elixir_extract_fn_name(call_node: &Node, source: &[u8], symbols: &mut Vec<Symbol>) {
     for i in 0..call_node.child_count() {
         if let Some(child) = call_node.child(i) {
             if child.kind() == "arguments" {
@@ -305,7 +533,7 @@ fn elixir_extract_fn_name(call_node: &Node, source: &[u8], symbols: &mut Vec<Str
                             if let Some(fn_id) = first_arg.child_by_field_name("target") {
                                 if fn_id.kind() == "identifier" {
                                     if let Ok(name) = fn_id.utf8_text(source) {
-                                        symbols.push(name.to_string());
+                                        symbols.push(Symbol::at(&fn_id, call_node, name.to_string(), source));
                                     }
                                 }
                             }
@@ -313,7 +541,7 @@ fn elixir_extract_fn_name(call_node: &Node, source: &[u8], symbols: &mut Vec<Str
                         "identifier" => {
                             // def func_name (no args, no parens)
                             if let Ok(name) = first_arg.utf8_text(source) {
-                                symbols.push(name.to_string());
+                                symbols.push(Symbol::at(&first_arg, call_node, name.to_string(), source));
                             }
                         }
                         "binary_operator" => {
@@ -323,7 +551,7 @@ fn elixir_extract_fn_name(call_node: &Node, source: &[u8], symbols: &mut Vec<Str
                                     if let Some(fn_id) = left.child_by_field_name("target") {
                                         if fn_id.kind() == "identifier" {
                                             if let Ok(name) = fn_id.utf8_text(source) {
-                                                symbols.push(name.to_string());
+                                                symbols.push(Symbol::at(&fn_id, call_node, name.to_string(), source));
                                             }
                                         }
                                     }
@@ -341,15 +569,15 @@ fn elixir_extract_fn_name(call_node: &Node, source: &[u8], symbols: &mut Vec<Str
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
-/// Extracts the text of a named field child (typically "name").
-fn field_name_text(node: &Node, field: &str, source: &[u8]) -> Option<String> {
+/// Extracts the text of a named field child (typically "name") as a symbol.
+fn field_symbol(node: &Node, field: &str, source: &[u8]) -> Option<Symbol> {
     let child = node.child_by_field_name(field)?;
     let text = child.utf8_text(source).ok()?;
     let text = text.trim();
     if text.is_empty() {
         None
     } else {
-        Some(text.to_string())
+        Some(Symbol::at(&child, node, text.to_string(), source))
     }
 }
 
@@ -466,6 +694,81 @@ fn standalone_function() {}
         assert!(symbols.contains(&"EventId".to_string()));
     }
 
+    #[test]
+    fn definition_lines_follow_symbol_order() {
+        let source = "#[derive(Debug)]\npub struct EventStore {\n    events: Vec<u8>,\n}\n\nimpl EventStore {\n    pub fn new() -> Self {\n        todo!()\n    }\n}\n";
+        let symbols = extract_symbol_lines("rust", source.as_bytes());
+        assert_eq!(
+            symbols,
+            vec![
+                Symbol {
+                    name: "EventStore".to_string(),
+                    kind: SymbolKind::Type,
+                    line: 2,
+                    end_line: 4,
+                },
+                Symbol {
+                    name: "new".to_string(),
+                    kind: SymbolKind::Function,
+                    line: 7,
+                    end_line: 9,
+                },
+            ]
+        );
+        assert_eq!(
+            definition_lines(&symbols, source),
+            "pub struct EventStore {\npub fn new() -> Self {"
+        );
+        assert_eq!(spans_from_bytes(&spans_to_bytes(&symbols)), Some(vec![(2, 4), (7, 9)]));
+        assert_eq!(spans_from_bytes(&[2]), None, "odd number of values");
+    }
+
+    #[test]
+    fn symbol_kinds_come_from_the_definition() {
+        let kinds = |lang: &str, source: &str| -> Vec<(String, SymbolKind)> {
+            extract_symbol_lines(lang, source.as_bytes())
+                .into_iter()
+                .map(|s| (s.name, s.kind))
+                .collect()
+        };
+        let js = "export const handler = () => 1;\nconst LIMIT = 3;\nclass Store {\n  save() {}\n}\n";
+        assert_eq!(
+            kinds("javascript", js),
+            vec![
+                ("handler".to_string(), SymbolKind::Function),
+                ("LIMIT".to_string(), SymbolKind::Constant),
+                ("Store".to_string(), SymbolKind::Type),
+                ("save".to_string(), SymbolKind::Function),
+            ]
+        );
+        let ex = "defmodule MyApp.Accounts do\n  defdelegate get(id), to: Repo\n  defp check(x), do: x\nend\n";
+        assert_eq!(
+            kinds("elixir", ex),
+            vec![
+                ("MyApp.Accounts".to_string(), SymbolKind::Module),
+                ("get".to_string(), SymbolKind::Reexport),
+                ("check".to_string(), SymbolKind::Function),
+            ]
+        );
+        let go = "type Store struct{}\nconst Limit = 3\nfunc (s *Store) Save() {}\n";
+        assert_eq!(
+            kinds("go", go),
+            vec![
+                ("Store".to_string(), SymbolKind::Type),
+                ("Limit".to_string(), SymbolKind::Constant),
+                ("Save".to_string(), SymbolKind::Function),
+            ]
+        );
+
+        let symbols = extract_symbol_lines("javascript", js.as_bytes());
+        let stored: Vec<SymbolKind> = symbols.iter().map(|s| s.kind).collect();
+        assert_eq!(kinds_from_bytes(&kinds_to_bytes(&symbols)), stored);
+        assert_eq!(kinds_from_bytes(&[200]), vec![SymbolKind::Other]);
+        assert_eq!("fn".parse(), Ok(SymbolKind::Function));
+        assert_eq!("Class".parse(), Ok(SymbolKind::Type));
+        assert!("variable".parse::<SymbolKind>().is_err());
+    }
+
     #[test]
     fn rust_fixture_validator() {
         let source = include_bytes!("../../tests/fixtures/sample_repo/src/validator.rs");
@@ -974,4 +1277,31 @@ const middleware = (ctx: Context) => ctx.next();
         assert!(symbols.contains(&"handleRequest".to_string()), "should find exported fn expression");
         assert!(symbols.contains(&"middleware".to_string()), "should find top-level arrow");
     }
+
+    #[test]
+    fn definition_kind_reads_the_definition_keyword() {
+        let cases = [
+            ("pub struct EventStore {", SymbolKind::Type),
+            ("pub(crate) fn append(&mut self, event: Event) {", SymbolKind::Function),
+            ("impl<T> Store for EventStore<T> {", SymbolKind::Impl),
+            ("pub const MAX_EVENTS: usize = 100;", SymbolKind::Constant),
+            ("static CACHE: Lazy<Map> = Lazy::new(Map::new);", SymbolKind::Constant),
+            ("export default class Router {", SymbolKind::Type),
+            ("export const ROUTER = createRouter();", SymbolKind::Constant),
+            ("export const fetchUser = async (id) => {", SymbolKind::Function),
+            ("static create(opts) {", SymbolKind::Function),
+            ("async handle(req) {", SymbolKind::Function),
+            ("async def fetch(self, url):", SymbolKind::Function),
+            ("MAX_RETRIES = 3", SymbolKind::Constant),
+            ("func (s *Server) Start() error {", SymbolKind::Function),
+            ("Server struct {", SymbolKind::Type),
+            ("defmodule MyApp.Accounts do", SymbolKind::Module),
+            ("defimpl Printable, for: Atom do", SymbolKind::Impl),
+            ("defdelegate get(id), to: Repo", SymbolKind::Reexport),
+            ("", SymbolKind::Other),
+        ];
+        for (line, kind) in cases {
+            assert_eq!(definition_kind(line), kind, "{}", line);
+        }
+    }
 }
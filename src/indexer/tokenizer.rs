@@ -0,0 +1,418 @@
+//! Identifier-aware tokenizers for the `content` and `symbols` fields.
+//!
+//! Each word is indexed as a whole and, when it is a compound identifier,
+//! as its parts: `EventStoreFactory` yields `eventstorefactory` plus `event`,
+//! `store`, `factory`. The whole token shares the first part's position and
+//! the parts take consecutive positions, so both `EventStoreFactory` and the
+//! phrase `"store factory"` match, and phrases spanning several words keep
+//! working.
+//!
+//! Queries go through the same analyzer, so a compound query word becomes a
+//! phrase over the whole token and its parts and still only matches the same
+//! identifier.
+//!
+//! Indexes built with `--cjk` use `code_cjk` for `content` instead: Chinese,
+//! Japanese and Korean text has no spaces between words, so each run of CJK
+//! characters is indexed as overlapping character bigrams (`事件存储` →
+//! `事件`, `件存`, `存储`) at consecutive positions. Any two-or-more character
+//! query word then matches as a phrase over its bigrams, wherever it sits in
+//! a sentence.
+//!
+//! The optional `content_ngram` field uses `ngram3`: lowercased character
+//! trigrams, used to pre-filter `--substring` searches. The optional
+//! `content_stem` field uses `code_stem`: the `code` tokens, Porter-stemmed.
+
+use tantivy::tokenizer::{
+    Language, LowerCaser, NgramTokenizer, RemoveLongFilter, Stemmer, TextAnalyzer, Token,
+    TokenStream, Tokenizer,
+};
+use tantivy::Index;
+
+/// Tokenizer name used by the `content` field.
+pub const CODE_TOKENIZER: &str = "code";
+/// Tokenizer name used by the `content` field of `--cjk` indexes.
+pub const CODE_CJK_TOKENIZER: &str = "code_cjk";
+/// Tokenizer name used by the `symbols` field.
+pub const SYMBOL_TOKENIZER: &str = "symbol";
+/// Tokenizer name used by the `content_ngram` field.
+pub const NGRAM_TOKENIZER: &str = "ngram3";
+/// Tokenizer name used by the `content_stem` field.
+pub const STEM_TOKENIZER: &str = "code_stem";
+
+/// Same limit as tantivy's default tokenizer.
+const MAX_TOKEN_LEN: usize = 40;
+
+/// Splits text into words and emits each word plus its identifier parts,
+/// lowercased.
+#[derive(Clone, Default)]
+pub struct CodeTokenizer {
+    /// Words are whitespace-separated (symbol lists) rather than runs of
+    /// letters, digits and `_` (source text).
+    whitespace_words: bool,
+    /// Runs of CJK characters are separate words, split into bigrams.
+    cjk: bool,
+    tokens: Vec<Token>,
+}
+
+impl CodeTokenizer {
+    /// Tokenizer for source text.
+    pub fn code() -> Self {
+        CodeTokenizer::default()
+    }
+
+    /// Tokenizer for whitespace-separated symbol names, where a word such as
+    /// `MyApp.EventManager` is kept whole.
+    pub fn symbols() -> Self {
+        CodeTokenizer {
+            whitespace_words: true,
+            ..CodeTokenizer::default()
+        }
+    }
+
+    /// Tokenizer for source text that also splits CJK runs into bigrams.
+    pub fn code_cjk() -> Self {
+        CodeTokenizer {
+            cjk: true,
+            ..CodeTokenizer::default()
+        }
+    }
+}
+
+impl Tokenizer for CodeTokenizer {
+    type TokenStream<'a> = CodeTokenStream<'a>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> CodeTokenStream<'a> {
+        self.tokens.clear();
+        let mut position = 0;
+        for (start, word) in words(text, self.whitespace_words, self.cjk) {
+            if self.cjk && word.starts_with(is_cjk) {
+                let bigrams = cjk_bigrams(word);
+                for (i, &(from, to)) in bigrams.iter().enumerate() {
+                    self.tokens.push(Token {
+                        offset_from: start + from,
+                        offset_to: start + to,
+                        position: position + i,
+                        text: word[from..to].to_string(),
+                        position_length: 1,
+                    });
+                }
+                position += bigrams.len();
+                continue;
+            }
+            let parts = identifier_parts(word);
+            if parts.is_empty() {
+                continue;
+            }
+            if parts.len() > 1 {
+                self.tokens.push(Token {
+                    offset_from: start,
+                    offset_to: start + word.len(),
+                    position,
+                    text: word.to_lowercase(),
+                    position_length: parts.len(),
+                });
+            }
+            for (i, (from, to)) in parts.iter().enumerate() {
+                self.tokens.push(Token {
+                    offset_from: start + from,
+                    offset_to: start + to,
+                    position: position + i,
+                    text: word[*from..*to].to_lowercase(),
+                    position_length: 1,
+                });
+            }
+            position += parts.len();
+        }
+        CodeTokenStream {
+            tokens: &mut self.tokens,
+            index: None,
+        }
+    }
+}
+
+pub struct CodeTokenStream<'a> {
+    tokens: &'a mut Vec<Token>,
+    index: Option<usize>,
+}
+
+impl TokenStream for CodeTokenStream<'_> {
+    fn advance(&mut self) -> bool {
+        let next = self.index.map_or(0, |i| i + 1);
+        self.index = Some(next);
+        next < self.tokens.len()
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.index.unwrap_or(0)]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.index.unwrap_or(0)]
+    }
+}
+
+/// Registers the `code`, `code_cjk`, `symbol`, `ngram3` and `code_stem`
+/// tokenizers on a tantivy index.
+pub fn register_tokenizers(index: &Index) {
+    let code = TextAnalyzer::builder(CodeTokenizer::code())
+        .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+        .build();
+    index.tokenizers().register(CODE_TOKENIZER, code);
+    let code_cjk = TextAnalyzer::builder(CodeTokenizer::code_cjk())
+        .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+        .build();
+    index.tokenizers().register(CODE_CJK_TOKENIZER, code_cjk);
+    let symbol = TextAnalyzer::builder(CodeTokenizer::symbols())
+        .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+        .build();
+    index.tokenizers().register(SYMBOL_TOKENIZER, symbol);
+    let ngram = TextAnalyzer::builder(
+        NgramTokenizer::new(3, 3, false).expect("3..=3 is a valid n-gram range"),
+    )
+    .filter(LowerCaser)
+    .build();
+    index.tokenizers().register(NGRAM_TOKENIZER, ngram);
+    index.tokenizers().register(STEM_TOKENIZER, stem_analyzer());
+}
+
+fn stem_analyzer() -> TextAnalyzer {
+    TextAnalyzer::builder(CodeTokenizer::code())
+        .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+        .filter(Stemmer::new(Language::English))
+        .build()
+}
+
+/// Distinct lowercased character trigrams of `text`, as the `ngram3`
+/// tokenizer indexes them. Empty when `text` is shorter than three chars.
+pub fn trigrams(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.to_lowercase().chars().collect();
+    let mut grams: Vec<String> = Vec::new();
+    for window in chars.windows(3) {
+        let gram: String = window.iter().collect();
+        if !grams.contains(&gram) {
+            grams.push(gram);
+        }
+    }
+    grams
+}
+
+/// Lowercased parts of every word in `text`, without the whole-word tokens:
+/// the terms a phrase over `text` must match at consecutive positions. With
+/// `cjk`, CJK runs give their bigrams, as `code_cjk` indexes them.
+pub fn part_tokens(text: &str, cjk: bool) -> Vec<String> {
+    words(text, false, cjk)
+        .flat_map(|(_, word)| {
+            let parts = if cjk && word.starts_with(is_cjk) {
+                cjk_bigrams(word)
+            } else {
+                identifier_parts(word)
+            };
+            parts
+                .into_iter()
+                .map(move |(from, to)| word[from..to].to_lowercase())
+        })
+        .collect()
+}
+
+/// Words of `text` with their byte offsets. With `cjk`, a run of CJK
+/// characters is a word of its own even when it touches other letters.
+fn words(text: &str, whitespace_words: bool, cjk: bool) -> impl Iterator<Item = (usize, &str)> {
+    let is_sep = move |c: char| {
+        if whitespace_words {
+            c.is_whitespace()
+        } else {
+            !(c.is_alphanumeric() || c == '_')
+        }
+    };
+    let mut rest = 0;
+    std::iter::from_fn(move || {
+        let tail = &text[rest..];
+        let start = rest + tail.find(|c: char| !is_sep(c))?;
+        let word = &text[start..];
+        let len = if cjk && word.starts_with(is_cjk) {
+            word.find(|c: char| !is_cjk(c)).unwrap_or(word.len())
+        } else if cjk {
+            word.find(|c: char| is_sep(c) || is_cjk(c)).unwrap_or(word.len())
+        } else {
+            word.find(is_sep).unwrap_or(word.len())
+        };
+        rest = start + len;
+        Some((start, &text[start..rest]))
+    })
+}
+
+/// Han ideographs, kana and Hangul: scripts written without spaces between
+/// words (Korean uses spaces, but bigrams still match inside them).
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1100}'..='\u{11FF}'
+            | '\u{3040}'..='\u{30FF}'
+            | '\u{3130}'..='\u{318F}'
+            | '\u{31F0}'..='\u{31FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{AC00}'..='\u{D7AF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{20000}'..='\u{2FA1F}'
+    )
+}
+
+/// Byte ranges of the overlapping character bigrams of a CJK run. A single
+/// character is its own token.
+fn cjk_bigrams(run: &str) -> Vec<(usize, usize)> {
+    let bounds: Vec<usize> = run
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(run.len()))
+        .collect();
+    if bounds.len() <= 2 {
+        return vec![(0, run.len())];
+    }
+    bounds.windows(3).map(|w| (w[0], w[2])).collect()
+}
+
+/// Byte ranges of the parts of an identifier: runs of letters and digits,
+/// further split at camelCase boundaries (`HTTPServer` → `HTTP`, `Server`).
+fn identifier_parts(word: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    let mut parts = Vec::new();
+    let mut start: Option<usize> = None;
+
+    for (i, &(offset, c)) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if let Some(s) = start.take() {
+                parts.push((s, offset));
+            }
+            continue;
+        }
+        if let Some(s) = start {
+            let prev = chars[i - 1].1;
+            let next = chars.get(i + 1).map(|&(_, n)| n);
+            let boundary = c.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_ascii_digit()
+                    || (prev.is_uppercase() && next.is_some_and(|n| n.is_lowercase())));
+            if boundary {
+                parts.push((s, offset));
+                start = Some(offset);
+            }
+        } else {
+            start = Some(offset);
+        }
+    }
+    if let Some(s) = start {
+        parts.push((s, word.len()));
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(mut tokenizer: CodeTokenizer, text: &str) -> Vec<(String, usize)> {
+        let mut out = Vec::new();
+        let mut stream = tokenizer.token_stream(text);
+        while stream.advance() {
+            let t = stream.token();
+            out.push((t.text.clone(), t.position));
+        }
+        out
+    }
+
+    fn owned(pairs: &[(&str, usize)]) -> Vec<(String, usize)> {
+        pairs.iter().map(|(s, p)| (s.to_string(), *p)).collect()
+    }
+
+    #[test]
+    fn splits_camel_case_and_keeps_whole_token() {
+        assert_eq!(
+            tokens(CodeTokenizer::code(), "new EventStoreFactory()"),
+            owned(&[
+                ("new", 0),
+                ("eventstorefactory", 1),
+                ("event", 1),
+                ("store", 2),
+                ("factory", 3),
+            ])
+        );
+    }
+
+    #[test]
+    fn splits_snake_case_and_acronyms() {
+        assert_eq!(
+            tokens(CodeTokenizer::code(), "validate_port HTTPServer"),
+            owned(&[
+                ("validate_port", 0),
+                ("validate", 0),
+                ("port", 1),
+                ("httpserver", 2),
+                ("http", 2),
+                ("server", 3),
+            ])
+        );
+    }
+
+    #[test]
+    fn symbol_words_are_whitespace_separated() {
+        assert_eq!(
+            tokens(CodeTokenizer::symbols(), "MyApp.Events run"),
+            owned(&[
+                ("myapp.events", 0),
+                ("my", 0),
+                ("app", 1),
+                ("events", 2),
+                ("run", 3),
+            ])
+        );
+    }
+
+    #[test]
+    fn trigrams_are_lowercased_and_distinct() {
+        assert_eq!(trigrams("entSto"), vec!["ent", "nts", "tst", "sto"]);
+        assert_eq!(trigrams("aaaa"), vec!["aaa"]);
+        assert!(trigrams("ab").is_empty());
+    }
+
+    #[test]
+    fn stemming_conflates_inflections() {
+        let stems = |text: &str| {
+            let mut analyzer = stem_analyzer();
+            let mut stream = analyzer.token_stream(text);
+            let mut out = Vec::new();
+            while stream.advance() {
+                out.push(stream.token().text.clone());
+            }
+            out
+        };
+        assert_eq!(stems("validating"), stems("validate"));
+        assert_eq!(stems("Handlers"), vec!["handler"]);
+    }
+
+    #[test]
+    fn part_tokens_drop_whole_words() {
+        assert_eq!(part_tokens("EventStore::new()", false), vec!["event", "store", "new"]);
+        assert_eq!(part_tokens("utf8 base64", false), vec!["utf8", "base64"]);
+    }
+
+    #[test]
+    fn cjk_runs_become_bigrams() {
+        assert_eq!(
+            tokens(CodeTokenizer::code_cjk(), "保存事件到EventStore中"),
+            owned(&[
+                ("保存", 0),
+                ("存事", 1),
+                ("事件", 2),
+                ("件到", 3),
+                ("eventstore", 4),
+                ("event", 4),
+                ("store", 5),
+                ("中", 6),
+            ])
+        );
+        assert_eq!(part_tokens("事件存储", true), vec!["事件", "件存", "存储"]);
+        // Without the option a CJK run stays one word.
+        assert_eq!(part_tokens("事件存储", false), vec!["事件存储"]);
+    }
+}
@@ -0,0 +1,292 @@
+//! Per-file embeddings for semantic reranking (`ns --semantic`).
+//!
+//! With an embedding model configured (`ns index --embed-model <DIR>`, see
+//! `embed`), every index write embeds the indexed files and saves the
+//! vectors to `.ns/vectors/vectors.bin`. The reranker compares them with
+//! the query's embedding, so a conceptual query ("where do we retry failed
+//! requests") can lift the lexical matches that are about it.
+//!
+//! Each vector is keyed by path and the file's stored `content_hash`:
+//! incremental runs embed only new and changed files, and switching models
+//! starts over. A file embeds as its path followed by its beginning, up to
+//! the model's `MAX_TOKENS`.
+//!
+//! `vectors.bin` is little-endian: the magic `NSV1`, the vector length
+//! (`u32`), the model directory (`u32` length, UTF-8), then per file its
+//! content hash (`u64`), path (`u32` length, UTF-8) and vector (`f32`s).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use tantivy::schema::Value;
+use tantivy::{Index, ReloadPolicy, TantivyDocument};
+
+use crate::error::NsError;
+use crate::schema::{content_hash_field, path_field};
+
+use super::embed::Embedder;
+
+/// Directory inside `.ns/` holding the vectors.
+pub const VECTORS_DIR: &str = "vectors";
+
+const VECTORS_FILE: &str = "vectors.bin";
+const MAGIC: &[u8; 4] = b"NSV1";
+
+/// Texts embedded per model run.
+const EMBED_BATCH: usize = 16;
+
+/// Bytes of a file read for its embedding; the model truncates further.
+const EMBED_PREFIX_BYTES: usize = 4096;
+
+/// Contents of `.ns/vectors/vectors.bin`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Vectors {
+    /// Model directory the vectors were computed with.
+    pub model: String,
+    /// Length of every vector.
+    pub dims: usize,
+    /// Embedding of each indexed path.
+    pub files: HashMap<String, FileVector>,
+}
+
+/// One file's embedding and the content it was computed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileVector {
+    pub content_hash: u64,
+    pub vector: Vec<f32>,
+}
+
+impl Vectors {
+    /// Cosine similarity of `query` to the embedding of `path`, or `None`
+    /// when the file has none.
+    pub fn similarity(&self, path: &str, query: &[f32]) -> Option<f32> {
+        self.files.get(path).map(|f| cosine(&f.vector, query))
+    }
+}
+
+/// Cosine similarity of two vectors (-1..=1); 0 when either is all zeros
+/// or their lengths differ.
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms =
+        a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|y| y * y).sum::<f32>().sqrt();
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+/// The vectors of the index at `root` and the embedding of `query` by the
+/// model that computed them, for `--semantic`.
+pub fn semantic_query(root: &Path, query: &str) -> Result<(Vectors, Vec<f32>), NsError> {
+    let vectors = read_vectors(root).ok_or(NsError::MissingIndexOption("--embed-model"))?;
+    let mut embedder = Embedder::load(Path::new(&vectors.model))?;
+    let query = embedder.embed(&[query])?.pop().unwrap_or_default();
+    Ok((vectors, query))
+}
+
+/// Path of the vectors file for the repo at `root`.
+pub fn vectors_path(root: &Path) -> PathBuf {
+    root.join(".ns").join(VECTORS_DIR).join(VECTORS_FILE)
+}
+
+/// Reads `.ns/vectors/vectors.bin`; `None` when there are no vectors or
+/// the file is unreadable.
+pub fn read_vectors(root: &Path) -> Option<Vectors> {
+    decode(&std::fs::read(vectors_path(root)).ok()?)
+}
+
+fn encode(vectors: &Vectors) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&(vectors.dims as u32).to_le_bytes());
+    out.extend_from_slice(&(vectors.model.len() as u32).to_le_bytes());
+    out.extend_from_slice(vectors.model.as_bytes());
+    let mut paths: Vec<&String> = vectors.files.keys().collect();
+    paths.sort();
+    for path in paths {
+        let file = &vectors.files[path];
+        out.extend_from_slice(&file.content_hash.to_le_bytes());
+        out.extend_from_slice(&(path.len() as u32).to_le_bytes());
+        out.extend_from_slice(path.as_bytes());
+        for v in &file.vector {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+    out
+}
+
+fn decode(bytes: &[u8]) -> Option<Vectors> {
+    let mut rest = bytes.strip_prefix(MAGIC)?;
+    let mut take = |n: usize| -> Option<&[u8]> {
+        if rest.len() < n {
+            return None;
+        }
+        let (head, tail) = rest.split_at(n);
+        rest = tail;
+        Some(head)
+    };
+    let u32_at = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize;
+
+    let dims = u32_at(take(4)?);
+    let model_len = u32_at(take(4)?);
+    let model = String::from_utf8(take(model_len)?.to_vec()).ok()?;
+    let mut files = HashMap::new();
+    while let Some(hash) = take(8) {
+        let content_hash = u64::from_le_bytes(hash.try_into().ok()?);
+        let path_len = u32_at(take(4)?);
+        let path = String::from_utf8(take(path_len)?.to_vec()).ok()?;
+        let vector = take(dims * 4)?
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+        files.insert(
+            path,
+            FileVector {
+                content_hash,
+                vector,
+            },
+        );
+    }
+    Some(Vectors { model, dims, files })
+}
+
+/// Brings `.ns/vectors/` in line with `index`: embeds the files whose
+/// content changed since their vector was computed and forgets the files no
+/// longer indexed. Removes the directory when `model` is `None`.
+pub(crate) fn update_vectors(
+    root: &Path,
+    index: &Index,
+    model: Option<&str>,
+) -> Result<(), NsError> {
+    let Some(model) = model else {
+        return match std::fs::remove_dir_all(root.join(".ns").join(VECTORS_DIR)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    };
+
+    let mut previous = read_vectors(root)
+        .filter(|v| v.model == model)
+        .unwrap_or_default();
+    let mut vectors = Vectors {
+        model: model.to_string(),
+        dims: previous.dims,
+        files: HashMap::new(),
+    };
+    let mut pending = Vec::new();
+    for (path, content_hash) in indexed_hashes(index)? {
+        match previous.files.remove(&path) {
+            Some(file) if file.content_hash == content_hash => {
+                vectors.files.insert(path, file);
+            }
+            _ => pending.push((path, content_hash)),
+        }
+    }
+
+    if !pending.is_empty() {
+        let mut embedder = Embedder::load(Path::new(model))?;
+        for batch in pending.chunks(EMBED_BATCH) {
+            let texts: Vec<String> = batch
+                .iter()
+                .map(|(path, _)| embed_text(root, path))
+                .collect();
+            let refs: Vec<&str> = texts.iter().map(String::as_str).collect();
+            for ((path, content_hash), vector) in batch.iter().zip(embedder.embed(&refs)?) {
+                vectors.dims = vector.len();
+                vectors.files.insert(
+                    path.clone(),
+                    FileVector {
+                        content_hash: *content_hash,
+                        vector,
+                    },
+                );
+            }
+        }
+    }
+
+    let path = vectors_path(root);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, encode(&vectors))?;
+    Ok(())
+}
+
+/// Path and stored content hash of every file in `index`, once per file
+/// however many chunks it has.
+fn indexed_hashes(index: &Index) -> Result<Vec<(String, u64)>, NsError> {
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let schema = index.schema();
+    let path_f = path_field(&schema);
+    let hash_f = content_hash_field(&schema);
+
+    let mut hashes = Vec::new();
+    for segment_reader in searcher.segment_readers() {
+        let store_reader = segment_reader.get_store_reader(1)?;
+        for doc_id in segment_reader.doc_ids_alive() {
+            let doc = store_reader.get::<TantivyDocument>(doc_id)?;
+            if let Some(path) = doc.get_first(path_f).and_then(|v| v.as_str()) {
+                let hash = doc.get_first(hash_f).and_then(|v| v.as_u64()).unwrap_or(0);
+                hashes.push((path.to_string(), hash));
+            }
+        }
+    }
+    hashes.sort();
+    hashes.dedup();
+    Ok(hashes)
+}
+
+/// Text embedded for the file at `rel_path`: its path, then its first
+/// `EMBED_PREFIX_BYTES` bytes (just the path if it can't be read).
+fn embed_text(root: &Path, rel_path: &str) -> String {
+    let content = std::fs::read(root.join(rel_path)).unwrap_or_default();
+    let prefix = &content[..content.len().min(EMBED_PREFIX_BYTES)];
+    format!("{}\n{}", rel_path, String::from_utf8_lossy(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vectors_round_trip_and_compare() {
+        let mut files = HashMap::new();
+        files.insert(
+            "src/retry.rs".to_string(),
+            FileVector {
+                content_hash: 7,
+                vector: vec![0.6, 0.8, 0.0],
+            },
+        );
+        files.insert(
+            "src/parse.rs".to_string(),
+            FileVector {
+                content_hash: 9,
+                vector: vec![0.0, 0.0, 1.0],
+            },
+        );
+        let vectors = Vectors {
+            model: "/models/minilm".to_string(),
+            dims: 3,
+            files,
+        };
+        assert_eq!(decode(&encode(&vectors)), Some(vectors.clone()));
+        assert_eq!(decode(b"NSV2"), None);
+
+        let query = [0.6, 0.8, 0.0];
+        assert!((vectors.similarity("src/retry.rs", &query).unwrap() - 1.0).abs() < 1e-6);
+        assert_eq!(vectors.similarity("src/parse.rs", &query), Some(0.0));
+        assert_eq!(vectors.similarity("src/missing.rs", &query), None);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine(&[1.0], &[1.0, 0.0]), 0.0);
+    }
+}
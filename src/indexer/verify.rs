@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use tantivy::schema::Value;
+use tantivy::{ReloadPolicy, TantivyDocument};
+
+use crate::error::NsError;
+use crate::schema::{content_hash_field, path_field, truncated_field};
+
+use super::walker::read_head;
+use super::writer::{content_hash, open_index};
+
+/// Result of comparing indexed documents against the working tree.
+#[derive(Debug, Clone, Default)]
+pub struct ContentCheck {
+    /// Live documents in the index.
+    pub indexed: usize,
+    /// Documents compared (the sample).
+    pub checked: usize,
+    /// Sampled paths whose file no longer exists.
+    pub missing: Vec<String>,
+    /// Sampled paths whose file content no longer matches the index.
+    pub changed: Vec<String>,
+}
+
+impl ContentCheck {
+    /// Percentage of checked documents that are missing or changed.
+    pub fn stale_percent(&self) -> f64 {
+        if self.checked == 0 {
+            0.0
+        } else {
+            (self.missing.len() + self.changed.len()) as f64 * 100.0 / self.checked as f64
+        }
+    }
+}
+
+/// Re-reads up to `sample` indexed files (all of them when `None`) and
+/// compares their content hash to the one recorded at index time. Files
+/// indexed truncated (`--truncate-large`) are compared by the same head.
+///
+/// The sample is spread evenly over the paths in sorted order, so repeated
+/// runs check the same files and results are comparable over time.
+pub fn verify_content(root: &Path, sample: Option<usize>) -> Result<ContentCheck, NsError> {
+    let (index, meta) = open_index(root)?;
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let schema = index.schema();
+    let path_f = path_field(&schema);
+    let hash_f = content_hash_field(&schema);
+    let truncated_f = truncated_field(&schema);
+    let head_limit = meta.truncate_large_kb.map(|kb| kb * 1024);
+
+    let mut docs: Vec<(String, Option<u64>, bool)> = Vec::new();
+    for segment_reader in searcher.segment_readers() {
+        let store_reader = segment_reader.get_store_reader(1)?;
+        for doc_id in segment_reader.doc_ids_alive() {
+            let doc = store_reader.get::<TantivyDocument>(doc_id)?;
+            let path = doc
+                .get_first(path_f)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+            let hash = doc.get_first(hash_f).and_then(|v| v.as_u64());
+            let truncated = doc.get_first(truncated_f).and_then(|v| v.as_u64()) == Some(1);
+            docs.push((path, hash, truncated));
+        }
+    }
+    // Chunks of one file share its path and hash.
+    docs.sort();
+    docs.dedup();
+
+    let mut check = ContentCheck {
+        indexed: docs.len(),
+        ..ContentCheck::default()
+    };
+    for (path, hash, truncated) in evenly_spaced(&docs, sample) {
+        check.checked += 1;
+        let read = match head_limit {
+            Some(limit) if *truncated => read_head(&root.join(path), limit),
+            _ => std::fs::read(root.join(path)),
+        };
+        match read {
+            Ok(bytes) => {
+                if *hash != Some(content_hash(&bytes)) {
+                    check.changed.push(path.clone());
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                check.missing.push(path.clone());
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(check)
+}
+
+/// Picks `n` items spread evenly across `items`, or all of them.
+pub(crate) fn evenly_spaced<T>(items: &[T], n: Option<usize>) -> Vec<&T> {
+    match n {
+        Some(n) if n < items.len() => (0..n).map(|i| &items[i * items.len() / n]).collect(),
+        _ => items.iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evenly_spaced_covers_the_range() {
+        let items: Vec<u32> = (0..10).collect();
+        assert_eq!(evenly_spaced(&items, Some(5)), vec![&0, &2, &4, &6, &8]);
+        assert_eq!(evenly_spaced(&items, Some(20)).len(), 10);
+        assert_eq!(evenly_spaced(&items, None).len(), 10);
+        assert!(evenly_spaced(&items, Some(0)).is_empty());
+    }
+
+    #[test]
+    fn stale_percent_counts_missing_and_changed() {
+        let check = ContentCheck {
+            indexed: 10,
+            checked: 4,
+            missing: vec!["a".to_string()],
+            changed: vec!["b".to_string()],
+        };
+        assert_eq!(check.stale_percent(), 50.0);
+        assert_eq!(ContentCheck::default().stale_percent(), 0.0);
+    }
+}
@@ -1,60 +1,291 @@
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
+use glob::Pattern;
 use ignore::WalkBuilder;
 
 use super::language::detect_language;
 
+/// Directory names never indexed, at any depth: git's own data, and ns
+/// indexes, including a nested one in a sub-directory indexed on its own.
+pub const SKIPPED_DIRS: &[&str] = &[".git", ".ns"];
+
+/// Whether `rel_path` is one of `SKIPPED_DIRS` or lies inside one. Paths
+/// from git use `/`; Windows walks use `\\`.
+pub fn in_skipped_dir(rel_path: &str) -> bool {
+    rel_path
+        .split(['/', '\\'])
+        .any(|component| SKIPPED_DIRS.contains(&component))
+}
+
+/// Whether `bytes` look like a binary file: a NUL byte in the first 512.
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(512)].contains(&0)
+}
+
+/// What a walk visits besides what `.gitignore` and `SKIPPED_DIRS` allow.
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    /// Files larger than this many bytes are skipped, or truncated with
+    /// `truncate_to`.
+    pub max_file_size: u64,
+    /// Index the first this many bytes of files over `max_file_size`
+    /// instead of skipping them (`ns index --truncate-large`), cut back to
+    /// the last full line. `None` skips them.
+    pub truncate_to: Option<u64>,
+    /// Globs over root-relative paths (`ns index --exclude`). A matching
+    /// file is skipped, and a matching directory is not descended into.
+    pub exclude: Vec<String>,
+    /// Directory levels to descend below the root (`ns index --max-depth`):
+    /// 1 visits only files in the root itself. `None` is unlimited.
+    pub max_depth: Option<usize>,
+    /// Follow symbolic links to files and directories (`ns index
+    /// --follow-symlinks`). Links back to a directory being walked are
+    /// reported and skipped, and a file reached by several paths is
+    /// indexed once.
+    pub follow_symlinks: bool,
+    /// Directories to walk instead of the whole root, relative to it
+    /// (`ns index --root A --root B`, indexed into their common parent).
+    /// Paths stay relative to the root, so they start with the directory
+    /// they came from. Empty walks everything.
+    pub roots: Vec<String>,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            max_file_size: 1_048_576,
+            truncate_to: None,
+            exclude: Vec::new(),
+            max_depth: None,
+            follow_symlinks: false,
+            roots: Vec::new(),
+        }
+    }
+}
+
+/// Whether a walk with `opts` would visit the file at `rel_path`, ignoring
+/// the file size limit: it is not excluded by `.gitignore` and friends,
+/// `SKIPPED_DIRS` or `opts`. Walks the whole tree; meant for diagnostics
+/// (`ns why`), not indexing.
+pub fn is_walked(root: &Path, rel_path: &str, opts: &WalkOptions) -> bool {
+    let target = root.join(rel_path);
+    repo_walker(root, opts)
+        .flatten()
+        .any(|entry| entry.path() == target)
+}
+
+/// The first of the `--exclude` globs `patterns` that matches `rel_path` or
+/// one of the directories above it, as the walk would have skipped it. A
+/// trailing `/` is ignored, so `vendor/` excludes the directory `vendor`.
+pub fn excluded_by<'a>(rel_path: &str, patterns: &'a [String]) -> Option<&'a str> {
+    let rel_path = rel_path.replace('\\', "/");
+    let mut prefixes: Vec<&str> = rel_path.match_indices('/').map(|(i, _)| &rel_path[..i]).collect();
+    prefixes.push(&rel_path);
+    patterns
+        .iter()
+        .find(|p| {
+            Pattern::new(p.trim_end_matches('/'))
+                .is_ok_and(|pattern| prefixes.iter().any(|prefix| pattern.matches(prefix)))
+        })
+        .map(String::as_str)
+}
+
+/// The one of `roots` (see `WalkOptions::roots`) that `rel_path` lies in,
+/// `""` when `roots` is empty and the whole tree is walked.
+pub fn walk_root_of<'a>(rel_path: &str, roots: &'a [String]) -> Option<&'a str> {
+    if roots.is_empty() {
+        return Some("");
+    }
+    let rel_path = rel_path.replace('\\', "/");
+    roots
+        .iter()
+        .find(|root| {
+            let root = root.trim_end_matches('/');
+            rel_path.strip_prefix(root).is_some_and(|rest| rest.starts_with('/'))
+        })
+        .map(String::as_str)
+}
+
+/// Checks an `--exclude` glob. Used as a clap `value_parser`, hence the
+/// `String` error.
+pub fn parse_exclude(s: &str) -> Result<String, String> {
+    Pattern::new(s.trim_end_matches('/'))
+        .map(|_| s.to_string())
+        .map_err(|e| format!("invalid glob '{}': {}", s, e))
+}
+
+/// The `.gitignore`-aware walker over `root` shared by `walk_repo` and
+/// `is_walked`, configured from `opts` (the file size limit aside).
+fn repo_walker(root: &Path, opts: &WalkOptions) -> ignore::Walk {
+    let root_dir = root.to_path_buf();
+    let mut starts = opts.roots.iter().map(|dir| root.join(dir));
+    let mut builder = WalkBuilder::new(starts.next().unwrap_or_else(|| root.to_path_buf()));
+    for start in starts {
+        builder.add(start);
+    }
+    let exclude: Vec<Pattern> = opts
+        .exclude
+        .iter()
+        .filter_map(|p| Pattern::new(p.trim_end_matches('/')).ok())
+        .collect();
+    builder
+        .follow_links(opts.follow_symlinks)
+        .max_depth(opts.max_depth)
+        .hidden(false) // don't skip dotfiles (gitignore handles that)
+        .filter_entry(move |entry| {
+            let name = entry.file_name().to_string_lossy();
+            // Skip .git and .ns directories, including nested ones
+            if entry.file_type().map_or(false, |ft| ft.is_dir())
+                && SKIPPED_DIRS.contains(&name.as_ref())
+            {
+                return false;
+            }
+            if exclude.is_empty() {
+                return true;
+            }
+            let rel = entry.path().strip_prefix(&root_dir).unwrap_or(entry.path());
+            let rel = rel.to_string_lossy().replace('\\', "/");
+            !exclude.iter().any(|pattern| pattern.matches(&rel))
+        })
+        .build()
+}
+
 /// A file that has been read and is ready for indexing.
 pub struct WalkedFile {
     /// Path relative to the repo root.
     pub rel_path: String,
-    /// Full file content as a UTF-8 string.
+    /// File content as a UTF-8 string: all of it, or its head when
+    /// `truncated`.
     pub content: String,
+    /// Size of the whole file on disk, in bytes.
+    pub size: u64,
+    /// Only the head of the file was read (`WalkOptions::truncate_to`).
+    pub truncated: bool,
     /// Detected language identifier, or `None` if unknown/unsupported.
     pub lang: Option<String>,
+    /// Last modification time in seconds since the Unix epoch; 0 when the
+    /// platform doesn't report it.
+    pub mtime: u64,
+}
+
+/// Why the walk left a file out, as reported by `ns index --verbose`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SkipKind {
+    /// Larger than `WalkOptions::max_file_size`, and not truncated.
+    TooLarge,
+    /// A NUL byte in the first 512 bytes.
+    Binary,
+    NonUtf8,
+    /// Left out by `.gitignore`, `.ignore` or a global git exclude.
+    Ignored,
+    /// Matches an `--exclude` glob.
+    Excluded,
+}
+
+impl SkipKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SkipKind::TooLarge => "too large",
+            SkipKind::Binary => "binary",
+            SkipKind::NonUtf8 => "non-UTF-8",
+            SkipKind::Ignored => "ignored",
+            SkipKind::Excluded => "excluded",
+        }
+    }
+}
+
+/// A file or directory the walk left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedFile {
+    /// Path relative to the repo root; directories, whose contents were
+    /// not walked at all, end in `/`.
+    pub rel_path: String,
+    pub kind: SkipKind,
 }
 
 /// Walks the repository at `root`, returning indexable files.
 ///
 /// Skips:
 /// - Files ignored by `.gitignore`
-/// - `.git/` and `.ns/` directories
+/// - `SKIPPED_DIRS` (`.git/`, `.ns/`) at any depth
 /// - Binary files (null byte in first 512 bytes)
 /// - Files larger than `max_file_size`
 /// - Non-UTF-8 files
 pub fn walk_repo(root: &Path, max_file_size: u64) -> Vec<WalkedFile> {
-    let mut files = Vec::new();
+    walk_repo_with(
+        root,
+        &WalkOptions {
+            max_file_size,
+            ..WalkOptions::default()
+        },
+    )
+}
 
-    let walker = WalkBuilder::new(root)
-        .follow_links(false)
-        .hidden(false) // don't skip dotfiles (gitignore handles that)
-        .filter_entry(|entry| {
-            let name = entry.file_name().to_string_lossy();
-            // Skip .git and .ns directories
-            if entry.file_type().map_or(false, |ft| ft.is_dir()) {
-                return name != ".git" && name != ".ns";
-            }
-            true
-        })
-        .build();
+/// Like `walk_repo`, also skipping what `opts` excludes.
+pub fn walk_repo_with(root: &Path, opts: &WalkOptions) -> Vec<WalkedFile> {
+    walk(root, opts, None)
+}
+
+/// Like `walk_repo_with`, also returning what was left out, sorted by path.
+/// Ignored and excluded directories are listed once, not file by file.
+/// Costs an extra directory listing of everything walked.
+pub fn walk_repo_reporting(root: &Path, opts: &WalkOptions) -> (Vec<WalkedFile>, Vec<SkippedFile>) {
+    let mut skipped = Vec::new();
+    let files = walk(root, opts, Some(&mut skipped));
+    skipped.sort_by(|a, b| a.rel_path.cmp(&b.rel_path));
+    (files, skipped)
+}
 
-    for result in walker {
+fn walk(root: &Path, opts: &WalkOptions, mut skipped: Option<&mut Vec<SkippedFile>>) -> Vec<WalkedFile> {
+    let mut files: Vec<WalkedFile> = Vec::new();
+    // Real path of each file so far → its index in `files`, when following
+    // symlinks.
+    let mut seen: HashMap<PathBuf, usize> = HashMap::new();
+    let real_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    // Every entry the walk yielded, and the directories it listed, for
+    // finding what it didn't yield.
+    let mut yielded: HashSet<PathBuf> = HashSet::new();
+    let mut listed_dirs: Vec<PathBuf> = Vec::new();
+    let reporting = skipped.is_some();
+    let mut skip = |path: &Path, kind| {
+        if let Some(skipped) = skipped.as_deref_mut() {
+            skipped.push(SkippedFile {
+                rel_path: relative(root, path),
+                kind,
+            });
+        }
+    };
+
+    for result in repo_walker(root, opts) {
         let entry = match result {
             Ok(e) => e,
+            Err(err) if is_loop(&err) => {
+                eprintln!("warning: not following symlink loop: {}", err);
+                continue;
+            }
             Err(err) => {
                 eprintln!("warning: walk error: {}", err);
                 continue;
             }
         };
 
+        let path = entry.path();
+        if reporting {
+            yielded.insert(path.to_path_buf());
+            let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+            if is_dir && opts.max_depth.is_none_or(|max| entry.depth() < max) {
+                listed_dirs.push(path.to_path_buf());
+            }
+        }
+
         // Only process files
         if !entry.file_type().map_or(false, |ft| ft.is_file()) {
             continue;
         }
 
-        let path = entry.path();
-
-        // Check file size
+        // The size decides whether the file is read whole, in part or not at all
         let metadata = match path.metadata() {
             Ok(m) => m,
             Err(err) => {
@@ -62,51 +293,166 @@ pub fn walk_repo(root: &Path, max_file_size: u64) -> Vec<WalkedFile> {
                 continue;
             }
         };
-        if metadata.len() > max_file_size {
-            continue;
-        }
-
-        // Single read: max_file_size guard above caps memory usage.
-        // Check first 512 bytes for null bytes (binary detection) on the same buffer.
-        let raw = match std::fs::read(path) {
-            Ok(bytes) => bytes,
-            Err(err) => {
-                eprintln!("warning: cannot read {}: {}", path.display(), err);
+        let (content, truncated) = match read_text(path, metadata.len(), opts) {
+            Ok(FileText::Text { content, truncated }) => (content, truncated),
+            Ok(FileText::Skipped(kind)) => {
+                if kind == SkipKind::NonUtf8 {
+                    eprintln!("warning: skipping non-UTF-8 file: {}", path.display());
+                }
+                skip(path, kind);
                 continue;
             }
-        };
-        let check_len = raw.len().min(512);
-        if raw[..check_len].contains(&0) {
-            continue;
-        }
-
-        // UTF-8 check
-        let content = match String::from_utf8(raw) {
-            Ok(s) => s,
-            Err(_) => {
-                eprintln!("warning: skipping non-UTF-8 file: {}", path.display());
+            Err(err) => {
+                eprintln!("warning: cannot read {}: {}", path.display(), err);
                 continue;
             }
         };
 
-        // Compute relative path
-        let rel_path = match path.strip_prefix(root) {
-            Ok(rel) => rel.to_string_lossy().to_string(),
-            Err(_) => path.to_string_lossy().to_string(),
-        };
+        let rel_path = relative(root, path);
 
         let lang = detect_language(path).map(|s| s.to_string());
-
-        files.push(WalkedFile {
+        let file = WalkedFile {
             rel_path,
             content,
+            size: metadata.len(),
+            truncated,
             lang,
-        });
+            mtime: mtime_secs(&metadata),
+        };
+
+        if opts.follow_symlinks {
+            let Ok(real) = path.canonicalize() else {
+                continue;
+            };
+            if let Some(&i) = seen.get(&real) {
+                // Keep the path the file really has, else the first in
+                // path order, so repeated walks agree.
+                let is_real = |rel: &str| real_root.join(rel) == real;
+                let prefer = is_real(&file.rel_path)
+                    || (!is_real(&files[i].rel_path) && file.rel_path < files[i].rel_path);
+                if prefer {
+                    files[i] = file;
+                }
+                continue;
+            }
+            seen.insert(real, files.len());
+        }
+        files.push(file);
     }
 
+    if let Some(skipped) = skipped {
+        skipped.extend(unwalked_entries(root, &listed_dirs, &yielded, &opts.exclude));
+    }
     files
 }
 
+/// A file's text as indexed, or why it is left out.
+pub enum FileText {
+    Text { content: String, truncated: bool },
+    Skipped(SkipKind),
+}
+
+/// Reads the file at `path`, `size` bytes long, the way a walk with `opts`
+/// indexes it: whole, or only its head when it is over `max_file_size` and
+/// `truncate_to` is set. Binary and non-UTF-8 files are skipped; the
+/// binary check runs on the same buffer.
+pub fn read_text(path: &Path, size: u64, opts: &WalkOptions) -> std::io::Result<FileText> {
+    let truncated = size > opts.max_file_size;
+    let raw = match opts.truncate_to {
+        _ if !truncated => std::fs::read(path)?,
+        Some(limit) => read_head(path, limit)?,
+        None => return Ok(FileText::Skipped(SkipKind::TooLarge)),
+    };
+    if looks_binary(&raw) {
+        return Ok(FileText::Skipped(SkipKind::Binary));
+    }
+    match String::from_utf8(raw) {
+        Ok(content) => Ok(FileText::Text { content, truncated }),
+        Err(_) => Ok(FileText::Skipped(SkipKind::NonUtf8)),
+    }
+}
+
+/// The first `limit` bytes of the file at `path`, cut back to the end of
+/// the last full line, or to the last whole UTF-8 character when the head
+/// is a single line.
+pub(crate) fn read_head(path: &Path, limit: u64) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    std::fs::File::open(path)?.take(limit).read_to_end(&mut head)?;
+    match head.iter().rposition(|&b| b == b'\n') {
+        Some(end) => head.truncate(end + 1),
+        None => {
+            if let Err(err) = std::str::from_utf8(&head) {
+                // A character cut off at the end, not invalid UTF-8 inside.
+                if err.error_len().is_none() {
+                    head.truncate(err.valid_up_to());
+                }
+            }
+        }
+    }
+    Ok(head)
+}
+
+/// Path of `path` relative to `root`, or all of it when outside `root`.
+fn relative(root: &Path, path: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(rel) => rel.to_string_lossy().to_string(),
+        Err(_) => path.to_string_lossy().to_string(),
+    }
+}
+
+/// Entries of `dirs` the walk didn't yield: ignored, or matching one of
+/// the `exclude` globs. `SKIPPED_DIRS` are not reported.
+fn unwalked_entries(
+    root: &Path,
+    dirs: &[PathBuf],
+    yielded: &HashSet<PathBuf>,
+    exclude: &[String],
+) -> Vec<SkippedFile> {
+    let mut skipped = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            if yielded.contains(&path) || SKIPPED_DIRS.contains(&name.to_string_lossy().as_ref()) {
+                continue;
+            }
+            let rel_path = relative(root, &path).replace('\\', "/");
+            let kind = match excluded_by(&rel_path, exclude) {
+                Some(_) => SkipKind::Excluded,
+                None => SkipKind::Ignored,
+            };
+            let is_dir = entry.file_type().is_ok_and(|ft| ft.is_dir());
+            skipped.push(SkippedFile {
+                rel_path: if is_dir { format!("{}/", rel_path) } else { rel_path },
+                kind,
+            });
+        }
+    }
+    skipped
+}
+
+/// Whether `err` is a symlink pointing back at a directory being walked,
+/// possibly wrapped with the path or depth it was found at.
+fn is_loop(err: &ignore::Error) -> bool {
+    match err {
+        ignore::Error::Loop { .. } => true,
+        ignore::Error::WithPath { err, .. } | ignore::Error::WithDepth { err, .. } => is_loop(err),
+        _ => false,
+    }
+}
+
+/// Modification time of `metadata` in seconds since the Unix epoch, or 0.
+pub fn mtime_secs(metadata: &std::fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -139,6 +485,191 @@ mod tests {
         assert_eq!(md_file.lang, None);
     }
 
+    #[test]
+    fn skips_nested_index_and_git_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for (path, text) in [
+            ("src/lib.rs", "fn lib() {}"),
+            (".ns/meta.json", "{}"),
+            ("tools/cli/.ns/meta.json", "{}"),
+            ("tools/cli/.ns/search_log.jsonl", "{}"),
+            ("tools/cli/main.rs", "fn main() {}"),
+            ("vendor/dep/.git/HEAD", "ref: refs/heads/main"),
+        ] {
+            std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            std::fs::write(root.join(path), text).unwrap();
+        }
+
+        let mut paths: Vec<String> = walk_repo(root, 1_048_576)
+            .into_iter()
+            .map(|f| f.rel_path.replace('\\', "/"))
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["src/lib.rs", "tools/cli/main.rs"]);
+    }
+
+    #[test]
+    fn exclude_globs_skip_files_and_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for path in ["src/lib.rs", "src/app.min.js", "web/dist/bundle.js", "vendor/dep/lib.rs"] {
+            std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            std::fs::write(root.join(path), "x").unwrap();
+        }
+        let opts = WalkOptions {
+            exclude: vec!["*.min.js".to_string(), "vendor/".to_string(), "**/dist".to_string()],
+            ..WalkOptions::default()
+        };
+
+        let mut paths: Vec<String> = walk_repo_with(root, &opts)
+            .into_iter()
+            .map(|f| f.rel_path.replace('\\', "/"))
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["src/lib.rs"]);
+        assert_eq!(excluded_by("src/app.min.js", &opts.exclude), Some("*.min.js"));
+        assert_eq!(excluded_by("vendor/dep/lib.rs", &opts.exclude), Some("vendor/"));
+        assert_eq!(excluded_by("src/lib.rs", &opts.exclude), None);
+        assert!(parse_exclude("src/[").is_err());
+    }
+
+    #[test]
+    fn max_depth_limits_directory_levels() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for path in ["top.rs", "src/lib.rs", "src/deep/mod.rs"] {
+            std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            std::fs::write(root.join(path), "x").unwrap();
+        }
+        let walked = |max_depth| {
+            let opts = WalkOptions {
+                max_depth,
+                ..WalkOptions::default()
+            };
+            let mut paths: Vec<String> = walk_repo_with(root, &opts)
+                .into_iter()
+                .map(|f| f.rel_path.replace('\\', "/"))
+                .collect();
+            paths.sort();
+            paths
+        };
+        assert_eq!(walked(Some(1)), vec!["top.rs"]);
+        assert_eq!(walked(Some(2)), vec!["src/lib.rs", "top.rs"]);
+        assert_eq!(walked(None).len(), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follow_symlinks_skips_loops_and_duplicates() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let vendored = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "x").unwrap();
+        std::fs::write(vendored.path().join("dep.rs"), "y").unwrap();
+        symlink(vendored.path(), root.join("vendor")).unwrap();
+        symlink(root.join("src/lib.rs"), root.join("alias.rs")).unwrap();
+        symlink(root, root.join("src/loop")).unwrap();
+
+        let walked = |follow_symlinks| {
+            let opts = WalkOptions {
+                follow_symlinks,
+                ..WalkOptions::default()
+            };
+            let mut paths: Vec<String> = walk_repo_with(root, &opts)
+                .into_iter()
+                .map(|f| f.rel_path)
+                .collect();
+            paths.sort();
+            paths
+        };
+        assert_eq!(walked(false), vec!["src/lib.rs"]);
+        assert_eq!(walked(true), vec!["src/lib.rs", "vendor/dep.rs"]);
+        let follow = WalkOptions {
+            follow_symlinks: true,
+            ..WalkOptions::default()
+        };
+        assert!(is_walked(root, "vendor/dep.rs", &follow));
+        assert!(!is_walked(root, "vendor/dep.rs", &WalkOptions::default()));
+    }
+
+    #[test]
+    fn reporting_walk_lists_skipped_files_by_reason() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("target/debug")).unwrap();
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+        std::fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+        std::fs::write(root.join("big.rs"), "x".repeat(64)).unwrap();
+        std::fs::write(root.join("logo.png"), [0x89, b'P', 0, 0]).unwrap();
+        std::fs::write(root.join("latin1.txt"), [b'c', 0xe9]).unwrap();
+        std::fs::write(root.join("run.log"), "log").unwrap();
+        std::fs::write(root.join("target/debug/out"), "x").unwrap();
+        std::fs::write(root.join("docs/gen.md"), "x").unwrap();
+
+        let opts = WalkOptions {
+            max_file_size: 32,
+            exclude: vec!["docs/".to_string()],
+            ..WalkOptions::default()
+        };
+        let (files, skipped) = walk_repo_reporting(root, &opts);
+        let mut walked: Vec<&str> = files.iter().map(|f| f.rel_path.as_str()).collect();
+        walked.sort();
+        assert_eq!(walked, vec![".gitignore", "main.rs"]);
+        let skipped: Vec<(&str, SkipKind)> = skipped.iter().map(|s| (s.rel_path.as_str(), s.kind)).collect();
+        assert_eq!(
+            skipped,
+            vec![
+                ("big.rs", SkipKind::TooLarge),
+                ("docs/", SkipKind::Excluded),
+                ("latin1.txt", SkipKind::NonUtf8),
+                ("logo.png", SkipKind::Binary),
+                ("run.log", SkipKind::Ignored),
+                ("target/", SkipKind::Ignored),
+            ]
+        );
+    }
+
+    #[test]
+    fn roots_walk_only_the_listed_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for path in ["api/main.go", "web/src/app.ts", "scratch/notes.md", "top.md"] {
+            std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            std::fs::write(root.join(path), "x").unwrap();
+        }
+        let opts = WalkOptions {
+            roots: vec!["api".to_string(), "web".to_string()],
+            ..WalkOptions::default()
+        };
+        let mut paths: Vec<String> = walk_repo_with(root, &opts)
+            .into_iter()
+            .map(|f| f.rel_path.replace('\\', "/"))
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["api/main.go", "web/src/app.ts"]);
+        assert_eq!(walk_root_of("web/src/app.ts", &opts.roots), Some("web"));
+        assert_eq!(walk_root_of("webapp/x.ts", &opts.roots), None);
+        assert_eq!(walk_root_of("top.md", &[]), Some(""));
+    }
+
+    #[test]
+    fn skipped_dirs_match_whole_components_at_any_depth() {
+        assert!(in_skipped_dir(".ns"));
+        assert!(in_skipped_dir(".ns/meta.json"));
+        assert!(in_skipped_dir("tools/cli/.ns/meta.json"));
+        assert!(in_skipped_dir("tools\\cli\\.ns\\meta.json"));
+        assert!(in_skipped_dir("vendor/dep/.git/HEAD"));
+        assert!(!in_skipped_dir("src/.nsrc"));
+        assert!(!in_skipped_dir("docs/my.ns/notes.md"));
+        assert!(!in_skipped_dir("src/lib.rs"));
+    }
+
     #[test]
     fn skips_large_files() {
         let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -152,4 +683,34 @@ mod tests {
             files.len()
         );
     }
+
+    #[test]
+    fn truncate_to_indexes_the_head_of_large_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("big.txt"), "first line\nsecond line\nthird line\n").unwrap();
+        std::fs::write(root.join("one_line.txt"), "ééééééééééééééééééééééé").unwrap();
+        std::fs::write(root.join("small.txt"), "tiny\n").unwrap();
+
+        let opts = WalkOptions {
+            max_file_size: 16,
+            truncate_to: Some(17),
+            ..WalkOptions::default()
+        };
+        let mut files = walk_repo_with(root, &opts);
+        files.sort_by(|a, b| a.rel_path.cmp(&b.rel_path));
+        let heads: Vec<(&str, &str, bool)> = files
+            .iter()
+            .map(|f| (f.rel_path.as_str(), f.content.as_str(), f.truncated))
+            .collect();
+        assert_eq!(
+            heads,
+            vec![
+                ("big.txt", "first line\n", true),
+                ("one_line.txt", "éééééééé", true),
+                ("small.txt", "tiny\n", false),
+            ]
+        );
+        assert_eq!(files[0].size, 34, "size is the whole file's");
+    }
 }
@@ -3,96 +3,162 @@ use std::path::Path;
 use std::time::Instant;
 
 use serde::{Deserialize, Serialize};
-use tantivy::tokenizer::{LowerCaser, TextAnalyzer, WhitespaceTokenizer};
-use tantivy::{Index, IndexWriter, TantivyDocument};
+use tantivy::{Index, IndexSettings, IndexWriter};
 
 use crate::error::NsError;
-use crate::schema::{
-    build_schema, content_field, lang_field, path_field, symbols_field, symbols_raw_field,
-};
-
-use super::symbols::extract_symbols;
-use super::walker::WalkedFile;
+use crate::schema::build_schema;
+
+use super::churn::update_churn;
+use super::document::{file_documents, OptionalFields};
+use super::health::HealthGuard;
+use super::prune::{fit_index_budget, PrunedFile, StoredTrim};
+use super::storage::{create_index_in, open_index_in, FsStorage, IndexStorage};
+use super::symbol_stats::{symbol_stats, SymbolStats};
+use super::vectors::update_vectors;
+use super::walker::{SkippedFile, WalkedFile};
+use super::notify::touch_updated;
+use super::IndexOptions;
 
 /// Metadata written to `.ns/meta.json` after indexing.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct IndexMeta {
     pub schema_version: u32,
     pub indexed_at: String,
     pub git_commit: Option<String>,
     pub file_count: usize,
     pub index_size_bytes: u64,
+    /// Index size budget in bytes (`--max-index-size`), reapplied by incremental runs.
+    #[serde(default)]
+    pub max_index_size: Option<u64>,
+    /// Paths dropped to honour `max_index_size`. Incremental runs don't re-add them.
+    #[serde(default)]
+    pub pruned_paths: Vec<String>,
+    /// Whether `content_ngram` is filled (`ns index --ngram`), reapplied by incremental runs.
+    #[serde(default)]
+    pub ngram: bool,
+    /// Whether `content_stem` is filled (`ns index --stem`), reapplied by incremental runs.
+    #[serde(default)]
+    pub stem: bool,
+    /// Whether `content` uses the `code_cjk` tokenizer (`ns index --cjk`).
+    #[serde(default)]
+    pub cjk: bool,
+    /// Command run after each index write (`ns index --on-update`), kept across rebuilds.
+    #[serde(default)]
+    pub on_update: Option<String>,
+    /// Symbol counts as of the last index write; `None` for indexes written
+    /// before they were collected.
+    #[serde(default)]
+    pub symbol_stats: Option<SymbolStats>,
+    /// Churn window in days (`ns index --churn-days`), kept across rebuilds;
+    /// `None` when churn is off.
+    #[serde(default)]
+    pub churn_days: Option<u32>,
+    /// Embedding model directory (`ns index --embed-model`), kept across
+    /// rebuilds; `None` when embeddings are off.
+    #[serde(default)]
+    pub embed_model: Option<String>,
+    /// Globs of paths left out of the index (`ns index --exclude`), kept
+    /// across rebuilds and applied by incremental runs.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Walk depth limit (`ns index --max-depth`), kept across rebuilds and
+    /// applied by incremental runs; `None` when unlimited.
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    /// Whether the walk follows symlinks (`ns index --follow-symlinks`),
+    /// reapplied by incremental runs.
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    /// Directories indexed instead of the whole root (`ns index --root A
+    /// --root B`), relative to it; kept across rebuilds and walked by
+    /// incremental runs. Empty when the whole root is indexed.
+    #[serde(default)]
+    pub roots: Vec<String>,
+    /// KB indexed from the head of files over the size limit (`ns index
+    /// --truncate-large`), kept across rebuilds and applied by incremental
+    /// runs; `None` when such files are skipped.
+    #[serde(default)]
+    pub truncate_large_kb: Option<u64>,
+    /// Built with `ns index --no-symbols`: the symbol, doc comment and
+    /// import fields are empty, so search warns that symbol boost is off.
+    /// Kept by incremental runs.
+    #[serde(default)]
+    pub no_symbols: bool,
+    /// Stored fields left out to fit `max_index_size`; incremental runs
+    /// write new documents the same way.
+    #[serde(default)]
+    pub stored_trim: StoredTrim,
+    /// Files longer than this many lines are indexed as overlapping chunks
+    /// (`ns index --chunk-lines`), kept across rebuilds and applied by
+    /// incremental runs; `None` when every file is one document.
+    #[serde(default)]
+    pub chunk_lines: Option<usize>,
 }
 
 /// Current schema version. Bump when schema changes.
-pub const SCHEMA_VERSION: u32 = 2;
+pub const SCHEMA_VERSION: u32 = 19;
 
 /// Stats returned by a full index build.
 #[derive(Debug)]
 pub struct FullIndexStats {
     pub file_count: usize,
     pub elapsed_ms: u64,
+    /// Stored fields left out to fit `IndexOptions::max_index_size`, if any.
+    pub trimmed: Option<StoredTrim>,
+    /// Documents dropped to fit `IndexOptions::max_index_size`.
+    pub pruned: Vec<PrunedFile>,
+    /// What the walk left out, with `IndexOptions::verbose`.
+    pub skipped: Vec<SkippedFile>,
 }
 
-/// Registers the custom "symbol" tokenizer on a tantivy index.
-pub fn register_symbol_tokenizer(index: &Index) {
-    let tokenizer = TextAnalyzer::builder(WhitespaceTokenizer::default())
-        .filter(LowerCaser)
-        .build();
-    index.tokenizers().register("symbol", tokenizer);
+/// Hash of a file's bytes as stored in the `content_hash` field.
+pub(crate) fn content_hash(bytes: &[u8]) -> u64 {
+    xxhash_rust::xxh3::xxh3_64(bytes)
 }
 
 /// Builds the tantivy index from walked files.
 ///
 /// Creates `.ns/index/` directory, writes documents, commits, and writes `meta.json`.
-/// Returns index stats (file count, elapsed time). Does not print to stderr.
-pub fn build_index(root: &Path, files: &[WalkedFile]) -> Result<FullIndexStats, NsError> {
+/// If `opts.max_index_size` is set and exceeded, prunes documents until it fits.
+/// Returns index stats (file count, elapsed time). Prints nothing to stderr
+/// but the `opts.verbose` progress bar.
+pub fn build_index(
+    root: &Path,
+    files: &[WalkedFile],
+    opts: &IndexOptions,
+) -> Result<FullIndexStats, NsError> {
     let ns_dir = root.join(".ns");
-    let index_dir = ns_dir.join("index");
-
-    // Wipe existing index for a clean full rebuild.
-    // create_in_dir requires an empty (or non-existent) directory.
-    if index_dir.exists() {
-        fs::remove_dir_all(&index_dir)?;
-    }
-    fs::create_dir_all(&index_dir)?;
+    let storage = FsStorage::for_root(root);
 
-    let schema = build_schema();
-    let index = Index::create_in_dir(&index_dir, schema.clone())?;
-    register_symbol_tokenizer(&index);
+    let previous = read_meta(root).ok();
+    let chunk_lines = resolve_chunk_lines(opts, previous.as_ref().and_then(|m| m.chunk_lines));
 
-    let content = content_field(&schema);
-    let symbols = symbols_field(&schema);
-    let symbols_raw = symbols_raw_field(&schema);
-    let path = path_field(&schema);
-    let lang = lang_field(&schema);
-
-    // 50 MB heap for the writer
-    let mut writer: IndexWriter = index.writer(50_000_000)?;
+    // Wipes any existing index for a clean full rebuild.
+    let schema = build_schema(opts.cjk);
+    let settings = IndexSettings {
+        docstore_compression: opts.compression.compressor(),
+        ..IndexSettings::default()
+    };
+    let index = create_index_in(&storage, schema.clone(), settings)?;
+    let mut health = HealthGuard::start(root, "full", files.len()).with_progress_bar(opts.verbose);
+
+    let mut writer: IndexWriter = opts.writer(&index)?;
+    let fill = OptionalFields {
+        ngram: opts.ngram,
+        stem: opts.stem,
+        cjk: opts.cjk,
+        symbols: !opts.no_symbols,
+        trim: StoredTrim::None,
+        chunk_lines,
+    };
 
     let start = Instant::now();
 
-    for file in files {
-        let mut doc = TantivyDocument::new();
-        doc.add_text(content, &file.content);
-
-        // Extract symbols via tree-sitter for supported languages
-        let symbol_names = file
-            .lang
-            .as_deref()
-            .map(|l| extract_symbols(l, file.content.as_bytes()))
-            .unwrap_or_default();
-
-        // symbols: space-separated for tokenized search
-        doc.add_text(symbols, &symbol_names.join(" "));
-        // symbols_raw: pipe-separated, original casing, for display
-        doc.add_text(symbols_raw, &symbol_names.join("|"));
-
-        doc.add_text(path, &file.rel_path);
-        if let Some(ref lang_str) = file.lang {
-            doc.add_text(lang, lang_str);
+    for (i, file) in files.iter().enumerate() {
+        for doc in file_documents(&schema, file, fill) {
+            writer.add_document(doc)?;
         }
-        writer.add_document(doc)?;
+        health.progress(i + 1);
     }
 
     writer.commit()?;
@@ -104,14 +170,41 @@ pub fn build_index(root: &Path, files: &[WalkedFile]) -> Result<FullIndexStats,
         .wait_merging_threads()
         .map_err(|e| NsError::Tantivy(e))?;
 
+    let (stored_trim, pruned) = match opts.max_index_size {
+        Some(budget) => fit_index_budget(root, &index, opts, budget, StoredTrim::None, |writer, trim| {
+            writer.delete_all_documents()?;
+            for file in files {
+                for doc in file_documents(&schema, file, OptionalFields { trim, ..fill }) {
+                    writer.add_document(doc)?;
+                }
+            }
+            Ok(())
+        })?,
+        None => (StoredTrim::None, Vec::new()),
+    };
+
     let elapsed = start.elapsed();
-    let file_count = files.len();
+    let file_count = files.len() - pruned.len();
 
     // Calculate index size
-    let index_size = dir_size(&index_dir);
+    let index_size = storage.size_bytes();
 
     // Get current git commit
     let git_commit = get_git_commit(root);
+    let on_update = resolve_on_update(opts, previous.as_ref().and_then(|m| m.on_update.clone()));
+    let churn_days = resolve_churn_days(opts, previous.as_ref().and_then(|m| m.churn_days));
+    update_churn(root, churn_days)?;
+    let exclude = resolve_exclude(
+        opts,
+        previous.as_ref().map(|m| m.exclude.clone()).unwrap_or_default(),
+    );
+    let max_depth = resolve_max_depth(opts, previous.as_ref().and_then(|m| m.max_depth));
+    let roots = resolve_roots(opts, previous.as_ref().map(|m| m.roots.clone()).unwrap_or_default());
+    let truncate_large_kb =
+        resolve_truncate_large_kb(opts, previous.as_ref().and_then(|m| m.truncate_large_kb));
+    let embed_model = resolve_embed_model(opts, previous.and_then(|m| m.embed_model));
+    update_vectors(root, &index, embed_model.as_deref())?;
+    let symbol_stats = symbol_stats(&index)?;
 
     // Write meta.json
     let meta = IndexMeta {
@@ -120,18 +213,120 @@ pub fn build_index(root: &Path, files: &[WalkedFile]) -> Result<FullIndexStats,
         git_commit,
         file_count,
         index_size_bytes: index_size,
+        max_index_size: opts.max_index_size,
+        pruned_paths: pruned.iter().map(|p| p.path.clone()).collect(),
+        ngram: opts.ngram,
+        stem: opts.stem,
+        cjk: opts.cjk,
+        on_update,
+        symbol_stats: Some(symbol_stats),
+        churn_days,
+        embed_model,
+        exclude,
+        max_depth,
+        follow_symlinks: opts.follow_symlinks,
+        roots,
+        truncate_large_kb,
+        no_symbols: opts.no_symbols,
+        stored_trim,
+        chunk_lines,
     };
 
     let meta_path = ns_dir.join("meta.json");
     let meta_json = serde_json::to_string(&meta)?;
     fs::write(&meta_path, &meta_json)?;
+    touch_updated(root, &meta.indexed_at)?;
 
     Ok(FullIndexStats {
         file_count,
         elapsed_ms: elapsed.as_millis() as u64,
+        trimmed: (stored_trim != StoredTrim::None).then_some(stored_trim),
+        pruned,
+        skipped: Vec::new(),
     })
 }
 
+/// The `--on-update` command to remember: the one given in `opts` (an empty
+/// string clears it), else `previous`.
+pub(crate) fn resolve_on_update(opts: &IndexOptions, previous: Option<String>) -> Option<String> {
+    match opts.on_update {
+        Some(ref command) if command.is_empty() => None,
+        Some(ref command) => Some(command.clone()),
+        None => previous,
+    }
+}
+
+/// The churn window to use: the one given in `opts` (0 turns churn off),
+/// else `previous`.
+pub(crate) fn resolve_churn_days(opts: &IndexOptions, previous: Option<u32>) -> Option<u32> {
+    match opts.churn_days {
+        Some(0) => None,
+        Some(days) => Some(days),
+        None => previous,
+    }
+}
+
+/// The embedding model to use: the one given in `opts` (an empty string
+/// turns embeddings off), else `previous`.
+pub(crate) fn resolve_embed_model(opts: &IndexOptions, previous: Option<String>) -> Option<String> {
+    match opts.embed_model {
+        Some(ref model) if model.is_empty() => None,
+        Some(ref model) => Some(model.clone()),
+        None => previous,
+    }
+}
+
+/// The `--exclude` globs to apply: the ones given in `opts` (a single empty
+/// glob clears them), else `previous`.
+pub(crate) fn resolve_exclude(opts: &IndexOptions, previous: Vec<String>) -> Vec<String> {
+    if opts.exclude.is_empty() {
+        return previous;
+    }
+    opts.exclude.iter().filter(|g| !g.is_empty()).cloned().collect()
+}
+
+/// The directories to index: the ones given in `opts` (an empty one, the
+/// whole tree, clears them), else `previous`.
+pub(crate) fn resolve_roots(opts: &IndexOptions, previous: Vec<String>) -> Vec<String> {
+    if opts.roots.is_empty() {
+        return previous;
+    }
+    if opts.roots.iter().any(|r| r.is_empty()) {
+        return Vec::new();
+    }
+    opts.roots.clone()
+}
+
+/// The walk depth limit to use: the one given in `opts` (0 removes it),
+/// else `previous`.
+pub(crate) fn resolve_max_depth(opts: &IndexOptions, previous: Option<usize>) -> Option<usize> {
+    match opts.max_depth {
+        Some(0) => None,
+        Some(depth) => Some(depth),
+        None => previous,
+    }
+}
+
+/// The head size for oversized files: the one given in `opts` (0 skips
+/// them again), else `previous`.
+pub(crate) fn resolve_truncate_large_kb(opts: &IndexOptions, previous: Option<u64>) -> Option<u64> {
+    match opts.truncate_large_kb {
+        Some(0) => None,
+        Some(kb) => Some(kb),
+        None => previous,
+    }
+}
+
+/// The chunk length for long files: the one given in `opts` (0 turns
+/// chunking off), else `previous`.
+pub(crate) fn resolve_chunk_lines(opts: &IndexOptions, previous: Option<usize>) -> Option<usize> {
+    match opts.chunk_lines {
+        Some(0) => None,
+        Some(lines) => Some(lines),
+        None => previous,
+    }
+}
+
 /// Opens an existing index at `.ns/index/` for reading or incremental writes.
 ///
 /// Reads `meta.json` once and returns it alongside the index, so callers
@@ -149,10 +344,7 @@ pub fn open_index(root: &Path) -> Result<(Index, IndexMeta), NsError> {
         });
     }
 
-    let index_dir = root.join(".ns").join("index");
-    let index = Index::open_in_dir(&index_dir)?;
-
-    register_symbol_tokenizer(&index);
+    let index = open_index_in(&FsStorage::for_root(root))?;
     Ok((index, meta))
 }
 
@@ -202,7 +394,11 @@ pub(crate) fn utc_timestamp_iso8601() -> String {
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs();
+    iso8601_at(secs)
+}
 
+/// `secs` since the Unix epoch as `YYYY-MM-DDTHH:MM:SSZ`.
+pub(crate) fn iso8601_at(secs: u64) -> String {
     // Manual UTC breakdown — avoids pulling in chrono/time crate
     let days = secs / 86400;
     let time_of_day = secs % 86400;
@@ -1,5 +1,9 @@
+pub mod bench;
+pub mod config;
 pub mod error;
+pub mod export;
 pub mod indexer;
+pub mod registry;
 pub mod schema;
 pub mod searcher;
 pub mod stats;
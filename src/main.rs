@@ -1,6 +1,10 @@
+mod bench;
 mod cmd;
+mod config;
 mod error;
+mod export;
 mod indexer;
+mod registry;
 mod schema;
 mod searcher;
 mod stats;
@@ -25,8 +29,28 @@ fn main() {
             cmd::search::run(&args, &argv);
         }
         Some(Command::Index(args)) => cmd::index::run(args),
-        Some(Command::Status) => cmd::status::run(),
+        Some(Command::Watch(args)) => cmd::watch::run(args),
+        Some(Command::Status(args)) => cmd::status::run(args),
+        Some(Command::Stats(args)) => cmd::stats::run(args),
+        Some(Command::Log(args)) => cmd::log::run(args),
+        Some(Command::Ls(args)) => cmd::ls::run(args, &cli),
+        Some(Command::Symbols(args)) => cmd::symbols::run(args, &cli),
+        Some(Command::Def(args)) => cmd::def::run(args, &cli),
+        Some(Command::Refs(args)) => cmd::refs::run(args, &cli),
+        Some(Command::Outline(args)) => cmd::outline::run(args, &cli),
         Some(Command::Hooks { action }) => cmd::hooks::run(action),
+        Some(Command::Export(args)) => cmd::export::run(args),
+        Some(Command::Freq(args)) => cmd::freq::run(args),
+        Some(Command::Complete(args)) => cmd::complete::run(args),
+        Some(Command::Doctor(args)) => cmd::doctor::run(args),
+        Some(Command::Audit(args)) => cmd::audit::run(args, &cli),
+        Some(Command::Tune(args)) => cmd::tune::run(args),
+        Some(Command::Bench(args)) => cmd::bench::run(args),
+        Some(Command::Repl) => cmd::repl::run(&cli),
+        Some(Command::Tui(args)) => cmd::tui::run(args, &cli),
+        Some(Command::Check(args)) => cmd::check::run(args),
+        Some(Command::Why(args)) => cmd::why::run(args),
+        Some(Command::Repos { action }) => cmd::repos::run(action),
         None => {
             // Default mode: search
             match &cli.query {
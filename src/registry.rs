@@ -0,0 +1,235 @@
+//! Global registry of indexed repositories (`~/.config/ns/repos.toml`).
+//!
+//! `ns index` records each repo it indexes here, so `ns repos` can list them
+//! and refresh them all at once. Registry updates are best-effort: a missing
+//! or read-only config directory never fails an index run.
+
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use fs4::fs_std::FileExt;
+use serde::{Deserialize, Serialize};
+
+use crate::error::NsError;
+use crate::indexer::writer::IndexMeta;
+
+/// One registered repository.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RepoEntry {
+    /// Short unique name (the directory name, suffixed on collision).
+    pub name: String,
+    /// Canonical repo root.
+    pub path: PathBuf,
+    /// `indexed_at` from the repo's `meta.json` at the last recorded index.
+    #[serde(default)]
+    pub last_indexed: Option<String>,
+    #[serde(default)]
+    pub file_count: usize,
+}
+
+/// Contents of `repos.toml`.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Registry {
+    #[serde(default, rename = "repo")]
+    pub repos: Vec<RepoEntry>,
+}
+
+/// Returns `$XDG_CONFIG_HOME/ns/repos.toml`, falling back to `~/.config/ns/repos.toml`.
+pub fn default_registry_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("ns").join("repos.toml"))
+}
+
+impl Registry {
+    /// Reads the registry at `path`. A missing file is an empty registry.
+    pub fn load(path: &Path) -> Result<Registry, NsError> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(toml::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Registry::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the registry to `path` atomically, creating parent directories.
+    pub fn save(&self, path: &Path) -> Result<(), NsError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let tmp_path = path.with_extension("toml.tmp");
+        fs::write(&tmp_path, content)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Inserts or updates the entry for `root` from its index metadata.
+    pub fn upsert(&mut self, root: &Path, meta: &IndexMeta) {
+        if let Some(entry) = self.repos.iter_mut().find(|r| r.path == root) {
+            entry.last_indexed = Some(meta.indexed_at.clone());
+            entry.file_count = meta.file_count;
+            return;
+        }
+        let name = self.unique_name(root);
+        self.repos.push(RepoEntry {
+            name,
+            path: root.to_path_buf(),
+            last_indexed: Some(meta.indexed_at.clone()),
+            file_count: meta.file_count,
+        });
+    }
+
+    /// Removes entries whose repo or index no longer exists. Returns the removed entries.
+    pub fn gc(&mut self) -> Vec<RepoEntry> {
+        let (keep, removed): (Vec<RepoEntry>, Vec<RepoEntry>) = self
+            .repos
+            .drain(..)
+            .partition(|r| r.path.join(".ns").join("meta.json").is_file());
+        self.repos = keep;
+        removed
+    }
+
+    /// Looks up a repo by registered name.
+    pub fn find(&self, name: &str) -> Option<&RepoEntry> {
+        self.repos.iter().find(|r| r.name == name)
+    }
+
+    fn unique_name(&self, root: &Path) -> String {
+        let base = root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "repo".to_string());
+        let mut name = base.clone();
+        let mut n = 2;
+        while self.find(&name).is_some() {
+            name = format!("{}-{}", base, n);
+            n += 1;
+        }
+        name
+    }
+}
+
+/// Loads, modifies and saves the registry at `path` under an exclusive lock.
+pub fn update_registry<T>(
+    path: &Path,
+    f: impl FnOnce(&mut Registry) -> T,
+) -> Result<T, NsError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .truncate(false)
+        .open(path.with_extension("lock"))?;
+    lock_file.lock_exclusive()?;
+
+    let result = (|| {
+        let mut registry = Registry::load(path)?;
+        let value = f(&mut registry);
+        registry.save(path)?;
+        Ok(value)
+    })();
+
+    let _ = lock_file.unlock();
+    result
+}
+
+/// Records a completed index of `root` in the default registry. Best-effort:
+/// errors (no home directory, read-only config, corrupt file) are ignored.
+pub fn record_index(root: &Path, meta: &IndexMeta) {
+    if let Some(path) = default_registry_path() {
+        let _ = update_registry(&path, |registry| registry.upsert(root, meta));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(file_count: usize) -> IndexMeta {
+        IndexMeta {
+            schema_version: 2,
+            indexed_at: "2026-03-01T12:00:00Z".to_string(),
+            git_commit: None,
+            file_count,
+            index_size_bytes: 0,
+            max_index_size: None,
+            pruned_paths: Vec::new(),
+            ngram: false,
+            stem: false,
+            cjk: false,
+            on_update: None,
+            symbol_stats: None,
+            churn_days: None,
+            embed_model: None,
+            exclude: Vec::new(),
+            max_depth: None,
+            follow_symlinks: false,
+            roots: Vec::new(),
+            truncate_large_kb: None,
+            no_symbols: false,
+            stored_trim: Default::default(),
+            chunk_lines: None,
+        }
+    }
+
+    #[test]
+    fn upsert_adds_then_updates() {
+        let mut registry = Registry::default();
+        registry.upsert(Path::new("/work/api"), &meta(10));
+        registry.upsert(Path::new("/work/api"), &meta(12));
+
+        assert_eq!(registry.repos.len(), 1);
+        assert_eq!(registry.repos[0].name, "api");
+        assert_eq!(registry.repos[0].file_count, 12);
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let mut registry = Registry::default();
+        registry.upsert(Path::new("/work/a/api"), &meta(1));
+        registry.upsert(Path::new("/work/b/api"), &meta(1));
+        registry.upsert(Path::new("/work/c/api"), &meta(1));
+
+        let names: Vec<&str> = registry.repos.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["api", "api-2", "api-3"]);
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ns").join("repos.toml");
+
+        assert_eq!(Registry::load(&path).unwrap(), Registry::default());
+
+        update_registry(&path, |r| r.upsert(Path::new("/work/api"), &meta(3))).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("[[repo]]"), "got: {}", content);
+
+        let loaded = Registry::load(&path).unwrap();
+        assert_eq!(loaded.repos[0].path, PathBuf::from("/work/api"));
+        assert_eq!(loaded.repos[0].last_indexed.as_deref(), Some("2026-03-01T12:00:00Z"));
+    }
+
+    #[test]
+    fn gc_drops_repos_without_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let live = dir.path().join("live");
+        fs::create_dir_all(live.join(".ns")).unwrap();
+        fs::write(live.join(".ns/meta.json"), "{}").unwrap();
+
+        let mut registry = Registry::default();
+        registry.upsert(&live, &meta(1));
+        registry.upsert(&dir.path().join("gone"), &meta(1));
+
+        let removed = registry.gc();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].name, "gone");
+        assert_eq!(registry.repos.len(), 1);
+    }
+}
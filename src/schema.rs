@@ -1,31 +1,71 @@
+use crate::indexer::tokenizer::{
+    CODE_CJK_TOKENIZER, CODE_TOKENIZER, NGRAM_TOKENIZER, STEM_TOKENIZER, SYMBOL_TOKENIZER,
+};
 use tantivy::schema::{
-    Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, STRING, STORED,
+    BytesOptions, Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, FAST,
+    INDEXED, STORED, STRING,
 };
 
 /// Builds the Tantivy schema for the nanosearch index.
 ///
 /// Fields:
-/// - `content`: full text of the file, indexed with default tokenizer, not stored
+/// - `content`: full text of the file, indexed with custom "code" tokenizer, not stored
 /// - `symbols`: extracted symbol names, indexed with custom "symbol" tokenizer, not stored
 /// - `symbols_raw`: raw symbol string, untokenized and stored (for display)
+/// - `symbols_def`: definition line of each symbol, stored only (for display)
 /// - `path`: file path relative to repo root, untokenized and stored
 /// - `lang`: detected language name, untokenized and stored
-pub fn build_schema() -> Schema {
+/// - `content_hash`: xxh3 hash of the file bytes, stored (for staleness checks)
+/// - `is_test`: 1 for test files, 0 otherwise, indexed and stored (for
+///   downweighting tests)
+/// - `content_ngram`: lowercased character trigrams of the file, doc ids only,
+///   filled only by `ns index --ngram` (for `--substring`)
+/// - `content_stem`: file content, Porter-stemmed, filled only by
+///   `ns index --stem` (for `--stem`)
+/// - `minhash`: MinHash signature of the file's word shingles, stored (for
+///   collapsing near-duplicate results)
+/// - `line_starts`: token position each line starts at, stored (for
+///   reporting matching lines from the postings)
+/// - `symbol_spans`: first and last line of each symbol's definition, stored
+///   (for `--granularity symbol`)
+/// - `symbol_kinds`: what each symbol's definition declares, stored (for
+///   `--kind`, ctags export and re-ranking)
+/// - `docs`: doc comments and docstrings of the file, indexed with the same
+///   tokenizer as `content`, not stored (for matching documentation prose)
+/// - `imports`: modules the file imports, one per line, indexed with the
+///   "code" tokenizer and stored (for `imports:<module>` queries)
+/// - `filename`: the file's name without extension, indexed with the "code"
+///   tokenizer, not stored (for matching files by name)
+/// - `size`, `mtime`: the file's size in bytes and last modification time
+///   in Unix seconds when indexed, indexed, fast and stored (for
+///   `--larger-than`, `--smaller-than` and `--modified-since`)
+/// - `truncated`: 1 when only the head of an oversized file was indexed
+///   (`ns index --truncate-large`), 0 otherwise, indexed and stored
+/// - `generated`: 1 for lockfiles, bundles, source maps and files marked as
+///   generated, 0 otherwise, indexed and stored (for downweighting them)
+///
+/// With `cjk` (`ns index --cjk`), `content` uses the "code_cjk" tokenizer,
+/// which also splits Chinese/Japanese/Korean text into character bigrams.
+/// The tokenizer name is saved with the index, so queries and incremental
+/// updates pick it up without further configuration.
+pub fn build_schema(cjk: bool) -> Schema {
     let mut builder = Schema::builder();
 
-    // content: TEXT indexed with default tokenizer, positions for BM25, not stored
+    // content: TEXT indexed with custom "code" tokenizer (words plus their
+    // camelCase/snake_case parts, lowercase), positions for BM25 and phrases, not stored
     let content_options = TextOptions::default().set_indexing_options(
         TextFieldIndexing::default()
-            .set_tokenizer("default")
+            .set_tokenizer(if cjk { CODE_CJK_TOKENIZER } else { CODE_TOKENIZER })
             .set_index_option(IndexRecordOption::WithFreqsAndPositions),
     );
     builder.add_text_field("content", content_options);
 
-    // symbols: TEXT indexed with custom "symbol" tokenizer (whitespace + lowercase),
-    // positions for BM25, not stored. The tokenizer itself is registered at index open time.
+    // symbols: TEXT indexed with custom "symbol" tokenizer (whitespace-separated names
+    // plus their parts, lowercase), positions for BM25, not stored. Both tokenizers are
+    // registered at index open time (see `indexer::tokenizer`).
     let symbols_options = TextOptions::default().set_indexing_options(
         TextFieldIndexing::default()
-            .set_tokenizer("symbol")
+            .set_tokenizer(SYMBOL_TOKENIZER)
             .set_index_option(IndexRecordOption::WithFreqsAndPositions),
     );
     builder.add_text_field("symbols", symbols_options);
@@ -36,12 +76,127 @@ pub fn build_schema() -> Schema {
     // `symbols` TEXT field above. This avoids indexing overhead for a display-only field.
     builder.add_text_field("symbols_raw", STRING | STORED);
 
+    // symbols_def: STORED only — the trimmed source line each symbol in
+    // `symbols_raw` is defined on, newline-separated in the same order
+    // (`pub struct EventStore {`), so JSON results can show signatures
+    // without reading the file. Not indexed.
+    builder.add_text_field("symbols_def", STORED);
+
     // path: STRING (untokenized) | STORED — used for delete_term in incremental indexing
     builder.add_text_field("path", STRING | STORED);
 
     // lang: STRING (untokenized) | STORED
     builder.add_text_field("lang", STRING | STORED);
 
+    // content_hash: u64 | STORED — compared against the working tree by
+    // `ns doctor --verify-content`. Not indexed; never searched.
+    builder.add_u64_field("content_hash", STORED);
+
+    // is_test: u64 INDEXED | STORED — 1 when the path looks like a test file
+    // (`language::is_test_path`), else 0. Queried as a term to downweight
+    // tests at search time; stored so results can report it.
+    builder.add_u64_field("is_test", INDEXED | STORED);
+
+    // content_ngram: TEXT indexed with the "ngram3" tokenizer (character trigrams,
+    // lowercase), doc ids only — trigram positions are meaningless, and matches are
+    // confirmed against the file anyway. Empty unless the index was built with --ngram.
+    let ngram_options = TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(NGRAM_TOKENIZER)
+            .set_index_option(IndexRecordOption::Basic),
+    );
+    builder.add_text_field("content_ngram", ngram_options);
+
+    // content_stem: TEXT indexed with the "code_stem" tokenizer (code tokens run
+    // through an English Porter stemmer). Positions are kept so compound query
+    // words still become phrases. Empty unless the index was built with --stem.
+    let stem_options = TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(STEM_TOKENIZER)
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+    );
+    builder.add_text_field("content_stem", stem_options);
+
+    // minhash: bytes | STORED — `indexer::minhash` signature, little-endian
+    // u32 slots. Compared between results at search time; never searched.
+    builder.add_bytes_field("minhash", BytesOptions::default().set_stored());
+
+    // line_starts: bytes | STORED — `indexer::lines` varint deltas, one per
+    // line. Maps `content` positions to line numbers; never searched.
+    builder.add_bytes_field("line_starts", BytesOptions::default().set_stored());
+
+    // symbol_spans: bytes | STORED — `symbols::spans_to_bytes` varints, the
+    // line range of each symbol in `symbols_raw`, same order. Lets results
+    // be split into symbols without parsing the file; never searched.
+    builder.add_bytes_field("symbol_spans", BytesOptions::default().set_stored());
+
+    // symbol_kinds: bytes | STORED — `symbols::kinds_to_bytes`, one byte per
+    // symbol in `symbols_raw`, same order: what each definition declares,
+    // from its syntax node. Read by `--kind` and the re-ranker; never searched.
+    builder.add_bytes_field("symbol_kinds", BytesOptions::default().set_stored());
+
+    // docs: TEXT indexed with the `content` tokenizer, positions for BM25 and
+    // phrases, not stored — the doc comments, docstrings and JSDoc blocks of
+    // the file (`indexer::docs`), newline-joined. They are in `content` too;
+    // this field lets queries weigh documentation prose on its own.
+    let docs_options = TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(if cjk { CODE_CJK_TOKENIZER } else { CODE_TOKENIZER })
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+    );
+    builder.add_text_field("docs", docs_options);
+
+    // imports: TEXT indexed with the "code" tokenizer, positions for phrases,
+    // and STORED — the module paths of the file's import statements
+    // (`indexer::imports`), one per line as written (`tokio::sync::mpsc`,
+    // `./store`). Searched as `imports:tokio`; never a default query field.
+    let imports_options = TextOptions::default()
+        .set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(CODE_TOKENIZER)
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        )
+        .set_stored();
+    builder.add_text_field("imports", imports_options);
+
+    // filename: TEXT indexed with the "code" tokenizer, not stored — the
+    // file's name without its extension (`language::filename_stem`), so
+    // `rule_validator` yields `rule_validator`, `rule` and `validator`.
+    // Positions because compound query words become phrases. A default
+    // query field: `validator` finds `rule_validator.py` even when the word
+    // never appears in it.
+    let filename_options = TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(CODE_TOKENIZER)
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+    );
+    builder.add_text_field("filename", filename_options);
+
+    // size, mtime: u64 INDEXED | FAST | STORED — file size in bytes and
+    // modification time in Unix seconds, as of indexing. Range-queried by
+    // the search filters; fast so ranges over many files stay cheap, and
+    // stored for display.
+    builder.add_u64_field("size", INDEXED | FAST | STORED);
+    builder.add_u64_field("mtime", INDEXED | FAST | STORED);
+
+    // truncated: u64 INDEXED | STORED — 1 when the file was over
+    // `--max-file-size` and only its head was indexed, else 0. `size` is
+    // still the whole file's. Stored to flag such results, indexed so
+    // `truncated:1` lists them.
+    builder.add_u64_field("truncated", INDEXED | STORED);
+
+    // generated: u64 INDEXED | STORED — 1 when the file looks machine-written
+    // (`generated::is_generated`), else 0. Queried as a term to downweight
+    // generated files, like `is_test`.
+    builder.add_u64_field("generated", INDEXED | STORED);
+
+    // chunk_line: u64 INDEXED | STORED — 0-based line of the file the
+    // document starts at. 0 for whole files; files over `--chunk-lines` are
+    // indexed as several overlapping chunks (`indexer::chunks`) sharing one
+    // path. Stored to map match lines back to the file, indexed so
+    // `chunk_line:0` counts each file once.
+    builder.add_u64_field("chunk_line", INDEXED | STORED);
+
     builder.build()
 }
 
@@ -66,6 +221,13 @@ pub fn symbols_raw_field(schema: &Schema) -> Field {
         .expect("schema missing 'symbols_raw' field")
 }
 
+/// Returns the `symbols_def` field handle.
+pub fn symbols_def_field(schema: &Schema) -> Field {
+    schema
+        .get_field("symbols_def")
+        .expect("schema missing 'symbols_def' field")
+}
+
 /// Returns the `path` field handle.
 pub fn path_field(schema: &Schema) -> Field {
     schema
@@ -80,25 +242,154 @@ pub fn lang_field(schema: &Schema) -> Field {
         .expect("schema missing 'lang' field")
 }
 
+/// Returns the `content_hash` field handle.
+pub fn content_hash_field(schema: &Schema) -> Field {
+    schema
+        .get_field("content_hash")
+        .expect("schema missing 'content_hash' field")
+}
+
+/// Returns the `is_test` field handle.
+pub fn is_test_field(schema: &Schema) -> Field {
+    schema
+        .get_field("is_test")
+        .expect("schema missing 'is_test' field")
+}
+
+/// Returns the `content_ngram` field handle.
+pub fn content_ngram_field(schema: &Schema) -> Field {
+    schema
+        .get_field("content_ngram")
+        .expect("schema missing 'content_ngram' field")
+}
+
+/// Returns the `content_stem` field handle.
+pub fn content_stem_field(schema: &Schema) -> Field {
+    schema
+        .get_field("content_stem")
+        .expect("schema missing 'content_stem' field")
+}
+
+/// Returns the `minhash` field handle.
+pub fn minhash_field(schema: &Schema) -> Field {
+    schema
+        .get_field("minhash")
+        .expect("schema missing 'minhash' field")
+}
+
+/// Returns the `line_starts` field handle.
+pub fn line_starts_field(schema: &Schema) -> Field {
+    schema
+        .get_field("line_starts")
+        .expect("schema missing 'line_starts' field")
+}
+
+/// Returns the `symbol_spans` field handle.
+pub fn symbol_spans_field(schema: &Schema) -> Field {
+    schema
+        .get_field("symbol_spans")
+        .expect("schema missing 'symbol_spans' field")
+}
+
+/// Returns the `symbol_kinds` field handle.
+pub fn symbol_kinds_field(schema: &Schema) -> Field {
+    schema
+        .get_field("symbol_kinds")
+        .expect("schema missing 'symbol_kinds' field")
+}
+
+/// Returns the `docs` field handle.
+pub fn docs_field(schema: &Schema) -> Field {
+    schema
+        .get_field("docs")
+        .expect("schema missing 'docs' field")
+}
+
+/// Returns the `imports` field handle.
+pub fn imports_field(schema: &Schema) -> Field {
+    schema
+        .get_field("imports")
+        .expect("schema missing 'imports' field")
+}
+
+/// Returns the `filename` field handle.
+pub fn filename_field(schema: &Schema) -> Field {
+    schema
+        .get_field("filename")
+        .expect("schema missing 'filename' field")
+}
+
+/// Returns the `size` field handle.
+pub fn size_field(schema: &Schema) -> Field {
+    schema
+        .get_field("size")
+        .expect("schema missing 'size' field")
+}
+
+/// Returns the `mtime` field handle.
+pub fn mtime_field(schema: &Schema) -> Field {
+    schema
+        .get_field("mtime")
+        .expect("schema missing 'mtime' field")
+}
+
+/// Returns the `truncated` field handle.
+pub fn truncated_field(schema: &Schema) -> Field {
+    schema
+        .get_field("truncated")
+        .expect("schema missing 'truncated' field")
+}
+
+/// Returns the `generated` field handle.
+pub fn generated_field(schema: &Schema) -> Field {
+    schema
+        .get_field("generated")
+        .expect("schema missing 'generated' field")
+}
+
+/// Returns the `chunk_line` field handle.
+pub fn chunk_line_field(schema: &Schema) -> Field {
+    schema
+        .get_field("chunk_line")
+        .expect("schema missing 'chunk_line' field")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn schema_has_five_fields() {
-        let schema = build_schema();
+    fn schema_has_twenty_two_fields() {
+        let schema = build_schema(false);
         let fields: Vec<_> = schema.fields().collect();
-        assert_eq!(fields.len(), 5, "schema should have exactly 5 fields");
+        assert_eq!(fields.len(), 22, "schema should have exactly 22 fields");
     }
 
     #[test]
     fn field_helpers_resolve() {
-        let schema = build_schema();
+        let schema = build_schema(false);
         // Each helper should return without panicking
         let _ = content_field(&schema);
         let _ = symbols_field(&schema);
         let _ = symbols_raw_field(&schema);
+        let _ = symbols_def_field(&schema);
         let _ = path_field(&schema);
         let _ = lang_field(&schema);
+        let _ = content_hash_field(&schema);
+        let _ = is_test_field(&schema);
+        let _ = content_ngram_field(&schema);
+        let _ = content_stem_field(&schema);
+        let _ = minhash_field(&schema);
+        let _ = line_starts_field(&schema);
+        let _ = symbol_spans_field(&schema);
+        let _ = symbol_kinds_field(&schema);
+        let _ = docs_field(&schema);
+        let _ = imports_field(&schema);
+        let _ = filename_field(&schema);
+        let _ = size_field(&schema);
+        let _ = mtime_field(&schema);
+        let _ = truncated_field(&schema);
+        let _ = generated_field(&schema);
+        let _ = chunk_line_field(&schema);
     }
 }
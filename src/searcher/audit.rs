@@ -0,0 +1,229 @@
+//! Exhaustive match listing (`ns audit`).
+//!
+//! Search ranks and cuts off at `--max-count`; an audit answers "where is
+//! every use of X" for migrations and deprecation sweeps. Every matching
+//! document is streamed segment by segment through `Weight::for_each_no_score`,
+//! so nothing is scored and no top-N heap is built, and each match is handed
+//! to the caller as soon as it is found. Results come in index order.
+//!
+//! A plain query requires every term (like `"a" AND "b"`); `--regex` uses the
+//! same literal pre-filter as `ns --regex`. With `lines`, each candidate file
+//! is read and its matching lines are reported too.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use tantivy::query::{
+    AllQuery, BooleanQuery, EnableScoring, Occur, Query, QueryParser, QueryParserError, TermQuery,
+};
+use tantivy::schema::{IndexRecordOption, Value};
+use tantivy::{TantivyDocument, Term};
+
+use crate::error::NsError;
+use crate::indexer::tokenizer::part_tokens;
+use crate::indexer::writer::open_index;
+use crate::schema::{content_field, lang_field, path_field, symbols_field};
+
+use super::query::{create_reader_with_retry, split_query_terms};
+use super::regex_search::{required_tokens, term_query};
+
+/// Options for `audit`.
+#[derive(Debug, Clone, Default)]
+pub struct AuditOptions {
+    /// Treat the query as a regular expression.
+    pub regex: bool,
+    /// Read each matching file and report its matching lines.
+    pub lines: bool,
+    /// Language filter (`-t`).
+    pub file_type: Option<String>,
+    /// Path glob filter (`-g`).
+    pub file_glob: Option<String>,
+}
+
+/// One matching file.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditMatch {
+    pub path: String,
+    /// 1-based line numbers and text of matching lines. Empty without `lines`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub lines: Vec<AuditLine>,
+}
+
+/// One matching line.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditLine {
+    pub line: usize,
+    pub text: String,
+}
+
+/// Totals for a finished audit.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditStats {
+    /// Files reported.
+    pub files: usize,
+    /// Lines reported (0 without `lines`).
+    pub lines: usize,
+    /// Index matches skipped because the file is gone or no longer has a
+    /// matching line (only checked with `lines`).
+    pub stale: usize,
+}
+
+/// Streams every file matching `query` to `emit`.
+///
+/// Stops at the first error from `emit` and returns it.
+pub fn audit(
+    root: &Path,
+    query: &str,
+    opts: &AuditOptions,
+    emit: &mut dyn FnMut(&AuditMatch) -> std::io::Result<()>,
+) -> Result<AuditStats, NsError> {
+    let (index, meta) = open_index(root)?;
+    let schema = index.schema();
+    let content = content_field(&schema);
+    let path_f = path_field(&schema);
+    let lang_f = lang_field(&schema);
+
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+    let line_re = if opts.regex {
+        for token in required_tokens(query, meta.cjk) {
+            clauses.push((Occur::Must, term_query(content, &token)?));
+        }
+        Regex::new(query)?
+    } else {
+        // Without a word to require, every document (and line) would match.
+        let (words, excluded) = split_query_terms(query, meta.cjk);
+        if part_tokens(&words, meta.cjk).is_empty() {
+            return Err(NsError::QueryParse(if excluded.is_empty() {
+                QueryParserError::SyntaxError("no words to search for".to_string())
+            } else {
+                QueryParserError::AllButQueryForbidden
+            }));
+        }
+        let mut parser = QueryParser::for_index(&index, vec![content, symbols_field(&schema)]);
+        parser.set_conjunction_by_default();
+        clauses.push((Occur::Must, parser.parse_query(query)?));
+        word_regex(&words)?
+    };
+    if let Some(ref lang) = opts.file_type {
+        clauses.push((
+            Occur::Must,
+            Box::new(TermQuery::new(
+                Term::from_field_text(lang_f, lang),
+                IndexRecordOption::Basic,
+            )),
+        ));
+    }
+    let query: Box<dyn Query> = if clauses.is_empty() {
+        Box::new(AllQuery)
+    } else {
+        Box::new(BooleanQuery::new(clauses))
+    };
+    let glob = match opts.file_glob {
+        Some(ref g) => Some(glob::Pattern::new(g)?),
+        None => None,
+    };
+
+    let reader = create_reader_with_retry(&index, root)?;
+    let searcher = reader.searcher();
+    let weight = query.weight(EnableScoring::disabled_from_searcher(&searcher))?;
+
+    let mut stats = AuditStats::default();
+    let mut failure: Option<NsError> = None;
+    // A file chunked by `ns index --chunk-lines` is reported once.
+    let mut seen: HashSet<String> = HashSet::new();
+    for segment in searcher.segment_readers() {
+        let store = segment.get_store_reader(1)?;
+        let alive = segment.alive_bitset();
+        weight.for_each_no_score(segment, &mut |docs| {
+            for &doc_id in docs {
+                if failure.is_some() {
+                    return;
+                }
+                if alive.is_some_and(|a| !a.is_alive(doc_id)) {
+                    continue;
+                }
+                let doc: TantivyDocument = match store.get(doc_id) {
+                    Ok(doc) => doc,
+                    Err(e) => {
+                        failure = Some(e.into());
+                        return;
+                    }
+                };
+                let path = doc.get_first(path_f).and_then(|v| v.as_str()).unwrap_or("");
+                if glob.as_ref().is_some_and(|g| !g.matches(path)) {
+                    continue;
+                }
+                if !seen.insert(path.to_string()) {
+                    continue;
+                }
+
+                let lines = if opts.lines {
+                    let lines = matching_lines(&root.join(path), &line_re);
+                    if lines.is_empty() {
+                        stats.stale += 1;
+                        continue;
+                    }
+                    lines
+                } else {
+                    Vec::new()
+                };
+                stats.files += 1;
+                stats.lines += lines.len();
+                let found = AuditMatch {
+                    path: path.to_string(),
+                    lines,
+                };
+                if let Err(e) = emit(&found) {
+                    failure = Some(e.into());
+                }
+            }
+        })?;
+        if let Some(err) = failure {
+            return Err(err);
+        }
+    }
+    Ok(stats)
+}
+
+/// Case-insensitive regex matching any searched word of a plain query
+/// (exclusions left out), used to pick lines. The index matched identifier parts, so this is a substring match.
+fn word_regex(query: &str) -> Result<Regex, NsError> {
+    let words: Vec<String> = query
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+        .map(regex::escape)
+        .collect();
+    Ok(RegexBuilder::new(&words.join("|"))
+        .case_insensitive(true)
+        .build()?)
+}
+
+/// Matching lines of the file at `path`; empty if it can't be read.
+fn matching_lines(path: &Path, re: &Regex) -> Vec<AuditLine> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| re.is_match(line))
+        .map(|(i, line)| AuditLine {
+            line: i + 1,
+            text: line.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_regex_matches_any_word_ignoring_case() {
+        let re = word_regex("EventStore append").unwrap();
+        assert!(re.is_match("let store = EVENTSTORE::new();"));
+        assert!(re.is_match("log.append(x)"));
+        assert!(!re.is_match("event store"));
+    }
+}
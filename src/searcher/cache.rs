@@ -0,0 +1,194 @@
+//! Per-process cache of extracted context lines.
+//!
+//! Once the index has ranked the files, reading and scanning them for
+//! context is most of the work of a search, and large files dominate it.
+//! Callers that search repeatedly in one process (editor integrations,
+//! agents calling `searcher::search` in a loop) often ask about the same
+//! files again: a retried query, or a refinement that reorders or repeats
+//! words. Context is cached per file version and matched-term set, so those
+//! lookups skip the read.
+//!
+//! A file version is its indexed content hash plus its size and mtime on
+//! disk, which notices edits made since indexing without reading the file.
+//! Results without a stored hash (indexes older than the `content_hash`
+//! field) are not cached.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use super::context::ContextResult;
+
+/// Extracted contexts kept per process; the oldest is evicted first.
+pub const CONTEXT_CACHE_ENTRIES: usize = 512;
+
+/// Identifies one extraction: which file version, which extractor with
+/// which settings, and which terms.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ContextKey {
+    path: String,
+    content_hash: u64,
+    len: u64,
+    modified: Option<SystemTime>,
+    /// Commit read from with `--context-from-git`.
+    commit: Option<String>,
+    extractor: &'static str,
+    context_window: usize,
+    max_lines: Option<usize>,
+    /// Query terms, sorted: extractors don't depend on their order.
+    terms: Vec<String>,
+}
+
+impl ContextKey {
+    /// Key for extracting `terms` from `rel_path` with `extractor`, or
+    /// `None` if the file has no stored hash or can't be stat'ed.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        root: &Path,
+        rel_path: &str,
+        content_hash: Option<u64>,
+        commit: Option<&str>,
+        extractor: &'static str,
+        context_window: usize,
+        max_lines: Option<usize>,
+        mut terms: Vec<String>,
+    ) -> Option<Self> {
+        let content_hash = content_hash?;
+        let metadata = std::fs::metadata(root.join(rel_path)).ok()?;
+        terms.sort();
+        Some(ContextKey {
+            path: rel_path.to_string(),
+            content_hash,
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            commit: commit.map(str::to_string),
+            extractor,
+            context_window,
+            max_lines,
+            terms,
+        })
+    }
+}
+
+/// A bounded map from `ContextKey` to extracted context.
+#[derive(Debug)]
+struct ContextCache {
+    capacity: usize,
+    entries: HashMap<ContextKey, ContextResult>,
+    /// Keys in insertion order, oldest first.
+    order: VecDeque<ContextKey>,
+}
+
+impl ContextCache {
+    fn new(capacity: usize) -> Self {
+        ContextCache {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&self, key: &ContextKey) -> Option<ContextResult> {
+        self.entries.get(key).cloned()
+    }
+
+    fn insert(&mut self, key: ContextKey, context: ContextResult) {
+        if self.entries.insert(key.clone(), context).is_some() {
+            return;
+        }
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+fn cache() -> &'static Mutex<ContextCache> {
+    static CACHE: OnceLock<Mutex<ContextCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(ContextCache::new(CONTEXT_CACHE_ENTRIES)))
+}
+
+/// Cached context for `key`, if any.
+pub(crate) fn cached_context(key: &ContextKey) -> Option<ContextResult> {
+    cache().lock().ok()?.get(key)
+}
+
+/// Remembers `context` as the extraction for `key`.
+pub(crate) fn cache_context(key: ContextKey, context: &ContextResult) {
+    if let Ok(mut cache) = cache().lock() {
+        cache.insert(key, context.clone());
+    }
+}
+
+/// Drops every cached context, e.g. after rewriting files in place within
+/// their mtime granularity.
+pub fn clear_context_cache() {
+    if let Ok(mut cache) = cache().lock() {
+        cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::searcher::context::ContextLine;
+
+    fn context(text: &str) -> ContextResult {
+        ContextResult {
+            lines: vec![ContextLine {
+                line_number: 1,
+                text: text.to_string(),
+            }],
+            truncated_count: 0,
+            matches: Vec::new(),
+        }
+    }
+
+    fn key(root: &Path, path: &str, terms: &[&str]) -> ContextKey {
+        let terms = terms.iter().map(|t| t.to_string()).collect();
+        ContextKey::new(root, path, Some(1), None, "lines", 2, Some(10), terms).unwrap()
+    }
+
+    #[test]
+    fn key_ignores_term_order_but_not_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+
+        let before = key(dir.path(), "a.rs", &["store", "event"]);
+        assert_eq!(before, key(dir.path(), "a.rs", &["event", "store"]));
+        assert_ne!(before, key(dir.path(), "a.rs", &["event"]));
+
+        std::fs::write(dir.path().join("a.rs"), "fn a() { grown }\n").unwrap();
+        assert_ne!(before, key(dir.path(), "a.rs", &["store", "event"]));
+
+        let unhashed = ContextKey::new(dir.path(), "a.rs", None, None, "lines", 2, None, vec![]);
+        assert!(unhashed.is_none());
+        let missing = ContextKey::new(dir.path(), "b.rs", Some(1), None, "lines", 2, None, vec![]);
+        assert!(missing.is_none());
+    }
+
+    #[test]
+    fn cache_evicts_oldest_entry_beyond_capacity() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            std::fs::write(dir.path().join(name), name).unwrap();
+        }
+        let mut cache = ContextCache::new(2);
+        cache.insert(key(dir.path(), "a.rs", &["x"]), context("a"));
+        cache.insert(key(dir.path(), "b.rs", &["x"]), context("b"));
+        cache.insert(key(dir.path(), "c.rs", &["x"]), context("c"));
+
+        assert!(cache.get(&key(dir.path(), "a.rs", &["x"])).is_none());
+        let hit = cache.get(&key(dir.path(), "c.rs", &["x"])).unwrap();
+        assert_eq!(hit.lines[0].text, "c");
+        assert_eq!(cache.entries.len(), 2);
+    }
+}
@@ -0,0 +1,212 @@
+//! Ranking calibration (`ns tune --calibrate`).
+//!
+//! Whether a boost or tokenizer change helps depends on the repo it runs
+//! on. Calibration gives a baseline to compare against: it picks symbols
+//! from the index, searches for each name the way an agent would, and checks
+//! whether a file defining the symbol ranks first. The sample is spread
+//! evenly over the sorted symbol names, so two runs over the same index ask
+//! the same queries.
+//!
+//! Names defined in more than `MAX_DEFINING_FILES` files (`new`, `init`) are
+//! skipped, as are names too short or too punctuated to be typed as a plain
+//! query; no single file is the right answer for them.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Serialize;
+use tantivy::schema::Value;
+use tantivy::{ReloadPolicy, TantivyDocument};
+
+use crate::config::load_config;
+use crate::error::NsError;
+use crate::indexer::verify::evenly_spaced;
+use crate::indexer::writer::open_index;
+use crate::schema::{path_field, symbols_raw_field};
+
+use super::query::{execute_search, SearchOptions};
+
+/// Results fetched per query; a definition ranked lower counts as not found.
+pub const CALIBRATION_DEPTH: usize = 10;
+
+/// Symbols defined in more files than this are not used as queries.
+pub(crate) const MAX_DEFINING_FILES: usize = 3;
+
+/// Symbols shorter than this are not used as queries.
+const MIN_SYMBOL_LEN: usize = 3;
+
+/// Outcome of a calibration run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Calibration {
+    /// Distinct symbols eligible as queries.
+    pub candidates: usize,
+    /// Queries run (the sample).
+    pub queries: usize,
+    /// Queries whose first result defines the symbol.
+    pub top1: usize,
+    /// Queries with a defining file among the first five results.
+    pub top5: usize,
+    /// Mean reciprocal rank of the first defining file (0 when not found
+    /// within `CALIBRATION_DEPTH`).
+    pub mrr: f64,
+    /// Queries whose first result does not define the symbol.
+    pub misses: Vec<CalibrationMiss>,
+}
+
+/// A query whose first result was not a defining file.
+#[derive(Debug, Clone, Serialize)]
+pub struct CalibrationMiss {
+    pub query: String,
+    /// Files defining the symbol.
+    pub expected: Vec<String>,
+    /// Path of the first result, if any.
+    pub got: Option<String>,
+    /// 1-based rank of the first defining file, if within `CALIBRATION_DEPTH`.
+    pub rank: Option<usize>,
+}
+
+impl Calibration {
+    /// Percentage of queries answered by the first result.
+    pub fn top1_percent(&self) -> f64 {
+        percent(self.top1 as u64, self.queries as u64)
+    }
+
+    /// Percentage of queries answered within the first five results.
+    pub fn top5_percent(&self) -> f64 {
+        percent(self.top5 as u64, self.queries as u64)
+    }
+}
+
+/// `n` as a percentage of `of`; 0 when `of` is 0.
+pub(crate) fn percent(n: u64, of: u64) -> f64 {
+    if of == 0 {
+        0.0
+    } else {
+        n as f64 * 100.0 / of as f64
+    }
+}
+
+/// Runs up to `sample` symbol queries (every eligible symbol when `None`)
+/// against the index at `root` with default options and the ranking settings
+/// from `.ns/config.toml`.
+pub fn calibrate(root: &Path, sample: Option<usize>) -> Result<Calibration, NsError> {
+    let definitions = defining_files(root)?;
+    let eligible: Vec<(&String, &Vec<String>)> = definitions
+        .iter()
+        .filter(|(name, files)| is_plain_symbol(name) && files.len() <= MAX_DEFINING_FILES)
+        .collect();
+
+    let mut opts = SearchOptions {
+        max_results: CALIBRATION_DEPTH,
+        dedupe: false,
+        verify: false,
+        force: true,
+        ..SearchOptions::default()
+    };
+    load_config(root)?.apply(&mut opts);
+    let mut calibration = Calibration {
+        candidates: eligible.len(),
+        ..Calibration::default()
+    };
+    let mut reciprocal_ranks = 0.0;
+    for (name, files) in evenly_spaced(&eligible, sample) {
+        let (results, _) = execute_search(root, name, &opts)?;
+        let rank = results
+            .iter()
+            .position(|r| files.contains(&r.path))
+            .map(|i| i + 1);
+
+        calibration.queries += 1;
+        if let Some(rank) = rank {
+            reciprocal_ranks += 1.0 / rank as f64;
+            if rank <= 5 {
+                calibration.top5 += 1;
+            }
+        }
+        if rank == Some(1) {
+            calibration.top1 += 1;
+        } else {
+            calibration.misses.push(CalibrationMiss {
+                query: name.to_string(),
+                expected: files.to_vec(),
+                got: results.first().map(|r| r.path.clone()),
+                rank,
+            });
+        }
+    }
+    if calibration.queries > 0 {
+        calibration.mrr = reciprocal_ranks / calibration.queries as f64;
+    }
+    Ok(calibration)
+}
+
+/// Every indexed symbol name with the sorted paths of the files defining it.
+pub(crate) fn defining_files(root: &Path) -> Result<BTreeMap<String, Vec<String>>, NsError> {
+    let (index, _meta) = open_index(root)?;
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let schema = index.schema();
+    let path_f = path_field(&schema);
+    let symbols_raw_f = symbols_raw_field(&schema);
+
+    let mut definitions: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for segment_reader in searcher.segment_readers() {
+        let store_reader = segment_reader.get_store_reader(1)?;
+        for doc_id in segment_reader.doc_ids_alive() {
+            let doc = store_reader.get::<TantivyDocument>(doc_id)?;
+            let path = doc.get_first(path_f).and_then(|v| v.as_str()).unwrap_or("");
+            let symbols = doc
+                .get_first(symbols_raw_f)
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            for name in symbols.split('|').filter(|s| !s.is_empty()) {
+                definitions
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(path.to_string());
+            }
+        }
+    }
+    for files in definitions.values_mut() {
+        files.sort();
+        files.dedup();
+    }
+    Ok(definitions)
+}
+
+/// Whether `name` is an identifier a user would type as a query as-is.
+pub(crate) fn is_plain_symbol(name: &str) -> bool {
+    name.chars().count() >= MIN_SYMBOL_LEN
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && name.chars().any(|c| c.is_alphabetic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_symbols_are_identifiers_of_some_length() {
+        assert!(is_plain_symbol("EventStore"));
+        assert!(is_plain_symbol("handle_request"));
+        assert!(!is_plain_symbol("id"));
+        assert!(!is_plain_symbol("MyApp.Accounts"));
+        assert!(!is_plain_symbol("___"));
+    }
+
+    #[test]
+    fn percentages_handle_empty_runs() {
+        let calibration = Calibration {
+            queries: 4,
+            top1: 3,
+            top5: 4,
+            ..Calibration::default()
+        };
+        assert_eq!(calibration.top1_percent(), 75.0);
+        assert_eq!(calibration.top5_percent(), 100.0);
+        assert_eq!(Calibration::default().top1_percent(), 0.0);
+    }
+}
@@ -0,0 +1,144 @@
+//! Prefix completion over indexed symbols and path segments (`ns complete`).
+//!
+//! Symbol candidates come from a range scan of the `symbols` term dictionary
+//! (an FST), so lookups cost the same regardless of index size. Path segments
+//! are collected from the `path` term dictionary, which holds one term per file.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Serialize;
+use tantivy::schema::{Field, IndexRecordOption, Value};
+use tantivy::{DocAddress, DocSet, Searcher, TantivyDocument, Term, TERMINATED};
+
+use crate::error::NsError;
+use crate::indexer::writer::open_index;
+use crate::schema::{path_field, symbols_field, symbols_raw_field};
+
+use super::query::create_reader_with_retry;
+
+/// Where a completion came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionKind {
+    Symbol,
+    Path,
+}
+
+/// One completion candidate.
+#[derive(Debug, Clone, Serialize)]
+pub struct Completion {
+    pub text: String,
+    pub kind: CompletionKind,
+    /// Number of indexed files containing the symbol or path segment.
+    pub files: u64,
+}
+
+/// Returns up to `limit` symbols and path segments starting with `prefix`
+/// (case-insensitive), most widespread first.
+pub fn complete(root: &Path, prefix: &str, limit: usize) -> Result<Vec<Completion>, NsError> {
+    let (index, _meta) = open_index(root)?;
+    let schema = index.schema();
+    let symbols_f = symbols_field(&schema);
+    let symbols_raw_f = symbols_raw_field(&schema);
+    let path_f = path_field(&schema);
+
+    let reader = create_reader_with_retry(&index, root)?;
+    let searcher = reader.searcher();
+    let prefix = prefix.to_lowercase();
+
+    let mut candidates: HashMap<(String, CompletionKind), u64> = HashMap::new();
+
+    for segment in searcher.segment_readers() {
+        // Symbol terms are lowercased by the "symbol" tokenizer.
+        let symbols_index = segment.inverted_index(symbols_f)?;
+        let mut stream = symbols_index
+            .terms()
+            .range()
+            .ge(prefix.as_bytes())
+            .into_stream()?;
+        while stream.advance() {
+            if !stream.key().starts_with(prefix.as_bytes()) {
+                break;
+            }
+            let term = String::from_utf8_lossy(stream.key()).into_owned();
+            *candidates.entry((term, CompletionKind::Symbol)).or_default() +=
+                stream.value().doc_freq as u64;
+        }
+
+        // Paths are stored verbatim; count each matching segment once per file.
+        let path_index = segment.inverted_index(path_f)?;
+        let mut stream = path_index.terms().stream()?;
+        while stream.advance() {
+            let path = String::from_utf8_lossy(stream.key());
+            let mut seen: Vec<&str> = Vec::new();
+            for part in path.split('/') {
+                if part.to_lowercase().starts_with(&prefix) && !seen.contains(&part) {
+                    seen.push(part);
+                    *candidates
+                        .entry((part.to_string(), CompletionKind::Path))
+                        .or_default() += 1;
+                }
+            }
+        }
+    }
+
+    let mut ranked: Vec<Completion> = candidates
+        .into_iter()
+        .map(|((text, kind), files)| Completion { text, kind, files })
+        .collect();
+    ranked.sort_by(|a, b| b.files.cmp(&a.files).then_with(|| a.text.cmp(&b.text)));
+
+    // The term dictionary only has lowercased symbols, plus the parts of
+    // compound names (`store` from `EventStore`). Recover the original
+    // spelling from one document's stored `symbols_raw`; terms that are not a
+    // whole symbol anywhere are parts and are skipped.
+    let mut completions = Vec::new();
+    for mut c in ranked {
+        if completions.len() == limit {
+            break;
+        }
+        if c.kind == CompletionKind::Symbol {
+            match original_case(&searcher, symbols_f, symbols_raw_f, &c.text)? {
+                Some(original) => c.text = original,
+                None => continue,
+            }
+        }
+        completions.push(c);
+    }
+
+    Ok(completions)
+}
+
+fn original_case(
+    searcher: &Searcher,
+    symbols_f: Field,
+    symbols_raw_f: Field,
+    lowered: &str,
+) -> Result<Option<String>, NsError> {
+    let term = Term::from_field_text(symbols_f, lowered);
+    for (segment_ord, segment) in searcher.segment_readers().iter().enumerate() {
+        let inverted = segment.inverted_index(symbols_f)?;
+        let Some(mut postings) = inverted.read_postings(&term, IndexRecordOption::Basic)? else {
+            continue;
+        };
+        let alive = segment.alive_bitset();
+        let mut doc = postings.doc();
+        while doc != TERMINATED {
+            if alive.is_none_or(|bits| bits.is_alive(doc)) {
+                let stored: TantivyDocument =
+                    searcher.doc(DocAddress::new(segment_ord as u32, doc))?;
+                let raw = stored
+                    .get_first(symbols_raw_f)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                return Ok(raw
+                    .split('|')
+                    .find(|s| s.to_lowercase() == lowered)
+                    .map(|s| s.to_string()));
+            }
+            doc = postings.advance();
+        }
+    }
+    Ok(None)
+}
@@ -1,20 +1,27 @@
 use std::collections::BTreeSet;
+use std::fmt;
 use std::path::Path;
+use std::str::FromStr;
+
+use regex::Regex;
 
 /// A single line from a matched file, with its 1-based line number.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ContextLine {
     pub line_number: usize,
     pub text: String,
 }
 
 /// Result of context extraction, including truncation info.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ContextResult {
     pub lines: Vec<ContextLine>,
     /// Number of additional matching lines that were omitted due to the cap.
     /// 0 when no truncation occurred.
     pub truncated_count: usize,
+    /// 1-based numbers of the lines that contain a match, whether shown or
+    /// not; the rest of `lines` is surrounding context.
+    pub matches: Vec<usize>,
 }
 
 /// Extracts context lines from a file that matched a search query.
@@ -29,22 +36,35 @@ pub struct ContextResult {
 /// `max_lines` of `Some(0)` means unlimited (no cap).
 ///
 /// If the file cannot be read (deleted/moved since indexing), returns an empty result.
+#[allow(dead_code)] // library entry point; the CLI reads files via `source`
 pub fn extract_context(
     root: &Path,
     rel_path: &str,
     query: &str,
     context_window: usize,
     max_lines: Option<usize>,
+) -> ContextResult {
+    match std::fs::read_to_string(root.join(rel_path)) {
+        Ok(content) => context_in(&content, query, context_window, max_lines),
+        Err(_) => ContextResult {
+            lines: Vec::new(),
+            truncated_count: 0,
+            matches: Vec::new(),
+        },
+    }
+}
+
+/// `extract_context` over already-read file content.
+pub(crate) fn context_in(
+    content: &str,
+    query: &str,
+    context_window: usize,
+    max_lines: Option<usize>,
 ) -> ContextResult {
     let empty = ContextResult {
         lines: Vec::new(),
         truncated_count: 0,
-    };
-
-    let full_path = root.join(rel_path);
-    let content = match std::fs::read_to_string(&full_path) {
-        Ok(s) => s,
-        Err(_) => return empty,
+        matches: Vec::new(),
     };
 
     let lines: Vec<&str> = content.lines().collect();
@@ -62,32 +82,114 @@ pub fn extract_context(
         return empty;
     }
 
-    // Find all line indices (0-based) that contain at least one query term
+    let match_indices = matching_lines(&lines, &terms);
+    context_from_matches(&lines, &match_indices, context_window, max_lines)
+}
+
+/// Context around lines already known to match (0-based `match_indices`,
+/// e.g. from the index's postings, see `match_lines`), expanded and capped
+/// as `context_in` does. Indices past the end of `content` are ignored.
+pub(crate) fn context_at(
+    content: &str,
+    match_indices: &BTreeSet<usize>,
+    context_window: usize,
+    max_lines: Option<usize>,
+) -> ContextResult {
+    let lines: Vec<&str> = content.lines().collect();
+    let in_file: BTreeSet<usize> = match_indices.range(..lines.len()).copied().collect();
+    context_from_matches(&lines, &in_file, context_window, max_lines)
+}
+
+/// Indices (0-based) of the lines containing at least one of `terms`,
+/// case-insensitively.
+pub(crate) fn matching_lines(lines: &[&str], terms: &[String]) -> BTreeSet<usize> {
     let mut match_indices = BTreeSet::new();
     for (i, line) in lines.iter().enumerate() {
         let lower = line.to_lowercase();
-        for term in &terms {
-            if lower.contains(term.as_str()) {
-                match_indices.insert(i);
-                break;
-            }
+        if terms.iter().any(|term| lower.contains(term.as_str())) {
+            match_indices.insert(i);
         }
     }
+    match_indices
+}
+
+/// Extracts the lines of a file that match `pattern`, expanded by ±`context_window`.
+///
+/// Used by `--regex` mode. `max_lines` and unreadable-file behaviour are the
+/// same as `extract_context`.
+#[allow(dead_code)] // library entry point; the CLI reads files via `source`
+pub fn extract_regex_context(
+    root: &Path,
+    rel_path: &str,
+    pattern: &Regex,
+    context_window: usize,
+    max_lines: Option<usize>,
+) -> ContextResult {
+    match std::fs::read_to_string(root.join(rel_path)) {
+        Ok(content) => regex_context_in(&content, pattern, context_window, max_lines),
+        Err(_) => ContextResult {
+            lines: Vec::new(),
+            truncated_count: 0,
+            matches: Vec::new(),
+        },
+    }
+}
 
+/// `extract_regex_context` over already-read file content.
+pub(crate) fn regex_context_in(
+    content: &str,
+    pattern: &Regex,
+    context_window: usize,
+    max_lines: Option<usize>,
+) -> ContextResult {
+    let lines: Vec<&str> = content.lines().collect();
+    let match_indices: BTreeSet<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| pattern.is_match(line))
+        .map(|(i, _)| i)
+        .collect();
+    context_from_matches(&lines, &match_indices, context_window, max_lines)
+}
+
+/// Expands matched line indices (0-based) by ±`context_window` and applies the
+/// per-file `max_lines` cap.
+fn context_from_matches(
+    lines: &[&str],
+    match_indices: &BTreeSet<usize>,
+    context_window: usize,
+    max_lines: Option<usize>,
+) -> ContextResult {
+    let total_lines = lines.len();
     if match_indices.is_empty() {
-        return empty;
+        return ContextResult {
+            lines: Vec::new(),
+            truncated_count: 0,
+            matches: Vec::new(),
+        };
     }
 
     // Expand matches by ±context_window, collecting all line indices to include
     let mut include_indices = BTreeSet::new();
-    for &idx in &match_indices {
+    for &idx in match_indices {
         let start = idx.saturating_sub(context_window);
         let end = (idx + context_window).min(total_lines - 1);
         for i in start..=end {
             include_indices.insert(i);
         }
     }
+    capped_lines(lines, &include_indices, match_indices, max_lines)
+}
 
+/// The lines at `include_indices` (0-based), at most `max_lines` of them
+/// (`Some(0)` or `None`: all), counting the rest as truncated.
+/// `match_indices` are the matching lines among them.
+pub(crate) fn capped_lines(
+    lines: &[&str],
+    include_indices: &BTreeSet<usize>,
+    match_indices: &BTreeSet<usize>,
+    max_lines: Option<usize>,
+) -> ContextResult {
     // Apply per-file context line cap
     // max_lines of Some(0) means unlimited (same as None)
     let cap = match max_lines {
@@ -114,6 +216,39 @@ pub fn extract_context(
     ContextResult {
         lines: context_lines,
         truncated_count,
+        matches: match_indices.iter().map(|i| i + 1).collect(),
+    }
+}
+
+/// How much of a file surrounds each matching line (`--context-scope`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContextScope {
+    /// ±`context_window` lines.
+    #[default]
+    Lines,
+    /// The whole innermost function or method (see
+    /// `spans::enclosing_functions_in`).
+    Function,
+}
+
+impl FromStr for ContextScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "lines" => Ok(ContextScope::Lines),
+            "function" => Ok(ContextScope::Function),
+            other => Err(format!("unknown context scope '{}': use lines or function", other)),
+        }
+    }
+}
+
+impl fmt::Display for ContextScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ContextScope::Lines => "lines",
+            ContextScope::Function => "function",
+        })
     }
 }
 
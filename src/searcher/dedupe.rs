@@ -0,0 +1,187 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::indexer::minhash::similarity;
+
+use super::query::SearchResult;
+
+/// Estimated similarity at or above which two files count as near-duplicates.
+pub const NEAR_DUPLICATE_SIMILARITY: f32 = 0.85;
+
+/// A result collapsed into a better-ranked one with nearly the same content.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NearDuplicate {
+    pub path: String,
+    /// Estimated share of word shingles the two files have in common.
+    pub similarity: f32,
+}
+
+/// Collapses results whose files have byte-identical content.
+///
+/// The highest-ranked copy is kept and the paths of the others are recorded
+/// in its `alternates`, in rank order. Typical sources are vendored libraries
+/// checked in under several directories, or the same file reached through
+/// more than one indexed root. Files that cannot be read are kept as-is.
+pub fn dedupe_by_content(root: &Path, results: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut kept: Vec<SearchResult> = Vec::with_capacity(results.len());
+    // (len, hash) → index into `kept`
+    let mut seen: HashMap<(u64, u64), usize> = HashMap::new();
+
+    for result in results {
+        let key = match std::fs::read(root.join(&result.path)) {
+            Ok(bytes) => content_key(&bytes),
+            Err(_) => {
+                kept.push(result);
+                continue;
+            }
+        };
+        match seen.get(&key) {
+            Some(&idx) => kept[idx].alternates.push(result.path),
+            None => {
+                seen.insert(key, kept.len());
+                kept.push(result);
+            }
+        }
+    }
+
+    kept
+}
+
+/// Collapses results whose files are near-identical by their MinHash
+/// `signatures` (keyed by path; results without one are kept as-is).
+///
+/// Each result is compared with the better-ranked ones kept so far and
+/// collapsed into the first with `NEAR_DUPLICATE_SIMILARITY` or more; its
+/// path and its own exact `alternates` are recorded in that result's
+/// `duplicates`. Typical sources are vendored copies a version apart and
+/// generated files differing by a header.
+pub fn collapse_near_duplicates(
+    results: Vec<SearchResult>,
+    signatures: &HashMap<String, Vec<u32>>,
+) -> Vec<SearchResult> {
+    let mut kept: Vec<SearchResult> = Vec::with_capacity(results.len());
+    for result in results {
+        let best = signatures.get(&result.path).and_then(|sig| {
+            kept.iter().position(|k| {
+                signatures
+                    .get(&k.path)
+                    .is_some_and(|other| similarity(sig, other) >= NEAR_DUPLICATE_SIMILARITY)
+            })
+            .map(|i| (i, similarity(sig, &signatures[&kept[i].path])))
+        });
+        match best {
+            Some((i, similarity)) => {
+                let similarity = (similarity * 100.0).round() / 100.0;
+                let SearchResult {
+                    path, alternates, ..
+                } = result;
+                for path in std::iter::once(path).chain(alternates) {
+                    kept[i].duplicates.push(NearDuplicate { path, similarity });
+                }
+            }
+            None => kept.push(result),
+        }
+    }
+    kept
+}
+
+/// Content identity key: length plus a 64-bit hash, so a hash collision would
+/// also need an exact length match to merge two different files.
+fn content_key(bytes: &[u8]) -> (u64, u64) {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    (bytes.len() as u64, hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::searcher::rerank::RerankSignals;
+
+    fn result(path: &str, score: f32) -> SearchResult {
+        SearchResult {
+            path: path.to_string(),
+            score,
+            lang: None,
+            symbols_raw: Vec::new(),
+            symbol_defs: Vec::new(),
+            symbol_spans: Vec::new(),
+            symbol_kinds: Vec::new(),
+            symbol: None,
+            score_content: score,
+            score_symbols: 0.0,
+            matched_fields: vec!["content".to_string()],
+            rerank: RerankSignals::default(),
+            terms: Vec::new(),
+            is_test: false,
+            vendored: false,
+            truncated: false,
+            generated: false,
+            alternates: Vec::new(),
+            duplicates: Vec::new(),
+            content_hash: None,
+            match_lines: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn collapses_identical_files_into_first_result() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("a.js"), "module.exports = 1;\n").unwrap();
+        std::fs::write(root.join("b.js"), "module.exports = 2;\n").unwrap();
+        std::fs::write(root.join("c.js"), "module.exports = 1;\n").unwrap();
+
+        let results = vec![result("a.js", 3.0), result("b.js", 2.0), result("c.js", 1.0)];
+        let deduped = dedupe_by_content(root, results);
+
+        let paths: Vec<&str> = deduped.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["a.js", "b.js"]);
+        assert_eq!(deduped[0].alternates, vec!["c.js".to_string()]);
+        assert!(deduped[1].alternates.is_empty());
+    }
+
+    #[test]
+    fn collapses_near_duplicates_into_best_ranked_copy() {
+        let sig = |fill: u32, same: usize| -> Vec<u32> {
+            (0..100).map(|i| if i < same { i as u32 } else { fill }).collect()
+        };
+        let signatures: HashMap<String, Vec<u32>> = [
+            ("vendor/a/lib.js", sig(1000, 100)),
+            ("src/app.js", sig(2000, 10)),
+            ("vendor/b/lib.js", sig(3000, 90)),
+        ]
+        .into_iter()
+        .map(|(p, s)| (p.to_string(), s))
+        .collect();
+
+        let mut copy = result("vendor/b/lib.js", 1.0);
+        copy.alternates = vec!["vendor/c/lib.js".to_string()];
+        let results = vec![
+            result("vendor/a/lib.js", 3.0),
+            result("src/app.js", 2.0),
+            copy,
+            result("unsigned.js", 0.5),
+        ];
+        let collapsed = collapse_near_duplicates(results, &signatures);
+
+        let paths: Vec<&str> = collapsed.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["vendor/a/lib.js", "src/app.js", "unsigned.js"]);
+        let dup_paths: Vec<&str> = collapsed[0].duplicates.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(dup_paths, vec!["vendor/b/lib.js", "vendor/c/lib.js"]);
+        assert_eq!(collapsed[0].duplicates[0].similarity, 0.9);
+        assert!(collapsed[1].duplicates.is_empty());
+    }
+
+    #[test]
+    fn keeps_unreadable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let results = vec![result("missing1.rs", 2.0), result("missing2.rs", 1.0)];
+        let deduped = dedupe_by_content(dir.path(), results);
+        assert_eq!(deduped.len(), 2);
+    }
+}
@@ -0,0 +1,98 @@
+//! Per-term score breakdown (`--explain`).
+//!
+//! A file's first-stage score is a sum over query terms and fields, with
+//! `symbols` boosted, before the language, test, vendor and re-rank factors
+//! multiply it. `TermExplainer` splits it back into those terms using
+//! tantivy's explanation of each term's BM25 score, so it's visible which
+//! word, in which field, made a file outrank the definition.
+//!
+//! Quoted phrases and fuzzy matches are not broken down; only the terms the
+//! unquoted words tokenize to are.
+
+use serde::Serialize;
+use tantivy::query::{EnableScoring, Explanation, Query, TermQuery, Weight};
+use tantivy::schema::{Field, IndexRecordOption};
+use tantivy::{DocAddress, Index, Searcher, Term};
+
+use crate::error::NsError;
+
+/// Score contribution of one query term in one field.
+#[derive(Debug, Clone, Serialize)]
+pub struct TermContribution {
+    /// Index field name (`content`, `content_stem`, `symbols`).
+    pub field: String,
+    /// The term as indexed, after tokenization.
+    pub term: String,
+    /// BM25 score of the term in this field, including the field's boost.
+    pub score: f32,
+    /// tantivy's breakdown of the unboosted score (idf, term frequency,
+    /// field norm).
+    pub explanation: Explanation,
+}
+
+/// Explains per-term scores of documents for one search.
+pub(crate) struct TermExplainer<'a> {
+    searcher: &'a Searcher,
+    /// (field name, term text, field boost, weight) per term and field.
+    terms: Vec<(String, String, f32, Box<dyn Weight>)>,
+}
+
+impl<'a> TermExplainer<'a> {
+    /// Prepares the terms `rest` tokenizes to in each of `fields`, given as
+    /// (field, boost) pairs.
+    pub(crate) fn new(
+        index: &Index,
+        searcher: &'a Searcher,
+        rest: &str,
+        fields: &[(Field, f32)],
+    ) -> Result<Self, NsError> {
+        let schema = index.schema();
+        let scoring = EnableScoring::enabled_from_searcher(searcher);
+        let mut terms = Vec::new();
+        for &(field, boost) in fields {
+            let name = schema.get_field_name(field).to_string();
+            let mut analyzer = index.tokenizer_for_field(field)?;
+            let mut stream = analyzer.token_stream(rest);
+            let mut seen: Vec<String> = Vec::new();
+            while stream.advance() {
+                let text = stream.token().text.clone();
+                if seen.contains(&text) {
+                    continue;
+                }
+                let query = TermQuery::new(
+                    Term::from_field_text(field, &text),
+                    IndexRecordOption::WithFreqs,
+                );
+                terms.push((name.clone(), text.clone(), boost, query.weight(scoring)?));
+                seen.push(text);
+            }
+        }
+        Ok(TermExplainer { searcher, terms })
+    }
+
+    /// Every prepared (field name, term) pair, in query order per field.
+    pub(crate) fn terms(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.terms.iter().map(|(field, term, _, _)| (field.as_str(), term.as_str()))
+    }
+
+    /// Contributions of the terms matching the document at `address`,
+    /// highest first.
+    pub(crate) fn explain(&self, address: DocAddress) -> Vec<TermContribution> {
+        let segment = self.searcher.segment_reader(address.segment_ord);
+        let mut contributions: Vec<TermContribution> = self
+            .terms
+            .iter()
+            .filter_map(|(field, term, boost, weight)| {
+                let explanation = weight.explain(segment, address.doc_id).ok()?;
+                Some(TermContribution {
+                    field: field.clone(),
+                    term: term.clone(),
+                    score: explanation.value() * boost,
+                    explanation,
+                })
+            })
+            .collect();
+        contributions.sort_by(|a, b| b.score.total_cmp(&a.score));
+        contributions
+    }
+}
@@ -1,23 +1,133 @@
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+use regex::Regex;
+
+use super::context::tokenize_query;
+use super::granularity::SymbolHit;
 use super::DisplayResult;
-use super::query::SearchStats;
+use super::query::{SearchResult, SearchStats};
+
+/// Line printed between non-contiguous groups of context lines, unless
+/// `--group-separator` replaces it.
+pub const DEFAULT_GROUP_SEPARATOR: &str = "...";
+
+/// ANSI escapes for colored text output.
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const MATCH: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+/// When to color text output (`--color`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// When stdout is a terminal and `NO_COLOR` is unset.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            other => Err(format!("unknown color choice '{}': use auto, always or never", other)),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// Whether output written to stdout should be colored.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::io::stdout().is_terminal()
+                    && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+            }
+        }
+    }
+}
+
+/// What colored text output highlights in context lines: matches of
+/// `regex` in `--regex`/`--substring` mode, otherwise of the query's terms.
+pub struct Highlight<'a> {
+    terms: Vec<String>,
+    regex: Option<&'a Regex>,
+}
+
+impl<'a> Highlight<'a> {
+    pub fn new(query_str: &str, regex: Option<&'a Regex>) -> Self {
+        Highlight {
+            terms: tokenize_query(query_str),
+            regex,
+        }
+    }
+
+    /// `text` with every match wrapped in the match color.
+    fn apply(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut at = 0;
+        for (start, end) in match_ranges(text, &self.terms, self.regex) {
+            out.push_str(&text[at..start]);
+            out.push_str(MATCH);
+            out.push_str(&text[start..end]);
+            out.push_str(RESET);
+            at = end;
+        }
+        out.push_str(&text[at..]);
+        out
+    }
+}
 
-/// Formats a single DisplayResult as human-readable text.
+/// Formats a single DisplayResult as human-readable text, with
+/// `group_separator` between non-contiguous groups of context lines.
+/// With `highlight`, the output is colored: a bold path, dimmed line
+/// numbers and highlighted matches.
 ///
 /// Used by the incremental budget-aware pipeline.
-pub fn format_single_text(display: &DisplayResult) -> String {
+pub fn format_single_text(
+    display: &DisplayResult,
+    group_separator: &str,
+    highlight: Option<&Highlight>,
+) -> String {
     let mut out = String::new();
 
-    // Header line: [rank] path (score, lang)
+    // Header line: [rank] path (score, lang), or for a symbol result
+    // [rank] name (kind)  path:start-end (score, lang)
     let lang_str = display
         .result
         .lang
         .as_deref()
         .unwrap_or("unknown");
 
-    out.push_str(&format!(
-        " [{}] {}  (score: {:.1}, lang: {})\n",
-        display.rank, display.result.path, display.result.score, lang_str
-    ));
+    let path = match highlight {
+        Some(_) => format!("{}{}{}", BOLD, display.result.path, RESET),
+        None => display.result.path.clone(),
+    };
+    match &display.result.symbol {
+        Some(hit) => out.push_str(&format!(
+            " [{}] {} ({})  {}:{}-{}  (score: {:.1}, lang: {})\n",
+            display.rank,
+            hit.name,
+            hit.kind.as_str(),
+            path,
+            hit.start_line,
+            hit.end_line,
+            display.result.score,
+            lang_str
+        )),
+        None => out.push_str(&format!(
+            " [{}] {}  (score: {:.1}, lang: {})\n",
+            display.rank, path, display.result.score, lang_str
+        )),
+    }
 
     // Short ranking annotation when there are matched fields
     if !display.result.matched_fields.is_empty() {
@@ -28,18 +138,61 @@ pub fn format_single_text(display: &DisplayResult) -> String {
         ));
     }
 
-    // Context lines — insert "..." separator between non-contiguous groups
+    // Other symbols of the same file merged into this result
+    if !display.merged.is_empty() {
+        let symbols: Vec<String> = display
+            .merged
+            .iter()
+            .map(|s| format!("{} ({}) {}-{}", s.name, s.kind.as_str(), s.start_line, s.end_line))
+            .collect();
+        out.push_str(&format!("      + also: {}\n", symbols.join(", ")));
+    }
+
+    // Only the head of an oversized file is indexed
+    if display.result.truncated {
+        out.push_str("      ! truncated: only the head of this file is indexed\n");
+    }
+
+    // Identical copies collapsed into this result
+    if !display.result.alternates.is_empty() {
+        out.push_str(&format!(
+            "      = identical: {}\n",
+            display.result.alternates.join(", ")
+        ));
+    }
+
+    // Near-identical files collapsed into this result
+    if !display.result.duplicates.is_empty() {
+        let duplicates: Vec<String> = display
+            .result
+            .duplicates
+            .iter()
+            .map(|d| format!("{} ({:.0}%)", d.path, d.similarity * 100.0))
+            .collect();
+        out.push_str(&format!("      ~ near-identical: {}\n", duplicates.join(", ")));
+    }
+
+    // Context lines — insert the group separator between non-contiguous groups
     let mut prev_line_number: Option<usize> = None;
     for line in &display.context_lines {
         if let Some(prev) = prev_line_number {
             if line.line_number > prev + 1 {
-                out.push_str("          ...\n");
+                out.push_str(&format!("          {}\n", group_separator));
             }
         }
-        out.push_str(&format!(
-            "     {:>4}: {}\n",
-            line.line_number, line.text
-        ));
+        match highlight {
+            Some(h) => out.push_str(&format!(
+                "     {}{:>4}:{} {}\n",
+                DIM,
+                line.line_number,
+                RESET,
+                h.apply(&line.text)
+            )),
+            None => out.push_str(&format!(
+                "     {:>4}: {}\n",
+                line.line_number, line.text
+            )),
+        }
         prev_line_number = Some(line.line_number);
     }
 
@@ -57,6 +210,37 @@ pub fn format_single_text(display: &DisplayResult) -> String {
     out
 }
 
+/// Indices into `result.symbols_raw` of the symbols containing one of
+/// `query_terms` (as from `tokenize_query`), case-insensitively.
+fn matched_symbols(result: &SearchResult, query_terms: &[String]) -> Vec<usize> {
+    result
+        .symbols_raw
+        .iter()
+        .enumerate()
+        .filter(|(_, sym)| {
+            let lower = sym.to_lowercase();
+            query_terms.iter().any(|qt| lower.contains(qt))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Formats a result as one `--summary` line:
+/// `path (score) — matched symbols: A, B`, leaving out the symbols part when
+/// none match `query_terms`.
+pub fn format_single_summary(result: &SearchResult, query_terms: &[String]) -> String {
+    let mut out = format!("{} ({:.1})", result.path, result.score);
+    let matched: Vec<&str> = matched_symbols(result, query_terms)
+        .into_iter()
+        .map(|i| result.symbols_raw[i].as_str())
+        .collect();
+    if !matched.is_empty() {
+        out.push_str(&format!(" — matched symbols: {}", matched.join(", ")));
+    }
+    out.push('\n');
+    out
+}
+
 /// Formats the search summary line (e.g. "3 results (searched 42 files in 2ms)").
 ///
 /// Separated from `format_text` so the CLI layer can direct this to stderr,
@@ -72,23 +256,43 @@ pub fn format_summary(stats: &SearchStats) -> String {
 
 /// Formats a single DisplayResult as a JSON value.
 ///
+/// Each context line carries the character offsets of its matches
+/// (`spans`, see `match_spans`): of `regex` in `--regex`/`--substring`
+/// mode, otherwise of the query's terms.
+///
 /// Used by the incremental budget-aware pipeline.
-pub fn format_single_json_value(d: &DisplayResult, query_str: &str) -> serde_json::Value {
-    let query_terms: Vec<String> = query_str
-        .split(|c: char| !c.is_alphanumeric())
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_lowercase())
+pub fn format_single_json_value(
+    d: &DisplayResult,
+    query_str: &str,
+    regex: Option<&Regex>,
+) -> serde_json::Value {
+    let query_terms = tokenize_query(query_str);
+
+    let matched_idx = matched_symbols(&d.result, &query_terms);
+    let matched: Vec<&str> = matched_idx
+        .iter()
+        .map(|&i| d.result.symbols_raw[i].as_str())
         .collect();
-
-    let matched: Vec<&str> = d
-        .result
-        .symbols_raw
+    // Definition line captured at index time for each matched symbol, so a
+    // consumer gets the signature without any context lines, with its kind
+    // and line range to jump to.
+    let definitions: Vec<serde_json::Value> = matched_idx
         .iter()
-        .filter(|sym| {
-            let lower = sym.to_lowercase();
-            query_terms.iter().any(|qt| lower.contains(qt))
+        .filter_map(|&i| {
+            let definition = d.result.symbol_defs.get(i).filter(|s| !s.is_empty())?;
+            let mut value = serde_json::json!({
+                "symbol": d.result.symbols_raw[i],
+                "definition": definition,
+            });
+            if let Some(kind) = d.result.symbol_kinds.get(i) {
+                value["kind"] = serde_json::json!(kind.as_str());
+            }
+            if let Some(&(start, end)) = d.result.symbol_spans.get(i) {
+                value["line"] = serde_json::json!(start);
+                value["end_line"] = serde_json::json!(end);
+            }
+            Some(value)
         })
-        .map(|s| s.as_str())
         .collect();
 
     let lines: Vec<serde_json::Value> = d
@@ -98,9 +302,17 @@ pub fn format_single_json_value(d: &DisplayResult, query_str: &str) -> serde_jso
             serde_json::json!({
                 "num": cl.line_number,
                 "text": cl.text,
+                "spans": match_spans(&cl.text, &query_terms, regex),
             })
         })
         .collect();
+    // The shown lines that hold a match; the rest of `lines` is context.
+    let matches: Vec<usize> = d
+        .context_lines
+        .iter()
+        .map(|cl| cl.line_number)
+        .filter(|n| d.matches.contains(n))
+        .collect();
 
     let mut value = serde_json::json!({
         "rank": d.rank,
@@ -108,26 +320,154 @@ pub fn format_single_json_value(d: &DisplayResult, query_str: &str) -> serde_jso
         "score": d.result.score,
         "lang": d.result.lang,
         "matched_symbols": matched,
+        "definitions": definitions,
         "lines": lines,
+        "matches": matches,
         "ranking_factors": {
             "bm25_content": ((d.result.score_content as f64) * 10.0).round() / 10.0,
             "bm25_symbols": ((d.result.score_symbols as f64) * 10.0).round() / 10.0,
             "symbol_boost": "3x",
             "matched_fields": d.result.matched_fields,
+            "exact_symbol": d.result.rerank.exact_symbol,
+            "symbol_kind": d.result.rerank.symbol_kind.map(|k| k.as_str()),
+            "coverage": d.result.rerank.coverage.map(|c| ((c as f64) * 100.0).round() / 100.0),
+            "proximity": d.result.rerank.proximity,
+            "path_match": ((d.result.rerank.path_match as f64) * 100.0).round() / 100.0,
+            "recency": ((d.result.rerank.recency as f64) * 100.0).round() / 100.0,
+            "churn": ((d.result.rerank.churn as f64) * 100.0).round() / 100.0,
+            "semantic": ((d.result.rerank.semantic as f64) * 100.0).round() / 100.0,
+            "test_file": d.result.is_test,
+            "generated": d.result.generated,
+            "vendored": d.result.vendored,
         },
     });
 
+    let symbol_value = |hit: &SymbolHit| {
+        serde_json::json!({
+            "name": hit.name,
+            "kind": hit.kind.as_str(),
+            "start_line": hit.start_line,
+            "end_line": hit.end_line,
+        })
+    };
+    if let Some(hit) = &d.result.symbol {
+        value["symbol"] = symbol_value(hit);
+    }
+    if !d.merged.is_empty() {
+        value["merged_symbols"] = d.merged.iter().map(symbol_value).collect();
+    }
+    if !d.result.terms.is_empty() {
+        value["ranking_factors"]["terms"] = serde_json::json!(d.result.terms);
+    }
     if d.truncated_count > 0 {
         value["truncated_lines"] = serde_json::json!(d.truncated_count);
     }
+    if d.result.truncated {
+        value["truncated_file"] = serde_json::json!(true);
+    }
+    if !d.result.alternates.is_empty() {
+        value["alternates"] = serde_json::json!(d.result.alternates);
+    }
+    if !d.result.duplicates.is_empty() {
+        value["duplicates"] = serde_json::json!(d.result.duplicates);
+    }
 
     value
 }
 
+/// `[start, end)` character offsets of the matches in `text` (see
+/// `match_ranges`). Offsets count Unicode scalar values, not bytes.
+fn match_spans(text: &str, terms: &[String], regex: Option<&Regex>) -> Vec<[usize; 2]> {
+    let chars = |byte: usize| text[..byte].chars().count();
+    match_ranges(text, terms, regex)
+        .into_iter()
+        .map(|(start, end)| [chars(start), chars(end)])
+        .collect()
+}
+
+/// Byte ranges of the matches in `text`, in order and merged where they
+/// overlap or touch: of `regex` when given, otherwise of `terms`
+/// (lowercase), matched case-insensitively as context extraction does.
+fn match_ranges(text: &str, terms: &[String], regex: Option<&Regex>) -> Vec<(usize, usize)> {
+    let mut bytes: Vec<(usize, usize)> = match regex {
+        Some(re) => re
+            .find_iter(text)
+            .filter(|m| !m.is_empty())
+            .map(|m| (m.start(), m.end()))
+            .collect(),
+        None => {
+            // Lowercase char by char, remembering where each lowered byte
+            // came from, since lowercasing can change a char's length.
+            let mut lower = String::with_capacity(text.len());
+            let mut origin = Vec::with_capacity(text.len() + 1);
+            for (i, c) in text.char_indices() {
+                let start = lower.len();
+                lower.extend(c.to_lowercase());
+                origin.resize(origin.len() + lower.len() - start, i);
+            }
+            origin.push(text.len());
+            terms
+                .iter()
+                .filter(|t| !t.is_empty())
+                .flat_map(|t| {
+                    lower
+                        .match_indices(t.as_str())
+                        .map(|(at, m)| (origin[at], origin[at + m.len()]))
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        }
+    };
+    bytes.sort_unstable();
+
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(bytes.len());
+    for (start, end) in bytes {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Strips a JSON result down to what a consumer needs (`--json-compact`).
+///
+/// Drops `ranking_factors`, removes empty or null fields (`lang`,
+/// `matched_symbols`, a line's `spans`) and rounds `score` to two decimals. The envelope is
+/// always single-line; this trims the per-result payload, which dominates
+/// token cost for LLM consumers.
+pub fn compact_json_value(value: &mut serde_json::Value) {
+    let Some(obj) = value.as_object_mut() else {
+        return;
+    };
+    obj.remove("ranking_factors");
+    if let Some(score) = obj.get("score").and_then(|s| s.as_f64()) {
+        obj.insert(
+            "score".to_string(),
+            serde_json::json!((score * 100.0).round() / 100.0),
+        );
+    }
+    obj.retain(|_, v| match v {
+        serde_json::Value::Null => false,
+        serde_json::Value::Array(a) => !a.is_empty(),
+        _ => true,
+    });
+    if let Some(lines) = obj.get_mut("lines").and_then(|l| l.as_array_mut()) {
+        for line in lines.iter_mut().filter_map(|l| l.as_object_mut()) {
+            if line.get("spans").and_then(|s| s.as_array()).is_some_and(|s| s.is_empty()) {
+                line.remove("spans");
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::searcher::context::ContextLine;
+    use crate::indexer::symbols::SymbolKind;
+    use crate::searcher::dedupe::NearDuplicate;
+    use crate::searcher::rerank::RerankSignals;
     use crate::searcher::query::{SearchResult, SearchStats};
     use crate::searcher::DisplayResult;
 
@@ -151,12 +491,28 @@ mod tests {
                 score,
                 lang: lang.map(|s| s.to_string()),
                 symbols_raw: symbols_raw.into_iter().map(|s| s.to_string()).collect(),
+                symbol_defs: Vec::new(),
+                symbol_spans: Vec::new(),
+                symbol_kinds: Vec::new(),
+                symbol: None,
                 score_content,
                 score_symbols,
                 matched_fields: matched_fields.into_iter().map(|s| s.to_string()).collect(),
+                rerank: RerankSignals::default(),
+                terms: Vec::new(),
+                is_test: false,
+                vendored: false,
+                truncated: false,
+                generated: false,
+                alternates: vec![],
+                duplicates: vec![],
+                content_hash: None,
+                match_lines: Vec::new(),
             },
             context_lines,
             truncated_count,
+            matches: Vec::new(),
+            merged: Vec::new(),
         }
     }
 
@@ -173,7 +529,7 @@ mod tests {
             0,
         );
 
-        let output = format_single_text(&display);
+        let output = format_single_text(&display, DEFAULT_GROUP_SEPARATOR, None);
         assert!(output.contains("[1] src/main.rs"));
         assert!(output.contains("score: 8.5"));
         assert!(output.contains("lang: rust"));
@@ -182,12 +538,214 @@ mod tests {
         assert!(!output.contains("result (searched"), "summary should not be in format output");
     }
 
+    #[test]
+    fn alternates_are_annotated() {
+        let mut display = make_display(
+            1, "vendor/a/lib.js", 4.0, Some("javascript"),
+            vec![], 4.0, 0.0,
+            vec!["content"],
+            vec![],
+            0,
+        );
+        let value = format_single_json_value(&display, "lib", None);
+        assert!(value.get("alternates").is_none(), "omitted when empty");
+
+        display.result.alternates = vec!["vendor/b/lib.js".to_string()];
+        let output = format_single_text(&display, DEFAULT_GROUP_SEPARATOR, None);
+        assert!(output.contains("= identical: vendor/b/lib.js"));
+        let value = format_single_json_value(&display, "lib", None);
+        assert_eq!(value["alternates"][0], "vendor/b/lib.js");
+        assert!(value.get("duplicates").is_none(), "omitted when empty");
+
+        display.result.duplicates = vec![NearDuplicate {
+            path: "vendor/c/lib.js".to_string(),
+            similarity: 0.92,
+        }];
+        let output = format_single_text(&display, DEFAULT_GROUP_SEPARATOR, None);
+        assert!(output.contains("~ near-identical: vendor/c/lib.js (92%)"));
+        let value = format_single_json_value(&display, "lib", None);
+        assert_eq!(value["duplicates"][0]["path"], "vendor/c/lib.js");
+    }
+
+    #[test]
+    fn truncated_files_are_flagged() {
+        let mut display = make_display(
+            1, "data/dump.sql", 2.0, None,
+            vec![], 2.0, 0.0,
+            vec!["content"],
+            vec![],
+            0,
+        );
+        let value = format_single_json_value(&display, "dump", None);
+        assert!(value.get("truncated_file").is_none(), "omitted when false");
+
+        display.result.truncated = true;
+        let output = format_single_text(&display, DEFAULT_GROUP_SEPARATOR, None);
+        assert!(output.contains("! truncated: only the head of this file is indexed"));
+        let value = format_single_json_value(&display, "dump", None);
+        assert_eq!(value["truncated_file"], true);
+    }
+
+    #[test]
+    fn compact_json_drops_diagnostics_and_empty_fields() {
+        let display = make_display(
+            1, "README.md", 2.0, None,
+            vec![], 2.0, 0.0,
+            vec!["content"],
+            vec![ContextLine { line_number: 3, text: "hello".to_string() }],
+            0,
+        );
+        let mut value = format_single_json_value(&display, "hello", None);
+        compact_json_value(&mut value);
+
+        assert!(value.get("ranking_factors").is_none());
+        assert!(value.get("lang").is_none(), "null lang is dropped");
+        assert!(value.get("matched_symbols").is_none(), "empty list is dropped");
+        assert_eq!(value["path"], "README.md");
+        assert_eq!(value["score"], 2.0);
+        assert_eq!(value["lines"][0]["num"], 3);
+    }
+
+    #[test]
+    fn json_lines_carry_match_offsets() {
+        let display = make_display(
+            1, "src/store.rs", 3.0, Some("rust"),
+            vec![], 3.0, 0.0,
+            vec!["content"],
+            vec![
+                ContextLine { line_number: 7, text: "let EventStore = event_store();".to_string() },
+                ContextLine { line_number: 8, text: "}".to_string() },
+            ],
+            0,
+        );
+        let value = format_single_json_value(&display, "EventStore store", None);
+        assert_eq!(value["lines"][0]["spans"], serde_json::json!([[4, 14], [23, 28]]));
+        assert_eq!(value["lines"][1]["spans"], serde_json::json!([]));
+
+        let re = Regex::new(r"event_\w+").unwrap();
+        let value = format_single_json_value(&display, "event_\\w+", Some(&re));
+        assert_eq!(value["lines"][0]["spans"], serde_json::json!([[17, 28]]));
+
+        let mut compact = value.clone();
+        compact_json_value(&mut compact);
+        assert!(compact["lines"][0].get("spans").is_some());
+        assert!(compact["lines"][1].get("spans").is_none(), "empty spans are dropped");
+    }
+
+    #[test]
+    fn json_matches_lists_only_shown_hit_lines() {
+        let mut display = make_display(
+            1, "src/store.rs", 3.0, Some("rust"),
+            vec![], 3.0, 0.0,
+            vec!["content"],
+            vec![
+                ContextLine { line_number: 6, text: "fn open() {".to_string() },
+                ContextLine { line_number: 7, text: "    let store = Store::new();".to_string() },
+                ContextLine { line_number: 8, text: "}".to_string() },
+            ],
+            0,
+        );
+        // Line 40 matches too, but was cut from the context.
+        display.matches = vec![7, 40];
+        let value = format_single_json_value(&display, "store", None);
+        assert_eq!(value["matches"], serde_json::json!([7]));
+
+        display.matches.clear();
+        let mut value = format_single_json_value(&display, "store", None);
+        compact_json_value(&mut value);
+        assert!(value.get("matches").is_none(), "empty matches are dropped");
+    }
+
+    #[test]
+    fn symbol_results_show_name_kind_and_line_range() {
+        let mut display = make_display(
+            1, "src/store.rs", 4.0, Some("rust"),
+            vec!["append"], 4.0, 0.0,
+            vec!["content"],
+            vec![ContextLine { line_number: 12, text: "pub fn append(&mut self) {".to_string() }],
+            0,
+        );
+        display.result.symbol = Some(SymbolHit {
+            name: "append".to_string(),
+            kind: SymbolKind::Function,
+            start_line: 12,
+            end_line: 20,
+        });
+        display.merged = vec![SymbolHit {
+            name: "get".to_string(),
+            kind: SymbolKind::Function,
+            start_line: 30,
+            end_line: 34,
+        }];
+        let text = format_single_text(&display, DEFAULT_GROUP_SEPARATOR, None);
+        assert!(text.starts_with(" [1] append (function)  src/store.rs:12-20  (score: 4.0"));
+        assert!(text.contains("      + also: get (function) 30-34\n"), "got: {}", text);
+        let value = format_single_json_value(&display, "append", None);
+        assert_eq!(
+            value["symbol"],
+            serde_json::json!({"name": "append", "kind": "function", "start_line": 12, "end_line": 20})
+        );
+        assert_eq!(value["merged_symbols"][0]["name"], "get");
+    }
+
+    #[test]
+    fn colored_text_highlights_matches() {
+        let display = make_display(
+            1, "src/store.rs", 3.0, Some("rust"),
+            vec![], 3.0, 0.0,
+            vec![],
+            vec![ContextLine { line_number: 7, text: "let store = Store::open();".to_string() }],
+            0,
+        );
+        let highlight = Highlight::new("store", None);
+        let output = format_single_text(&display, DEFAULT_GROUP_SEPARATOR, Some(&highlight));
+        assert!(output.contains(" [1] \x1b[1msrc/store.rs\x1b[0m  (score"));
+        assert!(output.contains(
+            "     \x1b[2m   7:\x1b[0m let \x1b[1;31mstore\x1b[0m = \x1b[1;31mStore\x1b[0m::open();\n"
+        ));
+
+        let plain = format_single_text(&display, DEFAULT_GROUP_SEPARATOR, None);
+        assert!(!plain.contains('\x1b'));
+        assert_eq!("always".parse(), Ok(ColorChoice::Always));
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+    }
+
+    #[test]
+    fn match_spans_count_characters_and_merge_overlaps() {
+        let terms = vec!["straße".to_string(), "aße".to_string()];
+        // "Ä" is two bytes but one character; the offsets count characters.
+        assert_eq!(match_spans("Ä STRAßE", &terms, None), vec![[2, 8]]);
+        assert!(match_spans("nothing here", &terms, None).is_empty());
+    }
+
+    #[test]
+    fn summary_line_lists_matched_symbols() {
+        let display = make_display(
+            2, "src/store.rs", 7.3, Some("rust"),
+            vec!["EventStore", "open", "StoreError"], 5.0, 2.25,
+            vec!["content", "symbols"],
+            vec![ContextLine { line_number: 3, text: "pub struct EventStore;".to_string() }],
+            0,
+        );
+        let terms = tokenize_query("store");
+        assert_eq!(
+            format_single_summary(&display.result, &terms),
+            "src/store.rs (7.3) — matched symbols: EventStore, StoreError\n"
+        );
+        let terms = tokenize_query("missing");
+        assert_eq!(format_single_summary(&display.result, &terms), "src/store.rs (7.3)\n");
+    }
+
     #[test]
     fn format_summary_correct() {
         let stats = SearchStats {
             total_results: 3,
             files_searched: 42,
             elapsed_ms: 2,
+            stale_results: 0,
+            indexed_commit: None,
+            route: None,
+            no_symbols: false,
         };
         assert_eq!(format_summary(&stats), "3 results (searched 42 files in 2ms)");
 
@@ -195,6 +753,10 @@ mod tests {
             total_results: 1,
             files_searched: 1,
             elapsed_ms: 0,
+            stale_results: 0,
+            indexed_commit: None,
+            route: None,
+            no_symbols: false,
         };
         assert_eq!(format_summary(&stats_one), "1 result (searched 1 file in 0ms)");
 
@@ -202,6 +764,10 @@ mod tests {
             total_results: 0,
             files_searched: 100,
             elapsed_ms: 1,
+            stale_results: 0,
+            indexed_commit: None,
+            route: None,
+            no_symbols: false,
         };
         assert_eq!(format_summary(&stats_zero), "0 results (searched 100 files in 1ms)");
     }
@@ -220,12 +786,16 @@ mod tests {
             ],
             0,
         );
-        let output = format_single_text(&display);
+        let output = format_single_text(&display, DEFAULT_GROUP_SEPARATOR, None);
         assert!(output.contains("..."), "should have separator between non-contiguous groups");
         let lines: Vec<&str> = output.lines().collect();
         let sep_idx = lines.iter().position(|l| l.contains("...")).unwrap();
         assert!(lines[sep_idx - 1].contains("4:"), "separator should follow line 4");
         assert!(lines[sep_idx + 1].contains("10:"), "separator should precede line 10");
+
+        let output = format_single_text(&display, "--", None);
+        assert!(!output.contains("..."));
+        assert_eq!(output.lines().filter(|l| l.trim() == "--").count(), 1);
     }
 
     #[test]
@@ -241,7 +811,7 @@ mod tests {
             ],
             0,
         );
-        let output = format_single_text(&display);
+        let output = format_single_text(&display, DEFAULT_GROUP_SEPARATOR, None);
         assert!(!output.contains("..."), "contiguous lines should have no separator");
     }
 
@@ -254,7 +824,7 @@ mod tests {
             vec![],
             0,
         );
-        let output = format_single_text(&display);
+        let output = format_single_text(&display, DEFAULT_GROUP_SEPARATOR, None);
         assert!(output.contains("lang: unknown"));
     }
 
@@ -270,7 +840,7 @@ mod tests {
             0,
         );
 
-        let parsed = format_single_json_value(&display, "EventStore");
+        let parsed = format_single_json_value(&display, "EventStore", None);
 
         assert_eq!(parsed["rank"], 1);
         assert_eq!(parsed["path"], "src/event_store.rs");
@@ -301,12 +871,47 @@ mod tests {
         );
 
         // Query "eventstore" (lowercase) should match "EventStore" (original case)
-        let parsed = format_single_json_value(&display, "eventstore");
+        let parsed = format_single_json_value(&display, "eventstore", None);
         let matched = parsed["matched_symbols"].as_array().unwrap();
         assert_eq!(matched.len(), 1);
         assert_eq!(matched[0], "EventStore");
     }
 
+    #[test]
+    fn single_json_value_lists_definitions_of_matched_symbols() {
+        let mut display = make_display(
+            1, "src/foo.rs", 5.0, Some("rust"),
+            vec!["EventStore", "unrelated_fn"], 3.0, 2.0,
+            vec!["symbols"],
+            vec![],
+            0,
+        );
+        display.result.symbol_defs = vec![
+            "pub struct EventStore {".to_string(),
+            "fn unrelated_fn() {}".to_string(),
+        ];
+
+        let parsed = format_single_json_value(&display, "EventStore", None);
+        assert_eq!(
+            parsed["definitions"],
+            serde_json::json!([{"symbol": "EventStore", "definition": "pub struct EventStore {"}])
+        );
+
+        display.result.symbol_kinds = vec![SymbolKind::Type, SymbolKind::Function];
+        display.result.symbol_spans = vec![(3, 9), (11, 11)];
+        let parsed = format_single_json_value(&display, "EventStore", None);
+        assert_eq!(
+            parsed["definitions"],
+            serde_json::json!([{
+                "symbol": "EventStore",
+                "definition": "pub struct EventStore {",
+                "kind": "type",
+                "line": 3,
+                "end_line": 9,
+            }])
+        );
+    }
+
     #[test]
     fn single_text_shows_truncation_indicator() {
         let display = make_display(
@@ -319,7 +924,7 @@ mod tests {
             ],
             47,
         );
-        let output = format_single_text(&display);
+        let output = format_single_text(&display, DEFAULT_GROUP_SEPARATOR, None);
         assert!(
             output.contains("... (47 more matching lines)"),
             "should show truncation indicator, got:\n{}",
@@ -338,7 +943,7 @@ mod tests {
             ],
             0,
         );
-        let output = format_single_text(&display);
+        let output = format_single_text(&display, DEFAULT_GROUP_SEPARATOR, None);
         assert!(
             !output.contains("more matching lines"),
             "should not show truncation indicator when truncated_count=0"
@@ -357,7 +962,7 @@ mod tests {
             47,
         );
 
-        let parsed = format_single_json_value(&display, "big");
+        let parsed = format_single_json_value(&display, "big", None);
         assert_eq!(
             parsed["truncated_lines"], 47,
             "JSON should include truncated_lines field"
@@ -374,7 +979,7 @@ mod tests {
             0,
         );
 
-        let parsed = format_single_json_value(&display, "small");
+        let parsed = format_single_json_value(&display, "small", None);
         assert!(
             parsed["truncated_lines"].is_null(),
             "JSON should not include truncated_lines when truncated_count=0"
@@ -0,0 +1,201 @@
+//! Term frequency report (`ns freq`).
+//!
+//! Reads document frequency and total occurrence counts straight from the
+//! postings lists, so users can see how noisy a term is before searching for
+//! it and pick the most selective identifier.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Serialize;
+use tantivy::postings::Postings;
+use tantivy::schema::{IndexRecordOption, Value};
+use tantivy::{DocAddress, DocSet, TantivyDocument, Term, TERMINATED};
+
+use crate::error::NsError;
+use crate::indexer::writer::open_index;
+use crate::schema::{content_field, lang_field, path_field, symbols_field};
+
+use super::query::create_reader_with_retry;
+
+/// How to group per-term counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FreqBreakdown {
+    /// By detected language.
+    Lang,
+    /// By top-level directory (`.` for files at the repo root).
+    Dir,
+}
+
+impl FromStr for FreqBreakdown {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "lang" => Ok(FreqBreakdown::Lang),
+            "dir" => Ok(FreqBreakdown::Dir),
+            other => Err(format!("unknown breakdown '{}': use lang or dir", other)),
+        }
+    }
+}
+
+/// Counts for one group of documents within a term's postings.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct FreqGroup {
+    pub key: String,
+    pub doc_freq: u64,
+    pub occurrences: u64,
+}
+
+/// Frequency of one index term.
+#[derive(Debug, Clone, Serialize)]
+pub struct TermFreq {
+    /// The term as indexed (lowercased, tokenized).
+    pub term: String,
+    /// Number of live documents containing the term.
+    pub doc_freq: u64,
+    /// Total number of occurrences across those documents.
+    pub occurrences: u64,
+    /// Per-group counts, sorted by `doc_freq` descending. Empty without a breakdown.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<FreqGroup>,
+}
+
+/// Looks up each term of `terms` in the index at `root`.
+///
+/// Terms are split into words like the searched field: `content` on
+/// characters other than letters, digits and `_`, `symbols` (with `sym`) on
+/// whitespace; both lowercase. Each word is looked up whole (a compound name
+/// like `EventStore` is indexed whole as well as by its parts). A multi-word
+/// argument is reported word by word. Returns the counts and
+/// the number of live documents in the index.
+pub fn term_frequencies(
+    root: &Path,
+    terms: &[String],
+    sym: bool,
+    by: Option<FreqBreakdown>,
+) -> Result<(Vec<TermFreq>, u64), NsError> {
+    let (index, _meta) = open_index(root)?;
+    let schema = index.schema();
+    let field = if sym {
+        symbols_field(&schema)
+    } else {
+        content_field(&schema)
+    };
+    let path_f = path_field(&schema);
+    let lang_f = lang_field(&schema);
+
+    let reader = create_reader_with_retry(&index, root)?;
+    let searcher = reader.searcher();
+
+    let mut tokens: Vec<String> = Vec::new();
+    for term in terms {
+        for token in tokenize(term, sym) {
+            if !tokens.contains(&token) {
+                tokens.push(token);
+            }
+        }
+    }
+
+    let mut report = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let term = Term::from_field_text(field, &token);
+        let mut doc_freq = 0;
+        let mut occurrences = 0;
+        let mut groups: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+
+        for (segment_ord, segment) in searcher.segment_readers().iter().enumerate() {
+            let inverted = segment.inverted_index(field)?;
+            let Some(mut postings) = inverted.read_postings(&term, IndexRecordOption::WithFreqs)?
+            else {
+                continue;
+            };
+            let alive = segment.alive_bitset();
+            let mut doc = postings.doc();
+            while doc != TERMINATED {
+                if alive.is_none_or(|bits| bits.is_alive(doc)) {
+                    let tf = postings.term_freq() as u64;
+                    doc_freq += 1;
+                    occurrences += tf;
+                    if let Some(by) = by {
+                        let stored: TantivyDocument =
+                            searcher.doc(DocAddress::new(segment_ord as u32, doc))?;
+                        let key = match by {
+                            FreqBreakdown::Lang => stored
+                                .get_first(lang_f)
+                                .and_then(|v| v.as_str())
+                                .filter(|s| !s.is_empty())
+                                .unwrap_or("other")
+                                .to_string(),
+                            FreqBreakdown::Dir => top_level_dir(
+                                stored.get_first(path_f).and_then(|v| v.as_str()).unwrap_or(""),
+                            ),
+                        };
+                        let entry = groups.entry(key).or_default();
+                        entry.0 += 1;
+                        entry.1 += tf;
+                    }
+                }
+                doc = postings.advance();
+            }
+        }
+
+        let mut groups: Vec<FreqGroup> = groups
+            .into_iter()
+            .map(|(key, (doc_freq, occurrences))| FreqGroup {
+                key,
+                doc_freq,
+                occurrences,
+            })
+            .collect();
+        groups.sort_by(|a, b| b.doc_freq.cmp(&a.doc_freq).then_with(|| a.key.cmp(&b.key)));
+
+        report.push(TermFreq {
+            term: token,
+            doc_freq,
+            occurrences,
+            groups,
+        });
+    }
+
+    Ok((report, searcher.num_docs()))
+}
+
+fn tokenize(term: &str, sym: bool) -> Vec<String> {
+    let pieces: Vec<&str> = if sym {
+        term.split_whitespace().collect()
+    } else {
+        term.split(|c: char| !(c.is_alphanumeric() || c == '_')).collect()
+    };
+    pieces
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_lowercase())
+        .collect()
+}
+
+/// First path component of a file in a subdirectory, or `.` for root files.
+fn top_level_dir(path: &str) -> String {
+    match path.split_once('/') {
+        Some((dir, _)) => dir.to_string(),
+        None => ".".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_matches_field_tokenizers() {
+        assert_eq!(tokenize("Event_Store::new", false), vec!["event_store", "new"]);
+        assert_eq!(tokenize("EventStore::new", true), vec!["eventstore::new"]);
+    }
+
+    #[test]
+    fn top_level_dir_groups_root_files() {
+        assert_eq!(top_level_dir("src/api/handler.rs"), "src");
+        assert_eq!(top_level_dir("README.md"), ".");
+    }
+}
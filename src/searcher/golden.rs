@@ -0,0 +1,152 @@
+//! Golden-query regression checks (`ns check`).
+//!
+//! A repo lists queries and the file each one should find in
+//! `.ns/golden.toml`:
+//!
+//! ```toml
+//! [[query]]
+//! query = "EventStore"
+//! expect = "src/event_store.rs"
+//!
+//! [[query]]
+//! query = "retry backoff"
+//! expect = "src/net/retry.rs"
+//! top = 3
+//! ```
+//!
+//! `expect` must rank first, or within the first `top` results when given.
+//! Queries run with default search options and the repo's `.ns/config.toml`,
+//! so a failing check after an ns upgrade or an index option change means
+//! ranking regressed for that query.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::load_config;
+use crate::error::NsError;
+
+use super::query::{execute_search, SearchOptions};
+
+/// Name of the golden-query file inside `.ns/`.
+pub const GOLDEN_FILE: &str = "golden.toml";
+
+/// Results fetched per query, so a miss still reports where the file ranked.
+const GOLDEN_DEPTH: usize = 10;
+
+/// One entry of `golden.toml`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GoldenQuery {
+    pub query: String,
+    /// Path, relative to the repo root, that the query should find.
+    pub expect: String,
+    /// `expect` must rank within this many results.
+    #[serde(default = "default_top")]
+    pub top: usize,
+}
+
+fn default_top() -> usize {
+    1
+}
+
+/// Contents of `golden.toml`.
+#[derive(Debug, Default, Deserialize)]
+struct GoldenFile {
+    #[serde(default, rename = "query")]
+    queries: Vec<GoldenQuery>,
+}
+
+/// Result of running one golden query.
+#[derive(Debug, Clone, Serialize)]
+pub struct GoldenOutcome {
+    #[serde(flatten)]
+    pub golden: GoldenQuery,
+    /// 1-based rank of `expect`, if within the fetched results.
+    pub rank: Option<usize>,
+    /// Path of the first result, if any.
+    pub got: Option<String>,
+}
+
+impl GoldenOutcome {
+    pub fn passed(&self) -> bool {
+        self.rank.is_some_and(|r| r <= self.golden.top)
+    }
+}
+
+/// Path of the golden-query file for the repo at `root`.
+pub fn golden_path(root: &Path) -> PathBuf {
+    root.join(".ns").join(GOLDEN_FILE)
+}
+
+/// Reads golden queries from `path`.
+pub fn load_golden(path: &Path) -> Result<Vec<GoldenQuery>, NsError> {
+    let content = std::fs::read_to_string(path)?;
+    let file: GoldenFile = toml::from_str(&content)?;
+    Ok(file.queries)
+}
+
+/// Runs each golden query against the index at `root`, in order, with the
+/// ranking settings from `.ns/config.toml`.
+pub fn run_golden(root: &Path, queries: &[GoldenQuery]) -> Result<Vec<GoldenOutcome>, NsError> {
+    let config = load_config(root)?;
+    let mut outcomes = Vec::with_capacity(queries.len());
+    for golden in queries {
+        let mut opts = SearchOptions {
+            max_results: golden.top.max(GOLDEN_DEPTH),
+            ..SearchOptions::default()
+        };
+        config.apply(&mut opts);
+        let (results, _) = execute_search(root, &golden.query, &opts)?;
+        let rank = results
+            .iter()
+            .position(|r| r.path == golden.expect || r.alternates.contains(&golden.expect))
+            .map(|i| i + 1);
+        outcomes.push(GoldenOutcome {
+            golden: golden.clone(),
+            rank,
+            got: results.first().map(|r| r.path.clone()),
+        });
+    }
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn golden_file_parses_with_default_top() {
+        let file: GoldenFile = toml::from_str(
+            r#"
+            [[query]]
+            query = "EventStore"
+            expect = "src/event_store.rs"
+
+            [[query]]
+            query = "handler"
+            expect = "src/handlers.ts"
+            top = 3
+            "#,
+        )
+        .unwrap();
+        assert_eq!(file.queries.len(), 2);
+        assert_eq!(file.queries[0].top, 1);
+        assert_eq!(file.queries[1].top, 3);
+    }
+
+    #[test]
+    fn outcome_passes_within_top() {
+        let outcome = |rank| GoldenOutcome {
+            golden: GoldenQuery {
+                query: "q".to_string(),
+                expect: "a.rs".to_string(),
+                top: 2,
+            },
+            rank,
+            got: None,
+        };
+        assert!(outcome(Some(2)).passed());
+        assert!(!outcome(Some(3)).passed());
+        assert!(!outcome(None).passed());
+    }
+}
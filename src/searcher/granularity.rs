@@ -0,0 +1,280 @@
+//! Symbol-granularity results (`--granularity symbol`).
+//!
+//! A file result says where to look, not at what: a hit in a 2,000-line
+//! file still has to be read to find the function it sits in. With
+//! `--granularity symbol`, each matching file is split into the symbols it
+//! defines, using the line range of each stored at index time
+//! (`symbol_spans`), and every symbol whose name or body holds a query term
+//! becomes a result of its own. Body hits come from the postings
+//! (`SearchResult::match_lines`) and count toward the innermost symbol
+//! around them, so a method's hits don't also credit its `impl` block.
+//! Files without a matching symbol are left out.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::indexer::symbols::SymbolKind;
+
+use super::context::{capped_lines, matching_lines, tokenize_query, ContextResult};
+use super::query::SearchResult;
+
+/// What a search result stands for (`--granularity`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Granularity {
+    /// A matching file.
+    #[default]
+    File,
+    /// A matching symbol definition.
+    Symbol,
+}
+
+impl FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "file" => Ok(Granularity::File),
+            "symbol" => Ok(Granularity::Symbol),
+            other => Err(format!("unknown granularity '{}': use file or symbol", other)),
+        }
+    }
+}
+
+impl fmt::Display for Granularity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Granularity::File => "file",
+            Granularity::Symbol => "symbol",
+        })
+    }
+}
+
+/// The symbol a `--granularity symbol` result stands for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolHit {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// 1-based first and last line of the definition.
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// Score a symbol gets for a name equal to a query term, and for a name
+/// merely containing one; body hits add their share of the file's hits.
+const EXACT_NAME: f32 = 2.0;
+const PARTIAL_NAME: f32 = 1.0;
+
+/// Splits file `results` into one result per matching symbol, scored by the
+/// file's score times how well the symbol matches `query_str`, best first.
+/// With `kinds` (`--kind`), only symbols of those kinds count. Symbols of
+/// the same file are shown as one result (see
+/// `merge_same_path`), so the symbols of at most `max_results` files are
+/// kept.
+pub fn expand_symbols(
+    results: Vec<SearchResult>,
+    query_str: &str,
+    kinds: &[SymbolKind],
+    max_results: usize,
+) -> Vec<SearchResult> {
+    let terms = tokenize_query(query_str);
+    let whole: String = terms.concat();
+    let mut hits = Vec::new();
+    for file in results {
+        if file.symbol_spans.len() != file.symbols_raw.len() {
+            continue;
+        }
+        let owners = innermost_owners(&file.symbol_spans, &file.match_lines);
+        for (i, name) in file.symbols_raw.iter().enumerate() {
+            let (start_line, end_line) = file.symbol_spans[i];
+            let lower = name.to_lowercase();
+            let name_score = if lower == whole || terms.contains(&lower) {
+                EXACT_NAME
+            } else if terms.iter().any(|t| lower.contains(t.as_str())) {
+                PARTIAL_NAME
+            } else {
+                0.0
+            };
+            let owned = owners.iter().filter(|&&o| o == Some(i)).count();
+            if name_score == 0.0 && owned == 0 {
+                continue;
+            }
+            let body_share = owned as f32 / file.match_lines.len().max(1) as f32;
+            let kind = file.symbol_kinds.get(i).copied().unwrap_or(SymbolKind::Other);
+            if !kinds.is_empty() && !kinds.contains(&kind) {
+                continue;
+            }
+            let mut hit = file.clone();
+            hit.score = file.score * (name_score + body_share);
+            hit.match_lines.retain(|&n| (start_line..=end_line).contains(&n));
+            hit.symbol = Some(SymbolHit {
+                name: name.clone(),
+                kind,
+                start_line,
+                end_line,
+            });
+            hits.push(hit);
+        }
+    }
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let mut files: Vec<&str> = Vec::new();
+    let keep = hits
+        .iter()
+        .position(|hit| {
+            if !files.contains(&hit.path.as_str()) {
+                files.push(&hit.path);
+            }
+            files.len() > max_results
+        })
+        .unwrap_or(hits.len());
+    hits.truncate(keep);
+    hits
+}
+
+/// Index into `spans` of the innermost span holding each of `lines`, if any.
+fn innermost_owners(spans: &[(usize, usize)], lines: &[usize]) -> Vec<Option<usize>> {
+    lines
+        .iter()
+        .map(|&line| {
+            spans
+                .iter()
+                .enumerate()
+                .filter(|(_, &(start, end))| start <= line && line <= end)
+                .min_by_key(|(_, &(start, end))| end - start)
+                .map(|(i, _)| i)
+        })
+        .collect()
+}
+
+/// Context for a symbol result: its whole definition when it fits in
+/// `max_lines`, otherwise its first line plus the lines within
+/// ±`context_window` of `matches` (1-based; found by scanning the
+/// definition for `query_str` when empty), capped as usual.
+pub(crate) fn symbol_context_in(
+    content: &str,
+    hit: &SymbolHit,
+    matches: &[usize],
+    query_str: &str,
+    context_window: usize,
+    max_lines: Option<usize>,
+) -> ContextResult {
+    let lines: Vec<&str> = content.lines().collect();
+    let first = hit.start_line.saturating_sub(1);
+    let last = hit.end_line.min(lines.len()).saturating_sub(1);
+    if lines.is_empty() || first > last {
+        return capped_lines(&lines, &BTreeSet::new(), &BTreeSet::new(), max_lines);
+    }
+    let match_indices: BTreeSet<usize> = if matches.is_empty() {
+        let terms = tokenize_query(query_str);
+        matching_lines(&lines[first..=last], &terms)
+            .into_iter()
+            .map(|i| i + first)
+            .collect()
+    } else {
+        matches
+            .iter()
+            .map(|n| n - 1)
+            .filter(|i| (first..=last).contains(i))
+            .collect()
+    };
+    let fits = match max_lines {
+        Some(0) | None => true,
+        Some(n) => last - first < n,
+    };
+    let include: BTreeSet<usize> = if fits {
+        (first..=last).collect()
+    } else {
+        std::iter::once(first)
+            .chain(match_indices.iter().flat_map(|&i| {
+                i.saturating_sub(context_window).max(first)..=(i + context_window).min(last)
+            }))
+            .collect()
+    };
+    capped_lines(&lines, &include, &match_indices, max_lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(score: f32, symbols: &[(&str, usize, usize)], match_lines: &[usize]) -> SearchResult {
+        SearchResult {
+            path: "src/store.rs".to_string(),
+            score,
+            lang: Some("rust".to_string()),
+            symbols_raw: symbols.iter().map(|s| s.0.to_string()).collect(),
+            symbol_defs: Vec::new(),
+            symbol_spans: symbols.iter().map(|s| (s.1, s.2)).collect(),
+            symbol_kinds: Vec::new(),
+            symbol: None,
+            score_content: 0.0,
+            score_symbols: 0.0,
+            matched_fields: Vec::new(),
+            rerank: Default::default(),
+            is_test: false,
+            vendored: false,
+            truncated: false,
+            generated: false,
+            terms: Vec::new(),
+            alternates: Vec::new(),
+            duplicates: Vec::new(),
+            content_hash: None,
+            match_lines: match_lines.to_vec(),
+        }
+    }
+
+    #[test]
+    fn granularity_parses() {
+        assert_eq!("symbol".parse(), Ok(Granularity::Symbol));
+        assert_eq!("FILE".parse(), Ok(Granularity::File));
+        assert!("line".parse::<Granularity>().is_err());
+    }
+
+    #[test]
+    fn symbols_rank_by_name_then_innermost_body_hits() {
+        // `impl EventStore` (10-30) holds `append` (12-20); `helper` (40-45)
+        // matches nothing.
+        let store = || {
+            file(
+                1.0,
+                &[("EventStore", 1, 5), ("EventStore", 10, 30), ("append", 12, 20), ("helper", 40, 45)],
+                &[3, 14, 15, 25],
+            )
+        };
+        let hits = expand_symbols(vec![store()], "EventStore append", &[], 10);
+        let names: Vec<(&str, usize)> = hits
+            .iter()
+            .map(|h| {
+                let s = h.symbol.as_ref().unwrap();
+                (s.name.as_str(), s.start_line)
+            })
+            .collect();
+        // All three names match a term; `append` owns two of the four hits.
+        assert_eq!(names, vec![("append", 12), ("EventStore", 1), ("EventStore", 10)]);
+        assert_eq!(hits[0].match_lines, vec![14, 15]);
+        assert_eq!(hits[0].score, 2.5);
+        assert_eq!(hits[2].score, 2.25, "line 25 is the impl's own, 14-15 are append's");
+        let mut other = file(0.5, &[("append", 1, 3)], &[2]);
+        other.path = "src/log.rs".to_string();
+        let capped = expand_symbols(vec![store(), other], "EventStore append", &[], 1);
+        assert_eq!(capped.len(), 3, "-m counts files, not symbols");
+    }
+
+    #[test]
+    fn symbol_context_is_the_definition_or_its_matches() {
+        let content = "fn a() {\n    one();\n    two();\n    three();\n}\n";
+        let hit = SymbolHit {
+            name: "a".to_string(),
+            kind: SymbolKind::Function,
+            start_line: 1,
+            end_line: 5,
+        };
+        let whole = symbol_context_in(content, &hit, &[], "three", 0, Some(30));
+        assert_eq!(whole.lines.len(), 5);
+        assert_eq!(whole.matches, vec![4]);
+
+        let cut = symbol_context_in(content, &hit, &[4], "", 0, Some(3));
+        let shown: Vec<usize> = cut.lines.iter().map(|l| l.line_number).collect();
+        assert_eq!(shown, vec![1, 4]);
+    }
+}
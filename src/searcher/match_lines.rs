@@ -0,0 +1,104 @@
+//! Matching lines read from the postings (see `indexer::lines`).
+//!
+//! Context extraction used to find the lines to show by scanning the file
+//! for query words as substrings, which disagrees with the tokenizer both
+//! ways: `store` also hits `restore`, and a `--stem` match on `validating`
+//! is missed. `LineMatcher` instead reads the positions of the query's
+//! terms in the document from the inverted index and maps them to lines
+//! with the document's stored `line_starts`.
+
+use tantivy::postings::Postings;
+use tantivy::schema::{Field, IndexRecordOption, Value};
+use tantivy::{DocAddress, DocSet, Index, Searcher, TantivyDocument, Term};
+
+use crate::error::NsError;
+use crate::indexer::lines::{self, line_of};
+
+/// Finds the lines of a document that hold the query's terms.
+pub(crate) struct LineMatcher<'a> {
+    searcher: &'a Searcher,
+    line_starts: Field,
+    chunk_line: Field,
+    terms: Vec<Term>,
+}
+
+impl<'a> LineMatcher<'a> {
+    /// Prepares the terms `words` tokenizes to in each of `fields`, which
+    /// must be indexed with positions. A compound word counts as its whole
+    /// token only: `EventStore` should not match every line with `event`.
+    pub(crate) fn new(
+        index: &Index,
+        searcher: &'a Searcher,
+        line_starts: Field,
+        chunk_line: Field,
+        words: &str,
+        fields: &[Field],
+    ) -> Result<Self, NsError> {
+        let mut terms: Vec<Term> = Vec::new();
+        for &field in fields {
+            let mut analyzer = index.tokenizer_for_field(field)?;
+            let mut stream = analyzer.token_stream(words);
+            let mut parts_until = 0;
+            while stream.advance() {
+                let token = stream.token();
+                if token.position < parts_until {
+                    continue;
+                }
+                if token.position_length > 1 {
+                    parts_until = token.position + token.position_length;
+                }
+                let term = Term::from_field_text(field, &token.text);
+                if !terms.contains(&term) {
+                    terms.push(term);
+                }
+            }
+        }
+        Ok(LineMatcher {
+            searcher,
+            line_starts,
+            chunk_line,
+            terms,
+        })
+    }
+
+    /// 1-based numbers of the lines of `doc` (at `address`) containing any
+    /// of the terms, in order, counted in the whole file when `doc` is a
+    /// chunk of it. Empty when none occur or the document has no stored
+    /// line starts.
+    pub(crate) fn lines(&self, address: DocAddress, doc: &TantivyDocument) -> Vec<usize> {
+        let Some(starts) = doc
+            .get_first(self.line_starts)
+            .and_then(|v| v.as_bytes())
+            .and_then(lines::from_bytes)
+        else {
+            return Vec::new();
+        };
+        let offset = doc.get_first(self.chunk_line).and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        let segment = self.searcher.segment_reader(address.segment_ord);
+        let mut found: Vec<usize> = Vec::new();
+        let mut positions: Vec<u32> = Vec::new();
+        for term in &self.terms {
+            let Ok(inverted) = segment.inverted_index(term.field()) else {
+                continue;
+            };
+            let Ok(Some(mut postings)) =
+                inverted.read_postings(term, IndexRecordOption::WithFreqsAndPositions)
+            else {
+                continue;
+            };
+            // `seek` must not move backwards, and the postings may already
+            // start past the document.
+            if postings.doc() < address.doc_id {
+                postings.seek(address.doc_id);
+            }
+            if postings.doc() != address.doc_id {
+                continue;
+            }
+            postings.positions(&mut positions);
+            found.extend(positions.iter().map(|&p| offset + line_of(&starts, p) + 1));
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
@@ -1,14 +1,51 @@
+pub mod audit;
+pub mod budget;
+pub mod cache;
+pub mod calibrate;
+pub mod complete;
 pub mod context;
+pub mod dedupe;
+pub mod explain;
 pub mod format;
+pub mod freq;
+pub mod golden;
+pub mod granularity;
+mod match_lines;
 pub mod query;
+pub mod refs;
+pub mod regex_search;
+pub mod rerank;
+mod sample;
+pub mod scan;
+pub mod session;
+mod source;
 pub mod spans;
+pub mod symbol_search;
+pub mod why;
 
+use std::collections::BTreeSet;
+use std::fmt;
 use std::path::Path;
 
+use regex::Regex;
+use serde::Serialize;
+
 use crate::error::NsError;
-use context::{extract_context, ContextLine};
-use format::{format_single_text, format_single_json_value};
+use crate::indexer::writer::content_hash;
+use budget::BudgetModel;
+use cache::{cache_context, cached_context, ContextKey};
+use context::{
+    context_at, context_in, matching_lines, regex_context_in, tokenize_query, ContextLine,
+    ContextResult, ContextScope,
+};
+use format::{
+    compact_json_value, format_single_json_value, format_single_summary, format_single_text,
+    Highlight,
+};
+use granularity::{expand_symbols, symbol_context_in, Granularity, SymbolHit};
 use query::{execute_search, SearchOptions, SearchResult, SearchStats};
+use regex_search::{execute_regex_search, execute_substring_search, substring_regex};
+use scan::execute_scan_search;
 
 /// A search result with extracted context lines, ready for display.
 #[derive(Debug)]
@@ -18,6 +55,12 @@ pub struct DisplayResult {
     pub context_lines: Vec<ContextLine>,
     /// Number of context lines omitted due to per-file cap.
     pub truncated_count: usize,
+    /// 1-based numbers of the lines that contain a match (see
+    /// `ContextResult::matches`).
+    pub matches: Vec<usize>,
+    /// Symbols of lower-ranked results for the same file, folded into this
+    /// one (see `merge_same_path`). Empty outside `--granularity symbol`.
+    pub merged: Vec<SymbolHit>,
 }
 
 /// Output of the search pipeline, including budget metadata.
@@ -25,21 +68,96 @@ pub struct DisplayResult {
 pub struct SearchOutput {
     pub formatted: String,
     pub stats: SearchStats,
+    #[allow(dead_code)] // read by library users; the CLI reports `warnings`
     pub budget_exhausted: bool,
+    #[allow(dead_code)] // read by library users; the CLI reports `warnings`
     pub results_omitted: usize,
+    /// Problems that didn't stop the search, in the order they arose. Also
+    /// listed in JSON (`warnings`) and as a text footer.
+    pub warnings: Vec<Warning>,
+    /// Results cut to their top matching line to fit the budget, in rank
+    /// order. Also listed in JSON (`stats.degraded`).
+    #[allow(dead_code)] // read by library users
+    pub degraded: Vec<Degraded>,
+}
+
+/// A result shown with only its top matching line because the full
+/// context didn't fit the budget (see `degrade_to_fit`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Degraded {
+    pub rank: usize,
+    pub path: String,
+    /// Context lines left out to fit.
+    pub cut_lines: usize,
+}
+
+/// Something a caller may want to act on that didn't stop the search.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Warning {
+    /// The token budget ran out before every result was shown.
+    BudgetExceeded { omitted: usize },
+    /// Results for files deleted since the last index were dropped.
+    StaleResults { dropped: usize },
+    /// A result's file could not be read, so it is shown without context.
+    MissingContext { path: String },
+    /// Files were scanned without an index (`--no-index`), so results are
+    /// ranked by matching lines only.
+    Unindexed { files: usize },
+    /// The index has no symbols (`ns index --no-symbols`), so definitions
+    /// get no boost.
+    NoSymbols,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::BudgetExceeded { omitted } => {
+                write!(f, "token budget exceeded, {} results omitted", omitted)
+            }
+            Warning::StaleResults { dropped } => {
+                let noun = if *dropped == 1 { "result" } else { "results" };
+                write!(
+                    f,
+                    "dropped {} {} for deleted files; run 'ns index --incremental' to update the index",
+                    dropped, noun
+                )
+            }
+            Warning::MissingContext { path } => {
+                write!(f, "cannot read {}; showing it without context", path)
+            }
+            Warning::Unindexed { files } => {
+                write!(
+                    f,
+                    "scanned {} files without an index; run 'ns index' for ranked results",
+                    files
+                )
+            }
+            Warning::NoSymbols => write!(
+                f,
+                "index built with --no-symbols: symbol boost is unavailable; run 'ns index' to add symbols"
+            ),
+        }
+    }
 }
 
 /// Output mode for formatting results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputMode {
     /// Human-readable text (default).
     Text,
     /// Bare file paths, one per line (`-l`/`--files`).
     FilesOnly,
+    /// One line per result: path, score and matched symbols, without
+    /// context (`--summary`).
+    Summary,
     /// Machine-readable JSON (`--json`).
     Json,
 }
 
 /// Runs the full search pipeline: query → context extraction → formatting.
+/// With `--granularity symbol`, file results are split into symbols first
+/// (see `granularity`).
 ///
 /// Returns a `SearchOutput` containing formatted output, stats, and budget metadata.
 pub fn search(
@@ -48,153 +166,578 @@ pub fn search(
     output_mode: OutputMode,
     opts: &SearchOptions,
 ) -> Result<SearchOutput, NsError> {
-    let (results, stats) = execute_search(root, query_str, opts)?;
+    let regex = if opts.regex {
+        Some(Regex::new(query_str)?)
+    } else if opts.substring {
+        Some(substring_regex(query_str)?)
+    } else {
+        None
+    };
+    let (results, mut stats) = if opts.no_index {
+        execute_scan_search(root, query_str, regex.as_ref(), opts)?
+    } else if opts.regex {
+        execute_regex_search(root, query_str, opts)?
+    } else if opts.substring {
+        execute_substring_search(root, query_str, opts)?
+    } else {
+        execute_search(root, query_str, opts)?
+    };
+    let results = match opts.granularity {
+        Granularity::File => results,
+        Granularity::Symbol => {
+            let symbols = expand_symbols(results, query_str, &opts.kinds, opts.max_results);
+            let files: BTreeSet<&str> = symbols.iter().map(|s| s.path.as_str()).collect();
+            stats.total_results = files.len();
+            symbols
+        }
+    };
+    let mut warnings = Vec::new();
+    let mut degraded = Vec::new();
+    if opts.no_index {
+        warnings.push(Warning::Unindexed {
+            files: stats.files_searched,
+        });
+    }
+    if stats.no_symbols {
+        warnings.push(Warning::NoSymbols);
+    }
+    if stats.stale_results > 0 {
+        warnings.push(Warning::StaleResults {
+            dropped: stats.stale_results,
+        });
+    }
 
     match output_mode {
         OutputMode::FilesOnly => {
             let (output, budget_exhausted, results_omitted) =
-                build_files_only_with_budget(&results, opts.budget);
+                build_files_only_with_budget(&results, opts.budget, opts.budget_model, opts.null_separated);
+            if budget_exhausted {
+                warnings.push(Warning::BudgetExceeded {
+                    omitted: results_omitted,
+                });
+            }
             Ok(SearchOutput {
                 formatted: output,
                 stats,
                 budget_exhausted,
                 results_omitted,
+                warnings,
+                degraded,
+            })
+        }
+        OutputMode::Summary => {
+            let query_terms = tokenize_query(query_str);
+            let (output, budget_exhausted, results_omitted) = build_lines_with_budget(
+                &results,
+                opts.budget,
+                opts.budget_model,
+                true,
+                |r| format_single_summary(r, &query_terms),
+            );
+            if budget_exhausted {
+                warnings.push(Warning::BudgetExceeded {
+                    omitted: results_omitted,
+                });
+            }
+            Ok(SearchOutput {
+                formatted: output,
+                stats,
+                budget_exhausted,
+                results_omitted,
+                warnings,
+                degraded,
             })
         }
         OutputMode::Text => {
-            let (output, budget_exhausted, results_omitted) =
-                build_text_with_budget(root, results, query_str, opts);
+            let (mut output, budget_exhausted, results_omitted) =
+                build_text_with_budget(
+                    root,
+                    results,
+                    query_str,
+                    regex.as_ref(),
+                    stats.indexed_commit.as_deref(),
+                    opts,
+                    &mut warnings,
+                    &mut degraded,
+                );
+            for warning in &warnings {
+                output.push_str(&format!("warning: {}\n", warning));
+            }
             Ok(SearchOutput {
                 formatted: output,
                 stats,
                 budget_exhausted,
                 results_omitted,
+                warnings,
+                degraded,
             })
         }
         OutputMode::Json => {
-            let (output, budget_exhausted, results_omitted) =
-                build_json_with_budget(root, results, query_str, opts, &stats);
+            let (output, budget_exhausted, results_omitted) = build_json_with_budget(
+                root,
+                results,
+                query_str,
+                regex.as_ref(),
+                opts,
+                &stats,
+                &mut warnings,
+                &mut degraded,
+            );
             Ok(SearchOutput {
                 formatted: output,
                 stats,
                 budget_exhausted,
                 results_omitted,
+                warnings,
+                degraded,
             })
         }
     }
 }
 
 /// Build files-only output with optional budget.
+///
+/// With `null_separated`, each path ends in NUL and the budget-exceeded marker
+/// is left out, so the output stays safe to feed to `xargs -0`.
 fn build_files_only_with_budget(
     results: &[SearchResult],
     budget: Option<usize>,
+    model: BudgetModel,
+    null_separated: bool,
 ) -> (String, bool, usize) {
-    let budget_chars = budget.map(|b| b * 4);
+    let terminator = if null_separated { '\0' } else { '\n' };
+    build_lines_with_budget(results, budget, model, !null_separated, |r| {
+        format!("{}{}", r.path, terminator)
+    })
+}
+
+/// Build output of one `line` per result with optional budget, ending in a
+/// budget-exceeded marker when results are left out and `marker` is set.
+fn build_lines_with_budget(
+    results: &[SearchResult],
+    budget: Option<usize>,
+    model: BudgetModel,
+    marker: bool,
+    line: impl Fn(&SearchResult) -> String,
+) -> (String, bool, usize) {
+    let budget_units = budget.map(|b| model.units(b));
     let mut out = String::new();
+    let mut used = 0;
     let mut emitted = 0;
 
     for r in results {
-        let line = format!("{}\n", r.path);
-        if let Some(cap) = budget_chars {
-            if out.len() + line.len() > cap && !out.is_empty() {
+        let line = line(r);
+        let cost = model.cost(&line);
+        if let Some(cap) = budget_units {
+            if used + cost > cap && !out.is_empty() {
                 let omitted = results.len() - emitted;
-                out.push_str(&format!("... ({} more results, budget exceeded)\n", omitted));
+                if marker {
+                    out.push_str(&format!("... ({} more results, budget exceeded)\n", omitted));
+                }
                 return (out, true, omitted);
             }
         }
         out.push_str(&line);
+        used += cost;
         emitted += 1;
     }
 
     (out, false, 0)
 }
 
-/// Build text output incrementally with optional budget.
-fn build_text_with_budget(
+/// Picks the context extractor for a result: the symbol's definition
+/// (`--granularity symbol`), regex line matches, signature lines
+/// (`--quick`), `--spans`, enclosing functions (`--context-scope
+/// function`), or grep-and-expand. With `--context-from-git`, changed files are read from
+/// `indexed_commit` (see `source`). Extractions are cached per file version
+/// and term set (see `cache`). An unreadable file adds a `MissingContext`
+/// warning.
+fn result_context(
+    root: &Path,
+    result: &SearchResult,
+    query_str: &str,
+    regex: Option<&Regex>,
+    indexed_commit: Option<&str>,
+    opts: &SearchOptions,
+    warnings: &mut Vec<Warning>,
+) -> ContextResult {
+    let commit = indexed_commit.filter(|_| opts.context_from_git);
+    let functions = opts.context_scope == ContextScope::Function;
+    let span = result
+        .symbol
+        .as_ref()
+        .map(|hit| format!("{}-{}", hit.start_line, hit.end_line));
+    let (extractor, terms) = match regex {
+        _ if span.is_some() => (
+            "symbol",
+            tokenize_query(query_str).into_iter().chain(span).collect(),
+        ),
+        Some(re) => ("regex", vec![re.as_str().to_string()]),
+        None if opts.signatures_only => ("signatures", tokenize_query(query_str)),
+        None if opts.spans => ("spans", tokenize_query(query_str)),
+        // Matching lines from the index determine the context of a file
+        // version on their own.
+        None if !result.match_lines.is_empty() => (
+            if functions { "indexed_functions" } else { "indexed_lines" },
+            result.match_lines.iter().map(ToString::to_string).collect(),
+        ),
+        None if functions => ("functions", tokenize_query(query_str)),
+        None => ("lines", tokenize_query(query_str)),
+    };
+    let key = ContextKey::new(
+        root,
+        &result.path,
+        result.content_hash,
+        commit,
+        extractor,
+        opts.context_window,
+        opts.max_context_lines,
+        terms,
+    );
+    if let Some(hit) = key.as_ref().and_then(cached_context) {
+        return hit;
+    }
+
+    let Some(content) = source::indexed_source(root, &result.path, result.content_hash, commit)
+    else {
+        warnings.push(Warning::MissingContext {
+            path: result.path.clone(),
+        });
+        return ContextResult {
+            lines: Vec::new(),
+            truncated_count: 0,
+            matches: Vec::new(),
+        };
+    };
+
+    let context = if let Some(hit) = &result.symbol {
+        // Indexed line numbers only describe the indexed version of the file.
+        let fresh = result.content_hash == Some(content_hash(content.as_bytes()));
+        let matches = if fresh { &result.match_lines[..] } else { &[] };
+        symbol_context_in(
+            &content,
+            hit,
+            matches,
+            query_str,
+            opts.context_window,
+            opts.max_context_lines,
+        )
+    } else if let Some(re) = regex {
+        regex_context_in(&content, re, opts.context_window, opts.max_context_lines)
+    } else if opts.signatures_only {
+        spans::signature_lines_in(&result.path, &content, query_str, opts.max_context_lines)
+    } else if opts.spans {
+        spans::best_spans_in(&result.path, &content, query_str, opts.max_context_lines)
+    } else {
+        // Indexed line numbers only describe the indexed version of the file.
+        let indexed = (!result.match_lines.is_empty()
+            && result.content_hash == Some(content_hash(content.as_bytes())))
+        .then(|| result.match_lines.iter().map(|n| n - 1).collect::<BTreeSet<usize>>());
+        match indexed {
+            Some(matches) if functions => spans::enclosing_functions_in(
+                &result.path,
+                &content,
+                &matches,
+                opts.context_window,
+                opts.max_context_lines,
+            ),
+            Some(matches) => {
+                context_at(&content, &matches, opts.context_window, opts.max_context_lines)
+            }
+            None if functions => {
+                let lines: Vec<&str> = content.lines().collect();
+                let matches = matching_lines(&lines, &tokenize_query(query_str));
+                spans::enclosing_functions_in(
+                    &result.path,
+                    &content,
+                    &matches,
+                    opts.context_window,
+                    opts.max_context_lines,
+                )
+            }
+            None => context_in(&content, query_str, opts.context_window, opts.max_context_lines),
+        }
+    };
+    if let Some(key) = key {
+        cache_context(key, &context);
+    }
+    context
+}
+
+/// Extracts each result's context (see `result_context`) and merges results
+/// for the same file (see `merge_same_path`), ranked from 1.
+fn display_results(
     root: &Path,
     results: Vec<SearchResult>,
     query_str: &str,
+    regex: Option<&Regex>,
+    indexed_commit: Option<&str>,
     opts: &SearchOptions,
-) -> (String, bool, usize) {
-    let budget_chars = opts.budget.map(|b| b * 4);
-    let mut out = String::new();
-    let total = results.len();
-    let mut emitted = 0;
+    warnings: &mut Vec<Warning>,
+) -> Vec<DisplayResult> {
+    let displays = results
+        .into_iter()
+        .enumerate()
+        .map(|(i, result)| {
+            let ctx =
+                result_context(root, &result, query_str, regex, indexed_commit, opts, warnings);
+            DisplayResult {
+                rank: i + 1,
+                result,
+                context_lines: ctx.lines,
+                truncated_count: ctx.truncated_count,
+                matches: ctx.matches,
+                merged: Vec::new(),
+            }
+        })
+        .collect();
+    merge_same_path(displays)
+}
 
-    for (i, result) in results.into_iter().enumerate() {
-        let ctx = if opts.spans {
-            spans::extract_best_spans(root, &result.path, query_str, opts.max_context_lines)
-        } else {
-            extract_context(root, &result.path, query_str, opts.context_window, opts.max_context_lines)
+/// Folds every result for a path already listed into the first (best
+/// ranked) one, so each file's header and overlapping context lines are
+/// shown once: context lines and matches are united in line order and
+/// truncated counts added up. Folded symbols go to `merged`. Ranks are
+/// renumbered.
+fn merge_same_path(displays: Vec<DisplayResult>) -> Vec<DisplayResult> {
+    let mut merged: Vec<DisplayResult> = Vec::with_capacity(displays.len());
+    for d in displays {
+        let Some(into) = merged.iter_mut().find(|m| m.result.path == d.result.path) else {
+            merged.push(d);
+            continue;
         };
-        let display = DisplayResult {
-            rank: i + 1,
-            result,
-            context_lines: ctx.lines,
-            truncated_count: ctx.truncated_count,
+        for line in d.context_lines {
+            if let Err(at) = into
+                .context_lines
+                .binary_search_by_key(&line.line_number, |l| l.line_number)
+            {
+                into.context_lines.insert(at, line);
+            }
+        }
+        into.matches.extend(d.matches);
+        into.matches.sort_unstable();
+        into.matches.dedup();
+        into.truncated_count += d.truncated_count;
+        into.merged.extend(d.result.symbol);
+        into.merged.extend(d.merged);
+    }
+    for (i, d) in merged.iter_mut().enumerate() {
+        d.rank = i + 1;
+    }
+    merged
+}
+
+/// Per-result allowance (in `BudgetModel::cost` units) when
+/// `opts.breadth_first` spreads the budget evenly over all results instead
+/// of filling it in rank order.
+fn breadth_first_share(budget_units: Option<usize>, total: usize, opts: &SearchOptions) -> Option<usize> {
+    if !opts.breadth_first || total == 0 {
+        return None;
+    }
+    budget_units.map(|cap| cap / total)
+}
+
+/// Drops trailing context lines until `len(display)` fits in `share`. The
+/// header is always kept; dropped lines are counted as truncated.
+fn fit_context(display: &mut DisplayResult, share: usize, len: impl Fn(&DisplayResult) -> usize) {
+    while !display.context_lines.is_empty() && len(display) > share {
+        display.context_lines.pop();
+        display.truncated_count += 1;
+    }
+}
+
+/// Fits `displays` into `cap` budget units, shrinking context before
+/// dropping results. Every result is first cut to its top matching line
+/// (or first context line); then as many results as fit are kept, at least
+/// one, and the kept ones get their full context back in rank order while
+/// it still fits. Returns the results left cut and the number dropped.
+fn degrade_to_fit(
+    displays: &mut Vec<DisplayResult>,
+    cap: usize,
+    cost: impl Fn(&DisplayResult) -> usize,
+) -> (Vec<Degraded>, usize) {
+    let full: Vec<usize> = displays.iter().map(&cost).collect();
+    if full.iter().sum::<usize>() <= cap {
+        return (Vec::new(), 0);
+    }
+
+    let mut saved: Vec<Option<(Vec<ContextLine>, usize)>> = Vec::with_capacity(displays.len());
+    for d in displays.iter_mut() {
+        if d.context_lines.len() <= 1 {
+            saved.push(None);
+            continue;
+        }
+        let top = d
+            .context_lines
+            .iter()
+            .position(|l| d.matches.contains(&l.line_number))
+            .unwrap_or(0);
+        let top = d.context_lines[top].clone();
+        let lines = std::mem::replace(&mut d.context_lines, vec![top]);
+        let truncated_count = d.truncated_count;
+        d.truncated_count += lines.len() - 1;
+        saved.push(Some((lines, truncated_count)));
+    }
+
+    let mut used = 0;
+    let mut kept = 0;
+    let mut trimmed = Vec::with_capacity(displays.len());
+    for d in displays.iter() {
+        let c = cost(d);
+        if kept > 0 && used + c > cap {
+            break;
+        }
+        used += c;
+        kept += 1;
+        trimmed.push(c);
+    }
+    let omitted = displays.len() - kept;
+    displays.truncate(kept);
+    saved.truncate(kept);
+
+    let mut degraded = Vec::new();
+    for (i, d) in displays.iter_mut().enumerate() {
+        let Some((lines, truncated_count)) = saved[i].take() else {
+            continue;
         };
-        let chunk = format_single_text(&display);
+        let extra = full[i].saturating_sub(trimmed[i]);
+        if used + extra <= cap {
+            d.context_lines = lines;
+            d.truncated_count = truncated_count;
+            used += extra;
+        } else {
+            degraded.push(Degraded {
+                rank: d.rank,
+                path: d.result.path.clone(),
+                cut_lines: d.truncated_count - truncated_count,
+            });
+        }
+    }
+    (degraded, omitted)
+}
 
-        if let Some(cap) = budget_chars {
-            if out.len() + chunk.len() > cap && !out.is_empty() {
-                let omitted = total - emitted;
-                out.push_str(&format!("... ({} more results, budget exceeded)\n", omitted));
-                return (out, true, omitted);
-            }
+/// Build text output with optional budget.
+///
+/// Over budget, results are cut to their top matching line before any is
+/// dropped (see `degrade_to_fit`); `degraded` receives the ones that were.
+#[allow(clippy::too_many_arguments)]
+fn build_text_with_budget(
+    root: &Path,
+    results: Vec<SearchResult>,
+    query_str: &str,
+    regex: Option<&Regex>,
+    indexed_commit: Option<&str>,
+    opts: &SearchOptions,
+    warnings: &mut Vec<Warning>,
+    degraded: &mut Vec<Degraded>,
+) -> (String, bool, usize) {
+    let model = opts.budget_model;
+    let budget_units = opts.budget.map(|b| model.units(b));
+    let highlight = opts.color.then(|| Highlight::new(query_str, regex));
+    let format = |d: &DisplayResult, highlight: Option<&Highlight>| {
+        let mut chunk = format_single_text(d, &opts.group_separator, highlight);
+        if let Some(ref separator) = opts.record_separator {
+            chunk.push_str(separator);
+        }
+        chunk
+    };
+    // Counted against the budget; color escapes are free.
+    let plain_cost = |d: &DisplayResult| model.cost(&format(d, None));
+
+    let mut displays =
+        display_results(root, results, query_str, regex, indexed_commit, opts, warnings);
+    if let Some(share) = breadth_first_share(budget_units, displays.len(), opts) {
+        for display in &mut displays {
+            fit_context(display, share, plain_cost);
         }
-        out.push_str(&chunk);
-        emitted += 1;
     }
+    let omitted = match budget_units {
+        Some(cap) => {
+            let (cut, omitted) = degrade_to_fit(&mut displays, cap, plain_cost);
+            *degraded = cut;
+            omitted
+        }
+        None => 0,
+    };
 
-    (out, false, 0)
+    let mut out = String::new();
+    for display in &displays {
+        out.push_str(&format(display, highlight.as_ref()));
+    }
+    if omitted > 0 {
+        out.push_str(&format!("... ({} more results, budget exceeded)\n", omitted));
+        warnings.push(Warning::BudgetExceeded { omitted });
+    }
+    (out, omitted > 0, omitted)
 }
 
-/// Build JSON output incrementally with optional budget.
+/// Build JSON output with optional budget, degrading results to fit it as
+/// `build_text_with_budget` does. Degraded results are listed in
+/// `stats.degraded`.
+#[allow(clippy::too_many_arguments)]
 fn build_json_with_budget(
     root: &Path,
     results: Vec<SearchResult>,
     query_str: &str,
+    regex: Option<&Regex>,
     opts: &SearchOptions,
     stats: &SearchStats,
+    warnings: &mut Vec<Warning>,
+    degraded: &mut Vec<Degraded>,
 ) -> (String, bool, usize) {
-    let budget_chars = opts.budget.map(|b| b * 4);
-    let total = results.len();
-    let mut result_values: Vec<serde_json::Value> = Vec::new();
-    let mut emitted = 0;
-    let mut budget_exhausted = false;
-    let mut results_omitted = 0;
+    let model = opts.budget_model;
+    let budget_units = opts.budget.map(|b| model.units(b));
 
     // Estimate the overhead for the JSON envelope (query, stats, etc.)
-    // We do a rough estimate: ~200 chars for the wrapper
-    let envelope_estimate = 200;
-    let mut running_chars = envelope_estimate;
+    // We do a rough estimate: ~50 tokens for the wrapper
+    let envelope_estimate = model.units(50);
+    let json_len = |d: &DisplayResult| {
+        let mut value = format_single_json_value(d, query_str, regex);
+        if opts.json_compact {
+            compact_json_value(&mut value);
+        }
+        serde_json::to_string(&value).map_or(0, |s| model.cost(&s))
+    };
 
-    for (i, result) in results.into_iter().enumerate() {
-        let ctx = if opts.spans {
-            spans::extract_best_spans(root, &result.path, query_str, opts.max_context_lines)
-        } else {
-            extract_context(root, &result.path, query_str, opts.context_window, opts.max_context_lines)
-        };
-        let display = DisplayResult {
-            rank: i + 1,
-            result,
-            context_lines: ctx.lines,
-            truncated_count: ctx.truncated_count,
-        };
-        let value = format_single_json_value(&display, query_str);
-        let value_str = serde_json::to_string(&value).unwrap_or_default();
-
-        if let Some(cap) = budget_chars {
-            if running_chars + value_str.len() > cap && !result_values.is_empty() {
-                results_omitted = total - emitted;
-                budget_exhausted = true;
-                break;
-            }
+    let mut displays = display_results(
+        root,
+        results,
+        query_str,
+        regex,
+        stats.indexed_commit.as_deref(),
+        opts,
+        warnings,
+    );
+    let share = breadth_first_share(
+        budget_units.map(|cap| cap.saturating_sub(envelope_estimate)),
+        displays.len(),
+        opts,
+    );
+    if let Some(share) = share {
+        for display in &mut displays {
+            fit_context(display, share, json_len);
         }
-        running_chars += value_str.len();
-        result_values.push(value);
-        emitted += 1;
     }
+    let results_omitted = match budget_units {
+        Some(cap) => {
+            let (cut, omitted) =
+                degrade_to_fit(&mut displays, cap.saturating_sub(envelope_estimate), json_len);
+            *degraded = cut;
+            omitted
+        }
+        None => 0,
+    };
+    let budget_exhausted = results_omitted > 0;
+    let result_values: Vec<serde_json::Value> = displays
+        .iter()
+        .map(|display| {
+            let mut value = format_single_json_value(display, query_str, regex);
+            if opts.json_compact {
+                compact_json_value(&mut value);
+            }
+            value
+        })
+        .collect();
 
     // Build final JSON
     let mut stats_obj = serde_json::json!({
@@ -202,16 +745,38 @@ fn build_json_with_budget(
         "files_searched": stats.files_searched,
         "elapsed_ms": stats.elapsed_ms,
     });
+    if stats.stale_results > 0 {
+        stats_obj["stale_results"] = serde_json::json!(stats.stale_results);
+    }
+    if let Some(route) = stats.route {
+        stats_obj["route"] = serde_json::json!(route.as_str());
+    }
+    if !degraded.is_empty() {
+        stats_obj["degraded"] = serde_json::json!(degraded);
+    }
     if budget_exhausted {
         stats_obj["budget_exceeded"] = serde_json::json!(true);
         stats_obj["results_omitted"] = serde_json::json!(results_omitted);
+        warnings.push(Warning::BudgetExceeded {
+            omitted: results_omitted,
+        });
     }
 
-    let json = serde_json::json!({
+    let mut json = serde_json::json!({
         "query": query_str,
         "results": result_values,
         "stats": stats_obj,
     });
+    if !warnings.is_empty() {
+        json["warnings"] = warnings
+            .iter()
+            .map(|w| {
+                let mut value = serde_json::json!(w);
+                value["message"] = serde_json::json!(w.to_string());
+                value
+            })
+            .collect();
+    }
 
     let formatted = serde_json::to_string(&json).unwrap_or_else(|_| "{}".to_string());
     (formatted, budget_exhausted, results_omitted)
@@ -220,6 +785,7 @@ fn build_json_with_budget(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rerank::RerankSignals;
 
     fn fake_result(path: &str) -> SearchResult {
         SearchResult {
@@ -227,9 +793,23 @@ mod tests {
             score: 5.0,
             lang: Some("rust".to_string()),
             symbols_raw: vec![],
+            symbol_defs: Vec::new(),
+            symbol_spans: Vec::new(),
+            symbol_kinds: Vec::new(),
+            symbol: None,
             score_content: 5.0,
             score_symbols: 0.0,
             matched_fields: vec!["content".to_string()],
+            rerank: RerankSignals::default(),
+            terms: Vec::new(),
+            is_test: false,
+            vendored: false,
+            truncated: false,
+            generated: false,
+            alternates: vec![],
+            duplicates: vec![],
+            content_hash: None,
+            match_lines: Vec::new(),
         }
     }
 
@@ -239,15 +819,16 @@ mod tests {
             .map(|i| fake_result(&format!("src/file_{}.rs", i)))
             .collect();
 
-        // Each line is ~16 chars. Budget of 10 tokens = 40 chars = ~2 lines
-        let (output, exhausted, omitted) = build_files_only_with_budget(&results, Some(10));
+        // Each line is ~6 tokens, so a budget of 10 fits one.
+        let (output, exhausted, omitted) =
+            build_files_only_with_budget(&results, Some(10), BudgetModel::default(), false);
         assert!(exhausted, "budget should be exhausted");
         assert!(omitted > 0, "should have omitted results");
         assert!(output.contains("budget exceeded"), "should show budget exceeded message");
 
         // Without budget, all should be emitted
         let (output_full, exhausted_full, omitted_full) =
-            build_files_only_with_budget(&results, None);
+            build_files_only_with_budget(&results, None, BudgetModel::default(), false);
         assert!(!exhausted_full);
         assert_eq!(omitted_full, 0);
         assert_eq!(output_full.lines().count(), 10);
@@ -259,12 +840,28 @@ mod tests {
             .map(|i| fake_result(&format!("src/file_{}.rs", i)))
             .collect();
 
-        let (output, exhausted, omitted) = build_files_only_with_budget(&results, None);
+        let (output, exhausted, omitted) = build_files_only_with_budget(&results, None, BudgetModel::default(), false);
         assert!(!exhausted);
         assert_eq!(omitted, 0);
         assert_eq!(output.lines().count(), 5);
     }
 
+    #[test]
+    fn files_only_null_separated() {
+        let results: Vec<SearchResult> = (0..10)
+            .map(|i| fake_result(&format!("src/my file_{}.rs", i)))
+            .collect();
+
+        let (output, _, _) = build_files_only_with_budget(&results, None, BudgetModel::default(), true);
+        assert_eq!(output.split_terminator('\0').count(), 10);
+        assert!(!output.contains('\n'));
+
+        let (output, exhausted, _) = build_files_only_with_budget(&results, Some(10), BudgetModel::default(), true);
+        assert!(exhausted);
+        assert!(output.ends_with('\0'), "no trailing marker in NUL mode");
+        assert!(!output.contains("budget exceeded"));
+    }
+
     #[test]
     fn text_budget_truncates() {
         use std::path::PathBuf;
@@ -280,14 +877,23 @@ mod tests {
         ];
 
         let opts_with_budget = SearchOptions {
-            budget: Some(50), // Very small budget: 50 tokens = 200 chars
+            budget: Some(50), // Very small budget
             max_context_lines: Some(5),
             ..Default::default()
         };
 
         let (output, exhausted, omitted) =
-            build_text_with_budget(&fixture, results, "EventStore", &opts_with_budget);
-        // The first result alone is >200 chars, so budget check kicks in before result 2
+            build_text_with_budget(
+                &fixture,
+                results,
+                "EventStore",
+                None,
+                None,
+                &opts_with_budget,
+                &mut Vec::new(),
+                &mut Vec::new(),
+            );
+        // The first result alone is >50 tokens, so budget check kicks in before result 2
         // But we always emit at least one result
         assert!(
             output.contains("[1]"),
@@ -299,6 +905,140 @@ mod tests {
         }
     }
 
+    #[test]
+    fn degrade_to_fit_cuts_context_before_dropping_results() {
+        let display = |rank: usize, lines: usize| DisplayResult {
+            rank,
+            result: fake_result(&format!("src/file_{}.rs", rank)),
+            context_lines: (1..=lines)
+                .map(|n| ContextLine {
+                    line_number: n,
+                    text: "x".repeat(9),
+                })
+                .collect(),
+            truncated_count: 0,
+            matches: vec![3],
+            merged: Vec::new(),
+        };
+        let cost = |d: &DisplayResult| 10 + 10 * d.context_lines.len();
+        let displays = || vec![display(1, 4), display(2, 4), display(3, 4)];
+
+        // Everything fits: untouched.
+        let mut all = displays();
+        assert_eq!(degrade_to_fit(&mut all, 150, cost), (Vec::new(), 0));
+        assert_eq!(all[2].context_lines.len(), 4);
+
+        // Cut to the top matching line, the first result gets its context back.
+        let mut cut = displays();
+        let (degraded, omitted) = degrade_to_fit(&mut cut, 100, cost);
+        assert_eq!(omitted, 0);
+        assert_eq!(cut[0].context_lines.len(), 4);
+        assert_eq!(cut[1].context_lines[0].line_number, 3, "keeps the matching line");
+        assert_eq!(cut[1].truncated_count, 3);
+        let ranks: Vec<usize> = degraded.iter().map(|d| d.rank).collect();
+        assert_eq!(ranks, vec![2, 3]);
+        assert_eq!(degraded[0].cut_lines, 3);
+
+        // Too tight even for one line each: the tail is dropped.
+        let mut tight = displays();
+        let (degraded, omitted) = degrade_to_fit(&mut tight, 45, cost);
+        assert_eq!((tight.len(), omitted), (2, 1));
+        assert_eq!(degraded.len(), 2);
+    }
+
+    #[test]
+    fn merge_same_path_unites_context_under_one_header() {
+        let symbol = |name: &str, start: usize, end: usize| SymbolHit {
+            name: name.to_string(),
+            kind: crate::indexer::symbols::SymbolKind::Function,
+            start_line: start,
+            end_line: end,
+        };
+        let display = |path: &str, lines: &[usize], hit: SymbolHit| {
+            let mut result = fake_result(path);
+            result.symbol = Some(hit);
+            DisplayResult {
+                rank: 0,
+                result,
+                context_lines: lines
+                    .iter()
+                    .map(|&n| ContextLine { line_number: n, text: String::new() })
+                    .collect(),
+                truncated_count: 1,
+                matches: vec![lines[0]],
+                merged: Vec::new(),
+            }
+        };
+        let merged = merge_same_path(vec![
+            display("src/a.rs", &[10, 11, 12], symbol("open", 10, 12)),
+            display("src/b.rs", &[3], symbol("close", 3, 3)),
+            display("src/a.rs", &[2, 11, 12, 13], symbol("read", 2, 13)),
+        ]);
+        assert_eq!(merged.len(), 2);
+        let a = &merged[0];
+        let shown: Vec<usize> = a.context_lines.iter().map(|l| l.line_number).collect();
+        assert_eq!(shown, vec![2, 10, 11, 12, 13]);
+        assert_eq!(a.matches, vec![2, 10]);
+        assert_eq!(a.truncated_count, 2);
+        assert_eq!(a.merged, vec![symbol("read", 2, 13)]);
+        assert_eq!((merged[1].rank, merged[1].result.path.as_str()), (2, "src/b.rs"));
+    }
+
+    #[test]
+    fn text_breadth_first_budget_keeps_every_result() {
+        use std::path::PathBuf;
+
+        let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/sample_repo");
+        let results = || {
+            vec![
+                fake_result("src/event_store.rs"),
+                fake_result("src/event_manager.ex"),
+                fake_result("README.md"),
+            ]
+        };
+        let opts = SearchOptions {
+            budget: Some(300),
+            max_context_lines: Some(0),
+            ..Default::default()
+        };
+
+        // Rank-order filling cuts context rather than dropping results.
+        let mut degraded = Vec::new();
+        let (output, depth_exhausted, _) =
+            build_text_with_budget(
+                &fixture,
+                results(),
+                "event",
+                None,
+                None,
+                &opts,
+                &mut Vec::new(),
+                &mut degraded,
+            );
+        assert!(!depth_exhausted, "{}", output);
+        assert!(!degraded.is_empty(), "the full context should not fit");
+
+        let breadth = SearchOptions {
+            breadth_first: true,
+            ..opts
+        };
+        let (output, exhausted, omitted) =
+            build_text_with_budget(
+                &fixture,
+                results(),
+                "event",
+                None,
+                None,
+                &breadth,
+                &mut Vec::new(),
+                &mut Vec::new(),
+            );
+        assert!(!exhausted && omitted == 0, "{}", output);
+        assert!(output.contains("[3]"));
+        assert!(BudgetModel::default().cost(&output) <= 300);
+    }
+
     #[test]
     fn text_no_budget_emits_all() {
         use std::path::PathBuf;
@@ -317,7 +1057,16 @@ mod tests {
         };
 
         let (output, exhausted, _omitted) =
-            build_text_with_budget(&fixture, results, "EventStore", &opts_no_budget);
+            build_text_with_budget(
+                &fixture,
+                results,
+                "EventStore",
+                None,
+                None,
+                &opts_no_budget,
+                &mut Vec::new(),
+                &mut Vec::new(),
+            );
         assert!(!exhausted);
         assert!(output.contains("[1]"));
         assert!(output.contains("[2]"));
@@ -340,6 +1089,10 @@ mod tests {
             total_results: 3,
             files_searched: 10,
             elapsed_ms: 1,
+            stale_results: 0,
+            indexed_commit: None,
+            route: None,
+            no_symbols: false,
         };
 
         let opts = SearchOptions {
@@ -349,7 +1102,16 @@ mod tests {
         };
 
         let (output, exhausted, omitted) =
-            build_json_with_budget(&fixture, results, "EventStore", &opts, &stats);
+            build_json_with_budget(
+                &fixture,
+                results,
+                "EventStore",
+                None,
+                &opts,
+                &stats,
+                &mut Vec::new(),
+                &mut Vec::new(),
+            );
 
         let parsed: serde_json::Value = serde_json::from_str(&output).expect("valid JSON");
         assert!(parsed["results"].is_array());
@@ -376,6 +1138,10 @@ mod tests {
             total_results: 1,
             files_searched: 10,
             elapsed_ms: 1,
+            stale_results: 0,
+            indexed_commit: None,
+            route: None,
+            no_symbols: false,
         };
 
         let opts = SearchOptions {
@@ -384,7 +1150,16 @@ mod tests {
         };
 
         let (output, exhausted, _) =
-            build_json_with_budget(&fixture, results, "EventStore", &opts, &stats);
+            build_json_with_budget(
+                &fixture,
+                results,
+                "EventStore",
+                None,
+                &opts,
+                &stats,
+                &mut Vec::new(),
+                &mut Vec::new(),
+            );
 
         assert!(!exhausted);
         let parsed: serde_json::Value = serde_json::from_str(&output).expect("valid JSON");
@@ -1,19 +1,47 @@
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::ops::Bound;
+use std::path::{Component, Path, PathBuf};
 use std::time::Instant;
 
-use tantivy::collector::TopDocs;
+use tantivy::collector::{DocSetCollector, TopDocs};
 use tantivy::query::{
-    BooleanQuery, BoostQuery, FuzzyTermQuery, Occur, Query, QueryParser, TermQuery,
+    BooleanQuery, BoostQuery, ConstScoreQuery, EnableScoring, FuzzyTermQuery, Occur,
+    PhraseQuery, Query, QueryParser, RangeQuery, RegexQuery, Scorer, TermQuery, Weight,
+};
+use tantivy::schema::{Field, IndexRecordOption, Value};
+use tantivy::{
+    DocAddress, DocSet, Index, IndexReader, ReloadPolicy, TantivyDocument, Term, TERMINATED,
 };
-use tantivy::schema::{IndexRecordOption, Value};
-use tantivy::{ReloadPolicy, TantivyDocument, Term};
 
 use crate::error::NsError;
-use crate::indexer::writer::open_index;
-use crate::schema::{content_field, lang_field, path_field, symbols_field, symbols_raw_field};
+use crate::indexer::bundle::open_bundle;
+use crate::indexer::prune::VENDOR_DIRS;
+use crate::indexer::language::LANGUAGES;
+use crate::indexer::minhash;
+use crate::indexer::symbols::{kinds_from_bytes, spans_from_bytes, SymbolKind};
+use crate::indexer::tokenizer::part_tokens;
+use crate::indexer::vectors::semantic_query;
+use crate::indexer::writer::IndexMeta;
+use crate::schema::{
+    chunk_line_field, content_field, content_hash_field, content_stem_field, docs_field, filename_field,
+    generated_field, is_test_field, lang_field, line_starts_field, minhash_field, mtime_field, path_field,
+    size_field, symbol_kinds_field, symbol_spans_field, symbols_def_field, symbols_field,
+    symbols_raw_field, truncated_field,
+};
+
+use super::budget::BudgetModel;
+use super::context::ContextScope;
+use super::dedupe::{collapse_near_duplicates, dedupe_by_content, NearDuplicate};
+use super::explain::{TermContribution, TermExplainer};
+use super::granularity::{Granularity, SymbolHit};
+use super::match_lines::LineMatcher;
+use super::format::DEFAULT_GROUP_SEPARATOR;
+use super::rerank::{rerank, Candidate, RerankSignals, Reranker, RERANK_POOL};
+use super::sample::{cap_per_dir, stratified, stratum, SAMPLE_POOL};
+use super::session::open_with_reader;
 
 /// A single search result from the tantivy index.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SearchResult {
     /// File path relative to the repo root.
     pub path: String,
@@ -23,12 +51,53 @@ pub struct SearchResult {
     pub lang: Option<String>,
     /// Raw symbol names extracted from the document (pipe-separated in index).
     pub symbols_raw: Vec<String>,
+    /// Definition line of each symbol in `symbols_raw`, same order
+    /// (`symbols_def` field). Empty for indexes that don't store them.
+    pub symbol_defs: Vec<String>,
+    /// First and last line of each symbol in `symbols_raw`, same order
+    /// (`symbol_spans` field). Empty for scan results.
+    pub symbol_spans: Vec<(usize, usize)>,
+    /// Kind of each symbol in `symbols_raw`, same order (`symbol_kinds`
+    /// field). Empty for scan results.
+    pub symbol_kinds: Vec<SymbolKind>,
+    /// The symbol this result stands for with `--granularity symbol` (see
+    /// `granularity`); `None` for a whole file.
+    pub symbol: Option<SymbolHit>,
     /// BM25 score contribution from the `content` field only.
     pub score_content: f32,
     /// BM25 score contribution from the `symbols` field only.
     pub score_symbols: f32,
     /// Which fields contributed to the match (e.g. ["content"], ["symbols"], or both).
     pub matched_fields: Vec<String>,
+    /// Second-stage ranking signals; `score` includes their multiplier.
+    /// All default for regex and sampled results, which skip that stage.
+    pub rerank: RerankSignals,
+    /// The file looks like a test (`is_test` field), so `score` includes
+    /// `test_weight`.
+    pub is_test: bool,
+    /// The path is under one of `vendor_dirs`, so `score` includes
+    /// `vendor_weight`.
+    pub vendored: bool,
+    /// Only the head of the file is indexed (`truncated` field, `ns index
+    /// --truncate-large`), so matches further down are missed.
+    pub truncated: bool,
+    /// The file looks generated (`generated` field), so `score` includes
+    /// `generated_weight`.
+    pub generated: bool,
+    /// Per-term, per-field score contributions, highest first (only with
+    /// `explain`).
+    pub terms: Vec<TermContribution>,
+    /// Paths of lower-ranked results with identical content, collapsed into this one.
+    pub alternates: Vec<String>,
+    /// Lower-ranked results with nearly the same content (stored `minhash`
+    /// signatures), collapsed into this one.
+    pub duplicates: Vec<NearDuplicate>,
+    /// Hash of the file content as indexed (see `source::indexed_source`).
+    pub content_hash: Option<u64>,
+    /// 1-based lines of the indexed content holding a query term, read from
+    /// the postings (see `match_lines`). Empty when unknown, in which case
+    /// context extraction scans the file for the query's words.
+    pub match_lines: Vec<usize>,
 }
 
 /// Summary statistics for a search operation.
@@ -40,10 +109,38 @@ pub struct SearchStats {
     pub files_searched: usize,
     /// Time taken for the search in milliseconds.
     pub elapsed_ms: u64,
+    /// Results dropped because their file no longer exists on disk.
+    pub stale_results: usize,
+    /// Git commit the index was last updated at, if the repo is a git repo.
+    pub indexed_commit: Option<String>,
+    /// How an identifier-like query was routed (see `execute_search`);
+    /// `None` when routing didn't apply.
+    pub route: Option<SearchRoute>,
+    /// The index was built with `ns index --no-symbols`, so nothing matched
+    /// symbols or got their boost.
+    pub no_symbols: bool,
+}
+
+/// Route taken by automatic query routing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchRoute {
+    /// Symbol matches came first, followed by the usual ranking.
+    Symbols,
+    /// No symbol matched, so the usual ranking was used alone.
+    Content,
+}
+
+impl SearchRoute {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchRoute::Symbols => "symbols",
+            SearchRoute::Content => "content",
+        }
+    }
 }
 
 /// Options that control search behaviour — maps 1:1 to CLI flags.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SearchOptions {
     /// Maximum number of results.
     pub max_results: usize,
@@ -61,8 +158,107 @@ pub struct SearchOptions {
     pub max_context_lines: Option<usize>,
     /// Token budget for total output. None means unlimited (default).
     pub budget: Option<usize>,
+    /// How output is counted against `budget` (see `budget`).
+    pub budget_model: BudgetModel,
     /// Use AST-guided span extraction instead of grep-and-expand.
     pub spans: bool,
+    /// Context around each match: a line window or the enclosing function.
+    pub context_scope: ContextScope,
+    /// Collapse results with byte-identical file content (see `dedupe`).
+    pub dedupe: bool,
+    /// Terminate `FilesOnly` paths with NUL instead of newline (`-0`/`--null`).
+    pub null_separated: bool,
+    /// Re-rank by embedding similarity to the query (`--semantic`); needs
+    /// an index built with `--embed-model`.
+    pub semantic: bool,
+    /// Printed between non-contiguous groups of context lines in text
+    /// output (default `format::DEFAULT_GROUP_SEPARATOR`).
+    pub group_separator: String,
+    /// Appended after each result in text output, so tools can split
+    /// records even when file content looks like a result header.
+    pub record_separator: Option<String>,
+    /// Color text output with ANSI escapes (`--color`, resolved by the CLI).
+    pub color: bool,
+    /// Trim JSON results to the essentials (see `format::compact_json_value`).
+    pub json_compact: bool,
+    /// Treat the query as a regular expression matched line by line
+    /// (see `regex_search`).
+    pub regex: bool,
+    /// Treat the query as a literal, case-insensitive substring matched per
+    /// line (see `regex_search`).
+    pub substring: bool,
+    /// Also match content against the Porter-stemmed `content_stem` field.
+    /// Requires an index built with `ns index --stem`.
+    pub stem: bool,
+    /// Drop results whose file was deleted since the last index.
+    pub verify: bool,
+    /// Search this index bundle (`ns export --bundle`) instead of `.ns/`.
+    pub index_file: Option<PathBuf>,
+    /// Read context for changed files from the indexed git commit.
+    pub context_from_git: bool,
+    /// Show only the first line of each matching definition as context
+    /// (see `spans::signature_lines_in`).
+    pub signatures_only: bool,
+    /// Split `budget` evenly across results, trimming each one's context,
+    /// rather than filling it in rank order and dropping the tail.
+    pub breadth_first: bool,
+    /// BM25 candidates re-scored by the second ranking stage
+    /// (`RERANK_POOL` by default, fewer with `quick`).
+    pub rerank_pool: usize,
+    /// Hard cap on `max_results` (`--max-count-hard`); `Some(0)` lifts it.
+    /// `None` uses `MAX_RESULTS_CEILING`.
+    pub max_results_ceiling: Option<usize>,
+    /// Return a stratified sample of this many matches (see `sample`)
+    /// instead of the top `max_results`.
+    pub sample: Option<usize>,
+    /// Skip the cost guard that rejects queries matching nearly every file
+    /// (see `check_query_cost`).
+    pub force: bool,
+    /// Require every unquoted word to match (AND) rather than any (OR).
+    pub all_terms: bool,
+    /// Only match these files or directories, relative to the repo root
+    /// (see `path_prefix_query`). Empty searches everything.
+    pub paths: Vec<String>,
+    /// Score multipliers keyed by language name (`rust`), or by extension
+    /// (`json`, `lock`) for files without a detected language. Usually set
+    /// from `[lang_boost]` in `.ns/config.toml` (see `scale_by_lang`).
+    pub lang_boost: Vec<(String, f32)>,
+    /// Score multiplier for test files (see `language::is_test_path`);
+    /// 1.0 ranks them like any other file (`--tests`).
+    pub test_weight: f32,
+    /// Score multiplier for lockfiles, bundles and other generated files
+    /// (see `generated::is_generated`); 1.0 ranks them like any other file.
+    pub generated_weight: f32,
+    /// Score multiplier for files under any of `vendor_dirs`
+    /// (see `scale_vendored`); 1.0 disables the penalty.
+    pub vendor_weight: f32,
+    /// Directory names whose contents count as vendored or generated,
+    /// wherever they appear in a path. Default: `default_vendor_dirs()`.
+    pub vendor_dirs: Vec<String>,
+    /// Route identifier-like queries (`EventStore`, `event_store`) to a
+    /// symbol search first (see `execute_search`).
+    pub auto_route: bool,
+    /// Fill `SearchResult::terms` with per-term score contributions.
+    pub explain: bool,
+    /// Scan the files on disk instead of reading the index (see `scan`).
+    pub no_index: bool,
+    /// Keep at most this many results per directory (`--max-per-dir`),
+    /// filling the page with the next-best files from other directories.
+    pub max_per_dir: Option<usize>,
+    /// Whether each result is a file or a symbol in one (see
+    /// `granularity`).
+    pub granularity: Granularity,
+    /// Only files defining a symbol of one of these kinds whose name holds
+    /// a query word (`--kind`); with `--granularity symbol`, only symbols
+    /// of these kinds. Empty keeps every file.
+    pub kinds: Vec<SymbolKind>,
+    /// Only files of more than this many bytes (`--larger-than`).
+    pub larger_than: Option<u64>,
+    /// Only files of fewer than this many bytes (`--smaller-than`).
+    pub smaller_than: Option<u64>,
+    /// Only files last modified at or after this Unix time
+    /// (`--modified-since`). Sizes and times are as of the last index.
+    pub modified_since: Option<u64>,
 }
 
 impl Default for SearchOptions {
@@ -76,105 +272,563 @@ impl Default for SearchOptions {
             fuzzy: false,
             max_context_lines: Some(30),
             budget: None,
+            budget_model: BudgetModel::default(),
             spans: false,
+            context_scope: ContextScope::Lines,
+            dedupe: true,
+            null_separated: false,
+            semantic: false,
+            group_separator: DEFAULT_GROUP_SEPARATOR.to_string(),
+            record_separator: None,
+            color: false,
+            json_compact: false,
+            regex: false,
+            substring: false,
+            stem: false,
+            verify: true,
+            index_file: None,
+            context_from_git: false,
+            signatures_only: false,
+            breadth_first: false,
+            rerank_pool: RERANK_POOL,
+            max_results_ceiling: None,
+            sample: None,
+            force: false,
+            all_terms: false,
+            paths: Vec::new(),
+            lang_boost: Vec::new(),
+            test_weight: DEFAULT_TEST_WEIGHT,
+            generated_weight: DEFAULT_GENERATED_WEIGHT,
+            vendor_weight: DEFAULT_VENDOR_WEIGHT,
+            vendor_dirs: default_vendor_dirs(),
+            auto_route: true,
+            explain: false,
+            no_index: false,
+            max_per_dir: None,
+            granularity: Granularity::File,
+            kinds: Vec::new(),
+            larger_than: None,
+            smaller_than: None,
+            modified_since: None,
+        }
+    }
+}
+
+/// Default `test_weight`: tests still match, but below the code they test.
+pub const DEFAULT_TEST_WEIGHT: f32 = 0.5;
+
+/// Default `generated_weight`: a lockfile or bundle only surfaces when
+/// nothing hand-written matches.
+pub const DEFAULT_GENERATED_WEIGHT: f32 = 0.1;
+
+/// Default `vendor_weight`: copies of third-party code and build output
+/// rank well below the repo's own files.
+pub const DEFAULT_VENDOR_WEIGHT: f32 = 0.3;
+
+/// Build output directories penalized along with `prune::VENDOR_DIRS`, for
+/// repos that don't gitignore them.
+const BUILD_DIRS: &[&str] = &["target", "dist", "build"];
+
+/// Default `vendor_dirs`: vendored and build output directories.
+pub fn default_vendor_dirs() -> Vec<String> {
+    VENDOR_DIRS
+        .iter()
+        .chain(BUILD_DIRS)
+        .map(|d| d.to_string())
+        .collect()
+}
+
+/// Result cap for `--quick`.
+const QUICK_MAX_RESULTS: usize = 5;
+/// Signature lines shown per result in `--quick`.
+const QUICK_CONTEXT_LINES: usize = 2;
+/// Token budget for `--quick`.
+const QUICK_BUDGET: usize = 300;
+/// Candidates re-ranked in `--quick`.
+const QUICK_RERANK_POOL: usize = 20;
+
+/// Result count for `--deep`.
+const DEEP_MAX_RESULTS: usize = 30;
+/// Context lines per result for `--deep`.
+const DEEP_CONTEXT_LINES: usize = 80;
+
+impl SearchOptions {
+    /// The `--quick` preset: trades completeness for latency and size.
+    ///
+    /// Caps results, budget and context (signature lines only), compacts JSON,
+    /// re-ranks only the top `QUICK_RERANK_POOL` candidates, and turns off
+    /// deduplication so no extra candidates are fetched and no files are read
+    /// just to be hashed. Tighter limits already set are kept.
+    pub fn quick(mut self) -> Self {
+        self.max_results = self.max_results.min(QUICK_MAX_RESULTS);
+        self.context_window = 0;
+        self.max_context_lines = Some(match self.max_context_lines {
+            Some(n) if n > 0 => n.min(QUICK_CONTEXT_LINES),
+            _ => QUICK_CONTEXT_LINES,
+        });
+        self.budget = Some(self.budget.map_or(QUICK_BUDGET, |b| b.min(QUICK_BUDGET)));
+        self.signatures_only = true;
+        self.json_compact = true;
+        self.dedupe = false;
+        self.rerank_pool = self.rerank_pool.min(QUICK_RERANK_POOL);
+        self
+    }
+
+    /// The `--deep` preset: maximal context for the one query where it pays.
+    ///
+    /// Raises the result count, shows whole enclosing definitions (`--spans`)
+    /// with a generous per-file line cap, and, under a budget, spreads it
+    /// across all results breadth-first. Larger limits already set are kept.
+    pub fn deep(mut self) -> Self {
+        self.max_results = self.max_results.max(DEEP_MAX_RESULTS);
+        self.spans = true;
+        self.signatures_only = false;
+        self.max_context_lines = match self.max_context_lines {
+            Some(0) | None => Some(0),
+            Some(n) => Some(n.max(DEEP_CONTEXT_LINES)),
+        };
+        self.breadth_first = true;
+        self
+    }
+}
+
+/// Default maximum number of results, to prevent unbounded file I/O during
+/// context extraction. Overridable per search via `max_results_ceiling`.
+pub(crate) const MAX_RESULTS_CEILING: usize = 100;
+
+impl SearchOptions {
+    /// The hard result cap in effect: `max_results_ceiling`, where 0 means
+    /// none, or `MAX_RESULTS_CEILING` by default.
+    pub(crate) fn result_ceiling(&self) -> usize {
+        match self.max_results_ceiling {
+            None => MAX_RESULTS_CEILING,
+            Some(0) => usize::MAX,
+            Some(n) => n,
+        }
+    }
+}
+
+/// Executes a search query against the index at `root`, routing
+/// identifier-like queries.
+///
+/// With `auto_route`, a single CamelCase or snake_case word (see
+/// `is_identifier_query`) is first searched in symbols only. Files defining
+/// a matching symbol come first, followed by the rest of the usual ranking;
+/// if no symbol matches, the usual ranking is returned as is. `stats.route`
+/// records which happened. Explicit modes (`sym_only`, `fuzzy`, `sample`)
+/// skip routing.
+pub fn execute_search(
+    root: &Path,
+    query_str: &str,
+    opts: &SearchOptions,
+) -> Result<(Vec<SearchResult>, SearchStats), NsError> {
+    if !opts.auto_route
+        || opts.sym_only
+        || opts.fuzzy
+        || opts.sample.is_some()
+        || !is_identifier_query(query_str)
+    {
+        return execute_ranked(root, query_str, opts);
+    }
+
+    let sym_opts = SearchOptions {
+        sym_only: true,
+        ..opts.clone()
+    };
+    let (sym_results, sym_stats) = execute_ranked(root, query_str, &sym_opts)?;
+    let (mut ranked, mut stats) = execute_ranked(root, query_str, opts)?;
+    stats.route = Some(if sym_results.is_empty() {
+        SearchRoute::Content
+    } else {
+        SearchRoute::Symbols
+    });
+
+    // Symbol hits keep their order but take their scores and matched fields
+    // from the full ranking when it has them.
+    let mut results = Vec::with_capacity(ranked.len().max(sym_results.len()));
+    for sym_result in sym_results {
+        match ranked.iter().position(|r| r.path == sym_result.path) {
+            Some(i) => results.push(ranked.remove(i)),
+            None => results.push(sym_result),
         }
     }
+    let limit = opts.max_results.min(opts.result_ceiling());
+    for result in ranked {
+        if results.len() >= limit && opts.max_per_dir.is_none() {
+            break;
+        }
+        let seen = results
+            .iter()
+            .any(|r| {
+                r.path == result.path
+                    || r.alternates.contains(&result.path)
+                    || r.duplicates.iter().any(|d| d.path == result.path)
+            });
+        if !seen {
+            results.push(result);
+        }
+    }
+    if let Some(max) = opts.max_per_dir {
+        results = cap_per_dir(results, max, |r| r.path.as_str());
+    }
+    results.truncate(limit);
+    stats.total_results = results.len();
+    stats.elapsed_ms += sym_stats.elapsed_ms;
+    stats.stale_results = stats.stale_results.max(sym_stats.stale_results);
+    Ok((results, stats))
 }
 
-/// Maximum number of results to prevent unbounded file I/O during context extraction.
-const MAX_RESULTS_CEILING: usize = 100;
+/// Whether `query` is a single identifier with internal structure: a
+/// CamelCase word (`EventStore`, `parseHTTP`) or a snake_case one
+/// (`event_store`). Plain words are left to the usual ranking.
+fn is_identifier_query(query: &str) -> bool {
+    let word = query.trim();
+    if word.is_empty() || !word.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return false;
+    }
+    let chars: Vec<char> = word.chars().collect();
+    let camel = chars
+        .windows(2)
+        .any(|w| w[0].is_lowercase() && w[1].is_uppercase());
+    let snake = chars
+        .windows(3)
+        .any(|w| w[0].is_alphanumeric() && w[1] == '_' && w[2].is_alphanumeric());
+    camel || snake
+}
 
-/// Executes a search query against the index at `root`.
+/// Executes a search query against the index at `root`, without routing.
 ///
 /// Opens the index (reads `meta.json` once), executes the BM25 query,
 /// and returns ranked results plus stats.
-/// `max_results` is clamped to `MAX_RESULTS_CEILING` (100) to prevent
-/// unbounded disk I/O during context extraction.
+/// `max_results` is clamped to `SearchOptions::result_ceiling` (100 unless
+/// overridden) to prevent unbounded disk I/O during context extraction.
 ///
 /// Search modes:
+/// - Quoted phrases (`"event store"`) in any mode become required
+///   `PhraseQuery` clauses, so their words must be adjacent and in order;
+///   `"init server"~5` lets them be up to five positions apart.
 /// - Default: searches both `content` and `symbols` fields, 3x boost on `symbols`.
 /// - `sym_only`: searches only `symbols` field (no content).
 /// - `fuzzy`: builds per-term `FuzzyTermQuery` (Levenshtein distance 1) instead
 ///   of using the `QueryParser`, with `Should` occurrence so any term can match.
+/// - `all_terms`: in any of the above, every unquoted word must match (in
+///   either field) instead of any one of them.
 ///
 /// Filters:
 /// - `file_type`: restricts results to files with the given language via a
 ///   `TermQuery` on the `lang` field combined with `BooleanQuery`.
+/// - `paths`: restricts results to the given files and directories via
+///   `path_prefix_query` on the `path` field.
 /// - `file_glob`: post-filters results by matching `path` against a glob pattern.
-pub fn execute_search(
+///
+/// With `verify`, results for files deleted since the last index are dropped
+/// and counted in `stale_results`.
+///
+/// With `dedupe`, files with identical content collapse into the best-ranked
+/// copy; the others are listed in its `alternates`. Near-identical files (by
+/// their stored `minhash` signatures) collapse the same way into its
+/// `duplicates`.
+///
+/// With `sample`, up to `SAMPLE_POOL` matches are ranked and a stratified
+/// sample of them is returned instead of the top `max_results`.
+///
+/// Ranking runs in two stages: the best `rerank_pool` documents by BM25
+/// (with the language, test and vendor factors) are re-scored by
+/// `rerank::Reranker`, which multiplies in an exact symbol bonus and the
+/// query's coverage, proximity, path and recency signals. Results whose
+/// symbols include an unquoted query word exactly (`EventStore`, not
+/// `EventStoreError`) thereby outrank files that only mention it.
+fn execute_ranked(
     root: &Path,
     query_str: &str,
     opts: &SearchOptions,
 ) -> Result<(Vec<SearchResult>, SearchStats), NsError> {
-    let max_results = opts.max_results.min(MAX_RESULTS_CEILING);
-    let (index, meta) = open_index(root)?;
+    let ceiling = opts.result_ceiling();
+    let max_results = opts.sample.unwrap_or(opts.max_results).min(ceiling);
+    let (index, meta, reader) = open_search_index(root, opts)?;
 
     let schema = index.schema();
     let content = content_field(&schema);
     let symbols_f = symbols_field(&schema);
+    let docs_f = docs_field(&schema);
+    let filename_f = filename_field(&schema);
     let path_f = path_field(&schema);
     let lang_f = lang_field(&schema);
     let symbols_raw_f = symbols_raw_field(&schema);
+    let symbols_def_f = symbols_def_field(&schema);
+    let symbol_spans_f = symbol_spans_field(&schema);
+    let symbol_kinds_f = symbol_kinds_field(&schema);
+    let content_hash_f = content_hash_field(&schema);
+    let is_test_f = is_test_field(&schema);
+    let truncated_f = truncated_field(&schema);
+    let generated_f = generated_field(&schema);
+    let minhash_f = minhash_field(&schema);
+    if opts.stem && !meta.stem {
+        return Err(NsError::MissingIndexOption("--stem"));
+    }
+    // With --stem, unquoted terms match stemmed content as well; an exact
+    // form also matching `content` still scores higher.
+    let mut content_fields = vec![content];
+    if opts.stem {
+        content_fields.push(content_stem_field(&schema));
+    }
+
+    // Quoted phrases become required PhraseQuery clauses; the rest of the
+    // query keeps its usual any-term semantics and only adds to the score.
+    let (phrases, rest) = split_phrases(query_str, meta.cjk);
+    let (excluded, rest) = split_negations(&rest, meta.cjk);
+    let phrase_field = if opts.sym_only { symbols_f } else { content };
+    let has_rest = phrases.is_empty() || !rest.trim().is_empty();
 
     // Build the base query based on mode
-    let base_query: Box<dyn Query> = if opts.fuzzy {
-        build_fuzzy_query(query_str, content, symbols_f, opts.sym_only)
-    } else if opts.sym_only {
-        let parser = QueryParser::for_index(&index, vec![symbols_f]);
-        parser.parse_query(query_str)?
+    let rest_query: Option<Box<dyn Query>> = if !has_rest {
+        None
+    } else if opts.fuzzy {
+        Some(build_fuzzy_query(&rest, content, symbols_f, opts.sym_only, opts.all_terms))
     } else {
-        let mut parser = QueryParser::for_index(&index, vec![content, symbols_f]);
-        parser.set_field_boost(symbols_f, 3.0);
-        parser.parse_query(query_str)?
+        let mut fields = if opts.sym_only {
+            Vec::new()
+        } else {
+            content_fields.clone()
+        };
+        fields.push(symbols_f);
+        if !opts.sym_only {
+            fields.push(docs_f);
+            fields.push(filename_f);
+        }
+        let mut parser = QueryParser::for_index(&index, fields);
+        if !opts.sym_only {
+            parser.set_field_boost(symbols_f, SYMBOLS_BOOST);
+            parser.set_field_boost(docs_f, DOCS_BOOST);
+            parser.set_field_boost(filename_f, FILENAME_BOOST);
+        }
+        if opts.all_terms {
+            parser.set_conjunction_by_default();
+        }
+        Some(parser.parse_query(&rest)?)
     };
+    let base_query = without_terms(
+        with_phrases(rest_query, &phrases, phrase_field),
+        &excluded,
+        content,
+    );
 
-    // Wrap with language filter if specified
-    let query: Box<dyn Query> = if let Some(ref lang_filter) = opts.file_type {
-        let lang_query: Box<dyn Query> = Box::new(TermQuery::new(
-            Term::from_field_text(lang_f, lang_filter),
-            IndexRecordOption::Basic,
+    // Wrap with language and path filters if specified
+    let mut filters: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+    if let Some(ref lang_filter) = opts.file_type {
+        filters.push((
+            Occur::Must,
+            Box::new(TermQuery::new(
+                Term::from_field_text(lang_f, lang_filter),
+                IndexRecordOption::Basic,
+            )),
         ));
-        Box::new(BooleanQuery::new(vec![
-            (Occur::Must, base_query),
-            (Occur::Must, lang_query),
-        ]))
-    } else {
+    }
+    if let Some(path_query) = path_prefix_query(root, path_f, &opts.paths) {
+        filters.push((Occur::Must, path_query));
+    }
+    filters.extend(
+        metadata_filters(&schema, opts)
+            .into_iter()
+            .map(|q| (Occur::Must, q)),
+    );
+    let query: Box<dyn Query> = if filters.is_empty() {
         base_query
+    } else {
+        filters.insert(0, (Occur::Must, base_query));
+        Box::new(BooleanQuery::new(filters))
     };
+    let query = scale_by_lang(query, lang_f, path_f, &opts.lang_boost)?;
+    let query = scale_flagged(query, is_test_f, opts.test_weight);
+    let query = scale_flagged(query, generated_f, opts.generated_weight);
+    let query = scale_vendored(query, path_f, &opts.vendor_dirs, opts.vendor_weight)?;
 
-    let reader = create_reader_with_retry(&index, root)?;
     let searcher = reader.searcher();
+    if !opts.force && !opts.fuzzy && phrases.is_empty() {
+        check_query_cost(&searcher, phrase_field, &rest, opts.all_terms)?;
+    }
 
     let start = Instant::now();
-    let top_docs = searcher.search(&query, &TopDocs::with_limit(max_results))?;
+    // Over-fetch when deduplicating so collapsed copies don't shrink the page.
+    // Never ask the collector for more slots than there are documents: with
+    // the ceiling lifted, `max_results` can be arbitrarily large.
+    // A per-directory cap or `--kind` can drop most of a page, so they keep
+    // the whole re-ranked pool to refill from.
+    let fetch_limit = if opts.max_per_dir.is_some() || !opts.kinds.is_empty() {
+        max_results.max(opts.rerank_pool)
+    } else if opts.dedupe {
+        max_results.saturating_mul(2).min(ceiling)
+    } else {
+        max_results
+    };
+    let num_docs = searcher.num_docs() as usize;
+    let candidates = if opts.sample.is_some() {
+        // A sample is drawn from a much larger candidate pool, ranked by
+        // BM25 only: re-ranking it all would cost more than the sample is
+        // worth.
+        let pool_limit = fetch_limit.max(SAMPLE_POOL).min(num_docs).max(1);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(pool_limit))?;
+        let mut keyed = Vec::with_capacity(top_docs.len());
+        for (score, address) in top_docs {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let path = doc.get_first(path_f).and_then(|v| v.as_str()).unwrap_or("");
+            let lang = doc.get_first(lang_f).and_then(|v| v.as_str()).filter(|s| !s.is_empty());
+            let key = stratum(path, lang);
+            keyed.push((
+                Candidate {
+                    score,
+                    address,
+                    doc,
+                    signals: RerankSignals::default(),
+                },
+                key,
+            ));
+        }
+        keyed = best_chunks(keyed, |(c, _)| &c.doc, path_f);
+        // No over-fetch here: the picks come back in rank order, so any
+        // extra would push out the other strata when the list is truncated.
+        stratified(keyed, max_results)
+    } else {
+        // Stage 1: the best `rerank_pool` documents by BM25, from the
+        // inverted index alone.
+        let pool_limit = fetch_limit.max(opts.rerank_pool).min(num_docs).max(1);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(pool_limit))?;
+        let mut candidates = Vec::with_capacity(top_docs.len());
+        for (score, address) in top_docs {
+            candidates.push(Candidate {
+                score,
+                address,
+                doc: searcher.doc(address)?,
+                signals: RerankSignals::default(),
+            });
+        }
+        // Stage 2: re-score them with signals from stored fields, positions
+        // and the file system, then keep the page.
+        let mut word_fields = if opts.sym_only {
+            Vec::new()
+        } else {
+            content_fields.clone()
+        };
+        word_fields.push(symbols_f);
+        let proximity_fields = if opts.sym_only {
+            Vec::new()
+        } else {
+            content_fields.clone()
+        };
+        let mut reranker = Reranker::new(
+            &index,
+            &searcher,
+            root,
+            path_f,
+            symbols_raw_f,
+            symbol_kinds_f,
+            &rest,
+            word_fields,
+            proximity_fields,
+            opts.fuzzy,
+        );
+        if opts.semantic {
+            let (vectors, query) = semantic_query(root, query_str)?;
+            reranker = reranker.with_semantic(vectors, query);
+        }
+        rerank(&mut candidates, &reranker);
+        let mut candidates = best_chunks(candidates, |c| &c.doc, path_f);
+        candidates.truncate(fetch_limit);
+        candidates
+    };
     let elapsed_ms = start.elapsed().as_millis() as u64;
 
     // Build per-field queries for re-scoring (explainable ranking).
     // These are only evaluated against the top-N docs, not the full index.
     let content_query: Option<Box<dyn Query>> = if opts.sym_only {
         None // No content field in sym-only mode
-    } else if opts.fuzzy {
-        Some(build_fuzzy_single_field_query(query_str, content))
     } else {
-        let parser = QueryParser::for_index(&index, vec![content]);
-        parser.parse_query(query_str).ok()
+        let rest_query = if !has_rest {
+            None
+        } else if opts.fuzzy {
+            Some(build_fuzzy_single_field_query(&rest, content))
+        } else {
+            let parser = QueryParser::for_index(&index, content_fields.clone());
+            parser.parse_query(&rest).ok()
+        };
+        Some(with_phrases(rest_query, &phrases, content))
     };
-    let symbols_query: Option<Box<dyn Query>> = if opts.fuzzy {
-        Some(build_fuzzy_single_field_query(query_str, symbols_f))
+    let symbols_query: Option<Box<dyn Query>> = {
+        let rest_query = if !has_rest {
+            None
+        } else if opts.fuzzy {
+            Some(build_fuzzy_single_field_query(&rest, symbols_f))
+        } else {
+            let parser = QueryParser::for_index(&index, vec![symbols_f]);
+            parser.parse_query(&rest).ok()
+        };
+        if opts.sym_only {
+            Some(with_phrases(rest_query, &phrases, symbols_f))
+        } else {
+            rest_query
+        }
+    };
+
+    let scoring = EnableScoring::enabled_from_searcher(&searcher);
+    let content_weight = content_query.as_ref().and_then(|q| q.weight(scoring).ok());
+    let symbols_weight = symbols_query.as_ref().and_then(|q| q.weight(scoring).ok());
+
+    let explainer = if opts.explain && has_rest {
+        let symbols_boost = if opts.sym_only { 1.0 } else { SYMBOLS_BOOST };
+        let mut fields = vec![(symbols_f, symbols_boost)];
+        if !opts.sym_only {
+            fields.extend(content_fields.iter().map(|&f| (f, 1.0)));
+            fields.push((docs_f, DOCS_BOOST));
+            fields.push((filename_f, FILENAME_BOOST));
+        }
+        Some(TermExplainer::new(&index, &searcher, &rest, &fields)?)
     } else {
-        let parser = QueryParser::for_index(&index, vec![symbols_f]);
-        parser.parse_query(query_str).ok()
+        None
     };
 
-    let mut results = Vec::with_capacity(top_docs.len());
-    for (score, doc_address) in &top_docs {
-        let doc: TantivyDocument = searcher.doc(*doc_address)?;
+    // Fuzzy terms aren't in the postings as typed; their context falls back
+    // to scanning the file.
+    let line_matcher = if opts.fuzzy {
+        None
+    } else {
+        let (words, _) = split_query_terms(query_str, meta.cjk);
+        Some(LineMatcher::new(
+            &index,
+            &searcher,
+            line_starts_field(&schema),
+            chunk_line_field(&schema),
+            &words,
+            &content_fields,
+        )?)
+    };
 
+    // Stage 3: results for the kept candidates.
+    let mut results = Vec::with_capacity(candidates.len());
+    let mut signatures = HashMap::new();
+    for Candidate {
+        score,
+        address: doc_address,
+        doc,
+        signals,
+    } in candidates
+    {
         let path_val = doc
             .get_first(path_f)
             .and_then(|v| v.as_str())
             .unwrap_or("")
             .to_string();
+        // A chunked file lists the symbols of all its chunks, not only of
+        // the one that ranked best.
+        let chunks = if meta.chunk_lines.is_some() {
+            file_chunks(&searcher, path_f, chunk_line_field(&schema), &path_val)?
+        } else {
+            Vec::new()
+        };
+        let symbol_docs: Vec<&TantivyDocument> = if chunks.is_empty() {
+            vec![&doc]
+        } else {
+            chunks.iter().collect()
+        };
 
         let lang_val = doc
             .get_first(lang_f)
@@ -182,29 +836,18 @@ pub fn execute_search(
             .map(|s| s.to_string())
             .filter(|s| !s.is_empty());
 
-        let symbols_raw_val = doc
-            .get_first(symbols_raw_f)
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
+        let content_hash = doc.get_first(content_hash_f).and_then(|v| v.as_u64());
 
-        let symbols: Vec<String> = if symbols_raw_val.is_empty() {
-            Vec::new()
-        } else {
-            symbols_raw_val.split('|').map(|s| s.to_string()).collect()
-        };
+        let symbols: Vec<String> = symbol_docs
+            .iter()
+            .filter_map(|d| d.get_first(symbols_raw_f).and_then(|v| v.as_str()))
+            .filter(|s| !s.is_empty())
+            .flat_map(|s| s.split('|').map(|s| s.to_string()))
+            .collect();
 
         // Re-score against individual field queries for explainability.
-        let score_content = content_query
-            .as_ref()
-            .and_then(|q| q.explain(&searcher, *doc_address).ok())
-            .map(|e| e.value() as f32)
-            .unwrap_or(0.0);
-        let score_symbols = symbols_query
-            .as_ref()
-            .and_then(|q| q.explain(&searcher, *doc_address).ok())
-            .map(|e| e.value() as f32)
-            .unwrap_or(0.0);
+        let score_content = field_score(content_weight.as_deref(), &searcher, doc_address);
+        let score_symbols = field_score(symbols_weight.as_deref(), &searcher, doc_address);
 
         let mut matched_fields = Vec::new();
         if score_content > 0.0 {
@@ -214,14 +857,38 @@ pub fn execute_search(
             matched_fields.push("symbols".to_string());
         }
 
+        if let Some(signature) = stored_minhash(&doc, minhash_f) {
+            signatures.insert(path_val.clone(), signature);
+        }
+        let vendored = in_dirs(&path_val, &opts.vendor_dirs);
         results.push(SearchResult {
             path: path_val,
-            score: *score,
+            score,
             lang: lang_val,
             symbols_raw: symbols,
+            symbol_defs: symbol_docs.iter().flat_map(|d| stored_definitions(d, symbols_def_f)).collect(),
+            symbol_spans: symbol_docs.iter().flat_map(|d| stored_spans(d, symbol_spans_f)).collect(),
+            symbol_kinds: symbol_docs.iter().flat_map(|d| stored_kinds(d, symbol_kinds_f)).collect(),
+            symbol: None,
             score_content,
             score_symbols,
             matched_fields,
+            rerank: signals,
+            terms: explainer
+                .as_ref()
+                .map(|e| e.explain(doc_address))
+                .unwrap_or_default(),
+            is_test: doc.get_first(is_test_f).and_then(|v| v.as_u64()) == Some(1),
+            vendored,
+            truncated: doc.get_first(truncated_f).and_then(|v| v.as_u64()) == Some(1),
+            generated: doc.get_first(generated_f).and_then(|v| v.as_u64()) == Some(1),
+            alternates: Vec::new(),
+            duplicates: Vec::new(),
+            content_hash,
+            match_lines: line_matcher
+                .as_ref()
+                .map(|m| m.lines(doc_address, &doc))
+                .unwrap_or_default(),
         });
     }
 
@@ -231,15 +898,325 @@ pub fn execute_search(
         results.retain(|r| pattern.matches(&r.path));
     }
 
+    // With symbol granularity, `--kind` picks symbols out of the files instead.
+    if !opts.kinds.is_empty() && opts.granularity == Granularity::File {
+        let words = super::context::tokenize_query(query_str);
+        results.retain(|r| defines_kind(r, &words, &opts.kinds));
+    }
+
+    let stale_results = if opts.verify {
+        drop_missing(root, &mut results)
+    } else {
+        0
+    };
+
+    if opts.dedupe {
+        results = dedupe_by_content(root, results);
+        results = collapse_near_duplicates(results, &signatures);
+    }
+    if let Some(max) = opts.max_per_dir {
+        results = cap_per_dir(results, max, |r| r.path.as_str());
+    }
+    results.truncate(max_results);
+
     let stats = SearchStats {
         total_results: results.len(),
         files_searched: meta.file_count,
         elapsed_ms,
+        stale_results,
+        indexed_commit: meta.git_commit,
+        route: None,
+        no_symbols: meta.no_symbols,
     };
 
     Ok((results, stats))
 }
 
+/// `ranked` without the chunks of files ranked earlier: a file chunked by
+/// `ns index --chunk-lines` is represented by its best-ranked chunk.
+pub(crate) fn best_chunks<T>(
+    ranked: Vec<T>,
+    doc: impl Fn(&T) -> &TantivyDocument,
+    path_f: Field,
+) -> Vec<T> {
+    let mut seen = HashSet::new();
+    ranked
+        .into_iter()
+        .filter(|item| {
+            let path = doc(item).get_first(path_f).and_then(|v| v.as_str()).unwrap_or("");
+            seen.insert(path.to_string())
+        })
+        .collect()
+}
+
+/// The documents of every chunk of the file at `path`, in file order.
+fn file_chunks(
+    searcher: &tantivy::Searcher,
+    path_f: Field,
+    chunk_line_f: Field,
+    path: &str,
+) -> Result<Vec<TantivyDocument>, NsError> {
+    let query = TermQuery::new(Term::from_field_text(path_f, path), IndexRecordOption::Basic);
+    let mut chunks = Vec::new();
+    for address in searcher.search(&query, &DocSetCollector)? {
+        let doc: TantivyDocument = searcher.doc(address)?;
+        let line = doc.get_first(chunk_line_f).and_then(|v| v.as_u64()).unwrap_or(0);
+        chunks.push((line, doc));
+    }
+    chunks.sort_by_key(|(line, _)| *line);
+    Ok(chunks.into_iter().map(|(_, doc)| doc).collect())
+}
+
+/// Whether `result` defines a symbol of one of `kinds` whose name contains
+/// one of the lowercased `words`.
+fn defines_kind(result: &SearchResult, words: &[String], kinds: &[SymbolKind]) -> bool {
+    result.symbols_raw.iter().enumerate().any(|(i, name)| {
+        let kind = result.symbol_kinds.get(i).copied().unwrap_or(SymbolKind::Other);
+        let lower = name.to_lowercase();
+        kinds.contains(&kind) && words.iter().any(|w| lower.contains(w.as_str()))
+    })
+}
+
+/// Multiplies the score of documents matching each selector by its factor.
+///
+/// Selectors must not overlap. The query becomes one boosted branch per
+/// selector plus an unboosted branch excluding them all, so exactly one
+/// branch matches a document. Factors of 1 are dropped.
+fn scale_by(query: Box<dyn Query>, selectors: Vec<(Box<dyn Query>, f32)>) -> Box<dyn Query> {
+    let mut branches: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+    let mut unscaled: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, query.box_clone())];
+    for (selector, factor) in selectors.into_iter().filter(|(_, f)| *f != 1.0) {
+        let branch = BooleanQuery::new(vec![
+            (Occur::Must, query.box_clone()),
+            (Occur::Must, Box::new(ConstScoreQuery::new(selector.box_clone(), 0.0))),
+        ]);
+        branches.push((Occur::Should, Box::new(BoostQuery::new(Box::new(branch), factor))));
+        unscaled.push((Occur::MustNot, selector));
+    }
+    if branches.is_empty() {
+        return query;
+    }
+    branches.push((Occur::Should, Box::new(BooleanQuery::new(unscaled))));
+    Box::new(BooleanQuery::new(branches))
+}
+
+/// Multiplies the score of each document by the `lang_boost` factor of its
+/// language, or of its extension when it has no language. Extension keys
+/// leave out files with a language, so a `.rs` file is scaled by `rust`
+/// alone even when `rs` is a key too.
+pub(crate) fn scale_by_lang(
+    query: Box<dyn Query>,
+    lang_f: Field,
+    path_f: Field,
+    factors: &[(String, f32)],
+) -> Result<Box<dyn Query>, NsError> {
+    let mut selectors: Vec<(Box<dyn Query>, f32)> = Vec::new();
+    for (key, factor) in factors {
+        let key = key.to_lowercase();
+        let selector: Box<dyn Query> = if LANGUAGES.contains(&key.as_str()) {
+            Box::new(TermQuery::new(
+                Term::from_field_text(lang_f, &key),
+                IndexRecordOption::Basic,
+            ))
+        } else {
+            let pattern = format!(r".*\.{}", regex::escape(&key));
+            let mut clauses: Vec<(Occur, Box<dyn Query>)> =
+                vec![(Occur::Must, Box::new(RegexQuery::from_pattern(&pattern, path_f)?))];
+            for lang in LANGUAGES {
+                let term = Term::from_field_text(lang_f, lang);
+                clauses.push((Occur::MustNot, Box::new(TermQuery::new(term, IndexRecordOption::Basic))));
+            }
+            Box::new(BooleanQuery::new(clauses))
+        };
+        selectors.push((selector, *factor));
+    }
+    Ok(scale_by(query, selectors))
+}
+
+/// Multiplies the score of files flagged in `flag_f` (`is_test` or
+/// `generated` = 1) by `factor`.
+fn scale_flagged(query: Box<dyn Query>, flag_f: Field, factor: f32) -> Box<dyn Query> {
+    let selector = TermQuery::new(
+        Term::from_field_u64(flag_f, 1),
+        IndexRecordOption::Basic,
+    );
+    scale_by(query, vec![(Box::new(selector), factor)])
+}
+
+/// Multiplies the score of files under any of `dirs` by `factor`.
+fn scale_vendored(
+    query: Box<dyn Query>,
+    path_f: Field,
+    dirs: &[String],
+    factor: f32,
+) -> Result<Box<dyn Query>, NsError> {
+    if dirs.is_empty() {
+        return Ok(query);
+    }
+    let names: Vec<String> = dirs.iter().map(|d| regex::escape(d)).collect();
+    let pattern = format!("(.*/)?({})/.*", names.join("|"));
+    let selector = RegexQuery::from_pattern(&pattern, path_f)?;
+    Ok(scale_by(query, vec![(Box::new(selector), factor)]))
+}
+
+/// Whether any directory component of `path` is one of `dirs`.
+pub(crate) fn in_dirs(path: &str, dirs: &[String]) -> bool {
+    let mut components: Vec<&str> = path.split(['/', '\\']).collect();
+    components.pop();
+    components.iter().any(|c| dirs.iter().any(|d| d == c))
+}
+
+/// Score of one document under `weight`, or 0.0 if it doesn't match.
+fn field_score(
+    weight: Option<&dyn Weight>,
+    searcher: &tantivy::Searcher,
+    addr: DocAddress,
+) -> f32 {
+    weight
+        .and_then(|w| scorer_at(w, searcher, addr))
+        .map_or(0.0, |mut scorer| scorer.score())
+}
+
+/// A scorer for `weight` positioned on `addr`, or None if the document
+/// doesn't match.
+///
+/// `Query::explain` would do, but a phrase scorer starts on its first match
+/// and panics when asked to seek back to an earlier document, so the
+/// scorer's position is checked first.
+pub(crate) fn scorer_at(
+    weight: &dyn Weight,
+    searcher: &tantivy::Searcher,
+    addr: DocAddress,
+) -> Option<Box<dyn Scorer>> {
+    let mut scorer = weight
+        .scorer(searcher.segment_reader(addr.segment_ord), 1.0)
+        .ok()?;
+    let doc = scorer.doc();
+    if doc == TERMINATED || doc > addr.doc_id {
+        return None;
+    }
+    if doc < addr.doc_id && scorer.seek(addr.doc_id) != addr.doc_id {
+        return None;
+    }
+    Some(scorer)
+}
+
+/// Query boost of the `symbols` field over `content`.
+pub(crate) const SYMBOLS_BOOST: f32 = 3.0;
+
+/// Query boost of the `docs` field. Doc prose is in `content` as well, so a
+/// word in a doc comment scores through both; this extra share is kept
+/// below the symbols boost so a definition still outranks its mention.
+pub(crate) const DOCS_BOOST: f32 = 1.5;
+
+/// Query boost of the `filename` field. A file's name holds a couple of
+/// words at most, so one matching weighs about as much as a definition.
+pub(crate) const FILENAME_BOOST: f32 = 2.0;
+
+/// Indexes smaller than this are cheap to search whatever the query.
+const COST_GUARD_MIN_DOCS: u64 = 100;
+/// Share of indexed files a query term may match before the guard trips.
+const COST_GUARD_RATIO: f64 = 0.8;
+
+/// Rejects queries whose broadest word matches nearly every document.
+///
+/// Unquoted words are alternatives, so the number of matching files is at
+/// least the document frequency of the most common one: `ns fn` ranks the
+/// whole repo and output fills with low-value hits. With `all_terms` the
+/// rarest word bounds the match count instead. Estimated from the term
+/// dictionary only; quoted phrases make a query selective and skip the check.
+pub(crate) fn check_query_cost(
+    searcher: &tantivy::Searcher,
+    field: Field,
+    rest: &str,
+    all_terms: bool,
+) -> Result<(), NsError> {
+    let total = searcher.num_docs();
+    if total < COST_GUARD_MIN_DOCS {
+        return Ok(());
+    }
+    let mut broadest: Option<(String, u64)> = None;
+    for word in rest.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        if word.is_empty() {
+            continue;
+        }
+        let matches = searcher.doc_freq(&Term::from_field_text(field, &word.to_lowercase()))?;
+        let better = |m: u64| if all_terms { matches < m } else { matches > m };
+        if broadest.as_ref().is_none_or(|(_, m)| better(*m)) {
+            broadest = Some((word.to_string(), matches));
+        }
+    }
+    match broadest {
+        Some((term, matches)) if matches as f64 >= total as f64 * COST_GUARD_RATIO => {
+            Err(NsError::QueryTooBroad {
+                term,
+                matches,
+                total,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Definition lines stored in `field` (`symbols_def`), one per symbol.
+pub(crate) fn stored_definitions(doc: &TantivyDocument, field: Field) -> Vec<String> {
+    doc.get_first(field)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.split('\n').map(|line| line.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Line ranges stored in `field` (`symbol_spans`), one per symbol; empty
+/// if the field is missing or truncated.
+pub(crate) fn stored_spans(doc: &TantivyDocument, field: Field) -> Vec<(usize, usize)> {
+    doc.get_first(field)
+        .and_then(|v| v.as_bytes())
+        .and_then(spans_from_bytes)
+        .unwrap_or_default()
+}
+
+/// Kinds stored in `field` (`symbol_kinds`), one per symbol.
+pub(crate) fn stored_kinds(doc: &TantivyDocument, field: Field) -> Vec<SymbolKind> {
+    doc.get_first(field)
+        .and_then(|v| v.as_bytes())
+        .map(kinds_from_bytes)
+        .unwrap_or_default()
+}
+
+/// MinHash signature stored in `field` (`minhash`), if any.
+pub(crate) fn stored_minhash(doc: &TantivyDocument, field: Field) -> Option<Vec<u32>> {
+    doc.get_first(field)
+        .and_then(|v| v.as_bytes())
+        .and_then(minhash::from_bytes)
+}
+
+/// Opens the index to search and a reader of it: the bundle in
+/// `opts.index_file` if set, otherwise `.ns/` under `root` (kept open
+/// between searches while a `session::keep_index_open` guard lives).
+pub(crate) fn open_search_index(
+    root: &Path,
+    opts: &SearchOptions,
+) -> Result<(Index, IndexMeta, IndexReader), NsError> {
+    match opts.index_file {
+        Some(ref bundle) => {
+            let (index, meta) = open_bundle(bundle)?;
+            let reader = create_reader_with_retry(&index, root)?;
+            Ok((index, meta, reader))
+        }
+        None => open_with_reader(root),
+    }
+}
+
+/// Removes results whose file no longer exists under `root` and returns how
+/// many were removed. The index only learns about deletions on the next
+/// `ns index`, so without this a deleted file can still rank.
+pub(crate) fn drop_missing(root: &Path, results: &mut Vec<SearchResult>) -> usize {
+    let before = results.len();
+    results.retain(|r| root.join(&r.path).is_file());
+    before - results.len()
+}
+
 /// Creates an IndexReader with retry logic for transient lock failures.
 ///
 /// Tantivy's reader creation acquires `META_LOCK` to prevent GC from deleting
@@ -250,7 +1227,7 @@ pub fn execute_search(
 ///
 /// Retry strategy: up to 3 attempts with 100ms delay. On final attempt,
 /// clean stale lock files and retry once more.
-fn create_reader_with_retry(
+pub(crate) fn create_reader_with_retry(
     index: &tantivy::Index,
     root: &Path,
 ) -> Result<tantivy::IndexReader, NsError> {
@@ -314,6 +1291,260 @@ fn clean_stale_locks(root: &Path) {
     }
 }
 
+/// A quoted phrase from the query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Phrase {
+    /// Lowercased identifier parts of the phrase's words.
+    tokens: Vec<String>,
+    /// Extra positions allowed between the tokens (`"a b"~N`); 0 requires
+    /// them adjacent and in order.
+    slop: u32,
+}
+
+/// Splits `"quoted phrases"` out of a query string.
+///
+/// Returns each phrase as the lowercased identifier parts of its words (which
+/// the `content` tokenizer indexes at consecutive positions; CJK bigrams
+/// when the index uses `code_cjk`) and the unquoted remainder. A closing quote followed by `~N` makes it a proximity phrase:
+/// `"init server"~5` matches when the words are within five positions of
+/// each other. An unmatched quote is dropped and the text after it treated as
+/// unquoted.
+fn split_phrases(query: &str, cjk: bool) -> (Vec<Phrase>, String) {
+    let mut phrases = Vec::new();
+    let mut rest = String::new();
+    let mut remaining = query;
+
+    while let Some(open) = remaining.find('"') {
+        rest.push_str(&remaining[..open]);
+        let after = &remaining[open + 1..];
+        let Some(close) = after.find('"') else {
+            remaining = after;
+            break;
+        };
+        let tokens = part_tokens(&after[..close], cjk);
+        remaining = &after[close + 1..];
+        let mut slop = 0;
+        if let Some(tail) = remaining.strip_prefix('~') {
+            let digits = tail.len() - tail.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            slop = tail[..digits].parse().unwrap_or(0);
+            remaining = &tail[digits..];
+        }
+        if !tokens.is_empty() {
+            phrases.push(Phrase { tokens, slop });
+        }
+        rest.push(' ');
+    }
+    rest.push_str(remaining);
+
+    (phrases, rest)
+}
+
+/// Splits `-term` / `!term` exclusions out of a query string.
+///
+/// Each excluded word is tokenized like a phrase, so `-event_store` excludes
+/// the adjacent words "event store". A marker must be followed by a letter,
+/// digit or `_`: `--flag` and `a-b` stay ordinary terms, and a lone `-` is
+/// dropped.
+fn split_negations(query: &str, cjk: bool) -> (Vec<Vec<String>>, String) {
+    let mut excluded = Vec::new();
+    let mut rest = Vec::new();
+
+    for word in query.split_whitespace() {
+        let negated = word
+            .strip_prefix('-')
+            .or_else(|| word.strip_prefix('!'))
+            .filter(|t| t.starts_with(|c: char| c.is_alphanumeric() || c == '_'));
+        match negated {
+            Some(term) => {
+                let tokens = part_tokens(term, cjk);
+                if !tokens.is_empty() {
+                    excluded.push(tokens);
+                }
+            }
+            None if word == "-" || word == "!" => {}
+            None => rest.push(word),
+        }
+    }
+
+    (excluded, rest.join(" "))
+}
+
+/// Matches documents whose path is one of `prefixes` or lies below one of
+/// them. Each prefix is an exact term plus a `[prefix/, prefix0)` range over
+/// the untokenized `path` field (`0` is the byte after `/`), so the filter is
+/// answered from the term dictionary and never matches `src/apiv2` for
+/// `src/api`. Scores are unaffected. `None` when nothing is restricted.
+pub(crate) fn path_prefix_query(
+    root: &Path,
+    field: Field,
+    prefixes: &[String],
+) -> Option<Box<dyn Query>> {
+    let prefixes: Vec<String> = prefixes.iter().map(|p| normalize_prefix(root, p)).collect();
+    // `.` or the root itself covers every file.
+    if prefixes.is_empty() || prefixes.iter().any(|p| p.is_empty()) {
+        return None;
+    }
+
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+    for prefix in &prefixes {
+        clauses.push((
+            Occur::Should,
+            Box::new(TermQuery::new(
+                Term::from_field_text(field, prefix),
+                IndexRecordOption::Basic,
+            )),
+        ));
+        clauses.push((
+            Occur::Should,
+            Box::new(RangeQuery::new(
+                Bound::Included(Term::from_field_text(field, &format!("{}/", prefix))),
+                Bound::Excluded(Term::from_field_text(field, &format!("{}0", prefix))),
+            )),
+        ));
+    }
+    Some(Box::new(ConstScoreQuery::new(
+        Box::new(BooleanQuery::new(clauses)),
+        0.0,
+    )))
+}
+
+/// Range queries for `--larger-than`, `--smaller-than` and
+/// `--modified-since` over the `size` and `mtime` fields. Both bounds of
+/// the size filters are exclusive.
+fn metadata_filters(schema: &tantivy::schema::Schema, opts: &SearchOptions) -> Vec<Box<dyn Query>> {
+    let size_f = size_field(schema);
+    let mut filters: Vec<Box<dyn Query>> = Vec::new();
+    if opts.larger_than.is_some() || opts.smaller_than.is_some() {
+        let lower = opts
+            .larger_than
+            .map_or(Bound::Unbounded, |n| Bound::Excluded(Term::from_field_u64(size_f, n)));
+        let upper = opts
+            .smaller_than
+            .map_or(Bound::Unbounded, |n| Bound::Excluded(Term::from_field_u64(size_f, n)));
+        filters.push(Box::new(RangeQuery::new(lower, upper)));
+    }
+    if let Some(since) = opts.modified_since {
+        filters.push(Box::new(RangeQuery::new(
+            Bound::Included(Term::from_field_u64(mtime_field(schema), since)),
+            Bound::Unbounded,
+        )));
+    }
+    filters
+        .into_iter()
+        .map(|q| Box::new(ConstScoreQuery::new(q, 0.0)) as Box<dyn Query>)
+        .collect()
+}
+
+/// Whether `path` is one of `prefixes` or lies below one of them, like
+/// `path_prefix_query` for paths that are not in an index. An empty list
+/// restricts nothing.
+pub(crate) fn under_prefixes(root: &Path, path: &str, prefixes: &[String]) -> bool {
+    prefixes.is_empty()
+        || prefixes.iter().any(|p| {
+            let prefix = normalize_prefix(root, p);
+            prefix.is_empty()
+                || path == prefix
+                || path.strip_prefix(&prefix).is_some_and(|rest| rest.starts_with('/'))
+        })
+}
+
+/// Turns a path argument into the form stored in `path`: `./src/api/` →
+/// `src/api`. Absolute paths inside `root` are made relative.
+pub(crate) fn normalize_prefix(root: &Path, prefix: &str) -> String {
+    let path = Path::new(prefix);
+    let rel = path.strip_prefix(root).unwrap_or(path);
+    rel.components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The searched words of `query` as text for a field tokenizer (the
+/// unquoted words and those of quoted phrases) and its `-term` exclusions,
+/// split as `execute_ranked` splits them.
+pub(crate) fn split_query_terms(query: &str, cjk: bool) -> (String, Vec<Vec<String>>) {
+    let (phrases, rest) = split_phrases(query, cjk);
+    let (excluded, mut words) = split_negations(&rest, cjk);
+    for phrase in &phrases {
+        words.push(' ');
+        words.push_str(&phrase.tokens.join(" "));
+    }
+    (words, excluded)
+}
+
+/// Whether `field` of the document at `addr` contains `tokens` as adjacent
+/// words, as an exclusion matches.
+pub(crate) fn contains_tokens(
+    searcher: &tantivy::Searcher,
+    field: Field,
+    tokens: &[String],
+    addr: DocAddress,
+) -> bool {
+    phrase_query(field, tokens, 0)
+        .weight(EnableScoring::disabled_from_searcher(searcher))
+        .ok()
+        .is_some_and(|w| scorer_at(w.as_ref(), searcher, addr).is_some())
+}
+
+/// Drops documents whose `field` contains any of the `excluded` terms.
+/// Symbol names always appear in `content`, so excluding on `content` also
+/// covers `--sym` searches.
+fn without_terms(query: Box<dyn Query>, excluded: &[Vec<String>], field: Field) -> Box<dyn Query> {
+    if excluded.is_empty() {
+        return query;
+    }
+
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, query)];
+    for tokens in excluded {
+        clauses.push((Occur::MustNot, phrase_query(field, tokens, 0)));
+    }
+    Box::new(BooleanQuery::new(clauses))
+}
+
+/// Requires every phrase (as a `PhraseQuery` on `field`) and lets `rest`
+/// contribute to the score. Without phrases, returns `rest` unchanged.
+fn with_phrases(
+    rest: Option<Box<dyn Query>>,
+    phrases: &[Phrase],
+    field: Field,
+) -> Box<dyn Query> {
+    if phrases.is_empty() {
+        return rest.unwrap_or_else(|| Box::new(BooleanQuery::new(vec![])));
+    }
+
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = phrases
+        .iter()
+        .map(|phrase| (Occur::Must, phrase_query(field, &phrase.tokens, phrase.slop)))
+        .collect();
+    if let Some(rest) = rest {
+        clauses.push((Occur::Should, rest));
+    }
+    Box::new(BooleanQuery::new(clauses))
+}
+
+/// `PhraseQuery` needs at least two terms; a one-word phrase is a plain term.
+/// A sloppy phrase scores by how often its terms co-occur within `slop`, so
+/// files where they are close together rank above scattered mentions.
+fn phrase_query(field: Field, tokens: &[String], slop: u32) -> Box<dyn Query> {
+    let terms: Vec<Term> = tokens
+        .iter()
+        .map(|t| Term::from_field_text(field, t))
+        .collect();
+    if terms.len() == 1 {
+        Box::new(TermQuery::new(
+            terms[0].clone(),
+            IndexRecordOption::WithFreqsAndPositions,
+        ))
+    } else {
+        let mut phrase = PhraseQuery::new(terms);
+        phrase.set_slop(slop);
+        Box::new(phrase)
+    }
+}
+
 /// Tokenizes a query string: splits on whitespace and lowercases each token.
 fn tokenize_query(query: &str) -> Vec<String> {
     query
@@ -346,17 +1577,20 @@ fn build_fuzzy_single_field_query(
 
 /// Builds a fuzzy query by tokenizing the input, creating a `FuzzyTermQuery`
 /// per token (Levenshtein distance 1, transposition cost 1), and combining
-/// them with `Should` occurrence so any term match contributes.
+/// them with `Should` occurrence so any term match contributes, or `Must`
+/// with `all_terms`.
 ///
-/// If `sym_only` is false, each term generates two clauses: one for `content`
-/// and one for `symbols` (with 3x boost on symbols).
+/// If `sym_only` is false, each term matches either of two clauses: one for
+/// `content` and one for `symbols` (with 3x boost on symbols).
 fn build_fuzzy_query(
     query_str: &str,
     content_field: tantivy::schema::Field,
     symbols_field: tantivy::schema::Field,
     sym_only: bool,
+    all_terms: bool,
 ) -> Box<dyn Query> {
     let tokens = tokenize_query(query_str);
+    let occur = if all_terms { Occur::Must } else { Occur::Should };
 
     let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
 
@@ -368,7 +1602,7 @@ fn build_fuzzy_query(
                 1,
                 true,
             );
-            clauses.push((Occur::Should, Box::new(ft)));
+            clauses.push((occur, Box::new(ft)));
         } else {
             // Content field (no boost)
             let ft_content = FuzzyTermQuery::new(
@@ -376,7 +1610,6 @@ fn build_fuzzy_query(
                 1,
                 true,
             );
-            clauses.push((Occur::Should, Box::new(ft_content)));
 
             // Symbols field with 3x boost
             let ft_symbols = FuzzyTermQuery::new(
@@ -385,7 +1618,11 @@ fn build_fuzzy_query(
                 true,
             );
             let boosted: Box<dyn Query> = Box::new(BoostQuery::new(Box::new(ft_symbols), 3.0));
-            clauses.push((Occur::Should, boosted));
+            let either = BooleanQuery::new(vec![
+                (Occur::Should, Box::new(ft_content) as Box<dyn Query>),
+                (Occur::Should, boosted),
+            ]);
+            clauses.push((occur, Box::new(either)));
         }
     }
 
@@ -396,3 +1633,126 @@ fn build_fuzzy_query(
         Box::new(BooleanQuery::new(clauses))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_phrases_extracts_quoted_runs() {
+        let (phrases, rest) = split_phrases(r#"append "event store" factory"#, false);
+        assert_eq!(
+            phrases,
+            vec![Phrase {
+                tokens: vec!["event".to_string(), "store".to_string()],
+                slop: 0,
+            }]
+        );
+        assert_eq!(rest.split_whitespace().collect::<Vec<_>>(), vec!["append", "factory"]);
+    }
+
+    #[test]
+    fn split_phrases_tokenizes_like_content() {
+        let (phrases, _) = split_phrases(r#""Event_Store::new()""#, false);
+        assert_eq!(phrases[0].tokens, vec!["event", "store", "new"]);
+    }
+
+    #[test]
+    fn split_phrases_reads_proximity_slop() {
+        let (phrases, rest) = split_phrases(r#""init server"~5 port "a b"~ x"#, false);
+        assert_eq!(phrases[0].tokens, vec!["init", "server"]);
+        assert_eq!(phrases[0].slop, 5);
+        assert_eq!(phrases[1].slop, 0);
+        assert_eq!(rest.split_whitespace().collect::<Vec<_>>(), vec!["port", "x"]);
+    }
+
+    #[test]
+    fn split_negations_extracts_excluded_terms() {
+        let (excluded, rest) = split_negations("EventStore -test !Mock_Store --verbose a-b -", false);
+        assert_eq!(excluded, vec![vec!["test"], vec!["mock", "store"]]);
+        assert_eq!(rest, "EventStore --verbose a-b");
+    }
+
+    #[test]
+    fn quick_preset_only_tightens_limits() {
+        let quick = SearchOptions::default().quick();
+        assert_eq!(quick.max_results, QUICK_MAX_RESULTS);
+        assert_eq!(quick.max_context_lines, Some(QUICK_CONTEXT_LINES));
+        assert_eq!(quick.budget, Some(QUICK_BUDGET));
+        assert!(quick.signatures_only && quick.json_compact && !quick.dedupe);
+        assert_eq!(quick.rerank_pool, QUICK_RERANK_POOL);
+
+        let tight = SearchOptions {
+            max_results: 2,
+            max_context_lines: Some(1),
+            budget: Some(100),
+            ..Default::default()
+        }
+        .quick();
+        assert_eq!(tight.max_results, 2);
+        assert_eq!(tight.max_context_lines, Some(1));
+        assert_eq!(tight.budget, Some(100));
+
+        let unlimited = SearchOptions {
+            max_context_lines: Some(0),
+            ..Default::default()
+        }
+        .quick();
+        assert_eq!(unlimited.max_context_lines, Some(QUICK_CONTEXT_LINES));
+    }
+
+    #[test]
+    fn deep_preset_only_widens_limits() {
+        let deep = SearchOptions::default().deep();
+        assert_eq!(deep.max_results, DEEP_MAX_RESULTS);
+        assert_eq!(deep.max_context_lines, Some(DEEP_CONTEXT_LINES));
+        assert!(deep.spans && deep.breadth_first);
+
+        let wide = SearchOptions {
+            max_results: 50,
+            max_context_lines: Some(0),
+            ..Default::default()
+        }
+        .deep();
+        assert_eq!(wide.max_results, 50);
+        assert_eq!(wide.max_context_lines, Some(0));
+    }
+
+    #[test]
+    fn identifier_queries_are_camel_or_snake_case_words() {
+        assert!(is_identifier_query("EventStore"));
+        assert!(is_identifier_query("parseHTTP"));
+        assert!(is_identifier_query(" event_store "));
+        assert!(!is_identifier_query("handler"));
+        assert!(!is_identifier_query("Router"));
+        assert!(!is_identifier_query("_private"));
+        assert!(!is_identifier_query("event store"));
+        assert!(!is_identifier_query("\"EventStore\""));
+    }
+
+    #[test]
+    fn in_dirs_matches_directory_components_only() {
+        let dirs = default_vendor_dirs();
+        assert!(in_dirs("vendor/github.com/x/y.go", &dirs));
+        assert!(in_dirs("web/node_modules/react/index.js", &dirs));
+        assert!(in_dirs("target/debug/build.rs", &dirs));
+        assert!(!in_dirs("src/vendor.rs", &dirs));
+        assert!(!in_dirs("src/build", &dirs));
+    }
+
+    #[test]
+    fn normalize_prefix_matches_stored_paths() {
+        let root = Path::new("/repo");
+        assert_eq!(normalize_prefix(root, "./src/api/"), "src/api");
+        assert_eq!(normalize_prefix(root, "/repo/tests"), "tests");
+        assert_eq!(normalize_prefix(root, "src/lib.rs"), "src/lib.rs");
+        assert_eq!(normalize_prefix(root, "."), "");
+    }
+
+    #[test]
+    fn split_phrases_ignores_unmatched_and_empty_quotes() {
+        let (phrases, rest) = split_phrases(r#"foo "" "bar"#, false);
+        assert!(phrases.is_empty());
+        assert_eq!(rest.split_whitespace().collect::<Vec<_>>(), vec!["foo", "bar"]);
+    }
+}
@@ -0,0 +1,145 @@
+//! Reference lookup (`ns refs`).
+//!
+//! Lists the lines that mention a symbol as a whole word, found the way
+//! `ns audit --regex --lines` finds them: the index narrows the files, then
+//! each is read. The symbol's definitions (`ns def`) say which files define
+//! it, and their definition lines are left out; the `imports` field says
+//! which files import it or the module defining it, so callers that pull it
+//! in by name can be told from files that merely mention the word.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use serde::Serialize;
+use tantivy::collector::DocSetCollector;
+use tantivy::query::{BooleanQuery, Occur, Query, TermQuery};
+use tantivy::schema::{IndexRecordOption, Value};
+use tantivy::{TantivyDocument, Term};
+
+use crate::error::NsError;
+use crate::indexer::language::filename_stem;
+use crate::indexer::writer::open_index;
+use crate::schema::{imports_field, path_field};
+
+use super::audit::{audit, AuditLine, AuditOptions};
+use super::query::create_reader_with_retry;
+use super::symbol_search::{find_definitions, FoundSymbol, SymbolSearchOptions};
+
+/// Options for `find_refs`.
+#[derive(Debug, Clone, Default)]
+pub struct RefsOptions {
+    /// Language filter (`-t`).
+    pub file_type: Option<String>,
+    /// Path glob filter (`-g`).
+    pub file_glob: Option<String>,
+    /// Leave out the files that define the symbol.
+    pub exclude_definitions: bool,
+}
+
+/// The references to a symbol in one file.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileRefs {
+    pub path: String,
+    /// The file defines the symbol.
+    pub defines: bool,
+    /// The file imports the symbol or the module defining it.
+    pub imports: bool,
+    /// Lines mentioning the symbol, its definition lines left out.
+    pub lines: Vec<AuditLine>,
+}
+
+/// The symbol's definitions and every file referencing it: importing files
+/// first, then other files, then the defining files, each by path.
+pub fn find_refs(
+    root: &Path,
+    name: &str,
+    opts: &RefsOptions,
+) -> Result<(Vec<FoundSymbol>, Vec<FileRefs>), NsError> {
+    // `Type::method` is referenced as `method`.
+    let word = name.rsplit([':', '.', '#']).next().unwrap_or(name);
+    // Definitions ignore case, references don't: `router` is not `Router`.
+    let mut definitions = find_definitions(root, name, &SymbolSearchOptions::default())?;
+    definitions.retain(|d| d.name == word);
+    let importers = importing_files(root, word, &definitions)?;
+
+    let audit_opts = AuditOptions {
+        regex: true,
+        lines: true,
+        file_type: opts.file_type.clone(),
+        file_glob: opts.file_glob.clone(),
+    };
+    let pattern = format!(r"\b{}\b", regex::escape(word));
+    let mut files = Vec::new();
+    audit(root, &pattern, &audit_opts, &mut |found| {
+        let defined_here: Vec<usize> = definitions
+            .iter()
+            .filter(|d| d.path == found.path)
+            .filter_map(|d| d.line)
+            .collect();
+        let defines = definitions.iter().any(|d| d.path == found.path);
+        if defines && opts.exclude_definitions {
+            return Ok(());
+        }
+        let lines: Vec<AuditLine> = found
+            .lines
+            .iter()
+            .filter(|l| !defined_here.contains(&l.line))
+            .cloned()
+            .collect();
+        if !lines.is_empty() {
+            files.push(FileRefs {
+                path: found.path.clone(),
+                defines,
+                imports: importers.contains(&found.path),
+                lines,
+            });
+        }
+        Ok(())
+    })?;
+    files.sort_by(|a, b| {
+        (!a.imports, a.defines, &a.path).cmp(&(!b.imports, b.defines, &b.path))
+    });
+    Ok((definitions, files))
+}
+
+/// Paths whose `imports` name `word` or the module (file stem) of one of
+/// its `definitions`.
+fn importing_files(
+    root: &Path,
+    word: &str,
+    definitions: &[FoundSymbol],
+) -> Result<HashSet<String>, NsError> {
+    let (index, _meta) = open_index(root)?;
+    let schema = index.schema();
+    let imports_f = imports_field(&schema);
+    let path_f = path_field(&schema);
+
+    let mut terms: Vec<String> = vec![word.to_lowercase()];
+    for def in definitions {
+        let module = filename_stem(&def.path).to_lowercase();
+        if !module.is_empty() && !terms.contains(&module) {
+            terms.push(module);
+        }
+    }
+    let clauses: Vec<(Occur, Box<dyn Query>)> = terms
+        .iter()
+        .map(|t| {
+            let query: Box<dyn Query> = Box::new(TermQuery::new(
+                Term::from_field_text(imports_f, t),
+                IndexRecordOption::Basic,
+            ));
+            (Occur::Should, query)
+        })
+        .collect();
+
+    let reader = create_reader_with_retry(&index, root)?;
+    let searcher = reader.searcher();
+    let mut paths = HashSet::new();
+    for address in searcher.search(&BooleanQuery::new(clauses), &DocSetCollector)? {
+        let doc: TantivyDocument = searcher.doc(address)?;
+        if let Some(path) = doc.get_first(path_f).and_then(|v| v.as_str()) {
+            paths.insert(path.to_string());
+        }
+    }
+    Ok(paths)
+}
@@ -0,0 +1,505 @@
+//! `--regex` mode: line-level regular expression search over indexed files.
+//!
+//! Content is not stored in the index, so matching happens against the files
+//! on disk. To avoid reading every file, literal fragments that any match
+//! must contain are turned into an index pre-filter: `fn \w+_handler`
+//! requires a `fn` term and a term containing `handler`. Patterns with no
+//! usable literal (e.g. `\d{4}-\d{2}`) fall back to scanning all indexed files.
+//!
+//! `--substring` mode reuses the same line matcher with a case-insensitive
+//! literal. When the index was built with `--ngram`, its pre-filter requires
+//! every trigram of the needle in `content_ngram`, so `entSto` finds
+//! `EventStore` without scanning the repo.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+
+use regex::{Regex, RegexBuilder};
+use regex_syntax::hir::{Hir, HirKind};
+use tantivy::collector::DocSetCollector;
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, RegexQuery, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Schema, Value};
+use tantivy::{TantivyDocument, Term};
+
+use crate::error::NsError;
+use crate::indexer::tokenizer::{is_cjk, trigrams};
+use crate::indexer::writer::IndexMeta;
+use crate::schema::{
+    content_field, content_hash_field, content_ngram_field, generated_field, is_test_field,
+    lang_field,
+    minhash_field, path_field, symbol_kinds_field, symbol_spans_field, symbols_def_field,
+    symbols_raw_field, truncated_field,
+};
+
+use super::dedupe::{collapse_near_duplicates, dedupe_by_content};
+use super::rerank::RerankSignals;
+use super::sample::{cap_per_dir, stratified, stratum};
+use super::query::{
+    in_dirs, open_search_index, path_prefix_query, stored_definitions, stored_kinds,
+    stored_minhash, stored_spans, SearchOptions, SearchResult, SearchStats,
+};
+
+/// Tokens longer than this are dropped by tantivy's default tokenizer
+/// (`RemoveLongFilter`), so they can't be used as pre-filter terms.
+const MAX_TOKEN_LEN: usize = 40;
+
+/// How a literal token must appear in an indexed term.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TermMatch {
+    /// The token is a whole term.
+    Exact(String),
+    /// The token is the end of a term (unbounded on the left).
+    Suffix(String),
+    /// The token is the start of a term (unbounded on the right).
+    Prefix(String),
+    /// The token appears somewhere inside a term.
+    Contains(String),
+}
+
+/// Runs a regex search. Files are ranked by number of matching lines.
+///
+/// Honours `file_type`, `paths`, `file_glob`, `dedupe`, `max_results` and `sample`
+/// like `execute_search`; `sym_only` and `fuzzy` do not apply. Files must be
+/// read to match them, so deleted files are always skipped (and counted in
+/// `stale_results`) regardless of `verify`.
+pub fn execute_regex_search(
+    root: &Path,
+    pattern: &str,
+    opts: &SearchOptions,
+) -> Result<(Vec<SearchResult>, SearchStats), NsError> {
+    let re = Regex::new(pattern)?;
+    search_lines(root, &re, opts, |schema, meta| {
+        let content = content_field(schema);
+        required_tokens(pattern, meta.cjk)
+            .iter()
+            .map(|token| term_query(content, token))
+            .collect()
+    })
+}
+
+/// Runs a `--substring` search: lines containing `needle`, ignoring case.
+///
+/// Same ranking and options as `execute_regex_search`. Uses the trigram
+/// pre-filter when the index has one and `needle` is at least three
+/// characters; otherwise falls back to the regex literal pre-filter.
+pub fn execute_substring_search(
+    root: &Path,
+    needle: &str,
+    opts: &SearchOptions,
+) -> Result<(Vec<SearchResult>, SearchStats), NsError> {
+    let re = substring_regex(needle)?;
+    search_lines(root, &re, opts, |schema, meta| {
+        let grams = trigrams(needle);
+        if meta.ngram && !grams.is_empty() {
+            let ngram = content_ngram_field(schema);
+            return Ok(grams
+                .iter()
+                .map(|gram| -> Box<dyn Query> {
+                    Box::new(TermQuery::new(
+                        Term::from_field_text(ngram, gram),
+                        IndexRecordOption::Basic,
+                    ))
+                })
+                .collect());
+        }
+        let content = content_field(schema);
+        required_tokens(&regex::escape(needle), meta.cjk)
+            .iter()
+            .map(|token| term_query(content, token))
+            .collect()
+    })
+}
+
+/// Case-insensitive regex matching `needle` literally.
+pub fn substring_regex(needle: &str) -> Result<Regex, NsError> {
+    Ok(RegexBuilder::new(&regex::escape(needle))
+        .case_insensitive(true)
+        .build()?)
+}
+
+/// Ranks indexed files by the number of lines matching `re`. `prefilter`
+/// returns queries every candidate must match.
+fn search_lines(
+    root: &Path,
+    re: &Regex,
+    opts: &SearchOptions,
+    prefilter: impl FnOnce(&Schema, &IndexMeta) -> Result<Vec<Box<dyn Query>>, NsError>,
+) -> Result<(Vec<SearchResult>, SearchStats), NsError> {
+    let (index, meta, reader) = open_search_index(root, opts)?;
+
+    let schema = index.schema();
+    let path_f = path_field(&schema);
+    let lang_f = lang_field(&schema);
+    let symbols_raw_f = symbols_raw_field(&schema);
+    let symbols_def_f = symbols_def_field(&schema);
+    let symbol_spans_f = symbol_spans_field(&schema);
+    let symbol_kinds_f = symbol_kinds_field(&schema);
+    let content_hash_f = content_hash_field(&schema);
+    let is_test_f = is_test_field(&schema);
+    let truncated_f = truncated_field(&schema);
+    let generated_f = generated_field(&schema);
+    let minhash_f = minhash_field(&schema);
+
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = prefilter(&schema, &meta)?
+        .into_iter()
+        .map(|query| (Occur::Must, query))
+        .collect();
+    if let Some(ref lang_filter) = opts.file_type {
+        clauses.push((
+            Occur::Must,
+            Box::new(TermQuery::new(
+                Term::from_field_text(lang_f, lang_filter),
+                IndexRecordOption::Basic,
+            )),
+        ));
+    }
+    if let Some(path_query) = path_prefix_query(root, path_f, &opts.paths) {
+        clauses.push((Occur::Must, path_query));
+    }
+    let query: Box<dyn Query> = if clauses.is_empty() {
+        Box::new(AllQuery)
+    } else {
+        Box::new(BooleanQuery::new(clauses))
+    };
+
+    let glob = match opts.file_glob {
+        Some(ref g) => Some(glob::Pattern::new(g)?),
+        None => None,
+    };
+
+    let searcher = reader.searcher();
+
+    let start = Instant::now();
+    let candidates = searcher.search(&query, &DocSetCollector)?;
+
+    // The symbols of a document: of the whole file, or of one chunk of it.
+    let symbols_of = |doc: &TantivyDocument| {
+        let names: Vec<String> = doc
+            .get_first(symbols_raw_f)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.split('|').map(|s| s.to_string()).collect())
+            .unwrap_or_default();
+        (
+            names,
+            stored_definitions(doc, symbols_def_f),
+            stored_spans(doc, symbol_spans_f),
+            stored_kinds(doc, symbol_kinds_f),
+        )
+    };
+
+    let mut results: Vec<SearchResult> = Vec::new();
+    let mut signatures = HashMap::new();
+    let mut stale_results = 0;
+    // Files already read, with their result if they matched. A file chunked
+    // by `ns index --chunk-lines` is read once; its other chunks only add
+    // their symbols.
+    let mut read: HashMap<String, Option<usize>> = HashMap::new();
+    for doc_address in candidates {
+        let doc: TantivyDocument = searcher.doc(doc_address)?;
+        let path = doc
+            .get_first(path_f)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        if let Some(ref g) = glob {
+            if !g.matches(&path) {
+                continue;
+            }
+        }
+        if let Some(seen) = read.get(&path) {
+            if let Some(&i) = seen.as_ref() {
+                let (names, defs, spans, kinds) = symbols_of(&doc);
+                let result = &mut results[i];
+                result.symbols_raw.extend(names);
+                result.symbol_defs.extend(defs);
+                result.symbol_spans.extend(spans);
+                result.symbol_kinds.extend(kinds);
+                if let Some(signature) = stored_minhash(&doc, minhash_f) {
+                    signatures.insert(path, signature);
+                }
+            }
+            continue;
+        }
+        read.insert(path.clone(), None);
+
+        let text = match std::fs::read_to_string(root.join(&path)) {
+            Ok(text) => text,
+            Err(e) => {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    stale_results += 1;
+                }
+                continue;
+            }
+        };
+        let matching_lines = text.lines().filter(|line| re.is_match(line)).count();
+        if matching_lines == 0 {
+            continue;
+        }
+
+        let lang = doc
+            .get_first(lang_f)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .filter(|s| !s.is_empty());
+        let (symbols_raw, symbol_defs, symbol_spans, symbol_kinds) = symbols_of(&doc);
+        if let Some(signature) = stored_minhash(&doc, minhash_f) {
+            signatures.insert(path.clone(), signature);
+        }
+        let is_test = doc.get_first(is_test_f).and_then(|v| v.as_u64()) == Some(1);
+        let vendored = in_dirs(&path, &opts.vendor_dirs);
+        let generated = doc.get_first(generated_f).and_then(|v| v.as_u64()) == Some(1);
+        let mut weight = if is_test { opts.test_weight } else { 1.0 };
+        if generated {
+            weight *= opts.generated_weight;
+        }
+        if vendored {
+            weight *= opts.vendor_weight;
+        }
+
+        read.insert(path.clone(), Some(results.len()));
+        results.push(SearchResult {
+            path,
+            score: matching_lines as f32 * weight,
+            lang,
+            symbols_raw,
+            symbol_defs,
+            symbol_spans,
+            symbol_kinds,
+            symbol: None,
+            score_content: matching_lines as f32,
+            score_symbols: 0.0,
+            matched_fields: vec!["content".to_string()],
+            rerank: RerankSignals::default(),
+            terms: Vec::new(),
+            is_test,
+            vendored,
+            truncated: doc.get_first(truncated_f).and_then(|v| v.as_u64()) == Some(1),
+            generated,
+            alternates: Vec::new(),
+            duplicates: Vec::new(),
+            content_hash: doc.get_first(content_hash_f).and_then(|v| v.as_u64()),
+            match_lines: Vec::new(),
+        });
+    }
+    let elapsed_ms = start.elapsed().as_millis() as u64;
+    let results = rank_line_results(root, results, &signatures, opts);
+
+    let stats = SearchStats {
+        total_results: results.len(),
+        files_searched: meta.file_count,
+        elapsed_ms,
+        stale_results,
+        indexed_commit: meta.git_commit,
+        route: None,
+        no_symbols: meta.no_symbols,
+    };
+    Ok((results, stats))
+}
+
+/// Sorts line-matched `results` by score (ties by path), then samples,
+/// dedupes (near-duplicates by `signatures`) and truncates them as `opts`
+/// asks.
+pub(crate) fn rank_line_results(
+    root: &Path,
+    mut results: Vec<SearchResult>,
+    signatures: &HashMap<String, Vec<u32>>,
+    opts: &SearchOptions,
+) -> Vec<SearchResult> {
+    results.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    let limit = opts.sample.unwrap_or(opts.max_results).min(opts.result_ceiling());
+    if opts.sample.is_some() {
+        let keyed = results
+            .into_iter()
+            .map(|r| {
+                let key = stratum(&r.path, r.lang.as_deref());
+                (r, key)
+            })
+            .collect();
+        results = stratified(keyed, limit);
+    }
+    if opts.dedupe {
+        results = dedupe_by_content(root, results);
+        results = collapse_near_duplicates(results, signatures);
+    }
+    if let Some(max) = opts.max_per_dir {
+        results = cap_per_dir(results, max, |r| r.path.as_str());
+    }
+    results.truncate(limit);
+    results
+}
+
+pub(crate) fn term_query(field: Field, token: &TermMatch) -> Result<Box<dyn Query>, NsError> {
+    let pattern = match token {
+        TermMatch::Exact(t) => {
+            return Ok(Box::new(TermQuery::new(
+                Term::from_field_text(field, t),
+                IndexRecordOption::Basic,
+            )))
+        }
+        TermMatch::Suffix(t) => format!(".*{}", regex::escape(t)),
+        TermMatch::Prefix(t) => format!("{}.*", regex::escape(t)),
+        TermMatch::Contains(t) => format!(".*{}.*", regex::escape(t)),
+    };
+    Ok(Box::new(RegexQuery::from_pattern(&pattern, field)?))
+}
+
+/// Returns index tokens that every match of `pattern` must contain.
+///
+/// Conservative: returns an empty list (scan everything) for patterns that
+/// fail to parse here or that contain case-insensitive or alternated parts
+/// only. Tokens are lowercased to match the default tokenizer; with `cjk`
+/// (an index built with `--cjk`), CJK runs become their bigrams.
+pub fn required_tokens(pattern: &str, cjk: bool) -> Vec<TermMatch> {
+    let Ok(hir) = regex_syntax::parse(pattern) else {
+        return Vec::new();
+    };
+    let mut literals = Vec::new();
+    required_literals(&hir, &mut literals);
+
+    let mut tokens = Vec::new();
+    for literal in literals {
+        tokens.extend(literal_tokens(&literal, cjk));
+    }
+    tokens
+}
+
+/// Collects literal strings that must appear in every match of `hir`.
+fn required_literals(hir: &Hir, out: &mut Vec<String>) {
+    match hir.kind() {
+        HirKind::Literal(lit) => {
+            if let Ok(s) = std::str::from_utf8(&lit.0) {
+                out.push(s.to_string());
+            }
+        }
+        HirKind::Concat(subs) => {
+            for sub in subs {
+                required_literals(sub, out);
+            }
+        }
+        HirKind::Capture(cap) => required_literals(&cap.sub, out),
+        HirKind::Repetition(rep) if rep.min >= 1 => required_literals(&rep.sub, out),
+        // Alternations, optional repetitions, classes and assertions
+        // don't guarantee any particular literal.
+        _ => {}
+    }
+}
+
+/// Splits a literal the way the default tokenizer would, recording whether
+/// each token is bounded within the literal (and so must be a whole term).
+///
+/// With `cjk`, CJK runs are split off the way `--cjk` indexes them, and
+/// each becomes its bigrams, all whole terms: a bigram inside the literal
+/// is one of the text's however long the run there is. A lone CJK
+/// character gives no term.
+fn literal_tokens(literal: &str, cjk: bool) -> Vec<TermMatch> {
+    let mut pieces: Vec<&str> = Vec::new();
+    for piece in literal.split(|c: char| !c.is_alphanumeric()) {
+        if cjk {
+            pieces.extend(cjk_runs(piece));
+        } else {
+            pieces.push(piece);
+        }
+    }
+    let last = pieces.len().saturating_sub(1);
+
+    pieces
+        .iter()
+        .enumerate()
+        .filter(|(_, piece)| !piece.is_empty())
+        .flat_map(|(i, piece)| {
+            if cjk && piece.starts_with(is_cjk) {
+                let chars: Vec<char> = piece.chars().collect();
+                return chars
+                    .windows(2)
+                    .map(|pair| TermMatch::Exact(pair.iter().collect()))
+                    .collect();
+            }
+            if piece.len() > MAX_TOKEN_LEN {
+                return Vec::new();
+            }
+            let token = piece.to_lowercase();
+            vec![match (i == 0, i == last) {
+                (false, false) => TermMatch::Exact(token),
+                (true, false) => TermMatch::Suffix(token),
+                (false, true) => TermMatch::Prefix(token),
+                (true, true) => TermMatch::Contains(token),
+            }]
+        })
+        .collect()
+}
+
+/// `piece` split where CJK characters meet other letters.
+fn cjk_runs(piece: &str) -> Vec<&str> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+    for (i, c) in piece.char_indices() {
+        if previous.is_some_and(|was| was != is_cjk(c)) {
+            runs.push(&piece[start..i]);
+            start = i;
+        }
+        previous = Some(is_cjk(c));
+    }
+    runs.push(&piece[start..]);
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_bounded_and_partial_tokens() {
+        assert_eq!(
+            required_tokens(r"fn \w+_handler", false),
+            vec![
+                TermMatch::Suffix("fn".to_string()),
+                TermMatch::Prefix("handler".to_string()),
+            ]
+        );
+        assert_eq!(
+            required_tokens(r"impl EventStore \{", false),
+            vec![
+                TermMatch::Suffix("impl".to_string()),
+                TermMatch::Exact("eventstore".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn alternations_and_classes_give_no_tokens() {
+        assert!(required_tokens(r"foo|bar", false).is_empty());
+        assert!(required_tokens(r"\d{4}-\d{2}", false).is_empty());
+        assert!(required_tokens(r"(?:handler)?\d+", false).is_empty());
+        assert!(required_tokens(r"(unclosed", false).is_empty());
+    }
+
+    #[test]
+    fn substring_regex_is_literal_and_case_insensitive() {
+        let re = substring_regex("entSto").unwrap();
+        assert!(re.is_match("pub struct EventStore {"));
+        assert!(!substring_regex("a.b").unwrap().is_match("axb"));
+    }
+
+    #[test]
+    fn required_repetitions_keep_literals() {
+        assert_eq!(
+            required_tokens(r"(validate)+", false),
+            vec![TermMatch::Contains("validate".to_string())]
+        );
+    }
+
+    #[test]
+    fn cjk_runs_become_bigrams() {
+        let exact = |t: &str| TermMatch::Exact(t.to_string());
+        assert_eq!(required_tokens("事件存储", true), vec![exact("事件"), exact("件存"), exact("存储")]);
+        assert_eq!(
+            required_tokens("save事件", true),
+            vec![TermMatch::Suffix("save".to_string()), exact("事件")]
+        );
+        assert!(required_tokens("事", true).is_empty());
+        assert_eq!(required_tokens("事件", false), vec![TermMatch::Contains("事件".to_string())]);
+    }
+}
@@ -0,0 +1,428 @@
+//! Second ranking stage: re-scoring the top BM25 candidates.
+//!
+//! `execute_search` first asks tantivy for the best `RERANK_POOL` documents
+//! by BM25 over the indexed fields, without reading stored fields. This
+//! stage then loads just those documents and multiplies each score by
+//! signals that are too costly to evaluate over the whole index, or that no
+//! tantivy query expresses:
+//!
+//! - exact symbol: a query word equals one of the file's symbols
+//!   (`EXACT_SYMBOL_BOOST`, or `MINOR_SYMBOL_BOOST` when the symbol is only
+//!   a constant, impl block or re-export, so `Router` prefers the class
+//!   declaration over a constant named `ROUTER`);
+//! - coverage: the share of distinct query words the file matches, for
+//!   queries of two or more words;
+//! - proximity: all query words occur within `PROXIMITY_SLOP` positions of
+//!   each other, in order;
+//! - path: the share of query words found in the file's path;
+//! - recency: how recently the file was modified, fading to nothing over
+//!   `RECENCY_WINDOW`;
+//! - churn: how often the file was committed to recently, when the index
+//!   was built with `--churn-days` (see `indexer::churn`);
+//! - semantic: with `--semantic`, the cosine similarity of the file's
+//!   embedding to the query's (see `indexer::vectors`).
+//!
+//! Each stage is usable on its own: `Reranker::signals` computes the
+//! signals of one document, and `RerankSignals::multiplier` turns them into
+//! a score factor.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use tantivy::query::{EnableScoring, QueryParser, Weight};
+use tantivy::schema::{Field, Value};
+use tantivy::{DocAddress, Index, Searcher, TantivyDocument};
+
+use crate::indexer::churn::{read_churn, Churn};
+use crate::indexer::vectors::Vectors;
+use crate::indexer::symbols::{kinds_from_bytes, SymbolKind};
+
+use super::query::scorer_at;
+
+/// Number of BM25 candidates re-ranked per search.
+pub(crate) const RERANK_POOL: usize = 200;
+
+/// Score multiplier for a file defining a symbol that equals a query word.
+pub(crate) const EXACT_SYMBOL_BOOST: f32 = 2.0;
+
+/// Score multiplier when the exactly matching symbol is not a declaration
+/// (see `SymbolKind::is_declaration`).
+const MINOR_SYMBOL_BOOST: f32 = 1.3;
+
+/// Score gain for matching every query word (scaled by coverage).
+const COVERAGE_WEIGHT: f32 = 0.5;
+
+/// Maximum gap, in token positions, between query words that still counts
+/// as close together.
+const PROXIMITY_SLOP: u32 = 8;
+
+/// Score multiplier when all query words occur close together.
+const PROXIMITY_BOOST: f32 = 1.3;
+
+/// Score gain when every query word appears in the path (scaled by share).
+const PATH_WEIGHT: f32 = 0.3;
+
+/// Score gain for a file modified just now, fading linearly to 0 over
+/// `RECENCY_WINDOW`.
+const RECENCY_WEIGHT: f32 = 0.1;
+
+/// Age at which a file stops counting as recently modified.
+const RECENCY_WINDOW: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Score gain for the most committed file in the churn window (scaled by
+/// `Churn::score`).
+const CHURN_WEIGHT: f32 = 0.2;
+
+/// Score gain for a file whose embedding points the same way as the
+/// query's (scaled by their cosine similarity). Large next to the other
+/// gains: a conceptual query's words are a weak lexical signal.
+const SEMANTIC_WEIGHT: f32 = 1.0;
+
+/// Second-stage ranking signals of one document.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RerankSignals {
+    /// A query word equals one of the file's symbols, ignoring case.
+    pub exact_symbol: bool,
+    /// Kind of that symbol, from its definition line; declarations win
+    /// when several match. `None` without an exact match.
+    pub symbol_kind: Option<SymbolKind>,
+    /// Share of distinct query words matched (0..=1); `None` for queries
+    /// of fewer than two words, where it says nothing.
+    pub coverage: Option<f32>,
+    /// All query words occur within `PROXIMITY_SLOP` positions, in order.
+    pub proximity: bool,
+    /// Share of query words found in the path (0..=1).
+    pub path_match: f32,
+    /// 1 for a file modified now, fading to 0 at `RECENCY_WINDOW` (0 when
+    /// the file can't be read).
+    pub recency: f32,
+    /// Commit frequency relative to the busiest file (0..=1); 0 when the
+    /// index has no churn data.
+    pub churn: f32,
+    /// Cosine similarity of the file's embedding to the query's, negative
+    /// values counted as 0; 0 without `--semantic` or a vector for the file.
+    pub semantic: f32,
+}
+
+impl RerankSignals {
+    /// Factor applied to the first-stage score.
+    pub fn multiplier(&self) -> f32 {
+        let mut m = 1.0;
+        if self.exact_symbol {
+            m *= match self.symbol_kind {
+                Some(kind) if !kind.is_declaration() => MINOR_SYMBOL_BOOST,
+                _ => EXACT_SYMBOL_BOOST,
+            };
+        }
+        if let Some(coverage) = self.coverage {
+            m *= 1.0 + COVERAGE_WEIGHT * coverage;
+        }
+        if self.proximity {
+            m *= PROXIMITY_BOOST;
+        }
+        m *= 1.0 + PATH_WEIGHT * self.path_match;
+        m *= 1.0 + RECENCY_WEIGHT * self.recency;
+        m *= 1.0 + CHURN_WEIGHT * self.churn;
+        m *= 1.0 + SEMANTIC_WEIGHT * self.semantic;
+        m
+    }
+}
+
+/// A first-stage hit with its stored document and, once re-ranked, its
+/// signals and adjusted score.
+pub(crate) struct Candidate {
+    pub score: f32,
+    pub address: DocAddress,
+    pub doc: TantivyDocument,
+    pub signals: RerankSignals,
+}
+
+/// Computes `RerankSignals` for documents of one search.
+pub(crate) struct Reranker<'a> {
+    searcher: &'a Searcher,
+    root: PathBuf,
+    path_f: Field,
+    symbols_raw_f: Field,
+    symbol_kinds_f: Field,
+    /// Distinct query words, lowercased.
+    words: Vec<String>,
+    /// Unquoted query words as typed, for exact symbol matches.
+    exact_terms: Vec<String>,
+    /// One weight per entry of `words`.
+    word_weights: Vec<Box<dyn Weight>>,
+    proximity_weight: Option<Box<dyn Weight>>,
+    now: SystemTime,
+    churn: Option<Churn>,
+    /// File vectors and the query's embedding, with `--semantic`.
+    semantic: Option<(Vectors, Vec<f32>)>,
+}
+
+impl<'a> Reranker<'a> {
+    /// Prepares signals for the unquoted part of a query (`rest`), matched
+    /// against `fields` (proximity against `proximity_fields`, none in
+    /// symbol-only mode). With `fuzzy`, words are not looked up in the
+    /// index, so coverage and proximity stay empty.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        index: &Index,
+        searcher: &'a Searcher,
+        root: &Path,
+        path_f: Field,
+        symbols_raw_f: Field,
+        symbol_kinds_f: Field,
+        rest: &str,
+        fields: Vec<Field>,
+        proximity_fields: Vec<Field>,
+        fuzzy: bool,
+    ) -> Self {
+        let words = query_words(rest);
+        let scoring = EnableScoring::disabled_from_searcher(searcher);
+        let mut word_weights = Vec::new();
+        let mut proximity_weight = None;
+        if !fuzzy && words.len() > 1 {
+            let parser = QueryParser::for_index(index, fields);
+            word_weights = words
+                .iter()
+                .filter_map(|w| parser.parse_query(w).ok())
+                .filter_map(|q| q.weight(scoring).ok())
+                .collect();
+            if word_weights.len() != words.len() {
+                word_weights.clear();
+            }
+            if !proximity_fields.is_empty() {
+                let parser = QueryParser::for_index(index, proximity_fields);
+                let phrase = format!("\"{}\"~{}", words.join(" "), PROXIMITY_SLOP);
+                proximity_weight = parser
+                    .parse_query(&phrase)
+                    .ok()
+                    .and_then(|q| q.weight(scoring).ok());
+            }
+        }
+        Reranker {
+            searcher,
+            root: root.to_path_buf(),
+            path_f,
+            symbols_raw_f,
+            symbol_kinds_f,
+            exact_terms: exact_symbol_terms(rest),
+            words,
+            word_weights,
+            proximity_weight,
+            now: SystemTime::now(),
+            churn: read_churn(root),
+            semantic: None,
+        }
+    }
+
+    /// Adds the semantic signal: the similarity of each file's vector in
+    /// `vectors` to `query`, the query's embedding.
+    pub(crate) fn with_semantic(mut self, vectors: Vectors, query: Vec<f32>) -> Self {
+        self.semantic = Some((vectors, query));
+        self
+    }
+
+    /// Signals of the document at `address`, whose stored fields are `doc`.
+    pub(crate) fn signals(&self, address: DocAddress, doc: &TantivyDocument) -> RerankSignals {
+        let path = doc
+            .get_first(self.path_f)
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let symbols = doc
+            .get_first(self.symbols_raw_f)
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let kinds = kinds_from_bytes(
+            doc.get_first(self.symbol_kinds_f)
+                .and_then(|v| v.as_bytes())
+                .unwrap_or_default(),
+        );
+
+        let coverage = (!self.word_weights.is_empty()).then(|| {
+            let matched = self
+                .word_weights
+                .iter()
+                .filter(|w| scorer_at(w.as_ref(), self.searcher, address).is_some())
+                .count();
+            matched as f32 / self.word_weights.len() as f32
+        });
+        let proximity = self
+            .proximity_weight
+            .as_deref()
+            .is_some_and(|w| scorer_at(w, self.searcher, address).is_some());
+
+        let symbol_kind = exact_symbol_kind(symbols, &kinds, &self.exact_terms);
+        RerankSignals {
+            exact_symbol: symbol_kind.is_some(),
+            symbol_kind,
+            coverage,
+            proximity,
+            path_match: path_match(path, &self.words),
+            recency: self.recency(path),
+            churn: self.churn.as_ref().map_or(0.0, |c| c.score(path)),
+            semantic: self
+                .semantic
+                .as_ref()
+                .and_then(|(vectors, query)| vectors.similarity(path, query))
+                .map_or(0.0, |s| s.max(0.0)),
+        }
+    }
+
+    fn recency(&self, path: &str) -> f32 {
+        let Ok(modified) = std::fs::metadata(self.root.join(path)).and_then(|m| m.modified())
+        else {
+            return 0.0;
+        };
+        let age = self.now.duration_since(modified).unwrap_or_default();
+        (1.0 - age.as_secs_f32() / RECENCY_WINDOW.as_secs_f32()).max(0.0)
+    }
+}
+
+/// Re-scores `candidates` with `reranker` and sorts them by the new score.
+/// Ties keep their first-stage order.
+pub(crate) fn rerank(candidates: &mut [Candidate], reranker: &Reranker) {
+    for c in candidates.iter_mut() {
+        c.signals = reranker.signals(c.address, &c.doc);
+        c.score *= c.signals.multiplier();
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+}
+
+/// Distinct words of `rest`, lowercased, split at anything but letters,
+/// digits and `_`.
+fn query_words(rest: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    for word in rest.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        let word = word.to_lowercase();
+        if !word.is_empty() && !words.contains(&word) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// Share of `words` contained in `path`, ignoring case.
+fn path_match(path: &str, words: &[String]) -> f32 {
+    if words.is_empty() {
+        return 0.0;
+    }
+    let path = path.to_lowercase();
+    let found = words
+        .iter()
+        .filter(|w| path.contains(String::as_str(w)))
+        .count();
+    found as f32 / words.len() as f32
+}
+
+/// Unquoted query words, lowercased, as candidates for an exact symbol
+/// match. Surrounding punctuation is dropped; `.` and `:` inside a word are
+/// kept so `MyApp.Accounts` and `io::Error` compare whole.
+fn exact_symbol_terms(rest: &str) -> Vec<String> {
+    rest.split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !(c.is_alphanumeric() || c == '_')))
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Kind of a symbol in the `|`-separated `symbols` that equals one of
+/// `terms`, ignoring case, or `None` if none does. `kinds` holds the
+/// symbols' kinds in the same order (a missing kind is `Other`). A
+/// declaration wins over earlier matches of other kinds.
+fn exact_symbol_kind(symbols: &str, kinds: &[SymbolKind], terms: &[String]) -> Option<SymbolKind> {
+    let mut found = None;
+    for (i, symbol) in symbols.split('|').enumerate() {
+        if symbol.is_empty() || !terms.contains(&symbol.to_lowercase()) {
+            continue;
+        }
+        let kind = kinds.get(i).copied().unwrap_or(SymbolKind::Other);
+        if kind.is_declaration() {
+            return Some(kind);
+        }
+        found.get_or_insert(kind);
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiplier_combines_signals() {
+        assert_eq!(RerankSignals::default().multiplier(), 1.0);
+        let all = RerankSignals {
+            exact_symbol: true,
+            symbol_kind: Some(SymbolKind::Type),
+            coverage: Some(1.0),
+            proximity: true,
+            path_match: 1.0,
+            recency: 1.0,
+            churn: 1.0,
+            semantic: 1.0,
+        };
+        let expected = EXACT_SYMBOL_BOOST
+            * (1.0 + COVERAGE_WEIGHT)
+            * PROXIMITY_BOOST
+            * (1.0 + PATH_WEIGHT)
+            * (1.0 + RECENCY_WEIGHT)
+            * (1.0 + CHURN_WEIGHT)
+            * (1.0 + SEMANTIC_WEIGHT);
+        assert!((all.multiplier() - expected).abs() < 1e-6);
+
+        let half = RerankSignals {
+            coverage: Some(0.5),
+            ..RerankSignals::default()
+        };
+        assert!(half.multiplier() < all.multiplier());
+        assert!(half.multiplier() > 1.0);
+
+        let constant = RerankSignals {
+            exact_symbol: true,
+            symbol_kind: Some(SymbolKind::Constant),
+            ..RerankSignals::default()
+        };
+        assert_eq!(constant.multiplier(), MINOR_SYMBOL_BOOST);
+    }
+
+    #[test]
+    fn query_words_are_distinct_and_lowercased() {
+        assert_eq!(
+            query_words("EventStore append, eventstore io::Error"),
+            vec!["eventstore", "append", "io", "error"]
+        );
+    }
+
+    #[test]
+    fn path_match_is_share_of_words_in_path() {
+        let words = vec!["event".to_string(), "append".to_string()];
+        assert_eq!(path_match("src/event_store.rs", &words), 0.5);
+        assert_eq!(path_match("src/Event_Append.rs", &words), 1.0);
+        assert_eq!(path_match("src/event_store.rs", &[]), 0.0);
+    }
+
+    #[test]
+    fn exact_symbol_ignores_case_and_partial_names() {
+        let terms = exact_symbol_terms("eventstore \"quoted\"");
+        assert_eq!(
+            exact_symbol_kind("Foo|EventStore", &[], &terms),
+            Some(SymbolKind::Other)
+        );
+        assert_eq!(exact_symbol_kind("EventStoreError", &[], &terms), None);
+        assert_eq!(exact_symbol_kind("", &[], &terms), None);
+    }
+
+    #[test]
+    fn exact_symbol_prefers_declarations() {
+        let terms = exact_symbol_terms("Router");
+        let symbols = "ROUTER|Router";
+        let kinds = [SymbolKind::Constant, SymbolKind::Type];
+        assert_eq!(
+            exact_symbol_kind(symbols, &kinds, &terms),
+            Some(SymbolKind::Type)
+        );
+        assert_eq!(
+            exact_symbol_kind("ROUTER", &kinds[..1], &terms),
+            Some(SymbolKind::Constant)
+        );
+    }
+}
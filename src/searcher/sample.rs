@@ -0,0 +1,139 @@
+//! `--sample N`: a stratified sample of the matches instead of the top N.
+//!
+//! For a very common term the top of a BM25 ranking is often one cluster of
+//! near-identical files. Sampling groups candidates by stratum (directory and
+//! language) and takes them round-robin, best-ranked first within each group,
+//! so every area of the repo where the pattern occurs is represented before
+//! any area gets a second pick.
+//!
+//! `--max-per-dir N` is the lighter-handed version: the ranking is kept,
+//! but once a directory has N results its lower-ranked files give way to
+//! the next-best ones from elsewhere.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Upper bound on candidates considered for a sample, so a term matching
+/// most of a huge repo doesn't load every stored document.
+pub(crate) const SAMPLE_POOL: usize = 2000;
+
+/// Stratum of a file: its parent directory and language.
+pub(crate) fn stratum(path: &str, lang: Option<&str>) -> String {
+    format!("{}\0{}", parent_dir(path), lang.unwrap_or(""))
+}
+
+/// Parent directory of `path`, `.` for files at the root.
+fn parent_dir(path: &str) -> String {
+    Path::new(path)
+        .parent()
+        .map(|p| p.to_string_lossy().into_owned())
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| ".".to_string())
+}
+
+/// Keeps at most `max` of `items` (rank order) from any one directory,
+/// dropping the lower-ranked rest; the order is unchanged.
+pub(crate) fn cap_per_dir<T>(items: Vec<T>, max: usize, path: impl Fn(&T) -> &str) -> Vec<T> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    items
+        .into_iter()
+        .filter(|item| {
+            let count = counts.entry(parent_dir(path(item))).or_insert(0);
+            *count += 1;
+            *count <= max
+        })
+        .collect()
+}
+
+/// Picks up to `n` items from `items` (rank order, each with its stratum),
+/// one stratum at a time in round-robin. Strata are visited in the order of
+/// their best-ranked item; the picks are returned in their original rank
+/// order.
+pub(crate) fn stratified<T>(items: Vec<(T, String)>, n: usize) -> Vec<T> {
+    let mut order: Vec<String> = Vec::new();
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, (_, key)) in items.iter().enumerate() {
+        groups
+            .entry(key.clone())
+            .or_insert_with(|| {
+                order.push(key.clone());
+                Vec::new()
+            })
+            .push(i);
+    }
+
+    let mut picked: Vec<usize> = Vec::with_capacity(n.min(items.len()));
+    let mut round = 0;
+    while picked.len() < n.min(items.len()) {
+        for key in &order {
+            if let Some(&i) = groups[key].get(round) {
+                picked.push(i);
+                if picked.len() == n {
+                    break;
+                }
+            }
+        }
+        round += 1;
+    }
+    picked.sort_unstable();
+
+    let mut items: Vec<Option<T>> = items.into_iter().map(|(item, _)| Some(item)).collect();
+    picked.into_iter().filter_map(|i| items[i].take()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyed(items: &[(&'static str, &str)]) -> Vec<(&'static str, String)> {
+        items.iter().map(|(v, k)| (*v, k.to_string())).collect()
+    }
+
+    #[test]
+    fn round_robin_across_strata_keeps_rank_order() {
+        let items = keyed(&[
+            ("a1", "a"),
+            ("a2", "a"),
+            ("a3", "a"),
+            ("b1", "b"),
+            ("a4", "a"),
+            ("c1", "c"),
+        ]);
+        assert_eq!(stratified(items, 4), vec!["a1", "a2", "b1", "c1"]);
+    }
+
+    #[test]
+    fn sample_larger_than_pool_returns_everything() {
+        let items = keyed(&[("a1", "a"), ("b1", "b")]);
+        assert_eq!(stratified(items, 10), vec!["a1", "b1"]);
+    }
+
+    #[test]
+    fn cap_per_dir_keeps_the_best_of_each_directory() {
+        let paths = vec![
+            "tests/fixtures/a.json",
+            "tests/fixtures/b.json",
+            "tests/fixtures/c.json",
+            "src/event.rs",
+            "tests/fixtures/d.json",
+            "README.md",
+            "tests/fixtures/nested/e.json",
+        ];
+        assert_eq!(
+            cap_per_dir(paths, 2, |p| p),
+            vec![
+                "tests/fixtures/a.json",
+                "tests/fixtures/b.json",
+                "src/event.rs",
+                "README.md",
+                "tests/fixtures/nested/e.json",
+            ]
+        );
+    }
+
+    #[test]
+    fn stratum_uses_parent_directory_and_language() {
+        assert_eq!(stratum("src/net/http.go", Some("go")), "src/net\0go");
+        assert_eq!(stratum("README.md", None), ".\0");
+    }
+}
@@ -0,0 +1,194 @@
+//! `--no-index`: searching the files on disk without an index.
+//!
+//! Walks the repo the way `ns index` does (`.gitignore` honoured, binary and
+//! oversized files skipped) and ranks files by matching lines, like
+//! `--regex`, so a repo without `.ns/` still answers through the same
+//! output formats, budget and dedupe. A line matches a plain query when it
+//! contains one of the query words, ignoring case, as context extraction
+//! matches lines; files matching more of the words rank higher, and files
+//! containing a `-term` exclusion are dropped, as in the index. There is no
+//! BM25, symbol boost or re-ranking, and every file is read, so
+//! `search` adds a `Warning::Unindexed` suggesting `ns index`.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+
+use regex::Regex;
+
+use crate::error::NsError;
+use crate::indexer::generated::is_generated;
+use crate::indexer::language::is_test_path;
+use crate::indexer::tokenizer::part_tokens;
+use crate::indexer::walker::walk_repo;
+use crate::indexer::IndexOptions;
+
+use super::context::tokenize_query;
+use super::query::{
+    in_dirs, split_query_terms, under_prefixes, SearchOptions, SearchResult, SearchStats,
+};
+use super::regex_search::rank_line_results;
+use super::rerank::RerankSignals;
+
+/// Ranks the files under `root` by lines matching `regex`, or by lines
+/// containing words of `query_str` when `regex` is `None`.
+///
+/// Honours `file_type`, `file_glob`, `paths`, `dedupe`, `sample` and
+/// `max_results` like `execute_search`. Files over the default
+/// `--max-file-size` are skipped, as `ns index` would skip them.
+pub fn execute_scan_search(
+    root: &Path,
+    query_str: &str,
+    regex: Option<&Regex>,
+    opts: &SearchOptions,
+) -> Result<(Vec<SearchResult>, SearchStats), NsError> {
+    let glob = match opts.file_glob {
+        Some(ref g) => Some(glob::Pattern::new(g)?),
+        None => None,
+    };
+    let (searched, excluded) = split_query_terms(query_str, false);
+    let mut words = tokenize_query(&searched);
+    words.sort();
+    words.dedup();
+
+    let start = Instant::now();
+    let files = walk_repo(root, IndexOptions::default().max_file_size);
+    let files_searched = files.len();
+    let mut results = Vec::new();
+    for file in files {
+        if opts.file_type.is_some() && file.lang != opts.file_type {
+            continue;
+        }
+        if glob.as_ref().is_some_and(|g| !g.matches(&file.rel_path)) {
+            continue;
+        }
+        if !under_prefixes(root, &file.rel_path, &opts.paths) {
+            continue;
+        }
+
+        let score = match regex {
+            Some(re) => file
+                .content
+                .lines()
+                .filter(|line| re.is_match(line))
+                .count() as f32,
+            None => word_score(&file.content, &words),
+        };
+        if score == 0.0 {
+            continue;
+        }
+        if regex.is_none() && excludes(&file.content, &excluded) {
+            continue;
+        }
+
+        let is_test = is_test_path(&file.rel_path);
+        let vendored = in_dirs(&file.rel_path, &opts.vendor_dirs);
+        let generated = is_generated(&file.rel_path, &file.content);
+        let mut weight = if is_test { opts.test_weight } else { 1.0 };
+        if generated {
+            weight *= opts.generated_weight;
+        }
+        if vendored {
+            weight *= opts.vendor_weight;
+        }
+        results.push(SearchResult {
+            path: file.rel_path,
+            score: score * weight,
+            lang: file.lang,
+            symbols_raw: Vec::new(),
+            symbol_defs: Vec::new(),
+            symbol_spans: Vec::new(),
+            symbol_kinds: Vec::new(),
+            symbol: None,
+            score_content: score,
+            score_symbols: 0.0,
+            matched_fields: vec!["content".to_string()],
+            rerank: RerankSignals::default(),
+            terms: Vec::new(),
+            is_test,
+            vendored,
+            truncated: file.truncated,
+            generated,
+            alternates: Vec::new(),
+            duplicates: Vec::new(),
+            content_hash: None,
+            match_lines: Vec::new(),
+        });
+    }
+    let elapsed_ms = start.elapsed().as_millis() as u64;
+    let results = rank_line_results(root, results, &HashMap::new(), opts);
+
+    let stats = SearchStats {
+        total_results: results.len(),
+        files_searched,
+        elapsed_ms,
+        stale_results: 0,
+        indexed_commit: None,
+        route: None,
+        no_symbols: false,
+    };
+    Ok((results, stats))
+}
+
+/// Lines of `content` containing any of `words` (lowercase), scaled by the
+/// share of `words` found anywhere in the file. 0 when nothing matches.
+fn word_score(content: &str, words: &[String]) -> f32 {
+    if words.is_empty() {
+        return 0.0;
+    }
+    let mut found = vec![false; words.len()];
+    let mut lines = 0;
+    for line in content.lines() {
+        let lower = line.to_lowercase();
+        let mut matched = false;
+        for (i, word) in words.iter().enumerate() {
+            if lower.contains(word.as_str()) {
+                found[i] = true;
+                matched = true;
+            }
+        }
+        if matched {
+            lines += 1;
+        }
+    }
+    let coverage = found.iter().filter(|f| **f).count() as f32 / words.len() as f32;
+    lines as f32 * coverage
+}
+
+/// Whether `content` holds any of the `excluded` token sequences as
+/// adjacent words, as an exclusion matches indexed content.
+fn excludes(content: &str, excluded: &[Vec<String>]) -> bool {
+    if excluded.is_empty() {
+        return false;
+    }
+    let tokens = part_tokens(content, false);
+    excluded
+        .iter()
+        .any(|phrase| tokens.windows(phrase.len()).any(|w| w == phrase.as_slice()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_score_prefers_files_matching_every_word() {
+        let words = vec!["event".to_string(), "store".to_string()];
+        let both = "struct EventStore;\nfn store() {}\n";
+        let one = "// event\n// event\n// event\n";
+        assert_eq!(word_score(both, &words), 2.0);
+        assert_eq!(word_score(one, &words), 1.5);
+        assert_eq!(word_score("nothing here\n", &words), 0.0);
+        assert_eq!(word_score(both, &[]), 0.0);
+    }
+
+    #[test]
+    fn exclusions_match_adjacent_words() {
+        let content = "struct EventStore;\nfn mock() {}\n";
+        let phrase = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        assert!(excludes(content, &[phrase(&["mock"])]));
+        assert!(excludes(content, &[phrase(&["event", "store"])]));
+        assert!(!excludes(content, &[phrase(&["store", "event"])]));
+        assert!(!excludes(content, &[]));
+    }
+}
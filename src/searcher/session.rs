@@ -0,0 +1,127 @@
+//! Index kept open between searches in one process (`ns repl`).
+//!
+//! Every search opens `.ns/`, reads `meta.json` and creates a reader. Next
+//! to spawning `ns` that is cheap, but once the process stays up it is most
+//! of a warm query. A long-lived caller turns on `keep_index_open`; searches
+//! of `.ns/` then share one `Index` and reader, reopened only when
+//! `meta.json` changes (every index write rewrites it), until the returned
+//! `KeptIndex` is dropped.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use tantivy::{Index, IndexReader};
+
+use crate::error::NsError;
+use crate::indexer::writer::{open_index, IndexMeta};
+
+use super::query::create_reader_with_retry;
+
+/// The index open for `root`, as of `meta.json`'s `stamp`.
+struct OpenIndex {
+    root: PathBuf,
+    stamp: Option<SystemTime>,
+    index: Index,
+    meta: IndexMeta,
+    reader: IndexReader,
+}
+
+#[derive(Default)]
+struct Session {
+    enabled: bool,
+    open: Option<OpenIndex>,
+}
+
+fn session() -> &'static Mutex<Session> {
+    static SESSION: OnceLock<Mutex<Session>> = OnceLock::new();
+    SESSION.get_or_init(Mutex::default)
+}
+
+/// Keeps the index open across the searches this process runs while the
+/// returned guard lives.
+pub fn keep_index_open() -> KeptIndex {
+    if let Ok(mut session) = session().lock() {
+        session.enabled = true;
+    }
+    KeptIndex(())
+}
+
+/// Turns `keep_index_open` off again, closing the index, when dropped.
+#[must_use = "the index is only kept open while the guard lives"]
+pub struct KeptIndex(());
+
+impl Drop for KeptIndex {
+    fn drop(&mut self) {
+        if let Ok(mut session) = session().lock() {
+            *session = Session::default();
+        }
+    }
+}
+
+/// The index under `root`, its metadata and a reader: the ones kept open
+/// while a `keep_index_open` guard lives and `meta.json` hasn't changed since,
+/// otherwise freshly opened.
+pub(crate) fn open_with_reader(root: &Path) -> Result<(Index, IndexMeta, IndexReader), NsError> {
+    let Ok(mut session) = session().lock() else {
+        return open_fresh(root);
+    };
+    if !session.enabled {
+        return open_fresh(root);
+    }
+    let stamp = std::fs::metadata(root.join(".ns").join("meta.json"))
+        .and_then(|m| m.modified())
+        .ok();
+    if let Some(open) = session
+        .open
+        .as_ref()
+        .filter(|open| open.root == root && open.stamp.is_some() && open.stamp == stamp)
+    {
+        return Ok((open.index.clone(), open.meta.clone(), open.reader.clone()));
+    }
+    let (index, meta, reader) = open_fresh(root)?;
+    session.open = Some(OpenIndex {
+        root: root.to_path_buf(),
+        stamp,
+        index: index.clone(),
+        meta: meta.clone(),
+        reader: reader.clone(),
+    });
+    Ok((index, meta, reader))
+}
+
+fn open_fresh(root: &Path) -> Result<(Index, IndexMeta, IndexReader), NsError> {
+    let (index, meta) = open_index(root)?;
+    let reader = create_reader_with_retry(&index, root)?;
+    Ok((index, meta, reader))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::{run_full_index, IndexOptions};
+
+    #[test]
+    fn kept_index_is_reopened_after_a_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("a.rs"), "fn alpha() {}\n").unwrap();
+        run_full_index(root, &IndexOptions::default()).unwrap();
+
+        let kept = keep_index_open();
+        let (_, first, _) = open_with_reader(root).unwrap();
+        let (_, again, reader) = open_with_reader(root).unwrap();
+        assert_eq!(first.indexed_at, again.indexed_at);
+        assert_eq!(reader.searcher().num_docs(), 1);
+
+        std::fs::write(root.join("b.rs"), "fn beta() {}\n").unwrap();
+        // Make sure the rewritten meta.json gets a different mtime.
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        run_full_index(root, &IndexOptions::default()).unwrap();
+        let (_, _, reader) = open_with_reader(root).unwrap();
+        assert_eq!(reader.searcher().num_docs(), 2);
+
+        drop(kept);
+        assert!(!session().lock().unwrap().enabled, "dropping the guard ends the session");
+    }
+}
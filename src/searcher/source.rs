@@ -0,0 +1,73 @@
+//! Which version of a file context lines are read from.
+//!
+//! Scores come from the index, so when the working tree has changed since the
+//! last `ns index`, context read from disk can describe a different file than
+//! the one that was ranked. With `--context-from-git`, a file whose content no
+//! longer matches its stored hash is read from the commit the index was built
+//! at instead, provided that blob is the indexed version.
+
+use std::path::Path;
+use std::process::Command;
+
+use crate::indexer::writer::content_hash;
+
+/// Returns the text to extract context from for `rel_path`.
+///
+/// Reads the working tree. When `indexed_commit` is set and the file is
+/// missing or its hash differs from `indexed_hash`, falls back to
+/// `git show <commit>:<path>` if that blob hashes to `indexed_hash`; otherwise
+/// keeps the working-tree copy (or `None` if there is none).
+pub(crate) fn indexed_source(
+    root: &Path,
+    rel_path: &str,
+    indexed_hash: Option<u64>,
+    indexed_commit: Option<&str>,
+) -> Option<String> {
+    let current = std::fs::read(root.join(rel_path)).ok();
+    let (Some(hash), Some(commit)) = (indexed_hash, indexed_commit) else {
+        return current.and_then(|bytes| String::from_utf8(bytes).ok());
+    };
+    if current.as_deref().is_some_and(|bytes| content_hash(bytes) == hash) {
+        return current.and_then(|bytes| String::from_utf8(bytes).ok());
+    }
+
+    match git_blob(root, commit, rel_path) {
+        Some(blob) if content_hash(&blob) == hash => String::from_utf8(blob).ok(),
+        _ => current.and_then(|bytes| String::from_utf8(bytes).ok()),
+    }
+}
+
+/// Contents of `rel_path` at `commit`, or `None` if git can't provide it.
+fn git_blob(root: &Path, commit: &str, rel_path: &str) -> Option<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("show")
+        .arg(format!("{}:{}", commit, rel_path))
+        .current_dir(root)
+        .output()
+        .ok()?;
+    output.status.success().then_some(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn without_commit_reads_working_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "now").unwrap();
+
+        let text = indexed_source(dir.path(), "a.txt", Some(content_hash(b"then")), None);
+        assert_eq!(text.as_deref(), Some("now"));
+        assert_eq!(indexed_source(dir.path(), "gone.txt", None, None), None);
+    }
+
+    #[test]
+    fn unknown_commit_falls_back_to_working_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "now").unwrap();
+
+        let text = indexed_source(dir.path(), "a.txt", Some(content_hash(b"then")), Some("HEAD"));
+        assert_eq!(text.as_deref(), Some("now"));
+    }
+}
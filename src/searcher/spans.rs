@@ -1,9 +1,12 @@
+use std::collections::BTreeSet;
 use std::path::Path;
 
 use tree_sitter::{Node, Parser};
 
 use crate::indexer::language::detect_language;
-use crate::searcher::context::{tokenize_query, ContextLine, ContextResult};
+use crate::searcher::context::{
+    capped_lines, context_in, matching_lines, tokenize_query, ContextLine, ContextResult,
+};
 
 /// A candidate span from the AST (or a fallback fixed window).
 #[derive(Debug)]
@@ -40,21 +43,35 @@ struct ScoredSpan {
 ///
 /// Returns the same `ContextResult` type as `extract_context` so that the
 /// downstream formatting pipeline requires no changes.
+#[allow(dead_code)] // library entry point; the CLI reads files via `source`
 pub fn extract_best_spans(
     root: &Path,
     rel_path: &str,
     query: &str,
     max_lines: Option<usize>,
+) -> ContextResult {
+    match std::fs::read_to_string(root.join(rel_path)) {
+        Ok(content) => best_spans_in(rel_path, &content, query, max_lines),
+        Err(_) => ContextResult {
+            lines: Vec::new(),
+            truncated_count: 0,
+            matches: Vec::new(),
+        },
+    }
+}
+
+/// `extract_best_spans` over already-read file content; `rel_path` only
+/// selects the grammar.
+pub(crate) fn best_spans_in(
+    rel_path: &str,
+    content: &str,
+    query: &str,
+    max_lines: Option<usize>,
 ) -> ContextResult {
     let empty = ContextResult {
         lines: Vec::new(),
         truncated_count: 0,
-    };
-
-    let full_path = root.join(rel_path);
-    let content = match std::fs::read_to_string(&full_path) {
-        Ok(s) => s,
-        Err(_) => return empty,
+        matches: Vec::new(),
     };
 
     let source = content.as_bytes();
@@ -95,7 +112,119 @@ pub fn extract_best_spans(
     }
 
     // Build output
-    build_context_result(&candidates, &selected, &file_lines, budget)
+    let mut result = build_context_result(&candidates, &selected, &file_lines, budget);
+    result.matches = one_based(&matching_lines(&file_lines, &terms));
+    result
+}
+
+/// First line of each matching definition, best-scoring first, capped at
+/// `max_lines` and returned in file order. Used by `--quick`, where a
+/// signature is usually all an agent needs to decide whether to open a file.
+///
+/// Files without a grammar (or without a matching definition) fall back to
+/// the matching lines themselves.
+pub(crate) fn signature_lines_in(
+    rel_path: &str,
+    content: &str,
+    query: &str,
+    max_lines: Option<usize>,
+) -> ContextResult {
+    let file_lines: Vec<&str> = content.lines().collect();
+    let terms = tokenize_query(query);
+    let lang = detect_language(Path::new(rel_path)).unwrap_or("");
+    let candidates = extract_span_candidates(lang, content.as_bytes(), file_lines.len());
+
+    let mut scored: Vec<ScoredSpan> = score_candidates(&candidates, &file_lines, &terms, 0)
+        .into_iter()
+        .filter(|s| candidates[s.idx].is_ast)
+        .collect();
+    if scored.is_empty() {
+        return context_in(content, query, 0, max_lines);
+    }
+    scored.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| candidates[a.idx].start_line.cmp(&candidates[b.idx].start_line))
+    });
+
+    let mut starts: Vec<usize> = Vec::new();
+    for ss in &scored {
+        let start = candidates[ss.idx].start_line;
+        if !starts.contains(&start) {
+            starts.push(start);
+        }
+    }
+    let cap = match max_lines {
+        Some(0) | None => usize::MAX,
+        Some(n) => n,
+    };
+    let truncated_count = starts.len().saturating_sub(cap);
+    starts.truncate(cap);
+    starts.sort_unstable();
+
+    ContextResult {
+        lines: starts
+            .into_iter()
+            .map(|i| ContextLine {
+                line_number: i + 1,
+                text: file_lines[i].to_string(),
+            })
+            .collect(),
+        truncated_count,
+        matches: one_based(&matching_lines(&file_lines, &terms)),
+    }
+}
+
+/// 1-based line numbers for 0-based `indices`.
+fn one_based(indices: &BTreeSet<usize>) -> Vec<usize> {
+    indices.iter().map(|i| i + 1).collect()
+}
+
+/// Span kinds that are a function or method, across the supported grammars.
+const FUNCTION_KINDS: &[&str] = &[
+    "function_item",
+    "function_declaration",
+    "function_definition",
+    "method_definition",
+    "method_declaration",
+    "def",
+];
+
+/// The whole innermost function or method around each matching line
+/// (0-based `matches`), for `--context-scope function`. Matches outside any
+/// function, and every match in a file without a grammar, get the usual
+/// ±`context_window` lines. `max_lines` caps the total as in `context_in`,
+/// so a long function is cut off at the cap rather than crowding out the
+/// files after it.
+pub(crate) fn enclosing_functions_in(
+    rel_path: &str,
+    content: &str,
+    matches: &BTreeSet<usize>,
+    context_window: usize,
+    max_lines: Option<usize>,
+) -> ContextResult {
+    let file_lines: Vec<&str> = content.lines().collect();
+    let lang = detect_language(Path::new(rel_path)).unwrap_or("");
+    let functions: Vec<SpanCandidate> =
+        extract_span_candidates(lang, content.as_bytes(), file_lines.len())
+            .into_iter()
+            .filter(|c| c.is_ast && FUNCTION_KINDS.contains(&c.kind))
+            .collect();
+
+    let in_file: BTreeSet<usize> = matches.range(..file_lines.len()).copied().collect();
+    let mut include = BTreeSet::new();
+    for &line in &in_file {
+        let innermost = functions
+            .iter()
+            .filter(|f| f.start_line <= line && line <= f.end_line)
+            .min_by_key(|f| f.end_line - f.start_line);
+        let (start, end) = match innermost {
+            Some(f) => (f.start_line, f.end_line),
+            None => (line.saturating_sub(context_window), line + context_window),
+        };
+        include.extend(start..=end.min(file_lines.len() - 1));
+    }
+    capped_lines(&file_lines, &include, &in_file, max_lines)
 }
 
 // ── Phase 1: Extract candidates ───────────────────────────────────────────────
@@ -683,10 +812,11 @@ fn build_context_result(
                 remaining -= span_lines;
             }
         } else {
-            // Truncate: first half + last half; gap shown by non-contiguous line numbers
+            // Truncate: first half + last half; gap shown by non-contiguous line numbers.
+            // A single line left shows the span's first line only.
             let half = remaining / 2;
-            let first_end = (c.start_line + half).saturating_sub(1).min(span_end);
-            let last_start = span_end.saturating_sub(half - 1).max(first_end + 1);
+            let first_end = (c.start_line + half.max(1)).saturating_sub(1).min(span_end);
+            let last_start = (span_end + 1).saturating_sub(half).max(first_end + 1);
 
             for i in c.start_line..=first_end {
                 context_lines.push(ContextLine {
@@ -708,6 +838,7 @@ fn build_context_result(
     ContextResult {
         lines: context_lines,
         truncated_count,
+        matches: Vec::new(),
     }
 }
 
@@ -847,6 +978,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn function_scope_returns_the_innermost_function() {
+        let source = "\
+use std::io;
+
+impl Store {
+    fn load(&self) -> io::Result<()> {
+        let path = self.path();
+        read_snapshot(path)?;
+        Ok(())
+    }
+
+    fn save(&self) {}
+}
+
+// read_snapshot is also mentioned here
+const LIMIT: usize = 3;
+";
+        let lines = |r: ContextResult| -> Vec<usize> {
+            r.lines.iter().map(|l| l.line_number).collect()
+        };
+        // read_snapshot is on lines 6 and 13.
+        let matches = BTreeSet::from([5, 12]);
+        let scoped = enclosing_functions_in("src/store.rs", source, &matches, 1, Some(0));
+        assert_eq!(lines(scoped), vec![4, 5, 6, 7, 8, 12, 13, 14]);
+
+        let capped = enclosing_functions_in("src/store.rs", source, &matches, 1, Some(3));
+        assert_eq!(capped.truncated_count, 5);
+        assert_eq!(lines(capped), vec![4, 5, 6]);
+
+        let plain = enclosing_functions_in("notes.txt", source, &matches, 0, Some(0));
+        assert_eq!(lines(plain), vec![6, 13]);
+    }
+
     // ── score_candidates ───────────────────────────────────────────────────────
 
     #[test]
@@ -946,6 +1111,26 @@ fn unrelated_function() {
         assert_eq!(selected[0], 0);
     }
 
+    #[test]
+    fn truncated_span_fits_a_one_line_remainder() {
+        let span = |start_line, end_line| SpanCandidate {
+            start_line,
+            end_line,
+            kind: "function_item",
+            symbol_name: None,
+            parent_idx: None,
+            is_ast: true,
+        };
+        let candidates = vec![span(0, 8), span(10, 19)];
+        let lines: Vec<String> = (0..20).map(|i| format!("line {}", i)).collect();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+
+        let result = build_context_result(&candidates, &[0, 1], &lines, 10);
+        let numbers: Vec<usize> = result.lines.iter().map(|l| l.line_number).collect();
+        assert_eq!(numbers, (1..=9).chain([11]).collect::<Vec<_>>());
+        assert_eq!(result.truncated_count, 9);
+    }
+
     #[test]
     fn pack_skips_overlapping_spans() {
         let candidates = vec![
@@ -1001,6 +1186,28 @@ fn unrelated_function() {
         assert_eq!(selected.len(), 5, "unlimited budget should select all non-overlapping spans");
     }
 
+    // ── signature_lines_in ─────────────────────────────────────────────────────
+
+    #[test]
+    fn signature_lines_are_definition_starts() {
+        let content = include_str!("../../tests/fixtures/sample_repo/src/event_store.rs");
+        let result = signature_lines_in("src/event_store.rs", content, "EventStore", Some(3));
+        assert!(!result.lines.is_empty());
+        assert!(result.lines.len() <= 3);
+        assert!(result
+            .lines
+            .iter()
+            .any(|l| l.text.contains("pub struct EventStore")));
+        assert!(result.lines.windows(2).all(|w| w[0].line_number < w[1].line_number));
+    }
+
+    #[test]
+    fn signature_lines_fall_back_to_matching_lines() {
+        let result = signature_lines_in("notes.txt", "one\nEventStore here\nthree\n", "EventStore", Some(3));
+        assert_eq!(result.lines.len(), 1);
+        assert_eq!(result.lines[0].line_number, 2);
+    }
+
     // ── extract_best_spans (integration) ──────────────────────────────────────
 
     #[test]
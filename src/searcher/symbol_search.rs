@@ -0,0 +1,327 @@
+//! Symbol listing and definition lookup (`ns symbols`, `ns def`).
+//!
+//! Search ranks files; these list definitions. A name pattern (`Event*`,
+//! `*_handler`, or a plain word matched anywhere in the name) is turned into
+//! a regex over the `symbols` term dictionary, whose terms are the
+//! lowercased names, so only files defining a matching name are read; a
+//! definition lookup is a single term. The stored `symbols_raw`,
+//! `symbol_kinds`, `symbol_spans` and `symbols_def` give each definition's
+//! original spelling, kind, lines and source line.
+
+use std::path::Path;
+
+use regex::Regex;
+use serde::Serialize;
+use tantivy::query::{BooleanQuery, Occur, Query, RegexQuery, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Value};
+use tantivy::{TantivyDocument, Term};
+
+use crate::error::NsError;
+use crate::indexer::symbols::SymbolKind;
+use crate::indexer::writer::open_index;
+use crate::schema::{
+    lang_field, path_field, symbol_kinds_field, symbol_spans_field, symbols_def_field,
+    symbols_field, symbols_raw_field,
+};
+
+use super::query::{create_reader_with_retry, stored_kinds, stored_spans};
+
+/// Options for `find_symbols` and `find_definitions`.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolSearchOptions {
+    /// Language filter (`-t`).
+    pub file_type: Option<String>,
+    /// Path glob filter (`-g`).
+    pub file_glob: Option<String>,
+    /// Only symbols of these kinds (`--kind`); all kinds when empty.
+    pub kinds: Vec<SymbolKind>,
+}
+
+/// One symbol definition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FoundSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub path: String,
+    /// 1-based first and last line of the definition; `None` when the
+    /// index was trimmed to its budget and has no spans.
+    pub line: Option<usize>,
+    pub end_line: Option<usize>,
+    /// The trimmed source line the symbol is defined on; `None` when the
+    /// index was trimmed to its budget.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub definition: Option<String>,
+}
+
+/// A case-insensitive name pattern: `*` matches any run of characters,
+/// `?` one character. A pattern without either matches names containing it.
+#[derive(Debug, Clone)]
+pub(crate) struct NamePattern {
+    /// Regex body over lowercased names, without anchors.
+    body: String,
+    regex: Regex,
+}
+
+impl NamePattern {
+    pub(crate) fn new(pattern: &str) -> Result<Self, NsError> {
+        let lowered = pattern.to_lowercase();
+        let glob = if lowered.contains(['*', '?']) {
+            lowered
+        } else {
+            format!("*{}*", lowered)
+        };
+        let body: String = glob
+            .chars()
+            .map(|c| match c {
+                '*' => ".*".to_string(),
+                '?' => ".".to_string(),
+                c => regex::escape(&c.to_string()),
+            })
+            .collect();
+        let regex = Regex::new(&format!("^{}$", body))?;
+        Ok(NamePattern { body, regex })
+    }
+
+    /// Whether `name` matches, ignoring case.
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        self.regex.is_match(&name.to_lowercase())
+    }
+
+    /// Query for documents with a `symbols` term matching the pattern.
+    fn query(&self, symbols_f: Field) -> Result<Box<dyn Query>, NsError> {
+        Ok(Box::new(RegexQuery::from_pattern(&self.body, symbols_f)?))
+    }
+}
+
+/// Every definition in the index at `root` whose name matches `pattern`
+/// (see `NamePattern`), sorted by name, then path and line.
+pub fn find_symbols(
+    root: &Path,
+    pattern: &str,
+    opts: &SymbolSearchOptions,
+) -> Result<Vec<FoundSymbol>, NsError> {
+    let pattern = NamePattern::new(pattern)?;
+    let mut found = collect_definitions(
+        root,
+        opts,
+        |symbols_f| pattern.query(symbols_f),
+        |name| pattern.matches(name),
+    )?;
+    found.sort_by(|a, b| {
+        a.name
+            .to_lowercase()
+            .cmp(&b.name.to_lowercase())
+            .then_with(|| a.path.cmp(&b.path))
+            .then_with(|| a.line.cmp(&b.line))
+    });
+    Ok(found)
+}
+
+/// Definitions of `name` (ignoring case) in the index at `root`, best
+/// first: exact spelling before other casings, declarations (see
+/// `SymbolKind::is_declaration`) before impls, constants and re-exports,
+/// then by path and line.
+///
+/// A qualified name (`EventStore::new`, `Router.register`) finds the last
+/// segment in files that define the one before it, those within the
+/// qualifier's lines first. Only the first definition of a name per file
+/// is indexed, so a Rust method usually sits in an `impl` block that isn't,
+/// and the file is what can be checked.
+pub fn find_definitions(
+    root: &Path,
+    name: &str,
+    opts: &SymbolSearchOptions,
+) -> Result<Vec<FoundSymbol>, NsError> {
+    if let Some((qualifier, name)) = split_qualified(name) {
+        let containers = find_definitions(root, qualifier, &SymbolSearchOptions {
+            kinds: Vec::new(),
+            ..opts.clone()
+        })?;
+        let mut found = find_definitions(root, name, opts)?;
+        found.retain(|s| containers.iter().any(|c| c.path == s.path));
+        found.sort_by_key(|s| !containers.iter().any(|c| c.path == s.path && encloses(c, s)));
+        return Ok(found);
+    }
+    let lowered = name.to_lowercase();
+    let mut found = collect_definitions(
+        root,
+        opts,
+        |symbols_f| {
+            Ok(Box::new(TermQuery::new(
+                Term::from_field_text(symbols_f, &lowered),
+                IndexRecordOption::Basic,
+            )))
+        },
+        |candidate| candidate.to_lowercase() == lowered,
+    )?;
+    found.sort_by_key(|s| {
+        (
+            s.name != name,
+            !s.kind.is_declaration(),
+            s.path.clone(),
+            s.line,
+        )
+    });
+    Ok(found)
+}
+
+/// The leading definitions of `found` (as sorted by `find_definitions`)
+/// that are as good as the first: the declarations spelled like the name
+/// when there are any.
+pub fn preferred<'a>(found: &'a [FoundSymbol], name: &str) -> &'a [FoundSymbol] {
+    let name = split_qualified(name).map_or(name, |(_, last)| last);
+    let class = |s: &FoundSymbol| (s.name != name, !s.kind.is_declaration());
+    let Some(best) = found.first().map(class) else {
+        return found;
+    };
+    let count = found.iter().take_while(|s| class(s) == best).count();
+    &found[..count]
+}
+
+/// `Type::method`, `Type.method` or `Type#method` split at the last
+/// separator into qualifier and name.
+fn split_qualified(name: &str) -> Option<(&str, &str)> {
+    ["::", ".", "#"]
+        .iter()
+        .filter_map(|sep| name.rfind(sep).map(|at| (&name[..at], &name[at + sep.len()..])))
+        .max_by_key(|(qualifier, _)| qualifier.len())
+        .filter(|(qualifier, name)| !qualifier.is_empty() && !name.is_empty())
+}
+
+/// Whether `inner`'s first line falls within `outer`'s lines.
+fn encloses(outer: &FoundSymbol, inner: &FoundSymbol) -> bool {
+    match (outer.line, outer.end_line, inner.line) {
+        (Some(start), Some(end), Some(line)) => start <= line && line <= end,
+        _ => false,
+    }
+}
+
+/// Definitions in files matching the `symbols` query built by `query`
+/// whose name passes `keep`, in index order.
+fn collect_definitions(
+    root: &Path,
+    opts: &SymbolSearchOptions,
+    query: impl FnOnce(Field) -> Result<Box<dyn Query>, NsError>,
+    keep: impl Fn(&str) -> bool,
+) -> Result<Vec<FoundSymbol>, NsError> {
+    let (index, _meta) = open_index(root)?;
+    let schema = index.schema();
+    let path_f = path_field(&schema);
+    let symbols_raw_f = symbols_raw_field(&schema);
+    let spans_f = symbol_spans_field(&schema);
+    let kinds_f = symbol_kinds_field(&schema);
+    let def_f = symbols_def_field(&schema);
+
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> =
+        vec![(Occur::Must, query(symbols_field(&schema))?)];
+    if let Some(ref lang) = opts.file_type {
+        clauses.push((
+            Occur::Must,
+            Box::new(TermQuery::new(
+                Term::from_field_text(lang_field(&schema), lang),
+                IndexRecordOption::Basic,
+            )),
+        ));
+    }
+    let query = BooleanQuery::new(clauses);
+    let glob = match opts.file_glob {
+        Some(ref g) => Some(glob::Pattern::new(g)?),
+        None => None,
+    };
+
+    let reader = create_reader_with_retry(&index, root)?;
+    let searcher = reader.searcher();
+    let docs = searcher.search(&query, &tantivy::collector::DocSetCollector)?;
+
+    let mut found = Vec::new();
+    for address in docs {
+        let doc: TantivyDocument = searcher.doc(address)?;
+        let Some(path) = doc.get_first(path_f).and_then(|v| v.as_str()) else {
+            continue;
+        };
+        if glob.as_ref().is_some_and(|g| !g.matches(path)) {
+            continue;
+        }
+        let names = doc.get_first(symbols_raw_f).and_then(|v| v.as_str()).unwrap_or("");
+        let spans = stored_spans(&doc, spans_f);
+        let kinds = stored_kinds(&doc, kinds_f);
+        let lines: Vec<&str> = doc
+            .get_first(def_f)
+            .and_then(|v| v.as_str())
+            .map(|s| s.split('\n').collect())
+            .unwrap_or_default();
+        for (i, name) in names.split('|').enumerate() {
+            if name.is_empty() || !keep(name) {
+                continue;
+            }
+            let kind = kinds.get(i).copied().unwrap_or(SymbolKind::Other);
+            if !opts.kinds.is_empty() && !opts.kinds.contains(&kind) {
+                continue;
+            }
+            let span = spans.get(i);
+            found.push(FoundSymbol {
+                name: name.to_string(),
+                kind,
+                path: path.to_string(),
+                line: span.map(|s| s.0),
+                end_line: span.map(|s| s.1),
+                definition: lines.get(i).map(|l| l.to_string()),
+            });
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_patterns_are_case_insensitive_globs() {
+        let prefix = NamePattern::new("Event*").unwrap();
+        assert!(prefix.matches("EventStore"));
+        assert!(prefix.matches("event_bus"));
+        assert!(!prefix.matches("handleEvent"));
+
+        let word = NamePattern::new("event").unwrap();
+        assert!(word.matches("handleEvent"), "a plain word matches anywhere");
+
+        let one = NamePattern::new("get?").unwrap();
+        assert!(one.matches("getX"));
+        assert!(!one.matches("getXY"));
+
+        let dotted = NamePattern::new("a.b*").unwrap();
+        assert!(dotted.matches("a.bc"));
+        assert!(!dotted.matches("axbc"), "other characters are literal");
+    }
+
+    #[test]
+    fn qualified_names_split_at_the_last_separator() {
+        assert_eq!(split_qualified("EventStore::new"), Some(("EventStore", "new")));
+        assert_eq!(split_qualified("a::B.c"), Some(("a::B", "c")));
+        assert_eq!(split_qualified("Router#register"), Some(("Router", "register")));
+        assert_eq!(split_qualified("EventStore"), None);
+        assert_eq!(split_qualified("::new"), None);
+    }
+
+    #[test]
+    fn preferred_keeps_the_best_class() {
+        let def = |name: &str, kind, path: &str| FoundSymbol {
+            name: name.to_string(),
+            kind,
+            path: path.to_string(),
+            line: Some(1),
+            end_line: Some(1),
+            definition: None,
+        };
+        let found = vec![
+            def("Store", SymbolKind::Type, "a.rs"),
+            def("Store", SymbolKind::Type, "b.rs"),
+            def("Store", SymbolKind::Impl, "a.rs"),
+            def("store", SymbolKind::Function, "c.rs"),
+        ];
+        assert_eq!(preferred(&found, "Store").len(), 2);
+        assert_eq!(preferred(&found[2..], "Store").len(), 1, "an impl when nothing else");
+        assert!(preferred(&[], "Store").is_empty());
+    }
+}
@@ -0,0 +1,306 @@
+//! Why a file does or doesn't rank for a query (`ns why <file> <query>`).
+//!
+//! When a file known to be relevant is missing from the results, the cause
+//! is one of a few things: it never made it into the index, none of the
+//! query's terms occur in it (tokenization splits identifiers differently
+//! than expected), an exclusion or a quoted phrase rules it out, dedupe
+//! folded it into a copy, or it matches but ranks below the page.
+//! `explain_file` checks them in that order and reports per-term matches
+//! and the file's would-be rank under the usual ranking.
+
+use std::fmt;
+use std::path::Path;
+
+use serde::Serialize;
+use tantivy::collector::DocSetCollector;
+use tantivy::query::TermQuery;
+use tantivy::schema::IndexRecordOption;
+use tantivy::Term;
+
+use crate::error::NsError;
+use crate::indexer::walker::{
+    excluded_by, in_skipped_dir, is_walked, read_text, walk_root_of, FileText, SkipKind,
+    WalkOptions,
+};
+use crate::indexer::writer::{open_index, IndexMeta};
+use crate::schema::{
+    content_field, content_stem_field, docs_field, filename_field, path_field, symbols_field,
+};
+
+use super::explain::{TermContribution, TermExplainer};
+use super::query::{
+    contains_tokens, create_reader_with_retry, execute_search, normalize_prefix, split_query_terms,
+    SearchOptions, SearchResult, DOCS_BOOST, FILENAME_BOOST, SYMBOLS_BOOST,
+};
+
+/// Why a file is not in the index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SkipReason {
+    /// No such file under the root.
+    Missing,
+    /// The path is a directory or another non-file.
+    NotAFile,
+    /// Inside `.git/` or `.ns/` (see `walker::SKIPPED_DIRS`).
+    SkippedDir,
+    /// Excluded by `.gitignore`, `.ignore` or a global git ignore.
+    Ignored,
+    /// Matches an `ns index --exclude` glob.
+    Excluded { pattern: String },
+    /// More directory levels down than `ns index --max-depth` walks.
+    TooDeep { depth: usize, max: usize },
+    /// Not under any of the directories given to `ns index --root`.
+    OutsideRoots { roots: Vec<String> },
+    /// Larger than the default `--max-file-size`.
+    TooLarge { size: u64, max: u64 },
+    /// A NUL byte near the start marks it as binary.
+    Binary,
+    /// Not valid UTF-8.
+    NotUtf8,
+    /// Dropped to fit `--max-index-size`.
+    Pruned,
+    /// Indexable, but created or un-ignored after the last index write.
+    NotYetIndexed,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Missing => write!(f, "no such file"),
+            SkipReason::NotAFile => write!(f, "not a regular file"),
+            SkipReason::SkippedDir => write!(f, "inside .git/ or .ns/, which are never indexed"),
+            SkipReason::Ignored => write!(f, "excluded by .gitignore or another ignore file"),
+            SkipReason::Excluded { pattern } => write!(f, "excluded by --exclude '{}'", pattern),
+            SkipReason::OutsideRoots { roots } => {
+                write!(f, "outside the indexed directories ({})", roots.join(", "))
+            }
+            SkipReason::TooDeep { depth, max } => {
+                write!(f, "{} levels below the root, deeper than --max-depth {}", depth, max)
+            }
+            SkipReason::TooLarge { size, max } => write!(
+                f,
+                "{} bytes, over the {}-byte default --max-file-size",
+                size, max
+            ),
+            SkipReason::Binary => write!(f, "looks binary (NUL byte in the first 512 bytes)"),
+            SkipReason::NotUtf8 => write!(f, "not valid UTF-8"),
+            SkipReason::Pruned => write!(f, "pruned to fit --max-index-size"),
+            SkipReason::NotYetIndexed => {
+                write!(f, "added after the last index write — run 'ns index'")
+            }
+        }
+    }
+}
+
+/// The outcome for the file, most decisive first.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "verdict", rename_all = "snake_case")]
+pub enum Verdict {
+    /// The file is not in the index.
+    NotIndexed { reason: SkipReason },
+    /// It contains one of the query's `-term` exclusions.
+    Excluded,
+    /// None of the query's terms occur in it.
+    NoMatch,
+    /// Some terms occur in it, but it doesn't match the query as a whole
+    /// (a quoted phrase is missing).
+    Partial,
+    /// Dedupe folded it into the result for `into`, an identical copy, or a
+    /// near-identical one when `identical` is false.
+    Collapsed { into: String, identical: bool },
+    /// It ranks `rank`-th of `total` matching files.
+    Ranked { rank: usize, total: usize },
+}
+
+/// One query term in one field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TermMatch {
+    /// Index field name (`content`, `content_stem`, `symbols`).
+    pub field: String,
+    /// The term as indexed, after tokenization.
+    pub term: String,
+    /// BM25 score of the term in the file, including the field boost;
+    /// `None` when the file doesn't contain it.
+    pub score: Option<f32>,
+}
+
+/// Everything `ns why` reports.
+#[derive(Debug, Serialize)]
+pub struct WhyReport {
+    /// The file, relative to the root as stored in the index.
+    pub path: String,
+    #[serde(flatten)]
+    pub verdict: Verdict,
+    /// Each query term in each searched field. Empty when not indexed.
+    pub terms: Vec<TermMatch>,
+    /// Exclusions (`-term`) the file contains, as the words they match.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub excluded_by: Vec<String>,
+    /// Results a search shows by default (`opts.max_results`).
+    pub page_size: usize,
+    /// The file's search result when it ranks, for its score and signals.
+    #[serde(skip)]
+    pub result: Option<SearchResult>,
+}
+
+/// Explains how `file` (relative to `root`, or absolute inside it) fares
+/// for `query` under the ranking `opts` describe.
+///
+/// `opts` should be what a plain search would use (defaults plus
+/// `.ns/config.toml`); the result limit and cost guard are lifted so every
+/// matching file gets a rank.
+pub fn explain_file(
+    root: &Path,
+    file: &str,
+    query: &str,
+    opts: &SearchOptions,
+) -> Result<WhyReport, NsError> {
+    let path = normalize_prefix(root, file);
+    let (index, meta) = open_index(root)?;
+    let schema = index.schema();
+    let path_f = path_field(&schema);
+    let content = content_field(&schema);
+    let reader = create_reader_with_retry(&index, root)?;
+    let searcher = reader.searcher();
+
+    let mut report = WhyReport {
+        path: path.clone(),
+        verdict: Verdict::NoMatch,
+        terms: Vec::new(),
+        excluded_by: Vec::new(),
+        page_size: opts.max_results,
+        result: None,
+    };
+
+    let lookup = TermQuery::new(
+        Term::from_field_text(path_f, &path),
+        IndexRecordOption::Basic,
+    );
+    let addresses = searcher.search(&lookup, &DocSetCollector)?;
+    if addresses.is_empty() {
+        report.verdict = Verdict::NotIndexed {
+            reason: skip_reason(root, &path, &meta),
+        };
+        return Ok(report);
+    }
+
+    let (words, excluded) = split_query_terms(query, meta.cjk);
+    let mut fields = vec![(symbols_field(&schema), SYMBOLS_BOOST), (content, 1.0)];
+    if opts.stem && meta.stem {
+        fields.push((content_stem_field(&schema), 1.0));
+    }
+    fields.push((docs_field(&schema), DOCS_BOOST));
+    fields.push((filename_field(&schema), FILENAME_BOOST));
+    let explainer = TermExplainer::new(&index, &searcher, &words, &fields)?;
+    // A file chunked by `ns index --chunk-lines` is explained by its
+    // best-scoring chunk, the one search would show.
+    let total = |contributions: &[TermContribution]| -> f32 {
+        contributions.iter().map(|c| c.score).sum()
+    };
+    let (address, contributions) = addresses
+        .into_iter()
+        .map(|address| (address, explainer.explain(address)))
+        .max_by(|a, b| total(&a.1).total_cmp(&total(&b.1)))
+        .expect("the file has at least one document");
+    report.terms = explainer
+        .terms()
+        .map(|(field, term)| TermMatch {
+            field: field.to_string(),
+            term: term.to_string(),
+            score: contributions
+                .iter()
+                .find(|c| c.field == field && c.term == term)
+                .map(|c| c.score),
+        })
+        .collect();
+    report.excluded_by = excluded
+        .iter()
+        .filter(|tokens| contains_tokens(&searcher, content, tokens, address))
+        .map(|tokens| tokens.join(" "))
+        .collect();
+
+    let ranked_opts = SearchOptions {
+        max_results: (searcher.num_docs() as usize).max(1),
+        max_results_ceiling: Some(0),
+        force: true,
+        ..opts.clone()
+    };
+    let (results, _) = execute_search(root, query, &ranked_opts)?;
+    let total = results.len();
+    report.verdict = if !report.excluded_by.is_empty() {
+        Verdict::Excluded
+    } else if let Some(i) = results.iter().position(|r| r.path == path) {
+        Verdict::Ranked { rank: i + 1, total }
+    } else if let Some(r) = results.iter().find(|r| r.alternates.contains(&path)) {
+        Verdict::Collapsed {
+            into: r.path.clone(),
+            identical: true,
+        }
+    } else if let Some(r) = results
+        .iter()
+        .find(|r| r.duplicates.iter().any(|d| d.path == path))
+    {
+        Verdict::Collapsed {
+            into: r.path.clone(),
+            identical: false,
+        }
+    } else if report.terms.iter().any(|t| t.score.is_some()) {
+        Verdict::Partial
+    } else {
+        Verdict::NoMatch
+    };
+    if matches!(report.verdict, Verdict::Ranked { .. }) {
+        report.result = results.into_iter().find(|r| r.path == path);
+    }
+    Ok(report)
+}
+
+/// Why the file at `path` is not in the index described by `meta`.
+fn skip_reason(root: &Path, path: &str, meta: &IndexMeta) -> SkipReason {
+    let full = root.join(path);
+    let Ok(metadata) = std::fs::metadata(&full) else {
+        return SkipReason::Missing;
+    };
+    if !metadata.is_file() {
+        return SkipReason::NotAFile;
+    }
+    if in_skipped_dir(path) {
+        return SkipReason::SkippedDir;
+    }
+    if meta.pruned_paths.iter().any(|p| p == path) {
+        return SkipReason::Pruned;
+    }
+    if let Some(pattern) = excluded_by(path, &meta.exclude) {
+        return SkipReason::Excluded {
+            pattern: pattern.to_string(),
+        };
+    }
+    let Some(walk_root) = walk_root_of(path, &meta.roots) else {
+        return SkipReason::OutsideRoots {
+            roots: meta.roots.clone(),
+        };
+    };
+    let levels = |p: &str| p.split(['/', '\\']).filter(|c| !c.is_empty()).count();
+    let depth = levels(path) - levels(walk_root);
+    if let Some(max) = meta.max_depth.filter(|&max| depth > max) {
+        return SkipReason::TooDeep { depth, max };
+    }
+    let walk = WalkOptions {
+        follow_symlinks: meta.follow_symlinks,
+        roots: meta.roots.clone(),
+        truncate_to: meta.truncate_large_kb.map(|kb| kb * 1024),
+        ..WalkOptions::default()
+    };
+    if !is_walked(root, path, &walk) {
+        return SkipReason::Ignored;
+    }
+    match read_text(&full, metadata.len(), &walk) {
+        Ok(FileText::Skipped(SkipKind::TooLarge)) => SkipReason::TooLarge {
+            size: metadata.len(),
+            max: walk.max_file_size,
+        },
+        Ok(FileText::Skipped(SkipKind::Binary)) => SkipReason::Binary,
+        Ok(FileText::Skipped(SkipKind::NonUtf8)) => SkipReason::NotUtf8,
+        _ => SkipReason::NotYetIndexed,
+    }
+}
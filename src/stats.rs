@@ -1,6 +1,8 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::path::Path;
+use std::str::FromStr;
 
 use fs4::fs_std::FileExt;
 use serde::{Deserialize, Serialize};
@@ -176,7 +178,7 @@ pub struct SearchLogEntry {
     pub error: Option<SearchLogError>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum SearchOutcome {
     Success,
@@ -184,7 +186,33 @@ pub enum SearchOutcome {
     Error,
 }
 
-#[derive(Serialize)]
+impl SearchOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchOutcome::Success => "success",
+            SearchOutcome::NoResults => "no_results",
+            SearchOutcome::Error => "error",
+        }
+    }
+}
+
+impl FromStr for SearchOutcome {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "success" => Ok(SearchOutcome::Success),
+            "no_results" => Ok(SearchOutcome::NoResults),
+            "error" => Ok(SearchOutcome::Error),
+            other => Err(format!(
+                "unknown outcome '{}': use success, no_results or error",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
 pub struct SearchLogFlags {
     pub file_type: Option<String>,
     pub file_glob: Option<String>,
@@ -198,6 +226,34 @@ pub struct SearchLogFlags {
     pub max_context_lines: usize,
     pub budget: Option<usize>,
     pub spans: bool,
+    pub no_dedupe: bool,
+    pub json_compact: bool,
+    pub regex: bool,
+    pub no_verify: bool,
+    pub context_from_git: bool,
+    pub quick: bool,
+    pub deep: bool,
+    pub max_count_hard: Option<usize>,
+    pub substring: bool,
+    pub stem: bool,
+    pub sample: Option<usize>,
+    pub force: bool,
+    pub all_terms: bool,
+    pub paths: Vec<String>,
+    pub tests: bool,
+    pub no_route: bool,
+    pub explain: bool,
+    pub no_index: bool,
+    pub semantic: bool,
+    pub max_per_dir: Option<usize>,
+    pub context_scope: Option<String>,
+    pub budget_model: Option<String>,
+    pub summary: bool,
+    pub granularity: Option<String>,
+    pub kind: Vec<String>,
+    pub larger_than: Option<u64>,
+    pub smaller_than: Option<u64>,
+    pub modified_since: Option<u64>,
 }
 
 #[derive(Serialize)]
@@ -251,6 +307,232 @@ pub fn format_token_count(tokens: u64) -> String {
     }
 }
 
+/// How `summarize_search_log` groups searches over time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogPeriod {
+    /// By UTC day (`2026-02-13`).
+    #[default]
+    Day,
+    /// By UTC month (`2026-02`).
+    Month,
+}
+
+impl FromStr for LogPeriod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "day" => Ok(LogPeriod::Day),
+            "month" => Ok(LogPeriod::Month),
+            other => Err(format!("unknown period '{}': use day or month", other)),
+        }
+    }
+}
+
+/// Searches in one period of `SearchLogSummary::over_time`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PeriodCount {
+    /// `YYYY-MM-DD` or `YYYY-MM`.
+    pub period: String,
+    pub searches: u64,
+    pub no_results: u64,
+    pub tokens: u64,
+}
+
+/// How often one query was run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueryCount {
+    pub query: String,
+    pub count: u64,
+}
+
+/// Aggregates of `.ns/search_log.jsonl`, for `ns stats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SearchLogSummary {
+    pub searches: u64,
+    pub successes: u64,
+    pub no_results: u64,
+    pub errors: u64,
+    /// Estimated tokens of output over all searches.
+    pub tokens: u64,
+    pub first_at: Option<String>,
+    pub last_at: Option<String>,
+    /// Oldest period first; periods without searches are left out.
+    pub over_time: Vec<PeriodCount>,
+    /// Most run queries, most first, then by query.
+    pub top_queries: Vec<QueryCount>,
+    /// Most run queries that found nothing, counting only those runs.
+    pub zero_result_queries: Vec<QueryCount>,
+    /// Failed searches by `SearchLogError::code`.
+    pub errors_by_code: BTreeMap<String, u64>,
+}
+
+/// One line of `.ns/search_log.jsonl`, as read back by `ns stats` and
+/// `ns log`. Fields missing from older entries are empty.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LoggedSearch {
+    #[serde(default)]
+    pub ts: String,
+    #[serde(default)]
+    pub query: String,
+    #[serde(default)]
+    pub tokens: u64,
+    #[serde(default)]
+    pub files: u64,
+    #[serde(default)]
+    outcome: Option<String>,
+    #[serde(default)]
+    pub error: Option<LoggedError>,
+    #[serde(default)]
+    pub argv: Vec<String>,
+    /// The line as logged.
+    #[serde(skip)]
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LoggedError {
+    #[serde(default)]
+    pub code: String,
+    #[serde(default)]
+    pub message: String,
+}
+
+impl LoggedSearch {
+    pub fn outcome(&self) -> SearchOutcome {
+        // Legacy log entries (v0.1.5) had no outcome field and were success-only.
+        match self.outcome.as_deref() {
+            Some("success") | None => SearchOutcome::Success,
+            Some("no_results") => SearchOutcome::NoResults,
+            Some(_) => SearchOutcome::Error,
+        }
+    }
+}
+
+/// Which logged searches `read_search_log` returns.
+#[derive(Debug, Clone, Default)]
+pub struct SearchLogFilter {
+    /// At or after this UTC timestamp (`YYYY-MM-DDTHH:MM:SSZ`).
+    pub since: Option<String>,
+    pub outcome: Option<SearchOutcome>,
+    /// Queries containing this, ignoring case.
+    pub query_contains: Option<String>,
+}
+
+impl SearchLogFilter {
+    fn matches(&self, search: &LoggedSearch) -> bool {
+        self.since.as_ref().is_none_or(|since| search.ts >= *since)
+            && self.outcome.is_none_or(|outcome| search.outcome() == outcome)
+            && self.query_contains.as_ref().is_none_or(|needle| {
+                search.query.to_lowercase().contains(&needle.to_lowercase())
+            })
+    }
+}
+
+/// The searches in `.ns/search_log.jsonl` that pass `filter`, oldest first.
+/// `None` when there is no log; lines that don't parse are skipped.
+pub fn read_search_log(root: &Path, filter: &SearchLogFilter) -> Option<Vec<LoggedSearch>> {
+    let content = fs::read_to_string(root.join(".ns").join("search_log.jsonl")).ok()?;
+    let searches = content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .filter_map(|line| {
+            let mut search = serde_json::from_str::<LoggedSearch>(line).ok()?;
+            search.line = line.to_string();
+            Some(search)
+        })
+        .filter(|search| filter.matches(search))
+        .collect();
+    Some(searches)
+}
+
+/// Summarizes the searches logged in `.ns/search_log.jsonl` at or after the
+/// UTC timestamp `since` (`YYYY-MM-DDTHH:MM:SSZ`), keeping the `top` most
+/// run queries in each list. `None` when there is no log; lines that don't
+/// parse are skipped.
+pub fn summarize_search_log(
+    root: &Path,
+    since: Option<&str>,
+    by: LogPeriod,
+    top: usize,
+) -> Option<SearchLogSummary> {
+    let filter = SearchLogFilter {
+        since: since.map(str::to_string),
+        ..SearchLogFilter::default()
+    };
+    let searches = read_search_log(root, &filter)?;
+    let mut summary = SearchLogSummary::default();
+    let mut periods: BTreeMap<String, PeriodCount> = BTreeMap::new();
+    let mut queries: HashMap<String, u64> = HashMap::new();
+    let mut zero_results: HashMap<String, u64> = HashMap::new();
+
+    for entry in searches {
+        let ts = entry.ts.as_str();
+        let tokens = entry.tokens;
+        summary.searches += 1;
+        summary.tokens = summary.tokens.saturating_add(tokens);
+        if !ts.is_empty() {
+            if summary.first_at.as_deref().is_none_or(|first| ts < first) {
+                summary.first_at = Some(ts.to_string());
+            }
+            if summary.last_at.as_deref().is_none_or(|last| ts > last) {
+                summary.last_at = Some(ts.to_string());
+            }
+        }
+
+        let period_len = match by {
+            LogPeriod::Day => 10,
+            LogPeriod::Month => 7,
+        };
+        let period = ts.get(..period_len).unwrap_or("unknown").to_string();
+        let count = periods.entry(period.clone()).or_insert_with(|| PeriodCount {
+            period,
+            searches: 0,
+            no_results: 0,
+            tokens: 0,
+        });
+        count.searches += 1;
+        count.tokens = count.tokens.saturating_add(tokens);
+
+        let query = entry.query.trim().to_string();
+        match entry.outcome() {
+            SearchOutcome::Success => summary.successes += 1,
+            SearchOutcome::NoResults => {
+                summary.no_results += 1;
+                count.no_results += 1;
+                *zero_results.entry(query.clone()).or_default() += 1;
+            }
+            SearchOutcome::Error => {
+                summary.errors += 1;
+                let code = entry
+                    .error
+                    .as_ref()
+                    .map_or_else(|| "unknown".to_string(), |e| e.code.clone());
+                *summary.errors_by_code.entry(code).or_default() += 1;
+            }
+        }
+        *queries.entry(query).or_default() += 1;
+    }
+
+    summary.over_time = periods.into_values().collect();
+    summary.top_queries = most_run(queries, top);
+    summary.zero_result_queries = most_run(zero_results, top);
+    Some(summary)
+}
+
+/// The `top` entries of `counts` with the highest count, ties by query.
+fn most_run(counts: HashMap<String, u64>, top: usize) -> Vec<QueryCount> {
+    let mut counts: Vec<QueryCount> = counts
+        .into_iter()
+        .filter(|(query, _)| !query.is_empty())
+        .map(|(query, count)| QueryCount { query, count })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.query.cmp(&b.query)));
+    counts.truncate(top);
+    counts
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -331,6 +613,7 @@ mod tests {
                 max_context_lines: 30,
                 budget: None,
                 spans: false,
+                ..Default::default()
             },
             argv: vec!["--".to_string(), "EventStore".to_string()],
             error: None,
@@ -361,6 +644,7 @@ mod tests {
                 max_context_lines: 10,
                 budget: Some(500),
                 spans: false,
+                ..Default::default()
             },
             argv: vec![
                 "--json".to_string(),
@@ -426,6 +710,7 @@ mod tests {
                 max_context_lines: 30,
                 budget: None,
                 spans: false,
+                ..Default::default()
             },
             argv: vec!["EventStore".to_string()],
             error: Some(SearchLogError {
@@ -479,6 +764,7 @@ mod tests {
                                 max_context_lines: 30,
                                 budget: None,
                                 spans: false,
+                                ..Default::default()
                             },
                             argv: vec![
                                 "-l".to_string(),
@@ -705,4 +991,44 @@ mod tests {
         assert_eq!(stats.total_estimated_tokens, expected * 10);
         assert_eq!(stats.total_output_chars, expected * 40);
     }
+
+    #[test]
+    fn search_log_summary_counts_outcomes_queries_and_days() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join(".ns")).unwrap();
+        let lines = [
+            r#"{"ts":"2026-02-12T09:00:00Z","query":"old","tokens":5}"#,
+            r#"{"ts":"2026-02-13T10:00:00Z","query":"EventStore","tokens":80,"outcome":"success"}"#,
+            r#"{"ts":"2026-02-13T11:00:00Z","query":"EventStore ","tokens":20,"outcome":"success"}"#,
+            r#"{"ts":"2026-02-13T12:00:00Z","query":"nothing here","tokens":0,"outcome":"no_results"}"#,
+            "not json",
+            r#"{"ts":"2026-02-14T08:00:00Z","query":"x","tokens":0,"outcome":"error","error":{"code":"no_index","message":"m"}}"#,
+        ];
+        fs::write(root.join(".ns/search_log.jsonl"), lines.join("\n")).unwrap();
+
+        let summary =
+            summarize_search_log(root, Some("2026-02-13T00:00:00Z"), LogPeriod::Day, 10).unwrap();
+        assert_eq!(
+            (summary.searches, summary.successes, summary.no_results, summary.errors),
+            (4, 2, 1, 1)
+        );
+        assert_eq!(summary.tokens, 100);
+        assert_eq!(summary.first_at.as_deref(), Some("2026-02-13T10:00:00Z"));
+        assert_eq!(summary.last_at.as_deref(), Some("2026-02-14T08:00:00Z"));
+        let days: Vec<(&str, u64)> = summary
+            .over_time
+            .iter()
+            .map(|p| (p.period.as_str(), p.searches))
+            .collect();
+        assert_eq!(days, vec![("2026-02-13", 3), ("2026-02-14", 1)]);
+        assert_eq!(summary.top_queries[0], QueryCount { query: "EventStore".to_string(), count: 2 });
+        assert_eq!(summary.zero_result_queries.len(), 1);
+        assert_eq!(summary.errors_by_code["no_index"], 1);
+
+        let months = summarize_search_log(root, None, LogPeriod::Month, 1).unwrap();
+        assert_eq!(months.over_time.len(), 1);
+        assert_eq!(months.top_queries.len(), 1);
+        assert!(summarize_search_log(dir.path().join("none").as_path(), None, LogPeriod::Day, 10).is_none());
+    }
 }
@@ -1,5 +1,7 @@
 use std::path::{Path, PathBuf};
 
+use ns::indexer::IndexOptions;
+
 /// Path to the source fixture repo (read-only — never write into this).
 pub fn fixture_source() -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample_repo")
@@ -31,6 +33,6 @@ pub fn isolated_fixture() -> (tempfile::TempDir, PathBuf) {
 /// Creates an isolated copy of the fixture repo and indexes it.
 pub fn indexed_fixture() -> (tempfile::TempDir, PathBuf) {
     let (tmp, root) = isolated_fixture();
-    ns::indexer::run_full_index(&root, 1_048_576).expect("indexing should succeed");
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
     (tmp, root)
 }
@@ -0,0 +1,101 @@
+mod common;
+
+#[test]
+fn scip_export_covers_all_documents() {
+    let (_tmp, root) = common::indexed_fixture();
+    let meta = ns::indexer::writer::read_meta(&root).expect("should read meta.json");
+
+    let mut out = Vec::new();
+    let stats = ns::export::scip::export_scip(&root, &mut out).expect("export should succeed");
+
+    assert_eq!(stats.documents, meta.file_count);
+    assert!(stats.symbols > 0, "fixture should have symbols");
+    assert!(stats.occurrences > 0, "definitions should be located");
+    assert_eq!(stats.bytes_written, out.len());
+
+    // Index.metadata is field 1, length-delimited.
+    assert_eq!(out[0], 0x0a);
+    let symbol = ns::export::scip::symbol_string("src/event_store.rs", "EventStore");
+    let haystack = String::from_utf8_lossy(&out);
+    assert!(haystack.contains(&symbol), "expected symbol {}", symbol);
+}
+
+#[test]
+fn ctags_export_lists_sorted_definitions() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    let mut out = Vec::new();
+    let stats = ns::export::ctags::export_ctags(&root, &mut out).expect("export should succeed");
+    assert!(stats.tags > 0 && stats.files > 0);
+    assert_eq!(stats.bytes_written, out.len());
+
+    let text = String::from_utf8(out).unwrap();
+    let tags: Vec<&str> = text.lines().filter(|l| !l.starts_with("!_TAG_")).collect();
+    assert_eq!(tags.len(), stats.tags);
+    let mut sorted = tags.clone();
+    sorted.sort();
+    assert_eq!(tags, sorted, "editors binary-search the file by name");
+
+    let source = std::fs::read_to_string(root.join("src/event_store.rs")).unwrap();
+    let tag = tags
+        .iter()
+        .find(|t| t.starts_with("EventStore\tsrc/event_store.rs\t"))
+        .expect("EventStore should be tagged");
+    let fields: Vec<&str> = tag.split('\t').collect();
+    assert_eq!(fields[3], "kind:type");
+    let line: usize = fields[2].trim_end_matches(";\"").parse().unwrap();
+    assert!(source.lines().nth(line - 1).unwrap().contains("struct EventStore"));
+}
+
+#[test]
+fn scip_export_without_index_fails() {
+    let (_tmp, root) = common::isolated_fixture();
+
+    let mut out = Vec::new();
+    assert!(ns::export::scip::export_scip(&root, &mut out).is_err());
+    assert!(out.is_empty());
+}
+
+#[test]
+fn bundle_export_is_searchable_without_ns_dir() {
+    use ns::searcher::query::SearchOptions;
+    use ns::searcher::OutputMode;
+
+    let (_tmp, root) = common::indexed_fixture();
+    let bundle_dir = tempfile::tempdir().unwrap();
+    let bundle = bundle_dir.path().join("index.nsb");
+
+    let mut out = Vec::new();
+    let stats = ns::indexer::bundle::write_bundle(&root, &mut out).expect("bundle should be written");
+    assert!(stats.files > 0);
+    assert_eq!(stats.bytes_written, out.len() as u64);
+    std::fs::write(&bundle, &out).unwrap();
+
+    // Search from a directory that has no .ns/ at all.
+    let so = ns::searcher::search(
+        bundle_dir.path(),
+        "EventStore",
+        OutputMode::Json,
+        &SearchOptions {
+            max_results: 5,
+            index_file: Some(bundle.clone()),
+            verify: false,
+            ..Default::default()
+        },
+    )
+    .expect("search over bundle should work");
+
+    assert!(so.formatted.contains("src/event_store.rs"));
+    assert!(!bundle_dir.path().join(".ns").exists());
+
+    // Line matching reads the source files, which a bundle doesn't carry.
+    for mode in ["--regex", "--substring"] {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_ns"))
+            .args(["--index-file", bundle.to_str().unwrap(), mode, "EventStore"])
+            .current_dir(bundle_dir.path())
+            .output()
+            .expect("should run ns");
+        assert!(!output.status.success(), "{} with a bundle is rejected", mode);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+    }
+}
@@ -409,3 +409,36 @@ fn install_remove_install_roundtrip_no_blank_accumulation() {
         content
     );
 }
+
+#[test]
+fn install_follows_hooks_config() {
+    let (_tmp, root) = git_repo();
+    let run = |action: &str| {
+        let output = std::process::Command::new(ns_binary())
+            .args(["hooks", action])
+            .current_dir(&root)
+            .output()
+            .expect("should run ns");
+        assert!(output.status.success(), "ns hooks {} should exit 0", action);
+        String::from_utf8_lossy(&output.stderr).to_string()
+    };
+    run("install");
+
+    fs::create_dir_all(root.join(".ns")).expect("mkdir .ns");
+    fs::write(
+        root.join(".ns/config.toml"),
+        "[hooks]\nevents = [\"post-commit\"]\nbackground = false\n",
+    )
+    .expect("write config");
+    let stderr = run("install");
+    assert!(stderr.contains("updated ns hook in post-commit"), "stderr: {}", stderr);
+    assert!(!stderr.contains("post-merge"), "stderr: {}", stderr);
+    let content = fs::read_to_string(root.join(".git/hooks/post-commit")).expect("read");
+    assert!(content.ends_with("\nns index --incremental\n"), "content: {:?}", content);
+
+    // Removal still covers hooks installed under the old config.
+    run("remove");
+    for &hook in HOOK_NAMES {
+        assert!(!root.join(".git/hooks").join(hook).exists(), "{} should be removed", hook);
+    }
+}
@@ -1,5 +1,6 @@
 mod common;
 
+use ns::indexer::IndexOptions;
 use ns::searcher::query::SearchOptions;
 use std::fs;
 use std::thread;
@@ -13,16 +14,13 @@ fn opts(max: usize) -> SearchOptions {
     }
 }
 
-// ── Mtime-based tests (no git repo) ─────────────────────────────────────────
+// ── Content-hash tests (no git repo) ────────────────────────────────────────
 
 #[test]
 fn incremental_no_changes_is_noop() {
     let (_tmp, root) = common::indexed_fixture();
 
-    // Small delay so mtime comparison works
-    thread::sleep(Duration::from_millis(50));
-
-    let stats = ns::indexer::run_incremental_index(&root, 1_048_576)
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental should succeed");
 
     assert_eq!(stats.added, 0);
@@ -34,17 +32,14 @@ fn incremental_no_changes_is_noop() {
 fn incremental_without_index_fails() {
     let (_tmp, root) = common::isolated_fixture();
 
-    let result = ns::indexer::run_incremental_index(&root, 1_048_576);
+    let result = ns::indexer::run_incremental_index(&root, &IndexOptions::default());
     assert!(result.is_err(), "incremental without existing index should fail");
 }
 
 #[test]
-fn incremental_detects_added_file_mtime() {
+fn incremental_detects_added_file() {
     let (_tmp, root) = common::indexed_fixture();
 
-    // Wait a moment so the new file has a later mtime than indexed_at
-    thread::sleep(Duration::from_secs(1));
-
     // Add a new Rust file
     let new_file = root.join("src").join("new_module.rs");
     fs::write(
@@ -53,7 +48,7 @@ fn incremental_detects_added_file_mtime() {
     )
     .expect("should write new file");
 
-    let stats = ns::indexer::run_incremental_index(&root, 1_048_576)
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental should succeed");
 
     assert!(stats.added >= 1, "should detect at least 1 added file, got {}", stats.added);
@@ -70,11 +65,38 @@ fn incremental_detects_added_file_mtime() {
 }
 
 #[test]
-fn incremental_detects_modified_file_mtime() {
+fn symbol_stats_follow_index_writes() {
     let (_tmp, root) = common::indexed_fixture();
+    let before = ns::indexer::writer::read_meta(&root)
+        .unwrap()
+        .symbol_stats
+        .expect("full index should collect symbol stats");
+    assert!(before.total >= before.distinct && before.distinct > 0);
+    assert!(before.by_kind.get("type").is_some_and(|&n| n > 0));
+    assert!(before.duplicates.iter().all(|d| d.files > 1));
+    fs::write(
+        root.join("src").join("extra.rs"),
+        "pub struct ExtraThing;\npub const EXTRA_LIMIT: usize = 3;\n",
+    )
+    .unwrap();
+    ns::indexer::run_incremental_index(&root, &IndexOptions::default())
+        .expect("incremental should succeed");
 
-    // Wait so mtime changes
-    thread::sleep(Duration::from_secs(1));
+    let after = ns::indexer::writer::read_meta(&root)
+        .unwrap()
+        .symbol_stats
+        .expect("incremental index should keep symbol stats");
+    assert_eq!(after.total, before.total + 2);
+    assert_eq!(after.files_with_symbols, before.files_with_symbols + 1);
+    assert_eq!(
+        after.by_kind.get("constant").copied().unwrap_or(0),
+        before.by_kind.get("constant").copied().unwrap_or(0) + 1
+    );
+}
+
+#[test]
+fn incremental_detects_modified_file() {
+    let (_tmp, root) = common::indexed_fixture();
 
     // Modify an existing file — add a new struct
     let file_path = root.join("src").join("event_store.rs");
@@ -82,7 +104,7 @@ fn incremental_detects_modified_file_mtime() {
     content.push_str("\npub struct IncrementalTestMarker;\n");
     fs::write(&file_path, &content).expect("should write modified file");
 
-    let stats = ns::indexer::run_incremental_index(&root, 1_048_576)
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental should succeed");
 
     assert!(
@@ -103,7 +125,29 @@ fn incremental_detects_modified_file_mtime() {
 }
 
 #[test]
-fn incremental_detects_deleted_file_mtime() {
+fn incremental_detects_same_second_edit_of_same_size() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    // Right after indexing, without waiting for the clock to tick: only the
+    // content tells the files apart.
+    let file_path = root.join("src").join("validator.rs");
+    let content = fs::read_to_string(&file_path).expect("should read file");
+    let edited = content.replacen("validate", "vALIDATE", 1);
+    assert_eq!(edited.len(), content.len());
+    fs::write(&file_path, &edited).expect("should write edited file");
+    // Touching a file without changing it is not a change.
+    let untouched = root.join("src").join("event_store.rs");
+    fs::write(&untouched, fs::read(&untouched).unwrap()).unwrap();
+
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
+        .expect("incremental should succeed");
+
+    assert_eq!(stats.modified_paths, vec!["src/validator.rs"]);
+    assert_eq!((stats.added, stats.deleted), (0, 0));
+}
+
+#[test]
+fn incremental_detects_deleted_file() {
     let (_tmp, root) = common::indexed_fixture();
 
     // Verify utils.js is searchable before deletion
@@ -118,7 +162,7 @@ fn incremental_detects_deleted_file_mtime() {
     // Delete a file
     fs::remove_file(root.join("src").join("utils.js")).expect("should delete file");
 
-    let stats = ns::indexer::run_incremental_index(&root, 1_048_576)
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental should succeed");
 
     assert!(
@@ -139,19 +183,288 @@ fn incremental_detects_deleted_file_mtime() {
     );
 }
 
+#[test]
+fn list_docs_shows_tombstones_until_merge() {
+    let (_tmp, root) = common::indexed_fixture();
+    fs::remove_file(root.join("src").join("utils.js")).expect("should delete file");
+    ns::indexer::run_incremental_index(&root, &IndexOptions::default())
+        .expect("incremental should succeed");
+
+    let (index, _) = ns::indexer::writer::open_index(&root).expect("index should open");
+    let listing = ns::indexer::listing::list_docs(&index, Some("src/"))
+        .expect("listing should succeed");
+
+    assert!(listing.docs.iter().all(|d| d.path.starts_with("src/")));
+    let utils: Vec<_> = listing.docs.iter().filter(|d| d.path == "src/utils.js").collect();
+    assert!(
+        utils.iter().all(|d| d.deleted),
+        "deleted file should only appear as a tombstone: {:?}",
+        utils
+    );
+    let deleted: u32 = listing.segments.iter().map(|s| s.deleted).sum();
+    assert_eq!(deleted as usize, utils.len());
+}
+
+#[test]
+fn optimize_merges_segments_and_drops_tombstones() {
+    let (_tmp, root) = common::indexed_fixture();
+    fs::remove_file(root.join("src").join("utils.js")).expect("should delete file");
+    ns::indexer::run_incremental_index(&root, &IndexOptions::default())
+        .expect("incremental should succeed");
+    fs::write(root.join("src").join("extra.rs"), "fn optimize_marker() {}\n").unwrap();
+    ns::indexer::run_incremental_index(&root, &IndexOptions::default())
+        .expect("incremental should succeed");
+
+    let stats = ns::indexer::optimize::optimize_index(&root, &IndexOptions::default()).expect("optimize should succeed");
+    assert!(stats.segments_before > 1, "{:?}", stats);
+    assert_eq!(stats.segments_after, 1);
+    assert!(stats.deleted_docs > 0);
+
+    let (index, meta) = ns::indexer::writer::open_index(&root).expect("index should open");
+    let listing = ns::indexer::listing::list_docs(&index, None).expect("listing should succeed");
+    assert!(listing.docs.iter().all(|d| !d.deleted && d.path != "src/utils.js"));
+    assert_eq!(meta.index_size_bytes, stats.bytes_after);
+
+    let (results, _) = ns::searcher::query::execute_search(&root, "optimize_marker", &opts(10))
+        .expect("search should work");
+    assert_eq!(results.len(), 1);
+}
+
+#[test]
+fn check_reports_orphans_then_damaged_segments() {
+    let (_tmp, root) = common::indexed_fixture();
+    let check = |root: &std::path::Path| ns::indexer::integrity::check_index(root).expect("check should run");
+    let report = check(&root);
+    assert!(report.is_ok(), "{:?}", report);
+    assert_eq!(report.indexed_files, report.meta_file_count);
+
+    fs::remove_file(root.join("src").join("utils.js")).expect("should delete file");
+    let report = check(&root);
+    assert_eq!(report.orphaned, vec!["src/utils.js"]);
+    assert!(!report.needs_rebuild());
+    ns::indexer::run_incremental_index(&root, &IndexOptions::default())
+        .expect("incremental should succeed");
+    assert!(check(&root).is_ok(), "deletes leave tombstones, not orphans");
+
+    let store = fs::read_dir(root.join(".ns").join("index"))
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|ext| ext == "store"))
+        .expect("a segment store");
+    fs::remove_file(&store).unwrap();
+    let report = check(&root);
+    assert!(report.needs_rebuild());
+    assert_eq!(report.missing_files.len(), 1);
+
+    let out = std::process::Command::new(env!("CARGO_BIN_EXE_ns"))
+        .args(["index", "--check"])
+        .current_dir(&root)
+        .output()
+        .expect("ns should run");
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("Run 'ns index' to rebuild"), "{}", stderr);
+}
+
+#[test]
+fn exclude_globs_are_remembered_by_incremental_runs() {
+    let (_tmp, root) = common::isolated_fixture();
+    let live_paths = |root: &std::path::Path| -> Vec<String> {
+        let (index, _) = ns::indexer::writer::open_index(root).expect("index should open");
+        let listing = ns::indexer::listing::list_docs(&index, None).expect("listing should succeed");
+        listing.docs.into_iter().filter(|d| !d.deleted).map(|d| d.path).collect()
+    };
+    let exclude = IndexOptions {
+        exclude: vec!["*.py".to_string(), "src/utils.js".to_string()],
+        ..Default::default()
+    };
+    ns::indexer::run_full_index(&root, &exclude).expect("indexing should succeed");
+    let paths = live_paths(&root);
+    assert!(paths.contains(&"src/event_store.rs".to_string()));
+    assert!(!paths.contains(&"src/models.py".to_string()));
+    assert!(!paths.contains(&"src/utils.js".to_string()));
+    let report = ns::searcher::why::explain_file(&root, "src/models.py", "user", &opts(10)).unwrap();
+    assert_eq!(
+        report.verdict,
+        ns::searcher::why::Verdict::NotIndexed {
+            reason: ns::searcher::why::SkipReason::Excluded {
+                pattern: "*.py".to_string()
+            }
+        }
+    );
+
+    fs::write(root.join("src").join("extra.py"), "def extra():\n    pass\n").unwrap();
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
+        .expect("incremental should succeed");
+    assert_eq!(stats.added, 0, "{:?}", stats.added_paths);
+    let (_, meta) = ns::indexer::writer::open_index(&root).unwrap();
+    assert_eq!(meta.exclude, vec!["*.py", "src/utils.js"]);
+
+    // Narrowing the globs drops nothing more and re-admits the rest.
+    let narrower = IndexOptions {
+        exclude: vec!["src/utils.js".to_string()],
+        ..Default::default()
+    };
+    let stats = ns::indexer::run_incremental_index(&root, &narrower).expect("incremental should succeed");
+    assert_eq!(stats.added_paths, vec!["src/extra.py", "src/models.py"]);
+
+    let clear = IndexOptions {
+        exclude: vec![String::new()],
+        ..Default::default()
+    };
+    ns::indexer::run_full_index(&root, &clear).expect("indexing should succeed");
+    assert!(live_paths(&root).contains(&"src/utils.js".to_string()));
+    assert!(ns::indexer::writer::read_meta(&root).unwrap().exclude.is_empty());
+}
+
+#[test]
+fn max_depth_is_remembered_by_incremental_runs() {
+    let (_tmp, root) = common::isolated_fixture();
+    let shallow = IndexOptions {
+        max_depth: Some(1),
+        ..Default::default()
+    };
+    let stats = ns::indexer::run_full_index(&root, &shallow)
+        .expect("indexing should succeed")
+        .expect("index should be built");
+    assert_eq!(stats.file_count, 2, "only README.md and config.json sit in the root");
+    let report = ns::searcher::why::explain_file(&root, "src/models.py", "user", &opts(10)).unwrap();
+    assert_eq!(
+        report.verdict,
+        ns::searcher::why::Verdict::NotIndexed {
+            reason: ns::searcher::why::SkipReason::TooDeep { depth: 2, max: 1 }
+        }
+    );
+
+    fs::write(root.join("src").join("extra.py"), "def extra():\n    pass\n").unwrap();
+    fs::write(root.join("notes.md"), "# notes\n").unwrap();
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
+        .expect("incremental should succeed");
+    assert_eq!(stats.added_paths, vec!["notes.md"]);
+
+    let unlimited = IndexOptions {
+        max_depth: Some(0),
+        ..Default::default()
+    };
+    let stats = ns::indexer::run_incremental_index(&root, &unlimited).expect("incremental should succeed");
+    assert_eq!(stats.added, 8, "{:?}", stats.added_paths);
+    assert_eq!(ns::indexer::writer::read_meta(&root).unwrap().max_depth, None);
+}
+
+#[test]
+fn truncated_heads_are_remembered_by_incremental_runs() {
+    let (_tmp, root) = common::isolated_fixture();
+    let filler = "insert into events values (1, 'filler');\n".repeat(100);
+    let dump = format!("-- headmarker\n{}-- tailmarker\n", filler);
+    fs::write(root.join("dump.sql"), dump).unwrap();
+
+    let truncate = IndexOptions {
+        max_file_size: 2048,
+        truncate_large_kb: Some(1),
+        ..Default::default()
+    };
+    ns::indexer::run_full_index(&root, &truncate).expect("indexing should succeed");
+    assert_eq!(ns::indexer::writer::read_meta(&root).unwrap().truncate_large_kb, Some(1));
+
+    let (results, _) = ns::searcher::query::execute_search(&root, "headmarker", &opts(10)).unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].truncated, "the result is flagged as truncated");
+    let (results, _) = ns::searcher::query::execute_search(&root, "tailmarker", &opts(10)).unwrap();
+    assert!(results.is_empty(), "the tail is past the indexed head");
+
+    let same_limit = IndexOptions {
+        max_file_size: 2048,
+        ..Default::default()
+    };
+    let stats = ns::indexer::run_incremental_index(&root, &same_limit).expect("incremental should succeed");
+    assert_eq!(stats.modified, 0, "the head hashes the same on every run");
+
+    let skip = IndexOptions {
+        max_file_size: 2048,
+        truncate_large_kb: Some(0),
+        ..Default::default()
+    };
+    let stats = ns::indexer::run_incremental_index(&root, &skip).expect("incremental should succeed");
+    assert_eq!(stats.deleted_paths, vec!["dump.sql"]);
+    assert_eq!(ns::indexer::writer::read_meta(&root).unwrap().truncate_large_kb, None);
+}
+
+#[cfg(unix)]
+#[test]
+fn followed_symlinks_stay_followed_by_incremental_runs() {
+    let (_tmp, root) = common::isolated_fixture();
+    let vendored = tempfile::tempdir().unwrap();
+    fs::write(vendored.path().join("dep.rs"), "fn vendored_dep() {}\n").unwrap();
+    std::os::unix::fs::symlink(vendored.path(), root.join("vendor")).unwrap();
+
+    let follow = IndexOptions {
+        follow_symlinks: true,
+        ..Default::default()
+    };
+    ns::indexer::run_full_index(&root, &follow).expect("indexing should succeed");
+    assert!(ns::indexer::writer::read_meta(&root).unwrap().follow_symlinks);
+
+    fs::write(vendored.path().join("more.rs"), "fn more() {}\n").unwrap();
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
+        .expect("incremental should succeed");
+    assert_eq!(stats.added_paths, vec!["vendor/more.rs"]);
+    assert_eq!(stats.deleted, 0, "vendor/dep.rs is still reached through the link");
+}
+
+#[test]
+fn verify_content_reports_changed_and_missing_files() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    let clean = ns::indexer::verify::verify_content(&root, None).expect("check should succeed");
+    assert_eq!(clean.checked, clean.indexed);
+    assert_eq!(clean.stale_percent(), 0.0);
+
+    fs::write(root.join("src").join("models.py"), "# rewritten\n").expect("should write");
+    fs::remove_file(root.join("src").join("utils.js")).expect("should delete file");
+
+    let check = ns::indexer::verify::verify_content(&root, None).expect("check should succeed");
+    assert_eq!(check.changed, vec!["src/models.py".to_string()]);
+    assert_eq!(check.missing, vec!["src/utils.js".to_string()]);
+    assert!(check.stale_percent() > 0.0);
+
+    let sampled = ns::indexer::verify::verify_content(&root, Some(2)).expect("check should succeed");
+    assert_eq!(sampled.checked, 2);
+}
+
+#[test]
+fn verify_content_compares_truncated_files_by_their_head() {
+    let (_tmp, root) = common::isolated_fixture();
+    let filler = "insert into events values (1, 'filler');\n".repeat(100);
+    fs::write(root.join("dump.sql"), format!("-- head\n{}", filler)).unwrap();
+    let truncate = IndexOptions {
+        max_file_size: 2048,
+        truncate_large_kb: Some(1),
+        ..Default::default()
+    };
+    ns::indexer::run_full_index(&root, &truncate).expect("indexing should succeed");
+
+    let clean = ns::indexer::verify::verify_content(&root, None).expect("check should succeed");
+    assert!(clean.changed.is_empty(), "{:?}", clean.changed);
+
+    fs::write(root.join("dump.sql"), format!("-- edited head\n{}", filler)).unwrap();
+    let check = ns::indexer::verify::verify_content(&root, None).expect("check should succeed");
+    assert_eq!(check.changed, vec!["dump.sql".to_string()]);
+}
+
 #[test]
 fn incremental_updates_meta_json() {
     let (_tmp, root) = common::indexed_fixture();
 
     let meta_before = ns::indexer::writer::read_meta(&root).expect("should read meta");
 
+    // indexed_at has one-second resolution.
     thread::sleep(Duration::from_secs(1));
 
     // Add a file to trigger an actual change
     let new_file = root.join("src").join("meta_test.rs");
     fs::write(&new_file, "pub fn meta_test_fn() {}\n").expect("should write file");
 
-    ns::indexer::run_incremental_index(&root, 1_048_576)
+    ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental should succeed");
 
     let meta_after = ns::indexer::writer::read_meta(&root).expect("should read updated meta");
@@ -169,7 +482,7 @@ fn incremental_updates_meta_json() {
 // ── Idempotency regression tests (Bug 2) ─────────────────────────────────────
 
 #[test]
-fn incremental_repeated_runs_are_idempotent_mtime() {
+fn incremental_repeated_runs_are_idempotent() {
     let (_tmp, root) = common::indexed_fixture();
 
     let meta_after_full = ns::indexer::writer::read_meta(&root).expect("read meta");
@@ -177,8 +490,7 @@ fn incremental_repeated_runs_are_idempotent_mtime() {
 
     // Run incremental 3 times — file count should stay constant
     for i in 1..=3 {
-        thread::sleep(Duration::from_millis(50));
-        let stats = ns::indexer::run_incremental_index(&root, 1_048_576)
+        let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
             .expect("incremental should succeed");
 
         assert_eq!(
@@ -236,7 +548,7 @@ fn git_indexed_fixture() -> (tempfile::TempDir, std::path::PathBuf) {
         .expect("git commit should succeed");
 
     // Now index — meta.json will capture the git commit hash
-    ns::indexer::run_full_index(&root, 1_048_576).expect("indexing should succeed");
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
 
     (tmp, root)
 }
@@ -269,7 +581,7 @@ fn incremental_git_detects_added_file() {
         .output()
         .expect("git commit should succeed");
 
-    let stats = ns::indexer::run_incremental_index(&root, 1_048_576)
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental should succeed");
 
     assert!(stats.added >= 1, "should detect added file via git, got {} added", stats.added);
@@ -310,7 +622,7 @@ fn incremental_git_detects_modified_file() {
         .output()
         .expect("git commit should succeed");
 
-    let stats = ns::indexer::run_incremental_index(&root, 1_048_576)
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental should succeed");
 
     assert!(
@@ -353,7 +665,7 @@ fn incremental_git_detects_deleted_file() {
         .output()
         .expect("git commit should succeed");
 
-    let stats = ns::indexer::run_incremental_index(&root, 1_048_576)
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental should succeed");
 
     assert!(
@@ -374,7 +686,7 @@ fn incremental_git_detects_deleted_file() {
 fn incremental_git_no_changes() {
     let (_tmp, root) = git_indexed_fixture();
 
-    let stats = ns::indexer::run_incremental_index(&root, 1_048_576)
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental should succeed");
 
     assert_eq!(stats.added, 0, "no files should be added");
@@ -394,7 +706,7 @@ fn incremental_git_uncommitted_changes() {
     )
     .expect("should write file");
 
-    let stats = ns::indexer::run_incremental_index(&root, 1_048_576)
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental should succeed");
 
     assert!(
@@ -414,6 +726,81 @@ fn incremental_git_uncommitted_changes() {
     );
 }
 
+#[test]
+fn churn_counts_commits_and_follows_index_writes() {
+    let (_tmp, root) = git_indexed_fixture();
+    for n in 0..2 {
+        fs::write(
+            root.join("src/validator.rs"),
+            format!("pub fn validate_input() {{}}\n// revision {}\n", n),
+        )
+        .expect("should write file");
+        for args in [
+            vec!["add", "src/validator.rs"],
+            vec!["-c", "user.name=Test", "-c", "user.email=test@test.com", "commit", "-m", "edit"],
+        ] {
+            std::process::Command::new("git")
+                .args(&args)
+                .current_dir(&root)
+                .output()
+                .expect("git should run");
+        }
+    }
+    assert!(ns::indexer::churn::read_churn(&root).is_none(), "churn is off by default");
+
+    let churn_opts = IndexOptions {
+        churn_days: Some(90),
+        ..Default::default()
+    };
+    ns::indexer::run_incremental_index(&root, &churn_opts).expect("incremental should succeed");
+    let churn = ns::indexer::churn::read_churn(&root).expect("should write .ns/churn.json");
+    assert_eq!(churn.days, 90);
+    assert_eq!(churn.commits.get("src/validator.rs"), Some(&3));
+    assert_eq!(churn.commits.get("src/server.go"), Some(&1));
+    assert_eq!(churn.score("src/validator.rs"), 1.0);
+    assert_eq!(ns::indexer::writer::read_meta(&root).unwrap().churn_days, Some(90));
+
+    let (results, _) = ns::searcher::query::execute_search(&root, "validate_input", &opts(10))
+        .expect("search should work");
+    let validator = results.iter().find(|r| r.path == "src/validator.rs").unwrap();
+    assert_eq!(validator.rerank.churn, 1.0);
+
+    // Rebuilds keep the window; 0 turns churn off.
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+    assert!(ns::indexer::churn::read_churn(&root).is_some());
+    let off = IndexOptions {
+        churn_days: Some(0),
+        ..Default::default()
+    };
+    ns::indexer::run_full_index(&root, &off).expect("indexing should succeed");
+    assert!(ns::indexer::churn::read_churn(&root).is_none());
+    assert_eq!(ns::indexer::writer::read_meta(&root).unwrap().churn_days, None);
+}
+
+#[test]
+fn incremental_git_skips_nested_ns_dirs() {
+    let (_tmp, root) = git_indexed_fixture();
+
+    // Index a sub-directory on its own: its .ns/ is untracked in the outer repo.
+    let nested = root.join("src");
+    ns::indexer::run_full_index(&nested, &IndexOptions::default())
+        .expect("nested indexing should succeed");
+    assert!(nested.join(".ns/meta.json").is_file());
+    fs::write(root.join("src/nested_added.rs"), "pub fn nested_added() {}\n").unwrap();
+
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
+        .expect("incremental should succeed");
+    assert_eq!(stats.added_paths, vec!["src/nested_added.rs".to_string()]);
+
+    let (results, _) = ns::searcher::query::execute_search(&root, "schema_version", &opts(10))
+        .expect("search should work");
+    assert!(
+        results.iter().all(|r| !r.path.contains(".ns/")),
+        "nested index files should not be indexed: {:?}",
+        results.iter().map(|r| &r.path).collect::<Vec<_>>()
+    );
+}
+
 /// Regression test for Bug 2: repeated incremental runs in a git repo with
 /// untracked files should NOT re-add those files each time.
 #[test]
@@ -425,7 +812,7 @@ fn incremental_git_repeated_runs_no_duplicates() {
     fs::write(&untracked, "pub fn untracked_fn() {}\n").expect("write untracked");
 
     // First incremental — should detect the untracked file as added
-    let stats1 = ns::indexer::run_incremental_index(&root, 1_048_576)
+    let stats1 = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental 1 should succeed");
     assert!(
         stats1.added >= 1,
@@ -438,7 +825,7 @@ fn incremental_git_repeated_runs_no_duplicates() {
     // Second incremental — no new files should be added.
     // (modified may be non-zero due to mtime granularity, but that's a safe
     // delete+re-add — the key invariant is that file count doesn't grow.)
-    let stats2 = ns::indexer::run_incremental_index(&root, 1_048_576)
+    let stats2 = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental 2 should succeed");
     assert_eq!(
         stats2.added, 0,
@@ -454,7 +841,7 @@ fn incremental_git_repeated_runs_no_duplicates() {
     );
 
     // Third incremental — still no growth
-    let stats3 = ns::indexer::run_incremental_index(&root, 1_048_576)
+    let stats3 = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental 3 should succeed");
     assert_eq!(stats3.added, 0, "third run should add 0 files");
 
@@ -479,3 +866,24 @@ fn incremental_git_repeated_runs_no_duplicates() {
         path_count
     );
 }
+
+#[test]
+fn incremental_stats_list_changed_paths() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    fs::write(root.join("src/added.rs"), "pub fn added() {}\n").unwrap();
+    fs::remove_file(root.join("src/utils.js")).unwrap();
+
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
+        .expect("incremental should succeed");
+
+    assert_eq!(stats.added_paths, vec!["src/added.rs"]);
+    assert_eq!(stats.deleted_paths, vec!["src/utils.js"]);
+    assert_eq!(stats.added, stats.added_paths.len());
+    assert_eq!(stats.modified, stats.modified_paths.len());
+    // Every re-indexed file is timed; deletions are not.
+    let timed: Vec<&str> = stats.file_timings.iter().map(|t| t.path.as_str()).collect();
+    assert!(timed.contains(&"src/added.rs"));
+    assert!(!timed.contains(&"src/utils.js"));
+    assert!(stats.file_timings.iter().all(|t| t.indexed));
+}
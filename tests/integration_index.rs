@@ -1,10 +1,13 @@
 mod common;
 
+use ns::indexer::prune::StoredTrim;
+use ns::indexer::{IndexOptions, StoredCompression};
+
 #[test]
 fn full_index_creates_ns_directory() {
     let (_tmp, root) = common::isolated_fixture();
 
-    let stats = ns::indexer::run_full_index(&root, 1_048_576)
+    let stats = ns::indexer::run_full_index(&root, &IndexOptions::default())
         .expect("indexing should succeed")
         .expect("should have indexable files");
 
@@ -18,20 +21,24 @@ fn full_index_creates_ns_directory() {
     );
 
     let meta = ns::indexer::writer::read_meta(&root).expect("should read meta.json");
-    assert_eq!(meta.schema_version, 2);
+    assert_eq!(meta.schema_version, 19);
     assert_eq!(meta.file_count, count);
     assert!(meta.index_size_bytes > 0);
     assert!(meta.indexed_at.contains('T'), "indexed_at should be ISO 8601");
+
+    let by_language = meta.symbol_stats.expect("symbol stats").by_language;
+    assert_eq!(by_language["rust"].files, 2, "event_store.rs and validator.rs");
+    assert!(by_language.values().all(|l| l.symbols > 0), "{:?}", by_language);
 }
 
 #[test]
 fn reindex_is_idempotent() {
     let (_tmp, root) = common::isolated_fixture();
 
-    let stats1 = ns::indexer::run_full_index(&root, 1_048_576)
+    let stats1 = ns::indexer::run_full_index(&root, &IndexOptions::default())
         .expect("first index should succeed")
         .expect("should have indexable files");
-    let stats2 = ns::indexer::run_full_index(&root, 1_048_576)
+    let stats2 = ns::indexer::run_full_index(&root, &IndexOptions::default())
         .expect("second index should succeed")
         .expect("should have indexable files");
 
@@ -45,3 +52,370 @@ fn status_without_index_fails() {
     let result = ns::indexer::writer::read_meta(&root);
     assert!(result.is_err(), "reading meta without index should fail");
 }
+
+#[test]
+fn max_index_size_prunes_documents() {
+    let (_tmp, root) = common::isolated_fixture();
+
+    let unbounded = ns::indexer::run_full_index(&root, &IndexOptions::default())
+        .expect("indexing should succeed")
+        .expect("should have indexable files");
+    let full_size = ns::indexer::writer::read_meta(&root).unwrap().index_size_bytes;
+
+    let opts = IndexOptions {
+        max_index_size: Some(full_size / 2),
+        ..IndexOptions::default()
+    };
+    let stats = ns::indexer::run_full_index(&root, &opts)
+        .expect("indexing should succeed")
+        .expect("should have indexable files");
+
+    assert!(!stats.pruned.is_empty(), "should prune at least one file");
+    assert_eq!(stats.file_count, unbounded.file_count - stats.pruned.len());
+
+    let meta = ns::indexer::writer::read_meta(&root).unwrap();
+    assert_eq!(meta.max_index_size, Some(full_size / 2));
+    assert_eq!(meta.pruned_paths.len(), stats.pruned.len());
+    assert!(meta.index_size_bytes < full_size);
+}
+
+#[test]
+fn index_budget_trims_stored_fields_before_pruning() {
+    let (_tmp, root) = common::isolated_fixture();
+
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).unwrap();
+    let full_size = ns::indexer::writer::read_meta(&root).unwrap().index_size_bytes;
+
+    let opts = IndexOptions {
+        max_index_size: Some(full_size - 1),
+        ..IndexOptions::default()
+    };
+    let stats = ns::indexer::run_full_index(&root, &opts).unwrap().unwrap();
+    assert_eq!(stats.trimmed, Some(StoredTrim::Content));
+    assert!(stats.pruned.is_empty(), "leaving out stored content is enough");
+    let meta = ns::indexer::writer::read_meta(&root).unwrap();
+    assert_eq!(meta.stored_trim, StoredTrim::Content);
+    assert!(meta.index_size_bytes < full_size);
+
+    std::fs::write(root.join("src/extra.rs"), "pub struct EventStoreCache;\n").unwrap();
+    ns::indexer::run_incremental_index(&root, &IndexOptions::default()).unwrap();
+
+    let search = ns::searcher::query::SearchOptions::default();
+    for query in ["EventStore", "EventStoreCache"] {
+        let (results, _) = ns::searcher::query::execute_search(&root, query, &search).unwrap();
+        assert!(!results.is_empty(), "{query} is still found");
+        assert!(results.iter().all(|r| r.symbol_defs.is_empty()), "no stored definition lines");
+    }
+}
+
+#[test]
+fn cli_index_budget_defaults_to_zstd() {
+    let (tmp, root) = common::isolated_fixture();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_ns"))
+        .args(["index", "--index-budget", "100"])
+        .current_dir(&root)
+        .env("XDG_CONFIG_HOME", tmp.path().join("config"))
+        .output()
+        .expect("should run ns");
+    assert!(output.status.success());
+
+    let (index, meta) = ns::indexer::writer::open_index(&root).unwrap();
+    assert_eq!(meta.max_index_size, Some(100 * 1024 * 1024));
+    let fp = ns::indexer::footprint::index_footprint(&index).unwrap();
+    assert_eq!(fp.compression, StoredCompression::Zstd);
+}
+
+#[test]
+fn zstd_compression_is_reported_in_footprint() {
+    let (_tmp, root) = common::isolated_fixture();
+
+    let opts = IndexOptions {
+        compression: StoredCompression::Zstd,
+        ..IndexOptions::default()
+    };
+    ns::indexer::run_full_index(&root, &opts)
+        .expect("indexing should succeed")
+        .expect("should have indexable files");
+
+    let (index, meta) = ns::indexer::writer::open_index(&root).expect("should open index");
+    let fp = ns::indexer::footprint::index_footprint(&index).expect("should measure footprint");
+
+    assert_eq!(fp.compression, StoredCompression::Zstd);
+    assert_eq!(fp.live_docs, meta.file_count as u64);
+    assert!(fp.store_bytes > 0);
+    assert!(fp.total_bytes > 0);
+
+    let content = fp.fields.iter().find(|f| f.name == "content").unwrap();
+    assert!(!content.stored, "content is not stored");
+    assert_eq!(content.stored_raw_bytes, 0);
+    assert!(content.postings_bytes > 0);
+
+    let path = fp.fields.iter().find(|f| f.name == "path").unwrap();
+    assert!(path.stored && path.stored_raw_bytes > 0);
+}
+
+#[test]
+fn index_writes_touch_updated_and_remember_on_update() {
+    let (_tmp, root) = common::isolated_fixture();
+    let opts = IndexOptions {
+        on_update: Some("touch hook_ran".to_string()),
+        ..Default::default()
+    };
+    ns::indexer::run_full_index(&root, &opts).expect("indexing should succeed");
+
+    let meta = ns::indexer::writer::read_meta(&root).expect("should read meta.json");
+    let updated = std::fs::read_to_string(root.join(".ns/updated")).expect("should write .ns/updated");
+    assert_eq!(updated.trim(), meta.indexed_at);
+    assert_eq!(meta.on_update.as_deref(), Some("touch hook_ran"));
+
+    // Rebuilds and incremental runs keep the command unless it is replaced or cleared.
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+    let meta = ns::indexer::writer::read_meta(&root).unwrap();
+    assert_eq!(meta.on_update.as_deref(), Some("touch hook_ran"));
+
+    let clear = IndexOptions {
+        on_update: Some(String::new()),
+        ..Default::default()
+    };
+    ns::indexer::run_full_index(&root, &clear).expect("indexing should succeed");
+    assert_eq!(ns::indexer::writer::read_meta(&root).unwrap().on_update, None);
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_index_runs_on_update_command() {
+    let (tmp, root) = common::isolated_fixture();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_ns"))
+        .args(["index", "--on-update", "echo \"$NS_ROOT\" > hook_ran"])
+        .current_dir(&root)
+        .env("XDG_CONFIG_HOME", tmp.path().join("config"))
+        .output()
+        .expect("should run ns");
+    assert!(output.status.success());
+
+    let ran = std::fs::read_to_string(root.join("hook_ran")).expect("hook should have run");
+    assert_eq!(ran.trim(), root.to_string_lossy());
+}
+
+#[cfg(unix)]
+#[test]
+fn status_reports_crashed_background_index() {
+    let (tmp, root) = common::indexed_fixture();
+    assert!(
+        !root.join(".ns/indexing.json").exists(),
+        "a finished run removes its health file"
+    );
+
+    let mut child = std::process::Command::new("true").spawn().unwrap();
+    let pid = child.id();
+    child.wait().unwrap();
+    let health = ns::indexer::health::IndexingHealth {
+        pid,
+        mode: "incremental".to_string(),
+        started_at: "2026-01-01T00:00:00Z".to_string(),
+        heartbeat_at: "2026-01-01T00:00:01Z".to_string(),
+        done: 2,
+        total: 5,
+    };
+    std::fs::write(
+        root.join(".ns/indexing.json"),
+        serde_json::to_string(&health).unwrap(),
+    )
+    .unwrap();
+
+    let ns = |args: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_ns"))
+            .args(args)
+            .current_dir(&root)
+            .env("XDG_CONFIG_HOME", tmp.path().join("config"))
+            .output()
+            .expect("should run ns")
+    };
+    let output = ns(&["status"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!("last background index crashed (PID {}", pid)),
+        "stdout: {}",
+        stdout
+    );
+
+    // The next completed run supersedes the crash record.
+    assert!(ns(&["index", "--incremental"]).status.success());
+    assert!(!root.join(".ns/indexing.json").exists());
+}
+
+#[test]
+fn verbose_index_reports_skipped_files_by_reason() {
+    let (tmp, root) = common::isolated_fixture();
+    std::fs::write(root.join("logo.png"), [0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+    std::fs::write(root.join("dump.sql"), "x".repeat(4096)).unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_ns"))
+        .args(["index", "--verbose", "--max-file-size", "2048", "--exclude", "config.json"])
+        .current_dir(&root)
+        .env("XDG_CONFIG_HOME", tmp.path().join("config"))
+        .output()
+        .expect("should run ns");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "Skipped 3 paths:\n  too large (1):\n    dump.sql\n  binary (1):\n    logo.png\n  excluded (1):\n    config.json\n"
+        ),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn several_roots_share_one_index_in_their_parent() {
+    let tmp = tempfile::tempdir().unwrap();
+    let workspace = tmp.path();
+    common::copy_dir_recursive(&common::fixture_source(), &workspace.join("api"));
+    std::fs::create_dir_all(workspace.join("web")).unwrap();
+    std::fs::write(workspace.join("web/app.ts"), "export function renderDashboard() {}\n").unwrap();
+    std::fs::create_dir_all(workspace.join("scratch")).unwrap();
+    std::fs::write(workspace.join("scratch/notes.md"), "renderDashboard ideas\n").unwrap();
+
+    let ns = |args: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_ns"))
+            .args(args)
+            .current_dir(workspace)
+            .env("XDG_CONFIG_HOME", workspace.join("config"))
+            .output()
+            .expect("should run ns")
+    };
+    assert!(ns(&["index", "--root", "api", "--root", "web"]).status.success());
+    assert!(workspace.join(".ns/index").is_dir(), "the index lives in the common parent");
+    let meta = ns::indexer::writer::read_meta(workspace).unwrap();
+    assert_eq!(meta.roots, vec!["api", "web"]);
+
+    let output = ns(&["-l", "renderDashboard"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "web/app.ts");
+    let output = ns(&["-l", "EventStore"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("api/src/event_store.rs"));
+
+    std::fs::write(workspace.join("web/extra.ts"), "export const x = 1;\n").unwrap();
+    std::fs::write(workspace.join("scratch/more.md"), "more\n").unwrap();
+    let stats = ns::indexer::run_incremental_index(workspace, &IndexOptions::default())
+        .expect("incremental should succeed");
+    assert_eq!(stats.added_paths, vec!["web/extra.ts"]);
+
+    assert!(ns(&["index"]).status.success());
+    assert_eq!(ns::indexer::writer::read_meta(workspace).unwrap().roots, vec!["api", "web"]);
+    assert!(ns(&["index", "--root", "."]).status.success());
+    assert!(ns::indexer::writer::read_meta(workspace).unwrap().roots.is_empty(), "--root . indexes everything again");
+    let output = ns(&["-l", "renderDashboard"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("scratch/notes.md"));
+}
+
+#[test]
+fn writer_memory_and_threads_are_configurable() {
+    let (_tmp, root) = common::isolated_fixture();
+    let opts = IndexOptions {
+        memory_mb: 30,
+        threads: Some(2),
+        ..Default::default()
+    };
+    let stats = ns::indexer::run_full_index(&root, &opts)
+        .expect("indexing should succeed")
+        .expect("should have indexable files");
+    assert_eq!(stats.file_count, 9);
+    std::fs::write(root.join("src/extra.rs"), "fn extra() {}\n").unwrap();
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions { threads: Some(1), ..opts })
+        .expect("incremental should succeed");
+    assert_eq!(stats.added, 1);
+
+    let starved = IndexOptions {
+        memory_mb: 30,
+        threads: Some(4),
+        ..Default::default()
+    };
+    assert!(ns::indexer::run_full_index(&root, &starved).is_err(), "7.5 MB per thread is too little");
+    let roomy = IndexOptions {
+        memory_mb: 5000,
+        ..Default::default()
+    };
+    assert!(ns::indexer::run_full_index(&root, &roomy).is_ok(), "split over enough threads to fit");
+    let huge = IndexOptions {
+        memory_mb: u64::MAX,
+        threads: Some(1),
+        ..Default::default()
+    };
+    assert!(ns::indexer::run_full_index(&root, &huge).is_err());
+}
+
+#[test]
+fn ls_lists_indexed_files_with_symbol_counts() {
+    use ns::indexer::listing::{list_files, ListOptions};
+    let (_tmp, root) = common::indexed_fixture();
+    let rust = ListOptions {
+        file_type: Some("rust".to_string()),
+        ..ListOptions::default()
+    };
+    let files = list_files(&root, &rust).expect("listing should work");
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, vec!["src/event_store.rs", "src/validator.rs"]);
+    assert!(files.iter().all(|f| f.symbols.is_some_and(|n| n > 0) && f.size > 0));
+
+    let glob = ListOptions {
+        file_glob: Some("*.md".to_string()),
+        ..ListOptions::default()
+    };
+    let docs = list_files(&root, &glob).expect("listing should work");
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0].symbols, Some(0));
+
+    let out = std::process::Command::new(env!("CARGO_BIN_EXE_ns"))
+        .args(["ls", "-t", "rust", "--paths-only"])
+        .current_dir(&root)
+        .output()
+        .expect("ns should run");
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&out.stdout),
+        "src/event_store.rs\nsrc/validator.rs\n"
+    );
+
+    let content_only = IndexOptions {
+        no_symbols: true,
+        ..IndexOptions::default()
+    };
+    ns::indexer::run_full_index(&root, &content_only).expect("indexing should succeed");
+    let files = list_files(&root, &rust).expect("listing should work");
+    assert!(files.iter().all(|f| f.symbols.is_none()), "unknown, not zero");
+}
+
+#[test]
+fn bench_rebuilds_the_index_and_times_queries() {
+    let (_tmp, root) = common::isolated_fixture();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_ns"))
+        .args(["bench", "--runs", "2", "--sample", "4", "--json"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(root.join(".ns/meta.json").is_file(), "bench leaves a usable index");
+    assert!(report["full_index_ms"].is_u64());
+    assert!(report["incremental_index_ms"].is_u64());
+    assert_eq!(report["runs"], 2);
+    let kinds: Vec<&str> = report["queries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|q| q["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, vec!["symbol", "symbol", "symbol", "symbol", "words", "words"]);
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_ns"))
+        .args(["bench", "--skip-index", "--runs", "1", "-q", "EventStore", "--json"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(report["full_index_ms"].is_null());
+    assert_eq!(report["queries"][0]["query"], "EventStore");
+    assert!(report["queries"][0]["results"].as_u64().unwrap() >= 1);
+}
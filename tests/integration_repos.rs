@@ -0,0 +1,79 @@
+//! Integration tests for the global repo registry (`ns repos`).
+
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+fn ns_binary() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_ns"))
+}
+
+/// Runs `ns` in `dir` with the registry redirected into `config_home`.
+fn run_ns(dir: &Path, config_home: &Path, args: &[&str]) -> Output {
+    std::process::Command::new(ns_binary())
+        .args(args)
+        .current_dir(dir)
+        .env("XDG_CONFIG_HOME", config_home)
+        .output()
+        .expect("should run ns")
+}
+
+#[test]
+fn index_registers_repo_and_list_shows_it() {
+    let (tmp, root) = common::isolated_fixture();
+    let config_home = tmp.path().join("config");
+
+    let output = run_ns(&root, &config_home, &["index"]);
+    assert!(output.status.success());
+
+    let registry = std::fs::read_to_string(config_home.join("ns/repos.toml"))
+        .expect("index should write the registry");
+    assert!(registry.contains("name = \"repo\""), "registry: {}", registry);
+
+    let output = run_ns(&root, &config_home, &["repos", "list"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("repo"), "stdout: {}", stdout);
+    assert!(stdout.contains(&root.canonicalize().unwrap().display().to_string()));
+
+    let output = run_ns(&root, &config_home, &["repos", "index-all"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn gc_removes_repos_without_index() {
+    let (tmp, root) = common::isolated_fixture();
+    let config_home = tmp.path().join("config");
+
+    assert!(run_ns(&root, &config_home, &["index"]).status.success());
+    std::fs::remove_dir_all(root.join(".ns")).unwrap();
+
+    let output = run_ns(&root, &config_home, &["repos", "gc"]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Removed 1 stale repo"), "stderr: {}", stderr);
+
+    let registry = std::fs::read_to_string(config_home.join("ns/repos.toml")).unwrap();
+    assert!(!registry.contains("[[repo]]"), "registry: {}", registry);
+}
+
+#[test]
+fn repo_flag_searches_a_registered_repo_from_anywhere() {
+    let (tmp, root) = common::isolated_fixture();
+    let config_home = tmp.path().join("config");
+    assert!(run_ns(&root, &config_home, &["index"]).status.success());
+
+    let elsewhere = tmp.path().join("elsewhere");
+    std::fs::create_dir_all(&elsewhere).unwrap();
+    let output = run_ns(&elsewhere, &config_home, &["--repo", "repo", "-l", "EventStore", "src/"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.lines().any(|l| l == "src/event_store.rs"), "stdout: {}", stdout);
+    assert!(!elsewhere.join(".ns").exists(), "nothing is written where ns runs");
+
+    let output = run_ns(&elsewhere, &config_home, &["--repo", "nope", "EventStore"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no registered repo named 'nope'"), "stderr: {}", stderr);
+}
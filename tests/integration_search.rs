@@ -1,7 +1,8 @@
 mod common;
 
-use ns::searcher::query::SearchOptions;
-use ns::searcher::OutputMode;
+use ns::indexer::IndexOptions;
+use ns::searcher::query::{SearchOptions, SearchResult};
+use ns::searcher::{OutputMode, Warning};
 use std::fs;
 use std::path::Path;
 
@@ -438,6 +439,250 @@ fn json_output_has_matched_symbols() {
     );
 }
 
+#[test]
+fn json_output_has_definition_of_matched_symbols() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    let so =
+        ns::searcher::search(&root, "EventStore", OutputMode::Json, &SearchOptions::default())
+            .expect("search should work");
+
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    let first = &parsed["results"][0];
+    assert_eq!(first["path"], "src/event_store.rs");
+    let definitions = first["definitions"].as_array().unwrap();
+    assert!(
+        definitions.iter().any(|d| d["symbol"] == "EventStore"
+            && d["definition"] == "pub struct EventStore {"),
+        "got: {:?}",
+        definitions
+    );
+}
+
+#[test]
+fn exact_symbol_match_outranks_partial_mentions() {
+    let (_tmp, root) = common::isolated_fixture();
+    let mut uses = String::from("fn wire_up() {\n");
+    for i in 0..30 {
+        uses.push_str(&format!(
+            "    let s{i} = EventStore::new(); s{i}.append(EventStore::default());\n"
+        ));
+    }
+    uses.push_str("}\n");
+    std::fs::write(root.join("src/uses.rs"), uses).unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).unwrap();
+
+    let so =
+        ns::searcher::search(&root, "EventStore", OutputMode::Json, &SearchOptions::default())
+            .expect("search should work");
+
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    let results = parsed["results"].as_array().unwrap();
+    assert_eq!(results[0]["path"], "src/event_store.rs");
+    assert_eq!(results[0]["ranking_factors"]["exact_symbol"], true);
+    let uses = results.iter().find(|r| r["path"] == "src/uses.rs").unwrap();
+    assert_eq!(uses["ranking_factors"]["exact_symbol"], false);
+}
+
+#[test]
+fn doc_comment_prose_outranks_plain_comments() {
+    let (_tmp, root) = common::isolated_fixture();
+    std::fs::write(
+        root.join("src/pacing.rs"),
+        "/// Throttles bursty writers.\nfn pace() {}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/timing.rs"),
+        "// Throttles bursty writers.\nfn time() {}\n",
+    )
+    .unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).unwrap();
+
+    let so = ns::searcher::search(
+        &root,
+        "throttles bursty writers",
+        OutputMode::Json,
+        &SearchOptions::default(),
+    )
+    .expect("search should work");
+
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    let results = parsed["results"].as_array().unwrap();
+    let paths: Vec<&str> = results.iter().map(|r| r["path"].as_str().unwrap()).collect();
+    assert_eq!(paths, vec!["src/pacing.rs", "src/timing.rs"]);
+    assert!(results[0]["score"].as_f64().unwrap() > results[1]["score"].as_f64().unwrap());
+}
+
+#[test]
+fn imports_field_finds_files_depending_on_a_module() {
+    let (_tmp, root) = common::isolated_fixture();
+    std::fs::write(
+        root.join("src/notes.rs"),
+        "// TODO: serve this over net/http like the Go server does.\nfn http_later() {}\n",
+    )
+    .unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).unwrap();
+
+    let paths = |query: &str| -> Vec<String> {
+        let (results, _) =
+            ns::searcher::query::execute_search(&root, query, &SearchOptions::default()).unwrap();
+        results.into_iter().map(|r| r.path).collect()
+    };
+    assert_eq!(paths("imports:http"), vec!["src/server.go"]);
+    assert_eq!(paths("imports:collections"), vec!["src/event_store.rs"]);
+    assert!(paths("http").contains(&"src/notes.rs".to_string()));
+}
+
+#[test]
+fn filename_words_match_files_that_never_mention_them() {
+    let (_tmp, root) = common::isolated_fixture();
+    std::fs::write(
+        root.join("src/rate_limiter.go"),
+        "package main\n\nfunc allow(n int) bool {\n\treturn n < 10\n}\n",
+    )
+    .unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).unwrap();
+
+    let (results, _) =
+        ns::searcher::query::execute_search(&root, "limiter", &SearchOptions::default()).unwrap();
+    let paths: Vec<&str> = results.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(paths, vec!["src/rate_limiter.go"]);
+    // The extension is not part of the name.
+    let (results, _) =
+        ns::searcher::query::execute_search(&root, "go", &SearchOptions::default()).unwrap();
+    assert!(results.iter().all(|r| r.path != "src/rate_limiter.go"));
+}
+
+#[test]
+fn size_and_mtime_filters_use_indexed_metadata() {
+    let (_tmp, root) = common::isolated_fixture();
+    // 2020-01-01T00:00:00Z
+    let old = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_577_836_800);
+    std::fs::File::options()
+        .write(true)
+        .open(root.join("src/validator.rs"))
+        .unwrap()
+        .set_modified(old)
+        .unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).unwrap();
+
+    let paths = |opts: SearchOptions| -> Vec<String> {
+        let (results, _) = ns::searcher::query::execute_search(&root, "valid", &opts).unwrap();
+        results.into_iter().map(|r| r.path).collect()
+    };
+    let all = paths(SearchOptions::default());
+    assert!(all.contains(&"src/validator.rs".to_string()));
+
+    let large = paths(SearchOptions {
+        larger_than: Some(1000),
+        ..SearchOptions::default()
+    });
+    assert!(!large.is_empty() && large.len() < all.len());
+    for path in &large {
+        assert!(std::fs::metadata(root.join(path)).unwrap().len() > 1000, "{path}");
+    }
+    let small = paths(SearchOptions {
+        smaller_than: Some(1000),
+        ..SearchOptions::default()
+    });
+    assert!(small.contains(&"src/validator.rs".to_string()));
+    for path in &small {
+        assert!(std::fs::metadata(root.join(path)).unwrap().len() < 1000, "{path}");
+    }
+
+    let recent = paths(SearchOptions {
+        // 2021-01-01T00:00:00Z
+        modified_since: Some(1_609_459_200),
+        ..SearchOptions::default()
+    });
+    assert_eq!(recent.len(), all.len() - 1);
+    assert!(!recent.contains(&"src/validator.rs".to_string()));
+}
+
+#[test]
+fn rerank_favors_files_matching_all_words_close_together() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    let so = ns::searcher::search(
+        &root,
+        "append event",
+        OutputMode::Json,
+        &SearchOptions::default(),
+    )
+    .expect("search should work");
+
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    let results = parsed["results"].as_array().unwrap();
+    assert_eq!(results[0]["path"], "src/event_store.rs");
+    let factors = &results[0]["ranking_factors"];
+    assert_eq!(factors["coverage"], 1.0);
+    assert_eq!(factors["proximity"], true);
+    assert_eq!(factors["path_match"], 0.5);
+    for r in &results[1..] {
+        assert!(r["ranking_factors"]["coverage"].as_f64().unwrap() < 1.0);
+        assert_eq!(r["ranking_factors"]["proximity"], false);
+    }
+}
+
+#[test]
+fn explain_breaks_score_into_terms_and_fields() {
+    let (_tmp, root) = common::indexed_fixture();
+    let opts = SearchOptions {
+        explain: true,
+        ..SearchOptions::default()
+    };
+
+    let so = ns::searcher::search(&root, "append event", OutputMode::Json, &opts)
+        .expect("search should work");
+
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    let first = &parsed["results"][0];
+    assert_eq!(first["path"], "src/event_store.rs");
+    let terms = first["ranking_factors"]["terms"].as_array().unwrap();
+    let append = terms
+        .iter()
+        .find(|t| t["field"] == "symbols" && t["term"] == "append")
+        .expect("append should match symbols");
+    let unboosted = append["explanation"]["value"].as_f64().unwrap();
+    assert!((append["score"].as_f64().unwrap() - unboosted * 3.0).abs() < 1e-3);
+    assert!(terms.iter().any(|t| t["field"] == "content"));
+    let scores: Vec<f64> = terms.iter().map(|t| t["score"].as_f64().unwrap()).collect();
+    assert!(scores.windows(2).all(|w| w[0] >= w[1]), "got: {:?}", scores);
+
+    let so = ns::searcher::search(&root, "append event", OutputMode::Json, &SearchOptions::default())
+        .expect("search should work");
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    assert!(parsed["results"][0]["ranking_factors"].get("terms").is_none());
+}
+
+#[test]
+fn exact_symbol_prefers_declarations_over_constants() {
+    let (_tmp, root) = common::isolated_fixture();
+    let mut routes = String::from("export const ROUTER = createRouter();\n");
+    for i in 0..20 {
+        routes.push_str(&format!("ROUTER.get('/r{i}', (req) => router.handle(req));\n"));
+    }
+    std::fs::write(root.join("src/routes.ts"), routes).unwrap();
+    std::fs::write(
+        root.join("src/app.ts"),
+        "export class Router {\n  handle(req) { return req; }\n}\n",
+    )
+    .unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).unwrap();
+
+    let so = ns::searcher::search(&root, "Router", OutputMode::Json, &SearchOptions::default())
+        .expect("search should work");
+
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    let results = parsed["results"].as_array().unwrap();
+    assert_eq!(results[0]["path"], "src/app.ts");
+    assert_eq!(results[0]["ranking_factors"]["symbol_kind"], "type");
+    let routes = results.iter().find(|r| r["path"] == "src/routes.ts").unwrap();
+    assert_eq!(routes["ranking_factors"]["exact_symbol"], true);
+    assert_eq!(routes["ranking_factors"]["symbol_kind"], "constant");
+}
+
 #[test]
 fn json_output_lines_use_num_field() {
     let (_tmp, root) = common::indexed_fixture();
@@ -571,7 +816,7 @@ fn search_with_stale_schema_version_returns_error() {
     // Tamper with meta.json to simulate a stale schema version
     let meta_path = root.join(".ns").join("meta.json");
     let content = std::fs::read_to_string(&meta_path).expect("should read meta");
-    let tampered = content.replace("\"schema_version\":2", "\"schema_version\":999");
+    let tampered = content.replace("\"schema_version\":19", "\"schema_version\":999");
     std::fs::write(&meta_path, &tampered).expect("should write tampered meta");
 
     let result = ns::searcher::search(
@@ -688,7 +933,7 @@ fn end_to_end_index_incremental_search() {
     let (_tmp, root) = common::isolated_fixture();
 
     // Full index
-    ns::indexer::run_full_index(&root, 1_048_576).expect("full index should succeed");
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("full index should succeed");
 
     // Verify search works
     let (results, _) =
@@ -696,10 +941,6 @@ fn end_to_end_index_incremental_search() {
             .expect("search should work after full index");
     assert!(!results.is_empty());
 
-    // Sleep required: without a git repo, incremental indexing uses mtime-based
-    // change detection. The new file needs a later mtime than indexed_at, and
-    // filesystem mtime granularity may be 1 second on some platforms.
-    std::thread::sleep(std::time::Duration::from_secs(1));
     std::fs::write(
         root.join("src").join("phase7_test.rs"),
         "pub struct Phase7Marker;\n",
@@ -707,7 +948,7 @@ fn end_to_end_index_incremental_search() {
     .expect("write should succeed");
 
     // Incremental index
-    let stats = ns::indexer::run_incremental_index(&root, 1_048_576)
+    let stats = ns::indexer::run_incremental_index(&root, &IndexOptions::default())
         .expect("incremental should succeed");
     assert!(stats.added >= 1, "should detect added file");
 
@@ -783,6 +1024,35 @@ fn read_search_log_entries(root: &Path) -> Vec<serde_json::Value> {
         .collect()
 }
 
+#[test]
+fn config_search_defaults_yield_to_flags() {
+    let (_tmp, root) = common::indexed_fixture();
+    fs::write(
+        root.join(".ns/config.toml"),
+        "[search]\nmax_count = 1\ntype = \"python\"\n",
+    )
+    .unwrap();
+    let files = |args: &[&str]| {
+        let output = std::process::Command::new(ns_binary())
+            .args(args)
+            .current_dir(&root)
+            .output()
+            .expect("should run ns");
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(files(&["-l", "user"]), vec!["src/models.py"]);
+    let widened = files(&["-l", "-m", "5", "-t", "rust", "struct"]);
+    assert!(widened.len() > 1, "{:?}", widened);
+    assert!(widened.iter().all(|p| p.ends_with(".rs")), "{:?}", widened);
+    let logs = read_search_log_entries(&root);
+    assert_eq!(logs[0]["flags"]["max_count"], 1);
+    assert_eq!(logs[0]["flags"]["file_type"], "python");
+}
+
 #[test]
 fn cli_search_without_index_stderr_and_exit_code() {
     let (_tmp, root) = common::isolated_fixture();
@@ -815,7 +1085,7 @@ fn cli_search_stale_schema_stderr_and_exit_code() {
     // Tamper with meta.json
     let meta_path = root.join(".ns").join("meta.json");
     let content = std::fs::read_to_string(&meta_path).expect("should read meta");
-    let tampered = content.replace("\"schema_version\":2", "\"schema_version\":999");
+    let tampered = content.replace("\"schema_version\":19", "\"schema_version\":999");
     std::fs::write(&meta_path, &tampered).expect("should write tampered meta");
 
     let output = std::process::Command::new(ns_binary())
@@ -893,6 +1163,119 @@ fn cli_no_results_json_exits_1_with_json_on_stdout() {
     assert_eq!(parsed["stats"]["total_results"], 0);
 }
 
+#[test]
+fn summary_prints_one_line_per_result() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    let output = std::process::Command::new(ns_binary())
+        .args(["--summary", "EventStore"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let first = stdout.lines().next().expect("at least one result");
+    assert!(first.starts_with("src/event_store.rs ("), "got: {}", stdout);
+    assert!(first.contains(" — matched symbols: "), "got: {}", first);
+    assert!(first.contains("EventStore"), "got: {}", first);
+    assert!(!stdout.contains("   1:"), "no context lines: {}", stdout);
+}
+
+#[test]
+fn granularity_symbol_returns_definitions() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    let output = std::process::Command::new(ns_binary())
+        .args(["--granularity", "symbol", "--json", "append"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(output.status.success());
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be valid JSON");
+    let first = &json["results"][0];
+    assert_eq!(first["path"], "src/event_store.rs");
+    assert_eq!(first["symbol"]["name"], "append");
+    assert_eq!(first["symbol"]["kind"], "function");
+    let (start, end) = (
+        first["symbol"]["start_line"].as_u64().unwrap(),
+        first["symbol"]["end_line"].as_u64().unwrap(),
+    );
+    assert!(start < end, "got {}-{}", start, end);
+    let lines = first["lines"].as_array().unwrap();
+    assert_eq!(lines[0]["num"].as_u64(), Some(start), "context starts at the definition");
+    assert!(lines.iter().all(|l| (start..=end).contains(&l["num"].as_u64().unwrap())));
+}
+
+#[test]
+fn symbol_hits_in_one_file_share_an_entry() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    let output = std::process::Command::new(ns_binary())
+        .args(["--granularity", "symbol", "--json", "event"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(output.status.success());
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be valid JSON");
+    let results = json["results"].as_array().unwrap();
+    let mut paths: Vec<&str> = results.iter().map(|r| r["path"].as_str().unwrap()).collect();
+    let total = paths.len();
+    paths.sort_unstable();
+    paths.dedup();
+    assert_eq!(paths.len(), total, "each file once: {:?}", paths);
+    let store = results.iter().find(|r| r["path"] == "src/event_store.rs").unwrap();
+    assert!(!store["merged_symbols"].as_array().unwrap().is_empty());
+    let nums: Vec<u64> = store["lines"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| l["num"].as_u64().unwrap())
+        .collect();
+    assert!(nums.windows(2).all(|w| w[0] < w[1]), "united in line order: {:?}", nums);
+}
+
+#[test]
+fn kind_filters_files_by_matching_definitions() {
+    let (_tmp, root) = common::indexed_fixture();
+    let paths = |kind: &str| -> Vec<String> {
+        let output = std::process::Command::new(ns_binary())
+            .args(["--kind", kind, "--json", "store"])
+            .current_dir(&root)
+            .output()
+            .expect("should run ns binary");
+        let json: serde_json::Value =
+            serde_json::from_slice(&output.stdout).expect("stdout should be valid JSON");
+        json["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["path"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // `EventStore` is a struct; no function name contains "store".
+    assert_eq!(paths("type"), vec!["src/event_store.rs"]);
+    assert_eq!(paths("struct,fn"), vec!["src/event_store.rs"]);
+    assert!(paths("function").is_empty());
+
+    let output = std::process::Command::new(ns_binary())
+        .args(["--json", "EventStore"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be valid JSON");
+    let definitions = json["results"][0]["definitions"].as_array().unwrap();
+    let definition = definitions
+        .iter()
+        .find(|d| d["symbol"] == "EventStore")
+        .expect("EventStore should be listed");
+    assert_eq!(definition["kind"], "type");
+    assert!(definition["line"].as_u64().unwrap() <= definition["end_line"].as_u64().unwrap());
+}
+
 #[test]
 fn cli_search_success_exits_0() {
     let (_tmp, root) = common::indexed_fixture();
@@ -1250,7 +1633,7 @@ fn performance_smoke_test() {
 
     // Index
     let start = std::time::Instant::now();
-    ns::indexer::run_full_index(&root, 1_048_576).expect("indexing should succeed");
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
     let index_ms = start.elapsed().as_millis();
 
     eprintln!("Performance: index took {}ms", index_ms);
@@ -1446,6 +1829,33 @@ fn cli_budget_zero_is_unlimited() {
     );
 }
 
+#[test]
+fn tight_budget_cuts_context_before_dropping_results() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    let opts = SearchOptions {
+        budget: Some(2000),
+        ..Default::default()
+    };
+    let so = ns::searcher::search(&root, "event", OutputMode::Json, &opts)
+        .expect("search should succeed");
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    let results = parsed["results"].as_array().unwrap();
+    assert!(!so.budget_exhausted, "every result should be kept");
+    assert_eq!(results.len(), so.stats.total_results);
+
+    let degraded = parsed["stats"]["degraded"].as_array().expect("stats.degraded");
+    assert!(!degraded.is_empty());
+    assert_eq!(degraded.len(), so.degraded.len());
+    for entry in degraded {
+        let rank = entry["rank"].as_u64().unwrap() as usize;
+        assert_eq!(results[rank - 1]["path"], entry["path"]);
+        assert_eq!(results[rank - 1]["lines"].as_array().unwrap().len(), 1);
+        assert!(entry["cut_lines"].as_u64().unwrap() > 0);
+    }
+    assert!(parsed["stats"]["budget_exceeded"].is_null());
+}
+
 #[test]
 fn budget_exceeded_shows_in_json_output() {
     let (_tmp, root) = common::indexed_fixture();
@@ -1512,3 +1922,1531 @@ fn cli_budget_flag_works() {
         "should find event_store.rs"
     );
 }
+
+#[test]
+fn budget_model_selects_the_tokenizer() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    let run = |model: &str| {
+        std::process::Command::new(ns_binary())
+            .args(["--budget", "60", "--budget-model", model, "event"])
+            .current_dir(&root)
+            .output()
+            .expect("should run ns binary")
+    };
+    for model in ["chars", "cl100k", "gpt-4o"] {
+        let output = run(model);
+        assert!(output.status.success(), "--budget-model {} should succeed", model);
+    }
+
+    let output = run("davinci");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unknown budget model"), "stderr: {}", stderr);
+}
+
+#[test]
+fn identical_files_are_collapsed_into_one_result() {
+    let (_tmp, root) = common::isolated_fixture();
+    std::fs::create_dir_all(root.join("vendor/copy")).unwrap();
+    std::fs::copy(
+        root.join("src/validator.rs"),
+        root.join("vendor/copy/validator.rs"),
+    )
+    .unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+
+    let (results, _) = ns::searcher::query::execute_search(&root, "validate", &opts(10))
+        .expect("search should work");
+    let copies: Vec<&SearchResult> = results
+        .iter()
+        .filter(|r| r.path.ends_with("validator.rs"))
+        .collect();
+    assert_eq!(copies.len(), 1, "identical copies should collapse");
+    assert_eq!(copies[0].alternates.len(), 1);
+
+    let no_dedupe = SearchOptions {
+        max_results: 10,
+        dedupe: false,
+        ..Default::default()
+    };
+    let (results, _) = ns::searcher::query::execute_search(&root, "validate", &no_dedupe)
+        .expect("search should work");
+    let copies = results.iter().filter(|r| r.path.ends_with("validator.rs")).count();
+    assert_eq!(copies, 2, "--no-dedupe keeps both copies");
+}
+
+#[test]
+fn regex_search_returns_line_matches() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    let regex_opts = SearchOptions {
+        regex: true,
+        context_window: 0,
+        ..Default::default()
+    };
+    let so = ns::searcher::search(&root, r"fn \w+_port", OutputMode::Text, &regex_opts)
+        .expect("regex search should work");
+
+    assert_eq!(so.stats.total_results, 1, "got: {}", so.formatted);
+    assert!(so.formatted.contains("src/validator.rs"));
+    assert!(so.formatted.contains("pub fn validate_port"), "got: {}", so.formatted);
+
+    // Patterns without literals scan every indexed file.
+    let (results, _) =
+        ns::searcher::regex_search::execute_regex_search(&root, r"\d{5}", &regex_opts)
+            .expect("regex search should work");
+    assert!(results.iter().any(|r| r.path == "src/validator.rs"));
+
+    let err = ns::searcher::search(&root, "valid(ate", OutputMode::Text, &regex_opts);
+    assert!(matches!(err, Err(ns::error::NsError::Regex(_))));
+}
+
+#[test]
+fn deleted_files_are_dropped_from_results() {
+    let (_tmp, root) = common::indexed_fixture();
+    fs::remove_file(root.join("src/validator.rs")).unwrap();
+
+    let (results, stats) = ns::searcher::query::execute_search(&root, "validate", &opts(10))
+        .expect("search should work");
+    assert!(results.iter().all(|r| r.path != "src/validator.rs"));
+    assert_eq!(stats.stale_results, 1);
+
+    let unverified = SearchOptions {
+        max_results: 10,
+        verify: false,
+        ..Default::default()
+    };
+    let (results, stats) = ns::searcher::query::execute_search(&root, "validate", &unverified)
+        .expect("search should work");
+    assert!(results.iter().any(|r| r.path == "src/validator.rs"));
+    assert_eq!(stats.stale_results, 0);
+}
+
+#[test]
+fn search_output_lists_warnings() {
+    let (_tmp, root) = common::indexed_fixture();
+    fs::remove_file(root.join("src/validator.rs")).unwrap();
+
+    let so = ns::searcher::search(&root, "validate", OutputMode::Json, &opts(10))
+        .expect("search should work");
+    assert_eq!(so.warnings, vec![Warning::StaleResults { dropped: 1 }]);
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    let warnings = parsed["warnings"].as_array().unwrap();
+    assert_eq!(warnings[0]["kind"], "stale_results");
+    assert_eq!(warnings[0]["dropped"], 1);
+    assert!(warnings[0]["message"].as_str().unwrap().contains("deleted files"));
+
+    let unverified = SearchOptions {
+        max_results: 10,
+        verify: false,
+        budget: Some(1),
+        ..Default::default()
+    };
+    let so = ns::searcher::search(&root, "validate", OutputMode::Text, &unverified)
+        .expect("search should work");
+    assert!(so.warnings.contains(&Warning::MissingContext {
+        path: "src/validator.rs".to_string()
+    }));
+    assert!(matches!(so.warnings.last(), Some(Warning::BudgetExceeded { .. })));
+    let footer: Vec<&str> = so.formatted.lines().rev().take(so.warnings.len()).collect();
+    assert!(footer.iter().all(|l| l.starts_with("warning: ")), "got: {}", so.formatted);
+
+    let (_tmp, root) = common::indexed_fixture();
+    let so = ns::searcher::search(&root, "validate", OutputMode::Json, &opts(10))
+        .expect("search should work");
+    assert!(so.warnings.is_empty());
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    assert!(parsed.get("warnings").is_none());
+}
+
+#[test]
+fn near_duplicate_files_collapse_into_one_result() {
+    let (_tmp, root) = common::isolated_fixture();
+    let original = fs::read_to_string(root.join("src/event_store.rs")).unwrap();
+    for copy in ["vendor/a/event_store.rs", "vendor/b/event_store.rs"] {
+        fs::create_dir_all(root.join(copy).parent().unwrap()).unwrap();
+    }
+    fs::write(
+        root.join("vendor/a/event_store.rs"),
+        format!("// vendored from upstream v1.2\n{}", original),
+    )
+    .unwrap();
+    fs::write(
+        root.join("vendor/b/event_store.rs"),
+        format!("// vendored from upstream v1.3\n{}", original),
+    )
+    .unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+
+    let so = ns::searcher::search(&root, "EventStore", OutputMode::Json, &opts(10))
+        .expect("search should work");
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    let results = parsed["results"].as_array().unwrap();
+    let paths: Vec<&str> = results.iter().map(|r| r["path"].as_str().unwrap()).collect();
+    assert_eq!(paths.iter().filter(|p| p.ends_with("event_store.rs")).count(), 1, "got {:?}", paths);
+    assert_eq!(results[0]["path"], "src/event_store.rs");
+    let duplicates = results[0]["duplicates"].as_array().expect("should list duplicates");
+    assert_eq!(duplicates.len(), 2);
+    assert!(duplicates.iter().all(|d| d["similarity"].as_f64().unwrap() >= 0.85));
+
+    let kept = SearchOptions {
+        max_results: 10,
+        dedupe: false,
+        ..Default::default()
+    };
+    let (results, _) = ns::searcher::query::execute_search(&root, "EventStore", &kept)
+        .expect("search should work");
+    assert_eq!(results.iter().filter(|r| r.path.ends_with("event_store.rs")).count(), 3);
+}
+
+#[test]
+fn no_index_scans_files_without_an_index() {
+    let (_tmp, root) = common::isolated_fixture();
+    let opts = SearchOptions {
+        max_results: 10,
+        no_index: true,
+        ..Default::default()
+    };
+
+    let so = ns::searcher::search(&root, "event store", OutputMode::Json, &opts)
+        .expect("scan should work without .ns/");
+    assert!(!root.join(".ns/index").exists());
+    assert!(matches!(so.warnings[0], Warning::Unindexed { files } if files > 0));
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    let results = parsed["results"].as_array().unwrap();
+    assert_eq!(results[0]["path"], "src/event_store.rs");
+    assert!(!results[0]["lines"].as_array().unwrap().is_empty());
+
+    let filtered = SearchOptions {
+        file_type: Some("rust".to_string()),
+        regex: true,
+        ..opts.clone()
+    };
+    let so = ns::searcher::search(&root, r"fn \w+\(", OutputMode::FilesOnly, &filtered)
+        .expect("regex scan should work");
+    assert!(so.formatted.lines().count() > 0);
+    assert!(so.formatted.lines().all(|p| p.ends_with(".rs")), "got: {}", so.formatted);
+
+    let output = std::process::Command::new(ns_binary())
+        .args(["--no-index", "EventStore"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("src/event_store.rs"));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("run 'ns index'"));
+    assert!(!root.join(".ns").exists(), "a scan leaves no .ns/ behind");
+
+    // `-term` exclusions apply as they do with an index.
+    let so = ns::searcher::search(&root, "event store -capacity", OutputMode::FilesOnly, &opts)
+        .expect("scan should work");
+    assert!(so.formatted.lines().count() > 0);
+    assert!(!so.formatted.contains("src/event_store.rs"), "got: {}", so.formatted);
+}
+
+#[test]
+fn record_separator_ends_every_text_result() {
+    let (_tmp, root) = common::indexed_fixture();
+    let opts = SearchOptions {
+        max_results: 5,
+        group_separator: "--".to_string(),
+        record_separator: Some("\x1e".to_string()),
+        ..Default::default()
+    };
+
+    let so = ns::searcher::search(&root, "event", OutputMode::Text, &opts)
+        .expect("search should succeed");
+    let records: Vec<&str> = so.formatted.split('\x1e').collect();
+    assert!(records.len() > 2, "expected several results, got: {}", so.formatted);
+    assert_eq!(records.last(), Some(&""), "every result should be terminated");
+    assert_eq!(records.len() - 1, so.stats.total_results.min(5));
+    assert!(records[..records.len() - 1]
+        .iter()
+        .all(|r| r.trim_start().starts_with('[')));
+    assert!(!so.formatted.contains("          ...\n"));
+
+    let output = std::process::Command::new(ns_binary())
+        .args(["--null", "--group-separator", "\\t", "event"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.matches('\0').count() > 1, "got: {}", stdout);
+    assert!(!stdout.contains("          ...\n"));
+}
+
+#[test]
+fn context_scope_function_shows_the_whole_function() {
+    let (_tmp, root) = common::isolated_fixture();
+    let body: String = (0..12).map(|i| format!("    let step_{} = {};\n", i, i)).collect();
+    fs::write(
+        root.join("src/rollover.rs"),
+        format!("fn rotate_logs() {{\n{}    compact_segments();\n}}\n", body),
+    )
+    .unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+
+    let run = |extra: &[&str]| -> String {
+        let output = std::process::Command::new(ns_binary())
+            .args(extra)
+            .arg("compact_segments")
+            .current_dir(&root)
+            .output()
+            .expect("should run ns binary");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    let window = run(&[]);
+    assert!(!window.contains("fn rotate_logs"), "got: {}", window);
+
+    let function = run(&["--context-scope", "function"]);
+    assert!(function.contains("fn rotate_logs"), "got: {}", function);
+    assert!(function.contains("let step_0 = 0;"));
+    let capped = run(&["--context-scope", "function", "--max-context-lines", "4"]);
+    assert!(capped.contains("fn rotate_logs") && !capped.contains("compact_segments();"));
+}
+
+#[test]
+fn color_always_adds_ansi_escapes() {
+    let (_tmp, root) = common::indexed_fixture();
+    let run = |extra: &[&str]| -> String {
+        let output = std::process::Command::new(ns_binary())
+            .args(extra)
+            .arg("EventStore")
+            .current_dir(&root)
+            .env_remove("NO_COLOR")
+            .output()
+            .expect("should run ns binary");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    // Piped stdout is not a terminal, so auto means no color.
+    assert!(!run(&[]).contains('\x1b'));
+    assert!(!run(&["--color", "never"]).contains('\x1b'));
+    let colored = run(&["--color", "always"]);
+    assert!(colored.contains("\x1b[1;31mEventStore\x1b[0m"), "got: {}", colored);
+}
+
+#[test]
+fn context_lines_follow_the_tokenizer() {
+    let (_tmp, root) = common::isolated_fixture();
+    fs::write(
+        root.join("src/backup.rs"),
+        "fn restore_backup() {}\n\nfn open() -> BackupStore {\n    todo!()\n}\n",
+    )
+    .unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+
+    let opts = SearchOptions {
+        max_results: 20,
+        context_window: 0,
+        paths: vec!["src/backup.rs".to_string()],
+        ..Default::default()
+    };
+    let so = ns::searcher::search(&root, "store", OutputMode::Json, &opts)
+        .expect("search should succeed");
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    let nums: Vec<u64> = parsed["results"][0]["lines"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| l["num"].as_u64().unwrap())
+        .collect();
+    // `restore` is one token, so only the BackupStore line matches.
+    assert_eq!(nums, vec![3]);
+    assert_eq!(parsed["results"][0]["matches"], serde_json::json!([3]));
+
+    // Once the file changes, the indexed lines no longer apply and context
+    // falls back to scanning it.
+    fs::write(root.join("src/backup.rs"), "// store\nfn restore_backup() {}\n").unwrap();
+    let so = ns::searcher::search(&root, "store", OutputMode::Json, &opts)
+        .expect("search should succeed");
+    let parsed: serde_json::Value = serde_json::from_str(&so.formatted).unwrap();
+    assert_eq!(parsed["results"][0]["lines"][0]["num"], 1);
+}
+
+#[test]
+fn quoted_phrase_requires_adjacent_terms() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    for fuzzy in [false, true] {
+        let phrase_opts = SearchOptions {
+            max_results: 10,
+            fuzzy,
+            ..Default::default()
+        };
+        let (results, _) =
+            ns::searcher::query::execute_search(&root, r#""event store""#, &phrase_opts)
+                .expect("phrase search should work");
+        assert!(
+            results.iter().any(|r| r.path == "src/event_store.rs"),
+            "fuzzy={}: {:?}",
+            fuzzy,
+            results.iter().map(|r| &r.path).collect::<Vec<_>>()
+        );
+
+        let (reversed, _) =
+            ns::searcher::query::execute_search(&root, r#""store event""#, &phrase_opts)
+                .expect("phrase search should work");
+        assert!(reversed.is_empty(), "fuzzy={}: word order should matter", fuzzy);
+    }
+}
+
+#[test]
+fn negated_terms_exclude_files() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    for fuzzy in [false, true] {
+        let opts = SearchOptions {
+            max_results: 10,
+            fuzzy,
+            ..Default::default()
+        };
+        let (all, _) = ns::searcher::query::execute_search(&root, "handler", &opts)
+            .expect("search should work");
+        assert!(all.iter().any(|r| r.path == "src/server.go"));
+
+        for query in ["handler -error", "handler !Error"] {
+            let (filtered, _) = ns::searcher::query::execute_search(&root, query, &opts)
+                .expect("search should work");
+            let paths: Vec<_> = filtered.iter().map(|r| r.path.as_str()).collect();
+            assert!(!paths.contains(&"src/server.go"), "fuzzy={} {}: {:?}", fuzzy, query, paths);
+            assert!(!paths.is_empty(), "fuzzy={} {}: other files should remain", fuzzy, query);
+        }
+    }
+}
+
+#[test]
+fn context_from_git_shows_indexed_version() {
+    let (_tmp, root) = common::isolated_fixture();
+    for args in [
+        vec!["init"],
+        vec!["add", "."],
+        vec!["-c", "user.name=Test", "-c", "user.email=test@test.com", "commit", "-m", "init"],
+    ] {
+        std::process::Command::new("git")
+            .args(&args)
+            .current_dir(&root)
+            .output()
+            .expect("git should run");
+    }
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+    fs::write(root.join("src/event_store.rs"), "// rewritten since indexing\n").unwrap();
+
+    let search = |context_from_git| {
+        let opts = SearchOptions {
+            max_results: 5,
+            context_from_git,
+            ..Default::default()
+        };
+        ns::searcher::search(&root, "EventStore", OutputMode::Text, &opts)
+            .expect("search should work")
+            .formatted
+    };
+
+    assert!(!search(false).contains("pub struct EventStore"));
+    assert!(search(true).contains("pub struct EventStore"));
+}
+
+#[test]
+fn identifier_parts_match_compound_names() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    for (query, sym_only) in [("store error", true), ("\"store error\"", false), ("EventStoreError", false)] {
+        let opts = SearchOptions {
+            max_results: 5,
+            sym_only,
+            ..Default::default()
+        };
+        let (results, _) = ns::searcher::query::execute_search(&root, query, &opts)
+            .expect("search should work");
+        assert!(
+            results.iter().any(|r| r.path == "src/event_store.rs"),
+            "{}: {:?}",
+            query,
+            results.iter().map(|r| &r.path).collect::<Vec<_>>()
+        );
+    }
+
+    // A compound query word still requires the whole identifier.
+    let (results, _) = ns::searcher::query::execute_search(&root, "StoreEvent", &opts(5))
+        .expect("search should work");
+    assert!(results.is_empty());
+}
+
+#[test]
+fn substring_search_uses_ngram_index() {
+    use ns::searcher::regex_search::execute_substring_search;
+
+    let (_tmp, root) = common::isolated_fixture();
+    let index_opts = IndexOptions {
+        ngram: true,
+        ..Default::default()
+    };
+    ns::indexer::run_full_index(&root, &index_opts).expect("indexing should succeed");
+
+    let substring_opts = SearchOptions {
+        substring: true,
+        ..opts(10)
+    };
+    let (results, _) = execute_substring_search(&root, "entSto", &substring_opts)
+        .expect("substring search should work");
+    assert_eq!(results.first().map(|r| r.path.as_str()), Some("src/event_store.rs"));
+
+    // Incremental runs keep filling the n-gram field.
+    fs::write(root.join("src/replay.rs"), "fn replay(log: &RecentStorage) {}\n").unwrap();
+    ns::indexer::run_incremental_index(&root, &IndexOptions::default())
+        .expect("incremental should succeed");
+    let (results, _) = execute_substring_search(&root, "entSto", &substring_opts)
+        .expect("substring search should work");
+    assert!(results.iter().any(|r| r.path == "src/replay.rs"));
+}
+
+#[test]
+fn stem_matches_inflected_words() {
+    let (_tmp, root) = common::isolated_fixture();
+    let stem_opts = SearchOptions {
+        stem: true,
+        ..opts(10)
+    };
+
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+    let err = ns::searcher::query::execute_search(&root, "validating", &stem_opts).unwrap_err();
+    assert!(matches!(err, ns::error::NsError::MissingIndexOption("--stem")));
+
+    let index_opts = IndexOptions {
+        stem: true,
+        ..Default::default()
+    };
+    ns::indexer::run_full_index(&root, &index_opts).expect("indexing should succeed");
+    let (results, _) = ns::searcher::query::execute_search(&root, "validating", &opts(10))
+        .expect("search should work");
+    assert!(results.is_empty(), "exact search should not stem");
+
+    let (results, _) = ns::searcher::query::execute_search(&root, "validating", &stem_opts)
+        .expect("search should work");
+    assert!(results.iter().any(|r| r.path == "src/validator.rs"));
+}
+
+#[test]
+fn semantic_needs_an_index_with_embeddings() {
+    let (_tmp, root) = common::isolated_fixture();
+    let semantic_opts = SearchOptions {
+        semantic: true,
+        ..opts(10)
+    };
+
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+    let err = ns::searcher::query::execute_search(&root, "retry requests", &semantic_opts).unwrap_err();
+    assert!(matches!(err, ns::error::NsError::MissingIndexOption("--embed-model")));
+
+    // An unusable model fails the index write rather than leaving stale vectors.
+    let index_opts = IndexOptions {
+        embed_model: Some(root.join("no-such-model").to_string_lossy().into_owned()),
+        ..Default::default()
+    };
+    let err = ns::indexer::run_full_index(&root, &index_opts).unwrap_err();
+    assert!(matches!(err, ns::error::NsError::Embedding(_)), "got: {}", err);
+    assert!(!root.join(".ns/vectors").exists());
+}
+
+#[test]
+fn why_explains_rank_misses_and_skipped_files() {
+    use ns::searcher::why::{explain_file, SkipReason, Verdict};
+
+    let (_tmp, root) = common::isolated_fixture();
+    std::fs::write(root.join(".ignore"), "generated/\n").unwrap();
+    std::fs::create_dir_all(root.join("generated")).unwrap();
+    std::fs::write(root.join("generated/events.rs"), "struct EventStore;\n").unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+    let opts = SearchOptions::default();
+
+    let report = explain_file(&root, "./src/event_store.rs", "event store", &opts).unwrap();
+    assert_eq!(report.path, "src/event_store.rs");
+    assert!(matches!(report.verdict, Verdict::Ranked { rank: 1, .. }), "got {:?}", report.verdict);
+    assert!(report.terms.iter().any(|t| t.field == "content" && t.term == "store" && t.score.is_some()));
+    assert!(report.result.is_some());
+
+    let report = explain_file(&root, "src/server.go", "EventStore", &opts).unwrap();
+    assert_eq!(report.verdict, Verdict::NoMatch);
+    assert!(report.terms.iter().all(|t| t.score.is_none()));
+
+    let report = explain_file(&root, "src/event_store.rs", "event -store", &opts).unwrap();
+    assert_eq!(report.verdict, Verdict::Excluded);
+    assert_eq!(report.excluded_by, vec!["store".to_string()]);
+
+    let report = explain_file(&root, "generated/events.rs", "EventStore", &opts).unwrap();
+    assert_eq!(report.verdict, Verdict::NotIndexed { reason: SkipReason::Ignored });
+
+    std::fs::write(root.join("src/late.rs"), "fn late() {}\n").unwrap();
+    let report = explain_file(&root, "src/late.rs", "late", &opts).unwrap();
+    assert_eq!(report.verdict, Verdict::NotIndexed { reason: SkipReason::NotYetIndexed });
+    let report = explain_file(&root, "src/missing.rs", "late", &opts).unwrap();
+    assert_eq!(report.verdict, Verdict::NotIndexed { reason: SkipReason::Missing });
+
+    let output = std::process::Command::new(ns_binary())
+        .args(["why", "src/event_store.rs", "event store"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("src/event_store.rs: rank 1 of"), "got: {}", stdout);
+}
+
+#[test]
+fn cjk_index_matches_words_inside_sentences() {
+    let (_tmp, root) = common::isolated_fixture();
+    fs::write(
+        root.join("src/storage_doc.rs"),
+        "// 这个模块负责把事件存储到数据库中\n// イベントを保存する\nfn persist() {}\n",
+    )
+    .unwrap();
+    let search = |query: &str| -> Vec<String> {
+        let (results, _) = ns::searcher::query::execute_search(&root, query, &opts(10))
+            .expect("search should work");
+        results.into_iter().map(|r| r.path).collect()
+    };
+
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+    assert!(search("事件存储").is_empty(), "a sentence is one token without --cjk");
+
+    let cjk = IndexOptions {
+        cjk: true,
+        ..Default::default()
+    };
+    ns::indexer::run_full_index(&root, &cjk).expect("indexing should succeed");
+    assert!(ns::indexer::writer::read_meta(&root).unwrap().cjk);
+    for query in ["事件存储", "数据库", "保存", "\"事件存储到数据库\"", "persist 数据库"] {
+        assert_eq!(search(query), vec!["src/storage_doc.rs"], "query {}", query);
+    }
+    assert!(search("\"数据库事件\"").is_empty(), "bigrams keep word order");
+
+    let (results, _) = ns::searcher::regex_search::execute_regex_search(&root, "事件存储", &opts(10))
+        .expect("regex search should work");
+    assert_eq!(results.len(), 1, "the regex pre-filter looks for bigrams");
+    let (results, _) = ns::searcher::regex_search::execute_substring_search(&root, "事件存储", &opts(10))
+        .expect("substring search should work");
+    assert_eq!(results.len(), 1, "so does the substring one");
+}
+
+#[test]
+fn sample_spreads_results_across_directories() {
+    let (_tmp, root) = common::isolated_fixture();
+    fs::create_dir_all(root.join("legacy")).unwrap();
+    for i in 0..12 {
+        fs::write(
+            root.join(format!("legacy/old_{}.rs", i)),
+            format!("// old_{}\n{}", i, "deprecated_api(); ".repeat(20)),
+        )
+        .unwrap();
+    }
+    for dir in ["tools", "web"] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+        let source = format!("fn {}() {{ deprecated_api(); }}\n", dir);
+        fs::write(root.join(dir).join("call.rs"), source).unwrap();
+    }
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+
+    let (top, _) = ns::searcher::query::execute_search(&root, "deprecated_api", &opts(3))
+        .expect("search should work");
+    assert!(top.iter().all(|r| r.path.starts_with("legacy/")));
+
+    let sample_opts = SearchOptions {
+        sample: Some(3),
+        ..opts(3)
+    };
+    let (sample, _) = ns::searcher::query::execute_search(&root, "deprecated_api", &sample_opts)
+        .expect("search should work");
+    let mut dirs: Vec<&str> = sample.iter().map(|r| r.path.split('/').next().unwrap()).collect();
+    dirs.sort();
+    assert_eq!(dirs, vec!["legacy", "tools", "web"]);
+}
+
+#[test]
+fn max_per_dir_refills_the_page_from_other_directories() {
+    let (_tmp, root) = common::isolated_fixture();
+    fs::create_dir_all(root.join("data/samples")).unwrap();
+    for i in 0..8 {
+        fs::write(
+            root.join(format!("data/samples/case_{}.rs", i)),
+            format!("// case_{}\n{}", i, "retry_budget(); ".repeat(20)),
+        )
+        .unwrap();
+    }
+    for dir in ["client", "server"] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+        let source = format!("fn {}() {{ retry_budget(); }}\n", dir);
+        fs::write(root.join(dir).join("retry.rs"), source).unwrap();
+    }
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+
+    let (top, _) = ns::searcher::query::execute_search(&root, "retry_budget", &opts(4))
+        .expect("search should work");
+    assert!(top.iter().all(|r| r.path.starts_with("data/samples/")));
+
+    let capped = SearchOptions {
+        max_per_dir: Some(2),
+        ..opts(4)
+    };
+    let (results, _) = ns::searcher::query::execute_search(&root, "retry_budget", &capped)
+        .expect("search should work");
+    let paths: Vec<&str> = results.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(paths.len(), 4);
+    assert!(paths[..2].iter().all(|p| p.starts_with("data/samples/")));
+    assert!(paths.contains(&"client/retry.rs") && paths.contains(&"server/retry.rs"));
+}
+
+#[test]
+fn proximity_phrase_requires_nearby_terms() {
+    let (_tmp, root) = common::isolated_fixture();
+    fs::write(root.join("src/near.rs"), "fn init() { start_server(); }\n").unwrap();
+    fs::write(
+        root.join("src/far.rs"),
+        format!("fn init() {{}}\n{}\nfn server() {{}}\n", "let filler = 1;\n".repeat(20)),
+    )
+    .unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+
+    let paths = |query: &str| -> Vec<String> {
+        let (results, _) = ns::searcher::query::execute_search(&root, query, &opts(10))
+            .expect("search should work");
+        results.into_iter().map(|r| r.path).collect()
+    };
+    assert!(paths("\"init server\"").is_empty());
+    assert_eq!(paths("\"init server\"~3"), vec!["src/near.rs"]);
+    let wide = paths("\"init server\"~200");
+    assert_eq!(wide.first().map(String::as_str), Some("src/near.rs"));
+    assert!(wide.contains(&"src/far.rs".to_string()));
+}
+
+#[test]
+fn all_terms_requires_every_word() {
+    let (_tmp, root) = common::indexed_fixture();
+
+    let paths = |opts: &SearchOptions| -> Vec<String> {
+        let (results, _) = ns::searcher::query::execute_search(&root, "handler error", opts)
+            .expect("search should work");
+        let mut paths: Vec<String> = results.into_iter().map(|r| r.path).collect();
+        paths.sort();
+        paths
+    };
+    assert!(paths(&opts(20)).len() > 2);
+
+    let all = SearchOptions {
+        all_terms: true,
+        ..opts(20)
+    };
+    assert_eq!(paths(&all), vec!["src/handlers.ts", "src/server.go"]);
+
+    let fuzzy_all = SearchOptions {
+        fuzzy: true,
+        ..all
+    };
+    assert_eq!(paths(&fuzzy_all), vec!["src/handlers.ts", "src/server.go"]);
+}
+
+#[test]
+fn positional_paths_restrict_results_to_subtrees() {
+    let (_tmp, root) = common::isolated_fixture();
+    fs::create_dir_all(root.join("src_old")).unwrap();
+    fs::create_dir_all(root.join("tests")).unwrap();
+    fs::write(root.join("src_old/handler.js"), "function oldHandler() {}\n").unwrap();
+    fs::write(root.join("tests/handler_test.js"), "test('handler', () => {});\n").unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+
+    let paths = |prefixes: &[&str], regex: bool| -> Vec<String> {
+        let opts = SearchOptions {
+            paths: prefixes.iter().map(|p| p.to_string()).collect(),
+            regex,
+            ..opts(20)
+        };
+        let out = ns::searcher::search(&root, "handler", OutputMode::FilesOnly, &opts)
+            .expect("search should work");
+        let mut paths: Vec<String> = out.formatted.lines().map(str::to_string).collect();
+        paths.sort();
+        paths
+    };
+
+    let everything = paths(&[], false);
+    assert!(everything.contains(&"src_old/handler.js".to_string()));
+    assert_eq!(paths(&["."], false), everything);
+
+    let in_src = paths(&["src"], false);
+    assert!(!in_src.is_empty());
+    assert!(in_src.iter().all(|p| p.starts_with("src/")), "{:?}", in_src);
+
+    assert_eq!(
+        paths(&["./tests/", "src/server.go"], false),
+        vec!["src/server.go", "tests/handler_test.js"]
+    );
+    assert_eq!(paths(&["tests"], true), vec!["tests/handler_test.js"]);
+}
+
+#[test]
+fn max_count_hard_lifts_result_ceiling() {
+    let (_tmp, root) = common::isolated_fixture();
+    for i in 0..130 {
+        fs::write(
+            root.join(format!("src/legacy_{}.rs", i)),
+            format!("fn caller_{}() {{ deprecated_api(); }}\n", i),
+        )
+        .unwrap();
+    }
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+
+    let search = |max_results_ceiling| {
+        let opts = SearchOptions {
+            max_results: 1000,
+            max_results_ceiling,
+            force: true,
+            ..Default::default()
+        };
+        ns::searcher::query::execute_search(&root, "deprecated_api", &opts)
+            .expect("search should work")
+            .0
+            .len()
+    };
+    assert_eq!(search(None), 100);
+    assert_eq!(search(Some(120)), 120);
+    assert_eq!(search(Some(0)), 130);
+
+    // -l has no context to extract, so it is exempt from the default cap.
+    let output = std::process::Command::new(ns_binary())
+        .args(["-l", "-m", "1000", "--force", "--", "deprecated_api"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 130);
+
+    let run = |env: &str| {
+        std::process::Command::new(ns_binary())
+            .args(["-l", "-m", "1000", "--force", "--", "deprecated_api"])
+            .env("NS_MAX_COUNT_HARD", env)
+            .current_dir(&root)
+            .output()
+            .expect("should run ns binary")
+    };
+    fs::write(root.join(".ns/config.toml"), "[search]\nmax_count_hard = 110\n").unwrap();
+    assert_eq!(String::from_utf8_lossy(&run("").stdout).lines().count(), 110);
+    assert_eq!(String::from_utf8_lossy(&run("120").stdout).lines().count(), 120, "env beats config");
+    let output = run("lots");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid NS_MAX_COUNT_HARD 'lots'"));
+}
+
+#[test]
+fn cost_guard_rejects_terms_matching_nearly_every_file() {
+    let (_tmp, root) = common::isolated_fixture();
+    for i in 0..120 {
+        fs::write(
+            root.join(format!("src/gen_{}.rs", i)),
+            format!("fn generated_{}() {{}}\n", i),
+        )
+        .unwrap();
+    }
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).expect("indexing should succeed");
+
+    let err = ns::searcher::query::execute_search(&root, "fn EventStore", &opts(5)).unwrap_err();
+    assert!(matches!(
+        err,
+        ns::error::NsError::QueryTooBroad { ref term, total: 129, .. } if term == "fn"
+    ));
+
+    // Selective queries, quoted phrases and --force are unaffected.
+    assert!(ns::searcher::query::execute_search(&root, "EventStore", &opts(5)).is_ok());
+    assert!(ns::searcher::query::execute_search(&root, "\"fn new\"", &opts(5)).is_ok());
+    let forced = SearchOptions {
+        force: true,
+        ..opts(5)
+    };
+    let (results, _) = ns::searcher::query::execute_search(&root, "fn", &forced)
+        .expect("forced search should work");
+    assert_eq!(results.len(), 5);
+
+    let output = std::process::Command::new(ns_binary())
+        .args(["--", "fn"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--force"));
+}
+
+#[test]
+fn freq_reports_doc_and_occurrence_counts() {
+    use ns::searcher::freq::{term_frequencies, FreqBreakdown};
+
+    let (_tmp, root) = common::indexed_fixture();
+    let terms = vec!["validate".to_string(), "zzznotaterm".to_string()];
+    let (report, total_docs) =
+        term_frequencies(&root, &terms, false, Some(FreqBreakdown::Lang)).expect("freq should work");
+
+    assert!(total_docs > 0);
+    assert_eq!(report.len(), 2);
+    let validate = &report[0];
+    assert_eq!(validate.term, "validate");
+    assert!(validate.doc_freq > 0 && validate.occurrences >= validate.doc_freq);
+    assert_eq!(
+        validate.groups.iter().map(|g| g.doc_freq).sum::<u64>(),
+        validate.doc_freq,
+        "groups should partition the documents"
+    );
+    assert!(validate.groups.iter().any(|g| g.key == "rust"));
+
+    assert_eq!(report[1].doc_freq, 0);
+    assert!(report[1].groups.is_empty());
+}
+
+#[test]
+fn complete_suggests_symbols_and_path_segments() {
+    use ns::searcher::complete::{complete, CompletionKind};
+
+    let (_tmp, root) = common::indexed_fixture();
+    let completions = complete(&root, "eventst", 20).expect("complete should work");
+
+    assert!(
+        completions
+            .iter()
+            .any(|c| c.kind == CompletionKind::Symbol && c.text == "EventStore"),
+        "symbol case should be restored: {:?}",
+        completions
+    );
+    assert!(completions
+        .iter()
+        .all(|c| c.text.to_lowercase().starts_with("eventst")));
+
+    let paths = complete(&root, "event_", 20).expect("complete should work");
+    assert!(paths
+        .iter()
+        .any(|c| c.kind == CompletionKind::Path && c.text == "event_store.rs"));
+
+    assert_eq!(complete(&root, "", 3).expect("complete should work").len(), 3);
+}
+
+#[test]
+fn audit_streams_every_match_unranked() {
+    use ns::searcher::audit::{audit, AuditOptions};
+
+    let (_tmp, root) = common::indexed_fixture();
+    let run = |query: &str, opts: &AuditOptions| {
+        let mut found = Vec::new();
+        let stats = audit(&root, query, opts, &mut |m| {
+            found.push(m.clone());
+            Ok(())
+        })
+        .expect("audit should work");
+        assert_eq!(stats.files, found.len());
+        found
+    };
+
+    let mut paths: Vec<String> = run("handler", &AuditOptions::default())
+        .into_iter()
+        .map(|m| m.path)
+        .collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec!["src/event_manager.ex", "src/handlers.ts", "src/server.go", "src/utils.js"]
+    );
+
+    // Every term is required, and matching lines are reported per file.
+    let lines_opts = AuditOptions {
+        lines: true,
+        ..Default::default()
+    };
+    let found = run("handler error", &lines_opts);
+    assert_eq!(found.len(), 2);
+    assert!(found.iter().all(|m| !m.lines.is_empty()));
+
+    // A query with nothing to require is refused rather than matching everything.
+    for query in ["-handler", "!! ..."] {
+        let result = audit(&root, query, &lines_opts, &mut |_| Ok(()));
+        assert!(matches!(result, Err(ns::error::NsError::QueryParse(_))), "{}", query);
+    }
+}
+
+#[test]
+fn calibration_queries_indexed_symbols() {
+    use ns::searcher::calibrate::calibrate;
+
+    let (_tmp, root) = common::indexed_fixture();
+    let all = calibrate(&root, None).expect("calibration should work");
+    assert!(all.candidates > 0);
+    assert_eq!(all.queries, all.candidates);
+    assert_eq!(all.top1 + all.misses.len(), all.queries);
+    assert!(all.top1 <= all.top5 && all.top5 <= all.queries);
+    assert!(all.mrr > 0.0 && all.mrr <= 1.0);
+
+    let sampled = calibrate(&root, Some(3)).unwrap();
+    assert_eq!(sampled.queries, 3);
+    assert_eq!(sampled.candidates, all.candidates);
+}
+
+#[test]
+fn golden_queries_report_rank_of_expected_file() {
+    use ns::searcher::golden::{golden_path, load_golden, run_golden};
+
+    let (_tmp, root) = common::indexed_fixture();
+    fs::write(
+        golden_path(&root),
+        r#"
+[[query]]
+query = "EventStore"
+expect = "src/event_store.rs"
+
+[[query]]
+query = "EventStore"
+expect = "README.md"
+"#,
+    )
+    .unwrap();
+
+    let queries = load_golden(&golden_path(&root)).expect("golden file should parse");
+    let outcomes = run_golden(&root, &queries).expect("golden queries should run");
+    assert!(outcomes[0].passed());
+    assert_eq!(outcomes[0].rank, Some(1));
+    assert!(!outcomes[1].passed());
+    assert_eq!(outcomes[1].got.as_deref(), Some("src/event_store.rs"));
+}
+
+#[test]
+fn lang_boost_multiplies_scores_by_language_or_extension() {
+    let (_tmp, root) = common::indexed_fixture();
+    let scores = |query: &str, lang_boost: Vec<(String, f32)>| {
+        let opts = SearchOptions {
+            lang_boost,
+            ..SearchOptions::default()
+        };
+        let (results, _) = ns::searcher::query::execute_search(&root, query, &opts).unwrap();
+        results
+            .into_iter()
+            .map(|r| (r.path, r.score))
+            .collect::<Vec<_>>()
+    };
+
+    let plain = scores("handler", Vec::new());
+    assert_ne!(plain[0].0, "src/server.go");
+    let go_score = plain.iter().find(|(p, _)| p == "src/server.go").unwrap().1;
+
+    let boosted = scores("handler", vec![("go".to_string(), 10.0)]);
+    assert_eq!(boosted[0].0, "src/server.go");
+    assert!((boosted[0].1 - go_score * 10.0).abs() < 1e-3 * go_score);
+    assert_eq!(boosted.len(), plain.len());
+
+    // Files without a language are keyed by extension.
+    assert_eq!(scores("server", Vec::new())[0].0, "src/server.go");
+    let readme_first = scores("server", vec![("md".to_string(), 100.0)]);
+    assert_eq!(readme_first[0].0, "README.md");
+
+    // A file with a language is scaled by its language key only, not also
+    // by its extension's.
+    let rust_path = "src/event_store.rs";
+    let rust_score = |scored: &[(String, f32)]| scored.iter().find(|(p, _)| p == rust_path).unwrap().1;
+    let plain = scores("event", Vec::new());
+    let both = scores("event", vec![("rust".to_string(), 2.0), ("rs".to_string(), 0.5)]);
+    assert_eq!(both.len(), plain.len());
+    assert!((rust_score(&both) - rust_score(&plain) * 2.0).abs() < 1e-3 * rust_score(&plain));
+}
+
+#[test]
+fn test_files_are_downweighted_unless_weight_is_one() {
+    let (_tmp, root) = common::isolated_fixture();
+    fs::create_dir_all(root.join("tests")).unwrap();
+    fs::write(
+        root.join("tests/server_test.go"),
+        "package tests\n\n// handler handler handler: exercises the server handler.\n\nfunc TestHandler() {}\n",
+    )
+    .unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).unwrap();
+
+    let run = |test_weight: f32| {
+        let opts = SearchOptions {
+            test_weight,
+            ..SearchOptions::default()
+        };
+        let (results, _) = ns::searcher::query::execute_search(&root, "handler", &opts).unwrap();
+        results
+    };
+    let score_of = |results: &[SearchResult]| {
+        let r = results.iter().find(|r| r.path == "tests/server_test.go").unwrap();
+        assert!(r.is_test);
+        r.score
+    };
+
+    let unweighted = run(1.0);
+    assert_eq!(unweighted[0].path, "tests/server_test.go");
+    assert!(unweighted.iter().filter(|r| r.is_test).count() == 1);
+
+    let weighted = run(ns::searcher::query::DEFAULT_TEST_WEIGHT);
+    assert_ne!(weighted[0].path, "tests/server_test.go");
+    let expected = score_of(&unweighted) * ns::searcher::query::DEFAULT_TEST_WEIGHT;
+    assert!((score_of(&weighted) - expected).abs() < 1e-3 * expected);
+}
+
+#[test]
+fn identifier_queries_are_routed_to_symbols_first() {
+    use ns::searcher::query::{execute_search, SearchRoute};
+
+    let (_tmp, root) = common::indexed_fixture();
+    let run = |query: &str, auto_route: bool| {
+        let opts = SearchOptions {
+            auto_route,
+            ..SearchOptions::default()
+        };
+        execute_search(&root, query, &opts).unwrap()
+    };
+
+    let (results, stats) = run("ApiResponse", true);
+    assert_eq!(stats.route, Some(SearchRoute::Symbols));
+    assert_eq!(results[0].path, "src/handlers.ts");
+    assert_eq!(results[0].matched_fields, vec!["content", "symbols"]);
+
+    // Mentioned but never defined: falls back to the usual ranking.
+    let (results, stats) = run("max_capacity", true);
+    assert_eq!(stats.route, Some(SearchRoute::Content));
+    assert_eq!(results[0].path, "src/event_store.rs");
+
+    assert_eq!(run("handler", true).1.route, None);
+    assert_eq!(run("ApiResponse", false).1.route, None);
+}
+
+#[test]
+fn vendored_files_are_penalized() {
+    let (_tmp, root) = common::isolated_fixture();
+    fs::create_dir_all(root.join("web/node_modules/router")).unwrap();
+    fs::write(
+        root.join("web/node_modules/router/index.js"),
+        "// handler handler handler handler\nfunction handler() { return handler; }\n",
+    )
+    .unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).unwrap();
+
+    let run = |vendor_weight: f32| {
+        let opts = SearchOptions {
+            vendor_weight,
+            ..SearchOptions::default()
+        };
+        let (results, _) = ns::searcher::query::execute_search(&root, "handler", &opts).unwrap();
+        results
+    };
+    let vendored = |results: &[SearchResult]| {
+        let r = results
+            .iter()
+            .find(|r| r.path == "web/node_modules/router/index.js")
+            .unwrap();
+        assert!(r.vendored);
+        r.score
+    };
+
+    let unpenalized = run(1.0);
+    assert_eq!(unpenalized[0].path, "web/node_modules/router/index.js");
+    assert_eq!(unpenalized.iter().filter(|r| r.vendored).count(), 1);
+
+    let penalized = run(ns::searcher::query::DEFAULT_VENDOR_WEIGHT);
+    assert_ne!(penalized[0].path, "web/node_modules/router/index.js");
+    let expected = vendored(&unpenalized) * ns::searcher::query::DEFAULT_VENDOR_WEIGHT;
+    assert!((vendored(&penalized) - expected).abs() < 1e-3 * expected);
+}
+
+#[test]
+fn generated_files_are_downweighted() {
+    let (_tmp, root) = common::isolated_fixture();
+    fs::create_dir_all(root.join("static")).unwrap();
+    let bundle = "var handler=function(h){return handler(h)};".repeat(100);
+    fs::write(root.join("static/app.js"), bundle).unwrap();
+    fs::write(root.join("package-lock.json"), "{\"name\": \"handler\"}\n").unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).unwrap();
+
+    let run = |generated_weight: f32| {
+        let opts = SearchOptions {
+            generated_weight,
+            ..SearchOptions::default()
+        };
+        let (results, _) = ns::searcher::query::execute_search(&root, "handler", &opts).unwrap();
+        results
+    };
+    let bundle_score = |results: &[SearchResult]| {
+        let r = results.iter().find(|r| r.path == "static/app.js").unwrap();
+        assert!(r.generated);
+        r.score
+    };
+
+    let unweighted = run(1.0);
+    assert_eq!(unweighted[0].path, "static/app.js");
+    let flagged: Vec<&str> = unweighted
+        .iter()
+        .filter(|r| r.generated)
+        .map(|r| r.path.as_str())
+        .collect();
+    assert_eq!(flagged, vec!["static/app.js", "package-lock.json"]);
+
+    let weighted = run(ns::searcher::query::DEFAULT_GENERATED_WEIGHT);
+    assert!(!weighted[0].generated);
+    let expected = bundle_score(&unweighted) * ns::searcher::query::DEFAULT_GENERATED_WEIGHT;
+    assert!((bundle_score(&weighted) - expected).abs() < 1e-3 * expected);
+}
+
+#[test]
+fn no_symbols_index_searches_content_and_warns() {
+    let (_tmp, root) = common::isolated_fixture();
+    let content_only = IndexOptions {
+        no_symbols: true,
+        ..IndexOptions::default()
+    };
+    ns::indexer::run_full_index(&root, &content_only).unwrap();
+    assert!(ns::indexer::writer::read_meta(&root).unwrap().no_symbols);
+
+    fs::write(root.join("src/extra.rs"), "pub struct EventStoreCache;\n").unwrap();
+    ns::indexer::run_incremental_index(&root, &IndexOptions::default()).unwrap();
+    assert!(ns::indexer::writer::read_meta(&root).unwrap().no_symbols, "kept by incremental runs");
+
+    let so = ns::searcher::search(&root, "EventStore", OutputMode::Text, &opts(10)).unwrap();
+    assert!(so.stats.no_symbols);
+    assert!(so.warnings.contains(&Warning::NoSymbols));
+    assert!(so.formatted.contains("warning: index built with --no-symbols"));
+    let (results, _) = ns::searcher::query::execute_search(&root, "EventStoreCache", &opts(10)).unwrap();
+    assert_eq!(results[0].path, "src/extra.rs");
+    assert!(results[0].symbols_raw.is_empty(), "no symbols were extracted");
+}
+
+#[test]
+fn chunked_files_rank_by_their_best_chunk() {
+    let (_tmp, root) = common::isolated_fixture();
+    let mut lines: Vec<String> = (0..3000).map(|i| format!("let value_{i} = compute({i});")).collect();
+    lines[2500] = "// upload the batch, then retry the upload once".to_string();
+    lines[10] = "fn first_helper() {}".to_string();
+    lines[2900] = "fn last_helper() {}".to_string();
+    fs::write(root.join("src/big.rs"), lines.join("\n") + "\n").unwrap();
+    let mut short: Vec<String> = (0..40).map(|i| format!("let item_{i} = other({i});")).collect();
+    short.push("// upload".to_string());
+    fs::write(root.join("src/short.rs"), short.join("\n") + "\n").unwrap();
+
+    let paths = |results: &[SearchResult]| -> Vec<String> {
+        results.iter().map(|r| r.path.clone()).collect()
+    };
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).unwrap();
+    let (whole, _) = ns::searcher::query::execute_search(&root, "upload", &opts(10)).unwrap();
+    assert_eq!(paths(&whole), vec!["src/short.rs", "src/big.rs"], "length buries the deep match");
+
+    let chunked = IndexOptions {
+        chunk_lines: Some(100),
+        ..IndexOptions::default()
+    };
+    ns::indexer::run_full_index(&root, &chunked).unwrap();
+    let meta = ns::indexer::writer::read_meta(&root).unwrap();
+    assert_eq!(meta.chunk_lines, Some(100));
+    let (results, stats) = ns::searcher::query::execute_search(&root, "upload", &opts(10)).unwrap();
+    assert_eq!(paths(&results), vec!["src/big.rs", "src/short.rs"], "one result per file");
+    assert_eq!(results[0].match_lines, vec![2501], "lines count from the top of the file");
+    assert_eq!(results[0].symbols_raw, vec!["first_helper", "last_helper"], "symbols of every chunk");
+    assert_eq!(results[0].symbol_kinds.len(), 2);
+    assert_eq!(stats.files_searched, meta.file_count);
+
+    fs::write(root.join("src/big.rs"), lines[..2000].join("\n") + "\n").unwrap();
+    ns::indexer::run_incremental_index(&root, &IndexOptions::default()).unwrap();
+    let meta = ns::indexer::writer::read_meta(&root).unwrap();
+    assert_eq!(meta.file_count, 11, "chunks are not counted as files");
+    let (results, _) = ns::searcher::query::execute_search(&root, "upload", &opts(10)).unwrap();
+    assert_eq!(paths(&results), vec!["src/short.rs"], "every chunk of the old version is gone");
+}
+
+#[test]
+fn symbols_lists_definitions_matching_a_pattern() {
+    use ns::indexer::symbols::SymbolKind;
+    use ns::searcher::symbol_search::{find_symbols, SymbolSearchOptions};
+    let (_tmp, root) = common::indexed_fixture();
+    let names = |pattern: &str, opts: &SymbolSearchOptions| -> Vec<String> {
+        find_symbols(&root, pattern, opts)
+            .expect("symbol search should work")
+            .into_iter()
+            .map(|s| s.name)
+            .collect()
+    };
+    let rust = SymbolSearchOptions {
+        file_type: Some("rust".to_string()),
+        ..SymbolSearchOptions::default()
+    };
+    assert_eq!(
+        names("Event*", &rust),
+        vec!["Event", "EventId", "EventStore", "EventStoreError"]
+    );
+    assert!(names("Event*", &SymbolSearchOptions::default()).contains(&"EventEmitter".to_string()));
+    assert!(names("event", &rust).contains(&"from_event".to_string()), "plain words match anywhere");
+
+    let functions = SymbolSearchOptions {
+        kinds: vec![SymbolKind::Function],
+        ..rust.clone()
+    };
+    assert_eq!(names("validate*", &functions), vec!["validate", "validate_identifier", "validate_port"]);
+
+    let found = find_symbols(&root, "EventStore", &rust).expect("symbol search should work");
+    let store = found.iter().find(|s| s.name == "EventStore").expect("EventStore is defined");
+    assert_eq!(store.path, "src/event_store.rs");
+    assert_eq!(store.kind, SymbolKind::Type);
+    assert_eq!(store.line, Some(26));
+
+    let out = std::process::Command::new(ns_binary())
+        .args(["symbols", "Event*", "-t", "rust", "--json"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(out.status.success());
+    let first: serde_json::Value = serde_json::from_str(
+        String::from_utf8_lossy(&out.stdout).lines().next().expect("one line per symbol"),
+    )
+    .unwrap();
+    assert_eq!(first["name"], "Event");
+    assert_eq!(first["kind"], "type");
+}
+
+#[test]
+fn def_finds_declarations_first() {
+    use ns::indexer::symbols::SymbolKind;
+    use ns::searcher::symbol_search::{find_definitions, preferred, SymbolSearchOptions};
+    let (_tmp, root) = common::indexed_fixture();
+    let opts = SymbolSearchOptions::default();
+
+    let found = find_definitions(&root, "eventstore", &opts).expect("lookup should work");
+    assert_eq!(found[0].name, "EventStore", "any casing finds it");
+    assert_eq!(found[0].kind, SymbolKind::Type);
+    assert_eq!((found[0].line, found[0].end_line), (Some(26), Some(29)));
+    assert_eq!(found[0].definition.as_deref(), Some("pub struct EventStore {"));
+    assert!(find_definitions(&root, "Event", &opts).unwrap().iter().all(|s| s.name == "Event"), "exact names only");
+
+    let news = find_definitions(&root, "new", &opts).expect("lookup should work");
+    assert_eq!(preferred(&news, "new").len(), 3);
+    let qualified = find_definitions(&root, "EventStore::new", &opts).expect("lookup should work");
+    let paths: Vec<&str> = qualified.iter().map(|s| s.path.as_str()).collect();
+    assert_eq!(paths, vec!["src/event_store.rs"]);
+
+    let out = std::process::Command::new(ns_binary())
+        .args(["def", "RuleValidator"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.starts_with("src/validator.rs:19"), "{}", stdout);
+
+    let missing = std::process::Command::new(ns_binary())
+        .args(["def", "NoSuchThing", "--json"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert_eq!(missing.status.code(), Some(1));
+    assert!(missing.stdout.is_empty());
+}
+
+#[test]
+fn refs_lists_references_and_labels_importers() {
+    use ns::searcher::refs::{find_refs, RefsOptions};
+    let (_tmp, root) = common::isolated_fixture();
+    fs::write(
+        root.join("src/app.ts"),
+        "import { Router } from \"./handlers\";\n\nconst router = new Router();\n",
+    )
+    .unwrap();
+    fs::write(root.join("src/notes.ts"), "// TODO: move Router setup here\nexport const x = 1;\n").unwrap();
+    ns::indexer::run_full_index(&root, &IndexOptions::default()).unwrap();
+
+    let (definitions, files) = find_refs(&root, "Router", &RefsOptions::default()).expect("refs should work");
+    assert_eq!(definitions[0].path, "src/handlers.ts");
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, vec!["src/app.ts", "src/notes.ts"], "a definition alone is not a reference");
+    assert!(files[0].imports && !files[0].defines);
+    let app_lines: Vec<usize> = files[0].lines.iter().map(|l| l.line).collect();
+    assert_eq!(app_lines, vec![1, 3]);
+    assert!(!files[1].imports);
+
+    let (definitions, files) = find_refs(&root, "EventStore", &RefsOptions::default()).expect("refs should work");
+    let store = files.iter().find(|f| f.path == "src/event_store.rs").expect("used where defined");
+    assert!(store.defines);
+    assert!(store.lines.iter().all(|l| Some(l.line) != definitions[0].line));
+    let opts = RefsOptions {
+        exclude_definitions: true,
+        ..RefsOptions::default()
+    };
+    let (_, files) = find_refs(&root, "EventStore", &opts).expect("refs should work");
+    assert!(files.iter().all(|f| f.path != "src/event_store.rs"));
+
+    let out = std::process::Command::new(ns_binary())
+        .args(["refs", "Router", "-l"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).starts_with("src/app.ts\n"));
+}
+
+#[test]
+fn outline_lists_nested_definitions_without_an_index() {
+    let (_tmp, root) = common::isolated_fixture();
+    assert!(!root.join(".ns").exists());
+
+    let out = std::process::Command::new(ns_binary())
+        .args(["outline", "src/event_store.rs", "--json"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let entries: Vec<serde_json::Value> = String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    let find = |name: &str, kind: &str| {
+        entries
+            .iter()
+            .find(|e| e["name"] == name && e["kind"] == kind)
+            .unwrap_or_else(|| panic!("{} {} in outline", kind, name))
+    };
+    assert_eq!(find("EventStore", "type")["depth"], 0);
+    assert_eq!(find("EventStore", "impl")["depth"], 0);
+    assert_eq!(find("append", "function")["depth"], 1);
+
+    fs::copy(root.join("src/handlers.ts"), root.join("handlers")).unwrap();
+    let out = std::process::Command::new(ns_binary())
+        .args(["outline", "handlers"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(!out.status.success(), "no extension, no language");
+    let out = std::process::Command::new(ns_binary())
+        .args(["outline", "handlers", "-t", "typescript"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("type ApiRequest"));
+}
+
+#[test]
+fn stats_summarizes_the_search_log() {
+    let (_tmp, root) = common::indexed_fixture();
+    for query in ["EventStore", "EventStore", "zzqqxxnothing"] {
+        std::process::Command::new(ns_binary())
+            .arg(query)
+            .current_dir(&root)
+            .output()
+            .expect("should run ns binary");
+    }
+
+    let out = std::process::Command::new(ns_binary())
+        .args(["stats", "--json", "--top", "1"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let summary: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(summary["searches"], 3);
+    assert_eq!(summary["no_results"], 1);
+    assert_eq!(summary["top_queries"][0]["query"], "EventStore");
+    assert_eq!(summary["top_queries"][0]["count"], 2);
+    assert_eq!(summary["top_queries"].as_array().unwrap().len(), 1);
+    assert_eq!(summary["zero_result_queries"][0]["query"], "zzqqxxnothing");
+    assert_eq!(summary["over_time"].as_array().unwrap().len(), 1);
+
+    let out = std::process::Command::new(ns_binary())
+        .args(["stats", "--since", "1d"])
+        .current_dir(&root)
+        .output()
+        .expect("should run ns binary");
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).starts_with("3 searches from "));
+}
+
+#[test]
+fn log_filters_the_search_log() {
+    let (_tmp, root) = common::indexed_fixture();
+    for args in [vec!["EventStore"], vec!["-t", "rust", "EventStore new"], vec!["zzqqxxnothing"]] {
+        std::process::Command::new(ns_binary())
+            .args(&args)
+            .current_dir(&root)
+            .output()
+            .expect("should run ns binary");
+    }
+
+    let log = |args: &[&str]| {
+        std::process::Command::new(ns_binary())
+            .arg("log")
+            .args(args)
+            .current_dir(&root)
+            .output()
+            .expect("should run ns binary")
+    };
+    let out = log(&["--outcome", "no_results", "--json"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let lines: Vec<serde_json::Value> = String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["query"], "zzqqxxnothing");
+    assert_eq!(lines[0]["zero_results"], true, "lines are printed as logged");
+
+    let out = log(&["--query-contains", "NEW"]);
+    let text = String::from_utf8_lossy(&out.stdout).to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", text);
+    assert!(lines[0].contains("success") && lines[0].ends_with("EventStore new"));
+    assert_eq!(lines[1], "    ns -t rust 'EventStore new'");
+
+    let out = log(&["-n", "1"]);
+    assert_eq!(String::from_utf8_lossy(&out.stdout).lines().count(), 1, "the most recent only");
+    assert!(!log(&["--outcome", "error"]).status.success(), "no errors logged");
+}
+
+#[test]
+fn repl_runs_queries_read_from_stdin() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let (_tmp, root) = common::indexed_fixture();
+    let mut child = Command::new(ns_binary())
+        .arg("repl")
+        .current_dir(&root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("should spawn ns");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b":t rust\n:json\nEventStore\n:m nope\n:t\n:l\nzzqqxxnothing\nEventStore\n")
+        .unwrap();
+    let out = child.wait_with_output().expect("should wait for ns");
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+
+    let stdout = String::from_utf8_lossy(&out.stdout).to_string();
+    let lines: Vec<&str> = stdout.lines().collect();
+    let first: serde_json::Value = serde_json::from_str(lines[0]).expect("first query prints JSON");
+    assert_eq!(first["query"], "EventStore");
+    assert!(first["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|r| r["path"].as_str().unwrap().ends_with(".rs")));
+    assert!(lines.len() > 1, "the no-result query doesn't end the session: {}", stdout);
+
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("flags: -t rust --json"), "{}", stderr);
+    assert!(stderr.contains("error: invalid value 'nope'"), "{}", stderr);
+}
+
+#[test]
+fn tui_needs_a_terminal() {
+    let (_tmp, root) = common::indexed_fixture();
+    let out = std::process::Command::new(ns_binary())
+        .arg("tui")
+        .current_dir(&root)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("should run ns binary");
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("ns tui needs a terminal"), "{}", stderr);
+}